@@ -0,0 +1,135 @@
+use beepkg::error::BeepkgError;
+use beepkg::events::SilentObserver;
+use beepkg::models;
+use beepkg::operations;
+use napi::{Error, Result, Status};
+use napi_derive::napi;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+// 错误消息以失败类型开头，例如 "[NotFound] Not found: demo@1.0.0"，类型与 Python 绑定的异常一致
+fn to_js_error(error: BeepkgError) -> Error {
+    let kind = match &error {
+        BeepkgError::Auth(_) | BeepkgError::AccessDenied(_) => "Auth",
+        BeepkgError::Network(_) | BeepkgError::Http(_) | BeepkgError::Offline(_) => "Network",
+        BeepkgError::NotFound(_) => "NotFound",
+        BeepkgError::Conflict(_) => "Conflict",
+        BeepkgError::Locked(_) => "Locked",
+        BeepkgError::ChecksumMismatch(_)
+        | BeepkgError::MissingChecksum
+        | BeepkgError::PolicyViolation(_) => "Integrity",
+        BeepkgError::Config(_) => "Config",
+        _ => "Other",
+    };
+    Error::new(Status::GenericFailure, format!("[{}] {}", kind, error))
+}
+
+#[napi(object)]
+pub struct ManagerOptions {
+    pub endpoint: String,
+    pub bucket: String,
+    pub access_key: Option<String>,
+    pub secret_key: Option<String>,
+    pub region: Option<String>,
+}
+
+/// 注册表中的一个包
+#[napi(object)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+    pub path: String,
+    pub size: i64,
+    pub created_at: String,
+}
+
+impl From<models::Package> for Package {
+    fn from(package: models::Package) -> Self {
+        Self {
+            name: package.name,
+            version: package.version,
+            path: package.storage.path,
+            size: package.storage.size as i64,
+            created_at: package.storage.created_at,
+        }
+    }
+}
+
+/// 连接一个注册表 bucket，所有操作返回 Promise，进度信息不输出到标准输出
+#[napi]
+pub struct PackageManager {
+    inner: Arc<operations::PackageManager>,
+}
+
+#[napi]
+impl PackageManager {
+    #[napi(constructor)]
+    pub fn new(options: ManagerOptions) -> Result<Self> {
+        let inner = operations::PackageManager::builder()
+            .endpoint(options.endpoint)
+            .bucket(options.bucket)
+            .credentials(
+                options.access_key.unwrap_or_default(),
+                options.secret_key.unwrap_or_default(),
+            )
+            .region(options.region)
+            .build()
+            .map_err(to_js_error)?
+            .observer(Arc::new(SilentObserver));
+        Ok(Self {
+            inner: Arc::new(inner),
+        })
+    }
+
+    /// 列出当前凭证可见的包
+    #[napi]
+    pub async fn list_packages(&self) -> Result<Vec<Package>> {
+        let packages = self.inner.list_packages().await.map_err(to_js_error)?;
+        Ok(packages.into_iter().map(Package::from).collect())
+    }
+
+    /// 推送包目录，force 为 true 时覆盖已存在的版本
+    #[napi]
+    pub async fn push(&self, path: String, force: Option<bool>) -> Result<()> {
+        let path = PathBuf::from(path);
+        let result = if force.unwrap_or(false) {
+            self.inner.force_push_package(&path).await
+        } else {
+            self.inner.push_package(&path).await
+        };
+        result.map_err(to_js_error)
+    }
+
+    /// 拉取包到 outputDir，package 为 name、name@version 或 name@channel
+    #[napi]
+    pub async fn pull(&self, package: String, output_dir: String) -> Result<()> {
+        self.inner
+            .pull_package(&package, &PathBuf::from(output_dir))
+            .await
+            .map_err(to_js_error)
+    }
+
+    /// 锁定包版本，expiresAt 为 RFC 3339 时间，未指定时永不过期
+    #[napi]
+    pub async fn lock(
+        &self,
+        name: String,
+        version: String,
+        reason: String,
+        user: String,
+        expires_at: Option<String>,
+    ) -> Result<()> {
+        self.inner
+            .lock_package(&name, &version, &reason, &user, expires_at.as_deref())
+            .await
+            .map_err(to_js_error)
+    }
+
+    #[napi]
+    pub async fn unlock(&self, name: String, version: String) -> Result<()> {
+        self.inner
+            .unlock_package(&name, &version)
+            .await
+            .map_err(to_js_error)
+    }
+}
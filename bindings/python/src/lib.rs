@@ -0,0 +1,178 @@
+use beepkg::blocking;
+use beepkg::error::BeepkgError as Error;
+use beepkg::events::SilentObserver;
+use beepkg::models;
+use beepkg::operations;
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+create_exception!(beepkg_py, BeepkgError, PyException, "beepkg 操作失败");
+create_exception!(beepkg_py, AuthError, BeepkgError, "凭证无效或访问被拒绝");
+create_exception!(
+    beepkg_py,
+    NetworkError,
+    BeepkgError,
+    "存储服务不可达或返回错误"
+);
+create_exception!(beepkg_py, NotFoundError, BeepkgError, "包或版本不存在");
+create_exception!(
+    beepkg_py,
+    ConflictError,
+    BeepkgError,
+    "版本已存在或存在更高版本"
+);
+create_exception!(beepkg_py, LockedError, BeepkgError, "包已被锁定");
+create_exception!(
+    beepkg_py,
+    IntegrityError,
+    BeepkgError,
+    "校验和、签名或信任策略验证失败"
+);
+create_exception!(beepkg_py, ConfigError, BeepkgError, "设置不完整或无效");
+
+// 按失败类型转换为对应的 Python 异常
+fn to_py_err(error: Error) -> PyErr {
+    let message = error.to_string();
+    match error {
+        Error::Auth(_) | Error::AccessDenied(_) => AuthError::new_err(message),
+        Error::Network(_) | Error::Http(_) | Error::Offline(_) => NetworkError::new_err(message),
+        Error::NotFound(_) => NotFoundError::new_err(message),
+        Error::Conflict(_) => ConflictError::new_err(message),
+        Error::Locked(_) => LockedError::new_err(message),
+        Error::ChecksumMismatch(_) | Error::MissingChecksum | Error::PolicyViolation(_) => {
+            IntegrityError::new_err(message)
+        }
+        Error::Config(_) => ConfigError::new_err(message),
+        _ => BeepkgError::new_err(message),
+    }
+}
+
+/// 注册表中的一个包
+#[pyclass(module = "beepkg_py", frozen, get_all)]
+struct Package {
+    name: String,
+    version: String,
+    path: String,
+    size: u64,
+    created_at: String,
+}
+
+#[pymethods]
+impl Package {
+    fn __repr__(&self) -> String {
+        format!("Package({}@{})", self.name, self.version)
+    }
+}
+
+impl From<models::Package> for Package {
+    fn from(package: models::Package) -> Self {
+        Self {
+            name: package.name,
+            version: package.version,
+            path: package.storage.path,
+            size: package.storage.size,
+            created_at: package.storage.created_at,
+        }
+    }
+}
+
+/// 连接一个注册表 bucket。操作期间释放 GIL，进度信息不输出到标准输出
+#[pyclass(module = "beepkg_py", frozen)]
+struct PackageManager {
+    inner: blocking::PackageManager,
+}
+
+#[pymethods]
+impl PackageManager {
+    #[new]
+    #[pyo3(signature = (endpoint, bucket, access_key=None, secret_key=None, region=None))]
+    fn new(
+        endpoint: &str,
+        bucket: &str,
+        access_key: Option<String>,
+        secret_key: Option<String>,
+        region: Option<String>,
+    ) -> PyResult<Self> {
+        let inner = operations::PackageManager::builder()
+            .endpoint(endpoint)
+            .bucket(bucket)
+            .credentials(
+                access_key.unwrap_or_default(),
+                secret_key.unwrap_or_default(),
+            )
+            .region(region)
+            .build()
+            .and_then(|manager| manager.observer(Arc::new(SilentObserver)).blocking())
+            .map_err(to_py_err)?;
+        Ok(Self { inner })
+    }
+
+    /// 列出当前凭证可见的包
+    fn list_packages(&self, py: Python<'_>) -> PyResult<Vec<Package>> {
+        let packages = py
+            .allow_threads(|| self.inner.list_packages())
+            .map_err(to_py_err)?;
+        Ok(packages.into_iter().map(Package::from).collect())
+    }
+
+    /// 推送包目录，force 为真时覆盖已存在的版本
+    #[pyo3(signature = (path, force=false))]
+    fn push(&self, py: Python<'_>, path: PathBuf, force: bool) -> PyResult<()> {
+        py.allow_threads(|| {
+            if force {
+                self.inner.force_push_package(&path)
+            } else {
+                self.inner.push_package(&path)
+            }
+        })
+        .map_err(to_py_err)
+    }
+
+    /// 拉取包到 output_dir，package 为 name、name@version 或 name@channel
+    fn pull(&self, py: Python<'_>, package: &str, output_dir: PathBuf) -> PyResult<()> {
+        py.allow_threads(|| self.inner.pull_package(package, &output_dir))
+            .map_err(to_py_err)
+    }
+
+    /// 锁定包版本，expires_at 为 RFC 3339 时间，未指定时永不过期
+    #[pyo3(signature = (name, version, reason, user, expires_at=None))]
+    fn lock(
+        &self,
+        py: Python<'_>,
+        name: &str,
+        version: &str,
+        reason: &str,
+        user: &str,
+        expires_at: Option<&str>,
+    ) -> PyResult<()> {
+        py.allow_threads(|| {
+            self.inner
+                .lock_package(name, version, reason, user, expires_at)
+        })
+        .map_err(to_py_err)
+    }
+
+    fn unlock(&self, py: Python<'_>, name: &str, version: &str) -> PyResult<()> {
+        py.allow_threads(|| self.inner.unlock_package(name, version))
+            .map_err(to_py_err)
+    }
+}
+
+#[pymodule]
+fn beepkg_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    m.add_class::<PackageManager>()?;
+    m.add_class::<Package>()?;
+    m.add("BeepkgError", py.get_type::<BeepkgError>())?;
+    m.add("AuthError", py.get_type::<AuthError>())?;
+    m.add("NetworkError", py.get_type::<NetworkError>())?;
+    m.add("NotFoundError", py.get_type::<NotFoundError>())?;
+    m.add("ConflictError", py.get_type::<ConflictError>())?;
+    m.add("LockedError", py.get_type::<LockedError>())?;
+    m.add("IntegrityError", py.get_type::<IntegrityError>())?;
+    m.add("ConfigError", py.get_type::<ConfigError>())?;
+    Ok(())
+}
@@ -0,0 +1,14 @@
+fn main() {
+    // gRPC 接口由 proto/beepkg.proto 生成，使用随依赖发布的 protoc，不需要在系统中安装
+    #[cfg(feature = "grpc")]
+    {
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("vendored protoc not found");
+        let mut config = prost_build::Config::new();
+        config.protoc_executable(protoc);
+        // 包内容使用 bytes::Bytes，收发时不复制
+        config.bytes(["."]);
+        tonic_build::configure()
+            .compile_protos_with_config(config, &["proto/beepkg.proto"], &["proto"])
+            .expect("failed to compile proto/beepkg.proto");
+    }
+}
@@ -0,0 +1,121 @@
+use crate::Result;
+use semver::{Comparator, Op, Prerelease, Version, VersionReq};
+use serde::{Deserialize, Serialize};
+
+/// 注册表中默认的安全公告索引对象
+pub const DEFAULT_FEED: &str = "advisories/index.json";
+
+/// 安全公告索引，以 JSON 形式保存在注册表中
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AdvisoryIndex {
+    #[serde(default)]
+    pub advisories: Vec<Advisory>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Advisory {
+    pub id: String,
+    /// 受影响的包名
+    pub package: String,
+    /// 受影响的版本范围（semver 版本需求，例如 ">=1.0.0, <1.2.3"）
+    pub affected: Vec<String>,
+    /// 已修复的版本
+    #[serde(default)]
+    pub fixed: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+impl Advisory {
+    /// 指定版本是否在受影响范围内，预发布版本按版本大小判断
+    pub fn affects(&self, version: &Version) -> bool {
+        self.affected
+            .iter()
+            .filter_map(|range| VersionReq::parse(range).ok())
+            .any(|range| matches(&range, version, true))
+    }
+}
+
+impl AdvisoryIndex {
+    /// 检查所有版本范围和修复版本都能被解析
+    pub fn validate(&self) -> Result<()> {
+        for advisory in &self.advisories {
+            for range in &advisory.affected {
+                VersionReq::parse(range).map_err(|e| {
+                    format!(
+                        "Advisory {} has invalid range {}: {}",
+                        advisory.id, range, e
+                    )
+                })?;
+            }
+            for fixed in &advisory.fixed {
+                Version::parse(fixed).map_err(|e| {
+                    format!(
+                        "Advisory {} has invalid fixed version {}: {}",
+                        advisory.id, fixed, e
+                    )
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 影响指定包版本的公告
+    pub fn affecting(&self, package: &str, version: &Version) -> Vec<&Advisory> {
+        self.advisories
+            .iter()
+            .filter(|advisory| advisory.package == package && advisory.affects(version))
+            .collect()
+    }
+}
+
+/// 依赖解析结果：pack.toml 中的版本需求及其在注册表中解析到的版本
+#[derive(Debug)]
+pub struct ResolvedDependency {
+    pub name: String,
+    pub requirement: String,
+    pub version: Option<Version>,
+}
+
+/// 将版本需求解析为具体版本：精确版本直接使用，否则取注册表中满足需求的最高版本。
+/// 除非 include_prerelease，预发布版本（-alpha、-rc 等）只在需求本身指定了同一版本的预发布时才会被选中
+pub fn resolve(
+    requirement: &str,
+    available: &[Version],
+    include_prerelease: bool,
+) -> Option<Version> {
+    if let Ok(version) = Version::parse(requirement) {
+        return Some(version);
+    }
+    let requirement = VersionReq::parse(requirement).ok()?;
+    available
+        .iter()
+        .filter(|version| matches(&requirement, version, include_prerelease))
+        .max()
+        .cloned()
+}
+
+/// 版本是否满足需求；include_prerelease 时预发布版本只按版本大小比较
+pub fn matches(requirement: &VersionReq, version: &Version, include_prerelease: bool) -> bool {
+    if requirement.matches(version) {
+        return true;
+    }
+    if !include_prerelease || version.pre.is_empty() {
+        return false;
+    }
+    // semver 只在某个比较符带有同一 major.minor.patch 的预发布标记时才接受预发布版本，
+    // 追加 >=X.Y.Z-0 后其余比较符只按版本大小判断
+    let mut requirement = requirement.clone();
+    requirement.comparators.push(Comparator {
+        op: Op::GreaterEq,
+        major: version.major,
+        minor: Some(version.minor),
+        patch: Some(version.patch),
+        pre: Prerelease::new("0").unwrap_or(Prerelease::EMPTY),
+    });
+    requirement.matches(version)
+}
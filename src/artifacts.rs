@@ -0,0 +1,189 @@
+use crate::Result;
+use crate::models::{Artifact, PackageMetadata, TargetConfig};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::collections::BTreeMap;
+
+/// 附加产物的对象键位于包文件名之后，例如 demo-1.0.0.zip.artifacts/linux-x86_64.zip、
+/// demo-1.0.0.zip.artifacts/docs.zip。以包文件名加 . 开头，镜像、导出和迁移时与其他附属文件一起处理
+pub const ARTIFACTS_INFIX: &str = ".artifacts/";
+
+/// 与平台无关的产物，找不到当前平台的产物时使用
+pub const NOARCH: &str = "noarch";
+
+/// 包的附加产物对象键
+pub fn artifact_key(archive_name: &str, file_name: &str) -> String {
+    format!("{}{}{}", archive_name, ARTIFACTS_INFIX, file_name)
+}
+
+/// 平台产物的对象键
+pub fn target_key(archive_name: &str, target: &str) -> String {
+    artifact_key(archive_name, &format!("{}.zip", target))
+}
+
+/// 对象键是否属于某个包的附加产物
+pub fn is_artifact_key(key: &str) -> bool {
+    key.contains(ARTIFACTS_INFIX)
+}
+
+/// 当前主机的平台，例如 linux-x86_64、windows-x86_64、macos-aarch64
+pub fn host_target() -> String {
+    format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+/// 检查产物名（平台名或 docs 等命名产物）：由字母、数字、- 和 _ 组成。
+/// 产物名是存储文件名中第一个 . 之前的部分，因此不能包含 .
+pub fn validate_artifact_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'));
+    if !valid {
+        return Err(format!("Invalid artifact name: {}", name).into());
+    }
+    Ok(())
+}
+
+/// 命名产物的存储文件名：产物名加上本地文件的扩展名，例如 docs + dist/api-docs.tar.gz -> docs.tar.gz
+pub fn artifact_file_name(name: &str, local_path: &str) -> String {
+    let base = local_path.rsplit(['/', '\\']).next().unwrap_or(local_path);
+    match base.split_once('.') {
+        Some((_, extension)) if !extension.is_empty() => format!("{}.{}", name, extension),
+        _ => name.to_string(),
+    }
+}
+
+/// 为请求的平台选择产物：优先完全匹配，其次是 noarch
+pub fn select_target<'a>(
+    targets: &'a BTreeMap<String, TargetConfig>,
+    requested: &str,
+) -> Option<&'a str> {
+    targets
+        .get_key_value(requested)
+        .or_else(|| targets.get_key_value(NOARCH))
+        .map(|(target, _)| target.as_str())
+}
+
+/// 从对象列表中找出包的附加产物，产物自身的校验、签名等附属文件除外
+pub fn list<'a>(
+    archive_name: &str,
+    objects: impl IntoIterator<Item = (&'a str, u64)>,
+) -> Vec<Artifact> {
+    let prefix = artifact_key(archive_name, "");
+    let candidates: Vec<(&str, u64)> = objects
+        .into_iter()
+        .filter_map(|(key, size)| Some((key.strip_prefix(&prefix)?, size)))
+        .collect();
+
+    let mut artifacts: Vec<Artifact> = candidates
+        .iter()
+        .filter(|(file_name, _)| {
+            !candidates.iter().any(|(other, _)| {
+                file_name.len() > other.len()
+                    && file_name.starts_with(other)
+                    && file_name[other.len()..].starts_with('.')
+            })
+        })
+        .map(|(file_name, size)| Artifact {
+            name: file_name.split('.').next().unwrap_or(file_name).to_string(),
+            file_name: file_name.to_string(),
+            key: format!("{}{}", prefix, file_name),
+            size: *size,
+        })
+        .collect();
+    artifacts.sort_by(|a, b| a.name.cmp(&b.name));
+    artifacts
+}
+
+/// 按 pack.toml 划分包内文件：[targets] 的 includes 匹配的文件放入各平台产物，
+/// [artifacts] 中列出的文件作为命名产物单独上传，其余文件留在主包中
+pub struct ArtifactLayout {
+    targets: Vec<(String, GlobSet)>,
+    named: BTreeMap<String, String>,
+    excluded: GlobSet,
+}
+
+impl ArtifactLayout {
+    pub fn new(metadata: &PackageMetadata) -> Result<Self> {
+        let mut targets = Vec::new();
+        for (target, config) in &metadata.targets {
+            validate_artifact_name(target)?;
+            let mut builder = GlobSetBuilder::new();
+            for pattern in &config.includes {
+                let glob = GlobBuilder::new(pattern)
+                    .literal_separator(true)
+                    .build()
+                    .map_err(|e| {
+                        format!("Invalid pattern {} for target {}: {}", pattern, target, e)
+                    })?;
+                builder.add(glob);
+            }
+            targets.push((target.clone(), builder.build()?));
+        }
+
+        let mut named = BTreeMap::new();
+        for (name, path) in &metadata.artifacts {
+            validate_artifact_name(name)?;
+            if metadata.targets.contains_key(name) {
+                return Err(format!("Artifact {} has the same name as a target", name).into());
+            }
+            named.insert(name.clone(), normalize(path));
+        }
+
+        Ok(Self {
+            targets,
+            named,
+            excluded: GlobSet::empty(),
+        })
+    }
+
+    /// 额外排除的文件（例如工作区的公共 excludes），既不放入主包也不放入平台产物
+    pub fn exclude(mut self, patterns: &[String]) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            let glob = GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| format!("Invalid exclude pattern {}: {}", pattern, e))?;
+            builder.add(glob);
+        }
+        self.excluded = builder.build()?;
+        Ok(self)
+    }
+
+    /// 声明的平台名
+    pub fn targets(&self) -> impl Iterator<Item = &str> {
+        self.targets.iter().map(|(target, _)| target.as_str())
+    }
+
+    /// 命名产物及其在包目录中的相对路径
+    pub fn named(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.named
+            .iter()
+            .map(|(name, path)| (name.as_str(), path.as_str()))
+    }
+
+    /// 文件所属的平台，不属于任何平台的文件留在主包中
+    pub fn target_of(&self, path: &str) -> Option<&str> {
+        let path = normalize(path);
+        if path == "pack.toml" || path == "pack.json" || self.excluded.is_match(&path) {
+            return None;
+        }
+        self.targets
+            .iter()
+            .find(|(_, matcher)| matcher.is_match(&path))
+            .map(|(target, _)| target.as_str())
+    }
+
+    /// 文件是否不放入主包
+    pub fn excludes(&self, path: &str) -> bool {
+        let normalized = normalize(path);
+        let metadata_file = normalized == "pack.toml" || normalized == "pack.json";
+        (!metadata_file && self.excluded.is_match(&normalized))
+            || self.target_of(path).is_some()
+            || self.named.values().any(|named| *named == normalized)
+    }
+}
+
+fn normalize(path: &str) -> String {
+    path.replace('\\', "/").trim_start_matches("./").to_string()
+}
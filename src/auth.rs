@@ -0,0 +1,177 @@
+use crate::Result;
+use crate::aws::AwsCredentials;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// 保存登录令牌的文件名
+pub const CREDENTIALS_FILE: &str = "credentials.toml";
+
+/// `beepkg login` 保存的令牌，按注册表地址区分
+///
+/// ```toml
+/// [tokens]
+/// "https://registry.example.com" = "..."
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TokenStore {
+    #[serde(default)]
+    pub tokens: BTreeMap<String, String>,
+}
+
+impl TokenStore {
+    /// 读取令牌文件，文件不存在时返回空集合
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Ok(toml::from_str(&content)
+                .map_err(|e| format!("Invalid credentials file {}: {}", path.display(), e))?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 写入令牌文件，Unix 上只允许当前用户读写
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = toml::to_string_pretty(self)?;
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        std::io::Write::write_all(&mut options.open(path)?, content.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn get(&self, endpoint: &str) -> Option<&str> {
+        self.tokens.get(&registry_key(endpoint)).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, endpoint: &str, token: &str) {
+        self.tokens
+            .insert(registry_key(endpoint), token.trim().to_string());
+    }
+
+    /// 删除令牌，返回之前是否存在
+    pub fn remove(&mut self, endpoint: &str) -> bool {
+        self.tokens.remove(&registry_key(endpoint)).is_some()
+    }
+}
+
+/// 注册表地址的规范形式：补全 https:// 前缀并去掉末尾的斜杠，与 PackageManager 的处理一致
+pub fn registry_key(endpoint: &str) -> String {
+    let endpoint = endpoint.trim();
+    let endpoint = if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
+        endpoint.to_string()
+    } else {
+        format!("https://{}", endpoint)
+    };
+    endpoint.trim_end_matches('/').to_string()
+}
+
+/// beepkg 的配置目录：BEEPKG_CONFIG_DIR > XDG_CONFIG_HOME/beepkg > ~/.config/beepkg（Windows 为 %APPDATA%\beepkg）
+pub fn config_dir() -> Result<PathBuf> {
+    if let Ok(dir) = std::env::var("BEEPKG_CONFIG_DIR") {
+        return Ok(PathBuf::from(dir));
+    }
+    if let Ok(dir) = std::env::var("XDG_CONFIG_HOME") {
+        return Ok(PathBuf::from(dir).join("beepkg"));
+    }
+    if let Ok(dir) = std::env::var("APPDATA") {
+        return Ok(PathBuf::from(dir).join("beepkg"));
+    }
+    std::env::var("HOME")
+        .map(|home| PathBuf::from(home).join(".config").join("beepkg"))
+        .map_err(|_| "Cannot locate the config directory, set BEEPKG_CONFIG_DIR".into())
+}
+
+pub fn credentials_path() -> Result<PathBuf> {
+    Ok(config_dir()?.join(CREDENTIALS_FILE))
+}
+
+/// 注册表使用的 Bearer 令牌：BEEPKG_TOKEN > `beepkg login` 保存的令牌
+pub fn resolve_token(endpoint: &str) -> Result<Option<String>> {
+    if let Some(token) = env_token() {
+        return Ok(Some(token));
+    }
+    Ok(saved_tokens()?.and_then(|tokens| tokens.get(endpoint).map(str::to_string)))
+}
+
+/// BEEPKG_TOKEN 中的令牌，空白时视为未设置
+pub fn env_token() -> Option<String> {
+    std::env::var("BEEPKG_TOKEN")
+        .ok()
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+}
+
+/// `beepkg login` 保存的令牌，找不到配置目录时视为没有保存的令牌
+pub fn saved_tokens() -> Result<Option<TokenStore>> {
+    let Ok(path) = credentials_path() else {
+        return Ok(None);
+    };
+    Ok(Some(TokenStore::load(&path)?))
+}
+
+/// 凭证助手输出的凭证，兼容 docker-credential-helper 的 Username / Secret 字段
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct HelperCredentials {
+    #[serde(alias = "Username")]
+    access_key_id: String,
+    #[serde(alias = "Secret")]
+    secret_access_key: String,
+    #[serde(default)]
+    session_token: Option<String>,
+    #[serde(default)]
+    expiration: Option<DateTime<Utc>>,
+}
+
+/// 运行凭证助手获取 S3 凭证：执行 `<helper> get`，从标准输入写入注册表地址，从标准输出读取 JSON
+///
+/// ```json
+/// {"AccessKeyId": "...", "SecretAccessKey": "...", "SessionToken": "...", "Expiration": "2024-01-01T00:00:00Z"}
+/// ```
+pub async fn credential_helper(program: &str, endpoint: &str) -> Result<AwsCredentials> {
+    let mut child = Command::new(program)
+        .arg("get")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run credential helper {}: {}", program, e))?;
+
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or("Failed to open credential helper stdin")?;
+    stdin.write_all(registry_key(endpoint).as_bytes()).await?;
+    drop(stdin);
+
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        return Err(format!(
+            "Credential helper {} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    let credentials: HelperCredentials = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Invalid credential helper output: {}", e))?;
+    Ok(AwsCredentials {
+        access_key_id: credentials.access_key_id,
+        secret_access_key: credentials.secret_access_key,
+        session_token: credentials.session_token.filter(|token| !token.is_empty()),
+        expiration: credentials.expiration,
+    })
+}
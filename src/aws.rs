@@ -0,0 +1,526 @@
+use crate::Result;
+use crate::auth;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::time::Duration;
+
+// 临时凭证在过期前多久刷新
+const REFRESH_MARGIN_SECS: i64 = 300;
+
+// EC2 实例元数据服务（IMDSv2）的默认地址
+const IMDS_ENDPOINT: &str = "http://169.254.169.254";
+
+// IMDSv2 会话令牌的有效期
+const IMDS_TOKEN_TTL_SECS: u32 = 21600;
+
+/// AWS 访问凭证，临时凭证带有会话令牌和过期时间
+#[derive(Debug, Clone)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+    pub expiration: Option<DateTime<Utc>>,
+}
+
+impl AwsCredentials {
+    /// 从 AWS_ACCESS_KEY_ID / AWS_SECRET_ACCESS_KEY / AWS_SESSION_TOKEN 读取
+    pub fn from_env() -> Result<Self> {
+        Ok(AwsCredentials {
+            access_key_id: std::env::var("AWS_ACCESS_KEY_ID")
+                .map_err(|_| "AWS_ACCESS_KEY_ID is not set")?,
+            secret_access_key: std::env::var("AWS_SECRET_ACCESS_KEY")
+                .map_err(|_| "AWS_SECRET_ACCESS_KEY is not set")?,
+            session_token: std::env::var("AWS_SESSION_TOKEN").ok(),
+            expiration: None,
+        })
+    }
+
+    /// 临时凭证是否即将过期，需要刷新
+    pub fn expires_soon(&self) -> bool {
+        self.expiration.is_some_and(|expiration| {
+            expiration - chrono::Duration::seconds(REFRESH_MARGIN_SECS) <= Utc::now()
+        })
+    }
+
+    /// 转换为 rusty-s3 签名使用的凭证
+    pub fn to_s3(&self) -> rusty_s3::Credentials {
+        match &self.session_token {
+            Some(token) => rusty_s3::Credentials::new_with_token(
+                self.access_key_id.clone(),
+                self.secret_access_key.clone(),
+                token.clone(),
+            ),
+            None => rusty_s3::Credentials::new(
+                self.access_key_id.clone(),
+                self.secret_access_key.clone(),
+            ),
+        }
+    }
+}
+
+/// STS AssumeRole 配置
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AssumeRole {
+    pub role_arn: String,
+    #[serde(default = "default_session_name")]
+    pub session_name: String,
+    #[serde(default)]
+    pub external_id: Option<String>,
+    #[serde(default = "default_duration")]
+    pub duration_seconds: u32,
+}
+
+fn default_session_name() -> String {
+    "beepkg".to_string()
+}
+
+fn default_duration() -> u32 {
+    3600
+}
+
+impl AssumeRole {
+    /// 从 BEEPKG_ASSUME_ROLE（角色 ARN）及 BEEPKG_ROLE_SESSION_NAME / BEEPKG_ROLE_EXTERNAL_ID /
+    /// BEEPKG_ROLE_DURATION 读取，未设置角色时返回 None
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(role_arn) = std::env::var("BEEPKG_ASSUME_ROLE") else {
+            return Ok(None);
+        };
+        let duration_seconds = match std::env::var("BEEPKG_ROLE_DURATION") {
+            Ok(value) => value
+                .parse()
+                .map_err(|_| format!("Invalid BEEPKG_ROLE_DURATION: {}", value))?,
+            Err(_) => default_duration(),
+        };
+        Ok(Some(AssumeRole {
+            role_arn,
+            session_name: std::env::var("BEEPKG_ROLE_SESSION_NAME")
+                .unwrap_or_else(|_| default_session_name()),
+            external_id: std::env::var("BEEPKG_ROLE_EXTERNAL_ID").ok(),
+            duration_seconds,
+        }))
+    }
+}
+
+/// 临时凭证的来源，凭证临近过期时重新获取
+#[derive(Debug, Clone)]
+pub enum CredentialProvider {
+    /// 使用源凭证调用 STS AssumeRole
+    AssumeRole { region: String, role: AssumeRole },
+    /// EKS IRSA：使用服务账号的 OIDC 令牌调用 STS AssumeRoleWithWebIdentity
+    WebIdentity {
+        region: String,
+        role_arn: String,
+        token_file: PathBuf,
+        session_name: String,
+    },
+    /// EC2 实例配置文件，通过 IMDSv2 获取
+    InstanceMetadata { endpoint: String },
+    /// 外部凭证助手（config.toml 中的 credential_helper）
+    Helper { program: String, endpoint: String },
+}
+
+impl CredentialProvider {
+    /// 未配置密钥时从运行环境获取凭证：EKS IRSA（AWS_ROLE_ARN + AWS_WEB_IDENTITY_TOKEN_FILE）>
+    /// EC2 实例元数据（AWS_EC2_METADATA_DISABLED=true 时跳过）
+    pub fn from_environment() -> Option<Self> {
+        if let (Ok(role_arn), Ok(token_file)) = (
+            std::env::var("AWS_ROLE_ARN"),
+            std::env::var("AWS_WEB_IDENTITY_TOKEN_FILE"),
+        ) {
+            return Some(CredentialProvider::WebIdentity {
+                region: default_region(),
+                role_arn,
+                token_file: PathBuf::from(token_file),
+                session_name: std::env::var("AWS_ROLE_SESSION_NAME")
+                    .unwrap_or_else(|_| default_session_name()),
+            });
+        }
+        let disabled = std::env::var("AWS_EC2_METADATA_DISABLED")
+            .is_ok_and(|value| value.eq_ignore_ascii_case("true"));
+        if disabled {
+            return None;
+        }
+        Some(CredentialProvider::InstanceMetadata {
+            endpoint: std::env::var("AWS_EC2_METADATA_SERVICE_ENDPOINT")
+                .unwrap_or_else(|_| IMDS_ENDPOINT.to_string()),
+        })
+    }
+
+    /// 调用 STS 时使用的区域，PackageManager 使用自己的签名区域
+    pub fn with_region(mut self, region: &str) -> Self {
+        match &mut self {
+            CredentialProvider::AssumeRole {
+                region: current, ..
+            }
+            | CredentialProvider::WebIdentity {
+                region: current, ..
+            } => *current = region.to_string(),
+            CredentialProvider::InstanceMetadata { .. } | CredentialProvider::Helper { .. } => {}
+        }
+        self
+    }
+
+    /// 获取新的临时凭证，source 为配置的静态凭证，未配置时读取 AWS 环境变量
+    ///
+    /// 返回 None 表示该来源在当前环境中不可用（例如不在 EC2 上运行）
+    pub async fn fetch(
+        &self,
+        client: &Client,
+        source: Option<&AwsCredentials>,
+    ) -> Result<Option<AwsCredentials>> {
+        match self {
+            CredentialProvider::AssumeRole { region, role } => {
+                let source = match source {
+                    Some(source) => source.clone(),
+                    None => AwsCredentials::from_env()?,
+                };
+                Ok(Some(assume_role(client, &source, region, role).await?))
+            }
+            CredentialProvider::WebIdentity {
+                region,
+                role_arn,
+                token_file,
+                session_name,
+            } => {
+                // 令牌文件会被 kubelet 定期轮换，每次刷新都重新读取
+                let token = std::fs::read_to_string(token_file).map_err(|e| {
+                    format!(
+                        "Cannot read web identity token {}: {}",
+                        token_file.display(),
+                        e
+                    )
+                })?;
+                let credentials =
+                    assume_role_with_web_identity(client, region, role_arn, session_name, &token)
+                        .await?;
+                Ok(Some(credentials))
+            }
+            CredentialProvider::InstanceMetadata { endpoint } => {
+                instance_metadata_credentials(client, endpoint).await
+            }
+            CredentialProvider::Helper { program, endpoint } => {
+                Ok(Some(auth::credential_helper(program, endpoint).await?))
+            }
+        }
+    }
+}
+
+/// 没有配置区域时使用的区域
+pub const DEFAULT_REGION: &str = "us-east-1";
+
+/// 环境变量中的区域：AWS_REGION > AWS_DEFAULT_REGION
+pub fn env_region() -> Option<String> {
+    std::env::var("AWS_REGION")
+        .or_else(|_| std::env::var("AWS_DEFAULT_REGION"))
+        .ok()
+}
+
+/// 默认区域：AWS_REGION > AWS_DEFAULT_REGION > us-east-1
+pub fn default_region() -> String {
+    env_region().unwrap_or_else(|| DEFAULT_REGION.to_string())
+}
+
+#[derive(Deserialize)]
+struct StsResponse {
+    #[serde(rename = "AssumeRoleResult", alias = "AssumeRoleWithWebIdentityResult")]
+    result: StsResult,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct StsResult {
+    credentials: StsCredentials,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct StsCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: String,
+    expiration: String,
+}
+
+impl From<StsCredentials> for AwsCredentials {
+    fn from(credentials: StsCredentials) -> Self {
+        AwsCredentials {
+            access_key_id: credentials.access_key_id,
+            secret_access_key: credentials.secret_access_key,
+            session_token: Some(credentials.session_token),
+            expiration: parse_expiration(&credentials.expiration),
+        }
+    }
+}
+
+/// IMDS 返回的实例配置文件凭证
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ImdsCredentials {
+    code: String,
+    access_key_id: String,
+    secret_access_key: String,
+    token: String,
+    expiration: String,
+}
+
+fn parse_expiration(expiration: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(expiration)
+        .map(|expiration| expiration.with_timezone(&Utc))
+        .ok()
+}
+
+// STS 服务地址，BEEPKG_STS_ENDPOINT 可指定兼容服务的地址
+fn sts_url(region: &str) -> Result<url::Url> {
+    let endpoint = std::env::var("BEEPKG_STS_ENDPOINT")
+        .unwrap_or_else(|_| format!("https://sts.{}.amazonaws.com", region));
+    Ok(url::Url::parse(&endpoint)?)
+}
+
+/// 调用 STS AssumeRole 获取临时凭证
+pub async fn assume_role(
+    client: &Client,
+    source: &AwsCredentials,
+    region: &str,
+    role: &AssumeRole,
+) -> Result<AwsCredentials> {
+    let url = sts_url(region)?;
+
+    // Serializer 不是 Send，不能跨越 await 存活
+    let payload = {
+        let mut form = url::form_urlencoded::Serializer::new(String::new());
+        form.append_pair("Action", "AssumeRole")
+            .append_pair("Version", "2011-06-15")
+            .append_pair("RoleArn", &role.role_arn)
+            .append_pair("RoleSessionName", &role.session_name)
+            .append_pair("DurationSeconds", &role.duration_seconds.to_string());
+        if let Some(external_id) = &role.external_id {
+            form.append_pair("ExternalId", external_id);
+        }
+        form.finish().into_bytes()
+    };
+
+    let headers = sign_post(
+        source,
+        region,
+        "sts",
+        &url,
+        &[(
+            "content-type",
+            "application/x-www-form-urlencoded; charset=utf-8".to_string(),
+        )],
+        &payload,
+    )?;
+    let mut request = client.post(url).body(payload);
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+
+    sts_credentials(request, "AssumeRole", &role.role_arn).await
+}
+
+/// 调用 STS AssumeRoleWithWebIdentity，请求由 OIDC 令牌认证，不需要签名
+pub async fn assume_role_with_web_identity(
+    client: &Client,
+    region: &str,
+    role_arn: &str,
+    session_name: &str,
+    token: &str,
+) -> Result<AwsCredentials> {
+    let url = sts_url(region)?;
+    let payload = url::form_urlencoded::Serializer::new(String::new())
+        .append_pair("Action", "AssumeRoleWithWebIdentity")
+        .append_pair("Version", "2011-06-15")
+        .append_pair("RoleArn", role_arn)
+        .append_pair("RoleSessionName", session_name)
+        .append_pair("WebIdentityToken", token.trim())
+        .finish();
+
+    let request = client
+        .post(url)
+        .header(
+            "content-type",
+            "application/x-www-form-urlencoded; charset=utf-8",
+        )
+        .body(payload);
+    sts_credentials(request, "AssumeRoleWithWebIdentity", role_arn).await
+}
+
+async fn sts_credentials(
+    request: reqwest::RequestBuilder,
+    action: &str,
+    role_arn: &str,
+) -> Result<AwsCredentials> {
+    let response = request.send().await?;
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(format!("STS {} {} failed: {} {}", action, role_arn, status, body).into());
+    }
+    let response: StsResponse = quick_xml::de::from_str(&body)
+        .map_err(|e| format!("Invalid STS {} response: {}", action, e))?;
+    Ok(response.result.credentials.into())
+}
+
+/// 通过 IMDSv2 获取 EC2 实例配置文件的临时凭证
+///
+/// 元数据服务不可达（不在 EC2 上）或实例未绑定配置文件时返回 None
+pub async fn instance_metadata_credentials(
+    client: &Client,
+    endpoint: &str,
+) -> Result<Option<AwsCredentials>> {
+    let endpoint = endpoint.trim_end_matches('/');
+
+    // 不在 EC2 上时连接会挂起，用较短的超时快速放弃
+    let response = client
+        .put(format!("{}/latest/api/token", endpoint))
+        .header(
+            "x-aws-ec2-metadata-token-ttl-seconds",
+            IMDS_TOKEN_TTL_SECS.to_string(),
+        )
+        .timeout(Duration::from_secs(1))
+        .send()
+        .await;
+    let Ok(response) = response else {
+        return Ok(None);
+    };
+    if !response.status().is_success() {
+        return Err(format!("IMDSv2 token request failed: {}", response.status()).into());
+    }
+    let token = response.text().await?;
+
+    let roles_url = format!("{}/latest/meta-data/iam/security-credentials/", endpoint);
+    let response = client
+        .get(&roles_url)
+        .header("x-aws-ec2-metadata-token", &token)
+        .send()
+        .await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(format!("IMDS role lookup failed: {}", response.status()).into());
+    }
+    let roles = response.text().await?;
+    let Some(role) = roles.lines().map(str::trim).find(|line| !line.is_empty()) else {
+        return Ok(None);
+    };
+
+    let response = client
+        .get(format!("{}{}", roles_url, role))
+        .header("x-aws-ec2-metadata-token", &token)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "IMDS credentials request for {} failed: {}",
+            role,
+            response.status()
+        )
+        .into());
+    }
+    let credentials: ImdsCredentials = serde_json::from_slice(&response.bytes().await?)
+        .map_err(|e| format!("Invalid IMDS credentials response: {}", e))?;
+    if credentials.code != "Success" {
+        return Err(format!(
+            "IMDS returned {} for instance profile {}",
+            credentials.code, role
+        )
+        .into());
+    }
+    Ok(Some(AwsCredentials {
+        access_key_id: credentials.access_key_id,
+        secret_access_key: credentials.secret_access_key,
+        session_token: Some(credentials.token),
+        expiration: parse_expiration(&credentials.expiration),
+    }))
+}
+
+/// 使用 SigV4 为 POST 请求签名，返回需要附加的请求头（包括 Authorization，不包括 host）
+///
+/// headers 中的名称必须为小写
+pub fn sign_post(
+    credentials: &AwsCredentials,
+    region: &str,
+    service: &str,
+    url: &url::Url,
+    headers: &[(&'static str, String)],
+    payload: &[u8],
+) -> Result<Vec<(&'static str, String)>> {
+    let host = match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        (None, _) => return Err(format!("Invalid AWS endpoint: {}", url).into()),
+    };
+
+    let now = Utc::now();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+
+    // SigV4 要求按名称排序的规范请求头
+    let mut headers = headers.to_vec();
+    headers.push(("host", host));
+    headers.push(("x-amz-date", amz_date.clone()));
+    if let Some(token) = &credentials.session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+    headers.sort_by(|a, b| a.0.cmp(b.0));
+
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request = format!(
+        "POST\n{}\n\n{}\n{}\n{}",
+        url.path(),
+        canonical_headers,
+        signed_headers,
+        hex_sha256(payload)
+    );
+
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex_sha256(canonical_request.as_bytes())
+    );
+
+    let mut signing_key = format!("AWS4{}", credentials.secret_access_key).into_bytes();
+    for part in [date.as_str(), region, service, "aws4_request"] {
+        signing_key = hmac_sha256(&signing_key, part.as_bytes());
+    }
+    let signature: String = hmac_sha256(&signing_key, string_to_sign.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+
+    headers.retain(|(name, _)| *name != "host");
+    headers.push((
+        "authorization",
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            credentials.access_key_id, scope, signed_headers, signature
+        ),
+    ));
+    Ok(headers)
+}
+
+fn hex_sha256(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
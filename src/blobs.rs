@@ -0,0 +1,73 @@
+use crate::Result;
+use crate::checksum::{Checksum, ChecksumAlgorithm};
+use serde::{Deserialize, Serialize};
+
+/// 内容寻址存储中 blob 的对象键前缀，完整的键为 blobs/<algo>/<hex>
+pub const BLOBS_PREFIX: &str = "blobs/";
+
+/// 指针对象的大小上限，更大的对象总是按包内容处理
+pub const MAX_POINTER_SIZE: u64 = 1024;
+
+/// 内容对应的 blob 对象键，例如 blobs/sha256/<hex>
+pub fn blob_key(checksum: &Checksum) -> String {
+    format!(
+        "{}{}/{}",
+        BLOBS_PREFIX,
+        checksum.algorithm.name(),
+        checksum.hex
+    )
+}
+
+/// 对象键是否是 blob
+pub fn is_blob_key(key: &str) -> bool {
+    key.starts_with(BLOBS_PREFIX)
+}
+
+/// 上传中的内容的临时对象键，例如 blobs/uploads/<随机数>.tmp。上传完成后复制到 blob_key 并删除，
+/// 中断后留下的对象由 gc 按临时文件清理
+pub fn staging_key() -> String {
+    format!("{}uploads/{:016x}.tmp", BLOBS_PREFIX, rand::random::<u64>())
+}
+
+/// cas 布局下存放在包文件对象键处的指针，指向保存实际内容的 blob。
+/// 多个版本或包的内容相同时指向同一个 blob
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlobPointer {
+    /// blob 的对象键
+    pub blob: String,
+    /// blob 内容的校验和（`algo:hex`）
+    pub checksum: String,
+    /// blob 的字节数
+    pub size: u64,
+}
+
+impl BlobPointer {
+    pub fn new(checksum: &Checksum, size: u64) -> Self {
+        Self {
+            blob: blob_key(checksum),
+            checksum: checksum.to_string(),
+            size,
+        }
+    }
+
+    /// 解析对象内容，不是指针（例如 flat 布局下的包文件）时返回 None。
+    /// 对象键必须与校验和一致，避免指针被改写为指向其他对象
+    pub fn parse(content: &[u8]) -> Option<Self> {
+        if content.len() as u64 > MAX_POINTER_SIZE {
+            return None;
+        }
+        let pointer: Self = serde_json::from_slice(content).ok()?;
+        let checksum = pointer.digest().ok()?;
+        (pointer.blob == blob_key(&checksum)).then_some(pointer)
+    }
+
+    /// blob 内容的校验和，拉取时按它验证下载的内容
+    pub fn digest(&self) -> Result<Checksum> {
+        Checksum::parse(&self.checksum, ChecksumAlgorithm::Sha256)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).unwrap_or_default()
+    }
+}
@@ -0,0 +1,168 @@
+use crate::advisory::ResolvedDependency;
+use crate::checksum::Checksum;
+use crate::diagnostics::Diagnosis;
+use crate::error::BeepkgError;
+use crate::models;
+use crate::operations::{self, PackageConflictStatus};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::runtime::{Builder, Runtime};
+
+/// 同步版本的 PackageManager，供不使用 async 的命令行工具和构建脚本调用。
+/// 操作在内部的单线程运行时上执行，不能在其他 tokio 运行时中调用
+pub struct PackageManager {
+    inner: operations::PackageManager,
+    runtime: Runtime,
+}
+
+impl operations::PackageManager {
+    /// 转换为同步接口
+    pub fn blocking(self) -> Result<PackageManager, BeepkgError> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        Ok(PackageManager {
+            inner: self,
+            runtime,
+        })
+    }
+}
+
+impl PackageManager {
+    /// 底层的异步 PackageManager
+    pub fn inner(&self) -> &operations::PackageManager {
+        &self.inner
+    }
+
+    /// 执行没有同步封装的操作，例如 `manager.block_on(|m| m.fsck(false))`
+    pub fn block_on<'a, F, T>(
+        &'a self,
+        operation: impl FnOnce(&'a operations::PackageManager) -> F,
+    ) -> T
+    where
+        F: Future<Output = T>,
+    {
+        self.runtime.block_on(operation(&self.inner))
+    }
+
+    pub fn list_packages(&self) -> Result<Vec<models::Package>, BeepkgError> {
+        self.block_on(|m| m.list_packages())
+    }
+
+    pub fn push_package(&self, package_path: &Path) -> Result<(), BeepkgError> {
+        self.block_on(|m| m.push_package(package_path))
+    }
+
+    pub fn force_push_package(&self, package_path: &Path) -> Result<(), BeepkgError> {
+        self.block_on(|m| m.force_push_package(package_path))
+    }
+
+    pub fn push_archive(
+        &self,
+        archive_path: &Path,
+        force: bool,
+    ) -> Result<models::PackageMetadata, BeepkgError> {
+        self.block_on(|m| m.push_archive(archive_path, force))
+    }
+
+    pub fn check_package_conflict(
+        &self,
+        package_name: &str,
+        version: &str,
+    ) -> Result<PackageConflictStatus, BeepkgError> {
+        self.block_on(|m| m.check_package_conflict(package_name, version))
+    }
+
+    pub fn exists(&self, name: &str, version: &str) -> Result<bool, BeepkgError> {
+        self.block_on(|m| m.exists(name, version))
+    }
+
+    pub fn stat(
+        &self,
+        name: &str,
+        version: &str,
+    ) -> Result<Option<models::PackageStat>, BeepkgError> {
+        self.block_on(|m| m.stat(name, version))
+    }
+
+    pub fn pull_package(&self, package_name: &str, output_dir: &Path) -> Result<(), BeepkgError> {
+        self.block_on(|m| m.pull_package(package_name, output_dir))
+    }
+
+    pub fn fetch_package(
+        &self,
+        package_name: &str,
+        output: &Path,
+    ) -> Result<Checksum, BeepkgError> {
+        self.block_on(|m| m.fetch_package(package_name, output))
+    }
+
+    pub fn install_package(
+        &self,
+        package: &str,
+        output_dir: &Path,
+        features: &[String],
+        default_features: bool,
+    ) -> Result<Vec<String>, BeepkgError> {
+        self.block_on(|m| m.install_package(package, output_dir, features, default_features))
+    }
+
+    pub fn resolve_dependencies(
+        &self,
+        dependencies: &HashMap<String, String>,
+    ) -> Result<Vec<ResolvedDependency>, BeepkgError> {
+        self.block_on(|m| m.resolve_dependencies(dependencies))
+    }
+
+    pub fn list_artifacts(&self, package: &str) -> Result<Vec<models::Artifact>, BeepkgError> {
+        self.block_on(|m| m.list_artifacts(package))
+    }
+
+    pub fn pull_artifact(
+        &self,
+        package: &str,
+        artifact: &str,
+        output_dir: &Path,
+    ) -> Result<PathBuf, BeepkgError> {
+        self.block_on(|m| m.pull_artifact(package, artifact, output_dir))
+    }
+
+    pub fn share_package(
+        &self,
+        package: &str,
+        expires: Duration,
+    ) -> Result<(url::Url, url::Url), BeepkgError> {
+        self.block_on(|m| m.share_package(package, expires))
+    }
+
+    pub fn tag_package(&self, package: &str, channel: &str) -> Result<bool, BeepkgError> {
+        self.block_on(|m| m.tag_package(package, channel))
+    }
+
+    pub fn list_channels(&self, package: &str) -> Result<BTreeMap<String, String>, BeepkgError> {
+        self.block_on(|m| m.list_channels(package))
+    }
+
+    pub fn lock_package(
+        &self,
+        package_name: &str,
+        version: &str,
+        reason: &str,
+        user: &str,
+        expires_at: Option<&str>,
+    ) -> Result<(), BeepkgError> {
+        self.block_on(|m| m.lock_package(package_name, version, reason, user, expires_at))
+    }
+
+    pub fn unlock_package(&self, package_name: &str, version: &str) -> Result<(), BeepkgError> {
+        self.block_on(|m| m.unlock_package(package_name, version))
+    }
+
+    pub fn test_connection(&self) -> Result<(bool, String), BeepkgError> {
+        self.block_on(|m| m.test_connection())
+    }
+
+    pub fn diagnose(&self, write: bool) -> Result<Diagnosis, BeepkgError> {
+        self.block_on(|m| m.diagnose(write))
+    }
+}
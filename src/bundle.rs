@@ -0,0 +1,166 @@
+use crate::Result;
+use crate::models::PackageAccess;
+use crate::project::ProjectLock;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// 当前的包集格式版本
+pub const FORMAT_VERSION: u32 = 1;
+
+/// 清单在包集中的路径，总是第一个条目
+pub const MANIFEST_PATH: &str = "manifest.json";
+
+/// 注册表对象在包集中的目录
+pub const OBJECTS_DIR: &str = "objects";
+
+/// 离线包集（tar + zstd）的清单
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format_version: u32,
+    pub created_at: String,
+    /// 导出时的注册表地址
+    pub source: String,
+    pub packages: Vec<BundlePackage>,
+    /// 所选包的可见性和授权
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub access: BTreeMap<String, PackageAccess>,
+    /// bundle create 生成的项目包集，bundle install 按它安装
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<BundleProject>,
+}
+
+/// 项目包集的环境配置和解析结果，包集中的包即锁文件中的全部包
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundleProject {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    pub lock: ProjectLock,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundlePackage {
+    /// 包文件的对象键
+    pub archive: String,
+    /// 包文件的校验和（`algo:hex`）
+    pub checksum: String,
+    /// 校验、签名、证明等附属文件的对象键
+    #[serde(default)]
+    pub sidecars: Vec<String>,
+}
+
+impl BundleManifest {
+    /// 清单中是否列出了该对象
+    pub fn contains(&self, key: &str) -> bool {
+        self.packages
+            .iter()
+            .any(|package| package.archive == key || package.sidecars.iter().any(|s| s == key))
+    }
+}
+
+/// 对象在包集中的路径
+pub fn object_path(key: &str) -> String {
+    format!("{}/{}", OBJECTS_DIR, key)
+}
+
+/// 写入包集：创建时先写入清单，随后逐个追加对象
+pub struct BundleWriter {
+    builder: tar::Builder<zstd::Encoder<'static, File>>,
+}
+
+impl BundleWriter {
+    pub fn create(path: &Path, manifest: &BundleManifest) -> Result<Self> {
+        Self::create_with_manifest(path, MANIFEST_PATH, manifest)
+    }
+
+    /// 以其他清单（例如注册表快照的清单）开头的归档，对象同样保存在 objects/ 下
+    pub fn create_with_manifest(
+        path: &Path,
+        manifest_path: &str,
+        manifest: &impl Serialize,
+    ) -> Result<Self> {
+        let encoder = zstd::Encoder::new(File::create(path)?, 0)?;
+        let mut writer = Self {
+            builder: tar::Builder::new(encoder),
+        };
+        writer.append_entry(manifest_path, &serde_json::to_vec_pretty(manifest)?)?;
+        Ok(writer)
+    }
+
+    /// 追加本地文件作为对象
+    pub fn append_file(&mut self, key: &str, path: &Path) -> Result<()> {
+        self.builder
+            .append_path_with_name(path, object_path(key))
+            .map_err(|e| format!("Failed to add {} to the bundle: {}", key, e))?;
+        Ok(())
+    }
+
+    /// 追加内存中的对象内容
+    pub fn append_bytes(&mut self, key: &str, data: &[u8]) -> Result<()> {
+        self.append_entry(&object_path(key), data)
+    }
+
+    /// 写完 tar 结尾并刷新 zstd 流
+    pub fn finish(self) -> Result<()> {
+        self.builder.into_inner()?.finish()?;
+        Ok(())
+    }
+
+    fn append_entry(&mut self, path: &str, data: &[u8]) -> Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+        header.set_cksum();
+        self.builder.append_data(&mut header, path, data)?;
+        Ok(())
+    }
+}
+
+/// 把包集解压到目录：第一个条目必须是清单，其余条目必须是清单中列出的对象，
+/// 对象保存在 `<dir>/objects/<key>`
+pub fn unpack(path: &Path, dir: &Path) -> Result<BundleManifest> {
+    let decoder = zstd::Decoder::new(File::open(path)?)
+        .map_err(|e| format!("Failed to open bundle {}: {}", path.display(), e))?;
+    let mut archive = tar::Archive::new(decoder);
+    let mut entries = archive.entries()?;
+
+    let mut first = entries
+        .next()
+        .ok_or_else(|| format!("Bundle {} is empty", path.display()))??;
+    if first.path()?.to_str() != Some(MANIFEST_PATH) {
+        return Err(format!(
+            "Bundle {} does not start with {}",
+            path.display(),
+            MANIFEST_PATH
+        )
+        .into());
+    }
+    let manifest: BundleManifest = serde_json::from_reader(BufReader::new(&mut first))
+        .map_err(|e| format!("Invalid bundle manifest: {}", e))?;
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(format!(
+            "Bundle format version {} is newer than the supported version {}",
+            manifest.format_version, FORMAT_VERSION
+        )
+        .into());
+    }
+
+    for entry in entries {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.to_string_lossy().into_owned();
+        let key = entry_path
+            .strip_prefix(OBJECTS_DIR)
+            .and_then(|key| key.strip_prefix('/'))
+            .filter(|key| manifest.contains(key))
+            .ok_or_else(|| format!("Unexpected entry in bundle: {}", entry_path))?;
+        // unpack_in 拒绝包含 .. 的路径
+        if !entry.unpack_in(dir)? {
+            return Err(format!("Refusing to unpack {} outside the target directory", key).into());
+        }
+    }
+
+    Ok(manifest)
+}
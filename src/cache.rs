@@ -0,0 +1,294 @@
+use crate::Result;
+use crate::checksum::{Checksum, ChecksumAlgorithm};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// 包内容按校验和存放的目录：archives/<algo>/<hex>
+const ARCHIVES_DIR: &str = "archives";
+
+/// 对象引用所在的目录：refs/<registry>/<key>.ref.json
+const REFS_DIR: &str = "refs";
+
+const REF_SUFFIX: &str = ".ref.json";
+
+/// 注册表中包名称的索引，list 时更新，补全包名时读取
+const PACKAGE_INDEX: &str = "package-index.json";
+
+/// 本地缓存目录：BEEPKG_CACHE_DIR > XDG_CACHE_HOME/beepkg > ~/.cache/beepkg（Windows 为 %LOCALAPPDATA%\beepkg\cache）
+pub fn cache_dir() -> Result<PathBuf> {
+    if let Ok(dir) = std::env::var("BEEPKG_CACHE_DIR") {
+        return Ok(PathBuf::from(dir));
+    }
+    if let Ok(dir) = std::env::var("XDG_CACHE_HOME") {
+        return Ok(PathBuf::from(dir).join("beepkg"));
+    }
+    if let Ok(dir) = std::env::var("LOCALAPPDATA") {
+        return Ok(PathBuf::from(dir).join("beepkg").join("cache"));
+    }
+    std::env::var("HOME")
+        .map(|home| PathBuf::from(home).join(".cache").join("beepkg"))
+        .map_err(|_| "Cannot locate the cache directory, set BEEPKG_CACHE_DIR".into())
+}
+
+/// 注册表中的对象在缓存中的引用：内容的校验和，以及拉取时验证过的签名者
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheRef {
+    /// 内容的校验和（`algo:hex`）
+    pub checksum: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer: Option<String>,
+    pub cached_at: String,
+}
+
+/// cache clean 的结果
+#[derive(Debug, Default)]
+pub struct CleanReport {
+    /// 删除的包文件数量
+    pub removed: usize,
+    /// 释放的字节数
+    pub freed: u64,
+    /// 清理后缓存中包文件的总大小
+    pub remaining: u64,
+}
+
+/// 下载过的包文件的本地缓存。内容按校验和存放，同一内容只保存一份；
+/// 对象引用按注册表区分，记录对象键对应的校验和，离线时据此找到内容
+pub struct Cache {
+    root: PathBuf,
+}
+
+impl Cache {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    pub fn from_env() -> Result<Self> {
+        Ok(Self::new(cache_dir()?))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// 内容在缓存中的路径
+    pub fn archive_path(&self, checksum: &Checksum) -> PathBuf {
+        self.root
+            .join(ARCHIVES_DIR)
+            .join(checksum.algorithm.name())
+            .join(&checksum.hex)
+    }
+
+    /// 把缓存的内容复制到 path 并重新校验。缓存中没有时返回 false，
+    /// 内容已损坏时删除缓存文件并返回 false
+    pub fn restore(&self, checksum: &Checksum, path: &Path) -> Result<bool> {
+        let cached = self.archive_path(checksum);
+        if !cached.is_file() {
+            return Ok(false);
+        }
+        let actual = Checksum::compute_reader(checksum.algorithm, std::fs::File::open(&cached)?)?;
+        if actual != *checksum {
+            std::fs::remove_file(&cached)?;
+            return Ok(false);
+        }
+        std::fs::copy(&cached, path)?;
+        // 修改时间作为最近使用时间，clean 时先删除最久未使用的内容
+        std::fs::File::options()
+            .write(true)
+            .open(&cached)?
+            .set_modified(SystemTime::now())?;
+        Ok(true)
+    }
+
+    /// 把已校验的文件存入缓存，先写临时文件再重命名，避免并发拉取看到不完整的内容
+    pub fn store(&self, checksum: &Checksum, path: &Path) -> Result<()> {
+        let cached = self.archive_path(checksum);
+        if cached.is_file() {
+            return Ok(());
+        }
+        let dir = cached.parent().ok_or("Invalid cache path")?;
+        std::fs::create_dir_all(dir)?;
+        let temp = tempfile::NamedTempFile::new_in(dir)?;
+        std::fs::copy(path, temp.path())?;
+        temp.persist(&cached).map_err(|e| e.error)?;
+        Ok(())
+    }
+
+    /// 记录注册表中对象键对应的内容
+    pub fn record(&self, registry: &str, key: &str, entry: &CacheRef) -> Result<()> {
+        let path = self.ref_path(registry, key);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_vec_pretty(entry)?)?;
+        Ok(())
+    }
+
+    /// 对象键在缓存中的引用，引用的内容已被清理时返回 None
+    pub fn lookup(&self, registry: &str, key: &str) -> Option<(Checksum, Option<String>)> {
+        let content = std::fs::read_to_string(self.ref_path(registry, key)).ok()?;
+        let entry: CacheRef = serde_json::from_str(&content).ok()?;
+        let checksum = Checksum::parse(&entry.checksum, ChecksumAlgorithm::Sha256).ok()?;
+        if !self.archive_path(&checksum).is_file() {
+            return None;
+        }
+        Some((checksum, entry.signer))
+    }
+
+    /// 缓存中某个包的版本，离线解析最新版本和版本范围时使用
+    pub fn versions(&self, registry: &str, name: &str) -> Vec<semver::Version> {
+        let (dir, base) = match name.rsplit_once('/') {
+            Some((scope, base)) => (self.registry_dir(registry).join(scope), base),
+            None => (self.registry_dir(registry), name),
+        };
+        let prefix = format!("{}-", base);
+        let suffix = format!(".zip{}", REF_SUFFIX);
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter_map(|file_name| {
+                let version = file_name.strip_prefix(&prefix)?.strip_suffix(&suffix)?;
+                semver::Version::parse(version).ok()
+            })
+            .filter(|version| {
+                self.lookup(registry, &format!("{}-{}.zip", name, version))
+                    .is_some()
+            })
+            .collect()
+    }
+
+    /// 缓存注册表中的小对象（例如注册表元数据），离线时读取
+    pub fn store_object(&self, registry: &str, key: &str, content: &[u8]) -> Result<()> {
+        let path = self.registry_dir(registry).join(key);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    pub fn load_object(&self, registry: &str, key: &str) -> Option<Vec<u8>> {
+        std::fs::read(self.registry_dir(registry).join(key)).ok()
+    }
+
+    /// 记录注册表中的全部包名称
+    pub fn store_package_names(&self, registry: &str, names: &[String]) -> Result<()> {
+        self.store_object(registry, PACKAGE_INDEX, &serde_json::to_vec(names)?)
+    }
+
+    /// 以 prefix 开头的包名称：来自上次 list 记录的索引，以及缓存过包文件的包。
+    /// 只读本地文件，不访问注册表
+    pub fn package_names(&self, registry: &str, prefix: &str) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .load_object(registry, PACKAGE_INDEX)
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default();
+        let dir = self.registry_dir(registry);
+        let suffix = format!(".zip{}", REF_SUFFIX);
+        for entry in walkdir::WalkDir::new(&dir).into_iter().flatten() {
+            let Ok(key) = entry.path().strip_prefix(&dir) else {
+                continue;
+            };
+            let key = key.to_string_lossy().replace('\\', "/");
+            let Some(stem) = key.strip_suffix(&suffix) else {
+                continue;
+            };
+            // <name>-<version>，版本中也可能有 -，找到能解析为版本的位置
+            let name = stem.match_indices('-').find_map(|(i, _)| {
+                semver::Version::parse(&stem[i + 1..])
+                    .is_ok()
+                    .then(|| stem[..i].to_string())
+            });
+            names.extend(name);
+        }
+        names.retain(|name| name.starts_with(prefix));
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// 缓存中包文件的总大小
+    pub fn size(&self) -> Result<u64> {
+        Ok(self.archives()?.iter().map(|(_, size, _)| size).sum())
+    }
+
+    /// 删除最久未使用的包文件，直到总大小不超过 max_size，随后删除失效的引用
+    pub fn clean(&self, max_size: u64) -> Result<CleanReport> {
+        let mut archives = self.archives()?;
+        archives.sort_by_key(|(_, _, used)| *used);
+
+        let mut report = CleanReport {
+            remaining: archives.iter().map(|(_, size, _)| size).sum(),
+            ..Default::default()
+        };
+        for (path, size, _) in archives {
+            if report.remaining <= max_size {
+                break;
+            }
+            std::fs::remove_file(path)?;
+            report.removed += 1;
+            report.freed += size;
+            report.remaining -= size;
+        }
+
+        let refs = self.root.join(REFS_DIR);
+        if refs.is_dir() {
+            for entry in walkdir::WalkDir::new(&refs) {
+                let entry = entry?;
+                let path = entry.path();
+                if !entry.file_type().is_file() || !path.to_string_lossy().ends_with(REF_SUFFIX) {
+                    continue;
+                }
+                let live = std::fs::read_to_string(path)
+                    .ok()
+                    .and_then(|content| serde_json::from_str::<CacheRef>(&content).ok())
+                    .and_then(|entry| {
+                        Checksum::parse(&entry.checksum, ChecksumAlgorithm::Sha256).ok()
+                    })
+                    .is_some_and(|checksum| self.archive_path(&checksum).is_file());
+                if !live {
+                    std::fs::remove_file(path)?;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    // 缓存中的包文件：路径、大小和最近使用时间
+    fn archives(&self) -> Result<Vec<(PathBuf, u64, SystemTime)>> {
+        let dir = self.root.join(ARCHIVES_DIR);
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut archives = Vec::new();
+        for entry in walkdir::WalkDir::new(dir) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            archives.push((
+                entry.into_path(),
+                metadata.len(),
+                metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            ));
+        }
+        Ok(archives)
+    }
+
+    // 每个注册表一个目录，名称由地址和 bucket 中的字母数字组成
+    fn registry_dir(&self, registry: &str) -> PathBuf {
+        let name: String = registry
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        self.root.join(REFS_DIR).join(name)
+    }
+
+    fn ref_path(&self, registry: &str, key: &str) -> PathBuf {
+        self.registry_dir(registry)
+            .join(format!("{}{}", key, REF_SUFFIX))
+    }
+}
@@ -0,0 +1,203 @@
+use crate::Result;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io::Read;
+
+/// 校验和算法，按优先级从高到低排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    Sha256,
+    Blake3,
+    Sha1,
+}
+
+impl ChecksumAlgorithm {
+    /// 拉取时按此顺序查找校验文件
+    pub const ALL: [ChecksumAlgorithm; 3] = [
+        ChecksumAlgorithm::Sha256,
+        ChecksumAlgorithm::Blake3,
+        ChecksumAlgorithm::Sha1,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Sha256 => "sha256",
+            ChecksumAlgorithm::Blake3 => "blake3",
+            ChecksumAlgorithm::Sha1 => "sha1",
+        }
+    }
+
+    /// 算法强度，用于信任策略中的最低算法要求；sha256 与 blake3 视为同级
+    pub fn strength(&self) -> u8 {
+        match self {
+            ChecksumAlgorithm::Sha1 => 0,
+            ChecksumAlgorithm::Sha256 | ChecksumAlgorithm::Blake3 => 1,
+        }
+    }
+
+    /// 包文件对应的校验文件名，例如 demo-1.0.0.zip.sha256
+    pub fn sidecar_name(&self, archive_name: &str) -> String {
+        format!("{}.{}", archive_name, self.name())
+    }
+
+    /// 十六进制摘要的长度
+    pub fn hex_len(&self) -> usize {
+        match self {
+            ChecksumAlgorithm::Sha256 | ChecksumAlgorithm::Blake3 => 64,
+            ChecksumAlgorithm::Sha1 => 40,
+        }
+    }
+
+    /// 计算数据的十六进制摘要
+    pub fn digest(&self, data: &[u8]) -> String {
+        let mut hasher = ChecksumHasher::new(*self);
+        hasher.update(data);
+        hasher.finalize().hex
+    }
+}
+
+/// 增量计算校验和，用于在上传/下载的数据流上边传输边计算
+#[derive(Clone)]
+pub enum ChecksumHasher {
+    Sha256(Sha256),
+    Blake3(Box<blake3::Hasher>),
+    Sha1(Sha1),
+}
+
+impl ChecksumHasher {
+    pub fn new(algorithm: ChecksumAlgorithm) -> Self {
+        match algorithm {
+            ChecksumAlgorithm::Sha256 => ChecksumHasher::Sha256(Sha256::new()),
+            ChecksumAlgorithm::Blake3 => ChecksumHasher::Blake3(Box::new(blake3::Hasher::new())),
+            ChecksumAlgorithm::Sha1 => ChecksumHasher::Sha1(Sha1::new()),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            ChecksumHasher::Sha256(hasher) => hasher.update(data),
+            ChecksumHasher::Blake3(hasher) => {
+                hasher.update(data);
+            }
+            ChecksumHasher::Sha1(hasher) => hasher.update(data),
+        }
+    }
+
+    pub fn finalize(self) -> Checksum {
+        let (algorithm, hex) = match self {
+            ChecksumHasher::Sha256(hasher) => (
+                ChecksumAlgorithm::Sha256,
+                format!("{:x}", hasher.finalize()),
+            ),
+            ChecksumHasher::Blake3(hasher) => (
+                ChecksumAlgorithm::Blake3,
+                hasher.finalize().to_hex().to_string(),
+            ),
+            ChecksumHasher::Sha1(hasher) => {
+                (ChecksumAlgorithm::Sha1, format!("{:x}", hasher.finalize()))
+            }
+        };
+
+        Checksum { algorithm, hex }
+    }
+}
+
+impl std::str::FromStr for ChecksumAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        ChecksumAlgorithm::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.name() == s.to_ascii_lowercase())
+            .ok_or_else(|| format!("Unsupported checksum algorithm: {}", s))
+    }
+}
+
+/// 每个版本汇总全部对象 sha256 的清单的扩展名，对象键为 `<包文件>.SHA256SUMS`
+pub const SUMS_EXTENSION: &str = "SHA256SUMS";
+
+/// 包文件对应的 SHA256SUMS 对象键
+pub fn sums_name(archive_name: &str) -> String {
+    format!("{}.{}", archive_name, SUMS_EXTENSION)
+}
+
+/// 生成与 sha256sum 输出相同格式的清单，每行为 `<hex>  <文件名>`，可以直接用 `sha256sum -c` 校验
+pub fn format_sums(entries: &[(String, Checksum)]) -> String {
+    entries
+        .iter()
+        .map(|(name, checksum)| format!("{}  {}\n", checksum.hex, name))
+        .collect()
+}
+
+/// 带算法标签的校验和，序列化格式为 `algo:hex`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checksum {
+    pub algorithm: ChecksumAlgorithm,
+    pub hex: String,
+}
+
+impl Checksum {
+    pub fn compute(algorithm: ChecksumAlgorithm, data: &[u8]) -> Self {
+        Self {
+            algorithm,
+            hex: algorithm.digest(data),
+        }
+    }
+
+    /// 解析校验文件内容；旧版 .sha1 文件只包含十六进制摘要，没有算法前缀
+    pub fn parse(content: &str, fallback: ChecksumAlgorithm) -> Result<Self> {
+        let content = content.trim();
+        let (algorithm, hex) = match content.split_once(':') {
+            Some((algorithm, hex)) => (algorithm.parse::<ChecksumAlgorithm>()?, hex),
+            None => (fallback, content),
+        };
+
+        if hex.is_empty() || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("Malformed checksum: {}", content).into());
+        }
+
+        Ok(Self {
+            algorithm,
+            hex: hex.to_ascii_lowercase(),
+        })
+    }
+
+    /// 解析固定内容的摘要（例如 `name@sha256:<hex>` 中的版本部分），必须带算法前缀且长度完整
+    pub fn parse_digest(value: &str) -> Option<Self> {
+        let (algorithm, hex) = value.split_once(':')?;
+        let algorithm = algorithm.parse::<ChecksumAlgorithm>().ok()?;
+        if hex.len() != algorithm.hex_len() || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        Some(Self {
+            algorithm,
+            hex: hex.to_ascii_lowercase(),
+        })
+    }
+
+    /// 以流方式计算数据的校验和，不把内容全部读入内存
+    pub fn compute_reader(algorithm: ChecksumAlgorithm, mut reader: impl Read) -> Result<Self> {
+        let mut hasher = ChecksumHasher::new(algorithm);
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+        }
+        Ok(hasher.finalize())
+    }
+
+    /// 校验数据是否与该校验和一致
+    pub fn verify(&self, data: &[u8]) -> bool {
+        self.algorithm.digest(data) == self.hex
+    }
+}
+
+impl fmt::Display for Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm.name(), self.hex)
+    }
+}
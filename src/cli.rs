@@ -1,3 +1,11 @@
+use crate::checksum::ChecksumAlgorithm;
+use crate::completion::Shell;
+use crate::listing::{Column, Filter, ListFormat, SortKey};
+use crate::models::{AuditAction, MetadataFormat, StorageLayout, Visibility};
+use crate::operations::UploadVerification;
+use crate::sbom::SbomFormat;
+use crate::security::SecretSource;
+use crate::stats::Period;
 use clap::{Parser, Subcommand};
 
 #[derive(Parser)]
@@ -19,14 +27,113 @@ pub enum Commands {
         /// MinIO bucket name
         #[arg(short, long)]
         bucket: String,
+
+        /// Also show each package's description and license (one request per package)
+        #[arg(long)]
+        details: bool,
+
+        /// Only list packages matching <field><op><value>, where op is ~ (glob), = or != and field
+        /// is name, version, author, license, description, keyword or label:<key> (e.g. 'name~ml-*',
+        /// 'label:qa=passed'); repeatable
+        #[arg(long = "filter", value_name = "FILTER")]
+        filters: Vec<Filter>,
+
+        /// Only list packages whose author contains this text
+        #[arg(long)]
+        author: Option<String>,
+
+        /// Sort by name, size or date
+        #[arg(long, default_value = "name")]
+        sort: SortKey,
+
+        /// Reverse the sort order
+        #[arg(long)]
+        reverse: bool,
+
+        /// Skip this many packages after sorting
+        #[arg(long, default_value_t = 0)]
+        offset: usize,
+
+        /// List at most this many packages
+        #[arg(long)]
+        limit: Option<usize>,
+
+        /// Print a table with these columns, comma separated: name, version, size, date, author,
+        /// license, description, artifacts, locked, channels
+        #[arg(long, value_delimiter = ',')]
+        columns: Vec<Column>,
+
+        /// Print an aligned table with human-readable sizes, relative upload times, lock markers
+        /// and channels (with the description when --details is given)
+        #[arg(short, long, conflicts_with = "columns")]
+        long: bool,
+
+        /// Output format: text or json
+        #[arg(long, default_value = "text")]
+        format: ListFormat,
+    },
+
+    /// Show a package's metadata: author, description, license, links, maintainers and dependencies
+    Info {
+        /// Package name, optionally with a version, range or channel (name@version)
+        package: String,
+
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Print a package's README without pulling the package
+    Readme {
+        /// Package name, optionally with a version, range or channel (name@version)
+        package: String,
+    },
+
+    /// Show the release notes of a package's versions, newest first
+    Changelog {
+        name: String,
+
+        /// Oldest version to include (e.g. 1.0 or 1.0.0)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Newest version to include
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Search the latest version of each package by name, description and keywords
+    Search {
+        query: String,
+
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
     },
 
     /// Push a package to registry
     Push {
-        /// Path to package directory (default: current directory)
+        /// Path to package directory, or the workspace root with --workspace (default: current directory)
         #[arg(short, long, default_value = ".")]
         package: String,
 
+        /// Push every member listed in workspace.toml, in dependency order
+        #[arg(long)]
+        workspace: bool,
+
+        /// Push a prebuilt package archive (.zip with pack.toml at its root) as is, instead of
+        /// archiving a package directory
+        #[arg(long, value_name = "FILE", conflicts_with = "workspace")]
+        archive: Option<String>,
+
+        /// With --workspace, remove the members already pushed if any member fails
+        #[arg(long, requires = "workspace", conflicts_with = "force")]
+        atomic: bool,
+
         /// MinIO access key
         #[arg(short, long)]
         key: Option<String>,
@@ -35,22 +142,258 @@ pub enum Commands {
         #[arg(short, long)]
         secret: Option<String>,
 
+        /// Session token for temporary credentials (defaults to S3_SESSION_TOKEN env var)
+        #[arg(long)]
+        session_token: Option<String>,
+
+        /// Registry from the config file to push to (default: S3_ENDPOINT)
+        #[arg(long)]
+        registry: Option<String>,
+
         /// Force push (overwrite existing package or ignore version warnings)
         #[arg(short, long)]
         force: bool,
+
+        /// Generate and upload a SLSA provenance attestation alongside the package
+        #[arg(long)]
+        provenance: bool,
+
+        /// Generate and upload an SBOM alongside the package (cyclonedx or spdx)
+        #[arg(long, value_name = "FORMAT")]
+        sbom: Option<SbomFormat>,
+
+        /// Point this release channel (e.g. beta) at the pushed version
+        #[arg(long)]
+        channel: Option<String>,
+
+        /// Run the package's pre_push hook from pack.toml
+        #[arg(long)]
+        allow_hooks: bool,
+
+        /// Also upload a binary delta from the previous version, so upgrades only download the changes
+        #[arg(long)]
+        delta: bool,
+
+        /// Release notes for this version (overrides [release] notes in pack.toml)
+        #[arg(long, value_name = "FILE", conflicts_with = "workspace")]
+        notes_file: Option<String>,
+
+        /// Upload under drafts/, invisible to list, pull and resolution until `beepkg release`
+        #[arg(long, conflicts_with_all = ["workspace", "force"])]
+        draft: bool,
+
+        /// Check uploaded archives afterwards: head compares size and ETag, full downloads them
+        /// again and compares checksums, none skips the check
+        #[arg(long, default_value = "head")]
+        verify_upload: UploadVerification,
     },
 
     /// Pull a package from registry
     Pull {
-        /// Package name and version (e.g. demo-pkg@2.1.0)
+        /// Package name with a version, range, release channel or content digest (e.g.
+        /// demo-pkg@2.1.0, demo-pkg@^2.0, demo-pkg@stable, demo-pkg@sha256:<hex>); the latest
+        /// version is pulled when omitted
         package: String,
 
+        /// Include prereleases (-alpha, -rc, ...) when resolving the latest version or a range
+        #[arg(long)]
+        pre: bool,
+
+        /// Platform artifact to install (e.g. linux-x86_64); defaults to the host platform
+        #[arg(long)]
+        target: Option<String>,
+
+        /// Only download this named artifact (e.g. docs) into the output directory
+        #[arg(long, conflicts_with = "target")]
+        artifact: Option<String>,
+
         /// Output directory
         #[arg(short, long)]
         output: Option<String>,
+
+        /// Fail unless the package carries a valid signature from a trusted key
+        #[arg(long)]
+        require_signature: bool,
+
+        /// Run the post_pull hooks declared by pulled packages
+        #[arg(long)]
+        allow_hooks: bool,
+
+        /// Trust policy file (defaults to BEEPKG_TRUST_POLICY or ./beepkg-trust.toml)
+        #[arg(long)]
+        policy: Option<String>,
+
+        /// Only pull from this registry (default: try configured registries in order)
+        #[arg(long)]
+        registry: Option<String>,
+
+        /// Do not access the network; only use packages from the local cache
+        #[arg(long)]
+        offline: bool,
+    },
+
+    /// Download a package archive without extracting it, verifying its checksum
+    Fetch {
+        /// Package name with a version, range, release channel or content digest; the latest
+        /// version when omitted
+        package: String,
+
+        /// File to write the archive to, or - for stdout
+        #[arg(short, long)]
+        output: String,
+
+        /// Include prereleases when resolving the latest version or a range
+        #[arg(long)]
+        pre: bool,
+
+        /// Fail unless the package carries a valid signature from a trusted key
+        #[arg(long)]
+        require_signature: bool,
+
+        /// Do not access the network; only use packages from the local cache
+        #[arg(long, conflicts_with = "require_signature")]
+        offline: bool,
     },
 
-    /// Test connection to MinIO server and bucket
+    /// Compare a package directory with the published archive of the same name@version
+    Status {
+        /// Path to the package directory
+        #[arg(short, long, default_value = ".")]
+        package: String,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Pull a package together with its dependencies, enabling optional features
+    Install {
+        /// Package name with a version, range or release channel; the latest version when omitted
+        package: String,
+
+        /// Features to enable, comma separated (e.g. gpu,cli)
+        #[arg(long, value_delimiter = ',')]
+        features: Vec<String>,
+
+        /// Do not enable the package's default feature
+        #[arg(long)]
+        no_default_features: bool,
+
+        /// Include prereleases when resolving versions and dependency ranges
+        #[arg(long)]
+        pre: bool,
+
+        /// Output directory; dependencies are installed under <output>/deps
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Fail unless every package carries a valid signature from a trusted key
+        #[arg(long)]
+        require_signature: bool,
+
+        /// Run the post_pull hooks declared by pulled packages
+        #[arg(long)]
+        allow_hooks: bool,
+
+        /// Install from this registry (default: the first configured registry)
+        #[arg(long)]
+        registry: Option<String>,
+
+        /// Do not access the network; only use packages from the local cache
+        #[arg(long)]
+        offline: bool,
+    },
+
+    /// Pull the dependency closure of a package into a vendor directory for offline builds
+    Vendor {
+        /// Path to the package directory whose dependencies are vendored
+        #[arg(short, long, default_value = ".")]
+        package: String,
+
+        /// Vendor directory; a vendor.json manifest is written next to the packages
+        #[arg(short, long, default_value = "vendor")]
+        output: String,
+
+        /// Features to enable, comma separated
+        #[arg(long, value_delimiter = ',')]
+        features: Vec<String>,
+
+        /// Do not enable the package's default feature
+        #[arg(long)]
+        no_default_features: bool,
+
+        /// Include prereleases when resolving dependency ranges
+        #[arg(long)]
+        pre: bool,
+
+        /// Re-pull exactly the digests recorded in vendor.json instead of resolving versions;
+        /// fails if any package's content was replaced
+        #[arg(long, conflicts_with_all = ["features", "no_default_features", "pre"])]
+        locked: bool,
+
+        /// Fail unless every package carries a valid signature from a trusted key
+        #[arg(long)]
+        require_signature: bool,
+
+        /// Pull from this registry (default: the first configured registry)
+        #[arg(long)]
+        registry: Option<String>,
+
+        /// Do not access the network; only use packages from the local cache
+        #[arg(long)]
+        offline: bool,
+    },
+
+    /// Install and upgrade the packages listed in project.toml, remove ones no longer listed, and
+    /// record the resolved versions in project-lock.json
+    Sync {
+        /// Project root containing project.toml
+        #[arg(short, long, default_value = ".")]
+        project: String,
+
+        /// Apply the [profile.<name>] overrides from project.toml; uses project-lock.<name>.json
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Install exactly the digests in project-lock.json without resolving versions; fails if
+        /// the lock file is missing or out of date with project.toml (for CI)
+        #[arg(long, conflicts_with = "pre")]
+        frozen: bool,
+
+        /// Include prereleases when resolving version ranges
+        #[arg(long)]
+        pre: bool,
+
+        /// Fail unless every package carries a valid signature from a trusted key
+        #[arg(long)]
+        require_signature: bool,
+
+        /// Pull from this registry (default: the first configured registry)
+        #[arg(long)]
+        registry: Option<String>,
+
+        /// Do not access the network; only use packages from the local cache
+        #[arg(long)]
+        offline: bool,
+    },
+
+    /// Generate an ed25519 key pair for signing packages, or an age encryption identity
+    Keygen {
+        /// Output path prefix, writes <output>.key and <output>.pub
+        #[arg(short, long, default_value = "beepkg")]
+        output: String,
+
+        /// Overwrite existing key files
+        #[arg(short, long)]
+        force: bool,
+
+        /// Generate an age encryption identity instead of a signing key
+        #[arg(long)]
+        age: bool,
+    },
+
+    /// Diagnose the connection to the MinIO server and bucket stage by stage: DNS, TCP, TLS,
+    /// credentials, write permission, clock skew and latency
     Test {
         /// MinIO endpoint URL (optional, defaults to S3_ENDPOINT env var)
         #[arg(short, long)]
@@ -67,6 +410,75 @@ pub enum Commands {
         /// MinIO secret key (optional)
         #[arg(short, long)]
         secret: Option<String>,
+
+        /// Session token for temporary credentials (optional)
+        #[arg(long)]
+        session_token: Option<String>,
+
+        /// Skip the write probe (upload and delete of a temporary object)
+        #[arg(long)]
+        read_only: bool,
+
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check the local setup: config file, environment variables, credential sources, the
+    /// encryption passphrase, the cache directory and the registry's schema version
+    Doctor {
+        /// Package directory whose encryption settings are checked
+        #[arg(short, long, default_value = ".")]
+        package: String,
+
+        /// Check this registry from the config file instead of S3_ENDPOINT
+        #[arg(long)]
+        registry: Option<String>,
+
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Find objects left behind by failed pushes and other junk, optionally deleting them
+    Gc {
+        /// Delete the objects found (default: only report them)
+        #[arg(long)]
+        delete: bool,
+
+        /// Only consider objects older than this (e.g. 1h, 7d), so pushes in progress are left alone
+        #[arg(long, default_value = "24h")]
+        min_age: String,
+
+        /// Also remove old versions according to the registry retention rules
+        #[arg(long)]
+        apply_retention: bool,
+    },
+
+    /// Check the registry metadata, checksums and blob pointers against the stored objects
+    Fsck {
+        /// Remove locks, channels and backups that refer to missing objects
+        #[arg(long)]
+        repair: bool,
+    },
+
+    /// Manage retention rules for old versions, applied by gc --apply-retention
+    Retention {
+        #[command(subcommand)]
+        action: RetentionCommands,
+    },
+
+    /// Capture the whole registry in a snapshot file or rebuild a bucket from one
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotCommands,
+    },
+
+    /// Generate sha256 checksum files for packages that only have legacy sha1 checksums
+    Rehash {
+        /// Only report which packages would be migrated
+        #[arg(long)]
+        dry_run: bool,
     },
 
     /// Lock a package to prevent modifications
@@ -81,6 +493,268 @@ pub enum Commands {
         /// Username of the person locking the package
         #[arg(short, long)]
         user: String,
+
+        /// Lock expiry time in RFC 3339 format (optional, never expires if not specified)
+        #[arg(long)]
+        expires: Option<String>,
+    },
+
+    /// List currently locked packages
+    Locks {
+        /// Only show locks for this package name
+        #[arg(short, long)]
+        package: Option<String>,
+    },
+
+    /// Check dependencies against the registry advisory database, or show the audit log
+    #[command(args_conflicts_with_subcommands = true)]
+    Audit {
+        #[command(subcommand)]
+        action: Option<AuditCommands>,
+
+        /// Path to the package directory whose dependencies are checked
+        #[arg(long, default_value = ".")]
+        path: String,
+
+        /// Registry object holding the advisory index (default: BEEPKG_ADVISORY_FEED or advisories/index.json)
+        #[arg(long)]
+        feed: Option<String>,
+
+        /// Include prereleases when resolving dependency ranges
+        #[arg(long)]
+        pre: bool,
+
+        /// Also check optional dependencies enabled by these features, comma separated
+        #[arg(long, value_delimiter = ',')]
+        features: Vec<String>,
+    },
+
+    /// Report how often packages are downloaded (requires `registry set-download-stats true`)
+    Stats {
+        #[command(subcommand)]
+        action: StatsCommands,
+    },
+
+    /// Upgrade metadata documents to the current schema version: the registry metadata and
+    /// package metadata summaries, or a local pack.toml/pack.json with --path
+    MigrateMetadata {
+        /// Package directory whose pack.toml or pack.json is rewritten (comments are not kept)
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Only report which documents would be migrated
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Show storage usage per namespace and manage size limits and quotas
+    Quota {
+        #[command(subcommand)]
+        action: QuotaCommands,
+    },
+
+    /// Show or change registry-wide settings
+    Registry {
+        #[command(subcommand)]
+        action: RegistryCommands,
+    },
+
+    /// Generate an SBOM for a local package, or show the SBOM of a published package
+    Sbom {
+        /// Published package (e.g. demo-pkg@2.1.0); omit to generate from a local package
+        package: Option<String>,
+
+        /// SBOM format (cyclonedx or spdx)
+        #[arg(short, long)]
+        format: Option<SbomFormat>,
+
+        /// Path to the local package directory
+        #[arg(long, default_value = ".")]
+        path: String,
+
+        /// Include an inventory of package files with sha256 hashes
+        #[arg(long)]
+        files: bool,
+
+        /// Write the SBOM to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Upload the generated SBOM next to the published package archive
+        #[arg(long)]
+        upload: bool,
+    },
+
+    /// Inspect provenance attestations of packages
+    Attestation {
+        #[command(subcommand)]
+        action: AttestationCommands,
+    },
+
+    /// Manage webhook notifications for registry events
+    Webhook {
+        #[command(subcommand)]
+        action: WebhookCommands,
+    },
+
+    /// Manage Slack and email notifiers for registry events
+    Notifier {
+        #[command(subcommand)]
+        action: NotifierCommands,
+    },
+
+    /// Show or change who can see a package
+    Access {
+        #[command(subcommand)]
+        action: AccessCommands,
+    },
+
+    /// Replicate packages between configured registries
+    Mirror {
+        #[command(subcommand)]
+        action: MirrorCommands,
+    },
+
+    /// Copy every package from one bucket into another using the current storage layout
+    Migrate {
+        /// Bucket holding the existing registry
+        #[arg(long)]
+        from_bucket: String,
+
+        /// Bucket receiving the migrated registry
+        #[arg(long)]
+        to_bucket: String,
+
+        /// Only report what would be migrated
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Write the migration report (JSON) to this file
+        #[arg(long)]
+        report: Option<String>,
+    },
+
+    /// Export packages and their metadata to a bundle for offline transfer
+    Export {
+        /// Packages to export (e.g. demo-pkg@2.1.0); exports everything when omitted
+        packages: Vec<String>,
+
+        /// Bundle file to write
+        #[arg(short, long, default_value = "registry.tar.zst")]
+        output: String,
+
+        /// Only export packages whose name matches this glob (e.g. "@acme/*")
+        #[arg(long)]
+        filter: Option<String>,
+    },
+
+    /// Import a bundle created by `beepkg export` into the registry
+    Import {
+        /// Bundle file (e.g. registry.tar.zst)
+        bundle: String,
+    },
+
+    /// Pack a project's resolved dependencies into a bundle, or install one without registry access
+    Bundle {
+        #[command(subcommand)]
+        action: BundleCommands,
+    },
+
+    /// Convert a cargo (.crate), npm (.tgz) or pip (.whl) artifact into a package and push it
+    ImportForeign {
+        /// Path to the artifact
+        path: String,
+
+        /// Force push, ignoring version conflicts
+        #[arg(short, long)]
+        force: bool,
+
+        /// Only print the generated pack.toml
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Convert package metadata between pack.json and pack.toml
+    Convert {
+        /// Target format (toml or json)
+        #[arg(long, value_name = "FORMAT")]
+        to: MetadataFormat,
+
+        /// Path to package directory (default: current directory)
+        #[arg(short, long, default_value = ".")]
+        package: String,
+
+        /// Keep the original metadata file
+        #[arg(long)]
+        keep: bool,
+    },
+
+    /// List the named and platform artifacts attached to a package version
+    Artifacts {
+        /// Package name with a version, range or release channel; the latest version when omitted
+        package: String,
+    },
+
+    /// Point a release channel at a published version, or list a package's channels
+    Tag {
+        /// Package name and version (e.g. demo-pkg@1.2.0), or just the name to list channels
+        package: String,
+
+        /// Channel name (e.g. stable, beta, nightly)
+        channel: Option<String>,
+    },
+
+    /// Point a channel (default: latest) back at the previous good version; after rolling back
+    /// latest, pulls and installs without a version use it until a higher version is pushed
+    Rollback {
+        /// Package name
+        name: String,
+
+        /// Channel to roll back
+        #[arg(long, default_value = "latest")]
+        channel: String,
+
+        /// Version to roll back to (default: the highest version below the current one)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Restore the version's archive from the last backup taken before it was force-pushed
+        #[arg(long)]
+        restore: bool,
+    },
+
+    /// Publish a version pushed with --draft, or list drafts; the archive is moved last, so the
+    /// version only becomes visible once everything is in place
+    Release {
+        /// Package name and version (e.g. demo-pkg@1.2.0); omit to list drafts
+        package: Option<String>,
+    },
+
+    /// Approve a push or channel change waiting on a protected channel, or list pending ones;
+    /// the approval is signed with BEEPKG_SIGNING_KEY, which must be trusted and differ from the requester's key
+    Approve {
+        /// Package name and version (e.g. demo-pkg@1.2.0); omit to list pending approvals
+        package: Option<String>,
+    },
+
+    /// Set or remove labels on a published version (e.g. git_sha=1a2b3c qa=passed), or show them;
+    /// list filters on them with --filter label:<key>=<value>
+    Annotate {
+        /// Package name and version (e.g. demo-pkg@1.2.0)
+        package: String,
+
+        /// Labels to set, as key=value
+        labels: Vec<String>,
+
+        /// Remove the label with this key; repeatable
+        #[arg(long = "remove", value_name = "KEY")]
+        remove: Vec<String>,
+    },
+
+    /// Manage default settings for scoped packages (@scope/name)
+    Scope {
+        #[command(subcommand)]
+        action: ScopeCommands,
     },
 
     /// Unlock a previously locked package
@@ -90,13 +764,24 @@ pub enum Commands {
     },
 
     /// Backup a package version
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
     Backup {
+        #[command(subcommand)]
+        action: Option<BackupCommands>,
+
         /// Package name and version (e.g. demo-pkg@2.1.0)
-        package: String,
+        #[arg(required = true)]
+        package: Option<String>,
 
         /// Reason for creating the backup
-        #[arg(short, long)]
-        reason: String,
+        #[arg(short, long, required = true)]
+        reason: Option<String>,
+    },
+
+    /// List backups recorded in the registry, with timestamps, sizes and reasons
+    Backups {
+        /// Only show backups of this package (name or name@version)
+        package: Option<String>,
     },
 
     /// Restore a package from backup
@@ -122,5 +807,702 @@ pub enum Commands {
         /// Encryption algorithm (default: aes-256-gcm)
         #[arg(short, long, default_value = "aes-256-gcm")]
         algorithm: String,
+
+        /// Encrypt to an age recipient public key instead of BEEPKG_USER_SECRET (repeatable)
+        #[arg(short, long = "recipient")]
+        recipients: Vec<String>,
+        /// Wrap a per-package data key with a KMS key: aws:<key-id> or vault:<transit-key>
+        #[arg(long, conflicts_with = "recipients")]
+        kms: Option<String>,
+
+        /// Where push reads the passphrase from: env, keyring or prompt
+        #[arg(long)]
+        secret_source: Option<SecretSource>,
+
+        /// Only encrypt files matching this glob, e.g. "secrets/**" (repeatable)
+        #[arg(long = "file")]
+        files: Vec<String>,
+
+        /// Argon2id memory cost in KiB for passphrase encryption
+        #[arg(long)]
+        kdf_memory: Option<u32>,
+
+        /// Argon2id iterations for passphrase encryption
+        #[arg(long)]
+        kdf_iterations: Option<u32>,
+
+        /// Argon2id parallelism for passphrase encryption
+        #[arg(long)]
+        kdf_parallelism: Option<u32>,
+    },
+
+    /// Manage the encryption passphrase stored in the OS keyring
+    Secret {
+        #[command(subcommand)]
+        action: SecretCommands,
+    },
+
+    /// Store a bearer token for a registry, sent instead of S3 credentials
+    Login {
+        /// Token to store (prompted for when omitted)
+        #[arg(long)]
+        token: Option<String>,
+
+        /// Registry endpoint (default: S3_ENDPOINT)
+        #[arg(long)]
+        endpoint: Option<String>,
+    },
+
+    /// Remove the stored bearer token for a registry
+    Logout {
+        /// Registry endpoint (default: S3_ENDPOINT)
+        #[arg(long)]
+        endpoint: Option<String>,
+    },
+
+    /// Print presigned download URLs for a public package archive and its checksum
+    Share {
+        /// Package to share (name@version)
+        package: String,
+
+        /// How long the URLs stay valid (e.g. 30m, 24h, 7d; at most 7 days)
+        #[arg(long, default_value = "24h")]
+        expires: String,
+    },
+
+    /// Generate a static index of the registry
+    Index {
+        #[command(subcommand)]
+        action: IndexCommands,
+    },
+
+    /// Inspect or clean the local package cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommands,
+    },
+
+    /// Print a shell completion script, e.g. `source <(beepkg completions bash)`
+    Completions {
+        /// bash, zsh or fish
+        shell: Shell,
+    },
+
+    /// Completion candidates for the completion scripts, answered from the local cache
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        #[command(subcommand)]
+        action: CompleteCommands,
+    },
+
+    /// Serve packages over HTTP, fetching from the upstream registry on a miss and caching them
+    /// locally; clients use the proxy address as their endpoint
+    Proxy {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        listen: String,
+
+        /// Upstream registry name from the config file (defaults to the first one, or S3_ENDPOINT)
+        #[arg(long)]
+        registry: Option<String>,
+
+        /// Serve a web dashboard under /-/ for browsing packages, locks, backups and the audit log
+        /// with the proxy's credentials
+        #[arg(long)]
+        dashboard: bool,
+
+        /// Require clients to send `authorization: Bearer <token>` (defaults to BEEPKG_PROXY_TOKEN)
+        #[arg(long)]
+        token: Option<String>,
+    },
+
+    /// Serve the registry operations (List, Info, Push, Pull, Lock, Unlock) over gRPC;
+    /// the interface is defined in proto/beepkg.proto
+    Grpc {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:50051")]
+        listen: String,
+
+        /// Registry name from the config file (defaults to the first one, or S3_ENDPOINT)
+        #[arg(long)]
+        registry: Option<String>,
+
+        /// Require clients to send `authorization: Bearer <token>` (defaults to BEEPKG_GRPC_TOKEN)
+        #[arg(long)]
+        token: Option<String>,
+    },
+
+    /// Run a long-lived daemon that serves push, pull and cache requests as line-delimited JSON
+    /// over a Unix socket, keeping credentials and HTTP connections warm between requests
+    Daemon {
+        /// Socket path (defaults to BEEPKG_DAEMON_SOCKET, $XDG_RUNTIME_DIR/beepkg.sock or a
+        /// per-user socket in the temp directory)
+        #[arg(long, global = true)]
+        socket: Option<String>,
+
+        /// Registry name from the config file (defaults to the first one, or S3_ENDPOINT)
+        #[arg(long)]
+        registry: Option<String>,
+
+        #[command(subcommand)]
+        action: Option<DaemonCommands>,
+    },
+
+    /// Push and pull packages stored as OCI artifacts in a container registry (Harbor, ECR, GHCR...);
+    /// a package maps to repository <namespace>/<name> and its version to a tag
+    Oci {
+        /// Registry URL with an optional namespace, e.g. https://harbor.example.com/team
+        /// (defaults to BEEPKG_OCI_REGISTRY; credentials from BEEPKG_OCI_USERNAME/BEEPKG_OCI_PASSWORD)
+        #[arg(long, global = true)]
+        registry: Option<String>,
+
+        #[command(subcommand)]
+        action: OciCommands,
+    },
+
+    /// List plugins (beepkg-<name> executables) found on PATH
+    Plugins,
+
+    /// Any other command runs the beepkg-<command> plugin from PATH
+    #[command(external_subcommand)]
+    External(Vec<String>),
+}
+
+#[derive(Subcommand)]
+pub enum BackupCommands {
+    /// Add a backup schedule, run by `backup run-due`
+    Schedule {
+        /// Five-field cron expression evaluated in UTC (e.g. "0 3 * * *")
+        #[arg(long)]
+        cron: String,
+
+        /// Only back up packages whose name matches this glob (e.g. 'critical-*')
+        #[arg(long, default_value = "*")]
+        filter: String,
+    },
+
+    /// List backup schedules and their last run
+    Schedules,
+
+    /// Remove a backup schedule by its number in `backup schedules`
+    Unschedule { index: usize },
+
+    /// Back up the latest version of matching packages for every schedule that is due (for cron or a Kubernetes CronJob)
+    RunDue,
+
+    /// Delete old backups, keeping the newest per package version and/or the recent ones
+    Prune {
+        /// Keep the newest N backups of each package version
+        #[arg(long, required_unless_present = "keep_days")]
+        keep_last: Option<usize>,
+
+        /// Keep every backup younger than this many days
+        #[arg(long)]
+        keep_days: Option<u64>,
+
+        /// Only report which backups would be deleted
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RetentionCommands {
+    /// List retention rules
+    List,
+
+    /// Set the retention rule for a package name or glob (e.g. demo, @acme/*, *)
+    Set {
+        pattern: String,
+
+        /// Keep the latest N versions
+        #[arg(long)]
+        keep_last: usize,
+
+        /// Do not keep versions just because a release channel points at them
+        #[arg(long)]
+        no_keep_channels: bool,
+
+        /// Do not keep locked versions
+        #[arg(long)]
+        no_keep_locked: bool,
+    },
+
+    /// Remove the retention rule for a pattern
+    Remove { pattern: String },
+}
+
+#[derive(Subcommand)]
+pub enum SnapshotCommands {
+    /// Write every registry object (metadata, audit log, sidecars) to a snapshot file
+    Create {
+        /// Snapshot file to write
+        #[arg(short, long, default_value = "snapshot.tar.zst")]
+        output: String,
+
+        /// Also include package archives, backups, artifacts, deltas and blobs
+        #[arg(long)]
+        blobs: bool,
+    },
+
+    /// Rebuild the registry from a snapshot, e.g. into a fresh bucket for a DR drill
+    Restore {
+        /// Snapshot file (e.g. snapshot.tar.zst)
+        snapshot: String,
+
+        /// Restore even if the bucket already contains objects, overwriting them
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BundleCommands {
+    /// Resolve every package of project.toml (including transitive dependencies) into a bundle
+    Create {
+        /// Project manifest to resolve
+        #[arg(short, long, default_value = "project.toml")]
+        manifest: String,
+
+        /// Apply the [profile.<name>] overrides from project.toml
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Bundle file to write
+        #[arg(short, long, default_value = "bundle.tar.zst")]
+        output: String,
+    },
+
+    /// Install a bundle created by `bundle create` into the project, e.g. on an air-gapped network
+    Install {
+        /// Bundle file (e.g. bundle.tar.zst)
+        bundle: String,
+
+        /// Project root containing project.toml
+        #[arg(short, long, default_value = ".")]
+        project: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum IndexCommands {
+    /// Write a browsable HTML/JSON index of all packages and versions, servable by any web server
+    Generate {
+        /// Output directory
+        #[arg(short, long, default_value = "site")]
+        output: String,
+
+        /// Link presigned URLs valid for this long (e.g. 24h, 7d) instead of plain object URLs
+        #[arg(long)]
+        presign: Option<String>,
+
+        /// Download every version to include author, description and dependencies from pack.toml
+        #[arg(long)]
+        metadata: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum OciCommands {
+    /// Push a package directory as an OCI artifact
+    Push {
+        /// Package directory containing pack.toml
+        #[arg(default_value = ".")]
+        path: String,
+
+        /// Overwrite the tag if the version already exists
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Pull a package (name@version) and extract it
+    Pull {
+        package: String,
+
+        /// Directory to extract into (defaults to <name>-<version>)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// List the versions of a package, newest first
+    List { name: String },
+}
+
+#[derive(Subcommand)]
+pub enum DaemonCommands {
+    /// Send one request to a running daemon and print the JSON result
+    Call {
+        /// Method name: ping, list, push, pull, fetch, exists, stat, cache.info or cache.clean
+        method: String,
+
+        /// Parameters as a JSON object, e.g. '{"package": "demo@1.0.0", "output": "/tmp/demo"}'
+        params: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommands {
+    /// Show the cache directory and its size
+    Info,
+
+    /// Remove cached packages, least recently used first
+    Clean {
+        /// Keep the cache under this size (e.g. 500MB, 5GB); removes everything when omitted
+        #[arg(long)]
+        max_size: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CompleteCommands {
+    /// Visible command names
+    Commands,
+
+    /// Package names starting with the prefix, or name@version for a prefix containing @
+    Packages {
+        #[arg(default_value = "", allow_hyphen_values = true)]
+        prefix: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SecretCommands {
+    /// Prompt for the passphrase and store it in the OS keyring
+    Store,
+
+    /// Remove the passphrase from the OS keyring
+    Delete,
+}
+
+#[derive(Subcommand)]
+pub enum AttestationCommands {
+    /// Show the provenance attestation of a package
+    Show {
+        /// Package name and version (e.g. demo-pkg@2.1.0)
+        package: String,
+
+        /// Print the raw in-toto statement as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum WebhookCommands {
+    /// List configured webhooks
+    List,
+
+    /// Add a webhook (replaces an existing one with the same URL)
+    Add {
+        /// Webhook URL receiving the JSON event payload
+        url: String,
+
+        /// Name of the environment variable holding the secret used to sign payloads
+        /// (HMAC-SHA256); the secret itself is not stored in the registry
+        #[arg(short, long)]
+        secret_env: Option<String>,
+
+        /// Events to subscribe to (repeatable, default: all events)
+        #[arg(short, long = "event")]
+        events: Vec<AuditAction>,
+    },
+
+    /// Remove a webhook
+    Remove {
+        /// Webhook URL to remove
+        url: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AccessCommands {
+    /// Show the visibility, owner and grants of a package
+    Show {
+        /// Package name
+        package: String,
+    },
+
+    /// Allow a user or team (team:<name>) to see a package
+    Grant {
+        /// Package name
+        package: String,
+
+        /// User name or team:<name>
+        principal: String,
+    },
+
+    /// Remove a user or team grant from a package
+    Revoke {
+        /// Package name
+        package: String,
+
+        /// User name or team:<name>
+        principal: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MirrorCommands {
+    /// Copy missing or updated packages, with their access rules, channels and locks, to another registry
+    Sync {
+        /// Source registry name from the config file
+        #[arg(long)]
+        from: String,
+
+        /// Target registry name from the config file
+        #[arg(long)]
+        to: String,
+
+        /// Only mirror packages whose name matches this glob (e.g. 'billing*')
+        #[arg(long)]
+        filter: Option<String>,
+
+        /// Number of packages copied in parallel
+        #[arg(short, long, default_value_t = 4)]
+        jobs: usize,
+
+        /// Only list the packages that would be copied
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Keep replicating new publishes to downstream registries until stopped
+    Watch {
+        /// Source registry name from the config file
+        #[arg(long)]
+        from: String,
+
+        /// Downstream registry names (repeatable)
+        #[arg(long, required = true)]
+        to: Vec<String>,
+
+        /// Only mirror packages whose name matches this glob (e.g. 'billing*')
+        #[arg(long)]
+        filter: Option<String>,
+
+        /// Number of packages copied in parallel per registry
+        #[arg(short, long, default_value_t = 4)]
+        jobs: usize,
+
+        /// Polling interval (e.g. 30s, 5m)
+        #[arg(long, default_value = "60s")]
+        interval: String,
+
+        /// Write the replication status of each registry to this JSON file
+        #[arg(long)]
+        status_file: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ScopeCommands {
+    /// List scopes with default settings
+    List,
+
+    /// Change the default settings of a scope
+    Set {
+        /// Scope name (e.g. payments or @payments)
+        scope: String,
+
+        /// Default visibility of new packages (public, private or team:<name>)
+        #[arg(long)]
+        visibility: Option<Visibility>,
+
+        /// Require packages in this scope to be signed
+        #[arg(long)]
+        require_signature: Option<bool>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum NotifierCommands {
+    /// List configured notifiers
+    List,
+
+    /// Add a Slack incoming-webhook notifier
+    AddSlack {
+        /// Slack incoming webhook URL
+        webhook_url: String,
+
+        /// Message template (placeholders: {name} {version} {package} {action} {verb} {actor} {timestamp} {details})
+        #[arg(short, long)]
+        template: Option<String>,
+
+        /// Events to subscribe to (repeatable, default: all events)
+        #[arg(short, long = "event")]
+        events: Vec<AuditAction>,
+    },
+
+    /// Add an SMTP email notifier
+    AddEmail {
+        /// SMTP server host
+        #[arg(long)]
+        smtp_host: String,
+
+        /// SMTP server port
+        #[arg(long, default_value_t = 587)]
+        smtp_port: u16,
+
+        /// Connect without STARTTLS (plain text)
+        #[arg(long)]
+        no_starttls: bool,
+
+        /// SMTP username
+        #[arg(long)]
+        username: Option<String>,
+
+        /// Name of the environment variable holding the SMTP password
+        #[arg(long)]
+        password_env: Option<String>,
+
+        /// Sender address
+        #[arg(long)]
+        from: String,
+
+        /// Recipient address (repeatable)
+        #[arg(long, required = true)]
+        to: Vec<String>,
+
+        /// Subject template
+        #[arg(long)]
+        subject: Option<String>,
+
+        /// Body template
+        #[arg(short, long)]
+        template: Option<String>,
+
+        /// Events to subscribe to (repeatable, default: all events)
+        #[arg(short, long = "event")]
+        events: Vec<AuditAction>,
+    },
+
+    /// Remove a notifier by its number in the list output
+    Remove {
+        /// Notifier number (starting at 1)
+        index: usize,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AuditCommands {
+    /// Show the audit log of registry operations
+    Log {
+        /// Only show records at or after this time (e.g. 2024-01-01 or an RFC 3339 timestamp)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only show records for this package name
+        #[arg(short, long)]
+        package: Option<String>,
+    },
+
+    /// Upload an advisory index (JSON) to the registry
+    Import {
+        /// Advisory index file
+        file: String,
+
+        /// Registry object to write (default: BEEPKG_ADVISORY_FEED or advisories/index.json)
+        #[arg(long)]
+        feed: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum StatsCommands {
+    /// Download counts per package, version and period, most downloaded first
+    Downloads {
+        /// Only report this package
+        name: Option<String>,
+
+        /// Only count downloads at or after this time (e.g. 2024-01-01 or an RFC 3339 timestamp;
+        /// default: the last 30 days)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Group counts by day, week or month
+        #[arg(long, default_value = "day")]
+        by: Period,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum QuotaCommands {
+    /// Bytes used by each namespace (a scope such as @payments, or an unscoped package) and its
+    /// quota, largest first
+    Status {
+        /// Only report this namespace
+        namespace: Option<String>,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Change the limits checked before a push uploads each file; 0 or none removes a limit
+    Set {
+        /// Largest package archive or artifact, e.g. 500MB
+        #[arg(long, conflicts_with = "scope")]
+        max_artifact_size: Option<String>,
+
+        /// Total bytes per namespace, e.g. 10GB (the default for every namespace, or for the
+        /// scope given with --scope)
+        #[arg(long)]
+        max_namespace_bytes: Option<String>,
+
+        /// Set the quota of this scope (e.g. payments or @payments) instead of the default
+        #[arg(long, requires = "max_namespace_bytes")]
+        scope: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RegistryCommands {
+    /// Show registry settings
+    Show,
+
+    /// Set the checksum algorithm used for newly pushed packages
+    SetChecksum {
+        /// Checksum algorithm (sha256 or blake3)
+        algorithm: ChecksumAlgorithm,
+    },
+
+    /// Make published versions immutable: force-push, mirror and import can no longer overwrite
+    /// an existing version, so every change needs a new version number
+    SetImmutable {
+        /// true to reject overwrites, false to allow them again
+        #[arg(action = clap::ArgAction::Set)]
+        enabled: bool,
+    },
+
+    /// Record every download (pulls, the proxy and the gRPC service) under stats/downloads/;
+    /// pulling clients need write access for their downloads to be counted
+    SetDownloadStats {
+        /// true to record downloads, false to stop
+        #[arg(action = clap::ArgAction::Set)]
+        enabled: bool,
+    },
+
+    /// Protect a release channel: pushes and tags to it wait until another user approves them
+    SetProtected {
+        /// Channel name (e.g. stable)
+        channel: String,
+
+        /// true to require approval, false to remove the protection
+        #[arg(action = clap::ArgAction::Set)]
+        enabled: bool,
+    },
+
+    /// Switch the storage layout and convert existing packages to it
+    SetLayout {
+        /// Storage layout: flat (one object per version) or cas (content-addressed blobs, deduplicated)
+        layout: StorageLayout,
+
+        /// Only report which objects would be converted
+        #[arg(long)]
+        dry_run: bool,
     },
 }
@@ -0,0 +1,91 @@
+/// 参数为包名称的命令，补全时调用 `beepkg __complete packages <前缀>`
+pub const PACKAGE_COMMANDS: &[&str] = &[
+    "pull",
+    "fetch",
+    "info",
+    "readme",
+    "changelog",
+    "lock",
+    "unlock",
+    "annotate",
+    "approve",
+    "release",
+    "rollback",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl std::str::FromStr for Shell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            other => Err(format!(
+                "Unknown shell: {} (expected bash, zsh or fish)",
+                other
+            )),
+        }
+    }
+}
+
+/// 补全脚本。命令名和包名称都通过隐藏的 `beepkg __complete` 命令获取，
+/// 包名称只读本地缓存，离线时也能补全
+pub fn script(shell: Shell) -> String {
+    match shell {
+        Shell::Bash => format!(
+            r#"_beepkg() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    if [ "$COMP_CWORD" -eq 1 ]; then
+        COMPREPLY=($(compgen -W "$(beepkg __complete commands 2>/dev/null)" -- "$cur"))
+        return
+    fi
+    case "${{COMP_WORDS[1]}}" in
+        {})
+            if [[ "$cur" != -* ]]; then
+                COMPREPLY=($(beepkg __complete packages "$cur" 2>/dev/null))
+            fi
+            ;;
+    esac
+}}
+complete -o default -F _beepkg beepkg
+"#,
+            PACKAGE_COMMANDS.join("|")
+        ),
+        Shell::Zsh => format!(
+            r#"_beepkg() {{
+    if (( CURRENT == 2 )); then
+        compadd -- ${{(f)"$(beepkg __complete commands 2>/dev/null)"}}
+        return
+    fi
+    case $words[2] in
+        {})
+            if [[ $PREFIX != -* ]]; then
+                compadd -- ${{(f)"$(beepkg __complete packages "$PREFIX" 2>/dev/null)"}}
+            fi
+            ;;
+        *)
+            _files
+            ;;
+    esac
+}}
+compdef _beepkg beepkg
+"#,
+            PACKAGE_COMMANDS.join("|")
+        ),
+        Shell::Fish => format!(
+            "complete -c beepkg -f -n __fish_use_subcommand \
+             -a '(beepkg __complete commands 2>/dev/null)'\n\
+             complete -c beepkg -n '__fish_seen_subcommand_from {}' \
+             -a '(beepkg __complete packages (commandline -ct) 2>/dev/null)'\n",
+            PACKAGE_COMMANDS.join(" ")
+        ),
+    }
+}
@@ -0,0 +1,331 @@
+use crate::Result;
+use crate::auth;
+use crate::operations::PackageManager;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// 用户配置文件名，位于 beepkg 配置目录
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// beepkg 的用户配置
+///
+/// ```toml
+/// credential_helper = "/usr/local/bin/vault-s3-helper"
+///
+/// [[registries]]
+/// name = "private"
+/// endpoint = "https://minio.internal.example.com"
+///
+/// [[registries]]
+/// name = "aws"
+/// endpoint = "https://s3.eu-central-1.amazonaws.com"
+/// bucket = "shared"
+/// url_style = "virtual-host"
+/// region = "eu-central-1"
+///
+/// [[registries]]
+/// name = "internal"
+/// endpoint = "https://minio.corp.example.com"
+///
+/// [registries.tls]
+/// ca_bundle = "/etc/beepkg/corp-ca.pem"
+/// system_roots = false
+/// client_cert = "/etc/beepkg/client.pem"
+/// client_key = "/etc/beepkg/client-key.pem"
+///
+/// [registries.proxy]
+/// url = "socks5h://proxy.corp.example.com:1080"
+/// no_proxy = "localhost,.corp.example.com"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// 获取 S3 凭证的外部程序，协议与 docker-credential-helper 相同
+    #[serde(default)]
+    pub credential_helper: Option<String>,
+    /// 拉取时按顺序依次尝试的注册表，推送时通过名称选择
+    #[serde(default)]
+    pub registries: Vec<RegistryConfig>,
+    /// S3_ENDPOINT 注册表的寻址方式，S3_URL_STYLE 优先
+    #[serde(default)]
+    pub url_style: Option<UrlStyle>,
+    /// S3_ENDPOINT 注册表的签名区域，S3_REGION 优先
+    #[serde(default)]
+    pub region: Option<String>,
+    /// 未单独配置 TLS 的注册表使用的 TLS 设置，BEEPKG_CA_BUNDLE 等环境变量优先
+    #[serde(default)]
+    pub tls: TlsConfig,
+    /// 未单独配置代理的注册表使用的代理，BEEPKG_PROXY 优先；
+    /// 都未设置时使用 HTTP_PROXY / HTTPS_PROXY / ALL_PROXY / NO_PROXY 环境变量
+    #[serde(default)]
+    pub proxy: Option<ProxyConfig>,
+}
+
+/// 显式的代理设置，覆盖 HTTP_PROXY 等环境变量
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProxyConfig {
+    /// 代理地址，支持 http://、https://、socks5:// 和 socks5h://（由代理解析域名）
+    pub url: String,
+    /// 不经过代理的主机，格式与 NO_PROXY 相同，未设置时使用 NO_PROXY 环境变量
+    #[serde(default)]
+    pub no_proxy: Option<String>,
+}
+
+impl ProxyConfig {
+    /// 从 BEEPKG_PROXY 和 BEEPKG_NO_PROXY 读取代理设置
+    pub fn from_env() -> Option<Self> {
+        let url = std::env::var("BEEPKG_PROXY")
+            .ok()
+            .filter(|url| !url.trim().is_empty())?;
+        Some(Self {
+            url,
+            no_proxy: std::env::var("BEEPKG_NO_PROXY").ok(),
+        })
+    }
+
+    /// 让 HTTP 客户端的所有请求经过该代理
+    pub fn apply(&self, builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder> {
+        let no_proxy = match &self.no_proxy {
+            Some(hosts) => reqwest::NoProxy::from_string(hosts),
+            None => reqwest::NoProxy::from_env(),
+        };
+        let proxy = reqwest::Proxy::all(&self.url)
+            .map_err(|e| format!("Invalid proxy {}: {}", self.url, e))?
+            .no_proxy(no_proxy);
+        Ok(builder.proxy(proxy))
+    }
+}
+
+/// HTTP 客户端的 TLS 设置：私有 CA 和 mTLS 客户端证书
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// 额外信任的 CA 证书（PEM，可以包含多个证书）
+    #[serde(default)]
+    pub ca_bundle: Option<PathBuf>,
+    /// 是否信任系统根证书，只信任内部 CA 时关闭
+    #[serde(default = "default_true")]
+    pub system_roots: bool,
+    /// mTLS 客户端证书（PEM）
+    #[serde(default)]
+    pub client_cert: Option<PathBuf>,
+    /// 客户端证书的私钥（PKCS#8 PEM）
+    #[serde(default)]
+    pub client_key: Option<PathBuf>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            ca_bundle: None,
+            system_roots: true,
+            client_cert: None,
+            client_key: None,
+        }
+    }
+}
+
+impl TlsConfig {
+    /// 用 BEEPKG_CA_BUNDLE、BEEPKG_TLS_SYSTEM_ROOTS、BEEPKG_CLIENT_CERT、BEEPKG_CLIENT_KEY 覆盖配置
+    pub fn with_env(mut self) -> Result<Self> {
+        if let Ok(path) = std::env::var("BEEPKG_CA_BUNDLE") {
+            self.ca_bundle = Some(PathBuf::from(path));
+        }
+        if let Ok(value) = std::env::var("BEEPKG_TLS_SYSTEM_ROOTS") {
+            self.system_roots = match value.to_ascii_lowercase().as_str() {
+                "1" | "true" | "yes" => true,
+                "0" | "false" | "no" => false,
+                other => {
+                    return Err(format!("Invalid BEEPKG_TLS_SYSTEM_ROOTS: {}", other).into());
+                }
+            };
+        }
+        if let Ok(path) = std::env::var("BEEPKG_CLIENT_CERT") {
+            self.client_cert = Some(PathBuf::from(path));
+        }
+        if let Ok(path) = std::env::var("BEEPKG_CLIENT_KEY") {
+            self.client_key = Some(PathBuf::from(path));
+        }
+        Ok(self)
+    }
+
+    /// 把 TLS 设置应用到 HTTP 客户端
+    pub fn apply(&self, mut builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder> {
+        if !self.system_roots && self.ca_bundle.is_none() {
+            return Err("Disabling system roots requires a CA bundle".into());
+        }
+        if let Some(path) = &self.ca_bundle {
+            let pem = std::fs::read(path)
+                .map_err(|e| format!("Failed to read CA bundle {}: {}", path.display(), e))?;
+            let certificates = reqwest::Certificate::from_pem_bundle(&pem)
+                .map_err(|e| format!("Invalid CA bundle {}: {}", path.display(), e))?;
+            if certificates.is_empty() {
+                return Err(
+                    format!("CA bundle {} contains no certificates", path.display()).into(),
+                );
+            }
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        builder = builder.tls_built_in_root_certs(self.system_roots);
+
+        match (&self.client_cert, &self.client_key) {
+            (Some(cert), Some(key)) => {
+                let cert_pem = std::fs::read(cert).map_err(|e| {
+                    format!(
+                        "Failed to read client certificate {}: {}",
+                        cert.display(),
+                        e
+                    )
+                })?;
+                let key_pem = std::fs::read(key)
+                    .map_err(|e| format!("Failed to read client key {}: {}", key.display(), e))?;
+                let identity = reqwest::Identity::from_pkcs8_pem(&cert_pem, &key_pem)
+                    .map_err(|e| format!("Invalid client certificate or key: {}", e))?;
+                builder = builder.identity(identity);
+            }
+            (None, None) => {}
+            _ => return Err("client_cert and client_key must be set together".into()),
+        }
+        Ok(builder)
+    }
+}
+
+/// bucket 的寻址方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UrlStyle {
+    /// https://endpoint/bucket/key，MinIO 等 S3 兼容服务的默认方式
+    #[default]
+    Path,
+    /// https://bucket.endpoint/key，AWS S3 新建的 bucket 只支持这种方式
+    VirtualHost,
+}
+
+impl std::str::FromStr for UrlStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "path" => Ok(UrlStyle::Path),
+            "virtual-host" | "virtual" => Ok(UrlStyle::VirtualHost),
+            other => Err(format!(
+                "Unknown URL style: {} (expected path or virtual-host)",
+                other
+            )),
+        }
+    }
+}
+
+/// 配置文件中的一个注册表
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RegistryConfig {
+    pub name: String,
+    pub endpoint: String,
+    #[serde(default = "default_bucket")]
+    pub bucket: String,
+    /// 未设置时使用 S3_ACCESS_KEY / S3_SECRET_KEY，也可以由凭证助手或 `beepkg login` 提供
+    #[serde(default)]
+    pub access_key: Option<String>,
+    #[serde(default)]
+    pub secret_key: Option<String>,
+    /// 未设置时使用顶层配置，默认为 path
+    #[serde(default)]
+    pub url_style: Option<UrlStyle>,
+    /// 未设置时使用顶层配置，默认为 AWS_REGION 或 us-east-1
+    #[serde(default)]
+    pub region: Option<String>,
+    /// 未设置时使用顶层的 TLS 设置
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    /// 未设置时使用顶层的代理设置
+    #[serde(default)]
+    pub proxy: Option<ProxyConfig>,
+}
+
+fn default_bucket() -> String {
+    "packages".to_string()
+}
+
+impl RegistryConfig {
+    /// 为该注册表创建 PackageManager
+    pub fn manager(&self) -> Result<PackageManager> {
+        let access_key = match &self.access_key {
+            Some(key) => key.clone(),
+            None => std::env::var("S3_ACCESS_KEY").unwrap_or_default(),
+        };
+        let secret_key = match &self.secret_key {
+            Some(key) => key.clone(),
+            None => std::env::var("S3_SECRET_KEY").unwrap_or_default(),
+        };
+        let manager = PackageManager::builder()
+            .endpoint(&self.endpoint)
+            .bucket(&self.bucket)
+            .credentials(access_key, secret_key)
+            .from_env()?
+            .url_style(self.url_style)
+            .region(self.region.clone())
+            .build()?;
+        let manager = match &self.tls {
+            Some(tls) => manager.tls(tls)?,
+            None => manager,
+        };
+        match &self.proxy {
+            Some(proxy) => Ok(manager.proxy(Some(proxy.clone()))?),
+            None => Ok(manager),
+        }
+    }
+}
+
+impl Config {
+    /// 读取配置文件，文件不存在时返回默认配置
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Ok(toml::from_str(&content)
+                .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 读取配置目录中的 config.toml，并应用 BEEPKG_CREDENTIAL_HELPER 等环境变量
+    pub fn from_env() -> Result<Self> {
+        // 找不到配置目录时使用默认配置
+        let mut config = match auth::config_dir() {
+            Ok(dir) => Self::load(&dir.join(CONFIG_FILE))?,
+            Err(_) => Self::default(),
+        };
+        if let Ok(helper) = std::env::var("BEEPKG_CREDENTIAL_HELPER") {
+            config.credential_helper = Some(helper).filter(|helper| !helper.trim().is_empty());
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// 检查注册表名称不重复
+    pub fn validate(&self) -> Result<()> {
+        for (i, registry) in self.registries.iter().enumerate() {
+            if self.registries[..i].iter().any(|r| r.name == registry.name) {
+                return Err(
+                    format!("Registry {} is configured more than once", registry.name).into(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// 按名称查找注册表
+    pub fn registry(&self, name: &str) -> Result<&RegistryConfig> {
+        self.registries
+            .iter()
+            .find(|registry| registry.name == name)
+            .ok_or_else(|| format!("Registry {} is not configured in {}", name, CONFIG_FILE).into())
+    }
+}
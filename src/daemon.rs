@@ -0,0 +1,323 @@
+use crate::cache::Cache;
+use crate::error::BeepkgError;
+use crate::listing::ListQuery;
+use crate::operations::PackageManager;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{UnixListener, UnixStream};
+
+/// 指定守护进程套接字路径的环境变量
+pub const SOCKET_ENV: &str = "BEEPKG_DAEMON_SOCKET";
+
+/// 默认的套接字路径：BEEPKG_DAEMON_SOCKET，其次 $XDG_RUNTIME_DIR/beepkg.sock，
+/// 最后是临时目录下按用户区分的 beepkg-<用户>.sock
+pub fn socket_path() -> PathBuf {
+    if let Ok(path) = std::env::var(SOCKET_ENV) {
+        return PathBuf::from(path);
+    }
+    if let Ok(dir) = std::env::var("XDG_RUNTIME_DIR") {
+        return Path::new(&dir).join("beepkg.sock");
+    }
+    let user = std::env::var("USER").unwrap_or_else(|_| "default".to_string());
+    std::env::temp_dir().join(format!("beepkg-{}.sock", user))
+}
+
+/// 一行一个 JSON 请求，id 原样返回
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonRequest {
+    #[serde(default)]
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+/// 一行一个 JSON 响应，result 和 error 只有一个
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonResponse {
+    pub id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<DaemonError>,
+}
+
+/// 请求失败的原因，code 与命令行的退出码相同
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonError {
+    pub code: i32,
+    pub message: String,
+}
+
+/// 守护进程的设置
+#[derive(Default)]
+pub struct Options {
+    /// cache.info 和 cache.clean 查询的本地缓存
+    pub cache: Option<Cache>,
+}
+
+/// 绑定套接字，只允许当前用户连接。套接字文件已存在时，能连接上说明已有守护进程在运行，
+/// 否则视为上次异常退出留下的文件并删除。
+///
+/// 套接字先在只有当前用户能访问的（0700）临时目录中创建并设置权限，再移动到 path，
+/// 因此其他用户不会在设置权限之前连接上
+pub async fn bind(path: &Path) -> Result<UnixListener, BeepkgError> {
+    if path.exists() {
+        if UnixStream::connect(path).await.is_ok() {
+            return Err(BeepkgError::Conflict(format!(
+                "a daemon is already listening on {}",
+                path.display()
+            )));
+        }
+        std::fs::remove_file(path)?;
+    }
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(parent)?;
+
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    let staging = parent.join(format!(".beepkg-{}", std::process::id()));
+    std::fs::DirBuilder::new().mode(0o700).create(&staging)?;
+    let staged = staging.join("sock");
+    let result = UnixListener::bind(&staged).and_then(|listener| {
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))?;
+        std::fs::rename(&staged, path)?;
+        Ok(listener)
+    });
+    let _ = std::fs::remove_dir_all(&staging);
+    Ok(result?)
+}
+
+/// 在 listener 上处理请求。所有请求共用 manager，凭证、会话和 HTTP 连接池在请求之间保持；
+/// 每个连接上的请求按顺序处理，不同连接并发处理
+pub async fn serve(
+    manager: Arc<PackageManager>,
+    listener: UnixListener,
+    options: Options,
+) -> Result<(), BeepkgError> {
+    let options = Arc::new(options);
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let (manager, options) = (manager.clone(), options.clone());
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(&manager, &options, stream).await {
+                log::warn!("daemon connection failed: {}", e);
+            }
+        });
+    }
+}
+
+async fn handle_connection(
+    manager: &PackageManager,
+    options: &Options,
+    stream: UnixStream,
+) -> Result<(), BeepkgError> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<DaemonRequest>(&line) {
+            Ok(request) => {
+                let result = dispatch(manager, options, &request.method, request.params).await;
+                response(request.id, result)
+            }
+            Err(e) => response(
+                Value::Null,
+                Err(BeepkgError::Config(format!("Invalid request: {}", e))),
+            ),
+        };
+        let mut content = serde_json::to_vec(&response)?;
+        content.push(b'\n');
+        writer.write_all(&content).await?;
+    }
+    Ok(())
+}
+
+fn response(id: Value, result: Result<Value, BeepkgError>) -> DaemonResponse {
+    match result {
+        Ok(result) => DaemonResponse {
+            id,
+            result: Some(result),
+            error: None,
+        },
+        Err(e) => DaemonResponse {
+            id,
+            result: None,
+            error: Some(DaemonError {
+                code: e.exit_code(),
+                message: e.to_string(),
+            }),
+        },
+    }
+}
+
+#[derive(Deserialize)]
+struct PushParams {
+    path: PathBuf,
+    #[serde(default)]
+    force: bool,
+    /// path 是已经打包好的包文件
+    #[serde(default)]
+    archive: bool,
+}
+
+#[derive(Deserialize)]
+struct PullParams {
+    package: String,
+    output: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct VersionParams {
+    name: String,
+    version: String,
+}
+
+#[derive(Deserialize)]
+struct CleanParams {
+    #[serde(default)]
+    max_size: u64,
+}
+
+fn params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, BeepkgError> {
+    serde_json::from_value(params)
+        .map_err(|e| BeepkgError::Config(format!("Invalid params: {}", e)))
+}
+
+async fn dispatch(
+    manager: &PackageManager,
+    options: &Options,
+    method: &str,
+    raw: Value,
+) -> Result<Value, BeepkgError> {
+    match method {
+        "ping" => Ok(json!({"version": env!("CARGO_PKG_VERSION")})),
+        "list" => {
+            // 参数与 list 命令的过滤、排序和分页选项相同
+            let query: ListQuery = if raw.is_null() {
+                ListQuery::default()
+            } else {
+                params(raw)?
+            };
+            Ok(serde_json::to_value(manager.query_packages(&query).await?)?)
+        }
+        "push" => {
+            let request: PushParams = params(raw)?;
+            let (name, version) = if request.archive {
+                let metadata = manager.push_archive(&request.path, request.force).await?;
+                (metadata.name, metadata.version)
+            } else {
+                let metadata = crate::models::PackageMetadata::load(&request.path)?;
+                if request.force {
+                    manager.force_push_package(&request.path).await?;
+                } else {
+                    manager.push_package(&request.path).await?;
+                }
+                (metadata.name, metadata.version)
+            };
+            Ok(json!({"name": name, "version": version}))
+        }
+        "pull" => {
+            let request: PullParams = params(raw)?;
+            manager
+                .pull_package(&request.package, &request.output)
+                .await?;
+            Ok(json!({"output": request.output}))
+        }
+        "fetch" => {
+            let request: PullParams = params(raw)?;
+            let checksum = manager
+                .fetch_package(&request.package, &request.output)
+                .await?;
+            Ok(json!({"output": request.output, "checksum": checksum.to_string()}))
+        }
+        "exists" => {
+            let request: VersionParams = params(raw)?;
+            Ok(json!(
+                manager.exists(&request.name, &request.version).await?
+            ))
+        }
+        "stat" => {
+            let request: VersionParams = params(raw)?;
+            Ok(serde_json::to_value(
+                manager.stat(&request.name, &request.version).await?,
+            )?)
+        }
+        "cache.info" => {
+            let cache = options.cache.as_ref().ok_or("The daemon has no cache")?;
+            Ok(json!({"root": cache.root(), "size": cache.size()?}))
+        }
+        "cache.clean" => {
+            let request: CleanParams = params(raw)?;
+            let cache = options.cache.as_ref().ok_or("The daemon has no cache")?;
+            let report = cache.clean(request.max_size)?;
+            Ok(json!({
+                "removed": report.removed,
+                "freed": report.freed,
+                "remaining": report.remaining,
+            }))
+        }
+        other => Err(BeepkgError::Config(format!("Unknown method: {}", other))),
+    }
+}
+
+/// 守护进程的客户端，一个连接上可以依次发送多个请求
+pub struct Client {
+    lines: tokio::io::Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+    next_id: u64,
+}
+
+impl Client {
+    pub async fn connect(path: &Path) -> Result<Self, BeepkgError> {
+        let stream = UnixStream::connect(path).await.map_err(|e| {
+            BeepkgError::Network(format!(
+                "cannot connect to the daemon at {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let (reader, writer) = stream.into_split();
+        Ok(Self {
+            lines: BufReader::new(reader).lines(),
+            writer,
+            next_id: 1,
+        })
+    }
+
+    /// 发送请求并等待结果；守护进程返回的错误转换为 BeepkgError::Remote，保留退出码
+    pub async fn call(&mut self, method: &str, params: Value) -> Result<Value, BeepkgError> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = DaemonRequest {
+            id: id.into(),
+            method: method.to_string(),
+            params,
+        };
+        let mut content = serde_json::to_vec(&request)?;
+        content.push(b'\n');
+        self.writer.write_all(&content).await?;
+
+        let line =
+            self.lines.next_line().await?.ok_or_else(|| {
+                BeepkgError::Network("the daemon closed the connection".to_string())
+            })?;
+        let response: DaemonResponse = serde_json::from_str(&line)?;
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(BeepkgError::Remote {
+                code: error.code,
+                message: error.message,
+            }),
+            (Some(result), None) => Ok(result),
+            (None, None) => Ok(Value::Null),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use crate::error::BeepkgError;
+use crate::operations::PackageManager;
+use serde::Serialize;
+
+/// 控制台页面，页面中的脚本通过同一服务下的 JSON 接口读取数据
+const PAGE: &str = include_str!("dashboard.html");
+
+/// 控制台的路径前缀。bucket 名称不能以 - 开头，不会与对象请求冲突
+pub const PREFIX: &str = "/-/";
+
+/// 未指定起始时间时审计日志显示的天数
+const DEFAULT_AUDIT_DAYS: i64 = 7;
+
+/// 控制台的响应内容
+pub struct Page {
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+/// 路径是否属于控制台
+pub fn is_dashboard_path(path: &str) -> bool {
+    path.starts_with(PREFIX)
+}
+
+/// 处理控制台请求：/-/ 返回页面，/-/api/ 下是只读的 JSON 接口
+///
+/// - `api/packages?q=<关键字>`：当前凭证可见的包版本，按包名中的关键字过滤
+/// - `api/locks?package=<包名>`：仍然有效的锁
+/// - `api/backups?package=<包名>`：注册表记录的备份
+/// - `api/audit?package=<包名>&since=<RFC 3339 时间>`：审计日志，默认最近 7 天
+pub async fn handle(
+    manager: &PackageManager,
+    path: &str,
+    query: &[(String, String)],
+) -> Result<Page, BeepkgError> {
+    let param = |name: &str| {
+        query
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+            .filter(|value| !value.is_empty())
+    };
+
+    match path.strip_prefix(PREFIX).unwrap_or_default() {
+        "" => Ok(Page {
+            content_type: "text/html; charset=utf-8",
+            body: PAGE.as_bytes().to_vec(),
+        }),
+        "api/packages" => {
+            let search = param("q").map(str::to_lowercase);
+            let packages: Vec<_> = manager
+                .list_packages()
+                .await?
+                .into_iter()
+                .filter(|package| {
+                    search
+                        .as_deref()
+                        .is_none_or(|search| package.name.to_lowercase().contains(search))
+                })
+                .collect();
+            json(&packages)
+        }
+        "api/locks" => json(&manager.list_locks(param("package")).await?),
+        "api/backups" => json(&manager.list_backups(param("package"), None).await?),
+        "api/audit" => {
+            let since = match param("since") {
+                Some(since) => chrono::DateTime::parse_from_rfc3339(since)
+                    .map_err(|e| format!("Invalid since: {}", e))?
+                    .with_timezone(&chrono::Utc),
+                None => chrono::Utc::now() - chrono::Duration::days(DEFAULT_AUDIT_DAYS),
+            };
+            let mut records = manager
+                .list_audit_records(Some(since), param("package"))
+                .await?;
+            // 最新的记录在前
+            records.reverse();
+            json(&records)
+        }
+        other => Err(BeepkgError::NotFound(format!("{}{}", PREFIX, other))),
+    }
+}
+
+fn json<T: Serialize>(value: &T) -> Result<Page, BeepkgError> {
+    Ok(Page {
+        content_type: "application/json",
+        body: serde_json::to_vec(value)?,
+    })
+}
@@ -0,0 +1,89 @@
+use crate::Result;
+#[cfg(feature = "archives")]
+use std::fs::File;
+#[cfg(feature = "archives")]
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// 差量的对象键位于新版本包文件名之后，例如 demo-1.1.0.zip.deltas/1.0.0.zst 是从 1.0.0 升级到 1.1.0 的差量。
+/// 以包文件名加 . 开头，删除、镜像时与其他附属文件一起处理
+pub const DELTAS_INFIX: &str = ".deltas/";
+
+/// 差量使用的压缩级别，推送大包时兼顾速度
+#[cfg(feature = "archives")]
+const LEVEL: i32 = 9;
+
+/// 解码时允许的最大窗口（2 GiB），与 zstd --long=31 相同
+#[cfg(feature = "archives")]
+const MAX_WINDOW_LOG: u32 = 31;
+
+#[cfg(feature = "archives")]
+const MIN_WINDOW_LOG: u32 = 10;
+
+/// 从 base_version 升级到该包文件的差量对象键
+pub fn delta_key(archive_name: &str, base_version: &str) -> String {
+    format!("{}{}{}.zst", archive_name, DELTAS_INFIX, base_version)
+}
+
+/// 对象键是否是差量
+pub fn is_delta_key(key: &str) -> bool {
+    key.contains(DELTAS_INFIX)
+}
+
+/// 差量不超过完整包的一半时才值得上传
+pub fn worthwhile(delta_size: u64, full_size: u64) -> bool {
+    delta_size.saturating_mul(2) <= full_size
+}
+
+/// 以旧版本的包文件为字典压缩新版本，效果与 zstd --patch-from 相同。
+/// 未变化的内容在差量中只是对旧版本的引用，因此差量大小取决于两个版本之间的变化
+#[cfg(feature = "archives")]
+pub fn create(base: &Path, target: &Path, output: &Path) -> Result<()> {
+    let dictionary = std::fs::read(base)?;
+    let target_size = std::fs::metadata(target)?.len();
+    let mut encoder = zstd::stream::write::Encoder::with_dictionary(
+        BufWriter::new(File::create(output)?),
+        LEVEL,
+        &dictionary,
+    )?;
+    // 窗口需要覆盖两个版本的全部内容，长距离匹配才能找到旧版本中相距很远的相同数据
+    encoder.long_distance_matching(true)?;
+    encoder.window_log(window_log(dictionary.len() as u64 + target_size))?;
+    encoder.include_checksum(true)?;
+    std::io::copy(&mut BufReader::new(File::open(target)?), &mut encoder)?;
+    encoder.finish()?.flush()?;
+    Ok(())
+}
+
+/// 用旧版本的包文件还原差量，结果写入 output。调用方仍需按校验文件验证还原的内容
+#[cfg(feature = "archives")]
+pub fn apply(base: &Path, delta: &Path, output: &Path) -> Result<()> {
+    let dictionary = std::fs::read(base)?;
+    let mut decoder = zstd::stream::read::Decoder::with_dictionary(
+        BufReader::new(File::open(delta)?),
+        &dictionary,
+    )?;
+    decoder.window_log_max(MAX_WINDOW_LOG)?;
+    let mut writer = BufWriter::new(File::create(output)?);
+    std::io::copy(&mut decoder, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+// 能容纳 size 字节的最小窗口
+#[cfg(feature = "archives")]
+fn window_log(size: u64) -> u32 {
+    let bits = 64 - size.saturating_sub(1).leading_zeros();
+    bits.clamp(MIN_WINDOW_LOG, MAX_WINDOW_LOG)
+}
+
+// 未启用 archives 特性时没有 zstd，推送跳过差量，拉取下载完整的包
+#[cfg(not(feature = "archives"))]
+pub fn create(_base: &Path, _target: &Path, _output: &Path) -> Result<()> {
+    Err("delta support requires the `archives` feature".into())
+}
+
+#[cfg(not(feature = "archives"))]
+pub fn apply(_base: &Path, _delta: &Path, _output: &Path) -> Result<()> {
+    Err("delta support requires the `archives` feature".into())
+}
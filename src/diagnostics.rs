@@ -0,0 +1,200 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// S3 拒绝与服务器时间相差超过 15 分钟的签名
+pub const MAX_CLOCK_SKEW_SECS: i64 = 15 * 60;
+
+/// 时钟偏差超过该值时给出警告
+pub const WARN_CLOCK_SKEW_SECS: i64 = 60;
+
+/// 往返延迟超过该值时给出警告
+pub const WARN_LATENCY_MS: u64 = 1000;
+
+/// 连接诊断的阶段，按顺序执行
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    /// 解析端点（或代理）的主机名
+    Dns,
+    /// 建立 TCP 连接
+    Tcp,
+    /// TLS 握手，端点为 http:// 时跳过
+    Tls,
+    /// 签名的 list 请求，检查凭证和 bucket
+    Auth,
+    /// 上传并删除一个探测对象
+    Write,
+    /// 比较本机时间与服务器响应的 Date
+    Clock,
+    /// 签名请求的往返延迟
+    Latency,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 7] = [
+        Stage::Dns,
+        Stage::Tcp,
+        Stage::Tls,
+        Stage::Auth,
+        Stage::Write,
+        Stage::Clock,
+        Stage::Latency,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Stage::Dns => "DNS",
+            Stage::Tcp => "TCP",
+            Stage::Tls => "TLS",
+            Stage::Auth => "Auth",
+            Stage::Write => "Write",
+            Stage::Clock => "Clock",
+            Stage::Latency => "Latency",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Ok,
+    Warning,
+    Failed,
+    Skipped,
+}
+
+/// 一个阶段的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct StageResult {
+    pub stage: Stage,
+    pub status: Status,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<u64>,
+}
+
+/// 连接诊断的结果，每个阶段一项
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnosis {
+    pub endpoint: String,
+    pub bucket: String,
+    pub stages: Vec<StageResult>,
+}
+
+impl Diagnosis {
+    pub fn new(endpoint: impl Into<String>, bucket: impl Into<String>) -> Self {
+        Diagnosis {
+            endpoint: endpoint.into(),
+            bucket: bucket.into(),
+            stages: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, stage: Stage, status: Status, detail: impl Into<String>) {
+        self.stages.push(StageResult {
+            stage,
+            status,
+            detail: detail.into(),
+            elapsed_ms: None,
+        });
+    }
+
+    pub fn push_timed(
+        &mut self,
+        stage: Stage,
+        status: Status,
+        detail: impl Into<String>,
+        elapsed: std::time::Duration,
+    ) {
+        self.push(stage, status, detail);
+        if let Some(last) = self.stages.last_mut() {
+            last.elapsed_ms = Some(elapsed.as_millis() as u64);
+        }
+    }
+
+    /// 为尚未执行的阶段补上跳过的结果
+    pub fn skip_remaining(&mut self, reason: &str) {
+        for stage in Stage::ALL {
+            if !self.stages.iter().any(|result| result.stage == stage) {
+                self.push(stage, Status::Skipped, reason);
+            }
+        }
+    }
+
+    pub fn get(&self, stage: Stage) -> Option<&StageResult> {
+        self.stages.iter().find(|result| result.stage == stage)
+    }
+
+    /// 第一个失败的阶段
+    pub fn first_failure(&self) -> Option<&StageResult> {
+        self.stages
+            .iter()
+            .find(|result| result.status == Status::Failed)
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.first_failure().is_none()
+    }
+}
+
+/// 本机时间减去服务器时间的秒数；date 为 HTTP Date 响应头（RFC 2822 格式）
+pub fn clock_skew(date: &str, local: DateTime<Utc>) -> Option<i64> {
+    let server = DateTime::parse_from_rfc2822(date).ok()?;
+    Some((local - server.with_timezone(&Utc)).num_seconds())
+}
+
+/// 根据时钟偏差判断状态
+pub fn check_clock(skew: i64) -> (Status, String) {
+    let direction = if skew >= 0 { "ahead of" } else { "behind" };
+    let detail = format!("local clock is {}s {} the server", skew.abs(), direction);
+    if skew.abs() >= MAX_CLOCK_SKEW_SECS {
+        (
+            Status::Failed,
+            format!("{}; signed requests are rejected beyond 15 minutes", detail),
+        )
+    } else if skew.abs() > WARN_CLOCK_SKEW_SECS {
+        (Status::Warning, detail)
+    } else {
+        (Status::Ok, detail)
+    }
+}
+
+/// S3 错误响应中的 Code，例如 InvalidAccessKeyId
+pub fn error_code(body: &str) -> Option<&str> {
+    let start = body.find("<Code>")? + "<Code>".len();
+    let end = start + body[start..].find("</Code>")?;
+    Some(body[start..end].trim())
+}
+
+/// 签名请求失败时说明可能的原因
+pub fn explain_auth_failure(status: u16, code: Option<&str>) -> String {
+    let hint = match code {
+        Some("InvalidAccessKeyId") => "the access key is not known to the server",
+        Some("SignatureDoesNotMatch") => "the secret key does not match the access key",
+        Some("RequestTimeTooSkewed") => "the local clock differs too much from the server",
+        Some("ExpiredToken") | Some("InvalidToken") => "the session token is expired or invalid",
+        Some("NoSuchBucket") => "the bucket does not exist",
+        Some("AccessDenied") => "the credentials are not allowed to list the bucket",
+        _ => match status {
+            401 | 403 => "the credentials were rejected",
+            404 => "the bucket does not exist",
+            _ => "the server returned an error",
+        },
+    };
+    match code {
+        Some(code) => format!("{} ({} {})", hint, status, code),
+        None => format!("{} ({})", hint, status),
+    }
+}
+
+/// 错误及其全部来源，reqwest 的错误信息本身通常不包含具体原因
+pub fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
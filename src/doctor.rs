@@ -0,0 +1,445 @@
+use crate::auth;
+use crate::aws::{AssumeRole, CredentialProvider};
+use crate::config::{Config, ProxyConfig, TlsConfig, UrlStyle};
+use crate::diagnostics::Status;
+use crate::models::{self, MetadataFormat, PackageMetadata};
+use crate::schema::Versioned;
+use crate::security::SecretSource;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// doctor 的一项检查
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    /// 检查的方面：config、environment、credentials、encryption、cache 或 registry
+    pub area: &'static str,
+    pub status: Status,
+    pub detail: String,
+    /// 如何修复，只在警告和失败时给出
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+impl Check {
+    pub fn ok(area: &'static str, detail: impl Into<String>) -> Self {
+        Check {
+            area,
+            status: Status::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    pub fn skipped(area: &'static str, detail: impl Into<String>) -> Self {
+        Check {
+            status: Status::Skipped,
+            ..Check::ok(area, detail)
+        }
+    }
+
+    pub fn warning(area: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Check {
+            status: Status::Warning,
+            fix: Some(fix.into()),
+            ..Check::ok(area, detail)
+        }
+    }
+
+    pub fn failed(area: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Check {
+            status: Status::Failed,
+            fix: Some(fix.into()),
+            ..Check::ok(area, detail)
+        }
+    }
+}
+
+/// 全部检查的结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Report {
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    pub fn push(&mut self, check: Check) {
+        self.checks.push(check);
+    }
+
+    pub fn extend(&mut self, checks: impl IntoIterator<Item = Check>) {
+        self.checks.extend(checks);
+    }
+
+    pub fn failures(&self) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.status == Status::Failed)
+            .count()
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.failures() == 0
+    }
+}
+
+/// 检查配置文件：语法、重复的注册表、TLS 文件和凭证助手是否存在
+pub fn check_config(path: &Path) -> Vec<Check> {
+    if !path.exists() {
+        return vec![Check::ok(
+            "config",
+            format!("{} not found, using defaults", path.display()),
+        )];
+    }
+    let config = match Config::load(path).and_then(|config| config.validate().map(|_| config)) {
+        Ok(config) => config,
+        Err(e) => {
+            return vec![Check::failed(
+                "config",
+                e.to_string(),
+                format!("fix or remove {}", path.display()),
+            )];
+        }
+    };
+
+    let mut checks = vec![Check::ok(
+        "config",
+        format!(
+            "{} is valid ({} registries)",
+            path.display(),
+            config.registries.len()
+        ),
+    )];
+    let tls = std::iter::once(("the default TLS settings".to_string(), &config.tls)).chain(
+        config.registries.iter().filter_map(|registry| {
+            let tls = registry.tls.as_ref()?;
+            Some((format!("registry {}", registry.name), tls))
+        }),
+    );
+    for (owner, tls) in tls {
+        let fix = format!("fix the TLS settings of {} in {}", owner, path.display());
+        for file in [&tls.ca_bundle, &tls.client_cert, &tls.client_key]
+            .into_iter()
+            .flatten()
+        {
+            if !file.exists() {
+                checks.push(Check::failed(
+                    "config",
+                    format!("{} of {} does not exist", file.display(), owner),
+                    fix.clone(),
+                ));
+            }
+        }
+        if tls.client_cert.is_some() != tls.client_key.is_some() {
+            checks.push(Check::failed(
+                "config",
+                format!("{} sets only one of client_cert and client_key", owner),
+                fix.clone(),
+            ));
+        }
+        if !tls.system_roots && tls.ca_bundle.is_none() {
+            checks.push(Check::failed(
+                "config",
+                format!("{} disables system roots without a CA bundle", owner),
+                fix,
+            ));
+        }
+    }
+    if let Some(helper) = &config.credential_helper
+        && find_program(helper).is_none()
+    {
+        checks.push(Check::failed(
+            "config",
+            format!("credential helper {} not found", helper),
+            "install the helper or fix credential_helper (or BEEPKG_CREDENTIAL_HELPER)",
+        ));
+    }
+    checks
+}
+
+/// 检查环境变量的取值是否有效，无效的值会使每个命令都失败
+pub fn check_environment() -> Vec<Check> {
+    let mut checks = Vec::new();
+    let mut fail = |variable: &str, error: String| {
+        checks.push(Check::failed(
+            "environment",
+            format!("{}: {}", variable, error),
+            format!("fix or unset {}", variable),
+        ));
+    };
+    if let Ok(style) = std::env::var("S3_URL_STYLE")
+        && let Err(e) = style.parse::<UrlStyle>()
+    {
+        fail("S3_URL_STYLE", e.to_string());
+    }
+    if let Err(e) = SecretSource::from_env() {
+        fail("BEEPKG_SECRET_SOURCE", e.to_string());
+    }
+    if let Err(e) = TlsConfig::default().with_env() {
+        fail("BEEPKG_TLS_SYSTEM_ROOTS", e.to_string());
+    }
+    if let Err(e) = AssumeRole::from_env() {
+        fail("BEEPKG_ROLE_DURATION", e.to_string());
+    }
+    if let Some(proxy) = ProxyConfig::from_env()
+        && let Err(e) = url::Url::parse(&proxy.url)
+    {
+        fail("BEEPKG_PROXY", e.to_string());
+    }
+    if let Ok(paths) = std::env::var("BEEPKG_TRUSTED_KEYS")
+        && let Err(e) = crate::signing::load_trusted_keys(&paths)
+    {
+        fail("BEEPKG_TRUSTED_KEYS", e.to_string());
+    }
+    if let Err(e) = models::ServerSideEncryption::from_env() {
+        fail("BEEPKG_SSE", e.to_string());
+    }
+    if checks.is_empty() {
+        let count = std::env::vars()
+            .filter(|(name, _)| name.starts_with("BEEPKG_") || name.starts_with("S3_"))
+            .count();
+        checks.push(Check::ok(
+            "environment",
+            format!("{} BEEPKG_/S3_ variables set, all valid", count),
+        ));
+    }
+    checks
+}
+
+/// 检查注册表使用的凭证来源，优先级与 PackageManager 相同：令牌 > AssumeRole > 密钥 >
+/// 凭证助手 > EKS IRSA > EC2 实例元数据
+pub fn check_credentials(
+    config: &Config,
+    endpoint: &str,
+    access_key: &str,
+    secret_key: &str,
+) -> Check {
+    const AREA: &str = "credentials";
+    const FIX: &str = "set S3_ACCESS_KEY and S3_SECRET_KEY, configure a credential_helper, \
+                       or run `beepkg login` for a token registry";
+    match auth::resolve_token(endpoint) {
+        Ok(Some(_)) => return Check::ok(AREA, "bearer token (BEEPKG_TOKEN or `beepkg login`)"),
+        Ok(None) => {}
+        Err(e) => {
+            return Check::failed(
+                AREA,
+                format!("cannot read saved tokens: {}", e),
+                "fix or remove the credentials file, then run `beepkg login` again",
+            );
+        }
+    }
+    if access_key.is_empty() != secret_key.is_empty() {
+        return Check::failed(
+            AREA,
+            "only one of the access key and the secret key is set",
+            "set both S3_ACCESS_KEY and S3_SECRET_KEY",
+        );
+    }
+    match AssumeRole::from_env() {
+        Ok(Some(role)) => {
+            let has_source = !access_key.is_empty() || std::env::var("AWS_ACCESS_KEY_ID").is_ok();
+            return if has_source {
+                Check::ok(AREA, format!("STS AssumeRole {}", role.role_arn))
+            } else {
+                Check::failed(
+                    AREA,
+                    format!(
+                        "BEEPKG_ASSUME_ROLE {} has no source credentials",
+                        role.role_arn
+                    ),
+                    "set S3_ACCESS_KEY/S3_SECRET_KEY or AWS_ACCESS_KEY_ID/AWS_SECRET_ACCESS_KEY",
+                )
+            };
+        }
+        Ok(None) => {}
+        Err(e) => return Check::failed(AREA, e.to_string(), "fix BEEPKG_ROLE_DURATION"),
+    }
+    if !access_key.is_empty() {
+        let shown: String = access_key.chars().take(4).collect();
+        return Check::ok(AREA, format!("static keys (access key {}...)", shown));
+    }
+    if let Some(helper) = &config.credential_helper {
+        return match find_program(helper) {
+            Some(path) => Check::ok(AREA, format!("credential helper {}", path.display())),
+            None => Check::failed(
+                AREA,
+                format!("credential helper {} not found", helper),
+                "install the helper or fix credential_helper (or BEEPKG_CREDENTIAL_HELPER)",
+            ),
+        };
+    }
+    match CredentialProvider::from_environment() {
+        Some(CredentialProvider::WebIdentity {
+            role_arn,
+            token_file,
+            ..
+        }) => {
+            if token_file.exists() {
+                Check::ok(AREA, format!("EKS web identity {}", role_arn))
+            } else {
+                Check::failed(
+                    AREA,
+                    format!("web identity token {} does not exist", token_file.display()),
+                    "fix AWS_WEB_IDENTITY_TOKEN_FILE or the service account mount",
+                )
+            }
+        }
+        Some(_) => Check::warning(
+            AREA,
+            "no credentials configured; EC2 instance metadata is tried, otherwise requests are anonymous",
+            FIX,
+        ),
+        None => Check::warning(
+            AREA,
+            "no credentials configured, requests are anonymous",
+            FIX,
+        ),
+    }
+}
+
+/// 包目录启用了口令加密时，检查推送能否取得口令（BEEPKG_USER_SECRET、密钥环或终端输入）
+pub fn check_encryption(package_dir: &Path) -> Check {
+    const AREA: &str = "encryption";
+    let has_metadata = [MetadataFormat::Toml, MetadataFormat::Json]
+        .iter()
+        .any(|format| package_dir.join(format.file_name()).exists());
+    if !has_metadata {
+        return Check::skipped(
+            AREA,
+            format!("no pack.toml or pack.json in {}", package_dir.display()),
+        );
+    }
+    let metadata = match PackageMetadata::load(package_dir) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            return Check::failed(
+                AREA,
+                format!("cannot read the package metadata: {}", e),
+                format!(
+                    "fix the pack.toml or pack.json in {}",
+                    package_dir.display()
+                ),
+            );
+        }
+    };
+    let Some(encryption) = metadata.encryption.filter(|encryption| encryption.enabled) else {
+        return Check::skipped(AREA, "encryption is not enabled");
+    };
+    if !encryption.recipients.is_empty() {
+        return Check::ok(
+            AREA,
+            format!(
+                "encrypts to {} age recipients, no passphrase needed",
+                encryption.recipients.len()
+            ),
+        );
+    }
+    if encryption.kms.is_some() {
+        return Check::ok(AREA, "KMS envelope encryption, no passphrase needed");
+    }
+    let source = match encryption
+        .secret_source
+        .map_or_else(SecretSource::from_env, Ok)
+    {
+        Ok(source) => source,
+        Err(e) => return Check::failed(AREA, e.to_string(), "fix BEEPKG_SECRET_SOURCE"),
+    };
+    match source {
+        SecretSource::Env => {
+            let present = std::env::var("BEEPKG_USER_SECRET").is_ok_and(|s| !s.is_empty());
+            if present {
+                Check::ok(AREA, "BEEPKG_USER_SECRET is set")
+            } else {
+                Check::failed(
+                    AREA,
+                    "encryption is enabled but BEEPKG_USER_SECRET is not set",
+                    "export BEEPKG_USER_SECRET, or set secret_source = \"keyring\" and run \
+                     `beepkg secret store`",
+                )
+            }
+        }
+        SecretSource::Keyring => match source.read() {
+            Ok(_) => Check::ok(AREA, "passphrase found in the system keyring"),
+            Err(e) => Check::failed(
+                AREA,
+                e.to_string(),
+                "run `beepkg secret store` to save the passphrase",
+            ),
+        },
+        SecretSource::Prompt => {
+            use std::io::IsTerminal;
+            if std::io::stdin().is_terminal() {
+                Check::ok(AREA, "the passphrase is prompted for at push time")
+            } else {
+                Check::warning(
+                    AREA,
+                    "the passphrase is prompted for, but standard input is not a terminal",
+                    "use secret_source = \"env\" or \"keyring\" in non-interactive runs",
+                )
+            }
+        }
+    }
+}
+
+/// 检查缓存目录存在（或可以创建）并且可写。缓存不可用时下载照常进行，只是不会缓存
+pub fn check_cache(root: &Path) -> Check {
+    const AREA: &str = "cache";
+    let fix = "set BEEPKG_CACHE_DIR to a writable directory";
+    if root.exists() && !root.is_dir() {
+        return Check::warning(AREA, format!("{} is not a directory", root.display()), fix);
+    }
+    if let Err(e) = std::fs::create_dir_all(root) {
+        return Check::warning(
+            AREA,
+            format!("cannot create {}: {}", root.display(), e),
+            fix,
+        );
+    }
+    match tempfile::NamedTempFile::new_in(root) {
+        Ok(_) => Check::ok(AREA, format!("{} is writable", root.display())),
+        Err(e) => Check::warning(
+            AREA,
+            format!("{} is not writable: {}", root.display(), e),
+            fix,
+        ),
+    }
+}
+
+/// 根据注册表元数据的格式版本（None 表示注册表还没有元数据）判断与当前版本是否兼容
+pub fn check_registry_schema(version: Option<u32>) -> Check {
+    const AREA: &str = "registry";
+    let supported = models::RegistryMetadata::VERSION;
+    match version {
+        None => Check::ok(AREA, "the registry has no metadata yet"),
+        Some(version) if version > supported => Check::failed(
+            AREA,
+            format!(
+                "registry metadata schema {} is newer than the supported version {}",
+                version, supported
+            ),
+            "upgrade beepkg",
+        ),
+        Some(version) if version < supported => Check::warning(
+            AREA,
+            format!(
+                "registry metadata schema {} is older than the current version {}",
+                version, supported
+            ),
+            "run `beepkg migrate-metadata`",
+        ),
+        Some(version) => Check::ok(AREA, format!("registry metadata schema {}", version)),
+    }
+}
+
+// 在 PATH 中查找程序；包含路径分隔符时直接检查该路径
+fn find_program(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    let extensions: &[&str] = if cfg!(windows) { &["", ".exe"] } else { &[""] };
+    std::env::split_paths(&std::env::var_os("PATH")?).find_map(|dir| {
+        extensions
+            .iter()
+            .map(|extension| dir.join(format!("{}{}", program, extension)))
+            .find(|candidate| candidate.is_file())
+    })
+}
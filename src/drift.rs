@@ -0,0 +1,77 @@
+use crate::Result;
+use crate::artifacts::ArtifactLayout;
+use crate::checksum::ChecksumAlgorithm;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// 本地包目录与已发布包文件的差异，路径使用 / 分隔
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DriftReport {
+    pub name: String,
+    pub version: String,
+    /// 两边都有但内容不同的文件
+    pub modified: Vec<String>,
+    /// 只在本地存在的文件
+    pub added: Vec<String>,
+    /// 只在已发布的包中存在的文件
+    pub removed: Vec<String>,
+}
+
+impl DriftReport {
+    pub fn is_clean(&self) -> bool {
+        self.modified.is_empty() && self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// 比较两份 路径 -> 摘要 的清单，结果按路径排序
+pub fn compare(
+    name: &str,
+    version: &str,
+    published: &BTreeMap<String, String>,
+    local: &BTreeMap<String, String>,
+) -> DriftReport {
+    let mut report = DriftReport {
+        name: name.to_string(),
+        version: version.to_string(),
+        ..Default::default()
+    };
+    for (path, digest) in local {
+        match published.get(path) {
+            Some(published) if published != digest => report.modified.push(path.clone()),
+            Some(_) => {}
+            None => report.added.push(path.clone()),
+        }
+    }
+    report.removed = published
+        .keys()
+        .filter(|path| !local.contains_key(*path))
+        .cloned()
+        .collect();
+    report
+}
+
+/// 包目录中推送时会放入主包的文件及其 sha256 摘要，跳过平台产物和命名产物
+pub fn local_hashes(
+    package_dir: &Path,
+    layout: &ArtifactLayout,
+) -> Result<BTreeMap<String, String>> {
+    let mut files = BTreeMap::new();
+    for entry in walkdir::WalkDir::new(package_dir) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let path = entry
+            .path()
+            .strip_prefix(package_dir)?
+            .to_string_lossy()
+            .replace('\\', "/");
+        if layout.excludes(&path) {
+            continue;
+        }
+        let digest = ChecksumAlgorithm::Sha256.digest(&std::fs::read(entry.path())?);
+        files.insert(path, digest);
+    }
+    Ok(files)
+}
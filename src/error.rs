@@ -0,0 +1,167 @@
+use crate::security::SecurityError;
+use reqwest::StatusCode;
+use thiserror::Error;
+
+/// PackageManager 返回的错误，库的使用者可以按失败类型处理
+#[derive(Error, Debug)]
+pub enum BeepkgError {
+    /// 凭证缺失、无效或已过期
+    #[error("Authentication failed: {0}")]
+    Auth(String),
+    /// 访问控制或存储服务拒绝了请求
+    #[error("Access denied: {0}")]
+    AccessDenied(String),
+    /// 存储服务返回了错误状态码
+    #[error("Network error: {0}")]
+    Network(String),
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Network access is disabled in offline mode: {0}")]
+    Offline(String),
+    #[error("Not found: {0}")]
+    NotFound(String),
+    /// 版本已存在或存在更高版本
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    #[error("Package is locked: {0}")]
+    Locked(String),
+    #[error("Checksum verification failed: {0}")]
+    ChecksumMismatch(String),
+    #[error("Missing checksum file")]
+    MissingChecksum,
+    #[error("Trust policy violation: {0}")]
+    PolicyViolation(String),
+    /// 超出注册表的大小限制或命名空间配额
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+    /// 注册表元数据、包元数据或存储服务响应无法解析
+    #[error("Invalid metadata: {0}")]
+    MetadataParse(String),
+    /// PackageManager 的设置不完整或无效
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+    #[error(transparent)]
+    Encryption(#[from] SecurityError),
+    #[error("Invalid package archive: {0}")]
+    Archive(#[from] zip::result::ZipError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// beepkg daemon 返回的错误，code 为对应的退出码
+    #[error("{message}")]
+    Remote { code: i32, message: String },
+    #[error("{0}")]
+    Other(String),
+}
+
+impl BeepkgError {
+    /// 按存储服务返回的状态码归类，context 描述失败的操作
+    pub fn from_status(status: StatusCode, context: impl std::fmt::Display) -> Self {
+        let message = format!("{}: {}", context, status);
+        match status {
+            StatusCode::UNAUTHORIZED => Self::Auth(message),
+            StatusCode::FORBIDDEN => Self::AccessDenied(message),
+            StatusCode::NOT_FOUND => Self::NotFound(message),
+            StatusCode::CONFLICT | StatusCode::PRECONDITION_FAILED => Self::Conflict(message),
+            _ => Self::Network(message),
+        }
+    }
+
+    /// 命令行退出码，便于脚本区分失败类型
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Auth(_) | Self::AccessDenied(_) => 3,
+            Self::Network(_) | Self::Http(_) | Self::Offline(_) => 4,
+            Self::NotFound(_) => 5,
+            Self::Conflict(_) => 6,
+            Self::Locked(_) => 7,
+            Self::ChecksumMismatch(_) | Self::MissingChecksum | Self::PolicyViolation(_) => 8,
+            Self::MetadataParse(_) => 9,
+            Self::Config(_) => 2,
+            Self::Encryption(_) => 10,
+            Self::QuotaExceeded(_) => 11,
+            Self::Remote { code, .. } => *code,
+            Self::Archive(_) | Self::Io(_) | Self::Other(_) => 1,
+        }
+    }
+}
+
+impl From<String> for BeepkgError {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+
+impl From<&str> for BeepkgError {
+    fn from(message: &str) -> Self {
+        Self::Other(message.to_string())
+    }
+}
+
+impl From<serde_json::Error> for BeepkgError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::MetadataParse(e.to_string())
+    }
+}
+
+impl From<toml::de::Error> for BeepkgError {
+    fn from(e: toml::de::Error) -> Self {
+        Self::MetadataParse(e.to_string())
+    }
+}
+
+impl From<quick_xml::DeError> for BeepkgError {
+    fn from(e: quick_xml::DeError) -> Self {
+        Self::MetadataParse(e.to_string())
+    }
+}
+
+impl From<walkdir::Error> for BeepkgError {
+    fn from(e: walkdir::Error) -> Self {
+        Self::Io(e.into())
+    }
+}
+
+// 没有单独分类的错误
+macro_rules! other_from {
+    ($($error:ty),* $(,)?) => {
+        $(
+            impl From<$error> for BeepkgError {
+                fn from(e: $error) -> Self {
+                    Self::Other(e.to_string())
+                }
+            }
+        )*
+    };
+}
+
+other_from!(
+    std::path::StripPrefixError,
+    chrono::ParseError,
+    chrono::OutOfRangeError,
+    globset::Error,
+    semver::Error,
+    url::ParseError,
+    rusty_s3::BucketError,
+);
+
+// 其他模块返回的 crate::Result 错误，能识别的类型保留原有分类
+impl From<Box<dyn std::error::Error + Send + Sync>> for BeepkgError {
+    fn from(error: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        let error = match error.downcast::<BeepkgError>() {
+            Ok(error) => return *error,
+            Err(error) => error,
+        };
+        let error = match error.downcast::<SecurityError>() {
+            Ok(error) => return Self::Encryption(*error),
+            Err(error) => error,
+        };
+        let error = match error.downcast::<reqwest::Error>() {
+            Ok(error) => return Self::Http(*error),
+            Err(error) => error,
+        };
+        match error.downcast::<std::io::Error>() {
+            Ok(error) => Self::Io(*error),
+            Err(error) => Self::Other(error.to_string()),
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::fmt;
+use std::path::PathBuf;
+
+/// PackageManager 在操作过程中发出的事件，嵌入方可以据此渲染自己的进度界面
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Event {
+    /// 开始把包目录打包为 zip
+    ArchiveStarted {
+        path: PathBuf,
+    },
+    /// 一个文件已加入包，bytes 为文件的原始大小
+    FileArchived {
+        path: PathBuf,
+        bytes: u64,
+        encrypted: bool,
+    },
+    ArchiveFinished {
+        path: PathBuf,
+    },
+    /// 开始上传对象，size 为对象的字节数
+    UploadStarted {
+        key: String,
+        size: u64,
+    },
+    /// 对象已上传的累计字节数
+    BytesUploaded {
+        key: String,
+        uploaded: u64,
+        total: u64,
+    },
+    UploadFinished {
+        key: String,
+        checksum: String,
+    },
+    DownloadStarted {
+        key: String,
+    },
+    /// 对象已下载的累计字节数，total 为服务端报告的大小
+    BytesDownloaded {
+        key: String,
+        downloaded: u64,
+        total: Option<u64>,
+    },
+    DownloadFinished {
+        key: String,
+        bytes: u64,
+    },
+    /// 下载的内容与校验文件一致
+    ChecksumVerified {
+        key: String,
+        checksum: String,
+    },
+    /// 包签名验证通过，signer 描述签名者（公钥、GPG 指纹或 sigstore 身份）
+    SignatureVerified {
+        signer: String,
+    },
+    /// 其他进度信息
+    Info(String),
+    /// 不影响操作结果的问题
+    Warning(String),
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::ArchiveStarted { path } => write!(f, "Creating zip archive at: {:?}", path),
+            Event::FileArchived {
+                path,
+                encrypted: true,
+                ..
+            } => write!(f, "Encrypted file: {:?}", path),
+            Event::FileArchived { path, bytes, .. } => {
+                write!(f, "Copied {} bytes for file: {:?}", bytes, path)
+            }
+            Event::ArchiveFinished { .. } => write!(f, "Finished creating zip archive"),
+            Event::UploadStarted { key, size } => write!(f, "Uploading {} ({} bytes)", key, size),
+            Event::BytesUploaded {
+                key,
+                uploaded,
+                total,
+            } => write!(f, "Uploaded {}/{} bytes of {}", uploaded, total, key),
+            Event::UploadFinished { key, checksum } => {
+                write!(f, "Uploaded {} (checksum {})", key, checksum)
+            }
+            Event::DownloadStarted { key } => write!(f, "Downloading {}", key),
+            Event::BytesDownloaded {
+                key,
+                downloaded,
+                total: Some(total),
+            } => write!(f, "Downloaded {}/{} bytes of {}", downloaded, total, key),
+            Event::BytesDownloaded {
+                key, downloaded, ..
+            } => write!(f, "Downloaded {} bytes of {}", downloaded, key),
+            Event::DownloadFinished { key, bytes } => {
+                write!(f, "Downloaded {} bytes for {}", bytes, key)
+            }
+            Event::ChecksumVerified { key, checksum } => {
+                write!(f, "Checksum verified for {}: {}", key, checksum)
+            }
+            Event::SignatureVerified { signer } => write!(f, "Signature verified with {}", signer),
+            Event::Info(message) => write!(f, "{}", message),
+            Event::Warning(message) => write!(f, "Warning: {}", message),
+        }
+    }
+}
+
+/// 接收 PackageManager 事件的观察者。回调在操作所在的任务中同步调用，不应阻塞
+pub trait Observer: Send + Sync {
+    fn on_event(&self, event: &Event);
+}
+
+impl<F> Observer for F
+where
+    F: Fn(&Event) + Send + Sync,
+{
+    fn on_event(&self, event: &Event) {
+        self(event)
+    }
+}
+
+/// 把事件转发到通道，由接收端在其他任务中处理
+impl Observer for tokio::sync::mpsc::UnboundedSender<Event> {
+    fn on_event(&self, event: &Event) {
+        // 接收端已关闭时丢弃事件
+        let _ = self.send(event.clone());
+    }
+}
+
+/// 命令行使用的观察者：把事件打印到标准输出，逐块的字节进度除外
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PrintObserver;
+
+impl Observer for PrintObserver {
+    fn on_event(&self, event: &Event) {
+        match event {
+            Event::BytesUploaded { .. } | Event::BytesDownloaded { .. } => {}
+            event => println!("{}", event),
+        }
+    }
+}
+
+/// 忽略所有事件，PackageManager 的默认观察者
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SilentObserver;
+
+impl Observer for SilentObserver {
+    fn on_event(&self, _event: &Event) {}
+}
@@ -0,0 +1,293 @@
+use crate::Result;
+use crate::models::PackageMetadata;
+use crate::schema::Versioned;
+use flate2::read::GzDecoder;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Component, Path, PathBuf};
+
+/// 可导入的其他包管理器产物
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForeignFormat {
+    /// cargo package 生成的 .crate
+    Crate,
+    /// npm pack 生成的 .tgz
+    Npm,
+    /// Python wheel（.whl）
+    Wheel,
+}
+
+impl ForeignFormat {
+    /// 按扩展名识别产物类型
+    pub fn detect(path: &Path) -> Result<Self> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        if name.ends_with(".crate") {
+            Ok(Self::Crate)
+        } else if name.ends_with(".tgz") || name.ends_with(".tar.gz") {
+            Ok(Self::Npm)
+        } else if name.ends_with(".whl") {
+            Ok(Self::Wheel)
+        } else {
+            Err(format!(
+                "Unsupported artifact {} (expected .crate, .tgz or .whl)",
+                path.display()
+            )
+            .into())
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Crate => "cargo",
+            Self::Npm => "npm",
+            Self::Wheel => "pip",
+        }
+    }
+}
+
+/// 把产物解压到目录，并根据其自带的元数据在目录中生成 pack.toml
+pub fn unpack(path: &Path, dir: &Path) -> Result<PackageMetadata> {
+    let format = ForeignFormat::detect(path)?;
+    let metadata = match format {
+        ForeignFormat::Crate => {
+            // .crate 和 npm 包中的文件都位于一个顶层目录下（name-version/、package/）
+            extract_tarball(path, dir)?;
+            crate_metadata(&std::fs::read_to_string(dir.join("Cargo.toml"))?)?
+        }
+        ForeignFormat::Npm => {
+            extract_tarball(path, dir)?;
+            npm_metadata(&std::fs::read_to_string(dir.join("package.json"))?)?
+        }
+        ForeignFormat::Wheel => {
+            zip::ZipArchive::new(File::open(path)?)?.extract(dir)?;
+            let metadata_path = std::fs::read_dir(dir)?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .find(|path| path.extension().is_some_and(|e| e == "dist-info"))
+                .map(|path| path.join("METADATA"))
+                .ok_or("Wheel has no .dist-info directory")?;
+            wheel_metadata(&std::fs::read_to_string(metadata_path)?)?
+        }
+    };
+
+    crate::models::validate_package_name(&metadata.name)?;
+    std::fs::write(dir.join("pack.toml"), toml::to_string_pretty(&metadata)?)?;
+    Ok(metadata)
+}
+
+// 解压 tar.gz 并去掉顶层目录
+fn extract_tarball(path: &Path, dir: &Path) -> Result<()> {
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(path)?));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.into_owned();
+        let mut components = entry_path.components();
+        components.next();
+        let relative: PathBuf = components.collect();
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(format!("Refusing to extract {}", entry_path.display()).into());
+        }
+
+        let target = dir.join(&relative);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        entry.unpack(&target)?;
+    }
+    Ok(())
+}
+
+fn package_metadata(
+    name: String,
+    version: String,
+    author: String,
+    description: String,
+    license: Option<String>,
+    dependencies: HashMap<String, String>,
+) -> PackageMetadata {
+    PackageMetadata {
+        schema_version: PackageMetadata::VERSION,
+        name,
+        version,
+        author,
+        description,
+        license,
+        homepage: None,
+        repository: None,
+        keywords: Vec::new(),
+        readme: None,
+        maintainers: Vec::new(),
+        includes: vec!["**/*".to_string()],
+        excludes: Vec::new(),
+        dependencies,
+        encryption: None,
+        visibility: None,
+        targets: Default::default(),
+        artifacts: Default::default(),
+        optional_dependencies: Default::default(),
+        features: Default::default(),
+        hooks: Default::default(),
+        release: Default::default(),
+    }
+}
+
+/// 从 Cargo.toml 生成元数据，依赖保留原有的版本需求
+pub fn crate_metadata(manifest: &str) -> Result<PackageMetadata> {
+    let manifest: toml::Value = toml::from_str(manifest)?;
+    let package = manifest
+        .get("package")
+        .ok_or("Cargo.toml has no [package]")?;
+    let field = |key: &str| {
+        package
+            .get(key)
+            .and_then(toml::Value::as_str)
+            .map(str::to_string)
+    };
+
+    let dependencies = manifest
+        .get("dependencies")
+        .and_then(toml::Value::as_table)
+        .map(|table| {
+            table
+                .iter()
+                .filter_map(|(name, spec)| {
+                    let requirement = match spec {
+                        toml::Value::String(version) => Some(version.as_str()),
+                        spec => spec.get("version").and_then(toml::Value::as_str),
+                    };
+                    requirement.map(|requirement| (name.clone(), requirement.to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(package_metadata(
+        field("name").ok_or("Cargo.toml has no package name")?,
+        field("version").ok_or("Cargo.toml has no package version")?,
+        package
+            .get("authors")
+            .and_then(toml::Value::as_array)
+            .map(|authors| {
+                authors
+                    .iter()
+                    .filter_map(toml::Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default(),
+        field("description").unwrap_or_default(),
+        field("license"),
+        dependencies,
+    ))
+}
+
+/// 从 package.json 生成元数据，@scope/name 形式的包名直接对应 beepkg 的作用域包
+pub fn npm_metadata(manifest: &str) -> Result<PackageMetadata> {
+    let manifest: serde_json::Value = serde_json::from_str(manifest)?;
+    let field = |key: &str| {
+        manifest
+            .get(key)
+            .and_then(serde_json::Value::as_str)
+            .map(str::to_string)
+    };
+
+    // author 可以是字符串，也可以是 {"name": ..., "email": ...}
+    let author = match manifest.get("author") {
+        Some(serde_json::Value::String(author)) => author.clone(),
+        Some(author) => author
+            .get("name")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        None => String::new(),
+    };
+    let dependencies = manifest
+        .get("dependencies")
+        .and_then(serde_json::Value::as_object)
+        .map(|dependencies| {
+            dependencies
+                .iter()
+                .filter_map(|(name, spec)| Some((name.clone(), spec.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(package_metadata(
+        field("name").ok_or("package.json has no name")?,
+        field("version").ok_or("package.json has no version")?,
+        author,
+        field("description").unwrap_or_default(),
+        field("license"),
+        dependencies,
+    ))
+}
+
+/// 从 wheel 的 METADATA（RFC 822 格式）生成元数据。
+/// 只保留无条件的 Requires-Dist，带环境标记（; extra == ...）的可选依赖被忽略
+pub fn wheel_metadata(metadata: &str) -> Result<PackageMetadata> {
+    let mut headers: Vec<(&str, &str)> = Vec::new();
+    // 空行之后是包的长描述
+    for line in metadata.lines().take_while(|line| !line.trim().is_empty()) {
+        if let Some((key, value)) = line.split_once(':') {
+            headers.push((key.trim(), value.trim()));
+        }
+    }
+    let field = |key: &str| {
+        headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.to_string())
+    };
+
+    let dependencies = headers
+        .iter()
+        .filter(|(key, _)| key.eq_ignore_ascii_case("Requires-Dist"))
+        .filter(|(_, value)| !value.contains(';'))
+        .map(|(_, value)| parse_requirement(value))
+        .collect();
+
+    Ok(package_metadata(
+        field("Name").ok_or("Wheel METADATA has no Name")?,
+        field("Version").ok_or("Wheel METADATA has no Version")?,
+        field("Author")
+            .or_else(|| field("Author-email"))
+            .unwrap_or_default(),
+        field("Summary").unwrap_or_default(),
+        field("License-Expression").or_else(|| field("License")),
+        dependencies,
+    ))
+}
+
+// 把 "requests (>=2.0,<3)" 或 "requests>=2.0" 拆分为包名和版本需求，未限定版本时为 "*"
+fn parse_requirement(requirement: &str) -> (String, String) {
+    let split = requirement
+        .find(|c: char| !(c.is_ascii_alphanumeric() || "-_.".contains(c)))
+        .unwrap_or(requirement.len());
+    let (name, spec) = requirement.split_at(split);
+    // 去掉 extras（requests[socks]）和括号，PEP 440 的 ==、~= 对应 semver 的 =、~
+    let spec = spec
+        .trim()
+        .trim_start_matches(|c| {
+            c != '(' && c != '<' && c != '>' && c != '=' && c != '~' && c != '!'
+        })
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .trim()
+        .replace("==", "=")
+        .replace("~=", "~");
+    let spec = if spec.is_empty() {
+        "*".to_string()
+    } else {
+        spec
+    };
+    (name.to_string(), spec)
+}
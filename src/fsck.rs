@@ -0,0 +1,189 @@
+use crate::checksum::ChecksumAlgorithm;
+use crate::models::RegistryMetadata;
+use std::collections::HashSet;
+use std::fmt;
+
+/// fsck 发现的不一致
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    /// 包文件没有任何算法的校验文件，无法被拉取
+    MissingChecksum { archive: String },
+    /// 锁定的版本已不存在
+    DanglingLock { name: String, version: String },
+    /// 已过期但仍留在注册表元数据中的锁
+    ExpiredLock { name: String, version: String },
+    /// 锁定时记录的校验和与当前的校验文件不一致
+    LockDrift {
+        name: String,
+        version: String,
+        locked: String,
+        current: String,
+    },
+    /// 注册表元数据中记录的备份对象已不存在
+    MissingBackup { backup_path: String },
+    /// 发布频道指向不存在的版本
+    DanglingChannel {
+        name: String,
+        channel: String,
+        version: String,
+    },
+    /// 指针引用的 blob 不存在
+    MissingBlob { key: String, blob: String },
+}
+
+impl Issue {
+    /// 能否由 fsck --repair 修复：只修复注册表元数据中指向不存在对象的记录，
+    /// 对象内容的问题需要重新推送或由 gc 清理
+    pub fn repairable(&self) -> bool {
+        matches!(
+            self,
+            Issue::DanglingLock { .. }
+                | Issue::ExpiredLock { .. }
+                | Issue::MissingBackup { .. }
+                | Issue::DanglingChannel { .. }
+        )
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::MissingChecksum { archive } => write!(f, "{} has no checksum file", archive),
+            Issue::DanglingLock { name, version } => {
+                write!(
+                    f,
+                    "lock on {}@{} refers to a missing package",
+                    name, version
+                )
+            }
+            Issue::ExpiredLock { name, version } => {
+                write!(f, "lock on {}@{} has expired", name, version)
+            }
+            Issue::LockDrift {
+                name,
+                version,
+                locked,
+                current,
+            } => write!(
+                f,
+                "{}@{} was locked with checksum {} but is now {}",
+                name, version, locked, current
+            ),
+            Issue::MissingBackup { backup_path } => {
+                write!(f, "backup {} is recorded but missing", backup_path)
+            }
+            Issue::DanglingChannel {
+                name,
+                channel,
+                version,
+            } => write!(
+                f,
+                "channel {} of {} points to missing version {}",
+                channel, name, version
+            ),
+            Issue::MissingBlob { key, blob } => write!(f, "{} points to missing {}", key, blob),
+        }
+    }
+}
+
+/// 对照存储桶中的对象检查注册表元数据和包文件：没有校验文件的包文件、
+/// 指向不存在版本的锁和发布频道、过期的锁，以及已不存在的备份。
+/// 存放在独立备份 bucket 中的备份不在 keys 中，不做检查
+pub fn check(
+    registry: &RegistryMetadata,
+    keys: &HashSet<&str>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut archives: Vec<&str> = keys
+        .iter()
+        .copied()
+        .filter(|key| is_archive_key(key))
+        .collect();
+    archives.sort_unstable();
+    for archive in archives {
+        let verified = ChecksumAlgorithm::ALL
+            .iter()
+            .any(|algorithm| keys.contains(algorithm.sidecar_name(archive).as_str()));
+        if !verified {
+            issues.push(Issue::MissingChecksum {
+                archive: archive.to_string(),
+            });
+        }
+    }
+
+    let exists =
+        |name: &str, version: &str| keys.contains(format!("{}-{}.zip", name, version).as_str());
+    for lock in &registry.locked_packages {
+        if !exists(&lock.name, &lock.version) {
+            issues.push(Issue::DanglingLock {
+                name: lock.name.clone(),
+                version: lock.version.clone(),
+            });
+        } else if lock.is_expired(now) {
+            issues.push(Issue::ExpiredLock {
+                name: lock.name.clone(),
+                version: lock.version.clone(),
+            });
+        }
+    }
+
+    for (name, channels) in &registry.channels {
+        for (channel, version) in channels {
+            if !exists(name, version) {
+                issues.push(Issue::DanglingChannel {
+                    name: name.clone(),
+                    channel: channel.clone(),
+                    version: version.clone(),
+                });
+            }
+        }
+    }
+
+    for backup in registry.backups.iter().filter(|b| b.bucket.is_none()) {
+        if !keys.contains(backup.backup_path.as_str()) {
+            issues.push(Issue::MissingBackup {
+                backup_path: backup.backup_path.clone(),
+            });
+        }
+    }
+    issues
+}
+
+/// 从注册表元数据中移除可修复问题对应的记录，返回修复的问题数量
+pub fn repair(registry: &mut RegistryMetadata, issues: &[Issue]) -> usize {
+    let mut repaired = 0;
+    for issue in issues {
+        let fixed = match issue {
+            Issue::DanglingLock { name, version } | Issue::ExpiredLock { name, version } => {
+                let count = registry.locked_packages.len();
+                registry
+                    .locked_packages
+                    .retain(|lock| !(lock.name == *name && lock.version == *version));
+                registry.locked_packages.len() < count
+            }
+            Issue::MissingBackup { backup_path } => {
+                let count = registry.backups.len();
+                registry
+                    .backups
+                    .retain(|backup| backup.backup_path != *backup_path);
+                registry.backups.len() < count
+            }
+            Issue::DanglingChannel { name, channel, .. } => registry
+                .channels
+                .get_mut(name)
+                .is_some_and(|channels| channels.remove(channel).is_some()),
+            _ => false,
+        };
+        if fixed {
+            repaired += 1;
+        }
+    }
+    registry.channels.retain(|_, channels| !channels.is_empty());
+    repaired
+}
+
+// 包文件的对象键：name-version.zip，备份和产物除外
+fn is_archive_key(key: &str) -> bool {
+    crate::models::parse_archive_key(key).is_some()
+}
@@ -0,0 +1,110 @@
+use crate::blobs;
+use crate::checksum::ChecksumAlgorithm;
+use crate::models;
+use std::collections::HashSet;
+
+/// 本工具不会写入、只可能由中断的上传留下的临时对象后缀
+pub const TEMP_SUFFIXES: [&str; 3] = [".tmp", ".part", ".partial"];
+
+/// 可以清理的对象类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GarbageKind {
+    /// 包文件已不存在的校验、签名、产物等附属文件
+    OrphanedSidecar,
+    /// 没有校验文件的包文件，通常是推送中途失败留下的，无法被拉取
+    UnverifiedArchive,
+    /// 注册表元数据中没有记录的备份
+    UnreferencedBackup,
+    /// 没有任何指针引用的 blob
+    UnreferencedBlob,
+    /// 临时对象
+    TempFile,
+    /// 未完成的分段上传
+    StaleUpload,
+}
+
+impl GarbageKind {
+    pub fn description(&self) -> &'static str {
+        match self {
+            GarbageKind::OrphanedSidecar => "sidecar without archive",
+            GarbageKind::UnverifiedArchive => "archive without checksum",
+            GarbageKind::UnreferencedBackup => "backup not in registry metadata",
+            GarbageKind::UnreferencedBlob => "unreferenced blob",
+            GarbageKind::TempFile => "temporary object",
+            GarbageKind::StaleUpload => "incomplete multipart upload",
+        }
+    }
+}
+
+/// 存储桶中的对象
+#[derive(Debug, Clone)]
+pub struct StoredObject {
+    pub key: String,
+    pub size: u64,
+    /// 早于保护期，可以清理；更新的对象可能属于正在进行的推送
+    pub expired: bool,
+}
+
+/// 找到的垃圾对象
+#[derive(Debug, Clone)]
+pub struct Garbage {
+    pub key: String,
+    pub kind: GarbageKind,
+    pub size: u64,
+    /// 分段上传的 ID，只用于 StaleUpload
+    pub upload_id: Option<String>,
+}
+
+/// 从对象列表中找出可以清理的对象。backups 为注册表元数据中记录的备份，
+/// referenced_blobs 为指针引用的 blob；只有 expired 的对象会被列出，
+/// 包文件是否存在等判断使用全部对象。无法识别的对象（审计日志、注册表元数据等）不会被列出
+pub fn find(
+    objects: &[StoredObject],
+    backups: &HashSet<&str>,
+    referenced_blobs: &HashSet<String>,
+) -> Vec<Garbage> {
+    let keys: HashSet<&str> = objects.iter().map(|object| object.key.as_str()).collect();
+    objects
+        .iter()
+        .filter(|object| object.expired)
+        .filter_map(|object| {
+            let kind = classify(&object.key, &keys, backups, referenced_blobs)?;
+            Some(Garbage {
+                key: object.key.clone(),
+                kind,
+                size: object.size,
+                upload_id: None,
+            })
+        })
+        .collect()
+}
+
+fn classify(
+    key: &str,
+    keys: &HashSet<&str>,
+    backups: &HashSet<&str>,
+    referenced_blobs: &HashSet<String>,
+) -> Option<GarbageKind> {
+    if TEMP_SUFFIXES.iter().any(|suffix| key.ends_with(suffix)) {
+        return Some(GarbageKind::TempFile);
+    }
+    if blobs::is_blob_key(key) {
+        return (!referenced_blobs.contains(key)).then_some(GarbageKind::UnreferencedBlob);
+    }
+    // 附属文件以 <archive>. 开头，其中 archive 以 .zip 结尾
+    if let Some(index) = key.find(".zip.") {
+        let archive = &key[..index + 4];
+        return (!keys.contains(archive)).then_some(GarbageKind::OrphanedSidecar);
+    }
+    if !key.ends_with(".zip") {
+        return None;
+    }
+    // 备份按完整的键格式识别，包名中含有 -backup- 的包仍按包文件处理
+    if models::parse_backup_key(key).is_some() {
+        return (!backups.contains(key)).then_some(GarbageKind::UnreferencedBackup);
+    }
+    let verified = ChecksumAlgorithm::ALL
+        .iter()
+        .any(|algorithm| keys.contains(algorithm.sidecar_name(key).as_str()));
+    (!verified).then_some(GarbageKind::UnverifiedArchive)
+}
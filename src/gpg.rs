@@ -0,0 +1,195 @@
+// GPG 签名调用系统中的 gpg，而不是链接 sequoia-openpgp 或 gpgme：这样直接使用团队已有的密钥环、
+// gpg-agent 和智能卡，也不引入 libgpgme 等 C 库或另一套 OpenPGP 实现。代价是运行时依赖 gpg，
+// 所以每次调用前检查程序存在且版本不低于 MIN_VERSION（--status-fd 的 VALIDSIG 和 keybox 密钥环）
+
+use crate::Result;
+use std::path::PathBuf;
+use std::process::{Output, Stdio};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// GPG 签名文件扩展名，例如 demo-1.0.0.zip.asc
+pub const SIGNATURE_EXTENSION: &str = "asc";
+
+// gpg 可执行文件，可通过 BEEPKG_GPG_PROGRAM 指定（例如 gpg2）
+fn gpg_command() -> Command {
+    Command::new(std::env::var("BEEPKG_GPG_PROGRAM").unwrap_or_else(|_| "gpg".to_string()))
+}
+
+/// 支持的最低 GnuPG 版本
+pub const MIN_VERSION: (u32, u32) = (2, 1);
+
+/// 检查 gpg 可以运行且版本不低于 MIN_VERSION，返回 `gpg --version` 报告的版本
+pub async fn check_program() -> Result<String> {
+    let output = gpg_command()
+        .arg("--version")
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| {
+            format!(
+                "Cannot run gpg ({}): install GnuPG {}.{} or later, or set BEEPKG_GPG_PROGRAM",
+                e, MIN_VERSION.0, MIN_VERSION.1
+            )
+        })?;
+    // 第一行形如 gpg (GnuPG) 2.4.3
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = stdout
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().last())
+        .filter(|_| output.status.success())
+        .ok_or("gpg --version did not report a version")?;
+    let mut parts = version.split('.').map(|part| part.parse::<u32>().ok());
+    let (Some(Some(major)), Some(Some(minor))) = (parts.next(), parts.next()) else {
+        return Err(format!("Cannot parse gpg version: {}", version).into());
+    };
+    if (major, minor) < MIN_VERSION {
+        return Err(format!(
+            "gpg {} is too old, GnuPG {}.{} or later is required",
+            version, MIN_VERSION.0, MIN_VERSION.1
+        )
+        .into());
+    }
+    Ok(version.to_string())
+}
+
+// 运行 gpg，并把消息写入其标准输入
+async fn run(mut command: Command, input: &[u8]) -> Result<Output> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run gpg: {}", e))?;
+
+    let mut stdin = child.stdin.take().ok_or("Failed to open gpg stdin")?;
+    stdin.write_all(input).await?;
+    drop(stdin);
+
+    Ok(child.wait_with_output().await?)
+}
+
+/// 使用 gpg 生成 ASCII armor 格式的分离签名，未指定密钥时使用 gpg 的默认密钥
+pub async fn sign(key_id: Option<&str>, message: &[u8]) -> Result<String> {
+    check_program().await?;
+    let mut command = gpg_command();
+    command.args(["--batch", "--yes", "--armor", "--detach-sign"]);
+    if let Some(key_id) = key_id {
+        command.args(["--local-user", key_id]);
+    }
+
+    let output = run(command, message).await?;
+    if !output.status.success() {
+        return Err(format!(
+            "gpg signing failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// 验证 GPG 签名时信任的公钥：keyring 为只包含受信任公钥的密钥环，fingerprints 为允许的
+/// 签名密钥（或其主密钥）指纹。两者至少指定一个，否则 gpg 会接受默认密钥环中任何公钥的签名
+#[derive(Debug, Clone, Default)]
+pub struct TrustPolicy {
+    pub keyring: Option<PathBuf>,
+    pub fingerprints: Vec<String>,
+}
+
+impl TrustPolicy {
+    /// 从 BEEPKG_GPG_KEYRING 和 BEEPKG_GPG_FINGERPRINTS（逗号分隔）读取，都未设置时返回 None
+    pub fn from_env() -> Option<Self> {
+        let keyring = std::env::var_os("BEEPKG_GPG_KEYRING").map(PathBuf::from);
+        let fingerprints: Vec<String> = std::env::var("BEEPKG_GPG_FINGERPRINTS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|fingerprint| !fingerprint.is_empty())
+            .map(str::to_string)
+            .collect();
+        (keyring.is_some() || !fingerprints.is_empty()).then_some(Self {
+            keyring,
+            fingerprints,
+        })
+    }
+
+    // 指纹是否在允许列表中，忽略大小写和空格；未指定允许列表时只依赖密钥环
+    fn allows(&self, fingerprints: &[&str]) -> bool {
+        let normalize = |fingerprint: &str| {
+            fingerprint
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<String>()
+                .to_ascii_uppercase()
+        };
+        self.fingerprints.is_empty()
+            || self.fingerprints.iter().any(|allowed| {
+                fingerprints
+                    .iter()
+                    .any(|fingerprint| normalize(allowed) == normalize(fingerprint))
+            })
+    }
+}
+
+/// 使用 gpg 验证分离签名，成功时返回签名密钥的指纹。
+/// 签名密钥必须来自 policy 指定的密钥环或在其指纹允许列表中
+pub async fn verify(signature: &[u8], message: &[u8], policy: &TrustPolicy) -> Result<String> {
+    if policy.keyring.is_none() && policy.fingerprints.is_empty() {
+        return Err(
+            "GPG verification needs a trusted keyring or fingerprint allow-list, \
+             set BEEPKG_GPG_KEYRING or BEEPKG_GPG_FINGERPRINTS"
+                .into(),
+        );
+    }
+
+    check_program().await?;
+    let mut signature_file = tempfile::NamedTempFile::new()?;
+    std::io::Write::write_all(&mut signature_file, signature)?;
+
+    let mut command = gpg_command();
+    command.args(["--batch", "--status-fd", "1"]);
+    if let Some(keyring) = &policy.keyring {
+        // gpg 会把不含路径分隔符的 keyring 名称解释为 GNUPGHOME 下的文件
+        command
+            .arg("--no-default-keyring")
+            .arg("--keyring")
+            .arg(std::path::absolute(keyring)?);
+    }
+    command.arg("--verify").arg(signature_file.path()).arg("-");
+
+    let output = run(command, message).await?;
+    let status = String::from_utf8_lossy(&output.stdout);
+    // VALIDSIG <签名密钥指纹> <日期> <时间戳> ... <主密钥指纹>
+    let fingerprints: Option<Vec<&str>> = status
+        .lines()
+        .find_map(|line| line.strip_prefix("[GNUPG:] VALIDSIG "))
+        .map(|rest| {
+            let fields: Vec<&str> = rest.split_whitespace().collect();
+            [fields.first(), fields.get(9)]
+                .into_iter()
+                .flatten()
+                .copied()
+                .collect()
+        });
+
+    match fingerprints {
+        Some(fingerprints) if output.status.success() && !fingerprints.is_empty() => {
+            if !policy.allows(&fingerprints) {
+                return Err(format!(
+                    "GPG signature by {} is not from a trusted fingerprint",
+                    fingerprints[0]
+                )
+                .into());
+            }
+            Ok(fingerprints[0].to_string())
+        }
+        _ => Err(format!(
+            "GPG signature verification failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into()),
+    }
+}
@@ -0,0 +1,299 @@
+// tonic 的接口以 Status 作为错误类型，无法缩小
+#![allow(clippy::result_large_err)]
+
+use crate::error::BeepkgError;
+use crate::models::PackageMetadata;
+use crate::operations::PackageManager;
+use futures_util::{Stream, StreamExt, stream};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio_util::io::ReaderStream;
+use tonic::transport::Server;
+use tonic::transport::server::TcpIncoming;
+use tonic::{Request, Response, Status, Streaming};
+
+/// 由 proto/beepkg.proto 生成的消息、服务端和客户端
+pub mod proto {
+    tonic::include_proto!("beepkg.v1");
+}
+
+use proto::registry_server::{Registry, RegistryServer};
+use proto::{pull_response, push_request};
+
+// 下载时每条消息的大小，低于 gRPC 默认的 4 MiB 消息上限
+const CHUNK_SIZE: usize = 256 * 1024;
+
+/// gRPC 服务的设置
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// 请求需要带上的令牌（authorization: Bearer <令牌>），为 None 时不检查
+    pub token: Option<String>,
+}
+
+/// 在 listener 上提供 beepkg.v1.Registry 服务，所有操作使用 manager 的凭证访问注册表
+pub async fn serve(
+    manager: Arc<PackageManager>,
+    listener: TcpListener,
+    options: Options,
+) -> Result<(), BeepkgError> {
+    let token = options.token;
+    let service = RegistryServer::with_interceptor(Service { manager }, move |request| {
+        authorize(token.as_deref(), request)
+    });
+    Server::builder()
+        .add_service(service)
+        .serve_with_incoming(TcpIncoming::from(listener))
+        .await
+        .map_err(|e| BeepkgError::Other(e.to_string()))
+}
+
+fn authorize(token: Option<&str>, request: Request<()>) -> Result<Request<()>, Status> {
+    let Some(token) = token else {
+        return Ok(request);
+    };
+    let provided = request
+        .metadata()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if provided == Some(token) {
+        Ok(request)
+    } else {
+        Err(Status::unauthenticated("Missing or invalid token"))
+    }
+}
+
+impl From<BeepkgError> for Status {
+    fn from(error: BeepkgError) -> Self {
+        let message = error.to_string();
+        match error {
+            BeepkgError::NotFound(_) => Status::not_found(message),
+            BeepkgError::Conflict(_) => Status::already_exists(message),
+            BeepkgError::Locked(_) => Status::failed_precondition(message),
+            BeepkgError::Auth(_) | BeepkgError::AccessDenied(_) => {
+                Status::permission_denied(message)
+            }
+            BeepkgError::Network(_) | BeepkgError::Http(_) | BeepkgError::Offline(_) => {
+                Status::unavailable(message)
+            }
+            BeepkgError::ChecksumMismatch(_)
+            | BeepkgError::MissingChecksum
+            | BeepkgError::PolicyViolation(_) => Status::data_loss(message),
+            BeepkgError::QuotaExceeded(_) => Status::resource_exhausted(message),
+            BeepkgError::Config(_) | BeepkgError::MetadataParse(_) => {
+                Status::invalid_argument(message)
+            }
+            _ => Status::internal(message),
+        }
+    }
+}
+
+fn to_proto(package: crate::models::Package) -> proto::Package {
+    proto::Package {
+        name: package.name,
+        version: package.version,
+        path: package.storage.path,
+        size: package.storage.size,
+        created_at: package.storage.created_at,
+    }
+}
+
+struct Service {
+    manager: Arc<PackageManager>,
+}
+
+#[tonic::async_trait]
+impl Registry for Service {
+    async fn list(
+        &self,
+        request: Request<proto::ListRequest>,
+    ) -> Result<Response<proto::ListResponse>, Status> {
+        let query = request.into_inner().query.to_lowercase();
+        let packages = self
+            .manager
+            .list_packages()
+            .await?
+            .into_iter()
+            .filter(|package| package.name.to_lowercase().contains(&query))
+            .map(to_proto)
+            .collect();
+        Ok(Response::new(proto::ListResponse { packages }))
+    }
+
+    async fn info(
+        &self,
+        request: Request<proto::InfoRequest>,
+    ) -> Result<Response<proto::InfoResponse>, Status> {
+        let name = request.into_inner().name;
+        let mut packages: Vec<_> = self
+            .manager
+            .list_packages()
+            .await?
+            .into_iter()
+            .filter(|package| package.name == name)
+            .collect();
+        if packages.is_empty() {
+            return Err(BeepkgError::NotFound(name).into());
+        }
+        // 从新到旧，无法解析的版本排在最后
+        packages.sort_by(|a, b| {
+            let a = semver::Version::parse(&a.version).ok();
+            let b = semver::Version::parse(&b.version).ok();
+            b.cmp(&a)
+        });
+
+        let locks = self.manager.list_locks(Some(&name)).await?;
+        let mut versions = Vec::new();
+        for package in packages {
+            let checksum = self.manager.grpc_checksum(&package.storage.path).await?;
+            let lock = locks.iter().find(|lock| lock.version == package.version);
+            versions.push(proto::Version {
+                size: package.storage.size,
+                created_at: package.storage.created_at,
+                checksum: checksum.map(|c| c.to_string()).unwrap_or_default(),
+                locked: lock.is_some(),
+                lock_reason: lock
+                    .map(|lock| lock.lock_reason.clone())
+                    .unwrap_or_default(),
+                version: package.version,
+            });
+        }
+        let channels = self
+            .manager
+            .list_channels(&name)
+            .await?
+            .into_iter()
+            .collect();
+        Ok(Response::new(proto::InfoResponse {
+            name,
+            versions,
+            channels,
+        }))
+    }
+
+    async fn push(
+        &self,
+        request: Request<Streaming<proto::PushRequest>>,
+    ) -> Result<Response<proto::PushResponse>, Status> {
+        let mut messages = request.into_inner();
+        let force = match messages.message().await?.and_then(|m| m.payload) {
+            Some(push_request::Payload::Header(header)) => header.force,
+            _ => {
+                return Err(Status::invalid_argument(
+                    "The first message must be a header",
+                ));
+            }
+        };
+
+        // 先把压缩包写入临时文件，再解压为包目录
+        let archive = tempfile::NamedTempFile::new()
+            .map_err(BeepkgError::from)?
+            .into_temp_path();
+        let mut file = tokio::fs::File::create(&archive)
+            .await
+            .map_err(BeepkgError::from)?;
+        while let Some(message) = messages.message().await? {
+            match message.payload {
+                Some(push_request::Payload::Chunk(chunk)) => {
+                    file.write_all(&chunk).await.map_err(BeepkgError::from)?
+                }
+                _ => return Err(Status::invalid_argument("Expected archive content")),
+            }
+        }
+        file.flush().await.map_err(BeepkgError::from)?;
+
+        let dir = tempfile::tempdir().map_err(BeepkgError::from)?;
+        let (source, target) = (archive.to_path_buf(), dir.path().to_path_buf());
+        let metadata = tokio::task::spawn_blocking(move || extract(source, target))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))??;
+
+        if force {
+            self.manager.force_push_package(dir.path()).await?;
+        } else {
+            self.manager.push_package(dir.path()).await?;
+        }
+        Ok(Response::new(proto::PushResponse {
+            name: metadata.name,
+            version: metadata.version,
+        }))
+    }
+
+    type PullStream = Pin<Box<dyn Stream<Item = Result<proto::PullResponse, Status>> + Send>>;
+
+    async fn pull(
+        &self,
+        request: Request<proto::PullRequest>,
+    ) -> Result<Response<Self::PullStream>, Status> {
+        let client = request.remote_addr().map(|addr| addr.ip());
+        let spec = request.into_inner().package;
+        let temp = tempfile::NamedTempFile::new()
+            .map_err(BeepkgError::from)?
+            .into_temp_path();
+        let (name, version, checksum) = self.manager.grpc_fetch(&spec, &temp, client).await?;
+
+        let file = tokio::fs::File::open(&temp)
+            .await
+            .map_err(BeepkgError::from)?;
+        let size = file.metadata().await.map_err(BeepkgError::from)?.len();
+        let header = proto::PullResponse {
+            payload: Some(pull_response::Payload::Header(proto::PullHeader {
+                name,
+                version,
+                checksum: checksum.to_string(),
+                size,
+            })),
+        };
+        // 临时文件在内容发送完后删除
+        let chunks = ReaderStream::with_capacity(file, CHUNK_SIZE).map(move |chunk| {
+            let _ = &temp;
+            chunk
+                .map(|chunk| proto::PullResponse {
+                    payload: Some(pull_response::Payload::Chunk(chunk)),
+                })
+                .map_err(|e| Status::internal(e.to_string()))
+        });
+        Ok(Response::new(Box::pin(
+            stream::once(async { Ok(header) }).chain(chunks),
+        )))
+    }
+
+    async fn lock(
+        &self,
+        request: Request<proto::LockRequest>,
+    ) -> Result<Response<proto::LockResponse>, Status> {
+        let request = request.into_inner();
+        let expires_at = Some(request.expires_at.as_str()).filter(|ts| !ts.is_empty());
+        self.manager
+            .lock_package(
+                &request.name,
+                &request.version,
+                &request.reason,
+                &request.user,
+                expires_at,
+            )
+            .await?;
+        Ok(Response::new(proto::LockResponse {}))
+    }
+
+    async fn unlock(
+        &self,
+        request: Request<proto::UnlockRequest>,
+    ) -> Result<Response<proto::UnlockResponse>, Status> {
+        let request = request.into_inner();
+        self.manager
+            .unlock_package(&request.name, &request.version)
+            .await?;
+        Ok(Response::new(proto::UnlockResponse {}))
+    }
+}
+
+// 解压上传的包目录并读取其中的元数据
+fn extract(archive: PathBuf, target: PathBuf) -> Result<PackageMetadata, BeepkgError> {
+    let mut zip = zip::ZipArchive::new(std::fs::File::open(archive)?)?;
+    zip.extract(&target)?;
+    Ok(PackageMetadata::load(&target)?)
+}
@@ -0,0 +1,134 @@
+use crate::Result;
+use crate::events::{Event, Observer};
+use crate::models::PackageMetadata;
+use std::path::{Component, Path};
+use std::process::Stdio;
+use tokio::process::Command;
+
+/// 传给钩子脚本的环境变量，其余环境变量（包括注册表凭证）都会被清除
+const INHERITED_ENV: &[&str] = &[
+    "PATH",
+    "HOME",
+    "LANG",
+    "TMPDIR",
+    "TEMP",
+    "TMP",
+    "USERPROFILE",
+    "SYSTEMROOT",
+    "COMSPEC",
+    "PATHEXT",
+];
+
+/// pack.toml 中 [hooks] 声明的钩子
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    /// 推送前打包之前运行，用于构建和生成代码
+    PrePush,
+    /// 拉取并解压之后运行，用于安装后的准备工作
+    PostPull,
+}
+
+impl Hook {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::PrePush => "pre_push",
+            Self::PostPull => "post_pull",
+        }
+    }
+}
+
+/// 钩子脚本必须是包目录内的相对路径
+pub fn validate_script(script: &str) -> Result<()> {
+    let path = Path::new(script);
+    let inside = !script.trim().is_empty()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !inside {
+        return Err(format!(
+            "Hook script {} must be a relative path inside the package directory",
+            script
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// 运行包声明的钩子：未声明时什么都不做；未允许钩子时只通过 observer 发出提示并跳过。
+///
+/// 脚本在包目录中运行，只继承 PATH、HOME 等基本环境变量，另外设置
+/// BEEPKG_HOOK、BEEPKG_PACKAGE_NAME、BEEPKG_PACKAGE_VERSION 和 BEEPKG_PACKAGE_DIR。
+/// .sh 脚本用 sh 运行，.ps1 脚本用 powershell 运行，其他文件直接执行
+pub async fn run(
+    hook: Hook,
+    metadata: &PackageMetadata,
+    package_dir: &Path,
+    allowed: bool,
+    observer: &dyn Observer,
+) -> Result<()> {
+    let script = match hook {
+        Hook::PrePush => metadata.hooks.pre_push.as_deref(),
+        Hook::PostPull => metadata.hooks.post_pull.as_deref(),
+    };
+    let Some(script) = script else {
+        return Ok(());
+    };
+    if !allowed {
+        observer.on_event(&Event::Info(format!(
+            "Skipping {} hook {} of {} (pass --allow-hooks to run it)",
+            hook.name(),
+            script,
+            metadata.name
+        )));
+        return Ok(());
+    }
+    validate_script(script)?;
+    let package_dir = package_dir.canonicalize()?;
+    let script_path = package_dir.join(script);
+    if !script_path.is_file() {
+        return Err(format!("Hook script not found: {}", script_path.display()).into());
+    }
+
+    let mut command = match script_path.extension().and_then(|e| e.to_str()) {
+        Some("sh") => {
+            let mut command = Command::new("sh");
+            command.arg(&script_path);
+            command
+        }
+        Some("ps1") => {
+            let mut command = Command::new("powershell");
+            command
+                .args(["-NoProfile", "-ExecutionPolicy", "Bypass", "-File"])
+                .arg(&script_path);
+            command
+        }
+        _ => Command::new(&script_path),
+    };
+    command
+        .current_dir(&package_dir)
+        .env_clear()
+        .envs(
+            INHERITED_ENV
+                .iter()
+                .filter_map(|key| Some((*key, std::env::var_os(key)?))),
+        )
+        .env("BEEPKG_HOOK", hook.name())
+        .env("BEEPKG_PACKAGE_NAME", &metadata.name)
+        .env("BEEPKG_PACKAGE_VERSION", &metadata.version)
+        .env("BEEPKG_PACKAGE_DIR", &package_dir)
+        .stdin(Stdio::null());
+
+    observer.on_event(&Event::Info(format!(
+        "Running {} hook {}",
+        hook.name(),
+        script
+    )));
+    let status = command
+        .status()
+        .await
+        .map_err(|e| format!("Failed to run {} hook {}: {}", hook.name(), script, e))?;
+    if !status.success() {
+        return Err(format!("{} hook {} failed: {}", hook.name(), script, status).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,159 @@
+use crate::Result;
+use crate::aws::{self, AwsCredentials};
+use crate::models::KmsConfig;
+use base64::{Engine as _, engine::general_purpose};
+use reqwest::Client;
+use serde_json::{Value, json};
+
+/// 使用 KMS 封装数据密钥，返回可保存的封装结果
+pub async fn wrap_key(client: &Client, config: &KmsConfig, data_key: &[u8]) -> Result<String> {
+    let plaintext = general_purpose::STANDARD.encode(data_key);
+    match config {
+        KmsConfig::Aws { key_id, region } => {
+            let response = aws_request(
+                client,
+                region.as_deref(),
+                "TrentService.Encrypt",
+                json!({ "KeyId": key_id, "Plaintext": plaintext }),
+            )
+            .await?;
+            json_string(&response, &["CiphertextBlob"])
+        }
+        KmsConfig::Vault {
+            key_id,
+            mount,
+            address,
+        } => {
+            let response = vault_request(
+                client,
+                address.as_deref(),
+                &format!("{}/encrypt/{}", mount, key_id),
+                json!({ "plaintext": plaintext }),
+            )
+            .await?;
+            json_string(&response, &["data", "ciphertext"])
+        }
+    }
+}
+
+/// 通过 KMS 解封数据密钥
+pub async fn unwrap_key(client: &Client, config: &KmsConfig, wrapped_key: &str) -> Result<Vec<u8>> {
+    let plaintext = match config {
+        KmsConfig::Aws { key_id, region } => {
+            let response = aws_request(
+                client,
+                region.as_deref(),
+                "TrentService.Decrypt",
+                json!({ "KeyId": key_id, "CiphertextBlob": wrapped_key }),
+            )
+            .await?;
+            json_string(&response, &["Plaintext"])?
+        }
+        KmsConfig::Vault {
+            key_id,
+            mount,
+            address,
+        } => {
+            let response = vault_request(
+                client,
+                address.as_deref(),
+                &format!("{}/decrypt/{}", mount, key_id),
+                json!({ "ciphertext": wrapped_key }),
+            )
+            .await?;
+            json_string(&response, &["data", "plaintext"])?
+        }
+    };
+    Ok(general_purpose::STANDARD.decode(plaintext)?)
+}
+
+fn json_string(value: &Value, path: &[&str]) -> Result<String> {
+    path.iter()
+        .try_fold(value, |value, key| value.get(key))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| format!("KMS response is missing {}", path.join(".")).into())
+}
+
+async fn vault_request(
+    client: &Client,
+    address: Option<&str>,
+    path: &str,
+    body: Value,
+) -> Result<Value> {
+    let address = match address {
+        Some(address) => address.to_string(),
+        None => std::env::var("VAULT_ADDR").map_err(|_| "VAULT_ADDR is not set")?,
+    };
+    let token = std::env::var("VAULT_TOKEN").map_err(|_| "VAULT_TOKEN is not set")?;
+
+    let mut request = client
+        .post(format!("{}/v1/{}", address.trim_end_matches('/'), path))
+        .header("X-Vault-Token", token)
+        .json(&body);
+    if let Ok(namespace) = std::env::var("VAULT_NAMESPACE") {
+        request = request.header("X-Vault-Namespace", namespace);
+    }
+
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!(
+            "Vault transit request failed: {} {}",
+            status,
+            response.text().await.unwrap_or_default()
+        )
+        .into());
+    }
+    Ok(response.json().await?)
+}
+
+async fn aws_request(
+    client: &Client,
+    region: Option<&str>,
+    target: &str,
+    body: Value,
+) -> Result<Value> {
+    let region = match region {
+        Some(region) => region.to_string(),
+        None => std::env::var("AWS_REGION")
+            .or_else(|_| std::env::var("AWS_DEFAULT_REGION"))
+            .map_err(|_| "Set a region in the kms config or AWS_REGION")?,
+    };
+    let credentials = AwsCredentials::from_env()?;
+
+    // BEEPKG_KMS_ENDPOINT 用于 localstack 等兼容服务
+    let endpoint = std::env::var("BEEPKG_KMS_ENDPOINT")
+        .unwrap_or_else(|_| format!("https://kms.{}.amazonaws.com", region));
+    let url = url::Url::parse(&endpoint)?;
+
+    let payload = serde_json::to_vec(&body)?;
+    let headers = aws::sign_post(
+        &credentials,
+        &region,
+        "kms",
+        &url,
+        &[
+            ("content-type", "application/x-amz-json-1.1".to_string()),
+            ("x-amz-target", target.to_string()),
+        ],
+        &payload,
+    )?;
+    let mut request = client.post(url).body(payload);
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!(
+            "AWS KMS {} failed: {} {}",
+            target,
+            status,
+            response.text().await.unwrap_or_default()
+        )
+        .into());
+    }
+    Ok(serde_json::from_slice(&response.bytes().await?)?)
+}
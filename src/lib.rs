@@ -1,8 +1,66 @@
+pub mod advisory;
+pub mod artifacts;
+pub mod auth;
+pub mod aws;
+pub mod blobs;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "archives")]
+pub mod bundle;
+pub mod cache;
+pub mod checksum;
+#[cfg(feature = "cli")]
 pub mod cli;
+pub mod completion;
+pub mod config;
+#[cfg(unix)]
+pub mod daemon;
+#[cfg(feature = "proxy")]
+pub mod dashboard;
+pub mod delta;
+pub mod diagnostics;
+pub mod doctor;
+pub mod drift;
+pub mod error;
+pub mod events;
+#[cfg(feature = "archives")]
+pub mod foreign;
+pub mod fsck;
+pub mod gc;
+pub mod gpg;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod hooks;
+pub mod kms;
+pub mod license;
+pub mod listing;
+pub mod metrics;
+pub mod mirror;
 pub mod models;
+pub mod notifiers;
+pub mod oci;
 pub mod operations;
+pub mod plugins;
+pub mod policy;
+pub mod project;
+pub mod provenance;
+#[cfg(feature = "proxy")]
+pub mod proxy;
+pub mod quota;
+pub mod readme;
+pub mod retention;
+pub mod sbom;
+pub mod schedule;
+pub mod schema;
 pub mod security;
-
+pub mod signing;
+pub mod sigstore;
+pub mod site;
+#[cfg(feature = "archives")]
+pub mod snapshot;
+pub mod stats;
+pub mod webhooks;
+pub mod workspace;
 
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
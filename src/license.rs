@@ -0,0 +1,164 @@
+use crate::Result;
+use serde::Deserialize;
+use std::path::Path;
+
+/// 项目目录下默认的许可证策略文件名
+pub const POLICY_FILE: &str = "beepkg-licenses.toml";
+
+/// 项目级许可证策略，install 和 audit 对解析出的依赖逐个检查
+///
+/// ```toml
+/// deny = ["GPL-3.0", "AGPL-3.0"]
+/// allow = ["MIT", "Apache-2.0", "BSD-3-Clause"]
+/// deny_unknown = true
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LicensePolicy {
+    /// 禁止的许可证，GPL-3.0 同时匹配 GPL-3.0-only 和 GPL-3.0-or-later
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// 允许的许可证，为空时不限制
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// 拒绝没有声明许可证的包
+    #[serde(default)]
+    pub deny_unknown: bool,
+}
+
+impl LicensePolicy {
+    /// 读取策略文件
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read license policy {}: {}", path.display(), e))?;
+        let policy = toml::from_str(&content)
+            .map_err(|e| format!("Invalid license policy {}: {}", path.display(), e))?;
+        Ok(policy)
+    }
+
+    /// 查找当前项目的策略文件：优先 BEEPKG_LICENSE_POLICY，其次当前目录下的 beepkg-licenses.toml
+    pub fn discover() -> Result<Option<Self>> {
+        if let Ok(path) = std::env::var("BEEPKG_LICENSE_POLICY") {
+            return Ok(Some(Self::load(Path::new(&path))?));
+        }
+        let path = std::env::current_dir()?.join(POLICY_FILE);
+        if path.exists() {
+            return Ok(Some(Self::load(&path)?));
+        }
+        Ok(None)
+    }
+
+    /// 检查包的许可证表达式，违反策略时返回原因。
+    /// OR 表达式只要有一个可选许可证满足即可，AND 表达式要求全部满足
+    pub fn evaluate(&self, license: Option<&str>) -> Option<String> {
+        let license = license.map(str::trim).filter(|l| !l.is_empty());
+        let Some(license) = license else {
+            return self.deny_unknown.then(|| "no license declared".to_string());
+        };
+        let tokens = tokenize(license);
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+        };
+        let accepted = match parser.expression(self) {
+            Some(accepted) if parser.position == tokens.len() => accepted,
+            // 无法解析的表达式按单个许可证名检查
+            _ => self.accepts(license),
+        };
+        (!accepted).then(|| format!("license {} is not allowed", license))
+    }
+
+    fn accepts(&self, id: &str) -> bool {
+        if self.deny.iter().any(|denied| license_matches(denied, id)) {
+            return false;
+        }
+        self.allow.is_empty()
+            || self
+                .allow
+                .iter()
+                .any(|allowed| license_matches(allowed, id))
+    }
+}
+
+// 不区分大小写，GPL-3.0 匹配 GPL-3.0-only、GPL-3.0-or-later 和 GPL-3.0+
+fn license_matches(pattern: &str, id: &str) -> bool {
+    let base = |id: &str| {
+        let id = id.trim().to_ascii_lowercase();
+        let id = id.strip_suffix('+').unwrap_or(&id).to_string();
+        ["-only", "-or-later"]
+            .iter()
+            .find_map(|suffix| id.strip_suffix(suffix).map(str::to_string))
+            .unwrap_or(id)
+    };
+    base(pattern) == base(id)
+}
+
+fn tokenize(expression: &str) -> Vec<String> {
+    expression
+        .replace('(', " ( ")
+        .replace(')', " ) ")
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+// SPDX 表达式：expression = term (OR term)*，term = factor (AND factor)*，
+// factor = "(" expression ")" | id [WITH exception]
+struct Parser<'a> {
+    tokens: &'a [String],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn next_is(&self, keyword: &str) -> bool {
+        self.tokens
+            .get(self.position)
+            .is_some_and(|token| token.eq_ignore_ascii_case(keyword))
+    }
+
+    fn expression(&mut self, policy: &LicensePolicy) -> Option<bool> {
+        let mut accepted = self.term(policy)?;
+        while self.next_is("OR") {
+            self.position += 1;
+            accepted |= self.term(policy)?;
+        }
+        Some(accepted)
+    }
+
+    fn term(&mut self, policy: &LicensePolicy) -> Option<bool> {
+        let mut accepted = self.factor(policy)?;
+        while self.next_is("AND") {
+            self.position += 1;
+            accepted &= self.factor(policy)?;
+        }
+        Some(accepted)
+    }
+
+    fn factor(&mut self, policy: &LicensePolicy) -> Option<bool> {
+        if self.next_is("(") {
+            self.position += 1;
+            let accepted = self.expression(policy)?;
+            if !self.next_is(")") {
+                return None;
+            }
+            self.position += 1;
+            return Some(accepted);
+        }
+        let id = self.tokens.get(self.position)?;
+        if ["OR", "AND", "WITH", ")"]
+            .iter()
+            .any(|k| id.eq_ignore_ascii_case(k))
+        {
+            return None;
+        }
+        self.position += 1;
+        // 例外条款不影响判断
+        if self.next_is("WITH") {
+            self.position += 2;
+            if self.position > self.tokens.len() {
+                return None;
+            }
+        }
+        Some(policy.accepts(id))
+    }
+}
@@ -0,0 +1,386 @@
+use crate::models::Package;
+use chrono::{DateTime, Utc};
+use globset::{GlobBuilder, GlobMatcher};
+use serde::Deserialize;
+use std::cmp::Ordering;
+
+/// 可以过滤的字段。除 name 和 version 外都需要读取每个包的元数据
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Field {
+    Name,
+    Version,
+    Author,
+    License,
+    Description,
+    Keyword,
+    /// `label:<key>`，`beepkg annotate` 附加的标签
+    Label(String),
+}
+
+impl Field {
+    pub fn needs_details(&self) -> bool {
+        !matches!(self, Field::Name | Field::Version)
+    }
+
+    // 字段的值，keyword 有多个值
+    fn values<'a>(&self, package: &'a Package) -> Vec<&'a str> {
+        match self {
+            Field::Name => vec![&package.name],
+            Field::Version => vec![&package.version],
+            Field::Author => vec![&package.author],
+            Field::License => vec![package.license.as_deref().unwrap_or_default()],
+            Field::Description => vec![&package.description],
+            Field::Keyword => package.keywords.iter().map(String::as_str).collect(),
+            Field::Label(key) => package
+                .labels
+                .get(key)
+                .map(String::as_str)
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+impl std::str::FromStr for Field {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(key) = s.trim().strip_prefix("label:") {
+            crate::models::validate_label_key(key).map_err(|e| e.to_string())?;
+            return Ok(Field::Label(key.to_string()));
+        }
+        match s.trim().to_ascii_lowercase().as_str() {
+            "name" => Ok(Field::Name),
+            "version" => Ok(Field::Version),
+            "author" => Ok(Field::Author),
+            "license" => Ok(Field::License),
+            "description" => Ok(Field::Description),
+            "keyword" | "keywords" => Ok(Field::Keyword),
+            other => Err(format!(
+                "Unknown field: {} (expected name, version, author, license, description, keyword or label:<key>)",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Condition {
+    /// `~`：glob，不区分大小写
+    Glob(GlobMatcher),
+    /// `=`：相等，不区分大小写
+    Equal(String),
+    /// `!=`
+    NotEqual(String),
+}
+
+/// 过滤条件 `<字段><运算符><值>`，运算符为 `~`（glob）、`=` 或 `!=`，例如 `name~ml-*`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Filter {
+    pub field: Field,
+    condition: Condition,
+}
+
+impl Filter {
+    pub fn matches(&self, package: &Package) -> bool {
+        let values = self.field.values(package);
+        match &self.condition {
+            Condition::Glob(glob) => values.iter().any(|value| glob.is_match(value)),
+            Condition::Equal(expected) => values.iter().any(|v| v.eq_ignore_ascii_case(expected)),
+            Condition::NotEqual(expected) => {
+                !values.iter().any(|v| v.eq_ignore_ascii_case(expected))
+            }
+        }
+    }
+
+    fn glob(field: Field, pattern: &str) -> Result<Self, String> {
+        let glob = GlobBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| format!("Invalid pattern {}: {}", pattern, e))?;
+        Ok(Filter {
+            field,
+            condition: Condition::Glob(glob.compile_matcher()),
+        })
+    }
+}
+
+impl std::str::FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let position = s
+            .find(['~', '=', '!'])
+            .ok_or_else(|| format!("Invalid filter {} (expected e.g. name~ml-*)", s))?;
+        let field: Field = s[..position].parse()?;
+        let rest = &s[position..];
+        if let Some(value) = rest.strip_prefix("!=") {
+            Ok(Filter {
+                field,
+                condition: Condition::NotEqual(value.to_string()),
+            })
+        } else if let Some(value) = rest.strip_prefix('=') {
+            Ok(Filter {
+                field,
+                condition: Condition::Equal(value.to_string()),
+            })
+        } else if let Some(pattern) = rest.strip_prefix('~') {
+            Filter::glob(field, pattern)
+        } else {
+            Err(format!("Invalid filter {} (expected ~, = or !=)", s))
+        }
+    }
+}
+
+impl TryFrom<String> for Filter {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// 排序方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum SortKey {
+    /// 按名称，同名的包按版本从低到高
+    #[default]
+    Name,
+    /// 按包文件大小从小到大
+    Size,
+    /// 按上传时间从早到晚
+    Date,
+}
+
+impl std::str::FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "name" => Ok(SortKey::Name),
+            "size" => Ok(SortKey::Size),
+            "date" => Ok(SortKey::Date),
+            other => Err(format!(
+                "Unknown sort key: {} (expected name, size or date)",
+                other
+            )),
+        }
+    }
+}
+
+impl TryFrom<String> for SortKey {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+// 名称相同时按 semver 比较版本，无法解析的版本排在前面
+fn by_name(a: &Package, b: &Package) -> Ordering {
+    a.name.cmp(&b.name).then_with(|| {
+        let version = |p: &Package| semver::Version::parse(&p.version).ok();
+        version(a)
+            .cmp(&version(b))
+            .then_with(|| a.version.cmp(&b.version))
+    })
+}
+
+/// list 可以显示的列
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Name,
+    Version,
+    Size,
+    Date,
+    Author,
+    License,
+    Description,
+    Artifacts,
+    /// 锁定的版本显示 locked
+    Locked,
+    /// 指向该版本的发布频道
+    Channels,
+}
+
+impl Column {
+    pub fn needs_details(&self) -> bool {
+        matches!(self, Column::Author | Column::License | Column::Description)
+    }
+
+    /// 是否需要读取注册表元数据中的锁和频道
+    pub fn needs_markers(&self) -> bool {
+        matches!(self, Column::Locked | Column::Channels)
+    }
+
+    /// 表头
+    pub fn title(&self) -> &'static str {
+        match self {
+            Column::Name => "NAME",
+            Column::Version => "VERSION",
+            Column::Size => "SIZE",
+            Column::Date => "DATE",
+            Column::Author => "AUTHOR",
+            Column::License => "LICENSE",
+            Column::Description => "DESCRIPTION",
+            Column::Artifacts => "ARTIFACTS",
+            Column::Locked => "LOCKED",
+            Column::Channels => "CHANNELS",
+        }
+    }
+
+    /// 包在这一列的值，大小为字节数
+    pub fn value(&self, package: &Package) -> String {
+        match self {
+            Column::Name => package.name.clone(),
+            Column::Version => package.version.clone(),
+            Column::Size => package.storage.size.to_string(),
+            Column::Date => package.storage.created_at.clone(),
+            Column::Author => package.author.clone(),
+            Column::License => package.license.clone().unwrap_or_default(),
+            Column::Description => package.description.clone(),
+            Column::Artifacts => package
+                .artifacts
+                .iter()
+                .map(|artifact| artifact.name.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+            Column::Locked => if package.is_locked { "locked" } else { "" }.to_string(),
+            Column::Channels => package.channels.join(","),
+        }
+    }
+}
+
+impl std::str::FromStr for Column {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "name" => Ok(Column::Name),
+            "version" => Ok(Column::Version),
+            "size" => Ok(Column::Size),
+            "date" => Ok(Column::Date),
+            "author" => Ok(Column::Author),
+            "license" => Ok(Column::License),
+            "description" => Ok(Column::Description),
+            "artifacts" => Ok(Column::Artifacts),
+            "locked" => Ok(Column::Locked),
+            "channels" => Ok(Column::Channels),
+            other => Err(format!("Unknown column: {}", other)),
+        }
+    }
+}
+
+/// 列出包时的过滤、排序和分页
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ListQuery {
+    /// 全部满足才列出
+    pub filters: Vec<Filter>,
+    /// 作者包含该文本（不区分大小写）
+    pub author: Option<String>,
+    pub sort: SortKey,
+    /// 倒序排列
+    pub reverse: bool,
+    /// 跳过排序后的前 offset 个包
+    pub offset: usize,
+    pub limit: Option<usize>,
+    /// 读取每个包的元数据（作者、描述、许可证等），过滤条件需要时自动读取
+    pub details: bool,
+    /// 标出锁定的版本和指向各版本的频道（读取一次注册表元数据）
+    pub markers: bool,
+}
+
+impl ListQuery {
+    /// 是否需要读取每个包的元数据
+    pub fn needs_details(&self) -> bool {
+        self.details
+            || self.author.is_some()
+            || self.filters.iter().any(|f| f.field.needs_details())
+    }
+
+    /// 只检查名称和版本的条件，读取元数据之前先用它缩小范围
+    pub fn matches_listing(&self, package: &Package) -> bool {
+        self.filters
+            .iter()
+            .filter(|f| !f.field.needs_details())
+            .all(|f| f.matches(package))
+    }
+
+    /// 检查全部条件
+    pub fn matches(&self, package: &Package) -> bool {
+        let author = self.author.as_ref().is_none_or(|author| {
+            package
+                .author
+                .to_lowercase()
+                .contains(&author.to_lowercase())
+        });
+        author && self.filters.iter().all(|f| f.matches(package))
+    }
+
+    /// 排序并分页
+    pub fn arrange(&self, mut packages: Vec<Package>) -> Vec<Package> {
+        packages.sort_by(|a, b| match self.sort {
+            SortKey::Name => by_name(a, b),
+            SortKey::Size => a
+                .storage
+                .size
+                .cmp(&b.storage.size)
+                .then_with(|| by_name(a, b)),
+            SortKey::Date => a
+                .storage
+                .created_at
+                .cmp(&b.storage.created_at)
+                .then_with(|| by_name(a, b)),
+        });
+        if self.reverse {
+            packages.reverse();
+        }
+        packages
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+}
+
+/// list 的输出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ListFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl std::str::FromStr for ListFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(ListFormat::Text),
+            "json" => Ok(ListFormat::Json),
+            other => Err(format!("Unknown format: {} (expected text or json)", other)),
+        }
+    }
+}
+
+/// 相对 now 的时间，例如 3 days ago；无法解析的时间原样返回
+pub fn relative_time(timestamp: &str, now: DateTime<Utc>) -> String {
+    let Ok(time) = DateTime::parse_from_rfc3339(timestamp) else {
+        return timestamp.to_string();
+    };
+    let seconds = (now - time.with_timezone(&Utc)).num_seconds();
+    let (count, unit) = match seconds {
+        // 时钟偏差可能使上传时间稍晚于当前时间
+        ..60 => return "just now".to_string(),
+        60..3600 => (seconds / 60, "minute"),
+        3600..86400 => (seconds / 3600, "hour"),
+        86400..2_592_000 => (seconds / 86400, "day"),
+        2_592_000..31_536_000 => (seconds / 2_592_000, "month"),
+        _ => (seconds / 31_536_000, "year"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{} {}{} ago", count, unit, plural)
+}
@@ -1,43 +1,270 @@
+use beepkg::advisory;
+use beepkg::auth;
+use beepkg::cache::Cache;
+use beepkg::checksum::ChecksumAlgorithm;
+use beepkg::completion;
+use beepkg::config::{self, Config};
+use beepkg::diagnostics;
+use beepkg::doctor;
+use beepkg::error::BeepkgError;
+use beepkg::events::{PrintObserver, SilentObserver};
+use beepkg::foreign;
+use beepkg::license::LicensePolicy;
+use beepkg::listing::{self, Column, ListFormat, ListQuery};
 use beepkg::models;
-use beepkg::security::SecurityManager;
-use beepkg::{Result, cli, operations};
-use clap::Parser;
+use beepkg::oci::OciRegistry;
+use beepkg::policy::TrustPolicy;
+use beepkg::project;
+use beepkg::sbom::{self, SbomFormat};
+use beepkg::security::{self, FileSelector, KdfParams, SecretSource, SecurityManager};
+use beepkg::signing;
+use beepkg::workspace::Workspace;
+use beepkg::{Result, cli, grpc, metrics, mirror, operations, plugins, proxy, site, stats};
+use clap::{CommandFactory, Parser};
 use dotenv::dotenv;
-use std::path::Path;
+use futures_util::StreamExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
+    if let Err(e) = run().await {
+        eprintln!("Error: {}", e);
+        // PackageManager 的错误按类型映射到退出码
+        let code = e
+            .downcast_ref::<BeepkgError>()
+            .map_or(1, BeepkgError::exit_code);
+        std::process::exit(code);
+    }
+}
+
+async fn run() -> Result<()> {
     // 加载 .env 文件
     dotenv().ok();
 
     env_logger::init();
     let args = cli::Cli::parse();
 
+    // 配置了 BEEPKG_METRICS_ADDR 时启动 Prometheus 指标端点，补全时不启动
+    if let Ok(addr) = std::env::var("BEEPKG_METRICS_ADDR")
+        && !matches!(args.command, cli::Commands::Complete { .. })
+    {
+        let addr = metrics::serve(&addr).await?;
+        println!("Metrics endpoint listening on http://{}/metrics", addr);
+    }
+
     match args.command {
-        cli::Commands::List { endpoint, bucket } => {
-            let manager = operations::PackageManager::new(
-                &endpoint, "", // Access key from env
-                "", // Secret key from env
-                &bucket,
-            )?;
-            let packages = manager.list_packages().await?;
+        cli::Commands::List {
+            endpoint,
+            bucket,
+            details,
+            filters,
+            author,
+            sort,
+            reverse,
+            offset,
+            limit,
+            mut columns,
+            long,
+            format,
+        } => {
+            // 列出时顺便更新缓存中的包名称索引，供补全使用
+            let manager = operations::PackageManager::builder()
+                .endpoint(&endpoint)
+                .bucket(&bucket)
+                .from_env()?
+                .build()?
+                .observer(Arc::new(PrintObserver))
+                .cache(Cache::from_env().ok());
+            let query = ListQuery {
+                filters,
+                author,
+                sort,
+                reverse,
+                offset,
+                limit,
+                details: details || columns.iter().any(Column::needs_details),
+                markers: long || columns.iter().any(Column::needs_markers),
+            };
+            let packages = manager.query_packages(&query).await?;
+            if format == ListFormat::Json {
+                println!("{}", serde_json::to_string_pretty(&packages)?);
+                return Ok(());
+            }
+            if long {
+                columns = vec![
+                    Column::Name,
+                    Column::Version,
+                    Column::Size,
+                    Column::Date,
+                    Column::Locked,
+                    Column::Channels,
+                ];
+                if details {
+                    columns.push(Column::Description);
+                }
+            }
+            if !columns.is_empty() {
+                print_table(&columns, &packages, long);
+                return Ok(());
+            }
             println!("Packages:");
             for pkg in packages {
                 println!("- {}@{}: {}", pkg.name, pkg.version, pkg.description);
+                if let Some(license) = &pkg.license {
+                    println!("  license: {}", license);
+                }
+                if let Some(homepage) = &pkg.homepage {
+                    println!("  homepage: {}", homepage);
+                }
+                if !pkg.keywords.is_empty() {
+                    println!("  keywords: {}", pkg.keywords.join(", "));
+                }
+                if !pkg.artifacts.is_empty() {
+                    let names: Vec<&str> = pkg.artifacts.iter().map(|a| a.name.as_str()).collect();
+                    println!("  artifacts: {}", names.join(", "));
+                }
+            }
+        }
+        cli::Commands::Info { package, json } => {
+            let info = manager_from_env()?.package_info(&package).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&info)?);
+                return Ok(());
+            }
+            println!("{}@{}", info.name, info.version);
+            println!("  author: {}", info.author);
+            println!("  description: {}", info.description);
+            println!(
+                "  license: {}",
+                info.license.as_deref().unwrap_or("(not declared)")
+            );
+            if let Some(homepage) = &info.homepage {
+                println!("  homepage: {}", homepage);
+            }
+            if let Some(repository) = &info.repository {
+                println!("  repository: {}", repository);
+            }
+            if !info.keywords.is_empty() {
+                println!("  keywords: {}", info.keywords.join(", "));
+            }
+            if let Some(readme) = &info.readme {
+                println!("  readme: {}", readme);
+            }
+            if !info.maintainers.is_empty() {
+                println!("  maintainers:");
+                for maintainer in &info.maintainers {
+                    println!("    {}", maintainer);
+                }
+            }
+            if !info.labels.is_empty() {
+                println!("  labels:");
+                for (key, value) in &info.labels {
+                    println!("    {}={}", key, value);
+                }
+            }
+            if !info.dependencies.is_empty() {
+                println!("  dependencies:");
+                for (name, requirement) in &info.dependencies {
+                    println!("    {} {}", name, requirement);
+                }
+            }
+        }
+        cli::Commands::Readme { package } => {
+            let readme = manager_from_env()?.readme(&package).await?;
+            println!("{}", readme.trim_end());
+        }
+        cli::Commands::Changelog {
+            name,
+            from,
+            to,
+            json,
+        } => {
+            let from = from.as_deref().map(parse_version_bound).transpose()?;
+            let to = to.as_deref().map(parse_version_bound).transpose()?;
+            let releases = manager_from_env()?
+                .changelog(&name, from.as_ref(), to.as_ref())
+                .await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&releases)?);
+                return Ok(());
+            }
+            for (index, release) in releases.iter().enumerate() {
+                if index > 0 {
+                    println!();
+                }
+                println!("## {} {}", release.name, release.version);
+                println!();
+                match &release.release_notes {
+                    Some(notes) => println!("{}", notes.trim_end()),
+                    None => println!("(no release notes)"),
+                }
+            }
+        }
+        cli::Commands::Search { query, json } => {
+            let results = manager_from_env()?.search_packages(&query).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&results)?);
+                return Ok(());
+            }
+            if results.is_empty() {
+                println!("No packages match {}", query);
+            }
+            for info in results {
+                println!("{}@{}: {}", info.name, info.version, info.description);
+                if !info.keywords.is_empty() {
+                    println!("  keywords: {}", info.keywords.join(", "));
+                }
+                if let Some(homepage) = &info.homepage {
+                    println!("  homepage: {}", homepage);
+                }
             }
         }
         cli::Commands::Push {
             key,
             secret,
+            session_token,
+            registry,
             package,
+            workspace,
+            archive,
+            atomic,
             force,
+            provenance,
+            sbom,
+            channel,
+            allow_hooks,
+            delta,
+            notes_file,
+            verify_upload,
+            draft,
         } => {
-            let endpoint = std::env::var("S3_ENDPOINT")?;
-            let bucket = std::env::var("S3_BUCKET").unwrap_or_else(|_| "packages".to_string());
+            let release_notes = notes_file.map(std::fs::read_to_string).transpose()?;
+            // 指定了注册表时使用配置文件中的地址和凭证
+            let (endpoint, bucket, key, secret) = match registry {
+                Some(name) => {
+                    let registry = Config::from_env()?.registry(&name)?.clone();
+                    (
+                        registry.endpoint,
+                        registry.bucket,
+                        key.or(registry.access_key),
+                        secret.or(registry.secret_key),
+                    )
+                }
+                None => (
+                    std::env::var("S3_ENDPOINT")?,
+                    std::env::var("S3_BUCKET").unwrap_or_else(|_| "packages".to_string()),
+                    key,
+                    secret,
+                ),
+            };
 
             // 优先使用命令行参数，其次使用环境变量
             let access_key = key.or_else(|| std::env::var("S3_ACCESS_KEY").ok());
             let secret_key = secret.or_else(|| std::env::var("S3_SECRET_KEY").ok());
+            let session_token = session_token.or_else(|| std::env::var("S3_SESSION_TOKEN").ok());
 
             println!(
                 "使用凭证: 访问密钥={}, 密钥={}",
@@ -49,33 +276,85 @@ async fn main() -> Result<()> {
                 }
             );
 
-            let manager = operations::PackageManager::new(
-                &endpoint,
-                access_key.as_deref().unwrap_or(""),
-                secret_key.as_deref().unwrap_or(""),
-                &bucket,
-            )?;
+            let manager = operations::PackageManager::builder()
+                .endpoint(&endpoint)
+                .bucket(&bucket)
+                .credentials(
+                    access_key.as_deref().unwrap_or_default(),
+                    secret_key.as_deref().unwrap_or_default(),
+                )
+                .from_env()?
+                .build()?
+                .observer(Arc::new(PrintObserver))
+                .session_token(session_token)
+                .provenance(provenance)
+                .sbom(sbom)
+                .channel(channel)
+                .allow_hooks(allow_hooks)
+                .deltas(delta)
+                .release_notes(release_notes)
+                .upload_verification(verify_upload)
+                .draft(draft)
+                .cache(Cache::from_env().ok());
 
-            // 根据 force 标志选择调用普通 push 还是强制 push
-            if force {
+            if let Some(archive) = archive {
+                let metadata = manager.push_archive(Path::new(&archive), force).await?;
+                println!(
+                    "Package {}@{} pushed from {}",
+                    metadata.name, metadata.version, archive
+                );
+            } else if workspace {
+                let root = Path::new(&package);
+                let workspace = Workspace::load(root)?;
+                let members = workspace.members(root)?;
+                let pushed = manager
+                    .excludes(workspace.excludes)
+                    .push_workspace(&members, force, atomic)
+                    .await?;
+                println!("Pushed {} workspace members:", pushed.len());
+                for package in pushed {
+                    println!("- {}", package);
+                }
+            } else if force {
+                // 根据 force 标志选择调用普通 push 还是强制 push
                 println!("使用强制推送模式，将忽略版本冲突");
                 manager.force_push_package(Path::new(&package)).await?;
+                println!("Package pushed successfully");
             } else {
                 manager.push_package(Path::new(&package)).await?;
+                println!("Package pushed successfully");
             }
-
-            println!("Package pushed successfully");
         }
-        cli::Commands::Pull { package, output } => {
-            let endpoint = std::env::var("S3_ENDPOINT")?;
-            let bucket = std::env::var("S3_BUCKET").unwrap_or_else(|_| "packages".to_string());
-
-            // 尝试从环境变量中读取凭证
-            let access_key = std::env::var("S3_ACCESS_KEY").unwrap_or_default();
-            let secret_key = std::env::var("S3_SECRET_KEY").unwrap_or_default();
-
-            let manager =
-                operations::PackageManager::new(&endpoint, &access_key, &secret_key, &bucket)?;
+        cli::Commands::Pull {
+            package,
+            pre,
+            target,
+            artifact,
+            output,
+            require_signature,
+            allow_hooks,
+            policy,
+            registry,
+            offline,
+        } => {
+            let policy = match policy {
+                Some(path) => Some(TrustPolicy::load(Path::new(&path))?),
+                None => TrustPolicy::discover()?,
+            };
+            let registries = pull_registries(registry)?
+                .into_iter()
+                .map(|(name, manager)| {
+                    let manager = manager
+                        .require_signature(require_signature)
+                        .trust_policy(policy.clone())
+                        .prerelease(pre)
+                        .target(target.clone())
+                        .allow_hooks(allow_hooks)
+                        .cache(Cache::from_env().ok())
+                        .offline(offline);
+                    (name, manager)
+                })
+                .collect::<Vec<_>>();
 
             // 为输出创建默认路径
             let output_path = match output {
@@ -83,14 +362,284 @@ async fn main() -> Result<()> {
                 None => std::env::current_dir()?.join("package"),
             };
 
-            manager.pull_package(&package, &output_path).await?;
-            println!("Package pulled to {}", output_path.display());
+            if let Some(artifact) = artifact {
+                // 命名产物只从第一个（或 --registry 指定的）注册表下载
+                let (source, manager) = registries.first().ok_or("No registries configured")?;
+                let path = manager
+                    .pull_artifact(&package, &artifact, &output_path)
+                    .await?;
+                println!("Artifact pulled from {} to {}", source, path.display());
+            } else {
+                let source =
+                    operations::pull_from_registries(&registries, &package, &output_path).await?;
+                println!(
+                    "Package pulled from {} to {}",
+                    source,
+                    output_path.display()
+                );
+            }
+        }
+        cli::Commands::Fetch {
+            package,
+            output,
+            pre,
+            require_signature,
+            offline,
+        } => {
+            let manager = manager_from_env()?
+                .require_signature(require_signature)
+                .prerelease(pre)
+                .cache(Cache::from_env().ok())
+                .offline(offline);
+            if output == "-" {
+                // 标准输出只写包文件内容
+                let manager = manager.observer(Arc::new(SilentObserver));
+                let mut stream = std::pin::pin!(manager.fetch_package_stream(&package).await?);
+                let mut stdout = tokio::io::stdout();
+                while let Some(chunk) = stream.next().await {
+                    stdout.write_all(&chunk?).await?;
+                }
+                stdout.flush().await?;
+            } else {
+                let checksum = manager.fetch_package(&package, Path::new(&output)).await?;
+                println!("Package fetched to {} ({})", output, checksum);
+            }
+        }
+        cli::Commands::Status { package, json } => {
+            let mut manager = manager_from_env()?.cache(Cache::from_env().ok());
+            if json {
+                manager = manager.observer(Arc::new(SilentObserver));
+            }
+            let report = manager.status(Path::new(&package)).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else if report.is_clean() {
+                println!(
+                    "{}@{} matches the published archive",
+                    report.name, report.version
+                );
+            } else {
+                println!(
+                    "{}@{} differs from the published archive:",
+                    report.name, report.version
+                );
+                for (mark, paths) in [
+                    ("M", &report.modified),
+                    ("A", &report.added),
+                    ("D", &report.removed),
+                ] {
+                    for path in paths {
+                        println!("  {} {}", mark, path);
+                    }
+                }
+            }
+            // 有差异时以非零状态退出，便于脚本判断
+            if !report.is_clean() {
+                return Err("Local files differ from the published archive".into());
+            }
+        }
+        cli::Commands::Install {
+            package,
+            features,
+            no_default_features,
+            pre,
+            output,
+            require_signature,
+            allow_hooks,
+            registry,
+            offline,
+        } => {
+            let (source, manager) = pull_registries(registry)?
+                .into_iter()
+                .next()
+                .ok_or("No registries configured")?;
+            let manager = manager
+                .require_signature(require_signature)
+                .trust_policy(TrustPolicy::discover()?)
+                .license_policy(LicensePolicy::discover()?)
+                .prerelease(pre)
+                .allow_hooks(allow_hooks)
+                .cache(Cache::from_env().ok())
+                .offline(offline);
+            let output_path = match output {
+                Some(path) => Path::new(&path).to_path_buf(),
+                None => std::env::current_dir()?.join("package"),
+            };
+
+            let installed = manager
+                .install_package(&package, &output_path, &features, !no_default_features)
+                .await?;
+            println!(
+                "Installed {} packages from {} to {}:",
+                installed.len(),
+                source,
+                output_path.display()
+            );
+            for package in installed {
+                println!("- {}", package);
+            }
+        }
+        cli::Commands::Vendor {
+            package,
+            output,
+            features,
+            no_default_features,
+            pre,
+            locked,
+            require_signature,
+            registry,
+            offline,
+        } => {
+            let (source, manager) = pull_registries(registry)?
+                .into_iter()
+                .next()
+                .ok_or("No registries configured")?;
+            let manager = manager
+                .require_signature(require_signature)
+                .trust_policy(TrustPolicy::discover()?)
+                .license_policy(LicensePolicy::discover()?)
+                .prerelease(pre)
+                .cache(Cache::from_env().ok())
+                .offline(offline);
+            let manifest = if locked {
+                manager.vendor_locked(Path::new(&output)).await?
+            } else {
+                manager
+                    .vendor_dependencies(
+                        Path::new(&package),
+                        Path::new(&output),
+                        &features,
+                        !no_default_features,
+                    )
+                    .await?
+            };
+            println!(
+                "Vendored {} packages from {} into {}:",
+                manifest.packages.len(),
+                source,
+                output
+            );
+            for package in manifest.packages {
+                println!(
+                    "- {}@{} ({})",
+                    package.name, package.version, package.checksum
+                );
+            }
+        }
+        cli::Commands::Sync {
+            project,
+            profile,
+            frozen,
+            pre,
+            require_signature,
+            registry,
+            offline,
+        } => {
+            let (source, manager) = pull_registries(registry)?
+                .into_iter()
+                .next()
+                .ok_or("No registries configured")?;
+            let manager = manager
+                .require_signature(require_signature)
+                .trust_policy(TrustPolicy::discover()?)
+                .license_policy(LicensePolicy::discover()?)
+                .prerelease(pre)
+                .cache(Cache::from_env().ok())
+                .offline(offline);
+            let report = manager
+                .sync_project(Path::new(&project), profile.as_deref(), frozen)
+                .await?;
+            match &profile {
+                Some(profile) => println!("Synced {} ({}) from {}", project, profile, source),
+                None => println!("Synced {} from {}", project, source),
+            }
+            for package in &report.installed {
+                println!("+ {}", package);
+            }
+            for package in &report.removed {
+                println!("- {}", package);
+            }
+            if !report.unchanged.is_empty() {
+                println!("{} packages up to date", report.unchanged.len());
+            }
+        }
+        cli::Commands::Keygen {
+            output,
+            force,
+            age: true,
+        } => {
+            let identity_path = format!("{}.age", output);
+            if !force && Path::new(&identity_path).exists() {
+                return Err(format!(
+                    "Identity file {} already exists, use --force to overwrite",
+                    identity_path
+                )
+                .into());
+            }
+
+            let (identity, recipient) = SecurityManager::generate_age_identity()?;
+            std::fs::write(
+                &identity_path,
+                format!(
+                    "# created: {}\n# public key: {}\n{}\n",
+                    chrono::Utc::now().to_rfc3339(),
+                    recipient,
+                    identity
+                ),
+            )?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(&identity_path, std::fs::Permissions::from_mode(0o600))?;
+            }
+
+            println!("Identity written to {}", identity_path);
+            println!("Public key: {}", recipient);
+            println!(
+                "Add the public key with `beepkg encrypt --enable -r {}` and set BEEPKG_AGE_IDENTITY={} to decrypt on pull",
+                recipient, identity_path
+            );
+        }
+        cli::Commands::Keygen {
+            output,
+            force,
+            age: false,
+        } => {
+            let key_path = format!("{}.key", output);
+            let pub_path = format!("{}.pub", output);
+            if !force && (Path::new(&key_path).exists() || Path::new(&pub_path).exists()) {
+                return Err(format!(
+                    "Key files {} / {} already exist, use --force to overwrite",
+                    key_path, pub_path
+                )
+                .into());
+            }
+
+            let (secret, public) = signing::generate_keypair()?;
+            std::fs::write(&key_path, format!("{}\n", secret))?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(&key_path, std::fs::Permissions::from_mode(0o600))?;
+            }
+            std::fs::write(&pub_path, format!("{}\n", public))?;
+
+            println!("Private key written to {}", key_path);
+            println!("Public key written to {}", pub_path);
+            println!("Public key: {}", public);
+            println!(
+                "Set BEEPKG_SIGNING_KEY={} to sign packages on push, and add {} to BEEPKG_TRUSTED_KEYS on consumers",
+                key_path, pub_path
+            );
         }
         cli::Commands::Test {
             endpoint,
             bucket,
             key,
             secret,
+            session_token,
+            read_only,
+            json,
         } => {
             // 获取端点和 bucket，优先使用命令行参数
             let endpoint = endpoint
@@ -104,150 +653,1686 @@ async fn main() -> Result<()> {
             // 优先使用命令行参数，其次使用环境变量
             let access_key = key.or_else(|| std::env::var("S3_ACCESS_KEY").ok());
             let secret_key = secret.or_else(|| std::env::var("S3_SECRET_KEY").ok());
+            let session_token = session_token.or_else(|| std::env::var("S3_SESSION_TOKEN").ok());
 
             // 创建 PackageManager
-            let manager = operations::PackageManager::new(
-                &endpoint,
-                access_key.as_deref().unwrap_or(""),
-                secret_key.as_deref().unwrap_or(""),
-                &bucket,
-            )?;
+            let manager = operations::PackageManager::builder()
+                .endpoint(&endpoint)
+                .bucket(&bucket)
+                .credentials(
+                    access_key.as_deref().unwrap_or_default(),
+                    secret_key.as_deref().unwrap_or_default(),
+                )
+                .from_env()?
+                .build()?
+                .observer(Arc::new(PrintObserver))
+                .session_token(session_token);
 
-            println!("测试连接到端点 {} 和 bucket {}", endpoint, bucket);
-            println!(
-                "使用凭证: 访问密钥={}, 密钥={}",
-                access_key.as_deref().unwrap_or("<未提供>"),
-                if secret_key.is_some() {
-                    "<已提供>"
-                } else {
-                    "<未提供>"
+            if !json {
+                println!("测试连接到端点 {} 和 bucket {}", endpoint, bucket);
+                println!(
+                    "使用凭证: 访问密钥={}, 密钥={}",
+                    access_key.as_deref().unwrap_or("<未提供>"),
+                    if secret_key.is_some() {
+                        "<已提供>"
+                    } else {
+                        "<未提供>"
+                    }
+                );
+            }
+
+            // 逐个阶段执行测试
+            let diagnosis = manager.diagnose(!read_only).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diagnosis)?);
+            } else {
+                for result in &diagnosis.stages {
+                    let mark = match result.status {
+                        diagnostics::Status::Ok => "✅",
+                        diagnostics::Status::Warning => "⚠️",
+                        diagnostics::Status::Failed => "❌",
+                        diagnostics::Status::Skipped => "➖",
+                    };
+                    let elapsed = result
+                        .elapsed_ms
+                        .map(|ms| format!(" ({} ms)", ms))
+                        .unwrap_or_default();
+                    println!(
+                        "{} {:<8} {}{}",
+                        mark,
+                        result.stage.title(),
+                        result.detail,
+                        elapsed
+                    );
                 }
-            );
+            }
+            if let Some(result) = diagnosis.first_failure() {
+                return Err(format!("Connection test failed at {}", result.stage.title()).into());
+            }
+        }
+        cli::Commands::Doctor {
+            package,
+            registry,
+            json,
+        } => {
+            let mut report = doctor::Report::default();
+            match auth::config_dir() {
+                Ok(dir) => report.extend(doctor::check_config(&dir.join(config::CONFIG_FILE))),
+                Err(e) => report.push(doctor::Check::warning(
+                    "config",
+                    e.to_string(),
+                    "set BEEPKG_CONFIG_DIR",
+                )),
+            }
+            report.extend(doctor::check_environment());
 
-            // 执行测试
-            let (success, message) = manager.test_connection().await?;
+            // 与其他命令相同的方式选择注册表：--registry 取自配置文件，否则使用 S3_ENDPOINT
+            let config = Config::from_env().unwrap_or_default();
+            let registry = match registry {
+                Some(name) => config.registry(&name).and_then(|registry| {
+                    let access_key = registry
+                        .access_key
+                        .clone()
+                        .unwrap_or_else(|| std::env::var("S3_ACCESS_KEY").unwrap_or_default());
+                    let secret_key = registry
+                        .secret_key
+                        .clone()
+                        .unwrap_or_else(|| std::env::var("S3_SECRET_KEY").unwrap_or_default());
+                    Ok((
+                        registry.endpoint.clone(),
+                        access_key,
+                        secret_key,
+                        printing(registry.manager()?),
+                    ))
+                }),
+                None => match std::env::var("S3_ENDPOINT") {
+                    Ok(endpoint) => manager_from_env().map(|manager| {
+                        (
+                            endpoint,
+                            std::env::var("S3_ACCESS_KEY").unwrap_or_default(),
+                            std::env::var("S3_SECRET_KEY").unwrap_or_default(),
+                            manager,
+                        )
+                    }),
+                    Err(_) => Err("no registry configured".into()),
+                },
+            };
+            match registry {
+                Ok((endpoint, access_key, secret_key, manager)) => {
+                    report.push(doctor::check_credentials(
+                        &config,
+                        &endpoint,
+                        &access_key,
+                        &secret_key,
+                    ));
+                    report.push(match manager.registry_schema_version().await {
+                        Ok(version) => doctor::check_registry_schema(version),
+                        Err(e) => doctor::Check::failed(
+                            "registry",
+                            format!("cannot read {}: {}", endpoint, e),
+                            "run `beepkg test` to diagnose the connection stage by stage",
+                        ),
+                    });
+                }
+                Err(e) => report.push(doctor::Check::failed(
+                    "registry",
+                    e.to_string(),
+                    "set S3_ENDPOINT, or add [[registries]] to config.toml and pass --registry",
+                )),
+            }
 
-            if success {
-                println!("✅ {}", message);
+            report.push(doctor::check_encryption(Path::new(&package)));
+            match Cache::from_env() {
+                Ok(cache) => report.push(doctor::check_cache(cache.root())),
+                Err(e) => report.push(doctor::Check::warning(
+                    "cache",
+                    e.to_string(),
+                    "set BEEPKG_CACHE_DIR",
+                )),
+            }
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
             } else {
-                println!("❌ {}", message);
+                for check in &report.checks {
+                    let mark = match check.status {
+                        diagnostics::Status::Ok => "✅",
+                        diagnostics::Status::Warning => "⚠️",
+                        diagnostics::Status::Failed => "❌",
+                        diagnostics::Status::Skipped => "➖",
+                    };
+                    println!("{} {:<12} {}", mark, check.area, check.detail);
+                    if let Some(fix) = &check.fix {
+                        println!("   {:<12} fix: {}", "", fix);
+                    }
+                }
+            }
+            if !report.is_ok() {
+                return Err(format!("{} checks failed", report.failures()).into());
+            }
+        }
+        cli::Commands::Gc {
+            delete,
+            min_age,
+            apply_retention,
+        } => {
+            let manager = manager_from_env()?;
+            // 先删除旧版本，它们留下的 blob 在同一次 gc 中清理
+            if apply_retention {
+                let reports = manager.apply_retention(delete).await?;
+                if reports.is_empty() {
+                    println!("No packages match a retention rule");
+                }
+                for report in &reports {
+                    println!("{} (rule {}):", report.package, report.pattern);
+                    for decision in &report.versions {
+                        if !decision.is_deleted() {
+                            let reasons: Vec<String> =
+                                decision.keep.iter().map(|r| r.to_string()).collect();
+                            println!("  keep    {} ({})", decision.version, reasons.join(", "));
+                        } else if delete {
+                            println!("  deleted {}", decision.version);
+                        } else {
+                            println!("  delete  {}", decision.version);
+                        }
+                    }
+                }
+            }
+
+            let report = manager
+                .collect_garbage(parse_duration(&min_age)?, delete)
+                .await?;
+
+            if report.garbage.is_empty() {
+                println!("No garbage found");
+            }
+            for item in &report.garbage {
+                println!(
+                    "{:<32} {} ({})",
+                    item.kind.description(),
+                    item.key,
+                    format_size(item.size)
+                );
+            }
+            let total: u64 = report.garbage.iter().map(|item| item.size).sum();
+            if delete {
+                println!(
+                    "Deleted {} objects, freed {}",
+                    report.deleted,
+                    format_size(report.freed)
+                );
+            } else if !report.garbage.is_empty() {
+                println!(
+                    "{} objects ({}) can be removed, run with --delete to remove them",
+                    report.garbage.len(),
+                    format_size(total)
+                );
+            }
+        }
+        cli::Commands::Retention { action } => {
+            let manager = manager_from_env()?;
+            match action {
+                cli::RetentionCommands::List => {
+                    let rules = manager.retention_rules().await?;
+                    if rules.is_empty() {
+                        println!("No retention rules");
+                    }
+                    for (pattern, rule) in &rules {
+                        let mut keeps = vec![format!("last {}", rule.keep_last)];
+                        if rule.keep_channels {
+                            keeps.push("channels".to_string());
+                        }
+                        if rule.keep_locked {
+                            keeps.push("locked".to_string());
+                        }
+                        println!("{}: keep {}", pattern, keeps.join(", "));
+                    }
+                }
+                cli::RetentionCommands::Set {
+                    pattern,
+                    keep_last,
+                    no_keep_channels,
+                    no_keep_locked,
+                } => {
+                    let rule = models::RetentionRule {
+                        keep_last,
+                        keep_channels: !no_keep_channels,
+                        keep_locked: !no_keep_locked,
+                    };
+                    manager.set_retention_rule(&pattern, rule).await?;
+                    println!("Retention rule for {} updated", pattern);
+                }
+                cli::RetentionCommands::Remove { pattern } => {
+                    manager.remove_retention_rule(&pattern).await?;
+                    println!("Retention rule for {} removed", pattern);
+                }
+            }
+        }
+        cli::Commands::Rehash { dry_run } => {
+            let manager = manager_from_env()?;
+            let migrated = manager.rehash_packages(dry_run).await?;
+
+            if migrated.is_empty() {
+                println!("All packages already use the registry checksum algorithm");
+            }
+            for archive in &migrated {
+                if dry_run {
+                    println!("Would rehash {}", archive);
+                } else {
+                    println!("Rehashed {}", archive);
+                }
             }
         }
         cli::Commands::Lock {
             package,
             reason,
             user,
+            expires,
         } => {
-            let endpoint = std::env::var("S3_ENDPOINT")?;
-            let bucket = std::env::var("S3_BUCKET").unwrap_or_else(|_| "packages".to_string());
+            let manager = manager_from_env()?;
+            let (name, version) = parse_package_spec(&package)?;
 
-            // 尝试从环境变量中读取凭证
-            let access_key = std::env::var("S3_ACCESS_KEY").unwrap_or_default();
-            let secret_key = std::env::var("S3_SECRET_KEY").unwrap_or_default();
+            manager
+                .lock_package(name, version, &reason, &user, expires.as_deref())
+                .await?;
+            println!("Package {}@{} has been locked", name, version);
+        }
+        cli::Commands::Locks { package } => {
+            let manager = manager_from_env()?;
+            let locks = manager.list_locks(package.as_deref()).await?;
 
-            let manager =
-                operations::PackageManager::new(&endpoint, &access_key, &secret_key, &bucket)?;
+            if locks.is_empty() {
+                println!("No locked packages");
+            } else {
+                println!("Locked packages:");
+                for lock in locks {
+                    println!(
+                        "- {}@{}: {} (locked by {} at {}, expires: {})",
+                        lock.name,
+                        lock.version,
+                        lock.lock_reason,
+                        lock.locked_by,
+                        lock.locked_at,
+                        lock.expires_at.as_deref().unwrap_or("never")
+                    );
+                }
+            }
+        }
+        cli::Commands::Audit {
+            action: None,
+            path,
+            feed,
+            pre,
+            features,
+        } => {
+            let metadata = models::PackageMetadata::load(Path::new(&path))?;
+            let manager = manager_from_env()?.prerelease(pre);
+            let license_policy = LicensePolicy::discover()?;
+            let feed = advisory_feed(feed);
+            let index = manager.fetch_advisories(&feed).await?;
+            let dependencies = manager
+                .resolve_dependencies(&metadata.active_dependencies(&features, true)?)
+                .await?;
+
+            let mut vulnerable = 0;
+            let mut license_violations = 0;
+            for dependency in &dependencies {
+                let Some(version) = &dependency.version else {
+                    println!(
+                        "? {} {} (not resolved in registry, skipped)",
+                        dependency.name, dependency.requirement
+                    );
+                    continue;
+                };
+
+                if let Some(policy) = &license_policy {
+                    let spec = format!("{}@{}", dependency.name, version);
+                    let info = manager.package_info(&spec).await?;
+                    if let Some(violation) = policy.evaluate(info.license.as_deref()) {
+                        license_violations += 1;
+                        println!("! {} {}", spec, violation);
+                    }
+                }
+
+                let advisories = index.affecting(&dependency.name, version);
+                if advisories.is_empty() {
+                    println!("  {}@{} ok", dependency.name, version);
+                    continue;
+                }
+
+                vulnerable += 1;
+                for advisory in advisories {
+                    println!(
+                        "! {}@{} {} [{}] {}",
+                        dependency.name,
+                        version,
+                        advisory.id,
+                        advisory.severity.as_deref().unwrap_or("unknown"),
+                        advisory.summary
+                    );
+                    if advisory.fixed.is_empty() {
+                        println!("    no fixed version available");
+                    } else {
+                        println!("    fixed in: {}", advisory.fixed.join(", "));
+                    }
+                    if let Some(url) = &advisory.url {
+                        println!("    {}", url);
+                    }
+                }
+            }
+
+            if license_violations > 0 {
+                return Err(BeepkgError::PolicyViolation(format!(
+                    "{} of {} dependencies violate the license policy",
+                    license_violations,
+                    dependencies.len()
+                ))
+                .into());
+            }
+            if vulnerable > 0 {
+                return Err(format!(
+                    "{} of {} dependencies have known vulnerabilities",
+                    vulnerable,
+                    dependencies.len()
+                )
+                .into());
+            }
+            println!(
+                "No known vulnerabilities in {} dependencies",
+                dependencies.len()
+            );
+        }
+        cli::Commands::Audit {
+            action: Some(cli::AuditCommands::Import { file, feed }),
+            ..
+        } => {
+            let index: advisory::AdvisoryIndex =
+                serde_json::from_str(&std::fs::read_to_string(&file)?)?;
+            let feed = advisory_feed(feed);
+            manager_from_env()?
+                .publish_advisories(&feed, &index)
+                .await?;
+            println!("Imported {} advisories to {}", index.advisories.len(), feed);
+        }
+        cli::Commands::Audit {
+            action: Some(cli::AuditCommands::Log { since, package }),
+            ..
+        } => {
+            let manager = manager_from_env()?;
+            let since = since.as_deref().map(parse_since).transpose()?;
+            let records = manager
+                .list_audit_records(since, package.as_deref())
+                .await?;
 
-            // 解析包名和版本
-            let (name, version) = match package.split_once('@') {
-                Some((n, v)) => (n, v),
-                None => return Err("Invalid package format, expected name@version".into()),
+            if records.is_empty() {
+                println!("No audit records found");
+            }
+            for record in records {
+                print!(
+                    "{} {} {} {} (client {})",
+                    record.timestamp,
+                    record.actor,
+                    record.action,
+                    record.package,
+                    record.client_version
+                );
+                if let Some(ip) = &record.source_ip {
+                    print!(" from {}", ip);
+                }
+                match &record.details {
+                    Some(details) => println!(": {}", details),
+                    None => println!(),
+                }
+            }
+        }
+        cli::Commands::Stats {
+            action:
+                cli::StatsCommands::Downloads {
+                    name,
+                    since,
+                    by,
+                    json,
+                },
+        } => {
+            let manager = manager_from_env()?;
+            let since = match since {
+                Some(since) => parse_since(&since)?,
+                None => chrono::Utc::now() - chrono::Duration::days(30),
             };
+            let records = manager.list_downloads(Some(since), name.as_deref()).await?;
+            let report = stats::summarize(&records, by);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+                return Ok(());
+            }
+            if report.is_empty() {
+                println!("No downloads recorded since {}", since.format("%Y-%m-%d"));
+            }
+            for package in report {
+                println!(
+                    "{}: {} downloads by {} clients",
+                    package.name, package.total, package.clients
+                );
+                let versions: Vec<String> = package
+                    .versions
+                    .iter()
+                    .map(|(version, count)| format!("{} ({})", version, count))
+                    .collect();
+                println!("  versions: {}", versions.join(", "));
+                for (period, count) in &package.periods {
+                    println!("  {:<10} {}", period, count);
+                }
+            }
+        }
+        cli::Commands::MigrateMetadata { path, dry_run } => {
+            let migrated = match path {
+                Some(path) => models::PackageMetadata::migrate(Path::new(&path), dry_run)?
+                    .into_iter()
+                    .collect(),
+                None => manager_from_env()?.migrate_metadata(dry_run).await?,
+            };
+            for document in &migrated {
+                let action = if dry_run { "Would migrate" } else { "Migrated" };
+                println!(
+                    "{} {} from schema version {} to {}",
+                    action, document.key, document.from, document.to
+                );
+            }
+            if migrated.is_empty() {
+                println!("All metadata is already at the current schema version");
+            }
+        }
+        cli::Commands::Quota { action } => {
+            let manager = manager_from_env()?;
+            match action {
+                cli::QuotaCommands::Status { namespace, json } => {
+                    let mut report = manager.quota_status().await?;
+                    if let Some(namespace) = &namespace {
+                        report.retain(|usage| &usage.namespace == namespace);
+                    }
+                    if json {
+                        println!("{}", serde_json::to_string_pretty(&report)?);
+                        return Ok(());
+                    }
+                    let settings = manager.registry_settings().await?;
+                    if let Some(max) = settings.quota.max_artifact_size {
+                        println!("Max artifact size: {}", format_size(max));
+                    }
+                    if report.is_empty() {
+                        println!("No packages stored");
+                    }
+                    for usage in report {
+                        let limit = match usage.limit {
+                            Some(limit) => format!(
+                                " of {} ({:.0}%)",
+                                format_size(limit),
+                                usage.used as f64 * 100.0 / limit.max(1) as f64
+                            ),
+                            None => String::new(),
+                        };
+                        println!(
+                            "{}: {}{} in {} versions{}",
+                            usage.namespace,
+                            format_size(usage.used),
+                            limit,
+                            usage.versions,
+                            if usage.exceeded() {
+                                " (over quota)"
+                            } else {
+                                ""
+                            }
+                        );
+                    }
+                }
+                cli::QuotaCommands::Set {
+                    max_artifact_size,
+                    max_namespace_bytes,
+                    scope,
+                } => {
+                    if let Some(scope) = scope {
+                        let max_bytes = parse_limit(max_namespace_bytes.as_deref().unwrap_or("0"))?;
+                        manager.set_scope_quota(&scope, max_bytes).await?;
+                        println!(
+                            "Quota of @{}: {}",
+                            scope.trim_start_matches('@'),
+                            max_bytes.map_or("registry default".to_string(), format_size)
+                        );
+                        return Ok(());
+                    }
+                    let mut quota = manager.registry_settings().await?.quota;
+                    if let Some(size) = max_artifact_size {
+                        quota.max_artifact_size = parse_limit(&size)?;
+                    }
+                    if let Some(size) = max_namespace_bytes {
+                        quota.max_namespace_bytes = parse_limit(&size)?;
+                    }
+                    manager.set_quota(quota.clone()).await?;
+                    let show =
+                        |limit: Option<u64>| limit.map_or("unlimited".to_string(), format_size);
+                    println!("Max artifact size: {}", show(quota.max_artifact_size));
+                    println!("Namespace quota: {}", show(quota.max_namespace_bytes));
+                }
+            }
+        }
+        cli::Commands::Registry { action } => {
+            let manager = manager_from_env()?;
+            match action {
+                cli::RegistryCommands::Show => {
+                    let settings = manager.registry_settings().await?;
+                    println!("Registry: {}", settings.registry_name);
+                    println!(
+                        "Checksum algorithm: {}",
+                        settings.checksum_algorithm.as_deref().unwrap_or("sha256")
+                    );
+                    println!("Storage layout: {}", settings.layout.name());
+                    println!("Immutable: {}", settings.immutable);
+                    println!("Download stats: {}", settings.download_stats);
+                    if !settings.protected_channels.is_empty() {
+                        println!(
+                            "Protected channels: {}",
+                            settings.protected_channels.join(", ")
+                        );
+                    }
+                    if let Some(max) = settings.quota.max_artifact_size {
+                        println!("Max artifact size: {}", format_size(max));
+                    }
+                    if let Some(max) = settings.quota.max_namespace_bytes {
+                        println!("Namespace quota: {}", format_size(max));
+                    }
+                    println!("Backups enabled: {}", settings.backup_enabled);
+                    println!("Last updated: {}", settings.last_updated);
+                }
+                cli::RegistryCommands::SetChecksum { algorithm } => {
+                    if algorithm == ChecksumAlgorithm::Sha1 {
+                        return Err("sha1 is only supported for reading legacy checksums".into());
+                    }
+                    manager.set_checksum_algorithm(algorithm).await?;
+                    println!("Checksum algorithm set to {}", algorithm.name());
+                }
+                cli::RegistryCommands::SetDownloadStats { enabled } => {
+                    manager.set_download_stats(enabled).await?;
+                    if enabled {
+                        println!("Recording downloads under stats/downloads/");
+                    } else {
+                        println!("No longer recording downloads");
+                    }
+                }
+                cli::RegistryCommands::SetImmutable { enabled } => {
+                    manager.set_immutable(enabled).await?;
+                    if enabled {
+                        println!("Published versions are now immutable");
+                    } else {
+                        println!("Published versions can be overwritten with --force");
+                    }
+                }
+                cli::RegistryCommands::SetProtected { channel, enabled } => {
+                    manager.set_channel_protected(&channel, enabled).await?;
+                    if enabled {
+                        println!("Channel {} now requires approval by a second user", channel);
+                    } else {
+                        println!("Channel {} is no longer protected", channel);
+                    }
+                }
+                cli::RegistryCommands::SetLayout { layout, dry_run } => {
+                    let converted = manager.migrate_layout(layout, dry_run).await?;
+                    for key in &converted {
+                        if dry_run {
+                            println!("Would convert {}", key);
+                        } else {
+                            println!("Converted {}", key);
+                        }
+                    }
+                    if dry_run {
+                        println!(
+                            "{} objects would be converted to the {} layout",
+                            converted.len(),
+                            layout.name()
+                        );
+                    } else {
+                        println!(
+                            "Storage layout set to {}, {} objects converted",
+                            layout.name(),
+                            converted.len()
+                        );
+                    }
+                }
+            }
+        }
+        cli::Commands::Sbom {
+            package: Some(package),
+            format,
+            output,
+            ..
+        } => {
+            let (name, version) = parse_package_spec(&package)?;
+            let manager = manager_from_env()?;
+            let (format, content) = manager.get_sbom(name, version, format).await?;
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, &content)?;
+                    println!("{} SBOM written to {}", format.name(), path);
+                }
+                None => println!("{}", String::from_utf8_lossy(&content)),
+            }
+        }
+        cli::Commands::Sbom {
+            package: None,
+            format,
+            path,
+            files,
+            output,
+            upload,
+        } => {
+            let format = format.unwrap_or(SbomFormat::CycloneDx);
+            let package_dir = Path::new(&path);
+            let metadata = models::PackageMetadata::load(package_dir)?;
+            let inventory = if files {
+                sbom::file_inventory(package_dir)?
+            } else {
+                Vec::new()
+            };
+            let document = sbom::generate(format, &metadata, &inventory);
 
-            manager.lock_package(name, version, &reason, &user).await?;
-            println!("Package {}@{} has been locked", name, version);
+            match &output {
+                Some(path) => {
+                    std::fs::write(path, serde_json::to_vec_pretty(&document)?)?;
+                    println!("{} SBOM written to {}", format.name(), path);
+                }
+                None if !upload => println!("{}", serde_json::to_string_pretty(&document)?),
+                None => {}
+            }
+
+            if upload {
+                manager_from_env()?
+                    .upload_sbom(&metadata.name, &metadata.version, format, document)
+                    .await?;
+                println!("SBOM attached to {}@{}", metadata.name, metadata.version);
+            }
         }
-        cli::Commands::Unlock { package } => {
-            let endpoint = std::env::var("S3_ENDPOINT")?;
-            let bucket = std::env::var("S3_BUCKET").unwrap_or_else(|_| "packages".to_string());
+        cli::Commands::Attestation { action } => match action {
+            cli::AttestationCommands::Show { package, json } => {
+                let (name, version) = parse_package_spec(&package)?;
+                let manager = manager_from_env()?;
+                let (statement, matches) = manager.get_attestation(name, version).await?;
+
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&statement)?);
+                } else {
+                    let predicate = &statement.predicate;
+                    let parameters = &predicate.build_definition.external_parameters;
+                    println!("Package:   {}", parameters.package);
+                    println!("Predicate: {}", statement.predicate_type);
+                    println!("Builder:   {}", predicate.run_details.builder.id);
+                    println!(
+                        "Source:    {}",
+                        parameters.repository.as_deref().unwrap_or("<unknown>")
+                    );
+                    println!(
+                        "Commit:    {}",
+                        parameters.commit.as_deref().unwrap_or("<unknown>")
+                    );
+                    println!(
+                        "Built:     {} - {}",
+                        predicate.run_details.metadata.started_on,
+                        predicate.run_details.metadata.finished_on
+                    );
+                    for subject in &statement.subject {
+                        for (algorithm, digest) in &subject.digest {
+                            println!(
+                                "Subject:   {} {}:{}",
+                                subject.name.as_deref().unwrap_or("<unnamed>"),
+                                algorithm,
+                                digest
+                            );
+                        }
+                    }
+                }
+
+                if !matches {
+                    eprintln!(
+                        "Warning: attestation subject does not match the current package checksum"
+                    );
+                }
+            }
+        },
+        cli::Commands::Webhook { action } => {
+            let manager = manager_from_env()?;
+            match action {
+                cli::WebhookCommands::List => {
+                    let webhooks = manager.list_webhooks().await?;
+                    if webhooks.is_empty() {
+                        println!("No webhooks configured");
+                    }
+                    for webhook in webhooks {
+                        let events = if webhook.events.is_empty() {
+                            "all events".to_string()
+                        } else {
+                            webhook
+                                .events
+                                .iter()
+                                .map(|e| e.to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        };
+                        println!(
+                            "- {} ({}{})",
+                            webhook.url,
+                            events,
+                            if webhook.secret_env.is_some() {
+                                ", signed"
+                            } else {
+                                ""
+                            }
+                        );
+                    }
+                }
+                cli::WebhookCommands::Add {
+                    url,
+                    secret_env,
+                    events,
+                } => {
+                    manager
+                        .add_webhook(models::WebhookConfig {
+                            url: url.clone(),
+                            secret_env,
+                            events,
+                        })
+                        .await?;
+                    println!("Webhook {} added", url);
+                }
+                cli::WebhookCommands::Remove { url } => {
+                    manager.remove_webhook(&url).await?;
+                    println!("Webhook {} removed", url);
+                }
+            }
+        }
+        cli::Commands::Access { action } => {
+            let manager = manager_from_env()?;
+            match action {
+                cli::AccessCommands::Show { package } => {
+                    let access = manager.package_access(&package).await?;
+                    println!("Visibility: {}", access.visibility);
+                    println!("Owner: {}", access.owner.as_deref().unwrap_or("<none>"));
+                    if access.grants.is_empty() {
+                        println!("No grants");
+                    }
+                    for grant in access.grants {
+                        println!("- {}", grant);
+                    }
+                }
+                cli::AccessCommands::Grant { package, principal } => {
+                    manager.grant_access(&package, &principal).await?;
+                    println!("Granted {} access to {}", principal, package);
+                }
+                cli::AccessCommands::Revoke { package, principal } => {
+                    manager.revoke_access(&package, &principal).await?;
+                    println!("Revoked {} access to {}", principal, package);
+                }
+            }
+        }
+        cli::Commands::Mirror { action } => match action {
+            cli::MirrorCommands::Sync {
+                from,
+                to,
+                filter,
+                jobs,
+                dry_run,
+            } => {
+                let config = Config::from_env()?;
+                let source = printing(config.registry(&from)?.manager()?);
+                let target = printing(config.registry(&to)?.manager()?);
+                let report = source
+                    .mirror_to(&target, filter.as_deref(), jobs, dry_run)
+                    .await?;
 
-            // 尝试从环境变量中读取凭证
+                for archive in &report.copied {
+                    if dry_run {
+                        println!("Would copy {}", archive);
+                    } else {
+                        println!("Copied {}", archive);
+                    }
+                }
+                for (archive, error) in &report.failed {
+                    println!("Failed to copy {}: {}", archive, error);
+                }
+                println!(
+                    "{} copied, {} up to date, {} failed",
+                    report.copied.len(),
+                    report.up_to_date,
+                    report.failed.len()
+                );
+                if !report.failed.is_empty() {
+                    return Err(format!("{} packages failed to mirror", report.failed.len()).into());
+                }
+            }
+            cli::MirrorCommands::Watch {
+                from,
+                to,
+                filter,
+                jobs,
+                interval,
+                status_file,
+            } => {
+                let config = Config::from_env()?;
+                let source = printing(config.registry(&from)?.manager()?);
+                let targets = to
+                    .into_iter()
+                    .map(|name| {
+                        let manager = printing(config.registry(&name)?.manager()?);
+                        Ok((name, manager))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let period = parse_duration(&interval)?;
+                if period.is_zero() {
+                    return Err("--interval must be greater than zero".into());
+                }
+                let options = mirror::WatchOptions {
+                    filter,
+                    jobs,
+                    interval: period,
+                    status_file: status_file.map(PathBuf::from),
+                };
+
+                println!(
+                    "Replicating {} to {} every {}",
+                    from,
+                    targets
+                        .iter()
+                        .map(|(name, _)| name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    interval
+                );
+                let stop = async {
+                    let _ = tokio::signal::ctrl_c().await;
+                };
+                mirror::watch(&source, &targets, &options, stop).await?;
+            }
+        },
+        cli::Commands::Migrate {
+            from_bucket,
+            to_bucket,
+            dry_run,
+            report,
+        } => {
+            let endpoint = std::env::var("S3_ENDPOINT")?;
             let access_key = std::env::var("S3_ACCESS_KEY").unwrap_or_default();
             let secret_key = std::env::var("S3_SECRET_KEY").unwrap_or_default();
+            let source = operations::PackageManager::builder()
+                .endpoint(&endpoint)
+                .bucket(from_bucket)
+                .credentials(&access_key, &secret_key)
+                .from_env()?
+                .build()?
+                .observer(Arc::new(PrintObserver));
+            let target = operations::PackageManager::builder()
+                .endpoint(&endpoint)
+                .bucket(to_bucket)
+                .credentials(access_key, secret_key)
+                .from_env()?
+                .build()?
+                .observer(Arc::new(PrintObserver));
 
-            let manager =
-                operations::PackageManager::new(&endpoint, &access_key, &secret_key, &bucket)?;
+            let entries = source.migrate_to(&target, dry_run).await?;
+            for entry in &entries {
+                let status = match entry.status {
+                    operations::MigrationStatus::Migrated => "migrated",
+                    operations::MigrationStatus::Planned => "would migrate",
+                    operations::MigrationStatus::Failed => "FAILED",
+                };
+                print!("{} {}", status, entry.source);
+                if let Some(target) = entry.target.as_ref().filter(|t| **t != entry.source) {
+                    print!(" -> {}", target);
+                }
+                println!();
+                for note in &entry.notes {
+                    println!("    {}", note);
+                }
+            }
 
-            // 解析包名和版本
-            let (name, version) = match package.split_once('@') {
-                Some((n, v)) => (n, v),
-                None => return Err("Invalid package format, expected name@version".into()),
+            let failed = entries
+                .iter()
+                .filter(|entry| entry.status == operations::MigrationStatus::Failed)
+                .count();
+            println!("{} packages, {} failed", entries.len(), failed);
+            if let Some(path) = report {
+                std::fs::write(&path, serde_json::to_string_pretty(&entries)?)?;
+                println!("Migration report written to {}", path);
+            }
+            if failed > 0 {
+                return Err(format!("{} packages failed to migrate", failed).into());
+            }
+        }
+        cli::Commands::Export {
+            packages,
+            output,
+            filter,
+        } => {
+            let manager = manager_from_env()?;
+            let manifest = manager
+                .export_bundle(Path::new(&output), &packages, filter.as_deref())
+                .await?;
+            for package in &manifest.packages {
+                println!("exported {}", package.archive);
+            }
+            println!("{} packages written to {}", manifest.packages.len(), output);
+        }
+        cli::Commands::Fsck { repair } => {
+            let manager = manager_from_env()?;
+            let report = manager.fsck(repair).await?;
+            for issue in &report.issues {
+                let hint = if issue.repairable() {
+                    ""
+                } else {
+                    " (not repairable)"
+                };
+                println!("{}{}", issue, hint);
+            }
+            if report.issues.is_empty() {
+                println!("No problems found");
+            } else if repair {
+                println!(
+                    "{} problems found, {} repaired",
+                    report.issues.len(),
+                    report.repaired
+                );
+            } else {
+                println!(
+                    "{} problems found, run with --repair to fix the repairable ones",
+                    report.issues.len()
+                );
+            }
+            if report.issues.len() > report.repaired {
+                return Err("The registry has unresolved problems".into());
+            }
+        }
+        cli::Commands::Snapshot { action } => {
+            let manager = manager_from_env()?;
+            match action {
+                cli::SnapshotCommands::Create { output, blobs } => {
+                    let manifest = manager.create_snapshot(Path::new(&output), blobs).await?;
+                    let included = manifest.objects.iter().filter(|o| o.included).count();
+                    println!(
+                        "{} of {} objects written to {}",
+                        included,
+                        manifest.objects.len(),
+                        output
+                    );
+                    if !blobs {
+                        println!("Package contents are not included, pass --blobs for a full copy");
+                    }
+                }
+                cli::SnapshotCommands::Restore { snapshot, force } => {
+                    let report = manager
+                        .restore_snapshot(Path::new(&snapshot), force)
+                        .await?;
+                    println!("{} objects restored", report.restored);
+                    for key in &report.missing {
+                        println!("MISSING {}", key);
+                    }
+                    if !report.missing.is_empty() {
+                        return Err(format!(
+                            "{} objects are neither in the snapshot nor in the bucket",
+                            report.missing.len()
+                        )
+                        .into());
+                    }
+                }
+            }
+        }
+        cli::Commands::Bundle { action } => match action {
+            cli::BundleCommands::Create {
+                manifest,
+                profile,
+                output,
+            } => {
+                let path = Path::new(&manifest);
+                if path.file_name().and_then(|name| name.to_str()) != Some(project::PROJECT_FILE) {
+                    return Err(format!(
+                        "The project manifest must be named {}",
+                        project::PROJECT_FILE
+                    )
+                    .into());
+                }
+                let root = path
+                    .parent()
+                    .filter(|parent| !parent.as_os_str().is_empty())
+                    .unwrap_or(Path::new("."));
+                let manager = manager_from_env()?;
+                let manifest = manager
+                    .create_project_bundle(root, profile.as_deref(), Path::new(&output))
+                    .await?;
+                for package in &manifest.packages {
+                    println!("bundled {}", package.archive);
+                }
+                println!("{} packages written to {}", manifest.packages.len(), output);
+            }
+            cli::BundleCommands::Install { bundle, project } => {
+                let manager = manager_from_env()?
+                    .trust_policy(TrustPolicy::discover()?)
+                    .license_policy(LicensePolicy::discover()?);
+                let report = manager
+                    .install_bundle(Path::new(&bundle), Path::new(&project))
+                    .await?;
+                println!("Installed {} into {}", bundle, project);
+                for package in &report.installed {
+                    println!("+ {}", package);
+                }
+                for package in &report.removed {
+                    println!("- {}", package);
+                }
+                if !report.unchanged.is_empty() {
+                    println!("{} packages up to date", report.unchanged.len());
+                }
+            }
+        },
+        cli::Commands::Import { bundle } => {
+            let manager = manager_from_env()?;
+            let report = manager.import_bundle(Path::new(&bundle)).await?;
+            for archive in &report.copied {
+                println!("imported {}", archive);
+            }
+            for (archive, error) in &report.failed {
+                println!("FAILED {}: {}", archive, error);
+            }
+            println!(
+                "{} imported, {} already present, {} failed",
+                report.copied.len(),
+                report.up_to_date,
+                report.failed.len()
+            );
+            if !report.failed.is_empty() {
+                return Err(format!("{} packages failed to import", report.failed.len()).into());
+            }
+        }
+        cli::Commands::ImportForeign {
+            path,
+            force,
+            dry_run,
+        } => {
+            let path = Path::new(&path);
+            let format = foreign::ForeignFormat::detect(path)?;
+            let dir = tempfile::tempdir()?;
+            let metadata = foreign::unpack(path, dir.path())?;
+            println!(
+                "Converted {} package {}@{}",
+                format.name(),
+                metadata.name,
+                metadata.version
+            );
+            if dry_run {
+                print!("{}", std::fs::read_to_string(dir.path().join("pack.toml"))?);
+            } else {
+                let manager = manager_from_env()?;
+                if force {
+                    manager.force_push_package(dir.path()).await?;
+                } else {
+                    manager.push_package(dir.path()).await?;
+                }
+                println!("Pushed {}@{}", metadata.name, metadata.version);
+            }
+        }
+        cli::Commands::Convert { to, package, keep } => {
+            let path = models::PackageMetadata::convert(Path::new(&package), to, keep)?;
+            println!("Metadata written to {}", path.display());
+        }
+        cli::Commands::Artifacts { package } => {
+            let artifacts = manager_from_env()?.list_artifacts(&package).await?;
+            if artifacts.is_empty() {
+                println!("No artifacts for {}", package);
+            }
+            for artifact in artifacts {
+                println!(
+                    "- {} ({}, {} bytes)",
+                    artifact.name, artifact.file_name, artifact.size
+                );
+            }
+        }
+        cli::Commands::Rollback {
+            name,
+            channel,
+            to,
+            restore,
+        } => {
+            let manager = manager_from_env()?;
+            let rollback = manager
+                .rollback(&name, &channel, to.as_deref(), restore)
+                .await?;
+            if let Some(backup) = &rollback.restored_backup {
+                println!(
+                    "Restored {}@{} from backup {}",
+                    rollback.name, rollback.to, backup
+                );
+            }
+            if rollback.pending {
+                println!(
+                    "Channel {} is protected; rollback of {} to {} awaits approval by another user",
+                    rollback.channel, rollback.name, rollback.to
+                );
+            } else {
+                println!(
+                    "Channel {} of {} rolled back from {} to {}",
+                    rollback.channel, rollback.name, rollback.from, rollback.to
+                );
+            }
+        }
+        cli::Commands::Release { package } => {
+            let manager = manager_from_env()?;
+            match package {
+                Some(package) => {
+                    manager.release(&package).await?;
+                    println!("Released {}", package);
+                }
+                None => {
+                    let drafts = manager.drafts().await?;
+                    if drafts.is_empty() {
+                        println!("No drafts");
+                    }
+                    for draft in drafts {
+                        let channel = draft
+                            .channel
+                            .map(|channel| format!(" -> {}", channel))
+                            .unwrap_or_default();
+                        println!(
+                            "{}@{}{} (pushed by {} at {})",
+                            draft.name, draft.version, channel, draft.created_by, draft.created_at
+                        );
+                    }
+                }
+            }
+        }
+        cli::Commands::Approve { package } => {
+            let manager = manager_from_env()?;
+            match package {
+                Some(package) => {
+                    for approval in manager.approve(&package).await? {
+                        println!(
+                            "Approved {}@{} for channel {} (requested by {})",
+                            approval.name,
+                            approval.version,
+                            approval.channel,
+                            approval.requested_by
+                        );
+                    }
+                }
+                None => {
+                    let pending = manager.pending_approvals().await?;
+                    if pending.is_empty() {
+                        println!("No pending approvals");
+                    }
+                    for request in pending {
+                        println!(
+                            "{}@{} -> {} (requested by {} at {})",
+                            request.name,
+                            request.version,
+                            request.channel,
+                            request.requested_by,
+                            request.requested_at
+                        );
+                    }
+                }
+            }
+        }
+        cli::Commands::Tag { package, channel } => {
+            let manager = manager_from_env()?;
+            match channel {
+                Some(channel) => {
+                    if manager.tag_package(&package, &channel).await? {
+                        println!("Channel {} now points to {}", channel, package);
+                    } else {
+                        println!(
+                            "Channel {} is protected; {} awaits approval by another user",
+                            channel, package
+                        );
+                    }
+                }
+                None => {
+                    let channels = manager.list_channels(&package).await?;
+                    if channels.is_empty() {
+                        println!("No channels for {}", package);
+                    }
+                    for (channel, version) in channels {
+                        println!("- {}: {}", channel, version);
+                    }
+                }
+            }
+        }
+        cli::Commands::Annotate {
+            package,
+            labels,
+            remove,
+        } => {
+            let manager = manager_from_env()?;
+            let labels = if labels.is_empty() && remove.is_empty() {
+                manager.package_info(&package).await?.labels
+            } else {
+                let set = labels
+                    .iter()
+                    .map(|label| models::parse_label(label))
+                    .collect::<Result<std::collections::BTreeMap<_, _>>>()?;
+                manager.annotate(&package, &set, &remove).await?
             };
+            if labels.is_empty() {
+                println!("No labels on {}", package);
+            }
+            for (key, value) in labels {
+                println!("- {}={}", key, value);
+            }
+        }
+        cli::Commands::Scope { action } => {
+            let manager = manager_from_env()?;
+            match action {
+                cli::ScopeCommands::List => {
+                    let scopes = manager.list_scopes().await?;
+                    if scopes.is_empty() {
+                        println!("No scopes configured");
+                    }
+                    for (scope, settings) in scopes {
+                        println!(
+                            "- @{} (visibility: {}, signatures {}{})",
+                            scope,
+                            settings.visibility.unwrap_or_default(),
+                            if settings.require_signature {
+                                "required"
+                            } else {
+                                "optional"
+                            },
+                            settings
+                                .max_bytes
+                                .map(|max| format!(", quota {}", format_size(max)))
+                                .unwrap_or_default()
+                        );
+                    }
+                }
+                cli::ScopeCommands::Set {
+                    scope,
+                    visibility,
+                    require_signature,
+                } => {
+                    let settings = manager
+                        .configure_scope(&scope, visibility, require_signature)
+                        .await?;
+                    println!(
+                        "Scope @{} updated (visibility: {}, signatures {})",
+                        scope.trim_start_matches('@'),
+                        settings.visibility.unwrap_or_default(),
+                        if settings.require_signature {
+                            "required"
+                        } else {
+                            "optional"
+                        }
+                    );
+                }
+            }
+        }
+        cli::Commands::Notifier { action } => {
+            let manager = manager_from_env()?;
+            match action {
+                cli::NotifierCommands::List => {
+                    let notifiers = manager.list_notifiers().await?;
+                    if notifiers.is_empty() {
+                        println!("No notifiers configured");
+                    }
+                    for (i, notifier) in notifiers.iter().enumerate() {
+                        match notifier {
+                            models::NotifierConfig::Slack { webhook_url, .. } => {
+                                println!("{}. slack {}", i + 1, webhook_url);
+                            }
+                            models::NotifierConfig::Email { smtp_host, to, .. } => {
+                                println!("{}. email via {} to {}", i + 1, smtp_host, to.join(", "));
+                            }
+                        }
+                    }
+                }
+                cli::NotifierCommands::AddSlack {
+                    webhook_url,
+                    template,
+                    events,
+                } => {
+                    manager
+                        .add_notifier(models::NotifierConfig::Slack {
+                            webhook_url,
+                            template,
+                            events,
+                        })
+                        .await?;
+                    println!("Slack notifier added");
+                }
+                cli::NotifierCommands::AddEmail {
+                    smtp_host,
+                    smtp_port,
+                    no_starttls,
+                    username,
+                    password_env,
+                    from,
+                    to,
+                    subject,
+                    template,
+                    events,
+                } => {
+                    manager
+                        .add_notifier(models::NotifierConfig::Email {
+                            smtp_host,
+                            smtp_port,
+                            starttls: !no_starttls,
+                            username,
+                            password_env,
+                            from,
+                            to,
+                            subject,
+                            template,
+                            events,
+                        })
+                        .await?;
+                    println!("Email notifier added");
+                }
+                cli::NotifierCommands::Remove { index } => {
+                    manager.remove_notifier(index).await?;
+                    println!("Notifier #{} removed", index);
+                }
+            }
+        }
+        cli::Commands::Unlock { package } => {
+            let manager = manager_from_env()?;
+            let (name, version) = parse_package_spec(&package)?;
 
             manager.unlock_package(name, version).await?;
             println!("Package {}@{} has been unlocked", name, version);
         }
-        cli::Commands::Backup { package, reason } => {
-            let endpoint = std::env::var("S3_ENDPOINT")?;
-            let bucket = std::env::var("S3_BUCKET").unwrap_or_else(|_| "packages".to_string());
-
-            // 尝试从环境变量中读取凭证
-            let access_key = std::env::var("S3_ACCESS_KEY").unwrap_or_default();
-            let secret_key = std::env::var("S3_SECRET_KEY").unwrap_or_default();
-
-            let manager =
-                operations::PackageManager::new(&endpoint, &access_key, &secret_key, &bucket)?;
-
-            // 解析包名和版本
-            let (name, version) = match package.split_once('@') {
-                Some((n, v)) => (n, v),
-                None => return Err("Invalid package format, expected name@version".into()),
+        cli::Commands::Backup {
+            action: Some(action),
+            ..
+        } => {
+            let manager = manager_from_env()?;
+            match action {
+                cli::BackupCommands::Schedule { cron, filter } => {
+                    manager.add_backup_schedule(&cron, &filter).await?;
+                    println!("Scheduled backups of {} at {:?} (UTC)", filter, cron);
+                }
+                cli::BackupCommands::Schedules => {
+                    let schedules = manager.list_backup_schedules().await?;
+                    if schedules.is_empty() {
+                        println!("No backup schedules");
+                    }
+                    for (index, schedule) in schedules.iter().enumerate() {
+                        println!("#{} {:?} {}", index + 1, schedule.cron, schedule.filter);
+                        match &schedule.last_run {
+                            Some(run) => println!(
+                                "    last run {}: {} backed up, {} failed",
+                                run.started_at,
+                                run.backed_up.len(),
+                                run.failed.len()
+                            ),
+                            None => println!("    never run"),
+                        }
+                    }
+                }
+                cli::BackupCommands::Unschedule { index } => {
+                    manager.remove_backup_schedule(index).await?;
+                    println!("Backup schedule #{} removed", index);
+                }
+                cli::BackupCommands::RunDue => {
+                    let runs = manager.run_due_backups().await?;
+                    if runs.is_empty() {
+                        println!("No backup schedules are due");
+                    }
+                    let mut failed = false;
+                    for (index, run) in &runs {
+                        println!("Schedule #{}:", index);
+                        for package in &run.backed_up {
+                            println!("  backed up {}", package);
+                        }
+                        for failure in &run.failed {
+                            println!("  FAILED {}", failure);
+                        }
+                        failed |= !run.failed.is_empty();
+                    }
+                    // 部分失败时以非零状态退出，便于 CronJob 报警
+                    if failed {
+                        return Err("Some scheduled backups failed".into());
+                    }
+                }
+                cli::BackupCommands::Prune {
+                    keep_last,
+                    keep_days,
+                    dry_run,
+                } => {
+                    let pruned = manager.prune_backups(keep_last, keep_days, dry_run).await?;
+                    for backup in &pruned {
+                        let action = if dry_run { "Would delete" } else { "Deleted" };
+                        println!("{} {} ({})", action, backup.backup_path, backup.timestamp);
+                    }
+                    if pruned.is_empty() {
+                        println!("No backups to prune");
+                    } else if !dry_run {
+                        println!("Pruned {} backups", pruned.len());
+                    }
+                }
+            }
+        }
+        cli::Commands::Backup {
+            package, reason, ..
+        } => {
+            let (Some(package), Some(reason)) = (package, reason) else {
+                return Err("Specify a package and --reason".into());
             };
+            let manager = manager_from_env()?;
+            let (name, version) = parse_package_spec(&package)?;
 
             manager.backup_package(name, version, &reason).await?;
             println!("Package {}@{} has been backed up", name, version);
         }
-        cli::Commands::Restore { package, timestamp } => {
-            let endpoint = std::env::var("S3_ENDPOINT")?;
-            let bucket = std::env::var("S3_BUCKET").unwrap_or_else(|_| "packages".to_string());
-
-            // 尝试从环境变量中读取凭证
-            let access_key = std::env::var("S3_ACCESS_KEY").unwrap_or_default();
-            let secret_key = std::env::var("S3_SECRET_KEY").unwrap_or_default();
-
-            let manager =
-                operations::PackageManager::new(&endpoint, &access_key, &secret_key, &bucket)?;
-
-            // 解析包名和版本
-            let (name, version) = match package.split_once('@') {
-                Some((n, v)) => (n, v),
-                None => return Err("Invalid package format, expected name@version".into()),
+        cli::Commands::Backups { package } => {
+            let manager = manager_from_env()?;
+            let (name, version) = match package.as_deref() {
+                Some(spec) => match models::split_package_spec(spec) {
+                    Some((name, version)) => (Some(name), Some(version)),
+                    None => (Some(spec), None),
+                },
+                None => (None, None),
             };
 
+            let backups = manager.list_backups(name, version).await?;
+            if backups.is_empty() {
+                println!("No backups found");
+            }
+            for backup in &backups {
+                let size = backup
+                    .size
+                    .map_or_else(|| "missing".to_string(), format_size);
+                let location = backup
+                    .bucket
+                    .as_ref()
+                    .map(|bucket| format!("  [{}]", bucket))
+                    .unwrap_or_default();
+                println!(
+                    "{}@{}  {}  {}  {}{}",
+                    backup.package, backup.version, backup.timestamp, size, backup.reason, location
+                );
+            }
+        }
+        cli::Commands::Restore { package, timestamp } => {
+            let manager = manager_from_env()?;
+            let (name, version) = parse_package_spec(&package)?;
+
             manager
                 .restore_package_from_backup(name, version, timestamp.as_deref())
                 .await?;
             println!("Package {}@{} has been restored from backup", name, version);
         }
+        cli::Commands::Secret { action } => match action {
+            cli::SecretCommands::Store => {
+                let secret = rpassword::prompt_password("Package passphrase: ")?;
+                if secret.is_empty() {
+                    return Err("Passphrase must not be empty".into());
+                }
+                if rpassword::prompt_password("Confirm passphrase: ")? != secret {
+                    return Err("Passphrases do not match".into());
+                }
+                security::store_keyring_secret(&secret)?;
+                println!(
+                    "Passphrase stored in the OS keyring, set BEEPKG_SECRET_SOURCE=keyring to use it"
+                );
+            }
+            cli::SecretCommands::Delete => {
+                security::delete_keyring_secret()?;
+                println!("Passphrase removed from the OS keyring");
+            }
+        },
+        cli::Commands::Login { token, endpoint } => {
+            let endpoint = registry_endpoint(endpoint)?;
+            let token = match token {
+                Some(token) => token,
+                None => rpassword::prompt_password("Token: ")?,
+            };
+            if token.trim().is_empty() {
+                return Err("Token must not be empty".into());
+            }
+
+            let path = auth::credentials_path()?;
+            let mut store = auth::TokenStore::load(&path)?;
+            store.insert(&endpoint, &token);
+            store.save(&path)?;
+            println!(
+                "Token for {} saved to {}",
+                auth::registry_key(&endpoint),
+                path.display()
+            );
+        }
+        cli::Commands::Logout { endpoint } => {
+            let endpoint = registry_endpoint(endpoint)?;
+            let path = auth::credentials_path()?;
+            let mut store = auth::TokenStore::load(&path)?;
+            if store.remove(&endpoint) {
+                store.save(&path)?;
+                println!("Token for {} removed", auth::registry_key(&endpoint));
+            } else {
+                println!("No token stored for {}", auth::registry_key(&endpoint));
+            }
+        }
+        cli::Commands::Share { package, expires } => {
+            let manager = manager_from_env()?;
+            let (archive, checksum) = manager
+                .share_package(&package, parse_duration(&expires)?)
+                .await?;
+            println!("Package:  {}", archive);
+            println!("Checksum: {}", checksum);
+            println!("URLs expire in {}", expires);
+        }
+        cli::Commands::Index { action } => match action {
+            cli::IndexCommands::Generate {
+                output,
+                presign,
+                metadata,
+            } => {
+                let manager = manager_from_env()?;
+                let expires = presign.as_deref().map(parse_duration).transpose()?;
+                let index = manager.site_index(expires, metadata).await?;
+                let files = site::write(&index, Path::new(&output))?;
+                println!(
+                    "Wrote {} files for {} packages to {}",
+                    files,
+                    index.packages.len(),
+                    output
+                );
+                if let Some(presign) = presign {
+                    println!("Download links expire in {}", presign);
+                }
+            }
+        },
         cli::Commands::Encrypt {
             package,
             enable,
             algorithm,
+            recipients,
+            kms,
+            secret_source,
+            files,
+            kdf_memory,
+            kdf_iterations,
+            kdf_parallelism,
         } => {
             let package_path = Path::new(&package);
             let toml_path = package_path.join("pack.toml");
 
             // 读取pack.toml
             let toml_content = std::fs::read_to_string(&toml_path)?;
-            let mut metadata: models::PackageMetadata = toml::from_str(&toml_content)?;
+            let mut metadata =
+                models::PackageMetadata::parse(&toml_content, models::MetadataFormat::Toml)?;
+
+            // 选择性加密的匹配规则在推送前校验
+            if !files.is_empty() {
+                FileSelector::new(&files)?;
+            }
 
             // 更新加密配置
-            if enable {
-                // 检查环境变量是否设置
-                if std::env::var("BEEPKG_USER_SECRET").is_err() {
+            if enable && !recipients.is_empty() {
+                // 按接收者公钥加密，不需要共享密码
+                SecurityManager::validate_recipients(&recipients)?;
+                println!(
+                    "Encryption enabled for package ({} age recipients)",
+                    recipients.len()
+                );
+                metadata.encryption = Some(models::EncryptionConfig {
+                    algorithm: Some("age".to_string()),
+                    encrypted_password: None,
+                    salt: None,
+                    enabled: true,
+                    recipients,
+                    kms: None,
+                    secret_source: None,
+                    kdf: None,
+                    files,
+                });
+            } else if let Some(kms) = kms.filter(|_| enable) {
+                let kms = parse_kms(&kms)?;
+                println!("Encryption enabled for package (KMS envelope encryption)");
+                metadata.encryption = Some(models::EncryptionConfig {
+                    algorithm: Some(algorithm),
+                    encrypted_password: None,
+                    salt: None,
+                    enabled: true,
+                    recipients: Vec::new(),
+                    kms: Some(kms),
+                    secret_source: None,
+                    kdf: None,
+                    files,
+                });
+            } else if enable {
+                // 口令来自环境变量时检查其是否设置
+                let source = match secret_source {
+                    Some(source) => source,
+                    None => SecretSource::from_env()?,
+                };
+                if source == SecretSource::Env && std::env::var("BEEPKG_USER_SECRET").is_err() {
                     return Err("BEEPKG_USER_SECRET environment variable is not set".into());
                 }
 
-                // 生成加密密码
-                let _security = SecurityManager::new();
-                let test_data = b"test";
-                let (encrypted_password, salt) = SecurityManager::encrypt_data(test_data)?;
+                // 未指定的参数沿用已有配置或默认值，记录在 pack.toml 中
+                let current = metadata
+                    .encryption
+                    .as_ref()
+                    .and_then(|e| e.kdf)
+                    .unwrap_or_default();
+                let kdf = KdfParams {
+                    memory_kib: kdf_memory.unwrap_or(current.memory_kib),
+                    iterations: kdf_iterations.unwrap_or(current.iterations),
+                    parallelism: kdf_parallelism.unwrap_or(current.parallelism),
+                    ..current
+                };
+                kdf.validate()?;
 
+                // 盐值和 nonce 在每次推送时随机生成，并与密文一起保存
                 metadata.encryption = Some(models::EncryptionConfig {
                     algorithm: Some(algorithm),
-                    encrypted_password: Some(encrypted_password),
-                    salt: Some(salt),
+                    encrypted_password: None,
+                    salt: None,
                     enabled: true,
+                    recipients: Vec::new(),
+                    kms: None,
+                    secret_source,
+                    kdf: Some(kdf),
+                    files,
                 });
 
                 println!("Encryption enabled for package");
@@ -262,7 +2347,440 @@ async fn main() -> Result<()> {
 
             println!("Package encryption configuration updated");
         }
+        cli::Commands::Cache { action } => {
+            let cache = Cache::from_env()?;
+            match action {
+                cli::CacheCommands::Info => {
+                    println!("Cache directory: {}", cache.root().display());
+                    println!("Size: {}", format_size(cache.size()?));
+                }
+                cli::CacheCommands::Clean { max_size } => {
+                    let max_size = match max_size {
+                        Some(size) => parse_size(&size)?,
+                        None => 0,
+                    };
+                    let report = cache.clean(max_size)?;
+                    println!(
+                        "Removed {} cached packages ({}), {} remaining",
+                        report.removed,
+                        format_size(report.freed),
+                        format_size(report.remaining)
+                    );
+                }
+            }
+        }
+        cli::Commands::Completions { shell } => {
+            print!("{}", completion::script(shell));
+        }
+        cli::Commands::Complete { action } => match action {
+            cli::CompleteCommands::Commands => {
+                for command in cli::Cli::command().get_subcommands() {
+                    if !command.is_hide_set() {
+                        println!("{}", command.get_name());
+                    }
+                }
+            }
+            cli::CompleteCommands::Packages { prefix } => {
+                // 补全只读本地缓存，出错时不输出候选项
+                let mut seen = std::collections::HashSet::new();
+                for (_, manager) in pull_registries(None).unwrap_or_default() {
+                    let manager = manager.cache(Cache::from_env().ok());
+                    for candidate in manager.complete_packages(&prefix) {
+                        if seen.insert(candidate.clone()) {
+                            println!("{}", candidate);
+                        }
+                    }
+                }
+            }
+        },
+        cli::Commands::Proxy {
+            listen,
+            registry,
+            dashboard,
+            token,
+        } => {
+            let (name, manager) = pull_registries(registry)?
+                .into_iter()
+                .next()
+                .ok_or("No registries configured")?;
+            let token = token.or_else(|| std::env::var("BEEPKG_PROXY_TOKEN").ok());
+            if token.is_none() {
+                println!(
+                    "Warning: no token set, any client that can connect can read public packages"
+                );
+            }
+            let cache = Cache::from_env()?;
+            println!("Caching packages in {}", cache.root().display());
+            let manager = manager.cache(Some(cache));
+
+            let listener = tokio::net::TcpListener::bind(&listen).await?;
+            let addr = listener.local_addr()?;
+            println!("Proxying {} on http://{}", name, addr);
+            if dashboard {
+                println!("Dashboard on http://{}/-/", addr);
+            }
+            let options = proxy::Options { dashboard, token };
+            proxy::serve(Arc::new(manager), listener, options).await?;
+        }
+        cli::Commands::Grpc {
+            listen,
+            registry,
+            token,
+        } => {
+            let (name, manager) = pull_registries(registry)?
+                .into_iter()
+                .next()
+                .ok_or("No registries configured")?;
+            let token = token.or_else(|| std::env::var("BEEPKG_GRPC_TOKEN").ok());
+            if token.is_none() {
+                println!("Warning: no token set, any client that can connect has full access");
+            }
+
+            let listener = tokio::net::TcpListener::bind(&listen).await?;
+            println!("Serving {} over gRPC on {}", name, listener.local_addr()?);
+            grpc::serve(Arc::new(manager), listener, grpc::Options { token }).await?;
+        }
+        cli::Commands::Daemon {
+            socket,
+            registry,
+            action,
+        } => {
+            #[cfg(unix)]
+            {
+                use beepkg::daemon;
+                let socket = socket.map_or_else(daemon::socket_path, PathBuf::from);
+                match action {
+                    Some(cli::DaemonCommands::Call { method, params }) => {
+                        let params = match params {
+                            Some(params) => serde_json::from_str(&params)?,
+                            None => serde_json::Value::Null,
+                        };
+                        let mut client = daemon::Client::connect(&socket).await?;
+                        let result = client.call(&method, params).await?;
+                        println!("{}", serde_json::to_string_pretty(&result)?);
+                    }
+                    None => {
+                        let (name, manager) = pull_registries(registry)?
+                            .into_iter()
+                            .next()
+                            .ok_or("No registries configured")?;
+                        let manager = Arc::new(manager.cache(Cache::from_env().ok()));
+                        let listener = daemon::bind(&socket).await?;
+                        println!("Serving {} on {}", name, socket.display());
+                        let options = daemon::Options {
+                            cache: Cache::from_env().ok(),
+                        };
+                        let result = tokio::select! {
+                            result = daemon::serve(manager, listener, options) => result,
+                            _ = tokio::signal::ctrl_c() => Ok(()),
+                        };
+                        std::fs::remove_file(&socket)?;
+                        result?;
+                    }
+                }
+            }
+            #[cfg(not(unix))]
+            {
+                let _ = (socket, registry, action);
+                return Err("beepkg daemon requires Unix domain sockets".into());
+            }
+        }
+        cli::Commands::Oci { registry, action } => {
+            let registry = match registry {
+                Some(url) => OciRegistry::new(&url)?.env_credentials(),
+                None => OciRegistry::from_env()?,
+            };
+            match action {
+                cli::OciCommands::Push { path, force } => {
+                    let package = registry.push(Path::new(&path), force).await?;
+                    println!(
+                        "Pushed {}@{} to {} ({})",
+                        package.name,
+                        package.version,
+                        registry.repository(&package.name),
+                        package.digest
+                    );
+                }
+                cli::OciCommands::Pull { package, output } => {
+                    let (name, version) = models::split_package_spec(&package)
+                        .ok_or("Invalid package format, expected name@version")?;
+                    let output = output.unwrap_or_else(|| {
+                        let name = name.trim_start_matches('@').replace('/', "-");
+                        format!("{}-{}", name, version)
+                    });
+                    let package = registry.install(name, version, Path::new(&output)).await?;
+                    println!(
+                        "Pulled {}@{} ({}) into {}",
+                        package.name, package.version, package.digest, output
+                    );
+                }
+                cli::OciCommands::List { name } => {
+                    let versions = registry.list_versions(&name).await?;
+                    if versions.is_empty() {
+                        println!("No versions of {} found", name);
+                    }
+                    for version in versions {
+                        println!("- {}", version);
+                    }
+                }
+            }
+        }
+        cli::Commands::Plugins => {
+            let plugins = plugins::list();
+            if plugins.is_empty() {
+                println!("No plugins found on PATH");
+            }
+            for plugin in plugins {
+                println!("- {}", plugin);
+            }
+        }
+        cli::Commands::External(args) => {
+            let (name, args) = args.split_first().ok_or("No command given")?;
+            let plugin = plugins::find(name).ok_or_else(|| {
+                format!(
+                    "Unknown command {} (no {}{} found on PATH)",
+                    name,
+                    plugins::PLUGIN_PREFIX,
+                    name
+                )
+            })?;
+            let context = plugins::PluginContext::from_env()?;
+            let code = plugins::run(&plugin, name, args, &context).await?;
+            if code != 0 {
+                std::process::exit(code);
+            }
+        }
     }
 
     Ok(())
 }
+
+/// 按列打印包，列宽取各列最长的值，大小以易读的单位显示；long 时上传时间显示为相对时间
+fn print_table(columns: &[Column], packages: &[models::Package], long: bool) {
+    let now = chrono::Utc::now();
+    let cell = |column: &Column, package: &models::Package| match column {
+        Column::Size => format_size(package.storage.size),
+        Column::Date if long => listing::relative_time(&package.storage.created_at, now),
+        column => column.value(package),
+    };
+    let rows: Vec<Vec<String>> = packages
+        .iter()
+        .map(|package| columns.iter().map(|column| cell(column, package)).collect())
+        .collect();
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .chain([column.title().len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let print_row = |values: Vec<String>| {
+        let line: Vec<String> = values
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{:<width$}", value, width = width))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    };
+    print_row(columns.iter().map(|c| c.title().to_string()).collect());
+    for row in rows {
+        print_row(row);
+    }
+}
+
+/// 根据环境变量创建 PackageManager
+fn manager_from_env() -> Result<operations::PackageManager> {
+    let endpoint = std::env::var("S3_ENDPOINT")?;
+    let bucket = std::env::var("S3_BUCKET").unwrap_or_else(|_| "packages".to_string());
+
+    // 尝试从环境变量中读取凭证
+    let access_key = std::env::var("S3_ACCESS_KEY").unwrap_or_default();
+    let secret_key = std::env::var("S3_SECRET_KEY").unwrap_or_default();
+
+    Ok(operations::PackageManager::builder()
+        .endpoint(&endpoint)
+        .bucket(bucket)
+        .credentials(&access_key, &secret_key)
+        .from_env()?
+        .build()?
+        .observer(Arc::new(PrintObserver))
+        .backup_store(backup_store_from_env(&endpoint, &access_key, &secret_key)?))
+}
+
+/// 配置了 BEEPKG_BACKUP_BUCKET 时备份存放到独立的 bucket，端点和凭证默认与注册表相同，
+/// 可通过 BEEPKG_BACKUP_ENDPOINT、BEEPKG_BACKUP_ACCESS_KEY、BEEPKG_BACKUP_SECRET_KEY 覆盖
+fn backup_store_from_env(
+    endpoint: &str,
+    access_key: &str,
+    secret_key: &str,
+) -> Result<Option<operations::PackageManager>> {
+    let Ok(bucket) = std::env::var("BEEPKG_BACKUP_BUCKET") else {
+        return Ok(None);
+    };
+    let endpoint = std::env::var("BEEPKG_BACKUP_ENDPOINT").unwrap_or_else(|_| endpoint.to_string());
+    let access_key =
+        std::env::var("BEEPKG_BACKUP_ACCESS_KEY").unwrap_or_else(|_| access_key.to_string());
+    let secret_key =
+        std::env::var("BEEPKG_BACKUP_SECRET_KEY").unwrap_or_else(|_| secret_key.to_string());
+    Ok(Some(
+        operations::PackageManager::builder()
+            .endpoint(endpoint)
+            .bucket(bucket)
+            .credentials(access_key, secret_key)
+            .from_env()?
+            .build()?
+            .observer(Arc::new(PrintObserver)),
+    ))
+}
+
+/// pull 依次尝试的注册表：指定名称时只使用该注册表，否则使用配置文件中的全部注册表，
+/// 未配置时使用 S3_ENDPOINT
+fn pull_registries(registry: Option<String>) -> Result<Vec<(String, operations::PackageManager)>> {
+    let config = Config::from_env()?;
+    if let Some(name) = registry {
+        let manager = printing(config.registry(&name)?.manager()?);
+        return Ok(vec![(name, manager)]);
+    }
+    if config.registries.is_empty() {
+        let endpoint = std::env::var("S3_ENDPOINT").unwrap_or_default();
+        return Ok(vec![(endpoint, manager_from_env()?)]);
+    }
+    config
+        .registries
+        .iter()
+        .map(|registry| Ok((registry.name.clone(), printing(registry.manager()?))))
+        .collect()
+}
+
+/// 命令行使用的 PackageManager 把事件打印到标准输出，库的默认观察者不输出
+fn printing(manager: operations::PackageManager) -> operations::PackageManager {
+    manager.observer(Arc::new(PrintObserver))
+}
+
+/// 登录使用的注册表地址：命令行参数 > S3_ENDPOINT
+fn registry_endpoint(endpoint: Option<String>) -> Result<String> {
+    match endpoint {
+        Some(endpoint) => Ok(endpoint),
+        None => {
+            std::env::var("S3_ENDPOINT").map_err(|_| "Pass --endpoint or set S3_ENDPOINT".into())
+        }
+    }
+}
+
+/// 安全公告索引在注册表中的位置：命令行参数 > BEEPKG_ADVISORY_FEED > 默认位置
+fn advisory_feed(feed: Option<String>) -> String {
+    feed.or_else(|| std::env::var("BEEPKG_ADVISORY_FEED").ok())
+        .unwrap_or_else(|| advisory::DEFAULT_FEED.to_string())
+}
+
+/// 解析 KMS 密钥：aws:<key-id> 或 vault:<transit-key>
+fn parse_kms(value: &str) -> Result<models::KmsConfig> {
+    match value.split_once(':') {
+        Some(("aws", key_id)) => Ok(models::KmsConfig::Aws {
+            key_id: key_id.to_string(),
+            region: None,
+        }),
+        Some(("vault", key_id)) => Ok(models::KmsConfig::Vault {
+            key_id: key_id.to_string(),
+            mount: "transit".to_string(),
+            address: None,
+        }),
+        _ => Err(format!(
+            "Invalid KMS key: {} (expected aws:<key-id> or vault:<transit-key>)",
+            value
+        )
+        .into()),
+    }
+}
+
+/// 解析 name@version 格式的包标识
+fn parse_package_spec(package: &str) -> Result<(&str, &str)> {
+    package
+        .split_once('@')
+        .ok_or_else(|| "Invalid package format, expected name@version".into())
+}
+
+/// 解析日期（YYYY-MM-DD）或 RFC 3339 时间戳
+/// 解析 30m / 24h / 7d 形式的时长
+fn parse_duration(value: &str) -> Result<Duration> {
+    let invalid = || format!("Invalid duration: {} (expected e.g. 30m, 24h or 7d)", value);
+    let split = value.len().saturating_sub(1);
+    let (amount, unit) = value.split_at_checked(split).ok_or_else(invalid)?;
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 24 * 3600,
+        _ => return Err(invalid().into()),
+    };
+    Ok(Duration::from_secs(amount.saturating_mul(seconds)))
+}
+
+/// 解析 500MB / 5GB 形式的大小，单位按 1024 进位，不带单位时为字节
+fn parse_size(value: &str) -> Result<u64> {
+    let invalid = || format!("Invalid size: {} (expected e.g. 500MB or 5GB)", value);
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        _ => return Err(invalid().into()),
+    };
+    Ok(amount.saturating_mul(multiplier))
+}
+
+/// 解析大小限制，0 或 none 表示不限制
+fn parse_limit(value: &str) -> Result<Option<u64>> {
+    if value.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    Ok(Some(parse_size(value)?).filter(|size| *size > 0))
+}
+
+/// 以合适的单位显示字节数
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+// 版本范围的端点，可以省略次版本号和修订号（1.0 即 1.0.0）
+fn parse_version_bound(value: &str) -> Result<semver::Version> {
+    let core = value.split(['-', '+']).next().unwrap_or_default();
+    let padding = ".0".repeat(3usize.saturating_sub(core.split('.').count()));
+    let version = format!("{}{}{}", core, padding, &value[core.len()..]);
+    Ok(
+        semver::Version::parse(&version)
+            .map_err(|e| format!("Invalid version {}: {}", value, e))?,
+    )
+}
+
+fn parse_since(value: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    if let Ok(ts) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(ts.with_timezone(&chrono::Utc));
+    }
+
+    let date = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date: {} (expected YYYY-MM-DD or RFC 3339)", value))?;
+    Ok(date.and_time(chrono::NaiveTime::MIN).and_utc())
+}
@@ -0,0 +1,153 @@
+use crate::Result;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+// 请求延迟直方图的桶上界（秒）
+const LATENCY_BUCKETS: [f64; 10] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 5.0, 30.0];
+
+/// 进程内的运行指标，以 Prometheus 文本格式导出
+#[derive(Default)]
+pub struct Metrics {
+    bytes_uploaded: AtomicU64,
+    bytes_downloaded: AtomicU64,
+    retries: AtomicU64,
+    // (method, outcome) -> 请求次数
+    requests: Mutex<BTreeMap<(String, &'static str), u64>>,
+    latency_buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+    latency_count: AtomicU64,
+    latency_sum_micros: AtomicU64,
+}
+
+/// 全局指标实例
+pub fn global() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::default)
+}
+
+impl Metrics {
+    /// 记录一次 HTTP 请求的结果和耗时
+    pub fn record_request(&self, method: &str, success: bool, elapsed: Duration) {
+        let outcome = if success { "success" } else { "error" };
+        *self
+            .requests
+            .lock()
+            .unwrap()
+            .entry((method.to_string(), outcome))
+            .or_default() += 1;
+
+        let seconds = elapsed.as_secs_f64();
+        for (bucket, bound) in self.latency_buckets.iter().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.latency_count.fetch_add(1, Ordering::Relaxed);
+        self.latency_sum_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_uploaded(&self, bytes: u64) {
+        self.bytes_uploaded.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn record_downloaded(&self, bytes: u64) {
+        self.bytes_downloaded.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn record_retry(&self) {
+        self.retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 以 Prometheus 文本格式渲染全部指标
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(out, "# TYPE beepkg_bytes_uploaded_total counter");
+        let _ = writeln!(
+            out,
+            "beepkg_bytes_uploaded_total {}",
+            self.bytes_uploaded.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(out, "# TYPE beepkg_bytes_downloaded_total counter");
+        let _ = writeln!(
+            out,
+            "beepkg_bytes_downloaded_total {}",
+            self.bytes_downloaded.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(out, "# TYPE beepkg_retries_total counter");
+        let _ = writeln!(
+            out,
+            "beepkg_retries_total {}",
+            self.retries.load(Ordering::Relaxed)
+        );
+
+        let _ = writeln!(out, "# TYPE beepkg_http_requests_total counter");
+        for ((method, outcome), count) in self.requests.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "beepkg_http_requests_total{{method=\"{}\",outcome=\"{}\"}} {}",
+                method, outcome, count
+            );
+        }
+
+        let _ = writeln!(out, "# TYPE beepkg_http_request_duration_seconds histogram");
+        for (bucket, bound) in self.latency_buckets.iter().zip(LATENCY_BUCKETS) {
+            let _ = writeln!(
+                out,
+                "beepkg_http_request_duration_seconds_bucket{{le=\"{}\"}} {}",
+                bound,
+                bucket.load(Ordering::Relaxed)
+            );
+        }
+        let count = self.latency_count.load(Ordering::Relaxed);
+        let _ = writeln!(
+            out,
+            "beepkg_http_request_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+            count
+        );
+        let _ = writeln!(
+            out,
+            "beepkg_http_request_duration_seconds_sum {}",
+            self.latency_sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0
+        );
+        let _ = writeln!(out, "beepkg_http_request_duration_seconds_count {}", count);
+
+        out
+    }
+}
+
+/// 在指定地址上启动 /metrics 端点（后台任务），供 Prometheus 抓取，返回实际监听的地址
+pub async fn serve(addr: &str) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr).await?;
+    let local_addr = listener.local_addr()?;
+
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                continue;
+            };
+            tokio::spawn(async move {
+                // 只读取请求头，任何路径都返回指标
+                let mut buf = [0u8; 1024];
+                if stream.read(&mut buf).await.is_err() {
+                    return;
+                }
+                let body = global().render();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            });
+        }
+    });
+
+    Ok(local_addr)
+}
@@ -0,0 +1,142 @@
+use crate::Result;
+use crate::events::Event;
+use crate::metrics;
+use crate::operations::{MirrorReport, PackageManager};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// 每轮同步的最多尝试次数，以及首次重试前的等待时间（之后每次翻倍）
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_BACKOFF: Duration = Duration::from_secs(5);
+
+/// `mirror watch` 的运行参数
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    /// 只复制包名匹配该 glob 的包
+    pub filter: Option<String>,
+    /// 每个下游注册表并行复制的包数量
+    pub jobs: usize,
+    /// 两轮轮询之间的间隔
+    pub interval: Duration,
+    /// 每轮结束后写入复制状态（JSON）的文件
+    pub status_file: Option<PathBuf>,
+}
+
+/// 下游注册表的复制状态
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetStatus {
+    pub registry: String,
+    /// 最近一次同步成功的时间
+    pub last_success: Option<String>,
+    /// 最近一次同步失败的错误，同步成功后清空
+    pub last_error: Option<String>,
+    /// 启动以来复制的包数量
+    pub copied: usize,
+    /// 最近一轮复制失败、等待下一轮重试的包
+    pub failing: Vec<String>,
+}
+
+/// 持续轮询源注册表，把新发布或更新的包复制到所有下游注册表，直到 stop 完成。
+/// 每轮按两边的对象列表比较差异（见 [`PackageManager::mirror_to`]），stop 只在两轮之间检查，
+/// 正在进行的一轮会先完成。单个下游注册表失败不影响其他注册表，失败的包在下一轮重新复制。
+/// 每轮的复制结果通过源注册表的 observer 发出
+pub async fn watch(
+    source: &PackageManager,
+    targets: &[(String, PackageManager)],
+    options: &WatchOptions,
+    stop: impl Future<Output = ()>,
+) -> Result<()> {
+    tokio::pin!(stop);
+    let mut statuses: Vec<TargetStatus> = targets
+        .iter()
+        .map(|(registry, _)| TargetStatus {
+            registry: registry.clone(),
+            last_success: None,
+            last_error: None,
+            copied: 0,
+            failing: Vec::new(),
+        })
+        .collect();
+
+    loop {
+        for ((registry, target), status) in targets.iter().zip(&mut statuses) {
+            let now = chrono::Utc::now().to_rfc3339();
+            match sync_with_retry(source, target, registry, options).await {
+                Ok(report) => {
+                    for archive in &report.copied {
+                        source.emit(Event::Info(format!(
+                            "[{}] {}: copied {}",
+                            now, registry, archive
+                        )));
+                    }
+                    for (archive, error) in &report.failed {
+                        source.emit(Event::Warning(format!(
+                            "[{}] {}: failed to copy {}: {}",
+                            now, registry, archive, error
+                        )));
+                    }
+                    status.copied += report.copied.len();
+                    status.failing = report
+                        .failed
+                        .into_iter()
+                        .map(|(archive, _)| archive)
+                        .collect();
+                    status.last_success = Some(now);
+                    status.last_error = None;
+                }
+                Err(e) => {
+                    source.emit(Event::Warning(format!(
+                        "[{}] {}: mirroring failed: {}",
+                        now, registry, e
+                    )));
+                    status.last_error = Some(e.to_string());
+                }
+            }
+        }
+
+        if let Some(path) = &options.status_file {
+            std::fs::write(path, serde_json::to_string_pretty(&statuses)?)?;
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(options.interval) => {}
+            _ = &mut stop => return Ok(()),
+        }
+    }
+}
+
+// 同步一个下游注册表，列举或元数据请求失败时按指数退避重试
+async fn sync_with_retry(
+    source: &PackageManager,
+    target: &PackageManager,
+    registry: &str,
+    options: &WatchOptions,
+) -> Result<MirrorReport> {
+    let mut last_error = None;
+
+    for attempt in 0..MAX_ATTEMPTS {
+        if attempt > 0 {
+            metrics::global().record_retry();
+            tokio::time::sleep(RETRY_BACKOFF * (1 << (attempt - 1))).await;
+        }
+
+        match source
+            .mirror_to(target, options.filter.as_deref(), options.jobs, false)
+            .await
+        {
+            Ok(report) => return Ok(report),
+            Err(e) => {
+                source.emit(Event::Warning(format!(
+                    "Mirroring to {} failed (attempt {}/{}): {}",
+                    registry,
+                    attempt + 1,
+                    MAX_ATTEMPTS,
+                    e
+                )));
+                last_error = Some(e);
+            }
+        }
+    }
+
+    Err(last_error.map_or_else(|| "Mirroring was not attempted".into(), Into::into))
+}
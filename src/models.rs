@@ -1,18 +1,110 @@
+use crate::schema::{self, MigratedDocument, Migration, Versioned};
+use crate::security::{KdfParams, SecretSource};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EncryptionConfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub algorithm: Option<String>,
+    /// 旧版本写入的字段，已不再使用，仅为兼容旧的 pack.toml 保留
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub encrypted_password: Option<String>,
+    /// 旧版本写入的字段，盐值现在随密文一起保存
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub salt: Option<String>,
+    /// age 接收者公钥，设置后按公钥加密，使用者用自己的私钥解密
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipients: Vec<String>,
+    /// 信封加密：每个包生成随机数据密钥，由 KMS 封装后随密文保存
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kms: Option<KmsConfig>,
+    /// 推送时读取口令的来源（env/keyring/prompt），未设置时由 BEEPKG_SECRET_SOURCE 决定
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_source: Option<SecretSource>,
+    /// 口令加密的 Argon2id 参数，未设置时使用默认参数；实际使用的参数同时写入密文头
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kdf: Option<KdfParams>,
+    /// 只加密包内匹配这些 glob 的文件（例如 "secrets/**"），其余文件保持明文；为空时加密整个包
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
     #[serde(default)]
     pub enabled: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "provider", rename_all = "kebab-case")]
+pub enum KmsConfig {
+    /// AWS KMS，凭证来自 AWS_ACCESS_KEY_ID / AWS_SECRET_ACCESS_KEY / AWS_SESSION_TOKEN
+    Aws {
+        key_id: String,
+        /// 未设置时使用 AWS_REGION 或 AWS_DEFAULT_REGION
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        region: Option<String>,
+    },
+    /// Vault Transit 引擎，令牌来自 VAULT_TOKEN
+    Vault {
+        key_id: String,
+        #[serde(default = "default_transit_mount")]
+        mount: String,
+        /// 未设置时使用 VAULT_ADDR
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        address: Option<String>,
+    },
+}
+
+fn default_transit_mount() -> String {
+    "transit".to_string()
+}
+
+/// 上传时要求存储层执行的服务端加密
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerSideEncryption {
+    /// SSE-S3（AES256）
+    S3,
+    /// SSE-KMS，未指定密钥时使用存储桶默认的 KMS 密钥
+    Kms { key_id: Option<String> },
+}
+
+impl ServerSideEncryption {
+    /// 从 BEEPKG_SSE（AES256 或 aws:kms）和 BEEPKG_SSE_KMS_KEY_ID 读取配置
+    pub fn from_env() -> crate::Result<Option<Self>> {
+        let Ok(mode) = std::env::var("BEEPKG_SSE") else {
+            return Ok(None);
+        };
+        let key_id = std::env::var("BEEPKG_SSE_KMS_KEY_ID").ok();
+        match mode.as_str() {
+            "AES256" | "aes256" | "s3" => Ok(Some(ServerSideEncryption::S3)),
+            "aws:kms" | "kms" => Ok(Some(ServerSideEncryption::Kms { key_id })),
+            other => Err(format!(
+                "Unknown server-side encryption mode: {} (expected AES256 or aws:kms)",
+                other
+            )
+            .into()),
+        }
+    }
+
+    /// PUT 请求需要携带的请求头
+    pub fn headers(&self) -> Vec<(&'static str, String)> {
+        match self {
+            ServerSideEncryption::S3 => {
+                vec![("x-amz-server-side-encryption", "AES256".to_string())]
+            }
+            ServerSideEncryption::Kms { key_id } => {
+                let mut headers = vec![("x-amz-server-side-encryption", "aws:kms".to_string())];
+                if let Some(key_id) = key_id {
+                    headers.push((
+                        "x-amz-server-side-encryption-aws-kms-key-id",
+                        key_id.clone(),
+                    ));
+                }
+                headers
+            }
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Project {
     pub schema_version: String,
@@ -27,12 +119,132 @@ pub struct Package {
     pub encryption: Option<EncryptionConfig>,
     pub author: String,
     pub description: String,
+    /// SPDX 许可证表达式
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    /// 包内 README 的路径
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub readme: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub maintainers: Vec<String>,
     pub dependencies: HashMap<String, String>,
     pub storage: Storage,
+    /// 主包之外的平台产物和命名产物
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<Artifact>,
     #[serde(default)]
     pub is_locked: bool,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub lock_reason: Option<String>,
+    /// 指向该版本的发布频道
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub channels: Vec<String>,
+    /// 发布后附加的标签，例如 qa=passed
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+}
+
+/// 推送时随包上传的元数据摘要（<归档>.meta.json），查看包信息时不必下载整个包
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageInfo {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub name: String,
+    pub version: String,
+    pub author: String,
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub readme: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub maintainers: Vec<String>,
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release_notes: Option<String>,
+    /// `beepkg annotate` 附加的标签
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+}
+
+impl Versioned for PackageInfo {
+    const DOCUMENT: &'static str = "package info";
+    const VERSION: u32 = 1;
+    const MIGRATIONS: &'static [Migration] = &[schema::unversioned];
+}
+
+impl PackageInfo {
+    /// 名称、描述或关键字是否包含 query（不区分大小写）
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        self.name.to_lowercase().contains(&query)
+            || self.description.to_lowercase().contains(&query)
+            || self
+                .keywords
+                .iter()
+                .any(|keyword| keyword.to_lowercase().contains(&query))
+    }
+}
+
+impl From<&PackageMetadata> for PackageInfo {
+    fn from(metadata: &PackageMetadata) -> Self {
+        PackageInfo {
+            schema_version: PackageInfo::VERSION,
+            name: metadata.name.clone(),
+            version: metadata.version.clone(),
+            author: metadata.author.clone(),
+            description: metadata.description.clone(),
+            license: metadata.license.clone(),
+            homepage: metadata.homepage.clone(),
+            repository: metadata.repository.clone(),
+            keywords: metadata.keywords.clone(),
+            readme: metadata.readme.clone(),
+            maintainers: metadata.maintainers.clone(),
+            dependencies: metadata.dependencies.clone().into_iter().collect(),
+            release_notes: metadata.release.notes.clone(),
+            labels: BTreeMap::new(),
+        }
+    }
+}
+
+/// 附加在包版本上的产物
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Artifact {
+    /// 产物名（平台名或 docs 等）
+    pub name: String,
+    /// 存储文件名，例如 docs.zip
+    pub file_name: String,
+    /// 对象键
+    pub key: String,
+    pub size: u64,
+}
+
+/// 用 HEAD 请求读取的包文件信息，不下载内容
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageStat {
+    pub name: String,
+    pub version: String,
+    /// 对象键
+    pub key: String,
+    /// 包内容的字节数，内容寻址布局下为指针指向的 blob 的大小
+    pub size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -46,15 +258,409 @@ pub struct Storage {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PackageMetadata {
+    /// 元数据的格式版本，省略时视为最早的版本，读取时升级到当前版本
+    #[serde(default)]
+    pub schema_version: u32,
     pub name: String,
     pub version: String,
     pub author: String,
     pub description: String,
+    /// SPDX 许可证表达式，例如 MIT 或 "MIT OR Apache-2.0"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// 项目主页
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    /// 源码仓库地址
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+    /// 搜索用的关键字
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    /// 包目录中 README 的相对路径，例如 README.md
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub readme: Option<String>,
+    /// 维护者，例如 "Alice <alice@example.com>"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub maintainers: Vec<String>,
     pub includes: Vec<String>,
     pub excludes: Vec<String>,
     pub dependencies: HashMap<String, String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub encryption: Option<EncryptionConfig>,
+    /// 包的可见性（public/private/team:<name>），推送时记录到注册表元数据
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<Visibility>,
+    /// 各平台的产物（linux-x86_64、windows-x86_64、noarch 等），拉取时按主机平台选择
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub targets: BTreeMap<String, TargetConfig>,
+    /// 随版本发布的命名产物：产物名 -> 包目录中的文件（例如 docs = "dist/docs.zip"），
+    /// 不放入主包，拉取时用 --artifact 单独下载
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub artifacts: BTreeMap<String, String>,
+    /// 可选依赖，只在启用的特性引用时安装
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub optional_dependencies: HashMap<String, String>,
+    /// 特性：特性名 -> 启用的其他特性或可选依赖，default 特性默认启用
+    ///
+    /// ```toml
+    /// [optional_dependencies]
+    /// cuda-runtime = "^12.0"
+    ///
+    /// [features]
+    /// default = ["cli"]
+    /// cli = []
+    /// gpu = ["cuda-runtime"]
+    /// ```
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub features: BTreeMap<String, Vec<String>>,
+    /// 推送前和拉取后运行的脚本，只在传入 --allow-hooks 时执行
+    #[serde(default, skip_serializing_if = "HooksConfig::is_empty")]
+    pub hooks: HooksConfig,
+    /// 版本的发布说明，推送时记录到元数据摘要
+    #[serde(default, skip_serializing_if = "ReleaseConfig::is_empty")]
+    pub release: ReleaseConfig,
+}
+
+/// 版本的发布信息，推送时可以用 --notes-file 代替
+///
+/// ```toml
+/// [release]
+/// notes = """
+/// - 新增 --json 输出
+/// - 修复 Windows 下的路径问题
+/// """
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReleaseConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+impl ReleaseConfig {
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_none()
+    }
+}
+
+/// 包的钩子脚本，路径相对于包目录
+///
+/// ```toml
+/// [hooks]
+/// pre_push = "scripts/build.sh"
+/// post_pull = "scripts/setup.sh"
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HooksConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_push: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_pull: Option<String>,
+}
+
+impl HooksConfig {
+    pub fn is_empty(&self) -> bool {
+        self.pre_push.is_none() && self.post_pull.is_none()
+    }
+}
+
+/// 默认启用的特性名
+pub const DEFAULT_FEATURE: &str = "default";
+
+/// vendor 目录中的清单文件名
+pub const VENDOR_MANIFEST: &str = "vendor.json";
+
+/// `beepkg vendor` 写入的清单，记录依赖闭包中每个包的版本和校验和
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VendorManifest {
+    pub generated_at: String,
+    /// 依赖所属的包（name@version）
+    pub package: String,
+    pub packages: Vec<VendoredPackage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VendoredPackage {
+    pub name: String,
+    pub version: String,
+    /// 包文件的校验和（`algo:hex`）
+    pub checksum: String,
+    /// 包解压到的目录，相对于 vendor 目录
+    pub path: String,
+}
+
+impl VendoredPackage {
+    /// 按内容摘要固定的包标识（name@sha256:<hex>），强制推送替换内容后无法再拉取
+    pub fn pinned_spec(&self) -> String {
+        format!("{}@{}", self.name, self.checksum)
+    }
+}
+
+/// 某个平台的产物包含的文件
+///
+/// ```toml
+/// [targets.linux-x86_64]
+/// includes = ["bin/linux-x86_64/**"]
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TargetConfig {
+    /// 相对于包目录的 glob，匹配的文件不放入主包，而是放入该平台的产物
+    #[serde(default)]
+    pub includes: Vec<String>,
+}
+
+impl Versioned for PackageMetadata {
+    const DOCUMENT: &'static str = "package metadata";
+    const VERSION: u32 = 1;
+    const MIGRATIONS: &'static [Migration] = &[schema::unversioned];
+}
+
+impl PackageMetadata {
+    /// 解析 pack.toml 或 pack.json 的内容，旧格式的元数据升级到当前版本
+    pub fn parse(content: &str, format: MetadataFormat) -> crate::Result<Self> {
+        Ok(match format {
+            MetadataFormat::Toml => schema::from_toml(content)?,
+            MetadataFormat::Json => schema::from_json(content.as_bytes())?,
+        })
+    }
+
+    /// 从包目录读取元数据，先检查 pack.toml，然后是 pack.json
+    pub fn load(package_dir: &Path) -> crate::Result<Self> {
+        let (path, format) = Self::find(package_dir)?;
+        Self::parse(&std::fs::read_to_string(&path)?, format)
+    }
+
+    /// 从包文件根目录的 pack.toml 或 pack.json 读取元数据
+    pub fn from_archive<R: std::io::Read + std::io::Seek>(
+        zip: &mut zip::ZipArchive<R>,
+    ) -> crate::Result<Self> {
+        for format in [MetadataFormat::Toml, MetadataFormat::Json] {
+            let Ok(mut file) = zip.by_name(format.file_name()) else {
+                continue;
+            };
+            let mut content = String::new();
+            std::io::Read::read_to_string(&mut file, &mut content)?;
+            return Self::parse(&content, format);
+        }
+        Err("Neither pack.toml nor pack.json found at the root of the archive".into())
+    }
+
+    // 包目录中的元数据文件，优先 pack.toml
+    fn find(package_dir: &Path) -> crate::Result<(PathBuf, MetadataFormat)> {
+        [MetadataFormat::Toml, MetadataFormat::Json]
+            .into_iter()
+            .map(|format| (package_dir.join(format.file_name()), format))
+            .find(|(path, _)| path.exists())
+            .ok_or_else(|| {
+                format!(
+                    "Neither pack.toml nor pack.json found in {}",
+                    package_dir.display()
+                )
+                .into()
+            })
+    }
+
+    /// 把包目录中的元数据文件改写为当前格式版本，已是当前版本时返回 None。
+    /// 改写后的文件不保留注释
+    pub fn migrate(package_dir: &Path, dry_run: bool) -> crate::Result<Option<MigratedDocument>> {
+        let (path, format) = Self::find(package_dir)?;
+        let content = std::fs::read_to_string(&path)?;
+        let from = match format {
+            MetadataFormat::Toml => schema::version_of(&toml::from_str(&content)?),
+            MetadataFormat::Json => schema::version_of(&serde_json::from_str(&content)?),
+        };
+        let metadata = Self::parse(&content, format)?;
+        if from == Self::VERSION {
+            return Ok(None);
+        }
+        if !dry_run {
+            metadata.write(&path, format)?;
+        }
+        Ok(Some(MigratedDocument {
+            key: path.display().to_string(),
+            from,
+            to: Self::VERSION,
+        }))
+    }
+
+    fn write(&self, path: &Path, format: MetadataFormat) -> crate::Result<()> {
+        let content = match format {
+            MetadataFormat::Toml => toml::to_string_pretty(self)?,
+            MetadataFormat::Json => serde_json::to_string_pretty(self)? + "\n",
+        };
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// 检查元数据：包名、semver 版本号以及依赖的版本需求
+    pub fn validate(&self) -> crate::Result<()> {
+        validate_package_name(&self.name)?;
+        semver::Version::parse(&self.version)
+            .map_err(|e| format!("Invalid version {}: {}", self.version, e))?;
+        for (name, requirement) in &self.dependencies {
+            if semver::VersionReq::parse(requirement).is_err()
+                && semver::Version::parse(requirement).is_err()
+            {
+                return Err(format!(
+                    "Invalid version requirement for dependency {}: {}",
+                    name, requirement
+                )
+                .into());
+            }
+        }
+        for (name, requirement) in &self.optional_dependencies {
+            if self.dependencies.contains_key(name) {
+                return Err(format!("Dependency {} is both required and optional", name).into());
+            }
+            if semver::VersionReq::parse(requirement).is_err()
+                && semver::Version::parse(requirement).is_err()
+            {
+                return Err(format!(
+                    "Invalid version requirement for optional dependency {}: {}",
+                    name, requirement
+                )
+                .into());
+            }
+        }
+        for (feature, enables) in &self.features {
+            for item in enables {
+                if !self.features.contains_key(item)
+                    && !self.optional_dependencies.contains_key(item)
+                {
+                    return Err(format!(
+                        "Feature {} enables {}, which is neither a feature nor an optional dependency",
+                        feature, item
+                    )
+                    .into());
+                }
+            }
+        }
+        for script in [&self.hooks.pre_push, &self.hooks.post_pull]
+            .into_iter()
+            .flatten()
+        {
+            crate::hooks::validate_script(script)?;
+        }
+        Ok(())
+    }
+
+    /// 按启用的特性计算需要安装的依赖：必需依赖加上特性（及其间接启用的特性）引用的可选依赖。
+    /// default_features 为 true 时同时启用 default 特性
+    pub fn active_dependencies(
+        &self,
+        features: &[String],
+        default_features: bool,
+    ) -> crate::Result<HashMap<String, String>> {
+        let mut pending: Vec<&str> = features.iter().map(String::as_str).collect();
+        if default_features && self.features.contains_key(DEFAULT_FEATURE) {
+            pending.push(DEFAULT_FEATURE);
+        }
+
+        let mut dependencies = self.dependencies.clone();
+        let mut enabled = HashSet::new();
+        while let Some(item) = pending.pop() {
+            if !enabled.insert(item) {
+                continue;
+            }
+            if let Some(requirement) = self.optional_dependencies.get(item) {
+                dependencies.insert(item.to_string(), requirement.clone());
+            } else if let Some(enables) = self.features.get(item) {
+                pending.extend(enables.iter().map(String::as_str));
+            } else {
+                return Err(format!("Package {} has no feature {}", self.name, item).into());
+            }
+        }
+        Ok(dependencies)
+    }
+
+    /// 在 pack.toml 和 pack.json 之间转换：读取另一种格式的文件，校验后写入目标格式，
+    /// keep_source 为 false 时删除原文件。返回写入的文件路径
+    pub fn convert(
+        package_dir: &Path,
+        to: MetadataFormat,
+        keep_source: bool,
+    ) -> crate::Result<PathBuf> {
+        let from = match to {
+            MetadataFormat::Toml => MetadataFormat::Json,
+            MetadataFormat::Json => MetadataFormat::Toml,
+        };
+        let source = package_dir.join(from.file_name());
+        let target = package_dir.join(to.file_name());
+        if !source.exists() {
+            return Err(format!(
+                "{} not found in {}",
+                from.file_name(),
+                package_dir.display()
+            )
+            .into());
+        }
+        if target.exists() {
+            return Err(format!("{} already exists, remove it first", target.display()).into());
+        }
+
+        let metadata = Self::parse(&std::fs::read_to_string(&source)?, from)?;
+        metadata
+            .validate()
+            .map_err(|e| format!("{}: {}", source.display(), e))?;
+        metadata.write(&target, to)?;
+        if !keep_source {
+            std::fs::remove_file(&source)?;
+        }
+        Ok(target)
+    }
+}
+
+/// 包元数据文件的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataFormat {
+    Toml,
+    Json,
+}
+
+impl MetadataFormat {
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            MetadataFormat::Toml => "pack.toml",
+            MetadataFormat::Json => "pack.json",
+        }
+    }
+}
+
+impl std::str::FromStr for MetadataFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "toml" => Ok(MetadataFormat::Toml),
+            "json" => Ok(MetadataFormat::Json),
+            other => Err(format!("Unknown metadata format: {}", other)),
+        }
+    }
+}
+
+/// 定时备份计划：到达 cron 时间后备份名称匹配 filter 的每个包的最新版本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupSchedule {
+    /// 五段式 cron 表达式，按 UTC 计算
+    pub cron: String,
+    /// 包名 glob
+    pub filter: String,
+    pub created_at: String,
+    /// 最近一次执行的结果
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<BackupRun>,
+}
+
+/// 定时备份的一次执行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupRun {
+    pub started_at: String,
+    /// 已备份的包（name@version）
+    #[serde(default)]
+    pub backed_up: Vec<String>,
+    /// 备份失败的包及错误信息
+    #[serde(default)]
+    pub failed: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -63,18 +669,579 @@ pub struct PackageBackup {
     pub backup_path: String,
     pub timestamp: String,
     pub reason: String,
+    /// 备份存放在独立的备份 bucket 时为该 bucket 的名称，未设置时与包在同一个 bucket 中
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bucket: Option<String>,
+}
+
+/// 注册表中包文件的存储布局
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageLayout {
+    /// 包文件直接存放在 name-version.zip
+    #[default]
+    Flat,
+    /// 内容寻址：包文件内容存放在 blobs/<algo>/<hex>，name-version.zip 是指向它的指针，
+    /// 相同的内容只保存一份
+    Cas,
+}
+
+impl StorageLayout {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StorageLayout::Flat => "flat",
+            StorageLayout::Cas => "cas",
+        }
+    }
+
+    pub fn is_flat(&self) -> bool {
+        *self == StorageLayout::Flat
+    }
+}
+
+impl std::str::FromStr for StorageLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "flat" => Ok(StorageLayout::Flat),
+            "cas" => Ok(StorageLayout::Cas),
+            other => Err(format!(
+                "Unknown storage layout: {} (expected flat or cas)",
+                other
+            )),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RegistryMetadata {
+    #[serde(default)]
+    pub schema_version: u32,
     pub registry_name: String,
     pub backup_enabled: bool,
     pub locked_packages: Vec<LockedPackage>,
     pub backups: Vec<PackageBackup>,
     pub last_updated: String,
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+    #[serde(default)]
+    pub notifiers: Vec<NotifierConfig>,
+    /// 新推送包使用的校验和算法（sha256/blake3），未设置时为 sha256
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum_algorithm: Option<String>,
+    /// 按包名记录的访问控制，未记录的包视为公开
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub access: BTreeMap<String, PackageAccess>,
+    /// 按作用域名（不含 @）记录的默认设置
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub scopes: BTreeMap<String, ScopeSettings>,
+    /// 发布频道：包名 -> 频道名（stable、beta 等）-> 版本
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub channels: BTreeMap<String, BTreeMap<String, String>>,
+    /// 定时备份计划，由 backup run-due 执行
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub backup_schedules: Vec<BackupSchedule>,
+    /// 旧版本的保留规则：包名或 glob（例如 @acme/*）-> 规则，gc --apply-retention 时应用
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub retention: BTreeMap<String, RetentionRule>,
+    /// 包文件的存储布局，未设置时为 flat
+    #[serde(default, skip_serializing_if = "StorageLayout::is_flat")]
+    pub layout: StorageLayout,
+    /// 不可变发布：已发布的版本不能被覆盖（包括强制推送、镜像和导入），修改必须发布新版本
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub immutable: bool,
+    /// 记录每次下载（拉取、代理和 gRPC 服务）到 stats/downloads/，供 stats downloads 统计
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub download_stats: bool,
+    /// 推送时检查的大小限制
+    #[serde(default, skip_serializing_if = "QuotaPolicy::is_empty")]
+    pub quota: QuotaPolicy,
+    /// 受保护的频道（例如 production）：推送或 tag 到这些频道需要另一个用户 approve
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub protected_channels: Vec<String>,
+    /// 等待批准的推送和频道变更
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pending_approvals: Vec<PendingApproval>,
+    /// 已批准的记录
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub approvals: Vec<Approval>,
+    /// 以 --draft 推送、尚未 release 的版本
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub drafts: Vec<Draft>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl Versioned for RegistryMetadata {
+    const DOCUMENT: &'static str = "registry metadata";
+    const VERSION: u32 = 2;
+    const MIGRATIONS: &'static [Migration] = &[schema::unversioned, drop_webhook_secrets];
+}
+
+// 版本 2 起 webhook 只记录保存签名密钥的环境变量名。版本 1 中明文保存的密钥被移除，
+// 这些 webhook 需要用 webhook add --secret-env 重新添加才会签名
+fn drop_webhook_secrets(fields: &mut serde_json::Map<String, serde_json::Value>) {
+    let Some(serde_json::Value::Array(webhooks)) = fields.get_mut("webhooks") else {
+        return;
+    };
+    for webhook in webhooks {
+        let Some(webhook) = webhook.as_object_mut() else {
+            continue;
+        };
+        if webhook.remove("secret").is_some() {
+            log::warn!(
+                "Dropped the plain-text secret of webhook {}, add it again with --secret-env",
+                webhook
+                    .get("url")
+                    .and_then(|url| url.as_str())
+                    .unwrap_or("?")
+            );
+        }
+    }
+}
+
+/// 注册表还没有元数据时使用的初始元数据
+impl Default for RegistryMetadata {
+    fn default() -> Self {
+        RegistryMetadata {
+            schema_version: Self::VERSION,
+            registry_name: "MinIO Package Registry".to_string(),
+            backup_enabled: false,
+            locked_packages: Vec::new(),
+            backups: Vec::new(),
+            last_updated: chrono::Utc::now().to_rfc3339(),
+            webhooks: Vec::new(),
+            notifiers: Vec::new(),
+            checksum_algorithm: None,
+            access: Default::default(),
+            scopes: Default::default(),
+            channels: Default::default(),
+            layout: Default::default(),
+            backup_schedules: Vec::new(),
+            retention: Default::default(),
+            immutable: false,
+            download_stats: false,
+            quota: Default::default(),
+            protected_channels: Vec::new(),
+            pending_approvals: Vec::new(),
+            approvals: Vec::new(),
+            drafts: Vec::new(),
+        }
+    }
+}
+
+impl RegistryMetadata {
+    /// 解析 name@<version|channel> 中的版本：存在同名频道时返回频道指向的版本，否则原样返回
+    pub fn resolve_version<'a>(&'a self, name: &str, version: &'a str) -> &'a str {
+        self.channels
+            .get(name)
+            .and_then(|channels| channels.get(version))
+            .map_or(version, String::as_str)
+    }
+
+    /// 没有指定版本时解析的版本：包有 latest 频道（例如回滚后）时使用它，否则为最新版本
+    pub fn default_version(&self, name: &str) -> &'static str {
+        let pinned = self
+            .channels
+            .get(name)
+            .is_some_and(|channels| channels.contains_key(LATEST_CHANNEL));
+        if pinned { LATEST_CHANNEL } else { "*" }
+    }
+
+    /// 新发布的版本更高时让回滚留下的 latest 频道前进；等待批准的版本和受保护的 latest 不移动
+    pub fn advance_latest(&mut self, name: &str, version: &str) {
+        if self.is_pending(name, version) || self.is_protected(LATEST_CHANNEL) {
+            return;
+        }
+        let Some(latest) = self
+            .channels
+            .get_mut(name)
+            .and_then(|channels| channels.get_mut(LATEST_CHANNEL))
+        else {
+            return;
+        };
+        let newer = semver::Version::parse(version)
+            .ok()
+            .zip(semver::Version::parse(latest).ok())
+            .is_some_and(|(new, current)| new > current);
+        if newer {
+            *latest = version.to_string();
+        }
+    }
+
+    pub fn is_protected(&self, channel: &str) -> bool {
+        self.protected_channels.iter().any(|c| c == channel)
+    }
+
+    /// 版本是否随推送等待批准，批准前对 pull 和 install 不可见
+    pub fn is_pending(&self, name: &str, version: &str) -> bool {
+        self.pending_approvals
+            .iter()
+            .any(|p| p.publish && p.name == name && p.version == version)
+    }
+}
+
+/// 不指定版本的拉取和安装使用的频道，rollback 默认回滚它
+pub const LATEST_CHANNEL: &str = "latest";
+
+/// 一次回滚：频道从 from 指回 to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Rollback {
+    pub name: String,
+    pub channel: String,
+    pub from: String,
+    pub to: String,
+    /// 从备份恢复了被强制推送覆盖的包文件时为备份的时间
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restored_backup: Option<String>,
+    /// 频道受保护，回滚等待另一个用户批准
+    pub pending: bool,
+}
+
+/// 推送或 tag 到受保护频道时等待批准的变更
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingApproval {
+    pub name: String,
+    pub version: String,
+    pub channel: String,
+    pub requested_by: String,
+    pub requested_at: String,
+    /// 版本随这次推送发布，对象保存在 pending/ 下，批准时移到正式位置；为 false 时只是把频道指向已发布的版本
+    #[serde(default)]
+    pub publish: bool,
+    /// 请求者用受信任的 ed25519 私钥对请求的签名（签名文件格式），批准时验证
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+/// 一次批准：谁请求、谁批准
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Approval {
+    pub name: String,
+    pub version: String,
+    pub channel: String,
+    pub requested_by: String,
+    pub requested_at: String,
+    pub approved_by: String,
+    pub approved_at: String,
+    /// 请求者和批准者的签名，两者由不同的受信任公钥签署
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_signature: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+/// 草稿版本：对象保存在 drafts/ 下，release 时移到正式位置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Draft {
+    pub name: String,
+    pub version: String,
+    /// 推送时指定的频道，release 时才指向该版本
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    pub created_by: String,
+    pub created_at: String,
+}
+
+/// 作用域（@org/name 中的 org）下所有包的默认设置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScopeSettings {
+    /// 默认可见性，pack.toml 中的设置优先
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<Visibility>,
+    /// 推送时必须签名，拉取时必须验证签名
+    #[serde(default)]
+    pub require_signature: bool,
+    /// 作用域内所有包的总字节数上限，覆盖注册表的默认值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<u64>,
+}
+
+/// 注册表的大小限制，推送时在上传包文件和产物之前检查
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaPolicy {
+    /// 单个包文件或产物的最大字节数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_artifact_size: Option<u64>,
+    /// 每个命名空间（作用域或未分作用域的包）的默认总字节数上限
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_namespace_bytes: Option<u64>,
+}
+
+impl QuotaPolicy {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// 旧版本的保留规则，满足任一条件的版本都会保留
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionRule {
+    /// 保留最新的 N 个版本
+    pub keep_last: usize,
+    /// 保留发布频道指向的版本
+    #[serde(default = "default_true")]
+    pub keep_channels: bool,
+    /// 保留锁定的版本
+    #[serde(default = "default_true")]
+    pub keep_locked: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// 包名的作用域，例如 @payments/billing-lib 的作用域为 payments
+pub fn package_scope(name: &str) -> Option<&str> {
+    name.strip_prefix('@')?
+        .split_once('/')
+        .map(|(scope, _)| scope)
+}
+
+/// 检查包名：作用域包必须是 @<scope>/<name> 形式，其余包名不能包含 /
+pub fn validate_package_name(name: &str) -> crate::Result<()> {
+    let valid = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+    let valid = match name.strip_prefix('@') {
+        Some(scoped) => scoped
+            .split_once('/')
+            .is_some_and(|(scope, name)| valid(scope) && valid(name)),
+        None => valid(name),
+    };
+    if !valid {
+        return Err(format!(
+            "Invalid package name: {} (expected name or @scope/name)",
+            name
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// 拆分 name@version，作用域包名以 @ 开头（@scope/name@1.0.0）
+pub fn split_package_spec(spec: &str) -> Option<(&str, &str)> {
+    spec.rsplit_once('@')
+        .filter(|(name, version)| !name.is_empty() && !version.is_empty())
+}
+
+/// 拆分包文件名 name-version（不含 .zip），版本须为合法的 semver，可以带预发布后缀（demo-1.0.0-rc.1）。
+/// 包名中也可以包含 -，取第一个使其后部分为合法版本号的位置
+pub fn split_archive_name(stem: &str) -> Option<(&str, &str)> {
+    stem.match_indices('-')
+        .map(|(index, _)| (&stem[..index], &stem[index + 1..]))
+        .find(|(name, version)| !name.is_empty() && semver::Version::parse(version).is_ok())
+}
+
+/// 包文件对象键 name-version.zip 中的包名和版本，备份和附加产物返回 None
+pub fn parse_archive_key(key: &str) -> Option<(&str, &str)> {
+    if parse_backup_key(key).is_some() || crate::artifacts::is_artifact_key(key) {
+        return None;
+    }
+    split_archive_name(key.strip_suffix(".zip")?)
+}
+
+/// 备份对象键 name-version-backup-<unix 时间戳>.zip 中的包名、版本和时间戳。
+/// 只按完整格式识别，包名中含有 -backup- 的包（db-backup-tool-1.0.0.zip）不是备份
+pub fn parse_backup_key(key: &str) -> Option<(&str, &str, i64)> {
+    let (archive, timestamp) = key.strip_suffix(".zip")?.rsplit_once("-backup-")?;
+    if timestamp.is_empty() || !timestamp.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let (name, version) = split_archive_name(archive)?;
+    Some((name, version, timestamp.parse().ok()?))
+}
+
+/// 检查频道名：由字母、数字和 -_. 组成，且不能是 semver 版本号，避免与版本混淆
+pub fn validate_channel(channel: &str) -> crate::Result<()> {
+    let valid = !channel.is_empty()
+        && channel
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && semver::Version::parse(channel).is_err();
+    if !valid {
+        return Err(format!("Invalid channel name: {}", channel).into());
+    }
+    Ok(())
+}
+
+/// 解析标签 `key=value`。key 由字母、数字和 -_./ 组成，例如 git_sha、ci/build
+pub fn parse_label(label: &str) -> crate::Result<(String, String)> {
+    let (key, value) = label
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid label {} (expected key=value)", label))?;
+    validate_label_key(key)?;
+    Ok((key.to_string(), value.to_string()))
+}
+
+pub fn validate_label_key(key: &str) -> crate::Result<()> {
+    let valid = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'));
+    if !valid {
+        return Err(format!("Invalid label key: {}", key).into());
+    }
+    Ok(())
+}
+
+/// 包的可见性
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Visibility {
+    /// 所有人可见
+    #[default]
+    Public,
+    /// 只有所有者和被授权的用户/团队可见
+    Private,
+    /// 指定团队的成员可见
+    Team(String),
+}
+
+impl std::fmt::Display for Visibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Visibility::Public => f.write_str("public"),
+            Visibility::Private => f.write_str("private"),
+            Visibility::Team(team) => write!(f, "team:{}", team),
+        }
+    }
+}
+
+impl std::str::FromStr for Visibility {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "public" => Ok(Visibility::Public),
+            "private" => Ok(Visibility::Private),
+            other => match other.strip_prefix("team:") {
+                Some(team) if !team.is_empty() => Ok(Visibility::Team(team.to_string())),
+                _ => Err(format!(
+                    "Unknown visibility: {} (expected public, private or team:<name>)",
+                    other
+                )),
+            },
+        }
+    }
+}
+
+impl TryFrom<String> for Visibility {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Visibility> for String {
+    fn from(visibility: Visibility) -> Self {
+        visibility.to_string()
+    }
+}
+
+/// 包的访问控制：可见性、所有者以及额外授权的用户或团队（team:<name>）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PackageAccess {
+    #[serde(default)]
+    pub visibility: Visibility,
+    /// 首次推送该包的用户，只有所有者可以修改授权
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub grants: Vec<String>,
+}
+
+impl PackageAccess {
+    /// 指定用户（及其所属团队）是否可以查看和拉取该包
+    pub fn allows(&self, user: &str, teams: &[String]) -> bool {
+        let is_member = |team: &str| teams.iter().any(|t| t == team);
+        let granted = self
+            .grants
+            .iter()
+            .any(|grant| match grant.strip_prefix("team:") {
+                Some(team) => is_member(team),
+                None => grant == user,
+            });
+        match &self.visibility {
+            Visibility::Public => true,
+            Visibility::Team(team) if is_member(team) => true,
+            _ => self.owner.as_deref() == Some(user) || granted,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// 保存签名密钥的环境变量名，密钥本身不写入注册表
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_env: Option<String>,
+    /// 订阅的事件，为空表示订阅全部事件
+    #[serde(default)]
+    pub events: Vec<AuditAction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum NotifierConfig {
+    Slack {
+        webhook_url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        template: Option<String>,
+        #[serde(default)]
+        events: Vec<AuditAction>,
+    },
+    Email {
+        smtp_host: String,
+        #[serde(default = "default_smtp_port")]
+        smtp_port: u16,
+        #[serde(default = "default_starttls")]
+        starttls: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        username: Option<String>,
+        /// 保存 SMTP 密码的环境变量名，密码本身不写入注册表
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        password_env: Option<String>,
+        from: String,
+        to: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        subject: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        template: Option<String>,
+        #[serde(default)]
+        events: Vec<AuditAction>,
+    },
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+fn default_starttls() -> bool {
+    true
+}
+
+impl NotifierConfig {
+    /// 该通知器是否订阅了指定事件
+    pub fn matches(&self, action: AuditAction) -> bool {
+        let events = match self {
+            NotifierConfig::Slack { events, .. } | NotifierConfig::Email { events, .. } => events,
+        };
+        events.is_empty() || events.contains(&action)
+    }
+}
+
+impl WebhookConfig {
+    /// 该 webhook 是否订阅了指定事件
+    pub fn matches(&self, action: AuditAction) -> bool {
+        self.events.is_empty() || self.events.contains(&action)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LockedPackage {
     pub name: String,
     pub version: String,
@@ -83,4 +1250,117 @@ pub struct LockedPackage {
     pub locked_by: String,
     #[serde(default)]
     pub checksum: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+}
+
+impl LockedPackage {
+    /// 锁是否已经过期（未设置过期时间的锁永不过期）
+    pub fn is_expired(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        self.expires_at
+            .as_deref()
+            .and_then(|ts| chrono::DateTime::parse_from_rfc3339(ts).ok())
+            .is_some_and(|expires| expires.with_timezone(&chrono::Utc) <= now)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuditAction {
+    Push,
+    ForcePush,
+    Lock,
+    Unlock,
+    Backup,
+    Restore,
+    Tag,
+    Delete,
+    Annotate,
+    Approve,
+    Rollback,
+}
+
+impl AuditAction {
+    /// 用于通知消息的动词形式
+    pub fn verb(&self) -> &'static str {
+        match self {
+            AuditAction::Push => "published",
+            AuditAction::ForcePush => "force-published",
+            AuditAction::Lock => "locked",
+            AuditAction::Unlock => "unlocked",
+            AuditAction::Backup => "backed up",
+            AuditAction::Restore => "restored",
+            AuditAction::Tag => "tagged",
+            AuditAction::Delete => "deleted",
+            AuditAction::Annotate => "annotated",
+            AuditAction::Approve => "approved",
+            AuditAction::Rollback => "rolled back",
+        }
+    }
+}
+
+impl std::fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            AuditAction::Push => "push",
+            AuditAction::ForcePush => "force-push",
+            AuditAction::Lock => "lock",
+            AuditAction::Unlock => "unlock",
+            AuditAction::Backup => "backup",
+            AuditAction::Restore => "restore",
+            AuditAction::Tag => "tag",
+            AuditAction::Delete => "delete",
+            AuditAction::Annotate => "annotate",
+            AuditAction::Approve => "approve",
+            AuditAction::Rollback => "rollback",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for AuditAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "push" => Ok(AuditAction::Push),
+            "force-push" => Ok(AuditAction::ForcePush),
+            "lock" => Ok(AuditAction::Lock),
+            "unlock" => Ok(AuditAction::Unlock),
+            "backup" => Ok(AuditAction::Backup),
+            "restore" => Ok(AuditAction::Restore),
+            "tag" => Ok(AuditAction::Tag),
+            "delete" => Ok(AuditAction::Delete),
+            "annotate" => Ok(AuditAction::Annotate),
+            "approve" => Ok(AuditAction::Approve),
+            "rollback" => Ok(AuditAction::Rollback),
+            other => Err(format!("Unknown action: {}", other)),
+        }
+    }
+}
+
+/// 一次包下载，每条记录单独存为 stats/downloads/ 下的一个对象
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadRecord {
+    /// 包名
+    pub package: String,
+    pub version: String,
+    pub timestamp: String,
+    /// 下载者：拉取时为 BEEPKG_USER，经代理或 gRPC 服务下载时为 proxy/grpc
+    pub client: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_ip: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub actor: String,
+    pub action: AuditAction,
+    pub package: String,
+    pub timestamp: String,
+    pub client_version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_ip: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
 }
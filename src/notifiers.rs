@@ -0,0 +1,113 @@
+use crate::Result;
+use crate::models::{AuditRecord, NotifierConfig};
+#[cfg(feature = "notify")]
+use lettre::message::{Mailbox, header::ContentType};
+#[cfg(feature = "notify")]
+use lettre::transport::smtp::authentication::Credentials;
+#[cfg(feature = "notify")]
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use reqwest::Client;
+
+/// Slack 消息和邮件标题的默认模板
+pub const DEFAULT_TEMPLATE: &str = "pkg {name} v{version} {verb} by {actor}";
+
+/// 邮件正文的默认模板
+pub const DEFAULT_EMAIL_TEMPLATE: &str =
+    "Package {package} was {verb} by {actor} at {timestamp}.\n\n{details}";
+
+/// 渲染消息模板，支持的占位符：
+/// {name} {version} {package} {action} {verb} {actor} {timestamp} {details}
+pub fn render_template(template: &str, record: &AuditRecord) -> String {
+    let (name, version) = record
+        .package
+        .split_once('@')
+        .unwrap_or((record.package.as_str(), ""));
+
+    template
+        .replace("{name}", name)
+        .replace("{version}", version)
+        .replace("{package}", &record.package)
+        .replace("{action}", &record.action.to_string())
+        .replace("{verb}", record.action.verb())
+        .replace("{actor}", &record.actor)
+        .replace("{timestamp}", &record.timestamp)
+        .replace("{details}", record.details.as_deref().unwrap_or(""))
+}
+
+/// 通过指定的通知器发送事件通知
+pub async fn notify(
+    client: &Client,
+    notifier: &NotifierConfig,
+    record: &AuditRecord,
+) -> Result<()> {
+    match notifier {
+        NotifierConfig::Slack {
+            webhook_url,
+            template,
+            ..
+        } => {
+            let text = render_template(template.as_deref().unwrap_or(DEFAULT_TEMPLATE), record);
+            let response = client
+                .post(webhook_url)
+                .json(&serde_json::json!({ "text": text }))
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                return Err(format!("Slack notification failed: {}", response.status()).into());
+            }
+            Ok(())
+        }
+        #[cfg(feature = "notify")]
+        NotifierConfig::Email {
+            smtp_host,
+            smtp_port,
+            starttls,
+            username,
+            password_env,
+            from,
+            to,
+            subject,
+            template,
+            ..
+        } => {
+            let subject = render_template(subject.as_deref().unwrap_or(DEFAULT_TEMPLATE), record);
+            let body = render_template(
+                template.as_deref().unwrap_or(DEFAULT_EMAIL_TEMPLATE),
+                record,
+            );
+
+            let mut builder = Message::builder()
+                .from(from.parse::<Mailbox>()?)
+                .subject(subject)
+                .header(ContentType::TEXT_PLAIN);
+            for recipient in to {
+                builder = builder.to(recipient.parse::<Mailbox>()?);
+            }
+            let message = builder.body(body)?;
+
+            let mut transport = if *starttls {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(smtp_host)?
+            } else {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(smtp_host)
+            }
+            .port(*smtp_port);
+
+            if let Some(username) = username {
+                let password = match password_env {
+                    Some(var) => std::env::var(var)
+                        .map_err(|_| format!("SMTP password variable {} is not set", var))?,
+                    None => String::new(),
+                };
+                transport = transport.credentials(Credentials::new(username.clone(), password));
+            }
+
+            transport.build().send(message).await?;
+            Ok(())
+        }
+        #[cfg(not(feature = "notify"))]
+        NotifierConfig::Email { .. } => {
+            Err("email notification support is not enabled in this build".into())
+        }
+    }
+}
@@ -0,0 +1,548 @@
+use crate::artifacts::ArtifactLayout;
+use crate::checksum::{Checksum, ChecksumAlgorithm};
+use crate::error::BeepkgError;
+use crate::models::PackageMetadata;
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use reqwest::{Client, Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// OCI 清单中标记 beepkg 包的 artifactType
+pub const ARTIFACT_TYPE: &str = "application/vnd.beepkg.package.v1";
+/// 配置 blob（pack.toml 的 JSON 形式）的媒体类型
+pub const CONFIG_MEDIA_TYPE: &str = "application/vnd.beepkg.config.v1+json";
+/// 包文件层的媒体类型
+pub const LAYER_MEDIA_TYPE: &str = "application/vnd.beepkg.package.layer.v1.zip";
+
+const MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+const TITLE_ANNOTATION: &str = "org.opencontainers.image.title";
+const VERSION_ANNOTATION: &str = "org.opencontainers.image.version";
+const CREATED_ANNOTATION: &str = "org.opencontainers.image.created";
+
+/// 以 OCI 制品（与 ORAS 相同的方式）把包存放在容器镜像仓库（Harbor、ECR、GHCR、distribution 等）中。
+/// 包名对应仓库 <命名空间>/<包名>，版本对应标签，包文件是清单中的唯一一层，pack.toml 作为配置 blob。
+///
+/// 平台产物、命名产物和加密不在 OCI 仓库中使用，推送包含这些设置的包会报错
+pub struct OciRegistry {
+    client: Client,
+    /// 仓库服务地址，例如 https://harbor.example.com
+    base_url: String,
+    /// 仓库名前缀，例如 team，为空时包名直接作为仓库名
+    namespace: String,
+    credentials: Option<(String, String)>,
+    // 按 scope 缓存的 Bearer 令牌
+    tokens: Mutex<HashMap<String, String>>,
+}
+
+/// OCI 仓库中的一个包版本
+#[derive(Debug, Clone)]
+pub struct OciPackage {
+    pub name: String,
+    pub version: String,
+    /// 包文件层的摘要（sha256:<十六进制>）
+    pub digest: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    schema_version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    media_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    artifact_type: Option<String>,
+    config: Descriptor,
+    layers: Vec<Descriptor>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    annotations: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Descriptor {
+    media_type: String,
+    digest: String,
+    size: u64,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    annotations: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TagList {
+    #[serde(default)]
+    tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    #[serde(default)]
+    token: Option<String>,
+    #[serde(default)]
+    access_token: Option<String>,
+}
+
+impl OciRegistry {
+    /// url 为仓库服务地址，可以带命名空间，例如 https://harbor.example.com/team
+    pub fn new(url: &str) -> Result<Self, BeepkgError> {
+        let parsed = url::Url::parse(url)
+            .map_err(|e| BeepkgError::Config(format!("Invalid OCI registry URL {}: {}", url, e)))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(BeepkgError::Config(format!(
+                "OCI registry URL must use http or https: {}",
+                url
+            )));
+        }
+        let namespace = parsed.path().trim_matches('/').to_string();
+        let base_url = url.trim_end_matches('/');
+        let base_url = base_url[..base_url.len() - namespace.len()]
+            .trim_end_matches('/')
+            .to_string();
+        Ok(Self {
+            client: Client::new(),
+            base_url,
+            namespace,
+            credentials: None,
+            tokens: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// 用户名和口令（或访问令牌），用于 Basic 认证和获取 Bearer 令牌
+    pub fn credentials(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.credentials = Some((username.into(), password.into()));
+        self
+    }
+
+    /// 从 BEEPKG_OCI_REGISTRY、BEEPKG_OCI_USERNAME 和 BEEPKG_OCI_PASSWORD 创建
+    pub fn from_env() -> Result<Self, BeepkgError> {
+        let url = std::env::var("BEEPKG_OCI_REGISTRY")
+            .map_err(|_| BeepkgError::Config("BEEPKG_OCI_REGISTRY is not set".to_string()))?;
+        Ok(Self::new(&url)?.env_credentials())
+    }
+
+    /// 设置了 BEEPKG_OCI_USERNAME 时使用它和 BEEPKG_OCI_PASSWORD 作为凭证
+    pub fn env_credentials(self) -> Self {
+        match std::env::var("BEEPKG_OCI_USERNAME") {
+            Ok(username) => self.credentials(
+                username,
+                std::env::var("BEEPKG_OCI_PASSWORD").unwrap_or_default(),
+            ),
+            Err(_) => self,
+        }
+    }
+
+    /// 包名对应的仓库名：去掉作用域的 @ 并转为小写，加上命名空间
+    pub fn repository(&self, name: &str) -> String {
+        let name = name.trim_start_matches('@').to_lowercase();
+        if self.namespace.is_empty() {
+            name
+        } else {
+            format!("{}/{}", self.namespace, name)
+        }
+    }
+
+    /// 推送包目录，force 为 false 时版本已存在则报错。返回包名、版本和包文件层的摘要
+    pub async fn push(&self, package_dir: &Path, force: bool) -> Result<OciPackage, BeepkgError> {
+        let metadata = PackageMetadata::load(package_dir)?;
+        metadata.validate()?;
+        if metadata.encryption.as_ref().is_some_and(|e| e.enabled) {
+            return Err(BeepkgError::Config(
+                "Encrypted packages are not supported in OCI registries".to_string(),
+            ));
+        }
+        if !metadata.targets.is_empty() || !metadata.artifacts.is_empty() {
+            return Err(BeepkgError::Config(
+                "Platform targets and artifacts are not supported in OCI registries".to_string(),
+            ));
+        }
+
+        let repository = self.repository(&metadata.name);
+        let tag = version_tag(&metadata.version);
+        if !force && self.manifest(&repository, &tag).await?.is_some() {
+            return Err(BeepkgError::Conflict(format!(
+                "Package {}@{} already exists. Use --force to overwrite.",
+                metadata.name, metadata.version
+            )));
+        }
+
+        let archive = build_archive(package_dir, &metadata)?;
+        let layer = Descriptor {
+            media_type: LAYER_MEDIA_TYPE.to_string(),
+            digest: digest(&archive),
+            size: archive.len() as u64,
+            annotations: BTreeMap::from([(
+                TITLE_ANNOTATION.to_string(),
+                format!("{}-{}.zip", metadata.name, metadata.version),
+            )]),
+        };
+        let config = serde_json::to_vec(&metadata)?;
+        let config = Descriptor {
+            media_type: CONFIG_MEDIA_TYPE.to_string(),
+            digest: digest(&config),
+            size: config.len() as u64,
+            annotations: BTreeMap::new(),
+        }
+        .upload(self, &repository, config)
+        .await?;
+        let layer = layer.upload(self, &repository, archive).await?;
+
+        let manifest = Manifest {
+            schema_version: 2,
+            media_type: Some(MANIFEST_MEDIA_TYPE.to_string()),
+            artifact_type: Some(ARTIFACT_TYPE.to_string()),
+            config,
+            annotations: BTreeMap::from([
+                (VERSION_ANNOTATION.to_string(), metadata.version.clone()),
+                (
+                    CREATED_ANNOTATION.to_string(),
+                    chrono::Utc::now().to_rfc3339(),
+                ),
+            ]),
+            layers: vec![layer],
+        };
+        let response = self
+            .send(
+                Method::PUT,
+                &repository,
+                &format!("manifests/{}", tag),
+                Some((MANIFEST_MEDIA_TYPE, serde_json::to_vec(&manifest)?)),
+                &[],
+                true,
+            )
+            .await?;
+        check(response, &format!("{}:{}", repository, tag)).await?;
+
+        Ok(OciPackage {
+            name: metadata.name,
+            version: metadata.version,
+            digest: manifest.layers[0].digest.clone(),
+            size: manifest.layers[0].size,
+        })
+    }
+
+    /// 下载 name@version 的包文件到 path，按清单中的摘要验证内容
+    pub async fn pull(
+        &self,
+        name: &str,
+        version: &str,
+        path: &Path,
+    ) -> Result<OciPackage, BeepkgError> {
+        let repository = self.repository(name);
+        let manifest = self
+            .manifest(&repository, &version_tag(version))
+            .await?
+            .ok_or_else(|| BeepkgError::NotFound(format!("{}@{}", name, version)))?;
+        let layer = manifest
+            .layers
+            .iter()
+            .find(|layer| layer.media_type == LAYER_MEDIA_TYPE)
+            .ok_or_else(|| {
+                BeepkgError::MetadataParse(format!("{}@{} is not a beepkg package", name, version))
+            })?;
+
+        let response = self
+            .send(
+                Method::GET,
+                &repository,
+                &format!("blobs/{}", layer.digest),
+                None,
+                &[],
+                false,
+            )
+            .await?;
+        let content = check(response, &layer.digest).await?.bytes().await?;
+        let actual = digest(&content);
+        if actual != layer.digest {
+            return Err(BeepkgError::ChecksumMismatch(format!(
+                "{}@{}: expected {}, got {}",
+                name, version, layer.digest, actual
+            )));
+        }
+        std::fs::write(path, &content)?;
+        Ok(OciPackage {
+            name: name.to_string(),
+            version: version.to_string(),
+            digest: actual,
+            size: content.len() as u64,
+        })
+    }
+
+    /// 下载 name@version 并解压到 output_dir
+    pub async fn install(
+        &self,
+        name: &str,
+        version: &str,
+        output_dir: &Path,
+    ) -> Result<OciPackage, BeepkgError> {
+        let archive = tempfile::NamedTempFile::new()?.into_temp_path();
+        let package = self.pull(name, version, &archive).await?;
+        std::fs::create_dir_all(output_dir)?;
+        zip::ZipArchive::new(std::fs::File::open(&archive)?)?.extract(output_dir)?;
+        Ok(package)
+    }
+
+    /// 包在仓库中的版本，按 semver 从新到旧排列，仓库不存在时为空
+    pub async fn list_versions(&self, name: &str) -> Result<Vec<String>, BeepkgError> {
+        let repository = self.repository(name);
+        let response = self
+            .send(Method::GET, &repository, "tags/list", None, &[], false)
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        let tags: TagList = check(response, &repository).await?.json().await?;
+        let mut versions: Vec<String> = tags
+            .tags
+            .unwrap_or_default()
+            .iter()
+            .map(|tag| tag_version(tag))
+            .collect();
+        versions.sort_by(|a, b| {
+            let a = semver::Version::parse(a).ok();
+            let b = semver::Version::parse(b).ok();
+            b.cmp(&a)
+        });
+        Ok(versions)
+    }
+
+    // 获取清单，标签不存在时为 None
+    async fn manifest(
+        &self,
+        repository: &str,
+        reference: &str,
+    ) -> Result<Option<Manifest>, BeepkgError> {
+        let response = self
+            .send(
+                Method::GET,
+                repository,
+                &format!("manifests/{}", reference),
+                None,
+                &[(header::ACCEPT, MANIFEST_MEDIA_TYPE)],
+                false,
+            )
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let content = check(response, reference).await?.bytes().await?;
+        Ok(Some(serde_json::from_slice(&content)?))
+    }
+
+    // 发送 /v2/<repository>/<path> 请求。收到 Bearer 挑战时获取令牌后重试一次
+    async fn send(
+        &self,
+        method: Method,
+        repository: &str,
+        path: &str,
+        body: Option<(&str, Vec<u8>)>,
+        headers: &[(header::HeaderName, &'static str)],
+        push: bool,
+    ) -> Result<reqwest::Response, BeepkgError> {
+        let url = if path.starts_with("http://") || path.starts_with("https://") {
+            path.to_string()
+        } else {
+            format!("{}/v2/{}/{}", self.base_url, repository, path)
+        };
+        let scope = format!(
+            "repository:{}:{}",
+            repository,
+            if push { "pull,push" } else { "pull" }
+        );
+
+        let mut challenged = false;
+        loop {
+            let mut request = self.client.request(method.clone(), &url);
+            let mut extra = HeaderMap::new();
+            for (name, value) in headers {
+                extra.insert(name, HeaderValue::from_static(value));
+            }
+            request = request.headers(extra);
+            if let Some((content_type, content)) = &body {
+                request = request
+                    .header(header::CONTENT_TYPE, *content_type)
+                    .body(content.clone());
+            }
+            let token = self.tokens.lock().unwrap().get(&scope).cloned();
+            request = match (token, &self.credentials) {
+                (Some(token), _) => request.bearer_auth(token),
+                (None, Some((username, password))) => request.basic_auth(username, Some(password)),
+                (None, None) => request,
+            };
+
+            let response = request.send().await?;
+            if response.status() != StatusCode::UNAUTHORIZED || challenged {
+                return Ok(response);
+            }
+            let Some(challenge) = response
+                .headers()
+                .get(header::WWW_AUTHENTICATE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .map(parse_challenge)
+            else {
+                return Ok(response);
+            };
+            let token = self.fetch_token(&challenge, &scope).await?;
+            self.tokens.lock().unwrap().insert(scope.clone(), token);
+            challenged = true;
+        }
+    }
+
+    // 按 WWW-Authenticate 中的 realm 和 service 获取令牌（Docker 令牌认证协议）
+    async fn fetch_token(
+        &self,
+        challenge: &HashMap<String, String>,
+        scope: &str,
+    ) -> Result<String, BeepkgError> {
+        let realm = challenge
+            .get("realm")
+            .ok_or_else(|| BeepkgError::Auth("Bearer challenge without realm".to_string()))?;
+        let mut query = vec![(
+            "scope",
+            challenge.get("scope").map_or(scope, String::as_str),
+        )];
+        if let Some(service) = challenge.get("service") {
+            query.push(("service", service));
+        }
+        let mut request = self.client.get(realm).query(&query);
+        if let Some((username, password)) = &self.credentials {
+            request = request.basic_auth(username, Some(password));
+        }
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(BeepkgError::Auth(format!(
+                "Token request to {} failed: {}",
+                realm,
+                response.status()
+            )));
+        }
+        let token: TokenResponse = response.json().await?;
+        token
+            .token
+            .or(token.access_token)
+            .ok_or_else(|| BeepkgError::Auth(format!("No token in response from {}", realm)))
+    }
+}
+
+impl Descriptor {
+    // 上传 blob（已存在时跳过），返回描述符本身
+    async fn upload(
+        self,
+        registry: &OciRegistry,
+        repository: &str,
+        content: Vec<u8>,
+    ) -> Result<Self, BeepkgError> {
+        let blob = format!("blobs/{}", self.digest);
+        let existing = registry
+            .send(Method::HEAD, repository, &blob, None, &[], true)
+            .await?;
+        if existing.status().is_success() {
+            return Ok(self);
+        }
+
+        // 单次上传：先申请上传地址，再带上摘要 PUT 全部内容
+        let response = registry
+            .send(Method::POST, repository, "blobs/uploads/", None, &[], true)
+            .await?;
+        let response = check(response, repository).await?;
+        let location = response
+            .headers()
+            .get(header::LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| BeepkgError::Other("Upload response without Location".to_string()))?;
+        let location = if location.starts_with('/') {
+            format!("{}{}", registry.base_url, location)
+        } else {
+            location.to_string()
+        };
+        let separator = if location.contains('?') { '&' } else { '?' };
+        let url = format!("{}{}digest={}", location, separator, self.digest);
+        let response = registry
+            .send(
+                Method::PUT,
+                repository,
+                &url,
+                Some(("application/octet-stream", content)),
+                &[],
+                true,
+            )
+            .await?;
+        check(response, &self.digest).await?;
+        Ok(self)
+    }
+}
+
+/// 版本对应的标签。标签不允许 +，与 Helm 相同替换为 _
+pub fn version_tag(version: &str) -> String {
+    version.replace('+', "_")
+}
+
+/// 标签对应的版本
+pub fn tag_version(tag: &str) -> String {
+    tag.replace('_', "+")
+}
+
+fn digest(content: &[u8]) -> String {
+    Checksum::compute(ChecksumAlgorithm::Sha256, content).to_string()
+}
+
+// 解析 realm="...",service="...",scope="..."
+fn parse_challenge(challenge: &str) -> HashMap<String, String> {
+    let mut params = HashMap::new();
+    let mut rest = challenge.trim();
+    while let Some((key, value)) = rest.split_once('=') {
+        let key = key.trim().trim_start_matches(',').trim().to_string();
+        let (value, remaining) = match value.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => value.split_once(',').unwrap_or((value, "")),
+        };
+        params.insert(key, value.to_string());
+        rest = remaining;
+    }
+    params
+}
+
+async fn check(response: reqwest::Response, what: &str) -> Result<reqwest::Response, BeepkgError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(match status {
+        StatusCode::NOT_FOUND => BeepkgError::NotFound(what.to_string()),
+        StatusCode::UNAUTHORIZED => BeepkgError::Auth(format!("{}: {}", what, body)),
+        StatusCode::FORBIDDEN => BeepkgError::AccessDenied(format!("{}: {}", what, body)),
+        _ => BeepkgError::Network(format!("{} returned {}: {}", what, status, body)),
+    })
+}
+
+// 与推送到 S3 时相同的文件选择，打包为内存中的 zip
+fn build_archive(package_dir: &Path, metadata: &PackageMetadata) -> Result<Vec<u8>, BeepkgError> {
+    let layout = ArtifactLayout::new(metadata)?;
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let mut entries: Vec<_> = walkdir::WalkDir::new(package_dir)
+        .into_iter()
+        .collect::<Result<_, _>>()?;
+    // 固定顺序，相同内容得到相同的摘要
+    entries.sort_by(|a, b| a.path().cmp(b.path()));
+    for entry in entries {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry
+            .path()
+            .strip_prefix(package_dir)
+            .map_err(|e| BeepkgError::Other(e.to_string()))?
+            .to_string_lossy()
+            .replace('\\', "/");
+        if layout.excludes(&relative) {
+            continue;
+        }
+        zip.start_file(relative, Default::default())?;
+        zip.write_all(&std::fs::read(entry.path())?)?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
@@ -1,15 +1,133 @@
+use crate::advisory::{self, AdvisoryIndex, ResolvedDependency};
+use crate::artifacts;
+use crate::auth;
+use crate::aws;
+use crate::blobs::{self, BlobPointer};
+#[cfg(feature = "archives")]
+use crate::bundle::{self, BundleManifest, BundlePackage, BundleProject, BundleWriter};
+use crate::cache::{Cache, CacheRef};
+use crate::checksum::{self, Checksum, ChecksumAlgorithm, ChecksumHasher};
+use crate::config::{self, Config};
+use crate::delta;
+use crate::diagnostics;
+use crate::drift;
+use crate::error::BeepkgError;
+use crate::events::{Event, Observer, SilentObserver};
+use crate::fsck;
+use crate::gc::{self, Garbage, GarbageKind};
+use crate::gpg;
+use crate::hooks::{self, Hook};
+use crate::kms;
+use crate::license::LicensePolicy;
+use crate::listing;
+use crate::metrics;
 use crate::models;
-use crate::security::SecurityManager;
+use crate::notifiers;
+use crate::policy::TrustPolicy;
+use crate::project::{self, Project, ProjectLock, SyncReport};
+use crate::provenance;
+use crate::quota;
+use crate::readme;
+use crate::retention::{self, VersionDecision};
+use crate::sbom::{self, SbomFormat};
+use crate::schedule::CronSchedule;
+use crate::schema::{self, Versioned};
+use crate::security::{
+    Cipher, Envelope, FileSelector, SecretSource, SecurityError, SecurityManager,
+};
+use crate::signing;
+use crate::sigstore;
+use crate::site;
+#[cfg(feature = "archives")]
+use crate::snapshot::{self, SnapshotManifest, SnapshotObject};
+use crate::webhooks;
+use crate::workspace::WorkspaceMember;
 use rusty_s3::{Bucket, Credentials, S3Action, UrlStyle};
-use sha1::{Digest, Sha1};
-use thiserror::Error;
 
-#[derive(Error, Debug)]
-pub enum PackageError {
-    #[error("Checksum verification failed: {0}")]
-    ChecksumMismatch(String),
-    #[error("Missing checksum file")]
-    MissingChecksum,
+/// gc 的结果
+#[derive(Debug, Default)]
+pub struct GcReport {
+    /// 找到的垃圾对象
+    pub garbage: Vec<Garbage>,
+    /// 已删除的对象数量，只报告时为 0
+    pub deleted: usize,
+    /// 已删除对象的总字节数
+    pub freed: u64,
+}
+
+/// 从快照恢复注册表的结果
+#[cfg(feature = "archives")]
+#[derive(Debug, Default)]
+pub struct SnapshotRestoreReport {
+    /// 上传的对象数量
+    pub restored: usize,
+    /// 快照中不包含内容、目标 bucket 中也不存在的对象
+    pub missing: Vec<String>,
+}
+
+/// fsck 的结果
+#[derive(Debug, Default)]
+pub struct FsckReport {
+    pub issues: Vec<fsck::Issue>,
+    /// 已修复的问题数量，只检查时为 0
+    pub repaired: usize,
+}
+
+/// 注册表元数据中记录的一个备份
+#[derive(Debug, Serialize)]
+pub struct BackupEntry {
+    pub package: String,
+    pub version: String,
+    pub timestamp: String,
+    pub reason: String,
+    /// 备份对象的键
+    pub backup_path: String,
+    /// 独立的备份 bucket，备份与包在同一个 bucket 中时为 None
+    pub bucket: Option<String>,
+    /// 备份对象的大小，对象已不存在时为 None
+    pub size: Option<u64>,
+}
+
+/// 对一个包应用保留规则的结果
+#[derive(Debug)]
+pub struct RetentionReport {
+    pub package: String,
+    /// 适用的规则（包名或 glob）
+    pub pattern: String,
+    /// 每个版本的决定，按版本从新到旧排列
+    pub versions: Vec<VersionDecision>,
+}
+
+/// 镜像同步的结果
+#[derive(Debug, Default)]
+pub struct MirrorReport {
+    /// 已复制（dry-run 时为需要复制）的包文件
+    pub copied: Vec<String>,
+    /// 目标注册表中已是最新的包数量
+    pub up_to_date: usize,
+    /// 复制失败的包文件及错误信息
+    pub failed: Vec<(String, String)>,
+}
+
+/// 迁移报告中的一项
+#[derive(Debug, Serialize)]
+pub struct MigrationEntry {
+    /// 源存储桶中的包文件
+    pub source: String,
+    /// 目标存储桶中的包文件，失败时为 None
+    pub target: Option<String>,
+    pub status: MigrationStatus,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MigrationStatus {
+    Migrated,
+    /// dry-run 时需要迁移的包
+    Planned,
+    Failed,
 }
 
 // Package conflict status enum
@@ -19,23 +137,213 @@ pub enum PackageConflictStatus {
     VersionExists,               // 完全相同的版本已存在
     HigherVersionExists(String), // 已存在更高版本
 }
+/// 推送后检查上传的包文件和产物是否完整保存
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UploadVerification {
+    /// 不检查
+    None,
+    /// HEAD 请求比较对象大小，ETag 是内容的 MD5 时（单次上传且未使用 KMS 或客户密钥加密）再比较 MD5
+    #[default]
+    Head,
+    /// 下载对象重新计算校验和
+    Full,
+}
+
+impl std::str::FromStr for UploadVerification {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(UploadVerification::None),
+            "head" => Ok(UploadVerification::Head),
+            "full" => Ok(UploadVerification::Full),
+            other => Err(format!(
+                "Unknown upload verification: {} (expected none, head or full)",
+                other
+            )),
+        }
+    }
+}
+
 use chrono;
+use futures_util::{Stream, StreamExt, TryStreamExt, stream};
+use md5::{Digest, Md5};
 use quick_xml::de::from_str;
 use reqwest::Client as ReqwestClient;
 use semver;
-use serde::Deserialize;
-use std::collections::HashMap;
-use std::error::Error;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-use std::time::Duration;
-use toml;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio_util::io::ReaderStream;
 use url;
 
+// 审计日志对象前缀
+const AUDIT_PREFIX: &str = "audit/";
+
+// 下载记录对象前缀
+const STATS_PREFIX: &str = "stats/downloads/";
+
+// 草稿推送的对象前缀，release 之前不在包列表中，拉取和解析都看不到
+const DRAFTS_PREFIX: &str = "drafts/";
+
+// 推送到受保护频道、等待批准的版本的对象前缀，批准后移到正式位置
+const PENDING_PREFIX: &str = "pending/";
+
+// 草稿和等待批准的对象不属于已发布的包
+fn is_staged_key(key: &str) -> bool {
+    key.starts_with(DRAFTS_PREFIX) || key.starts_with(PENDING_PREFIX)
+}
+
+// 批准请求和批准的签名内容，action 为 request 或 approve，两者不能互相替代
+fn approval_message(action: &str, name: &str, version: &str, channel: &str) -> String {
+    format!("beepkg {} {}@{} {}", action, name, version, channel)
+}
+
+// 注册表元数据对象
+const REGISTRY_METADATA_KEY: &str = "registry-metadata.json";
+
+// 安装时依赖所在的子目录
+const DEPS_DIR: &str = "deps";
+
+// 请求的默认超时时间
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+// S3 预签名地址的最长有效期（7 天）
+const MAX_PRESIGN_EXPIRY: Duration = Duration::from_secs(7 * 24 * 3600);
+
+// age 接收者加密和 KMS 信封加密在内存中处理整个文件，超过该大小时拒绝处理（512 MiB）
+const MAX_IN_MEMORY_CIPHER_SIZE: u64 = 512 * 1024 * 1024;
+
+// 从归档存储类型中取回的备份保持可读的天数
+const BACKUP_RESTORE_DAYS: u32 = 7;
+
 // 自定义结构体用于解析 XML 响应
 #[derive(Debug, Deserialize)]
 struct ListObjectsResponse {
     #[serde(rename = "Contents", default)]
     contents: Vec<S3Object>,
+    #[serde(rename = "IsTruncated", default)]
+    is_truncated: bool,
+    #[serde(rename = "NextContinuationToken")]
+    next_continuation_token: Option<String>,
+}
+
+// stream_packages 的分页状态
+struct PackagePages {
+    continuation_token: Option<String>,
+    done: bool,
+    registry: Option<models::RegistryMetadata>,
+    // 上一页末尾的包及其已列出的产物
+    carried: Vec<S3Object>,
+}
+
+// 推送的包文件及随它上传的内容，push_package、force_push_package 和 push_archive 都交给 publish_archive 发布
+struct Publication<'a> {
+    metadata: &'a models::PackageMetadata,
+    // 包文件的对象键，由 push_key 得到
+    zip_name: &'a str,
+    zip_path: &'a Path,
+    // 整个包文件加密时的设置，按文件加密或不加密时为 None
+    encryption: Option<&'a models::EncryptionConfig>,
+    // 从包目录推送时随包上传的平台产物和命名产物：包目录和产物布局
+    artifacts: Option<(&'a Path, &'a artifacts::ArtifactLayout)>,
+    // 按文件加密的设置，产物中匹配的文件同样加密
+    selective: &'a Option<(FileSelector, Cipher)>,
+    // 构建证明中的源码仓库信息取自该目录
+    source_dir: &'a Path,
+    sbom: Option<(SbomFormat, serde_json::Value)>,
+    readme: Option<Vec<u8>>,
+    started_on: chrono::DateTime<chrono::Utc>,
+}
+
+// 推送时上传的 README 副本的对象名
+fn readme_key(archive_name: &str) -> String {
+    format!("{}.readme", archive_name)
+}
+
+// 读取 pack.toml 中声明的 README，推送前确认文件存在
+fn read_declared_readme(
+    package_path: &Path,
+    metadata: &models::PackageMetadata,
+) -> Result<Option<Vec<u8>>, BeepkgError> {
+    let Some(readme) = &metadata.readme else {
+        return Ok(None);
+    };
+    let path = package_path.join(readme);
+    let content = std::fs::read(&path)
+        .map_err(|e| format!("README {} not readable: {}", path.display(), e))?;
+    Ok(Some(content))
+}
+
+// 包元数据摘要的对象名后缀
+const PACKAGE_INFO_SUFFIX: &str = ".meta.json";
+
+fn package_info_key(archive_name: &str) -> String {
+    format!("{}{}", archive_name, PACKAGE_INFO_SUFFIX)
+}
+
+// 从对象列表中识别包归档，产物需与归档在同一列表中
+fn packages_from_objects(objects: &[S3Object]) -> Vec<models::Package> {
+    let artifact_objects: Vec<(&str, u64)> = objects
+        .iter()
+        .filter(|obj| artifacts::is_artifact_key(&obj.key))
+        .map(|obj| (obj.key.as_str(), obj.size.unwrap_or(0)))
+        .collect();
+
+    let mut packages = Vec::new();
+    for obj in objects {
+        if is_staged_key(&obj.key) {
+            continue;
+        }
+        if let Some((name, version)) = models::parse_archive_key(&obj.key) {
+            packages.push(models::Package {
+                name: name.to_string(),
+                version: version.to_string(),
+                author: String::new(),      // Will be populated from metadata
+                description: String::new(), // Will be populated from metadata
+                license: None,
+                homepage: None,
+                repository: None,
+                keywords: Vec::new(),
+                readme: None,
+                maintainers: Vec::new(),
+                dependencies: HashMap::new(), // Will be populated from metadata
+                encryption: None,
+                is_locked: false,
+                lock_reason: None,
+                channels: Vec::new(),
+                labels: BTreeMap::new(),
+                storage: models::Storage {
+                    path: obj.key.clone(),
+                    checksum: String::new(),
+                    size: obj.size.unwrap_or(0),
+                    created_at: obj.last_modified.clone().unwrap_or_default(),
+                },
+                artifacts: artifacts::list(&obj.key, artifact_objects.iter().copied()),
+            });
+        }
+    }
+    packages
+}
+
+#[derive(Debug, Deserialize)]
+struct ListMultipartUploadsResponse {
+    #[serde(rename = "Upload", default)]
+    uploads: Vec<MultipartUpload>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MultipartUpload {
+    #[serde(rename = "Key")]
+    key: String,
+    #[serde(rename = "UploadId")]
+    upload_id: String,
+    #[serde(rename = "Initiated")]
+    initiated: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -46,23 +354,221 @@ struct S3Object {
     size: Option<u64>,
     #[serde(rename = "LastModified")]
     last_modified: Option<String>,
+    #[serde(rename = "ETag")]
+    etag: Option<String>,
+}
+
+// 拉取时已获取的解密密钥，选择性加密的包中多个文件共用
+#[derive(Default)]
+struct DecryptionKeys {
+    secret: Option<String>,
+    identities: Option<String>,
+    data_keys: HashMap<String, Vec<u8>>,
+}
+
+impl DecryptionKeys {
+    // 口令只读取一次（prompt 来源不会重复询问）
+    fn secret(&mut self) -> Result<&str, SecurityError> {
+        if self.secret.is_none() {
+            self.secret = Some(SecretSource::from_env()?.read()?);
+        }
+        Ok(self.secret.as_deref().unwrap_or_default())
+    }
 }
 
 pub struct PackageManager {
     bucket: Bucket,
     client: ReqwestClient,
-    credentials: Option<Credentials>,
+    // 创建 HTTP 客户端使用的超时、TLS 和代理设置
+    timeout: Duration,
+    tls: config::TlsConfig,
+    proxy: Option<config::ProxyConfig>,
+    credentials: Option<aws::AwsCredentials>,
+    credential_provider: Option<aws::CredentialProvider>,
+    // 从凭证来源获取的临时凭证：None 表示尚未获取，Some(None) 表示来源不可用
+    temporary_credentials: Mutex<Option<Option<aws::AwsCredentials>>>,
+    bearer_token: Option<String>,
+    actor: String,
+    // 操作者所属的团队，用于检查 team:<name> 可见性和授权
+    teams: Vec<String>,
+    trusted_keys: Vec<signing::VerifyingKey>,
+    // 签署批准请求和批准的私钥，未设置时取自 BEEPKG_SIGNING_KEY
+    approval_key: Option<signing::SigningKey>,
+    require_signature: bool,
+    trust_policy: Option<TrustPolicy>,
+    license_policy: Option<LicensePolicy>,
+    release_notes: Option<String>,
+    // 解析最新版本和版本范围时是否包含预发布版本
+    include_prerelease: bool,
+    provenance: bool,
+    sbom: Option<SbomFormat>,
+    // 推送后指向新版本的发布频道
+    channel: Option<String>,
+    // 拉取的平台产物，未设置时使用主机平台
+    target: Option<String>,
+    // 是否运行包声明的 pre_push / post_pull 钩子
+    allow_hooks: bool,
+    // 推送时额外排除的文件（工作区的公共 excludes）
+    excludes: Vec<String>,
+    // 下载过的包文件的本地缓存
+    cache: Option<Cache>,
+    // 离线模式：不发送任何请求，只使用本地缓存
+    offline: bool,
+    // 推送时上传从上一个版本生成的差量
+    deltas: bool,
+    // 推送为草稿，对象上传到 drafts/ 下
+    draft: bool,
+    // 推送后检查上传的对象
+    upload_verification: UploadVerification,
+    server_side_encryption: Option<models::ServerSideEncryption>,
+    // 存放备份的独立 bucket（可以位于其他端点），请求按其自身的凭证签名
+    backup_store: Option<Box<PackageManager>>,
+    // 备份对象的 S3 存储类型，例如 GLACIER、STANDARD_IA
+    backup_storage_class: Option<String>,
+    // 接收操作进度事件，默认打印到标准输出
+    observer: Arc<dyn Observer>,
 }
 
-impl PackageManager {
-    pub fn new(
-        endpoint: &str,
-        access_key: &str,
-        secret_key: &str,
-        bucket: &str,
-    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+/// PackageManager 的构造器，build 时检查设置是否完整
+///
+/// build 只使用构造器上的设置，不读取配置文件和环境变量；需要时显式调用 with_config 或 from_env
+#[derive(Debug, Clone, Default)]
+pub struct PackageManagerBuilder {
+    endpoint: Option<String>,
+    bucket: Option<String>,
+    access_key: String,
+    secret_key: String,
+    url_style: Option<config::UrlStyle>,
+    region: Option<String>,
+    timeout: Option<Duration>,
+    // 寻址方式、签名区域、凭证助手、TLS 和代理的默认值
+    config: Config,
+    // 以下设置由 from_env 读取
+    session_token: Option<String>,
+    bearer_token: Option<String>,
+    saved_tokens: Option<auth::TokenStore>,
+    assume_role: Option<aws::AssumeRole>,
+    environment_credentials: Option<aws::CredentialProvider>,
+    actor: Option<String>,
+    teams: Vec<String>,
+    trusted_keys: Vec<signing::VerifyingKey>,
+    server_side_encryption: Option<models::ServerSideEncryption>,
+    backup_storage_class: Option<String>,
+}
+
+impl PackageManagerBuilder {
+    /// 存储服务的端点，没有 http(s):// 前缀时使用 https
+    pub fn endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = Some(endpoint.into());
+        self
+    }
+
+    pub fn bucket(mut self, bucket: impl Into<String>) -> Self {
+        self.bucket = Some(bucket.into());
+        self
+    }
+
+    /// S3 静态凭证，两者都为空时不签名（或使用凭证助手、实例配置文件等来源）
+    pub fn credentials(
+        mut self,
+        access_key: impl Into<String>,
+        secret_key: impl Into<String>,
+    ) -> Self {
+        self.access_key = access_key.into();
+        self.secret_key = secret_key.into();
+        self
+    }
+
+    /// bucket 的寻址方式，未设置时使用 with_config / from_env 读取的设置，默认 path
+    pub fn url_style(mut self, url_style: Option<config::UrlStyle>) -> Self {
+        self.url_style = url_style;
+        self
+    }
+
+    /// 签名区域，也用于 STS 请求，未设置时使用 with_config / from_env 读取的设置，默认 us-east-1
+    pub fn region(mut self, region: Option<String>) -> Self {
+        self.region = region;
+        self
+    }
+
+    /// 单个请求的超时时间，默认 30 秒
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// 使用配置文件中的寻址方式、签名区域、凭证助手、TLS 和代理设置，构造器上显式设置的值优先
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// 读取配置文件和环境变量，CLI 使用这种方式创建 PackageManager：
+    ///
+    /// - S3_URL_STYLE、S3_REGION（其次为配置文件和 AWS_REGION），BEEPKG_CA_BUNDLE、BEEPKG_PROXY 等
+    ///   覆盖配置文件的 TLS 和代理设置
+    /// - S3_SESSION_TOKEN，BEEPKG_TOKEN 和 `beepkg login` 保存的令牌
+    /// - BEEPKG_ASSUME_ROLE，未配置密钥时使用的 EKS IRSA 或 EC2 实例配置文件
+    /// - 审计日志的操作者 BEEPKG_USER（其次为 USER、USERNAME）和 BEEPKG_TEAMS
+    /// - BEEPKG_TRUSTED_KEYS、BEEPKG_SSE 和 BEEPKG_BACKUP_STORAGE_CLASS
+    pub fn from_env(self) -> Result<Self, BeepkgError> {
+        let mut config = Config::from_env()?;
+        if let Ok(style) = std::env::var("S3_URL_STYLE") {
+            config.url_style = Some(style.parse()?);
+        }
+        config.region = std::env::var("S3_REGION")
+            .ok()
+            .or(config.region)
+            .or_else(aws::env_region);
+        config.tls = config.tls.with_env()?;
+        config.proxy = config::ProxyConfig::from_env().or(config.proxy);
+
+        let mut builder = self.with_config(config);
+        builder.session_token = std::env::var("S3_SESSION_TOKEN").ok();
+        builder.bearer_token = auth::env_token();
+        builder.saved_tokens = auth::saved_tokens()?;
+        builder.assume_role = aws::AssumeRole::from_env()?;
+        builder.environment_credentials = aws::CredentialProvider::from_environment();
+        builder.actor = std::env::var("BEEPKG_USER")
+            .or_else(|_| std::env::var("USER"))
+            .or_else(|_| std::env::var("USERNAME"))
+            .ok();
+        builder.teams = std::env::var("BEEPKG_TEAMS")
+            .map(|teams| parse_teams(&teams))
+            .unwrap_or_default();
+        builder.trusted_keys = match std::env::var("BEEPKG_TRUSTED_KEYS") {
+            Ok(paths) => signing::load_trusted_keys(&paths)?,
+            Err(_) => Vec::new(),
+        };
+        builder.server_side_encryption = models::ServerSideEncryption::from_env()?;
+        builder.backup_storage_class = std::env::var("BEEPKG_BACKUP_STORAGE_CLASS")
+            .ok()
+            .filter(|class| !class.trim().is_empty());
+        Ok(builder)
+    }
+
+    pub fn build(self) -> Result<PackageManager, BeepkgError> {
+        let endpoint = self
+            .endpoint
+            .filter(|endpoint| !endpoint.trim().is_empty())
+            .ok_or_else(|| BeepkgError::Config("an endpoint is required".to_string()))?;
+        let bucket = self
+            .bucket
+            .filter(|bucket| !bucket.trim().is_empty())
+            .ok_or_else(|| BeepkgError::Config("a bucket is required".to_string()))?;
+        if self.access_key.is_empty() != self.secret_key.is_empty() {
+            return Err(BeepkgError::Config(
+                "access key and secret key must be set together".to_string(),
+            ));
+        }
+        let timeout = self.timeout.unwrap_or(DEFAULT_TIMEOUT);
+        if timeout.is_zero() {
+            return Err(BeepkgError::Config("timeout must be positive".to_string()));
+        }
+        let (access_key, secret_key) = (self.access_key, self.secret_key);
+
         // 处理端点 URL，确保是正确的绝对 URL
-        println!("原始端点: {}", endpoint);
+        log::debug!("原始端点: {}", endpoint);
 
         // 确保有 http(s):// 前缀
         let base_url = if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
@@ -74,89 +580,477 @@ impl PackageManager {
         // 删除末尾的斜杠
         let base_url = base_url.trim_end_matches('/').to_string();
 
-        println!("处理后的端点: {}", base_url);
+        log::debug!("处理后的端点: {}", base_url);
 
         // 创建 rusty-s3 bucket，使用 Url::parse 解析 URL
         let url = url::Url::parse(&base_url)?;
-        println!("解析的 URL: {}", url);
-
-        let bucket = Bucket::new(
-            url,
-            UrlStyle::Path,
-            bucket.to_string(),
-            "us-east-1".to_string(),
-        )?;
+        log::debug!("解析的 URL: {}", url);
 
-        println!("创建的 bucket URL: {}", bucket.base_url());
+        let config = self.config;
+        let url_style = match self.url_style.or(config.url_style).unwrap_or_default() {
+            config::UrlStyle::Path => UrlStyle::Path,
+            config::UrlStyle::VirtualHost => UrlStyle::VirtualHost,
+        };
+        let region = self
+            .region
+            .or(config.region)
+            .unwrap_or_else(|| aws::DEFAULT_REGION.to_string());
+        let bucket = Bucket::new(url, url_style, bucket.to_string(), region.clone())?;
+
+        log::debug!("创建的 bucket URL: {}", bucket.base_url());
+
+        // 配置了令牌时使用 Bearer 认证，请求不再做 S3 签名
+        let bearer_token = self.bearer_token.or_else(|| {
+            self.saved_tokens
+                .as_ref()
+                .and_then(|tokens| tokens.get(&base_url))
+                .map(str::to_string)
+        });
+        if bearer_token.is_some() {
+            log::debug!("使用令牌认证");
+        }
 
         // 准备凭证
-        let credentials = if !access_key.is_empty() && !secret_key.is_empty() {
-            Some(Credentials::new(
-                access_key.to_string(),
-                secret_key.to_string(),
-            ))
+        let credentials = if bearer_token.is_some() {
+            None
+        } else if !access_key.is_empty() && !secret_key.is_empty() {
+            Some(aws::AwsCredentials {
+                access_key_id: access_key.to_string(),
+                secret_access_key: secret_key.to_string(),
+                session_token: self.session_token,
+                expiration: None,
+            })
         } else {
             None
         };
 
-        // 创建 HTTP 客户端
-        let client = ReqwestClient::builder()
-            .timeout(Duration::from_secs(30))
-            .build()?;
+        // 配置了 AssumeRole 时，请求签名前先通过 STS 换取临时凭证；
+        // 未配置密钥时依次使用凭证助手、EKS IRSA 或 EC2 实例配置文件
+        let credential_provider = match self.assume_role {
+            _ if bearer_token.is_some() => None,
+            Some(role) => Some(aws::CredentialProvider::AssumeRole {
+                region: region.clone(),
+                role,
+            }),
+            None if credentials.is_some() => None,
+            None => match config.credential_helper {
+                Some(program) => Some(aws::CredentialProvider::Helper {
+                    program,
+                    endpoint: base_url.clone(),
+                }),
+                None => self
+                    .environment_credentials
+                    .map(|provider| provider.with_region(&region)),
+            },
+        };
+
+        // 创建 HTTP 客户端，使用私有 CA、客户端证书和代理
+        let tls = config.tls;
+        let proxy = config.proxy;
+        let client = http_client(&tls, proxy.as_ref(), timeout)?;
+
+        // 审计日志中记录的操作者
+        let actor = self.actor.unwrap_or_else(|| "unknown".to_string());
+        let teams = self.teams;
+
+        // 拉取时用于验证签名的受信任公钥
+        let trusted_keys = self.trusted_keys;
+
+        // 由存储层加密的注册表要求每次上传都携带 SSE 请求头
+        let server_side_encryption = self.server_side_encryption;
+
+        let backup_storage_class = self.backup_storage_class;
 
-        Ok(Self {
+        Ok(PackageManager {
             bucket,
             client,
+            timeout,
+            tls,
+            proxy,
             credentials,
+            credential_provider,
+            temporary_credentials: Mutex::new(None),
+            bearer_token,
+            actor,
+            teams,
+            trusted_keys,
+            approval_key: None,
+            require_signature: false,
+            trust_policy: None,
+            license_policy: None,
+            release_notes: None,
+            include_prerelease: false,
+            provenance: false,
+            sbom: None,
+            channel: None,
+            target: None,
+            allow_hooks: false,
+            excludes: Vec::new(),
+            cache: None,
+            offline: false,
+            deltas: false,
+            draft: false,
+            upload_verification: UploadVerification::default(),
+            server_side_encryption,
+            backup_store: None,
+            backup_storage_class,
+            observer: Arc::new(SilentObserver),
         })
     }
+}
 
-    pub async fn list_packages(
-        &self,
-    ) -> Result<Vec<models::Package>, Box<dyn Error + Send + Sync>> {
-        let mut packages = Vec::new();
+impl PackageManager {
+    /// 创建 PackageManager 的构造器
+    pub fn builder() -> PackageManagerBuilder {
+        PackageManagerBuilder::default()
+    }
 
-        // 创建列表对象的操作
-        let action = self.bucket.list_objects_v2(self.credentials.as_ref());
-        let url = action.sign(Duration::from_secs(3600));
+    /// 寻址方式和签名区域依次取自 S3_URL_STYLE / S3_REGION、配置文件和默认值（path、AWS_REGION 或 us-east-1）
+    #[deprecated(note = "use PackageManager::builder()")]
+    pub fn new(
+        endpoint: &str,
+        access_key: &str,
+        secret_key: &str,
+        bucket: &str,
+    ) -> Result<Self, BeepkgError> {
+        Self::builder()
+            .endpoint(endpoint)
+            .bucket(bucket)
+            .credentials(access_key, secret_key)
+            .from_env()?
+            .build()
+    }
 
-        // 执行请求
-        let response = self.client.get(url).send().await?;
-        let content = response.text().await?;
+    /// 使用 Bearer 令牌认证（面向 HTTP 注册表服务和支持令牌的 S3 网关），设置后不再使用 S3 签名
+    pub fn bearer_token(mut self, token: Option<String>) -> Self {
+        if token.is_some() {
+            self.credentials = None;
+            self.credential_provider = None;
+        }
+        self.bearer_token = token;
+        self
+    }
 
-        // 解析 XML 响应
-        let list_result: ListObjectsResponse = from_str(&content)?;
-
-        for obj in list_result.contents {
-            if let Some(name) = obj.key.strip_suffix(".zip") {
-                let parts: Vec<&str> = name.split('-').collect();
-                if parts.len() >= 2 {
-                    packages.push(models::Package {
-                        name: parts[0..parts.len() - 1].join("-"),
-                        version: parts.last().unwrap().to_string(),
-                        author: String::new(), // Will be populated from metadata
-                        description: String::new(), // Will be populated from metadata
-                        dependencies: HashMap::new(), // Will be populated from metadata
-                        encryption: None,
-                        is_locked: false,
-                        lock_reason: None,
-                        storage: models::Storage {
-                            path: obj.key.clone(),
-                            checksum: String::new(),
-                            size: obj.size.unwrap_or(0),
-                            created_at: obj.last_modified.unwrap_or_default(),
-                        },
-                    });
+    /// 静态凭证附带的会话令牌，用于 STS 颁发的临时凭证
+    pub fn session_token(mut self, token: Option<String>) -> Self {
+        if let Some(credentials) = &mut self.credentials {
+            credentials.session_token = token;
+        }
+        self
+    }
+
+    /// 请求签名前通过 STS AssumeRole 换取临时凭证，源凭证为静态凭证（未配置时读取 AWS 环境变量）
+    pub fn assume_role(mut self, role: Option<aws::AssumeRole>) -> Self {
+        self.credential_provider = role.map(|role| aws::CredentialProvider::AssumeRole {
+            region: self.bucket.region().to_string(),
+            role,
+        });
+        *self.temporary_credentials.get_mut().unwrap() = None;
+        self
+    }
+
+    /// 使用指定的 TLS 设置（私有 CA、mTLS 客户端证书）重新创建 HTTP 客户端
+    pub fn tls(mut self, tls: &config::TlsConfig) -> Result<Self, BeepkgError> {
+        self.client = http_client(tls, self.proxy.as_ref(), self.timeout)?;
+        self.tls = tls.clone();
+        Ok(self)
+    }
+
+    /// 请求经过指定的代理（HTTP 或 SOCKS5），None 时使用 HTTP_PROXY 等环境变量
+    pub fn proxy(mut self, proxy: Option<config::ProxyConfig>) -> Result<Self, BeepkgError> {
+        self.client = http_client(&self.tls, proxy.as_ref(), self.timeout)?;
+        self.proxy = proxy;
+        Ok(self)
+    }
+
+    /// 把备份存放到独立的 bucket（可以位于其他端点、使用其他凭证），
+    /// 主 bucket 被删除后备份仍然可用
+    pub fn backup_store(mut self, store: Option<PackageManager>) -> Self {
+        self.backup_store = store.map(Box::new);
+        self
+    }
+
+    /// 备份对象的 S3 存储类型，例如 GLACIER
+    pub fn backup_storage_class(mut self, storage_class: Option<String>) -> Self {
+        self.backup_storage_class = storage_class;
+        self
+    }
+
+    /// 接收打包、上传、下载和校验等进度事件，默认忽略所有事件
+    pub fn observer(mut self, observer: Arc<dyn Observer>) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    /// 审计日志和批准记录中的操作者，默认取 BEEPKG_USER、USER 或 USERNAME
+    pub fn actor(mut self, actor: impl Into<String>) -> Self {
+        self.actor = actor.into();
+        self
+    }
+
+    /// 操作者所属的团队，决定 team:<name> 可见性的包是否可见
+    pub fn teams(mut self, teams: Vec<String>) -> Self {
+        self.teams = teams;
+        self
+    }
+
+    /// 验证包签名和批准签名的受信任公钥，默认取自 BEEPKG_TRUSTED_KEYS
+    pub fn trusted_keys(mut self, keys: Vec<signing::VerifyingKey>) -> Self {
+        self.trusted_keys = keys;
+        self
+    }
+
+    /// 签署受保护频道的批准请求和批准的 ed25519 私钥，公钥必须在受信任公钥中。
+    /// 未设置时使用 BEEPKG_SIGNING_KEY
+    pub fn approval_key(mut self, key: Option<signing::SigningKey>) -> Self {
+        self.approval_key = key;
+        self
+    }
+
+    /// 拉取时要求包必须带有受信任公钥的有效签名
+    pub fn require_signature(mut self, required: bool) -> Self {
+        self.require_signature = required;
+        self
+    }
+
+    /// 拉取时在解压前检查的项目信任策略
+    pub fn trust_policy(mut self, policy: Option<TrustPolicy>) -> Self {
+        self.trust_policy = policy;
+        self
+    }
+
+    /// 拉取时在解压前检查的项目许可证策略
+    pub fn license_policy(mut self, policy: Option<LicensePolicy>) -> Self {
+        self.license_policy = policy;
+        self
+    }
+
+    /// 推送时生成并上传 SLSA 构建来源证明
+    pub fn provenance(mut self, enabled: bool) -> Self {
+        self.provenance = enabled;
+        self
+    }
+
+    /// 解析最新版本和版本范围时包含 -alpha、-rc 等预发布版本
+    pub fn prerelease(mut self, enabled: bool) -> Self {
+        self.include_prerelease = enabled;
+        self
+    }
+
+    /// 推送时生成并上传指定格式的 SBOM
+    pub fn sbom(mut self, format: Option<SbomFormat>) -> Self {
+        self.sbom = format;
+        self
+    }
+
+    /// 推送时记录的发布说明，优先于 pack.toml 中的 [release] notes
+    pub fn release_notes(mut self, notes: Option<String>) -> Self {
+        self.release_notes = notes;
+        self
+    }
+
+    /// 推送成功后把该发布频道（例如 beta）指向新版本
+    pub fn channel(mut self, channel: Option<String>) -> Self {
+        self.channel = channel;
+        self
+    }
+
+    /// 拉取指定平台（例如 linux-x86_64）的产物，而不是主机平台
+    pub fn target(mut self, target: Option<String>) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// 运行包在 pack.toml [hooks] 中声明的脚本，默认只提示并跳过
+    pub fn allow_hooks(mut self, allow: bool) -> Self {
+        self.allow_hooks = allow;
+        self
+    }
+
+    /// 推送时不打包的文件（相对于包目录的 glob），用于工作区的公共 excludes
+    pub fn excludes(mut self, patterns: Vec<String>) -> Self {
+        self.excludes = patterns;
+        self
+    }
+
+    /// 拉取时使用的本地缓存，相同内容只下载一次
+    pub fn cache(mut self, cache: Option<Cache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// 离线模式：禁止所有网络请求，拉取时只使用本地缓存中验证过的包
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// 推送时生成并上传从上一个版本升级的差量，拉取升级时只需下载变化的部分
+    pub fn deltas(mut self, deltas: bool) -> Self {
+        self.deltas = deltas;
+        self
+    }
+
+    /// 推送为草稿：对象上传到 drafts/ 下，`release` 发布之前对列表、拉取和解析都不可见
+    pub fn draft(mut self, draft: bool) -> Self {
+        self.draft = draft;
+        self
+    }
+
+    /// 推送后检查上传的包文件和产物，存储中的内容与发送的不一致时删除对象并报错
+    pub fn upload_verification(mut self, verification: UploadVerification) -> Self {
+        self.upload_verification = verification;
+        self
+    }
+
+    /// 所有上传请求使用的服务端加密方式
+    pub fn server_side_encryption(mut self, sse: Option<models::ServerSideEncryption>) -> Self {
+        self.server_side_encryption = sse;
+        self
+    }
+
+    pub async fn list_packages(&self) -> Result<Vec<models::Package>, BeepkgError> {
+        let packages: Vec<models::Package> = self.stream_packages().try_collect().await?;
+        if let Some(cache) = &self.cache {
+            // 记录包名称供补全使用，失败不影响列出
+            let mut names: Vec<String> = packages.iter().map(|p| p.name.clone()).collect();
+            names.dedup();
+            let _ = cache.store_package_names(&self.cache_registry(), &names);
+        }
+        Ok(packages)
+    }
+
+    /// 补全包名称，只读本地缓存，不访问注册表。prefix 包含 @ 时补全缓存中已有的版本
+    pub fn complete_packages(&self, prefix: &str) -> Vec<String> {
+        let Some(cache) = &self.cache else {
+            return Vec::new();
+        };
+        let registry = self.cache_registry();
+        match prefix.split_once('@') {
+            Some((name, version)) => {
+                let mut versions = cache.versions(&registry, name);
+                versions.sort();
+                versions
+                    .iter()
+                    .rev()
+                    .map(|v| v.to_string())
+                    .filter(|v| v.starts_with(version))
+                    .map(|v| format!("{}@{}", name, v))
+                    .collect()
+            }
+            None => cache.package_names(&registry, prefix),
+        }
+    }
+
+    /// 按条件列出包：先按名称和版本过滤，需要时再读取剩余包的元数据（每个包一个请求），
+    /// 最后排序并分页
+    pub async fn query_packages(
+        &self,
+        query: &listing::ListQuery,
+    ) -> Result<Vec<models::Package>, BeepkgError> {
+        let mut packages: Vec<models::Package> = self
+            .list_packages()
+            .await?
+            .into_iter()
+            .filter(|package| query.matches_listing(package))
+            .collect();
+        if query.needs_details() {
+            for package in &mut packages {
+                match self.archive_info(&package.storage.path).await {
+                    Ok(info) => {
+                        package.author = info.author;
+                        package.description = info.description;
+                        package.license = info.license;
+                        package.homepage = info.homepage;
+                        package.keywords = info.keywords;
+                        package.labels = info.labels;
+                    }
+                    Err(e) => self.emit(Event::Warning(format!(
+                        "Failed to read metadata of {}@{}: {}",
+                        package.name, package.version, e
+                    ))),
                 }
             }
         }
-        Ok(packages)
+        if query.markers {
+            let registry = self.get_registry_metadata().await?;
+            let now = chrono::Utc::now();
+            for package in &mut packages {
+                let lock = registry.locked_packages.iter().find(|lock| {
+                    lock.name == package.name
+                        && lock.version == package.version
+                        && !lock.is_expired(now)
+                });
+                package.is_locked = lock.is_some();
+                package.lock_reason = lock.map(|lock| lock.lock_reason.clone());
+                package.channels = registry
+                    .channels
+                    .get(&package.name)
+                    .into_iter()
+                    .flatten()
+                    .filter(|(_, version)| **version == package.version)
+                    .map(|(channel, _)| channel.clone())
+                    .collect();
+            }
+        }
+        packages.retain(|package| query.matches(package));
+        Ok(query.arrange(packages))
+    }
+
+    /// 逐页列出包，按需请求下一页，适合在不缓冲整个列表的情况下处理大型注册表
+    pub fn stream_packages(&self) -> impl Stream<Item = Result<models::Package, BeepkgError>> + '_ {
+        let pages = PackagePages {
+            continuation_token: None,
+            done: false,
+            registry: None,
+            carried: Vec::new(),
+        };
+        stream::try_unfold(pages, move |pages| self.next_package_page(pages))
+            .map_ok(|packages| stream::iter(packages.into_iter().map(Ok)))
+            .try_flatten()
     }
 
-    pub async fn push_package(
+    async fn next_package_page(
         &self,
-        package_path: &Path,
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        mut pages: PackagePages,
+    ) -> Result<Option<(Vec<models::Package>, PackagePages)>, BeepkgError> {
+        if pages.done {
+            return Ok(None);
+        }
+        // 隐藏当前用户无权查看的包
+        let registry = match pages.registry.take() {
+            Some(registry) => registry,
+            None => self.get_registry_metadata().await?,
+        };
+
+        let page = self
+            .list_objects_page("", None, pages.continuation_token.as_deref())
+            .await?;
+        let mut objects = std::mem::take(&mut pages.carried);
+        objects.extend(page.contents);
+
+        match page.next_continuation_token {
+            Some(token) if page.is_truncated => {
+                pages.continuation_token = Some(token);
+                // 本页最后一个包的产物可能在下一页，留到下一页一起处理
+                let split = objects
+                    .iter()
+                    .rposition(|obj| !artifacts::is_artifact_key(&obj.key))
+                    .unwrap_or(0);
+                pages.carried = objects.split_off(split);
+            }
+            _ => pages.done = true,
+        }
+
+        let mut packages = packages_from_objects(&objects);
+        packages.retain(|package| self.can_access(&registry, &package.name));
+        pages.registry = Some(registry);
+        Ok(Some((packages, pages)))
+    }
+
+    pub async fn push_package(&self, package_path: &Path) -> Result<(), BeepkgError> {
+        let started_on = chrono::Utc::now();
+
         // Validate package path exists
         if !package_path.exists() {
             return Err("Package path does not exist".into());
@@ -166,17 +1060,18 @@ impl PackageManager {
         let toml_path = package_path.join("pack.toml");
         let json_path = package_path.join("pack.json");
 
-        let mut metadata: models::PackageMetadata = if toml_path.exists() {
+        let metadata: models::PackageMetadata = if toml_path.exists() {
             // 读取TOML格式
             let toml_content = std::fs::read_to_string(&toml_path)?;
-            toml::from_str(&toml_content)?
+            schema::from_toml(&toml_content)?
         } else if json_path.exists() {
             // 读取JSON格式
             let json_content = std::fs::read_to_string(&json_path)?;
-            serde_json::from_str(&json_content)?
+            schema::from_json(json_content.as_bytes())?
         } else {
             return Err("Neither pack.toml nor pack.json found in package directory".into());
         };
+        self.check_publish(&metadata.name).await?;
 
         // 检查包是否已存在以及版本冲突
         match self
@@ -188,196 +1083,208 @@ impl PackageManager {
                     // 继续处理，没有冲突
                 }
                 PackageConflictStatus::VersionExists => {
-                    return Err(format!("Package {}@{} already exists. Use --force to overwrite or choose a different version.", 
-                        metadata.name, metadata.version).into());
+                    return Err(BeepkgError::Conflict(format!(
+                        "Package {}@{} already exists. Use --force to overwrite or choose a different version.",
+                        metadata.name, metadata.version
+                    )));
                 }
                 PackageConflictStatus::HigherVersionExists(existing_version) => {
-                    return Err(format!("A higher version ({}) of package {} already exists. Current version: {}. Use --force to ignore this warning or choose a higher version.", 
-                        existing_version, metadata.name, metadata.version).into());
+                    return Err(BeepkgError::Conflict(format!(
+                        "A higher version ({}) of package {} already exists. Current version: {}. Use --force to ignore this warning or choose a higher version.",
+                        existing_version, metadata.name, metadata.version
+                    )));
                 }
             },
-            Err(e) => {
-                return Err(format!("Error checking package conflicts: {}", e).into());
-            }
+            Err(e) => return Err(e),
         }
+        hooks::run(
+            Hook::PrePush,
+            &metadata,
+            package_path,
+            self.allow_hooks,
+            &*self.observer,
+        )
+        .await?;
+        let declared_readme = read_declared_readme(package_path, &metadata)?;
 
         // Create zip archive
-        let zip_name = format!("{}-{}.zip", metadata.name, metadata.version);
+        let zip_name = self.push_key(&metadata).await?;
         let storage_dir = std::env::var("LOCAL_STORAGE_DIR")
             .map(PathBuf::from)
             .unwrap_or_else(|_| std::env::temp_dir());
-        let zip_path = storage_dir.join(&zip_name);
-        println!("Using storage directory: {:?}", storage_dir);
+        let zip_path = storage_dir.join(local_file_name(&zip_name));
+        self.emit(Event::Info(format!(
+            "Using storage directory: {:?}",
+            storage_dir
+        )));
+        let encryption = metadata.encryption.as_ref().filter(|e| e.enabled);
+        let selective = self.selective_encryption(encryption).await?;
+        let layout = artifacts::ArtifactLayout::new(&metadata)?.exclude(&self.excludes)?;
         let file = std::fs::File::create(&zip_path)?;
         let mut zip = zip::ZipWriter::new(file);
 
         // Add files to zip
+        self.emit(Event::ArchiveStarted {
+            path: zip_path.clone(),
+        });
         for entry in walkdir::WalkDir::new(package_path) {
             let entry = entry?;
             if entry.file_type().is_file() {
                 let path = entry.path();
-                let relative_path = path.strip_prefix(package_path)?;
-                zip.start_file(relative_path.to_string_lossy(), Default::default())?;
-                std::io::copy(&mut std::fs::File::open(path)?, &mut zip)?;
+                let relative_path = path.strip_prefix(package_path)?.to_string_lossy();
+                // 平台相关的文件和命名产物单独上传
+                if layout.excludes(&relative_path) {
+                    continue;
+                }
+                zip.start_file(relative_path.clone(), Default::default())?;
+                let (bytes, encrypted) = match &selective {
+                    Some((selector, cipher)) if selector.matches(&relative_path) => {
+                        (encrypt_file(cipher, path, &mut zip)?, true)
+                    }
+                    _ => (
+                        std::io::copy(&mut std::fs::File::open(path)?, &mut zip)?,
+                        false,
+                    ),
+                };
+                self.emit(Event::FileArchived {
+                    path: path.to_path_buf(),
+                    bytes,
+                    encrypted,
+                });
             }
         }
         zip.finish()?;
+        self.emit(Event::ArchiveFinished {
+            path: zip_path.clone(),
+        });
 
-        // Read zip file content
-        let mut file_content = std::fs::read(&zip_path)?;
-
-        // Check if encryption is enabled in pack.toml
-        if let Some(encryption) = &metadata.encryption {
-            if encryption.enabled {
-                let security = SecurityManager::new();
-                let (encrypted_data, salt) = SecurityManager::encrypt_data(&file_content)
-                    .map_err(|e| format!("Encryption failed: {}", e))?;
+        let document = match self.sbom {
+            Some(format) => Some((
+                format,
+                sbom::generate(format, &metadata, &sbom::file_inventory(package_path)?),
+            )),
+            None => None,
+        };
+        self.publish_archive(
+            Publication {
+                metadata: &metadata,
+                zip_name: &zip_name,
+                zip_path: &zip_path,
+                encryption: encryption.filter(|_| selective.is_none()),
+                artifacts: Some((package_path, &layout)),
+                selective: &selective,
+                source_dir: package_path,
+                sbom: document,
+                readme: declared_readme,
+                started_on,
+            },
+            false,
+        )
+        .await?;
 
-                // Update encryption config with salt
-                if let Some(encryption) = &mut metadata.encryption {
-                    encryption.salt = Some(salt);
-                }
+        // Clean up temp file
+        std::fs::remove_file(zip_path)?;
+        Ok(())
+    }
 
-                file_content = encrypted_data.into_bytes();
+    // 检查包是否存在以及版本冲突。相同版本用 HEAD 请求检查，更高版本只列出同名包的对象，
+    // 不必列出整个 bucket
+    pub async fn check_package_conflict(
+        &self,
+        package_name: &str,
+        version: &str,
+    ) -> Result<PackageConflictStatus, BeepkgError> {
+        let key = format!("{}-{}.zip", package_name, version);
+        if self.head_object(&key).await?.is_some() {
+            // 检查包是否被锁定
+            let registry = self.get_registry_metadata().await?;
+            let now = chrono::Utc::now();
+            let lock = registry.locked_packages.iter().find(|lock| {
+                lock.name == package_name && lock.version == version && !lock.is_expired(now)
+            });
+            if let Some(lock) = lock {
+                return Err(BeepkgError::Locked(format!(
+                    "{}@{} cannot be modified. Reason: {}",
+                    package_name, version, lock.lock_reason
+                )));
             }
+            return Ok(PackageConflictStatus::VersionExists);
         }
 
-        // Calculate sha1 hash
-        let mut hasher = Sha1::new();
-        hasher.update(&file_content);
-        let checksum = format!("{:x}", hasher.finalize());
+        // 解析当前版本
+        let current_version = semver::Version::parse(version)
+            .map_err(|_| format!("Invalid version format: {}", version))?;
 
-        // Upload package file
-        let action = self.bucket.put_object(self.credentials.as_ref(), &zip_name);
-        let url = action.sign(Duration::from_secs(3600));
+        // 推送正式版本时忽略已有的预发布版本，例如已有 2.0.0-rc.1 时仍可发布 1.9.x
+        let highest_version = self
+            .package_versions(package_name)
+            .await?
+            .into_iter()
+            .filter(|existing| existing.pre.is_empty() || !current_version.pre.is_empty())
+            .filter(|existing| *existing > current_version)
+            .max();
+        if let Some(highest_version) = highest_version {
+            return Ok(PackageConflictStatus::HigherVersionExists(
+                highest_version.to_string(),
+            ));
+        }
 
-        let response = self
-            .client
-            .put(url)
-            .header("Content-Type", "application/zip")
-            .body(file_content)
-            .send()
-            .await?;
+        // 没有冲突
+        Ok(PackageConflictStatus::NoConflict)
+    }
 
-        if !response.status().is_success() {
-            return Err(format!("Failed to upload object: {}", response.status()).into());
+    /// 包的某个版本是否存在，无权查看时视为不存在。
+    /// 发送两个请求：读取注册表元数据（GET）检查访问权限，对包文件发送一个 HEAD 请求
+    pub async fn exists(&self, name: &str, version: &str) -> Result<bool, BeepkgError> {
+        let registry = self.get_registry_metadata().await?;
+        if !self.can_access(&registry, name) {
+            return Ok(false);
         }
+        let key = format!("{}-{}.zip", name, version);
+        Ok(self.head_object(&key).await?.is_some())
+    }
 
-        // Upload checksum file
-        let checksum_name = format!("{}.sha1", zip_name);
-        let action = self
-            .bucket
-            .put_object(self.credentials.as_ref(), &checksum_name);
-        let url = action.sign(Duration::from_secs(3600));
-
-        let response = self
-            .client
-            .put(url)
-            .header("Content-Type", "text/plain")
-            .body(checksum.clone())
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Err(format!("Failed to upload checksum file: {}", response.status()).into());
-        }
-
-        // Clean up temp file
-        std::fs::remove_file(zip_path)?;
-
-        // Update package checksum in registry metadata
-        let mut registry_meta = self.get_registry_metadata().await?;
-        if let Some(pkg) = registry_meta
-            .locked_packages
-            .iter_mut()
-            .find(|p| p.name == metadata.name && p.version == metadata.version)
-        {
-            pkg.checksum = checksum;
-        }
-        self.save_registry_metadata(&registry_meta).await?;
-
-        Ok(())
-    }
-
-    // 检查包是否存在以及版本冲突
-    pub async fn check_package_conflict(
+    /// 读取包文件的大小、ETag 和修改时间，版本不存在或无权查看时返回 None。
+    /// 与 exists 一样读取注册表元数据并发送 HEAD 请求；包文件不超过指针大小上限时
+    /// 还要下载它（一个 GET），判断是否是指向 blob 的指针并取得内容的实际大小
+    pub async fn stat(
         &self,
-        package_name: &str,
+        name: &str,
         version: &str,
-    ) -> Result<PackageConflictStatus, Box<dyn Error + Send + Sync>> {
-        // 获取所有可用包
-        let packages = self.list_packages().await?;
-
-        // 过滤出与给定包名相同的包
-        let same_name_packages: Vec<&models::Package> =
-            packages.iter().filter(|p| p.name == package_name).collect();
-
-        if same_name_packages.is_empty() {
-            // 没有同名包，没有冲突
-            return Ok(PackageConflictStatus::NoConflict);
-        }
-
-        // 检查是否有相同版本
-        for pkg in &same_name_packages {
-            if pkg.version == version {
-                // 检查包是否被锁定
-                if pkg.is_locked {
-                    return Err(format!(
-                        "Package {}@{} is locked and cannot be modified. Reason: {}",
-                        package_name,
-                        version,
-                        pkg.lock_reason.as_deref().unwrap_or("Unknown")
-                    )
-                    .into());
-                }
-                return Ok(PackageConflictStatus::VersionExists);
-            }
-        }
-
-        // 解析当前版本
-        let current_version = semver::Version::parse(version)
-            .map_err(|_| format!("Invalid version format: {}", version))?;
-
-        // 检查是否有更高版本
-        let mut higher_versions = Vec::new();
-
-        for pkg in same_name_packages {
-            if let Ok(existing_version) = semver::Version::parse(&pkg.version) {
-                if existing_version > current_version {
-                    higher_versions.push(pkg.version.clone());
-                }
-            }
-        }
-
-        if !higher_versions.is_empty() {
-            // 找出最高版本
-            let highest_version = higher_versions
-                .iter()
-                .max_by(|a, b| {
-                    let a_ver =
-                        semver::Version::parse(a).unwrap_or_else(|_| semver::Version::new(0, 0, 0));
-                    let b_ver =
-                        semver::Version::parse(b).unwrap_or_else(|_| semver::Version::new(0, 0, 0));
-                    a_ver.cmp(&b_ver)
-                })
-                .unwrap();
-
-            return Ok(PackageConflictStatus::HigherVersionExists(
-                highest_version.to_string(),
-            ));
+    ) -> Result<Option<models::PackageStat>, BeepkgError> {
+        let registry = self.get_registry_metadata().await?;
+        if !self.can_access(&registry, name) {
+            return Ok(None);
         }
-
-        // 没有冲突
-        Ok(PackageConflictStatus::NoConflict)
+        let key = format!("{}-{}.zip", name, version);
+        let Some(headers) = self.head_object(&key).await? else {
+            return Ok(None);
+        };
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let size = header("content-length")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0);
+        Ok(Some(models::PackageStat {
+            name: name.to_string(),
+            version: version.to_string(),
+            size: self.logical_size(&key, size).await?,
+            etag: header("etag").map(|etag| etag.trim_matches('"').to_string()),
+            last_modified: header("last-modified"),
+            key,
+        }))
     }
 
     // 强制推送包，忽略冲突
-    pub async fn force_push_package(
-        &self,
-        package_path: &Path,
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    pub async fn force_push_package(&self, package_path: &Path) -> Result<(), BeepkgError> {
+        let started_on = chrono::Utc::now();
+
         // Validate package path exists with debug info
-        println!("Validating package path: {:?}", package_path);
+        log::debug!("Validating package path: {:?}", package_path);
         if !package_path.exists() {
             return Err(format!("Package path does not exist: {:?}", package_path).into());
         }
@@ -385,114 +1292,407 @@ impl PackageManager {
         // 先尝试读取pack.toml，如果不存在再尝试pack.json
         let toml_path = package_path.join("pack.toml");
         let json_path = package_path.join("pack.json");
-        println!("Checking for metadata files at: {:?} and {:?}", toml_path, json_path);
+        log::debug!(
+            "Checking for metadata files at: {:?} and {:?}",
+            toml_path,
+            json_path
+        );
 
         let metadata: models::PackageMetadata = if toml_path.exists() {
-            println!("Found pack.toml at {:?}", toml_path);
+            log::debug!("Found pack.toml at {:?}", toml_path);
             let toml_content = std::fs::read_to_string(&toml_path)?;
-            toml::from_str(&toml_content)?
+            schema::from_toml(&toml_content)?
         } else if json_path.exists() {
-            println!("Found pack.json at {:?}", json_path);
+            log::debug!("Found pack.json at {:?}", json_path);
             let json_content = std::fs::read_to_string(&json_path)?;
-            serde_json::from_str(&json_content)?
+            schema::from_json(json_content.as_bytes())?
         } else {
             return Err(format!(
                 "Neither pack.toml nor pack.json found in package directory: {:?}",
                 package_path
-            ).into());
+            )
+            .into());
         };
+        self.check_publish(&metadata.name).await?;
+        self.ensure_not_overwriting(&metadata.name, &metadata.version)
+            .await?;
+        hooks::run(
+            Hook::PrePush,
+            &metadata,
+            package_path,
+            self.allow_hooks,
+            &*self.observer,
+        )
+        .await?;
+        let declared_readme = read_declared_readme(package_path, &metadata)?;
 
         // Create zip archive (不进行冲突检查)
-        let zip_name = format!("{}-{}.zip", metadata.name, metadata.version);
-        let zip_path = std::env::temp_dir().join(&zip_name);
-        println!("Creating zip archive at: {:?}", zip_path);
-        
+        let zip_name = self.push_key(&metadata).await?;
+        let zip_path = std::env::temp_dir().join(local_file_name(&zip_name));
+        let encryption = metadata.encryption.as_ref().filter(|e| e.enabled);
+        let selective = self.selective_encryption(encryption).await?;
+        let layout = artifacts::ArtifactLayout::new(&metadata)?.exclude(&self.excludes)?;
         let file = std::fs::File::create(&zip_path)?;
         let mut zip = zip::ZipWriter::new(file);
 
-        // Add files to zip with debug info
-        println!("Adding files to zip from: {:?}", package_path);
+        // Add files to zip
+        self.emit(Event::ArchiveStarted {
+            path: zip_path.clone(),
+        });
         for entry in walkdir::WalkDir::new(package_path) {
             let entry = entry?;
             if entry.file_type().is_file() {
                 let path = entry.path();
-                println!("Adding file to zip: {:?}", path);
-                let relative_path = path.strip_prefix(package_path)?;
-                zip.start_file(relative_path.to_string_lossy(), Default::default())?;
-                let bytes_copied = std::io::copy(&mut std::fs::File::open(path)?, &mut zip)?;
-                println!("Copied {} bytes for file: {:?}", bytes_copied, path);
+                log::debug!("Adding file to zip: {:?}", path);
+                let relative_path = path.strip_prefix(package_path)?.to_string_lossy();
+                // 平台相关的文件和命名产物单独上传
+                if layout.excludes(&relative_path) {
+                    continue;
+                }
+                zip.start_file(relative_path.clone(), Default::default())?;
+                let (bytes, encrypted) = match &selective {
+                    Some((selector, cipher)) if selector.matches(&relative_path) => {
+                        (encrypt_file(cipher, path, &mut zip)?, true)
+                    }
+                    _ => (
+                        std::io::copy(&mut std::fs::File::open(path)?, &mut zip)?,
+                        false,
+                    ),
+                };
+                self.emit(Event::FileArchived {
+                    path: path.to_path_buf(),
+                    bytes,
+                    encrypted,
+                });
             }
         }
         zip.finish()?;
-        println!("Finished creating zip archive");
-
-        // Read zip file content and calculate checksum
-        println!("Reading zip file content from: {:?}", zip_path);
-        let file_content = std::fs::read(&zip_path)?;
-        let mut hasher = Sha1::new();
-        hasher.update(&file_content);
-        let checksum = format!("{:x}", hasher.finalize());
-        println!("Calculated checksum for zip: {}", checksum);
-
-        // 创建 PUT 对象操作
-        let action = self.bucket.put_object(self.credentials.as_ref(), &zip_name);
-        let url = action.sign(Duration::from_secs(3600));
+        self.emit(Event::ArchiveFinished {
+            path: zip_path.clone(),
+        });
 
-        // 上传对象
-        println!("Uploading package to: {}", url);
-        println!("Package size: {} bytes", file_content.len());
-        
-        let response = self
-            .client
-            .put(url)
-            .header("Content-Type", "application/zip")
-            .body(file_content)
-            .send()
-            .await?;
+        let document = match self.sbom {
+            Some(format) => Some((
+                format,
+                sbom::generate(format, &metadata, &sbom::file_inventory(package_path)?),
+            )),
+            None => None,
+        };
+        self.publish_archive(
+            Publication {
+                metadata: &metadata,
+                zip_name: &zip_name,
+                zip_path: &zip_path,
+                encryption: encryption.filter(|_| selective.is_none()),
+                artifacts: Some((package_path, &layout)),
+                selective: &selective,
+                source_dir: package_path,
+                sbom: document,
+                readme: declared_readme,
+                started_on,
+            },
+            true,
+        )
+        .await?;
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            println!("Upload failed with status: {}, body: {}", status, body);
-            return Err(format!("Failed to upload object: {}", status).into());
+        // Clean up temp file
+        std::fs::remove_file(zip_path)?;
+        Ok(())
+    }
+
+    /// 推送已经打包好的包文件，不重新打包，上传的就是 CI 测试过的同一份字节。
+    /// 元数据读取自包文件根目录的 pack.toml 或 pack.json；平台产物、命名产物和按文件加密
+    /// 需要包目录，不支持。不运行 pre_push 钩子。force 时忽略版本冲突。返回包的元数据
+    pub async fn push_archive(
+        &self,
+        archive_path: &Path,
+        force: bool,
+    ) -> Result<models::PackageMetadata, BeepkgError> {
+        let started_on = chrono::Utc::now();
+
+        let file = std::fs::File::open(archive_path)
+            .map_err(|e| format!("Failed to open archive {}: {}", archive_path.display(), e))?;
+        let mut archive = zip::ZipArchive::new(file)?;
+        for index in 0..archive.len() {
+            let entry = archive.by_index(index)?;
+            if entry.enclosed_name().is_none() {
+                return Err(format!("Archive contains an unsafe path: {}", entry.name()).into());
+            }
+        }
+        let metadata = models::PackageMetadata::from_archive(&mut archive)?;
+        semver::Version::parse(&metadata.version)
+            .map_err(|_| format!("Invalid version format: {}", metadata.version))?;
+        let layout = artifacts::ArtifactLayout::new(&metadata)?;
+        if layout.targets().next().is_some() || layout.named().next().is_some() {
+            return Err(
+                "Packages with targets or artifacts must be pushed from the package directory"
+                    .into(),
+            );
+        }
+        let encryption = metadata.encryption.as_ref().filter(|e| e.enabled);
+        if encryption.is_some_and(|e| !e.files.is_empty()) {
+            return Err(
+                "Selectively encrypted packages must be pushed from the package directory".into(),
+            );
         }
-        println!("Upload successful");
+        self.check_publish(&metadata.name).await?;
 
-        // Upload checksum file
-        let checksum_name = format!("{}.sha1", zip_name);
-        let action = self
-            .bucket
-            .put_object(self.credentials.as_ref(), &checksum_name);
-        let url = action.sign(Duration::from_secs(3600));
+        if force {
+            self.ensure_not_overwriting(&metadata.name, &metadata.version)
+                .await?;
+        } else {
+            match self
+                .check_package_conflict(&metadata.name, &metadata.version)
+                .await?
+            {
+                PackageConflictStatus::NoConflict => {}
+                PackageConflictStatus::VersionExists => {
+                    return Err(BeepkgError::Conflict(format!(
+                        "Package {}@{} already exists. Use --force to overwrite or choose a different version.",
+                        metadata.name, metadata.version
+                    )));
+                }
+                PackageConflictStatus::HigherVersionExists(existing_version) => {
+                    return Err(BeepkgError::Conflict(format!(
+                        "A higher version ({}) of package {} already exists. Current version: {}. Use --force to ignore this warning or choose a higher version.",
+                        existing_version, metadata.name, metadata.version
+                    )));
+                }
+            }
+        }
+        let declared_readme = match &metadata.readme {
+            Some(readme) => {
+                let name = readme.trim_start_matches("./");
+                let mut content = Vec::new();
+                archive
+                    .by_name(name)
+                    .map_err(|e| format!("README {} not readable: {}", name, e))?
+                    .read_to_end(&mut content)?;
+                Some(content)
+            }
+            None => None,
+        };
+        let document = match self.sbom {
+            Some(format) => Some((
+                format,
+                sbom::generate(format, &metadata, &sbom::archive_inventory(&mut archive)?),
+            )),
+            None => None,
+        };
+        drop(archive);
 
-        let response = self
-            .client
-            .put(url)
-            .header("Content-Type", "text/plain")
-            .body(checksum.clone())
-            .send()
+        // 加密时会在文件旁边写入临时文件，先复制到临时目录，避免改动用户的目录
+        let zip_name = self.push_key(&metadata).await?;
+        let temp_dir = tempfile::tempdir()?;
+        let upload_path = if encryption.is_some() {
+            let copy = temp_dir.path().join(local_file_name(&zip_name));
+            std::fs::copy(archive_path, &copy)?;
+            copy
+        } else {
+            archive_path.to_path_buf()
+        };
+        // 源码仓库信息取自包文件所在的目录
+        let source_dir = match archive_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        self.publish_archive(
+            Publication {
+                metadata: &metadata,
+                zip_name: &zip_name,
+                zip_path: &upload_path,
+                encryption,
+                artifacts: None,
+                selective: &None,
+                source_dir,
+                sbom: document,
+                readme: declared_readme,
+                started_on,
+            },
+            force,
+        )
+        .await?;
+        Ok(metadata)
+    }
+
+    // 推送的共同步骤：上传包文件（需要时先加密）、校验文件、签名、产物、构建证明、SBOM、包信息、
+    // README 和差量，最后更新汇总校验文件。草稿只记录到草稿列表；其他推送更新注册表元数据
+    // （锁定版本的校验和、访问规则和频道）并写入审计日志，force 时记录为强制推送
+    async fn publish_archive(
+        &self,
+        publication: Publication<'_>,
+        force: bool,
+    ) -> Result<(), BeepkgError> {
+        let Publication {
+            metadata,
+            zip_name,
+            zip_path,
+            encryption,
+            artifacts,
+            selective,
+            source_dir,
+            sbom,
+            readme,
+            started_on,
+        } = publication;
+
+        // 以流方式上传对象，同时计算校验和
+        let algorithm = self.registry_checksum_algorithm().await?;
+        let checksum = self
+            .upload_archive(&metadata.name, zip_name, zip_path, algorithm, encryption)
             .await?;
 
-        if !response.status().is_success() {
-            return Err(format!("Failed to upload checksum file: {}", response.status()).into());
+        self.upload_checksum(zip_name, &checksum).await?;
+        self.sign_package(zip_name, &checksum).await?;
+        if let Some((package_path, layout)) = artifacts {
+            self.upload_artifacts(
+                metadata,
+                package_path,
+                layout,
+                algorithm,
+                encryption,
+                selective,
+            )
+            .await?;
+        }
+        self.upload_attestation(zip_name, metadata, &checksum, source_dir, started_on)
+            .await?;
+        self.store_sbom(zip_name, sbom).await?;
+        self.upload_package_info(zip_name, metadata).await?;
+        if let Some(readme) = readme {
+            self.put_object_bytes(&readme_key(zip_name), readme, "text/plain; charset=utf-8")
+                .await?;
+        }
+        self.upload_delta(zip_name, zip_path, metadata, encryption.is_some())
+            .await;
+        self.upload_sums(zip_name, &checksum).await?;
+        if self.draft {
+            return self.record_draft(metadata).await;
         }
 
-        // Clean up temp file
-        std::fs::remove_file(zip_path)?;
+        // 锁定记录中的校验和随上传的内容更新
+        let mut registry_meta = self.get_registry_metadata().await?;
+        if let Some(pkg) = registry_meta
+            .locked_packages
+            .iter_mut()
+            .find(|p| p.name == metadata.name && p.version == metadata.version)
+        {
+            pkg.checksum = checksum.to_string();
+        }
+        self.record_access(&mut registry_meta, metadata);
+        self.record_channel(&mut registry_meta, metadata)?;
+        self.save_registry_metadata(&registry_meta).await?;
 
-        Ok(())
+        let action = if force {
+            models::AuditAction::ForcePush
+        } else {
+            models::AuditAction::Push
+        };
+        self.record_audit(
+            action,
+            &format!("{}@{}", metadata.name, metadata.version),
+            None,
+        )
+        .await
+    }
+
+    /// 按依赖顺序推送工作区的成员。推送前先检查所有成员的发布权限和版本冲突（force 时跳过冲突检查）。
+    /// atomic 时任一成员推送失败，删除本次推送的所有成员（包括失败成员已上传的部分）的包文件和附属文件，
+    /// 不能与 force 同时使用，否则会删除被覆盖的版本。返回推送的 name@version
+    pub async fn push_workspace(
+        &self,
+        members: &[WorkspaceMember],
+        force: bool,
+        atomic: bool,
+    ) -> Result<Vec<String>, BeepkgError> {
+        if force && atomic {
+            return Err("Atomic workspace pushes cannot be forced".into());
+        }
+        for member in members {
+            self.check_publish(&member.metadata.name).await?;
+            if force {
+                self.ensure_not_overwriting(&member.metadata.name, &member.metadata.version)
+                    .await?;
+                continue;
+            }
+            match self
+                .check_package_conflict(&member.metadata.name, &member.metadata.version)
+                .await?
+            {
+                PackageConflictStatus::NoConflict => {}
+                PackageConflictStatus::VersionExists => {
+                    return Err(BeepkgError::Conflict(format!(
+                        "Package {} already exists",
+                        member.spec()
+                    )));
+                }
+                PackageConflictStatus::HigherVersionExists(existing) => {
+                    return Err(BeepkgError::Conflict(format!(
+                        "A higher version ({}) of package {} already exists",
+                        existing, member.metadata.name
+                    )));
+                }
+            }
+        }
+
+        let mut pushed: Vec<&WorkspaceMember> = Vec::new();
+        for member in members {
+            self.emit(Event::Info(format!(
+                "Pushing workspace member {}",
+                member.spec()
+            )));
+            let result = if force {
+                self.force_push_package(&member.path).await
+            } else {
+                self.push_package(&member.path).await
+            };
+            let Err(e) = result else {
+                pushed.push(member);
+                continue;
+            };
+            if !atomic {
+                let pushed: Vec<String> = pushed.iter().map(|member| member.spec()).collect();
+                return Err(format!(
+                    "Failed to push {}: {} (already pushed: {})",
+                    member.spec(),
+                    e,
+                    if pushed.is_empty() {
+                        "none".to_string()
+                    } else {
+                        pushed.join(", ")
+                    }
+                )
+                .into());
+            }
+
+            pushed.push(member);
+            for member in pushed.iter().rev() {
+                self.emit(Event::Info(format!("Rolling back {}", member.spec())));
+                self.remove_archive(&format!(
+                    "{}-{}.zip",
+                    member.metadata.name, member.metadata.version
+                ))
+                .await?;
+            }
+            return Err(format!(
+                "Failed to push {}: {} (workspace push rolled back)",
+                member.spec(),
+                e
+            )
+            .into());
+        }
+        Ok(pushed.iter().map(|member| member.spec()).collect())
     }
 
     pub async fn pull_package(
         &self,
         package_name: &str,
         output_dir: &Path,
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
-        // Parse package name and version
-        let (name, version) = match package_name.split_once('@') {
-            Some((n, v)) => (n, v),
-            None => return Err("Invalid package format, expected name@version".into()),
-        };
+    ) -> Result<(), BeepkgError> {
+        let registry = self.get_registry_metadata().await?;
+        let (name, version) = self.resolve_package_spec(&registry, package_name).await?;
+        let name = name.as_str();
+        let require_signature = self.signature_required(&registry, name);
 
         // Create temp directory
         let temp_dir = std::env::temp_dir().join(format!("{}-{}", name, version));
@@ -500,91 +1700,60 @@ impl PackageManager {
 
         // Download package and checksum
         let zip_name = format!("{}-{}.zip", name, version);
-        let checksum_name = format!("{}.sha1", zip_name);
-        let zip_path = temp_dir.join(&zip_name);
-        let _checksum_path = temp_dir.join(&checksum_name);
+        let zip_path = temp_dir.join(local_file_name(&zip_name));
 
         // Download package file with debug info
-        println!("Downloading package {}@{}", name, version);
-        let action = self.bucket.get_object(self.credentials.as_ref(), &zip_name);
-        let url = action.sign(Duration::from_secs(3600));
-        println!("Download URL: {}", url);
-
-        let response = self.client.get(url).send().await?;
-        if !response.status().is_success() {
-            return Err(format!("Failed to download package: {}", response.status()).into());
-        }
-
-        let bytes = response.bytes().await?;
-        println!("Downloaded {} bytes", bytes.len());
-        std::fs::write(&zip_path, &bytes)?;
-        println!("Saved package to: {:?}", zip_path);
-
-        // Download checksum file
-        println!("Downloading checksum file");
-        let action = self
-            .bucket
-            .get_object(self.credentials.as_ref(), &checksum_name);
-        let url = action.sign(Duration::from_secs(3600));
-
-        let response = self.client.get(url).send().await;
-        let expected_checksum = match response {
-            Ok(resp) if resp.status().is_success() => {
-                let checksum = resp.text().await?;
-                println!("Expected checksum: {}", checksum);
-                checksum
-            },
-            _ => {
-                println!("Failed to download checksum file");
-                return Err(PackageError::MissingChecksum.into())
-            },
-        };
-
-        // Verify checksum
-        println!("Calculating actual checksum...");
-        let mut hasher = Sha1::new();
-        hasher.update(&bytes);
-        let actual_checksum = format!("{:x}", hasher.finalize());
-        println!("Actual checksum: {}", actual_checksum);
-
-        if actual_checksum != expected_checksum {
-            let err_msg = format!(
-                "Package {}@{} checksum mismatch:\nExpected: {}\nActual: {}\nBytes length: {}",
-                name, version, expected_checksum, actual_checksum, bytes.len()
-            );
-            println!("{}", err_msg);
-            return Err(PackageError::ChecksumMismatch(err_msg).into());
+        self.emit(Event::Info(format!(
+            "Downloading package {}@{}",
+            name, version
+        )));
+        let (expected_checksum, signer) = self
+            .fetch_verified(&zip_name, &zip_path, require_signature)
+            .await?;
+        // 按摘要固定时，下载的内容必须仍是这个摘要（解析之后可能被强制推送替换）
+        let pinned = models::split_package_spec(package_name)
+            .and_then(|(_, requested)| Checksum::parse_digest(requested));
+        if let Some(pinned) = pinned.filter(|pinned| *pinned != expected_checksum) {
+            std::fs::remove_dir_all(&temp_dir)?;
+            return Err(BeepkgError::ChecksumMismatch(format!(
+                "{}@{} was replaced: pinned {}, registry has {}",
+                name, version, pinned, expected_checksum
+            )));
         }
+        self.emit(Event::Info(format!("Saved package to: {:?}", zip_path)));
 
-        // Extract package if checksum matches
-        let _file = std::fs::File::open(&zip_path)?;
-        let content = std::fs::read(&zip_path)?;
+        let mut keys = DecryptionKeys::default();
+        self.decrypt_archive(&zip_path, &mut keys).await?;
 
-        // Check if decryption is needed
+        // 解压之前检查项目信任策略
         let metadata = self.get_package_metadata(&zip_path)?;
-        let content = if let Some(encryption) = &metadata.encryption {
-            if encryption.enabled {
-                if let (Some(encrypted_password), Some(salt)) =
-                    (&encryption.encrypted_password, &encryption.salt)
-                {
-                    let security = SecurityManager::new();
-                    SecurityManager::decrypt_data(encrypted_password, salt)
-                        .map_err(|e| format!("Decryption failed: {}", e))?
-                } else {
-                    return Err("Missing encrypted password or salt for decryption".into());
-                }
-            } else {
-                content
+        if let Some(policy) = &self.trust_policy {
+            let violations =
+                policy.evaluate(&expected_checksum, signer.as_deref(), &metadata.author);
+            if !violations.is_empty() {
+                std::fs::remove_dir_all(&temp_dir)?;
+                return Err(BeepkgError::PolicyViolation(format!(
+                    "{}@{}: {}",
+                    name,
+                    version,
+                    violations.join("; ")
+                )));
             }
-        } else {
-            content
-        };
-
-        // Write decrypted content back to temp file
-        std::fs::write(&zip_path, &content)?;
+            self.emit(Event::Info("Package satisfies trust policy".to_string()));
+        }
+        if let Some(policy) = &self.license_policy
+            && let Some(violation) = policy.evaluate(metadata.license.as_deref())
+        {
+            std::fs::remove_dir_all(&temp_dir)?;
+            return Err(BeepkgError::PolicyViolation(format!(
+                "{}@{}: {}",
+                name, version, violation
+            )));
+        }
 
         let file = std::fs::File::open(&zip_path)?;
         let mut archive = zip::ZipArchive::new(file)?;
+        let mut entry_names: Vec<String> = archive.file_names().map(str::to_string).collect();
         archive.extract(output_dir)?;
 
         // Verify metadata - 先检查pack.toml，然后是pack.json
@@ -594,11 +1763,11 @@ impl PackageManager {
         let metadata: models::PackageMetadata = if toml_path.exists() {
             // 读取TOML格式
             let toml_content = std::fs::read_to_string(&toml_path)?;
-            toml::from_str(&toml_content)?
+            schema::from_toml(&toml_content)?
         } else if json_path.exists() {
             // 读取JSON格式
             let json_content = std::fs::read_to_string(&json_path)?;
-            serde_json::from_str(&json_content)?
+            schema::from_json(json_content.as_bytes())?
         } else {
             return Err("Neither pack.toml nor pack.json found in downloaded package".into());
         };
@@ -607,350 +1776,6238 @@ impl PackageManager {
             return Err("Downloaded package metadata mismatch".into());
         }
 
+        // 声明了平台产物的包：下载当前平台（或 --target 指定平台）的产物并解压到同一目录
+        if !metadata.targets.is_empty() {
+            let requested = self.target.clone().unwrap_or_else(artifacts::host_target);
+            let target =
+                artifacts::select_target(&metadata.targets, &requested).ok_or_else(|| {
+                    format!(
+                        "{}@{} has no artifact for target {} (available: {})",
+                        name,
+                        version,
+                        requested,
+                        metadata
+                            .targets
+                            .keys()
+                            .cloned()
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                })?;
+            self.emit(Event::Info(format!("Downloading {} artifact", target)));
+            let target_path = temp_dir.join(local_file_name(&format!("{}.zip", target)));
+            self.download_artifact(
+                &artifacts::target_key(&zip_name, target),
+                &target_path,
+                require_signature,
+                &mut keys,
+            )
+            .await?;
+            let mut archive = zip::ZipArchive::new(std::fs::File::open(&target_path)?)?;
+            entry_names.extend(archive.file_names().map(str::to_string));
+            archive.extract(output_dir)?;
+            std::fs::remove_file(target_path)?;
+        }
+
+        // 选择性加密的包：解密匹配的文件，缺少密钥时保留密文，其余文件照常可用
+        if let Some(encryption) = metadata
+            .encryption
+            .as_ref()
+            .filter(|e| e.enabled && !e.files.is_empty())
+        {
+            let selector = FileSelector::new(&encryption.files)?;
+            for entry_name in entry_names.iter().filter(|name| selector.matches(name)) {
+                let path = output_dir.join(entry_name);
+                let content = std::fs::read(&path)?;
+                match self.decrypt_bytes(&content, &mut keys).await {
+                    Ok(Some(decrypted)) => std::fs::write(&path, decrypted)?,
+                    Ok(None) => {}
+                    Err(e) => self.emit(Event::Warning(format!(
+                        "leaving {} encrypted: {}",
+                        entry_name, e
+                    ))),
+                }
+            }
+        }
+
         // Clean up temp files
         std::fs::remove_file(zip_path)?;
         std::fs::remove_dir_all(temp_dir)?;
 
+        hooks::run(
+            Hook::PostPull,
+            &metadata,
+            output_dir,
+            self.allow_hooks,
+            &*self.observer,
+        )
+        .await?;
+
+        if registry.download_stats && !self.offline {
+            self.record_download(name, &version, &self.actor, None)
+                .await;
+        }
         Ok(())
     }
 
-    /// 测试连接到 MinIO 存储和 bucket 的可用性
-    pub async fn test_connection(&self) -> Result<(bool, String), Box<dyn Error + Send + Sync>> {
-        // 测试 MinIO 连接
-        let action = self.bucket.list_objects_v2(self.credentials.as_ref());
-        let url = action.sign(Duration::from_secs(10));
-
-        // 尝试发送请求
-        let response = match self.client.get(url).send().await {
-            Ok(resp) => resp,
-            Err(e) => return Ok((false, format!("无法连接到存储服务: {}", e))),
+    /// 下载包文件到指定路径但不解压：按校验和（和签名）验证内容，加密的包保持原样。
+    /// 返回包文件的校验和
+    pub async fn fetch_package(
+        &self,
+        package_name: &str,
+        output: &Path,
+    ) -> Result<Checksum, BeepkgError> {
+        let registry = self.get_registry_metadata().await?;
+        let (name, version) = self.resolve_package_spec(&registry, package_name).await?;
+        let name = name.as_str();
+        let require_signature = self.signature_required(&registry, name);
+
+        // 先下载到输出目录中的临时文件，验证通过后再改名，失败时不留下不完整的文件
+        let dir = match output.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
         };
+        std::fs::create_dir_all(dir)?;
+        let temp = tempfile::NamedTempFile::new_in(dir)?;
 
-        // 检查状态码
-        if !response.status().is_success() {
-            return Ok((
-                false,
-                format!("存储服务返回错误状态码: {}", response.status()),
-            ));
+        let zip_name = format!("{}-{}.zip", name, version);
+        self.emit(Event::Info(format!(
+            "Fetching package {}@{}",
+            name, version
+        )));
+        let (checksum, _) = self
+            .fetch_verified(&zip_name, temp.path(), require_signature)
+            .await?;
+        let pinned = models::split_package_spec(package_name)
+            .and_then(|(_, requested)| Checksum::parse_digest(requested));
+        if let Some(pinned) = pinned.filter(|pinned| *pinned != checksum) {
+            return Err(BeepkgError::ChecksumMismatch(format!(
+                "{}@{} was replaced: pinned {}, registry has {}",
+                name, version, pinned, checksum
+            )));
         }
+        temp.persist(output).map_err(|e| e.error)?;
+        self.emit(Event::Info(format!("Saved package to: {:?}", output)));
 
-        // 尝试解析 XML 响应，检查 bucket 是否可用
-        let content = match response.text().await {
-            Ok(text) => text,
-            Err(e) => return Ok((false, format!("无法读取响应内容: {}", e))),
-        };
-
-        // 尝试解析 XML 内容
-        match from_str::<ListObjectsResponse>(&content) {
-            Ok(_) => Ok((
-                true,
-                format!("成功连接到存储服务，bucket '{}' 可用", self.bucket.name()),
-            )),
-            Err(e) => Ok((false, format!("无法解析响应内容，bucket 可能不存在: {}", e))),
+        if registry.download_stats && !self.offline {
+            self.record_download(name, &version, &self.actor, None)
+                .await;
         }
+        Ok(checksum)
     }
 
-    // 锁定特定版本的包，防止被修改
-    pub async fn lock_package(
+    /// 以字节流下载包文件，不落盘。边下载边计算校验和，
+    /// 内容与校验文件不符时流的最后一项是 ChecksumMismatch 错误，调用方应丢弃已接收的数据。
+    /// 流式下载不使用本地缓存和差量，离线时不可用
+    pub async fn fetch_package_stream(
         &self,
         package_name: &str,
-        version: &str,
-        reason: &str,
-        user: &str,
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
-        // 获取注册表元数据
-        let mut metadata = self.get_registry_metadata().await?;
-
-        // 检查包是否存在
-        let packages = self.list_packages().await?;
-        let found = packages
-            .iter()
-            .any(|p| p.name == package_name && p.version == version);
-
-        if !found {
-            return Err(format!("Package {}@{} does not exist", package_name, version).into());
+    ) -> Result<impl Stream<Item = Result<bytes::Bytes, BeepkgError>> + Send + 'static, BeepkgError>
+    {
+        if self.offline {
+            return Err(BeepkgError::Offline(
+                "streaming a package requires network access".to_string(),
+            ));
         }
+        let registry = self.get_registry_metadata().await?;
+        let (name, version) = self.resolve_package_spec(&registry, package_name).await?;
+        let require_signature = self.signature_required(&registry, &name);
 
-        // 检查包是否已经被锁定
-        if metadata
-            .locked_packages
-            .iter()
-            .any(|lp| lp.name == package_name && lp.version == version)
-        {
-            return Err(format!("Package {}@{} is already locked", package_name, version).into());
+        let key = format!("{}-{}.zip", name, version);
+        let expected = self.fetch_checksum(&key).await?;
+        self.verify_signature(&key, &expected, require_signature)
+            .await?;
+        let pinned = models::split_package_spec(package_name)
+            .and_then(|(_, requested)| Checksum::parse_digest(requested));
+        if let Some(pinned) = pinned.filter(|pinned| *pinned != expected) {
+            return Err(BeepkgError::ChecksumMismatch(format!(
+                "{}@{} was replaced: pinned {}, registry has {}",
+                name, version, pinned, expected
+            )));
         }
 
-        // 添加锁定信息
-        let now = chrono::Utc::now().to_rfc3339();
-        // Get package checksum if available
-        let package = packages
-            .iter()
-            .find(|p| p.name == package_name && p.version == version);
-        let checksum = package.map_or("".to_string(), |p| p.storage.checksum.clone());
+        if registry.download_stats {
+            self.record_download(&name, &version, &self.actor, None)
+                .await;
+        }
 
-        metadata.locked_packages.push(models::LockedPackage {
-            name: package_name.to_string(),
-            version: version.to_string(),
-            lock_reason: reason.to_string(),
-            locked_at: now.clone(),
-            locked_by: user.to_string(),
-            checksum,
-        });
+        // 指针对象改为下载它指向的 blob
+        type Chunks =
+            std::pin::Pin<Box<dyn Stream<Item = Result<bytes::Bytes, BeepkgError>> + Send>>;
+        let response = self.get_object_response(&key).await?;
+        let chunks: Chunks = if response
+            .content_length()
+            .is_some_and(|size| size <= blobs::MAX_POINTER_SIZE)
+        {
+            let content = response.bytes().await?;
+            match BlobPointer::parse(&content) {
+                Some(pointer) => {
+                    let response = self.get_object_response(&pointer.blob).await?;
+                    Box::pin(response.bytes_stream().map_err(BeepkgError::from))
+                }
+                None => Box::pin(stream::iter([Ok(content)])),
+            }
+        } else {
+            Box::pin(response.bytes_stream().map_err(BeepkgError::from))
+        };
+        let hasher = Some(ChecksumHasher::new(expected.algorithm));
+        Ok(stream::unfold(
+            (chunks, hasher, expected, key),
+            |(mut chunks, mut hasher, expected, key)| async move {
+                let state = hasher.as_mut()?;
+                match chunks.next().await {
+                    Some(Ok(chunk)) => {
+                        state.update(&chunk);
+                        Some((Ok(chunk), (chunks, hasher, expected, key)))
+                    }
+                    Some(Err(e)) => Some((Err(e), (chunks, None, expected, key))),
+                    None => {
+                        let actual = hasher.take()?.finalize();
+                        if actual == expected {
+                            return None;
+                        }
+                        let err = BeepkgError::ChecksumMismatch(format!(
+                            "{} checksum mismatch:\nExpected: {}\nActual: {}",
+                            key, expected, actual
+                        ));
+                        Some((Err(err), (chunks, None, expected, key)))
+                    }
+                }
+            },
+        ))
+    }
 
-        metadata.last_updated = now;
+    /// 比较包目录中的文件与已发布的同一 name@version 的包文件（按每个文件的 sha256），
+    /// 列出修改、新增和删除的文件。按文件加密的内容解密后比较
+    pub async fn status(&self, package_dir: &Path) -> Result<drift::DriftReport, BeepkgError> {
+        let metadata = models::PackageMetadata::load(package_dir)?;
+        let layout = artifacts::ArtifactLayout::new(&metadata)?.exclude(&self.excludes)?;
+        let local = drift::local_hashes(package_dir, &layout)?;
 
-        // 保存更新后的元数据
+        let registry = self.get_registry_metadata().await?;
+        self.ensure_access(&registry, &metadata.name)?;
+        let zip_name = format!("{}-{}.zip", metadata.name, metadata.version);
+        if !self.offline && self.head_object(&zip_name).await?.is_none() {
+            return Err(BeepkgError::NotFound(format!(
+                "{}@{} is not published",
+                metadata.name, metadata.version
+            )));
+        }
+        let temp_dir = tempfile::tempdir()?;
+        let zip_path = temp_dir.path().join(local_file_name(&zip_name));
+        self.fetch_verified(
+            &zip_name,
+            &zip_path,
+            self.signature_required(&registry, &metadata.name),
+        )
+        .await?;
+        let mut keys = DecryptionKeys::default();
+        self.decrypt_archive(&zip_path, &mut keys).await?;
+
+        let selector = match metadata.encryption.as_ref().filter(|e| e.enabled) {
+            Some(encryption) if !encryption.files.is_empty() => {
+                Some(FileSelector::new(&encryption.files)?)
+            }
+            _ => None,
+        };
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&zip_path)?)?;
+        let mut published = BTreeMap::new();
+        for index in 0..archive.len() {
+            let (name, mut content) = {
+                let mut entry = archive.by_index(index)?;
+                if entry.is_dir() {
+                    continue;
+                }
+                let mut content = Vec::new();
+                entry.read_to_end(&mut content)?;
+                (entry.name().replace('\\', "/"), content)
+            };
+            if selector.as_ref().is_some_and(|s| s.matches(&name))
+                && let Some(decrypted) = self.decrypt_bytes(&content, &mut keys).await?
+            {
+                content = decrypted;
+            }
+            published.insert(name, ChecksumAlgorithm::Sha256.digest(&content));
+        }
+        Ok(drift::compare(
+            &metadata.name,
+            &metadata.version,
+            &published,
+            &local,
+        ))
+    }
+
+    /// 安装包及其依赖：包解压到输出目录，依赖（包括启用的特性引用的可选依赖）逐个拉取到
+    /// `<output>/deps/<name>`。特性只作用于要安装的包，间接依赖启用各自的 default 特性。
+    /// 同名依赖只安装一次，已安装的版本不满足其他包的版本需求时报错。返回安装的 name@version
+    pub async fn install_package(
+        &self,
+        package: &str,
+        output_dir: &Path,
+        features: &[String],
+        default_features: bool,
+    ) -> Result<Vec<String>, BeepkgError> {
+        self.pull_package(package, output_dir).await?;
+        let metadata = models::PackageMetadata::load(output_dir)?;
+        let dependencies = metadata.active_dependencies(features, default_features)?;
+
+        let mut installed = vec![format!("{}@{}", metadata.name, metadata.version)];
+        let mut versions = HashMap::from([(metadata.name.clone(), metadata.version.clone())]);
+        let pulled = self
+            .pull_dependency_closure(
+                &installed[0],
+                dependencies,
+                &output_dir.join(DEPS_DIR),
+                &mut versions,
+                &HashMap::new(),
+            )
+            .await?;
+        installed.extend(
+            pulled
+                .into_iter()
+                .map(|(name, version)| format!("{}@{}", name, version)),
+        );
+        Ok(installed)
+    }
+
+    /// 把包目录中 pack.toml 的依赖闭包拉取到 vendor 目录：每个依赖解压到 `<output>/<name>`，
+    /// 并写入记录版本和校验和的 vendor.json，之后构建无需访问注册表。
+    /// 重新 vendor 时先删除清单中列出的旧目录
+    pub async fn vendor_dependencies(
+        &self,
+        package_dir: &Path,
+        output_dir: &Path,
+        features: &[String],
+        default_features: bool,
+    ) -> Result<models::VendorManifest, BeepkgError> {
+        let metadata = models::PackageMetadata::load(package_dir)?;
+        let dependencies = metadata.active_dependencies(features, default_features)?;
+
+        let manifest_path = output_dir.join(models::VENDOR_MANIFEST);
+        if manifest_path.exists() {
+            let previous: models::VendorManifest =
+                serde_json::from_str(&std::fs::read_to_string(&manifest_path)?)?;
+            // 只删除 vendor 目录内的路径
+            for package in previous.packages {
+                let relative = Path::new(&package.path);
+                let inside = relative
+                    .components()
+                    .all(|component| matches!(component, std::path::Component::Normal(_)));
+                if inside && output_dir.join(relative).is_dir() {
+                    std::fs::remove_dir_all(output_dir.join(relative))?;
+                }
+            }
+        } else if output_dir
+            .read_dir()
+            .is_ok_and(|mut entries| entries.next().is_some())
+        {
+            return Err(format!(
+                "{} is not empty and has no {}",
+                output_dir.display(),
+                models::VENDOR_MANIFEST
+            )
+            .into());
+        }
+
+        let root = format!("{}@{}", metadata.name, metadata.version);
+        let mut versions = HashMap::from([(metadata.name.clone(), metadata.version.clone())]);
+        let pulled = self
+            .pull_dependency_closure(
+                &root,
+                dependencies,
+                output_dir,
+                &mut versions,
+                &HashMap::new(),
+            )
+            .await?;
+
+        let mut packages = Vec::new();
+        for (name, version) in pulled {
+            let checksum = self
+                .fetch_checksum(&format!("{}-{}.zip", name, version))
+                .await?;
+            packages.push(models::VendoredPackage {
+                path: name.clone(),
+                name,
+                version,
+                checksum: checksum.to_string(),
+            });
+        }
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+        let manifest = models::VendorManifest {
+            generated_at: chrono::Utc::now().to_rfc3339(),
+            package: root,
+            packages,
+        };
+        std::fs::create_dir_all(output_dir)?;
+        std::fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)?;
+        Ok(manifest)
+    }
+
+    /// 按 vendor.json 中记录的摘要重新拉取每个依赖（name@<checksum>），不重新解析版本范围；
+    /// 任何依赖的内容与记录不同时报错，用于可重现的构建
+    pub async fn vendor_locked(
+        &self,
+        output_dir: &Path,
+    ) -> Result<models::VendorManifest, BeepkgError> {
+        let manifest_path = output_dir.join(models::VENDOR_MANIFEST);
+        if !manifest_path.exists() {
+            return Err(BeepkgError::NotFound(manifest_path.display().to_string()));
+        }
+        let manifest: models::VendorManifest =
+            serde_json::from_str(&std::fs::read_to_string(&manifest_path)?)?;
+        self.pull_pinned(&manifest.packages, output_dir, models::VENDOR_MANIFEST)
+            .await?;
+        Ok(manifest)
+    }
+
+    // 按记录的摘要拉取每个包到 dir/<path>，先确认所有路径都在 dir 内且都有可用的摘要；source 为记录所在的文件
+    async fn pull_pinned(
+        &self,
+        packages: &[models::VendoredPackage],
+        dir: &Path,
+        source: &str,
+    ) -> Result<(), BeepkgError> {
+        for package in packages {
+            if !project::is_inside(&package.path) {
+                return Err(format!("Invalid path {} in {}", package.path, source).into());
+            }
+            if Checksum::parse_digest(&package.checksum).is_none() {
+                return Err(format!(
+                    "{}@{} has no usable checksum in {}",
+                    package.name, package.version, source
+                )
+                .into());
+            }
+        }
+
+        for package in packages {
+            let spec = package.pinned_spec();
+            self.emit(Event::Info(format!(
+                "Pulling {}@{} ({})",
+                package.name, package.version, package.checksum
+            )));
+            let package_dir = dir.join(&package.path);
+            if package_dir.is_dir() {
+                std::fs::remove_dir_all(&package_dir)?;
+            }
+            self.pull_package(&spec, &package_dir).await?;
+        }
+        Ok(())
+    }
+
+    /// 按项目根目录中的 project.toml 同步安装目录：解析各个包的版本需求及其依赖闭包，
+    /// 安装新的或内容变化的包，删除不再需要的包，并在内容变化时写入锁文件。
+    /// profile 选择 project.toml 中的环境配置，每个配置有自己的锁文件。
+    /// frozen 时不解析版本，按锁文件中的摘要安装，锁文件缺失或与 project.toml 不一致时报错，用于 CI
+    pub async fn sync_project(
+        &self,
+        root: &Path,
+        profile: Option<&str>,
+        frozen: bool,
+    ) -> Result<SyncReport, BeepkgError> {
+        let manifest = Project::load(root)?;
+        let requirements = manifest.requirements(profile)?;
+        let dir = root.join(&manifest.dir);
+        let lock_file = project::lock_file(profile);
+        let previous = ProjectLock::load(root, profile)?;
+        if frozen {
+            let lock = previous.ok_or_else(|| {
+                BeepkgError::NotFound(root.join(&lock_file).display().to_string())
+            })?;
+            if lock.requirements != requirements {
+                return Err(format!(
+                    "{} is out of date with {}; run beepkg sync and commit it",
+                    lock_file,
+                    project::PROJECT_FILE
+                )
+                .into());
+            }
+            return self.install_locked(&dir, &lock.packages, &lock_file).await;
+        }
+
+        let installed = project::load_installed(&dir)?;
+        let reuse = reusable_packages(&dir, &installed);
+        let packages = self.resolve_project(&requirements, &dir, &reuse).await?;
+        let report = finish_sync(&dir, &installed, &reuse, &packages)?;
+        let lock = ProjectLock {
+            generated_at: chrono::Utc::now().to_rfc3339(),
+            requirements,
+            packages,
+        };
+        if !previous.is_some_and(|previous| previous.same_as(&lock)) {
+            lock.save(root, profile)?;
+        }
+        Ok(report)
+    }
+
+    // 按锁定的摘要安装到 dir：只拉取与安装记录不同的包，删除不再需要的包
+    async fn install_locked(
+        &self,
+        dir: &Path,
+        packages: &[models::VendoredPackage],
+        source: &str,
+    ) -> Result<SyncReport, BeepkgError> {
+        // 安装目录中的包可能来自另一个环境配置，按安装记录而不是锁文件判断内容是否变化
+        let installed = project::load_installed(dir)?;
+        let reuse = reusable_packages(dir, &installed);
+        let changed: Vec<_> = packages
+            .iter()
+            .filter(|p| reuse.get(&p.name) != Some(&p.checksum))
+            .cloned()
+            .collect();
+        self.pull_pinned(&changed, dir, source).await?;
+        finish_sync(dir, &installed, &reuse, packages)
+    }
+
+    // 解析项目需求的依赖闭包并拉取到 dir，返回按名称排序的包及其校验和
+    async fn resolve_project(
+        &self,
+        requirements: &BTreeMap<String, String>,
+        dir: &Path,
+        reuse: &HashMap<String, String>,
+    ) -> Result<Vec<models::VendoredPackage>, BeepkgError> {
+        let resolved = self
+            .pull_dependency_closure(
+                project::PROJECT_FILE,
+                requirements.clone().into_iter().collect(),
+                dir,
+                &mut HashMap::new(),
+                reuse,
+            )
+            .await?;
+        let mut packages = Vec::new();
+        for (name, version) in resolved {
+            let checksum = self
+                .fetch_checksum(&format!("{}-{}.zip", name, version))
+                .await?
+                .to_string();
+            packages.push(models::VendoredPackage {
+                path: name.clone(),
+                name,
+                version,
+                checksum,
+            });
+        }
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(packages)
+    }
+
+    // 拉取依赖闭包：每个依赖解压到 dir/<name>，间接依赖启用各自的 default 特性。
+    // versions 记录已安装的包（名称 -> 版本），同名依赖只拉取一次，已有版本不满足需求时报错。
+    // reuse 为 dir 中已有的包（名称 -> 校验和），内容未变时不重新拉取，变化时先删除旧目录。
+    // 返回新解析的（名称, 版本），包括没有重新拉取的包
+    async fn pull_dependency_closure(
+        &self,
+        parent: &str,
+        dependencies: HashMap<String, String>,
+        dir: &Path,
+        versions: &mut HashMap<String, String>,
+        reuse: &HashMap<String, String>,
+    ) -> Result<Vec<(String, String)>, BeepkgError> {
+        let mut pulled = Vec::new();
+        let mut pending = vec![(parent.to_string(), dependencies)];
+        while let Some((parent, dependencies)) = pending.pop() {
+            for dependency in self.resolve_dependencies(&dependencies).await? {
+                if let Some(existing) = versions.get(&dependency.name) {
+                    let satisfied = semver::Version::parse(existing).is_ok_and(|existing| {
+                        semver::VersionReq::parse(&dependency.requirement)
+                            .is_ok_and(|req| advisory::matches(&req, &existing, true))
+                    });
+                    if !satisfied {
+                        return Err(format!(
+                            "{} requires {} {}, but {} is already installed",
+                            parent, dependency.name, dependency.requirement, existing
+                        )
+                        .into());
+                    }
+                    continue;
+                }
+                let version = dependency.version.ok_or_else(|| {
+                    format!(
+                        "No version of {} matches {} (required by {})",
+                        dependency.name, dependency.requirement, parent
+                    )
+                })?;
+
+                let spec = format!("{}@{}", dependency.name, version);
+                let dependency_dir = dir.join(&dependency.name);
+                let unchanged = match reuse.get(&dependency.name) {
+                    Some(checksum) => {
+                        let archive = format!("{}-{}.zip", dependency.name, version);
+                        self.fetch_checksum(&archive).await?.to_string() == *checksum
+                    }
+                    None => false,
+                };
+                if unchanged {
+                    self.emit(Event::Info(format!("{} is up to date", spec)));
+                } else {
+                    if reuse.contains_key(&dependency.name) && dependency_dir.is_dir() {
+                        std::fs::remove_dir_all(&dependency_dir)?;
+                    }
+                    self.emit(Event::Info(format!("Pulling dependency {}", spec)));
+                    self.pull_package(&spec, &dependency_dir).await?;
+                }
+                let dependency_metadata = models::PackageMetadata::load(&dependency_dir)?;
+                versions.insert(dependency.name.clone(), version.to_string());
+                pending.push((spec, dependency_metadata.active_dependencies(&[], true)?));
+                pulled.push((dependency.name, version.to_string()));
+            }
+        }
+        Ok(pulled)
+    }
+
+    /// 包版本的命名产物和平台产物
+    pub async fn list_artifacts(
+        &self,
+        package: &str,
+    ) -> Result<Vec<models::Artifact>, BeepkgError> {
+        let registry = self.get_registry_metadata().await?;
+        let (name, version) = self.resolve_package_spec(&registry, package).await?;
+        self.version_artifacts(&format!("{}-{}.zip", name, version))
+            .await
+    }
+
+    /// 只下载包版本的一个命名产物（例如 docs）到输出目录，返回保存的路径
+    pub async fn pull_artifact(
+        &self,
+        package: &str,
+        artifact: &str,
+        output_dir: &Path,
+    ) -> Result<PathBuf, BeepkgError> {
+        let registry = self.get_registry_metadata().await?;
+        let (name, version) = self.resolve_package_spec(&registry, package).await?;
+        let available = self
+            .version_artifacts(&format!("{}-{}.zip", name, version))
+            .await?;
+        let Some(found) = available.iter().find(|a| a.name == artifact) else {
+            let names: Vec<&str> = available.iter().map(|a| a.name.as_str()).collect();
+            return Err(format!(
+                "{}@{} has no artifact {} (available: {})",
+                name,
+                version,
+                artifact,
+                if names.is_empty() {
+                    "none".to_string()
+                } else {
+                    names.join(", ")
+                }
+            )
+            .into());
+        };
+
+        std::fs::create_dir_all(output_dir)?;
+        let path = output_dir.join(local_file_name(&found.file_name));
+        self.emit(Event::Info(format!(
+            "Downloading {} artifact of {}@{}",
+            found.name, name, version
+        )));
+        let mut keys = DecryptionKeys::default();
+        self.download_artifact(
+            &found.key,
+            &path,
+            self.signature_required(&registry, &name),
+            &mut keys,
+        )
+        .await?;
+        Ok(path)
+    }
+
+    /// 生成包文件及其校验文件的预签名下载地址，持有地址的人无需注册表凭证即可下载。
+    /// 地址绕过注册表的访问控制，因此只能分享所有人可见的包
+    ///
+    /// 使用临时凭证签名时，地址在凭证过期后同样失效
+    pub async fn share_package(
+        &self,
+        package: &str,
+        expires: Duration,
+    ) -> Result<(url::Url, url::Url), BeepkgError> {
+        let (name, version) = package
+            .split_once('@')
+            .ok_or("Invalid package format, expected name@version")?;
+        if expires.is_zero() || expires > MAX_PRESIGN_EXPIRY {
+            return Err("Presigned URLs must expire within 7 days".into());
+        }
+        if self.bearer_token.is_some() {
+            return Err("Presigned URLs require S3 credentials, not a bearer token".into());
+        }
+        let credentials = self
+            .credentials()
+            .await?
+            .ok_or("Presigned URLs require S3 credentials")?;
+
+        let registry = self.get_registry_metadata().await?;
+        self.ensure_access(&registry, name)?;
+        if registry
+            .access
+            .get(name)
+            .is_some_and(|access| access.visibility != models::Visibility::Public)
+        {
+            return Err(BeepkgError::AccessDenied(format!(
+                "{} is not public, presigned URLs would bypass its access control",
+                name
+            )));
+        }
+
+        let archive_name = format!("{}-{}.zip", name, version);
+        if self.head_object(&archive_name).await?.is_none() {
+            return Err(BeepkgError::NotFound(package.to_string()));
+        }
+        // 与拉取时相同的顺序查找校验文件
+        let checksum = self.fetch_checksum(&archive_name).await?;
+        let checksum_name = checksum.algorithm.sidecar_name(&archive_name);
+
+        let archive_url = self
+            .bucket
+            .get_object(Some(&credentials), &archive_name)
+            .sign(expires);
+        let checksum_url = self
+            .bucket
+            .get_object(Some(&credentials), &checksum_name)
+            .sign(expires);
+        Ok((archive_url, checksum_url))
+    }
+
+    /// 生成静态索引的内容。presign 指定时下载地址为预签名地址，否则为对象地址（需要 bucket 公开可读）；
+    /// metadata 为 true 时下载每个版本读取 pack.toml 中的作者、描述和依赖
+    pub async fn site_index(
+        &self,
+        presign: Option<Duration>,
+        metadata: bool,
+    ) -> Result<site::SiteIndex, BeepkgError> {
+        let credentials = match presign {
+            Some(expires) => {
+                if expires.is_zero() || expires > MAX_PRESIGN_EXPIRY {
+                    return Err("Presigned URLs must expire within 7 days".into());
+                }
+                if self.bearer_token.is_some() {
+                    return Err("Presigned URLs require S3 credentials, not a bearer token".into());
+                }
+                let credentials = self
+                    .credentials()
+                    .await?
+                    .ok_or("Presigned URLs require S3 credentials")?;
+                Some((credentials, expires))
+            }
+            None => None,
+        };
+        let registry = self.get_registry_metadata().await?;
+        let now = chrono::Utc::now();
+
+        let mut grouped: BTreeMap<String, Vec<models::Package>> = BTreeMap::new();
+        for package in self.list_packages().await? {
+            grouped
+                .entry(package.name.clone())
+                .or_default()
+                .push(package);
+        }
+
+        let mut packages = Vec::new();
+        for (name, mut versions) in grouped {
+            // 从新到旧，无法解析的版本排在最后
+            versions.sort_by(|a, b| {
+                let a = semver::Version::parse(&a.version).ok();
+                let b = semver::Version::parse(&b.version).ok();
+                b.cmp(&a)
+            });
+
+            let mut entries = Vec::new();
+            for package in versions {
+                let key = &package.storage.path;
+                let url = match &credentials {
+                    Some((credentials, expires)) => self
+                        .bucket
+                        .get_object(Some(credentials), key)
+                        .sign(*expires),
+                    None => self
+                        .bucket
+                        .object_url(key)
+                        .map_err(|e| BeepkgError::Other(e.to_string()))?,
+                };
+                let checksum = match self.fetch_checksum(key).await {
+                    Ok(checksum) => Some(format!("{}:{}", checksum.algorithm.name(), checksum.hex)),
+                    Err(BeepkgError::MissingChecksum) => None,
+                    Err(e) => return Err(e),
+                };
+                let lock = registry.locked_packages.iter().find(|lock| {
+                    lock.name == package.name
+                        && lock.version == package.version
+                        && !lock.is_expired(now)
+                });
+                let metadata = if metadata {
+                    match self.site_metadata(key).await {
+                        Ok(metadata) => Some(metadata),
+                        Err(e) => {
+                            self.emit(Event::Warning(format!(
+                                "failed to read metadata of {}: {}",
+                                key, e
+                            )));
+                            None
+                        }
+                    }
+                } else {
+                    None
+                };
+                entries.push(site::SiteVersion {
+                    file: key.rsplit('/').next().unwrap_or(key).to_string(),
+                    url: url.to_string(),
+                    size: package.storage.size,
+                    created_at: package.storage.created_at.clone(),
+                    checksum,
+                    locked: lock.is_some(),
+                    lock_reason: lock.map(|lock| lock.lock_reason.clone()),
+                    metadata,
+                    version: package.version,
+                });
+            }
+
+            packages.push(site::SitePackage {
+                latest: entries[0].version.clone(),
+                channels: registry.channels.get(&name).cloned().unwrap_or_default(),
+                versions: entries,
+                name,
+            });
+        }
+
+        Ok(site::SiteIndex {
+            registry: self.cache_registry(),
+            generated_at: now.to_rfc3339(),
+            packages,
+        })
+    }
+
+    // 下载并验证包文件（优先使用缓存），读取其中的 pack.toml
+    async fn site_metadata(&self, key: &str) -> Result<site::SiteMetadata, BeepkgError> {
+        let info = self.archive_info(key).await?;
+        Ok(site::SiteMetadata {
+            author: info.author,
+            description: info.description,
+            dependencies: info.dependencies,
+        })
+    }
+
+    /// 查看包的元数据（作者、描述、许可证和依赖），spec 为 name 或 name@version
+    pub async fn package_info(&self, spec: &str) -> Result<models::PackageInfo, BeepkgError> {
+        let registry = self.get_registry_metadata().await?;
+        let (name, version) = self.resolve_package_spec(&registry, spec).await?;
+        self.archive_info(&format!("{}-{}.zip", name, version))
+            .await
+    }
+
+    /// 按名称、描述和关键字搜索包，每个包只检查最新版本，结果按名称排列
+    pub async fn search_packages(
+        &self,
+        query: &str,
+    ) -> Result<Vec<models::PackageInfo>, BeepkgError> {
+        let mut latest: BTreeMap<String, models::Package> = BTreeMap::new();
+        for package in self.list_packages().await? {
+            // 能解析为 semver 的版本高于无法解析的版本
+            let newer = latest.get(&package.name).is_none_or(|current| {
+                semver::Version::parse(&package.version).ok()
+                    > semver::Version::parse(&current.version).ok()
+            });
+            if newer {
+                latest.insert(package.name.clone(), package);
+            }
+        }
+
+        let mut results = Vec::new();
+        for package in latest.into_values() {
+            match self.archive_info(&package.storage.path).await {
+                Ok(info) if info.matches(query) => results.push(info),
+                Ok(_) => {}
+                Err(e) => self.emit(Event::Warning(format!(
+                    "Failed to read metadata of {}@{}: {}",
+                    package.name, package.version, e
+                ))),
+            }
+        }
+        Ok(results)
+    }
+
+    // 优先读取推送时上传的元数据摘要，较早推送的包没有摘要时下载归档读取
+    async fn archive_info(&self, key: &str) -> Result<models::PackageInfo, BeepkgError> {
+        if let Some(content) = self.get_object_bytes(&package_info_key(key)).await? {
+            return schema::from_json(&content);
+        }
+        let temp = tempfile::NamedTempFile::new()?.into_temp_path();
+        self.fetch_verified(key, &temp, false).await?;
+        let metadata = self.get_package_metadata(&temp)?;
+        Ok(models::PackageInfo::from(&metadata))
+    }
+
+    async fn upload_package_info(
+        &self,
+        archive_name: &str,
+        metadata: &models::PackageMetadata,
+    ) -> Result<(), BeepkgError> {
+        let mut info = models::PackageInfo::from(metadata);
+        if let Some(notes) = &self.release_notes {
+            info.release_notes = Some(notes.clone());
+        }
+        self.put_object_bytes(
+            &package_info_key(archive_name),
+            serde_json::to_vec_pretty(&info)?,
+            "application/json",
+        )
+        .await
+    }
+
+    /// 读取包的 README：优先推送时上传的副本，没有时用范围请求只下载归档中的 README 文件
+    pub async fn readme(&self, spec: &str) -> Result<String, BeepkgError> {
+        let registry = self.get_registry_metadata().await?;
+        let (name, version) = self.resolve_package_spec(&registry, spec).await?;
+        let zip_name = format!("{}-{}.zip", name, version);
+        if let Some(content) = self.get_object_bytes(&readme_key(&zip_name)).await? {
+            return Ok(String::from_utf8_lossy(&content).to_string());
+        }
+
+        // 元数据摘要中可能声明了 README 的路径，没有摘要时按常见文件名查找
+        let declared = match self.get_object_bytes(&package_info_key(&zip_name)).await? {
+            Some(content) => schema::from_json::<models::PackageInfo>(&content)?.readme,
+            None => None,
+        };
+        let content = self
+            .read_archive_entry(&zip_name, |names| readme::find(names, declared.as_deref()))
+            .await?
+            .ok_or_else(|| BeepkgError::NotFound(format!("README of {}@{}", name, version)))?;
+        Ok(String::from_utf8_lossy(&content).to_string())
+    }
+
+    // 用范围请求读取归档中的一个文件：先下载归档末尾找到中央目录，再只下载该文件的数据。
+    // select 从文件名中选择要读取的文件，没有选中时返回 None
+    async fn read_archive_entry(
+        &self,
+        key: &str,
+        select: impl FnOnce(Vec<&str>) -> Option<String>,
+    ) -> Result<Option<Vec<u8>>, BeepkgError> {
+        let tail_range = format!("bytes=-{}", readme::TAIL_SIZE);
+        let mut key = key.to_string();
+        let (offset, tail, len) = loop {
+            let (offset, data, len) = self.get_object_range(&key, &tail_range).await?;
+            // 内容寻址布局下包文件是指向 blob 的指针
+            match BlobPointer::parse(&data).filter(|_| offset == 0 && data.len() as u64 == len) {
+                Some(pointer) => key = pointer.blob,
+                None => break (offset, data, len),
+            }
+        };
+
+        let (directory_offset, directory_size) = readme::central_directory(&tail)
+            .ok_or_else(|| format!("{} is not a readable zip archive (encrypted?)", key))?;
+        let directory_end = directory_offset + directory_size;
+        let mut archive = readme::SparseArchive::new(len);
+        archive.insert(offset, tail);
+        if !archive.contains(directory_offset, directory_end) {
+            let range = format!("bytes={}-{}", directory_offset, directory_end - 1);
+            let (offset, data, _) = self.get_object_range(&key, &range).await?;
+            archive.insert(offset, data);
+        }
+        let entries = readme::parse_central_directory(
+            archive
+                .slice(directory_offset, directory_end)
+                .unwrap_or_default(),
+        );
+        let Some(name) = select(entries.iter().map(|entry| entry.name.as_str()).collect()) else {
+            return Ok(None);
+        };
+        let Some(entry) = entries.iter().find(|entry| entry.name == name) else {
+            return Ok(None);
+        };
+
+        let (start, end) = entry.range(len);
+        if !archive.contains(start, end) {
+            let range = format!("bytes={}-{}", start, end - 1);
+            let (offset, data, _) = self.get_object_range(&key, &range).await?;
+            archive.insert(offset, data);
+        }
+        // 本地扩展字段比中央目录中的长时补齐剩余部分
+        let header = archive.slice(start, start + 30).unwrap_or_default();
+        if let Some(actual) = entry.local_end(header).filter(|actual| *actual > end) {
+            let range = format!("bytes={}-{}", end, actual.min(len) - 1);
+            let (offset, data, _) = self.get_object_range(&key, &range).await?;
+            archive.insert(offset, data);
+        }
+        let mut zip = zip::ZipArchive::new(archive)?;
+        let mut content = Vec::new();
+        zip.by_name(&name)?.read_to_end(&mut content)?;
+        Ok(Some(content))
+    }
+
+    /// 读取 from 到 to（含两端）之间各版本的元数据和发布说明，从新到旧排列
+    pub async fn changelog(
+        &self,
+        name: &str,
+        from: Option<&semver::Version>,
+        to: Option<&semver::Version>,
+    ) -> Result<Vec<models::PackageInfo>, BeepkgError> {
+        let registry = self.get_registry_metadata().await?;
+        self.ensure_access(&registry, name)?;
+        let mut versions: Vec<semver::Version> = self
+            .package_versions(name)
+            .await?
+            .into_iter()
+            .filter(|version| from.is_none_or(|from| version >= from))
+            .filter(|version| to.is_none_or(|to| version <= to))
+            .collect();
+        if versions.is_empty() {
+            return Err(BeepkgError::NotFound(format!(
+                "versions of {} in the requested range",
+                name
+            )));
+        }
+        versions.sort_by(|a, b| b.cmp(a));
+
+        let mut releases = Vec::new();
+        for version in versions {
+            releases.push(
+                self.archive_info(&format!("{}-{}.zip", name, version))
+                    .await?,
+            );
+        }
+        Ok(releases)
+    }
+
+    /// 测试连接到 MinIO 存储和 bucket 的可用性，返回是否成功和第一个失败阶段的说明；
+    /// 逐个阶段的结果见 diagnose
+    pub async fn test_connection(&self) -> Result<(bool, String), BeepkgError> {
+        let diagnosis = self.diagnose(true).await?;
+        Ok(match diagnosis.first_failure() {
+            Some(result) => (
+                false,
+                format!("{}: {}", result.stage.title(), result.detail),
+            ),
+            None => (
+                true,
+                format!("成功连接到存储服务，bucket '{}' 可用", self.bucket.name()),
+            ),
+        })
+    }
+
+    /// 分阶段诊断到存储服务的连接：DNS、TCP、TLS、凭证、写权限、时钟偏差和往返延迟，
+    /// 网络层失败时跳过后面的阶段。write 为 false 时不上传探测对象
+    pub async fn diagnose(&self, write: bool) -> Result<diagnostics::Diagnosis, BeepkgError> {
+        use diagnostics::{Stage, Status};
+
+        if self.offline {
+            return Err(BeepkgError::Offline("connection test".to_string()));
+        }
+        let base = self.bucket.base_url().clone();
+        let mut diagnosis = diagnostics::Diagnosis::new(base.as_str(), self.bucket.name());
+
+        // 配置了代理时直接连接的是代理
+        let (target, via) = match &self.proxy {
+            Some(proxy) => (
+                url::Url::parse(&proxy.url)
+                    .map_err(|e| BeepkgError::Config(format!("Invalid proxy URL: {}", e)))?,
+                " (proxy)",
+            ),
+            None => (base.clone(), ""),
+        };
+        let host = target.host_str().unwrap_or_default().to_string();
+        let default_port = if target.scheme().starts_with("socks") {
+            1080
+        } else {
+            80
+        };
+        let port = target.port_or_known_default().unwrap_or(default_port);
+
+        let started = Instant::now();
+        let resolved = match tokio::time::timeout(
+            self.timeout,
+            tokio::net::lookup_host((host.as_str(), port)),
+        )
+        .await
+        {
+            Ok(Ok(addresses)) => Ok(addresses.collect::<Vec<_>>()),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => Err("timed out".to_string()),
+        };
+        let addresses = match resolved {
+            Ok(addresses) if !addresses.is_empty() => {
+                let list: Vec<String> = addresses.iter().map(|a| a.ip().to_string()).collect();
+                diagnosis.push_timed(
+                    Stage::Dns,
+                    Status::Ok,
+                    format!("{}{} -> {}", host, via, list.join(", ")),
+                    started.elapsed(),
+                );
+                addresses
+            }
+            other => {
+                let reason = other.err().unwrap_or_else(|| "no addresses".to_string());
+                diagnosis.push(
+                    Stage::Dns,
+                    Status::Failed,
+                    format!("cannot resolve {}{}: {}", host, via, reason),
+                );
+                diagnosis.skip_remaining("skipped because DNS failed");
+                return Ok(diagnosis);
+            }
+        };
+
+        let started = Instant::now();
+        match tokio::time::timeout(self.timeout, tokio::net::TcpStream::connect(&addresses[..]))
+            .await
+        {
+            Ok(Ok(stream)) => {
+                let peer = stream
+                    .peer_addr()
+                    .map(|a| a.to_string())
+                    .unwrap_or_default();
+                diagnosis.push_timed(
+                    Stage::Tcp,
+                    Status::Ok,
+                    format!("connected to {}{}", peer, via),
+                    started.elapsed(),
+                );
+            }
+            failed => {
+                let reason = match failed {
+                    Ok(Err(e)) => e.to_string(),
+                    _ => "timed out".to_string(),
+                };
+                diagnosis.push(
+                    Stage::Tcp,
+                    Status::Failed,
+                    format!("cannot connect to {}:{}{}: {}", host, port, via, reason),
+                );
+                diagnosis.skip_remaining("skipped because TCP failed");
+                return Ok(diagnosis);
+            }
+        }
+
+        // 只检查握手，任何 HTTP 响应都说明 TLS 正常
+        let mut server_date = None;
+        if base.scheme() == "https" {
+            let started = Instant::now();
+            match self.client.head(base.clone()).send().await {
+                Ok(response) => {
+                    server_date = response.headers().get(reqwest::header::DATE).cloned();
+                    diagnosis.push_timed(
+                        Stage::Tls,
+                        Status::Ok,
+                        "handshake succeeded",
+                        started.elapsed(),
+                    );
+                }
+                Err(e) => {
+                    diagnosis.push(Stage::Tls, Status::Failed, diagnostics::error_chain(&e));
+                    diagnosis.skip_remaining("skipped because TLS failed");
+                    return Ok(diagnosis);
+                }
+            }
+        } else {
+            diagnosis.push(Stage::Tls, Status::Skipped, "plain HTTP endpoint");
+        }
+
+        let started = Instant::now();
+        let response = match self
+            .send(self.client.get(self.probe_list_url().await?))
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => {
+                diagnosis.push(Stage::Auth, Status::Failed, e.to_string());
+                diagnosis.skip_remaining("skipped because the request failed");
+                return Ok(diagnosis);
+            }
+        };
+        let elapsed = started.elapsed();
+        if let Some(date) = response.headers().get(reqwest::header::DATE) {
+            server_date = Some(date.clone());
+        }
+        let status = response.status();
+        let content = response.text().await.unwrap_or_default();
+        let authenticated = status.is_success();
+        if !authenticated {
+            let code = diagnostics::error_code(&content);
+            let detail = diagnostics::explain_auth_failure(status.as_u16(), code);
+            diagnosis.push_timed(Stage::Auth, Status::Failed, detail, elapsed);
+        } else if let Err(e) = from_str::<ListObjectsResponse>(&content) {
+            diagnosis.push_timed(
+                Stage::Auth,
+                Status::Failed,
+                format!("the response is not a bucket listing: {}", e),
+                elapsed,
+            );
+        } else {
+            diagnosis.push_timed(
+                Stage::Auth,
+                Status::Ok,
+                format!("signed list of bucket '{}' succeeded", self.bucket.name()),
+                elapsed,
+            );
+        }
+
+        if !write {
+            diagnosis.push(Stage::Write, Status::Skipped, "not requested");
+        } else if diagnosis.get(Stage::Auth).map(|r| r.status) != Some(Status::Ok) {
+            diagnosis.push(
+                Stage::Write,
+                Status::Skipped,
+                "skipped because authentication failed",
+            );
+        } else {
+            // 以 .tmp 结尾，删除失败时 gc 会清理
+            let key = format!(".beepkg-probe-{:016x}.tmp", rand::random::<u64>());
+            let started = Instant::now();
+            let (status, detail) = match self
+                .put_object_bytes(&key, "beepkg connection test", "text/plain")
+                .await
+            {
+                Err(BeepkgError::AccessDenied(e)) => (
+                    Status::Warning,
+                    format!("the credentials are read-only, pushes will fail: {}", e),
+                ),
+                Err(e) => (Status::Failed, format!("cannot upload {}: {}", key, e)),
+                Ok(()) => match self.delete_object(&key).await {
+                    Ok(()) => (Status::Ok, format!("uploaded and deleted {}", key)),
+                    Err(e) => (
+                        Status::Warning,
+                        format!(
+                            "uploaded {} but cannot delete it (gc removes it): {}",
+                            key, e
+                        ),
+                    ),
+                },
+            };
+            diagnosis.push_timed(Stage::Write, status, detail, started.elapsed());
+        }
+
+        match server_date
+            .as_ref()
+            .and_then(|date| date.to_str().ok())
+            .and_then(|date| diagnostics::clock_skew(date, chrono::Utc::now()))
+        {
+            Some(skew) => {
+                let (status, detail) = diagnostics::check_clock(skew);
+                diagnosis.push(Stage::Clock, status, detail);
+            }
+            None => diagnosis.push(
+                Stage::Clock,
+                Status::Skipped,
+                "the server sent no Date header",
+            ),
+        }
+
+        // 签名请求的往返时间，不论凭证是否有效
+        let mut samples = Vec::new();
+        for _ in 0..3 {
+            let started = Instant::now();
+            if let Err(e) = self
+                .send(self.client.get(self.probe_list_url().await?))
+                .await
+            {
+                diagnosis.push(Stage::Latency, Status::Failed, e.to_string());
+                return Ok(diagnosis);
+            }
+            samples.push(started.elapsed());
+        }
+        let average = samples.iter().sum::<Duration>() / samples.len() as u32;
+        let (min, max) = (
+            samples.iter().min().copied().unwrap_or_default(),
+            samples.iter().max().copied().unwrap_or_default(),
+        );
+        let status = if average.as_millis() as u64 > diagnostics::WARN_LATENCY_MS {
+            Status::Warning
+        } else {
+            Status::Ok
+        };
+        diagnosis.push_timed(
+            Stage::Latency,
+            status,
+            format!(
+                "min {} ms, avg {} ms, max {} ms over {} requests",
+                min.as_millis(),
+                average.as_millis(),
+                max.as_millis(),
+                samples.len()
+            ),
+            average,
+        );
+        Ok(diagnosis)
+    }
+
+    // 只列出一个对象的签名 list 请求
+    async fn probe_list_url(&self) -> Result<url::Url, BeepkgError> {
+        let credentials = self.credentials().await?;
+        let mut action = self.bucket.list_objects_v2(credentials.as_ref());
+        action.with_max_keys(1);
+        Ok(action.sign(Duration::from_secs(60)))
+    }
+
+    // 锁定特定版本的包，防止被修改
+    pub async fn lock_package(
+        &self,
+        package_name: &str,
+        version: &str,
+        reason: &str,
+        user: &str,
+        expires_at: Option<&str>,
+    ) -> Result<(), BeepkgError> {
+        // 校验过期时间格式
+        if let Some(ts) = expires_at {
+            chrono::DateTime::parse_from_rfc3339(ts)
+                .map_err(|_| format!("Invalid expiry timestamp (expected RFC 3339): {}", ts))?;
+        }
+
+        // 获取注册表元数据
+        let mut metadata = self.get_registry_metadata().await?;
+
+        // 清理该包已过期的锁
+        let now_utc = chrono::Utc::now();
+        metadata.locked_packages.retain(|lp| {
+            !(lp.name == package_name && lp.version == version && lp.is_expired(now_utc))
+        });
+
+        // 检查包是否存在
+        let packages = self.list_packages().await?;
+        let found = packages
+            .iter()
+            .any(|p| p.name == package_name && p.version == version);
+
+        if !found {
+            return Err(BeepkgError::NotFound(format!(
+                "{}@{}",
+                package_name, version
+            )));
+        }
+
+        // 检查包是否已经被锁定
+        if metadata
+            .locked_packages
+            .iter()
+            .any(|lp| lp.name == package_name && lp.version == version)
+        {
+            return Err(BeepkgError::Locked(format!("{}@{}", package_name, version)));
+        }
+
+        // 添加锁定信息
+        let now = chrono::Utc::now().to_rfc3339();
+        // Get package checksum if available
+        let package = packages
+            .iter()
+            .find(|p| p.name == package_name && p.version == version);
+        let checksum = package.map_or("".to_string(), |p| p.storage.checksum.clone());
+
+        metadata.locked_packages.push(models::LockedPackage {
+            name: package_name.to_string(),
+            version: version.to_string(),
+            lock_reason: reason.to_string(),
+            locked_at: now.clone(),
+            locked_by: user.to_string(),
+            checksum,
+            expires_at: expires_at.map(str::to_string),
+        });
+
+        metadata.last_updated = now;
+
+        // 保存更新后的元数据
+        self.save_registry_metadata(&metadata).await?;
+
+        self.record_audit(
+            models::AuditAction::Lock,
+            &format!("{}@{}", package_name, version),
+            Some(format!("locked by {}: {}", user, reason)),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    // 列出当前仍然有效的锁，可按包名过滤
+    pub async fn list_locks(
+        &self,
+        package_name: Option<&str>,
+    ) -> Result<Vec<models::LockedPackage>, BeepkgError> {
+        let metadata = self.get_registry_metadata().await?;
+        let now = chrono::Utc::now();
+
+        let locks = metadata
+            .locked_packages
+            .into_iter()
+            .filter(|lp| package_name.is_none_or(|name| lp.name == name))
+            .filter(|lp| !lp.is_expired(now))
+            .collect();
+
+        Ok(locks)
+    }
+
+    // 解锁特定版本的包
+    pub async fn unlock_package(
+        &self,
+        package_name: &str,
+        version: &str,
+    ) -> Result<(), BeepkgError> {
+        // 获取注册表元数据
+        let mut metadata = self.get_registry_metadata().await?;
+
+        // 查找锁定的包索引
+        let index = metadata
+            .locked_packages
+            .iter()
+            .position(|lp| lp.name == package_name && lp.version == version);
+
+        if let Some(idx) = index {
+            // 移除锁定信息
+            metadata.locked_packages.remove(idx);
+            metadata.last_updated = chrono::Utc::now().to_rfc3339();
+
+            // 保存更新后的元数据
+            self.save_registry_metadata(&metadata).await?;
+
+            self.record_audit(
+                models::AuditAction::Unlock,
+                &format!("{}@{}", package_name, version),
+                None,
+            )
+            .await?;
+            Ok(())
+        } else {
+            Err(format!("Package {}@{} is not locked", package_name, version).into())
+        }
+    }
+
+    // 备份特定版本的包
+    pub async fn backup_package(
+        &self,
+        package_name: &str,
+        version: &str,
+        reason: &str,
+    ) -> Result<(), BeepkgError> {
+        // 检查包是否存在
+        let packages = self.list_packages().await?;
+        let package = packages
+            .iter()
+            .find(|p| p.name == package_name && p.version == version);
+
+        let package = match package {
+            Some(pkg) => pkg,
+            None => {
+                return Err(BeepkgError::NotFound(format!(
+                    "{}@{}",
+                    package_name, version
+                )));
+            }
+        };
+
+        // 获取注册表元数据
+        let mut metadata = self.get_registry_metadata().await?;
+
+        // 如果备份未启用，则启用它
+        if !metadata.backup_enabled {
+            metadata.backup_enabled = true;
+        }
+
+        // 创建备份名称
+        let now = chrono::Utc::now();
+        let timestamp = now.to_rfc3339();
+        let backup_name = format!(
+            "{}-{}-backup-{}.zip",
+            package_name,
+            version,
+            now.timestamp()
+        );
+
+        // 复制包到备份位置
+        let source_key = &package.storage.path;
+        let bytes = match &self.backup_store {
+            // 独立的备份 bucket 中保存完整内容，而不是指向主 bucket 中内容寻址对象的指针
+            Some(_) => self
+                .get_object_bytes(source_key)
+                .await?
+                .ok_or_else(|| format!("Failed to download object for backup: {}", source_key))?,
+            None => {
+                let credentials = self.credentials().await?;
+                let action = self.bucket.get_object(credentials.as_ref(), source_key);
+                let url = action.sign(Duration::from_secs(3600));
+
+                // 下载原始对象
+                let response = self.send(self.client.get(url)).await?;
+                if !response.status().is_success() {
+                    return Err(BeepkgError::from_status(
+                        response.status(),
+                        "Failed to download object for backup",
+                    ));
+                }
+                response.bytes().await?
+            }
+        };
+
+        // 上传备份对象，使用独立的备份 bucket 时按其自身的凭证签名
+        let store = self.backup_store.as_deref().unwrap_or(self);
+        let storage_class = self
+            .backup_storage_class
+            .as_ref()
+            .map(|class| ("x-amz-storage-class", class.clone()));
+        let request = store
+            .put_request_with_headers(&backup_name, "application/zip", storage_class)
+            .await?
+            .body(bytes);
+        let response = store.send(request).await?;
+
+        if !response.status().is_success() {
+            return Err(BeepkgError::from_status(
+                response.status(),
+                "Failed to upload backup",
+            ));
+        }
+
+        // 更新元数据
+        metadata.backups.push(models::PackageBackup {
+            original_path: source_key.to_string(),
+            backup_path: backup_name,
+            timestamp,
+            reason: reason.to_string(),
+            bucket: self
+                .backup_store
+                .as_ref()
+                .map(|store| store.bucket.name().to_string()),
+        });
+
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+
+        // 保存更新后的元数据
+        self.save_registry_metadata(&metadata).await?;
+
+        self.record_audit(
+            models::AuditAction::Backup,
+            &format!("{}@{}", package_name, version),
+            Some(reason.to_string()),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// 添加定时备份计划
+    pub async fn add_backup_schedule(&self, cron: &str, filter: &str) -> Result<(), BeepkgError> {
+        CronSchedule::parse(cron)?;
+        globset::Glob::new(filter).map_err(|e| format!("Invalid filter {}: {}", filter, e))?;
+        let mut metadata = self.get_registry_metadata().await?;
+        metadata.backup_schedules.push(models::BackupSchedule {
+            cron: cron.to_string(),
+            filter: filter.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            last_run: None,
+        });
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&metadata).await
+    }
+
+    /// 定时备份计划及最近一次执行的结果
+    pub async fn list_backup_schedules(&self) -> Result<Vec<models::BackupSchedule>, BeepkgError> {
+        Ok(self.get_registry_metadata().await?.backup_schedules)
+    }
+
+    // 按序号（从 1 开始，与 list 输出一致）删除定时备份计划
+    pub async fn remove_backup_schedule(&self, index: usize) -> Result<(), BeepkgError> {
+        let mut metadata = self.get_registry_metadata().await?;
+        if index == 0 || index > metadata.backup_schedules.len() {
+            return Err(BeepkgError::NotFound(format!("backup schedule #{}", index)));
+        }
+        metadata.backup_schedules.remove(index - 1);
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&metadata).await
+    }
+
+    /// 执行到期的定时备份：自上次执行（从未执行时为创建时间）以来到过 cron 时间的计划，
+    /// 备份名称匹配的每个包的最新版本，结果记录在注册表元数据中。适合由 cron 或 Kubernetes CronJob 定期调用，
+    /// 返回执行的计划序号（从 1 开始）及结果
+    pub async fn run_due_backups(&self) -> Result<Vec<(usize, models::BackupRun)>, BeepkgError> {
+        let now = chrono::Utc::now();
+        let schedules = self.get_registry_metadata().await?.backup_schedules;
+        let mut due = Vec::new();
+        for (index, schedule) in schedules.iter().enumerate() {
+            let since = schedule
+                .last_run
+                .as_ref()
+                .map_or(schedule.created_at.as_str(), |run| run.started_at.as_str());
+            let since = chrono::DateTime::parse_from_rfc3339(since)?.with_timezone(&chrono::Utc);
+            if CronSchedule::parse(&schedule.cron)?.is_due(since, now) {
+                due.push(index);
+            }
+        }
+        if due.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let packages = self.list_packages().await?;
+        let mut runs = Vec::new();
+        for index in due {
+            let schedule = &schedules[index];
+            let matcher = globset::Glob::new(&schedule.filter)?.compile_matcher();
+            // 每个包只备份最新版本
+            let mut latest: BTreeMap<&str, semver::Version> = BTreeMap::new();
+            for package in packages.iter().filter(|p| matcher.is_match(&p.name)) {
+                let Ok(version) = semver::Version::parse(&package.version) else {
+                    continue;
+                };
+                if latest
+                    .get(package.name.as_str())
+                    .is_none_or(|v| version > *v)
+                {
+                    latest.insert(&package.name, version);
+                }
+            }
+
+            let mut run = models::BackupRun {
+                started_at: now.to_rfc3339(),
+                backed_up: Vec::new(),
+                failed: Vec::new(),
+            };
+            let reason = format!("scheduled backup ({})", schedule.cron);
+            for (name, version) in latest {
+                let spec = format!("{}@{}", name, version);
+                match self
+                    .backup_package(name, &version.to_string(), &reason)
+                    .await
+                {
+                    Ok(()) => run.backed_up.push(spec),
+                    Err(e) => run.failed.push(format!("{}: {}", spec, e)),
+                }
+            }
+            runs.push((index + 1, run));
+        }
+
+        // 备份过程中元数据已被更新，重新读取后再记录执行结果
+        let mut metadata = self.get_registry_metadata().await?;
+        for (number, run) in &runs {
+            if let Some(schedule) = metadata.backup_schedules.get_mut(number - 1) {
+                schedule.last_run = Some(run.clone());
+            }
+        }
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&metadata).await?;
+        Ok(runs)
+    }
+
+    /// 注册表元数据中记录的备份，按包名、版本（semver 顺序）和时间排列，可按包名和版本过滤。
+    /// 大小从每个存储桶的一次列表中读取，备份对象已被删除时为 None
+    pub async fn list_backups(
+        &self,
+        package_name: Option<&str>,
+        version: Option<&str>,
+    ) -> Result<Vec<BackupEntry>, BeepkgError> {
+        let metadata = self.get_registry_metadata().await?;
+        let mut entries: Vec<BackupEntry> = metadata
+            .backups
+            .into_iter()
+            .filter_map(|backup| {
+                let (name, backup_version) = backup_source(&backup.original_path)?;
+                let matches = package_name.is_none_or(|n| n == name)
+                    && version.is_none_or(|v| v == backup_version);
+                matches.then(|| BackupEntry {
+                    package: name.to_string(),
+                    version: backup_version.to_string(),
+                    timestamp: backup.timestamp,
+                    reason: backup.reason,
+                    backup_path: backup.backup_path,
+                    bucket: backup.bucket,
+                    size: None,
+                })
+            })
+            .collect();
+        if entries.is_empty() {
+            return Ok(entries);
+        }
+
+        let mut sizes: HashMap<(Option<String>, String), u64> = HashMap::new();
+        let mut stores = vec![(None, self)];
+        if let Some(store) = &self.backup_store {
+            let bucket = store.bucket.name().to_string();
+            if entries
+                .iter()
+                .any(|entry| entry.bucket.as_ref() == Some(&bucket))
+            {
+                stores.push((Some(bucket), store.as_ref()));
+            }
+        }
+        for (bucket, store) in stores {
+            for object in store.list_objects("", None).await? {
+                if models::parse_backup_key(&object.key).is_some() {
+                    sizes.insert((bucket.clone(), object.key), object.size.unwrap_or(0));
+                }
+            }
+        }
+        for entry in &mut entries {
+            entry.size = sizes
+                .get(&(entry.bucket.clone(), entry.backup_path.clone()))
+                .copied();
+        }
+        // 版本按 semver 排序（1.9.0 在 1.10.0 之前），无法解析的版本排在最后
+        entries.sort_by_cached_key(|entry| {
+            let version = semver::Version::parse(&entry.version).ok();
+            (
+                entry.package.clone(),
+                version.is_none(),
+                version,
+                entry.version.clone(),
+                entry.timestamp.clone(),
+            )
+        });
+        Ok(entries)
+    }
+
+    /// 按保留规则清理备份，返回被清理的备份记录。先从注册表元数据中移除记录再删除对象：
+    /// 保存元数据即完成清理，之后删除对象失败时留下的对象会被 gc 作为未记录的备份清理，
+    /// 而不会出现元数据引用已删除对象的情况。dry_run 时只返回将被清理的记录
+    pub async fn prune_backups(
+        &self,
+        keep_last: Option<usize>,
+        keep_days: Option<u64>,
+        dry_run: bool,
+    ) -> Result<Vec<models::PackageBackup>, BeepkgError> {
+        if keep_last.is_none() && keep_days.is_none() {
+            return Err("Specify --keep-last and/or --keep-days".into());
+        }
+        let mut metadata = self.get_registry_metadata().await?;
+        let pruned_indices =
+            retention::prune_backups(&metadata.backups, keep_last, keep_days, chrono::Utc::now());
+        let (pruned, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut metadata.backups)
+            .into_iter()
+            .enumerate()
+            .partition(|(index, _)| pruned_indices.binary_search(index).is_ok());
+        let pruned: Vec<models::PackageBackup> =
+            pruned.into_iter().map(|(_, backup)| backup).collect();
+        if dry_run || pruned.is_empty() {
+            return Ok(pruned);
+        }
+
+        metadata.backups = kept.into_iter().map(|(_, backup)| backup).collect();
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&metadata).await?;
+        for backup in &pruned {
+            self.backup_store_for(backup)?
+                .delete_object(&backup.backup_path)
+                .await?;
+        }
+        Ok(pruned)
+    }
+
+    // 从备份恢复特定版本的包
+    pub async fn restore_package_from_backup(
+        &self,
+        package_name: &str,
+        version: &str,
+        timestamp: Option<&str>,
+    ) -> Result<(), BeepkgError> {
+        // 获取注册表元数据
+        let metadata = self.get_registry_metadata().await?;
+
+        // 查找备份
+        let mut filtered_backups: Vec<&models::PackageBackup> = metadata
+            .backups
+            .iter()
+            .filter(|b| backup_source(&b.original_path) == Some((package_name, version)))
+            .collect();
+
+        if filtered_backups.is_empty() {
+            return Err(BeepkgError::NotFound(format!(
+                "No backups for package {}@{}",
+                package_name, version
+            )));
+        }
+
+        // 如果指定了时间戳，找到特定备份
+        let backup = if let Some(ts) = timestamp {
+            filtered_backups
+                .iter()
+                .find(|b| b.timestamp.starts_with(ts))
+                .ok_or_else(|| BeepkgError::NotFound(format!("No backup with timestamp {}", ts)))?
+        } else {
+            // 否则使用最新的备份
+            filtered_backups.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+            filtered_backups
+                .first()
+                .ok_or_else(|| "Failed to get latest backup".to_string())?
+        };
+        // 只能恢复已被删除的版本，不可变注册表中已存在的版本不能被备份覆盖
+        self.ensure_not_overwriting(package_name, version).await?;
+
+        // 从备份恢复，备份位于独立的备份 bucket 时按其自身的凭证签名
+        let backup_key = &backup.backup_path;
+        let store = self.backup_store_for(backup)?;
+        let credentials = store.credentials().await?;
+        let action = store.bucket.get_object(credentials.as_ref(), backup_key);
+        let url = action.sign(Duration::from_secs(3600));
+
+        // 下载备份对象
+        let response = store.send(store.client.get(url)).await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            // GLACIER、DEEP_ARCHIVE 等归档存储类型的对象需要先取回（RestoreObject）才能读取，
+            // 此时发起取回请求并提示稍后重试，而不是报告为无权访问
+            if response.text().await?.contains("InvalidObjectState") {
+                let message = if store.request_restore(backup_key).await? {
+                    format!(
+                        "requested a restore that keeps it readable for {} days",
+                        BACKUP_RESTORE_DAYS
+                    )
+                } else {
+                    "a restore of it is already in progress".to_string()
+                };
+                return Err(format!(
+                    "Backup {} is in archival storage and cannot be read yet; {}. \
+                     Run the restore again once the storage service has finished, which can take several hours",
+                    backup_key, message
+                )
+                .into());
+            }
+            return Err(BeepkgError::from_status(
+                status,
+                "Failed to download backup",
+            ));
+        }
+
+        let bytes = response.bytes().await?;
+
+        // 确定原始路径
+        let original_key = &backup.original_path;
+
+        // 上传回原始位置
+        let request = self
+            .put_request(original_key, "application/zip")
+            .await?
+            .body(bytes);
+        let response = self.send(request).await?;
+
+        if !response.status().is_success() {
+            return Err(BeepkgError::from_status(
+                response.status(),
+                "Failed to restore package",
+            ));
+        }
+
+        self.record_audit(
+            models::AuditAction::Restore,
+            &format!("{}@{}", package_name, version),
+            Some(format!("from backup {}", backup.timestamp)),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    // 查询审计日志，可按起始时间和包名过滤
+    pub async fn list_audit_records(
+        &self,
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        package_name: Option<&str>,
+    ) -> Result<Vec<models::AuditRecord>, BeepkgError> {
+        // 审计对象的键以时间戳开头，可以直接利用 start-after 跳过更早的记录
+        let start_after =
+            since.map(|ts| format!("{}{}", AUDIT_PREFIX, ts.format("%Y-%m-%dT%H:%M:%S")));
+        let objects = self
+            .list_objects(AUDIT_PREFIX, start_after.as_deref())
+            .await?;
+
+        let mut records = Vec::new();
+        for obj in objects {
+            let Some(content) = self.get_object_bytes(&obj.key).await? else {
+                continue;
+            };
+            let record: models::AuditRecord = serde_json::from_slice(&content)?;
+
+            // 作用域包名以 @ 开头，按最后一个 @ 拆分版本
+            let record_name = models::split_package_spec(&record.package)
+                .map_or(record.package.as_str(), |(name, _)| name);
+            if package_name.is_some_and(|name| name != record_name) {
+                continue;
+            }
+
+            records.push(record);
+        }
+
+        Ok(records)
+    }
+
+    /// 查询下载记录，可按起始时间和包名过滤
+    pub async fn list_downloads(
+        &self,
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        package_name: Option<&str>,
+    ) -> Result<Vec<models::DownloadRecord>, BeepkgError> {
+        // 与审计日志相同，对象键以时间戳开头
+        let start_after =
+            since.map(|ts| format!("{}{}", STATS_PREFIX, ts.format("%Y-%m-%dT%H:%M:%S")));
+        let objects = self
+            .list_objects(STATS_PREFIX, start_after.as_deref())
+            .await?;
+
+        let mut records = Vec::new();
+        for obj in objects {
+            let Some(content) = self.get_object_bytes(&obj.key).await? else {
+                continue;
+            };
+            let record: models::DownloadRecord = serde_json::from_slice(&content)?;
+            if package_name.is_some_and(|name| name != record.package) {
+                continue;
+            }
+            records.push(record);
+        }
+        Ok(records)
+    }
+
+    // 记录一次下载。下载者可能没有写权限，失败只提示，不影响下载
+    async fn record_download(
+        &self,
+        name: &str,
+        version: &str,
+        client: &str,
+        source_ip: Option<String>,
+    ) {
+        let now = chrono::Utc::now();
+        let record = models::DownloadRecord {
+            package: name.to_string(),
+            version: version.to_string(),
+            timestamp: now.to_rfc3339(),
+            client: client.to_string(),
+            source_ip,
+        };
+        let key = format!(
+            "{}{}-{:08x}.json",
+            STATS_PREFIX,
+            now.format("%Y-%m-%dT%H:%M:%S%.6fZ"),
+            rand::random::<u32>()
+        );
+        let result = match serde_json::to_vec(&record) {
+            Ok(content) => {
+                self.put_object_bytes(&key, content, "application/json")
+                    .await
+            }
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = result {
+            self.emit(Event::Warning(format!(
+                "failed to record download of {}@{}: {}",
+                name, version, e
+            )));
+        }
+    }
+
+    // 代理服务在注册表开启下载统计时记录包文件的下载
+    #[cfg(feature = "proxy")]
+    async fn record_served_download(&self, key: &str, client: &str, source_ip: Option<String>) {
+        let Some(name) = archive_package_name(key) else {
+            return;
+        };
+        let Some(version) = key
+            .strip_suffix(".zip")
+            .and_then(|stem| stem.strip_prefix(name))
+            .and_then(|rest| rest.strip_prefix('-'))
+        else {
+            return;
+        };
+        match self.get_registry_metadata().await {
+            Ok(registry) if registry.download_stats => {
+                self.record_download(name, version, client, source_ip).await
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Failed to load registry metadata: {}", e),
+        }
+    }
+
+    // 追加一条审计记录，每条记录单独存为一个对象，保证日志只追加不修改
+    async fn record_audit(
+        &self,
+        action: models::AuditAction,
+        package: &str,
+        details: Option<String>,
+    ) -> Result<(), BeepkgError> {
+        let now = chrono::Utc::now();
+        let record = models::AuditRecord {
+            actor: self.actor.clone(),
+            action,
+            package: package.to_string(),
+            timestamp: now.to_rfc3339(),
+            client_version: env!("CARGO_PKG_VERSION").to_string(),
+            source_ip: None,
+            details,
+        };
+
+        let key = format!(
+            "{}{}-{:08x}.json",
+            AUDIT_PREFIX,
+            now.format("%Y-%m-%dT%H:%M:%S%.6fZ"),
+            rand::random::<u32>()
+        );
+        let content = serde_json::to_string_pretty(&record)?;
+
+        self.put_object_bytes(&key, content.into_bytes(), "application/json")
+            .await?;
+
+        self.dispatch_notifications(&record).await;
+
+        Ok(())
+    }
+
+    // 将事件推送到注册表中配置的 webhook 和通知器，投递失败只记录警告，不影响主操作
+    async fn dispatch_notifications(&self, record: &models::AuditRecord) {
+        let registry_meta = match self.get_registry_metadata().await {
+            Ok(meta) => meta,
+            Err(e) => {
+                log::warn!("Failed to load webhook configuration: {}", e);
+                return;
+            }
+        };
+
+        for webhook in registry_meta
+            .webhooks
+            .iter()
+            .filter(|w| w.matches(record.action))
+        {
+            if let Err(e) = webhooks::deliver(&self.client, webhook, record).await {
+                log::warn!("{}", e);
+            }
+        }
+
+        for notifier in registry_meta
+            .notifiers
+            .iter()
+            .filter(|n| n.matches(record.action))
+        {
+            if let Err(e) = notifiers::notify(&self.client, notifier, record).await {
+                log::warn!("Notification failed: {}", e);
+            }
+        }
+    }
+
+    // 列出注册表中配置的通知器
+    pub async fn list_notifiers(&self) -> Result<Vec<models::NotifierConfig>, BeepkgError> {
+        Ok(self.get_registry_metadata().await?.notifiers)
+    }
+
+    // 添加通知器
+    pub async fn add_notifier(&self, notifier: models::NotifierConfig) -> Result<(), BeepkgError> {
+        let mut metadata = self.get_registry_metadata().await?;
+        metadata.notifiers.push(notifier);
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&metadata).await
+    }
+
+    // 按序号（从 1 开始，与 list 输出一致）删除通知器
+    pub async fn remove_notifier(&self, index: usize) -> Result<(), BeepkgError> {
+        let mut metadata = self.get_registry_metadata().await?;
+        if index == 0 || index > metadata.notifiers.len() {
+            return Err(BeepkgError::NotFound(format!("notifier #{}", index)));
+        }
+        metadata.notifiers.remove(index - 1);
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&metadata).await
+    }
+
+    /// 包的访问控制设置，未记录的包为公开
+    pub async fn package_access(
+        &self,
+        package: &str,
+    ) -> Result<models::PackageAccess, BeepkgError> {
+        let metadata = self.get_registry_metadata().await?;
+        Ok(metadata.access.get(package).cloned().unwrap_or_default())
+    }
+
+    /// 授权用户或团队（team:<name>）访问包，只有所有者可以修改授权
+    pub async fn grant_access(&self, package: &str, principal: &str) -> Result<(), BeepkgError> {
+        validate_principal(principal)?;
+        let mut metadata = self.get_registry_metadata().await?;
+        let access = metadata.access.entry(package.to_string()).or_default();
+        self.check_owner(access, package)?;
+        access.owner.get_or_insert_with(|| self.actor.clone());
+        if !access.grants.iter().any(|grant| grant == principal) {
+            access.grants.push(principal.to_string());
+        }
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&metadata).await
+    }
+
+    /// 撤销用户或团队对包的授权
+    pub async fn revoke_access(&self, package: &str, principal: &str) -> Result<(), BeepkgError> {
+        let mut metadata = self.get_registry_metadata().await?;
+        let access = metadata
+            .access
+            .get_mut(package)
+            .ok_or_else(|| format!("{} has no access grants", package))?;
+        self.check_owner(access, package)?;
+        let before = access.grants.len();
+        access.grants.retain(|grant| grant != principal);
+        if access.grants.len() == before {
+            return Err(format!("{} is not granted access to {}", principal, package).into());
+        }
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&metadata).await
+    }
+
+    // 当前用户是否可以查看和拉取包
+    fn can_access(&self, registry: &models::RegistryMetadata, package: &str) -> bool {
+        visible_to(registry, package, &self.actor, &self.teams)
+    }
+
+    // 拉取和推送前检查访问权限
+    fn ensure_access(
+        &self,
+        registry: &models::RegistryMetadata,
+        package: &str,
+    ) -> Result<(), BeepkgError> {
+        if !self.can_access(registry, package) {
+            return Err(BeepkgError::AccessDenied(format!(
+                "{} is not visible to {}",
+                package, self.actor
+            )));
+        }
+        Ok(())
+    }
+
+    // 推送前检查包名、访问权限以及作用域的签名要求
+    async fn check_publish(&self, package: &str) -> Result<(), BeepkgError> {
+        models::validate_package_name(package)?;
+        if let Some(channel) = &self.channel {
+            models::validate_channel(channel)?;
+        }
+        let registry = self.get_registry_metadata().await?;
+        self.ensure_access(&registry, package)?;
+
+        let scope = models::package_scope(package);
+        let settings = scope.and_then(|scope| registry.scopes.get(scope));
+        if settings.is_some_and(|settings| settings.require_signature)
+            && signing::configured_backend()?.is_none()
+        {
+            return Err(BeepkgError::PolicyViolation(format!(
+                "packages in @{} must be signed, configure a signing key",
+                scope.unwrap_or_default()
+            )));
+        }
+        Ok(())
+    }
+
+    // 不可变注册表中已发布的版本不能被强制推送覆盖，更低的新版本仍可以强制推送
+    async fn ensure_not_overwriting(&self, name: &str, version: &str) -> Result<(), BeepkgError> {
+        if !self.get_registry_metadata().await?.immutable {
+            return Ok(());
+        }
+        match self.check_package_conflict(name, version).await? {
+            PackageConflictStatus::VersionExists => Err(BeepkgError::Conflict(format!(
+                "{}@{} already exists and the registry is immutable; publish a new version",
+                name, version
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    // 推送时指定了发布频道则把频道指向新版本；受保护的频道改为等待批准，批准前新版本不可见
+    fn record_channel(
+        &self,
+        registry: &mut models::RegistryMetadata,
+        metadata: &models::PackageMetadata,
+    ) -> Result<(), BeepkgError> {
+        if let Some(channel) = &self.channel {
+            self.publish_channel(registry, &metadata.name, &metadata.version, channel)?;
+        }
+        registry.advance_latest(&metadata.name, &metadata.version);
+        Ok(())
+    }
+
+    // 把频道指向新发布的版本，频道受保护时改为等待批准
+    fn publish_channel(
+        &self,
+        registry: &mut models::RegistryMetadata,
+        name: &str,
+        version: &str,
+        channel: &str,
+    ) -> Result<(), BeepkgError> {
+        if registry.is_protected(channel) {
+            return self.request_approval(registry, name, version, channel, true);
+        }
+        registry
+            .channels
+            .entry(name.to_string())
+            .or_default()
+            .insert(channel.to_string(), version.to_string());
+        Ok(())
+    }
+
+    // 推送时包文件的对象键，草稿放在 drafts/ 下，推送到受保护频道的版本放在 pending/ 下，
+    // 附属文件和产物的键都由它派生
+    async fn push_key(&self, metadata: &models::PackageMetadata) -> Result<String, BeepkgError> {
+        let key = format!("{}-{}.zip", metadata.name, metadata.version);
+        if self.draft {
+            return Ok(format!("{}{}", DRAFTS_PREFIX, key));
+        }
+        match &self.channel {
+            Some(channel) if self.get_registry_metadata().await?.is_protected(channel) => {
+                // 上传之前确认能签署批准请求
+                self.approval_signature(&approval_message(
+                    "request",
+                    &metadata.name,
+                    &metadata.version,
+                    channel,
+                ))?;
+                Ok(format!("{}{}", PENDING_PREFIX, key))
+            }
+            _ => Ok(key),
+        }
+    }
+
+    // 记录上传完成的草稿，同一版本的旧草稿被替换；推送时指定的频道在 release 时才移动
+    async fn record_draft(&self, metadata: &models::PackageMetadata) -> Result<(), BeepkgError> {
+        let mut registry = self.get_registry_metadata().await?;
+        self.record_access(&mut registry, metadata);
+        registry
+            .drafts
+            .retain(|d| !(d.name == metadata.name && d.version == metadata.version));
+        registry.drafts.push(models::Draft {
+            name: metadata.name.clone(),
+            version: metadata.version.clone(),
+            channel: self.channel.clone(),
+            created_by: self.actor.clone(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        });
+        registry.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&registry).await?;
+        self.emit(Event::Info(format!(
+            "Draft {}@{} uploaded; publish it with beepkg release {}@{}",
+            metadata.name, metadata.version, metadata.name, metadata.version
+        )));
+        Ok(())
+    }
+
+    // 用批准私钥签名，私钥的公钥必须受信任，否则其他用户无法验证签名者
+    fn approval_signature(&self, message: &str) -> Result<String, BeepkgError> {
+        let configured;
+        let key = match &self.approval_key {
+            Some(key) => key,
+            None => match signing::configured_backend()? {
+                Some(signing::SigningBackend::Ed25519(key)) => {
+                    configured = key;
+                    &configured
+                }
+                _ => {
+                    return Err(BeepkgError::PolicyViolation(
+                        "Protected channels need an ed25519 signing key (BEEPKG_SIGNING_KEY) \
+                         to sign approval requests and approvals"
+                            .to_string(),
+                    ));
+                }
+            },
+        };
+        let signature = signing::sign(key, message.as_bytes());
+        signing::verify(&signature, message.as_bytes(), &self.trusted_keys).map_err(|_| {
+            BeepkgError::PolicyViolation(format!(
+                "Signing key {} is not in BEEPKG_TRUSTED_KEYS and cannot request or grant approvals",
+                signing::public_key_of(key)
+            ))
+        })?;
+        Ok(signature)
+    }
+
+    // 记录等待批准的变更，同一版本和频道的旧请求被替换。请求由请求者的受信任私钥签名，
+    // 批准时据此确认批准者与请求者不是同一把密钥
+    fn request_approval(
+        &self,
+        registry: &mut models::RegistryMetadata,
+        name: &str,
+        version: &str,
+        channel: &str,
+        publish: bool,
+    ) -> Result<(), BeepkgError> {
+        let signature =
+            self.approval_signature(&approval_message("request", name, version, channel))?;
+        registry
+            .pending_approvals
+            .retain(|p| !(p.name == name && p.version == version && p.channel == channel));
+        registry.pending_approvals.push(models::PendingApproval {
+            name: name.to_string(),
+            version: version.to_string(),
+            channel: channel.to_string(),
+            requested_by: self.actor.clone(),
+            requested_at: chrono::Utc::now().to_rfc3339(),
+            publish,
+            signature: Some(signature),
+        });
+        self.emit(Event::Info(format!(
+            "Channel {} is protected: {}@{} awaits approval by another user (beepkg approve {}@{})",
+            channel, name, version, name, version
+        )));
+        Ok(())
+    }
+
+    /// 把发布频道指向包的某个已发布版本，例如 `tag demo@1.2.0 stable`。
+    /// 频道受保护时变更等待另一个用户批准，此时返回 false
+    pub async fn tag_package(&self, package: &str, channel: &str) -> Result<bool, BeepkgError> {
+        let (name, version) = models::split_package_spec(package)
+            .ok_or("Invalid package format, expected name@version")?;
+        models::validate_channel(channel)?;
+
+        let mut metadata = self.get_registry_metadata().await?;
+        self.ensure_access(&metadata, name)?;
+        if let Some(access) = metadata.access.get(name) {
+            self.check_owner(access, name)?;
+        }
+        let zip_name = format!("{}-{}.zip", name, version);
+        self.fetch_checksum(&zip_name)
+            .await
+            .map_err(|_| BeepkgError::NotFound(format!("Package {}@{}", name, version)))?;
+
+        let applied = !metadata.is_protected(channel);
+        if applied {
+            metadata
+                .channels
+                .entry(name.to_string())
+                .or_default()
+                .insert(channel.to_string(), version.to_string());
+        } else {
+            self.request_approval(&mut metadata, name, version, channel, false)?;
+        }
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&metadata).await?;
+
+        let detail = if applied {
+            format!("channel {}", channel)
+        } else {
+            format!("channel {} (pending approval)", channel)
+        };
+        self.record_audit(
+            models::AuditAction::Tag,
+            &format!("{}@{}", name, version),
+            Some(detail),
+        )
+        .await?;
+        Ok(applied)
+    }
+
+    /// 批准等待中的推送或频道变更。请求和批准都由受信任公钥对应的私钥签名，批准者的密钥和用户名
+    /// 必须不同于请求者；批准后频道指向该版本，随推送发布的版本对 pull 和 install 可见，
+    /// 批准记录和双方的签名保存在注册表元数据中
+    pub async fn approve(&self, package: &str) -> Result<Vec<models::Approval>, BeepkgError> {
+        let (name, version) = models::split_package_spec(package)
+            .ok_or("Invalid package format, expected name@version")?;
+        let mut metadata = self.get_registry_metadata().await?;
+        self.ensure_access(&metadata, name)?;
+
+        let (pending, remaining): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut metadata.pending_approvals)
+                .into_iter()
+                .partition(|p| p.name == name && p.version == version);
+        if pending.is_empty() {
+            return Err(BeepkgError::NotFound(format!(
+                "{}@{} has no pending approval",
+                name, version
+            )));
+        }
+        if let Some(own) = pending.iter().find(|p| p.requested_by == self.actor) {
+            return Err(BeepkgError::PolicyViolation(format!(
+                "{} requested {}@{} for channel {}; another user must approve it",
+                own.requested_by, name, version, own.channel
+            )));
+        }
+        // 签名验证在移动任何对象之前完成
+        let mut signatures = Vec::new();
+        for request in &pending {
+            let unsigned = || {
+                BeepkgError::PolicyViolation(format!(
+                    "The request for {}@{} on channel {} has no valid signature from a trusted key; \
+                     request it again with a trusted BEEPKG_SIGNING_KEY",
+                    name, version, request.channel
+                ))
+            };
+            let requester = signing::verify(
+                request.signature.as_deref().ok_or_else(unsigned)?,
+                approval_message("request", name, version, &request.channel).as_bytes(),
+                &self.trusted_keys,
+            )
+            .map_err(|_| unsigned())?;
+            let message = approval_message("approve", name, version, &request.channel);
+            let signature = self.approval_signature(&message)?;
+            let approver = signing::verify(&signature, message.as_bytes(), &self.trusted_keys)?;
+            if approver == requester {
+                return Err(BeepkgError::PolicyViolation(format!(
+                    "{}@{} for channel {} was requested with key {}; another key must approve it",
+                    name,
+                    version,
+                    request.channel,
+                    signing::encode_public_key(&requester)
+                )));
+            }
+            signatures.push(signature);
+        }
+
+        // 随推送发布的版本从 pending/ 移到正式位置后才修改频道
+        if pending.iter().any(|p| p.publish) {
+            let archive = format!("{}-{}.zip", name, version);
+            self.move_staged(&archive, PENDING_PREFIX, "").await?;
+        }
+        metadata.pending_approvals = remaining;
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut approved = Vec::new();
+        for (request, signature) in pending.into_iter().zip(signatures) {
+            metadata
+                .channels
+                .entry(name.to_string())
+                .or_default()
+                .insert(request.channel.clone(), version.to_string());
+            if request.publish {
+                metadata.advance_latest(name, version);
+            }
+            approved.push(models::Approval {
+                name: request.name,
+                version: request.version,
+                channel: request.channel,
+                requested_by: request.requested_by,
+                requested_at: request.requested_at,
+                approved_by: self.actor.clone(),
+                approved_at: now.clone(),
+                request_signature: request.signature,
+                signature: Some(signature),
+            });
+        }
+        metadata.approvals.extend(approved.iter().cloned());
+        metadata.last_updated = now;
+        self.save_registry_metadata(&metadata).await?;
+
+        for approval in &approved {
+            self.record_audit(
+                models::AuditAction::Approve,
+                &format!("{}@{}", name, version),
+                Some(format!(
+                    "channel {} requested by {}",
+                    approval.channel, approval.requested_by
+                )),
+            )
+            .await?;
+        }
+        Ok(approved)
+    }
+
+    /// 当前用户可见的包中等待批准的变更
+    pub async fn pending_approvals(&self) -> Result<Vec<models::PendingApproval>, BeepkgError> {
+        let metadata = self.get_registry_metadata().await?;
+        Ok(metadata
+            .pending_approvals
+            .iter()
+            .filter(|p| self.can_access(&metadata, &p.name))
+            .cloned()
+            .collect())
+    }
+
+    /// 当前用户可见的草稿
+    pub async fn drafts(&self) -> Result<Vec<models::Draft>, BeepkgError> {
+        let metadata = self.get_registry_metadata().await?;
+        Ok(metadata
+            .drafts
+            .iter()
+            .filter(|d| self.can_access(&metadata, &d.name))
+            .cloned()
+            .collect())
+    }
+
+    // 把 from 前缀下的包文件连同附属文件和产物移到 to 前缀下：先移动附属文件和产物，最后移动校验过的包文件，
+    // 所以目标位置不会出现只上传了一半的版本。之后删除原来的对象
+    async fn move_staged(&self, archive: &str, from: &str, to: &str) -> Result<(), BeepkgError> {
+        let staged_archive = format!("{}{}", from, archive);
+        let target_archive = format!("{}{}", to, archive);
+        let keys: Vec<String> = self
+            .list_objects(&staged_archive, None)
+            .await?
+            .into_iter()
+            .map(|object| object.key)
+            .collect();
+        if !keys.contains(&staged_archive) {
+            return Err(BeepkgError::NotFound(staged_archive));
+        }
+        for key in keys.iter().filter(|key| **key != staged_archive) {
+            let content = self
+                .get_raw_object_bytes(key)
+                .await?
+                .ok_or_else(|| format!("{} disappeared while moving {}", key, archive))?;
+            let target = format!("{}{}", to, &key[from.len()..]);
+            self.put_object_bytes(&target, content, content_type(key))
+                .await?;
+        }
+        let expected = self.fetch_checksum(&staged_archive).await?;
+        let file = tempfile::NamedTempFile::new()?;
+        let (actual, _) = self
+            .download_file_streaming(&staged_archive, file.path(), expected.algorithm)
+            .await?;
+        if actual != expected {
+            return Err(BeepkgError::ChecksumMismatch(format!(
+                "{}: expected {}, got {}",
+                staged_archive, expected, actual
+            )));
+        }
+        self.upload_file_streaming(&target_archive, file.path(), expected.algorithm)
+            .await?;
+        for key in &keys {
+            self.delete_object(key).await?;
+        }
+        Ok(())
+    }
+
+    /// 发布草稿：先把附属文件和产物移到正式位置，最后移动包文件。包文件出现之前该版本不在列表中，
+    /// 所以不会看到上传了一半的版本。之后删除草稿对象并移动推送时指定的频道；
+    /// 频道受保护时版本移到等待批准的位置，批准后才发布
+    pub async fn release(&self, package: &str) -> Result<(), BeepkgError> {
+        let (name, version) = models::split_package_spec(package)
+            .ok_or("Invalid package format, expected name@version")?;
+        self.check_publish(name).await?;
+        let registry = self.get_registry_metadata().await?;
+        let draft = registry
+            .drafts
+            .iter()
+            .find(|d| d.name == name && d.version == version)
+            .ok_or_else(|| BeepkgError::NotFound(format!("draft {}@{}", name, version)))?;
+        let archive = format!("{}-{}.zip", name, version);
+        if self.head_object(&archive).await?.is_some() {
+            return Err(BeepkgError::Conflict(format!(
+                "{}@{} is already published",
+                name, version
+            )));
+        }
+
+        // 频道受保护时草稿改为等待批准，移到 pending/ 下
+        let pending = draft
+            .channel
+            .as_deref()
+            .is_some_and(|channel| registry.is_protected(channel));
+        if let Some(channel) = draft.channel.as_deref().filter(|_| pending) {
+            self.approval_signature(&approval_message("request", name, version, channel))?;
+        }
+        let target = if pending { PENDING_PREFIX } else { "" };
+        self.move_staged(&archive, DRAFTS_PREFIX, target).await?;
+
+        let mut metadata = self.get_registry_metadata().await?;
+        let position = metadata
+            .drafts
+            .iter()
+            .position(|d| d.name == name && d.version == version);
+        if let Some(channel) = position
+            .map(|position| metadata.drafts.remove(position))
+            .and_then(|draft| draft.channel)
+        {
+            self.publish_channel(&mut metadata, name, version, &channel)?;
+        }
+        metadata.advance_latest(name, version);
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&metadata).await?;
+
+        self.record_audit(
+            models::AuditAction::Push,
+            &format!("{}@{}", name, version),
+            Some("released from draft".to_string()),
+        )
+        .await
+    }
+
+    /// 把频道指回上一个正常的版本：to 未指定时取低于当前版本的最高已发布版本。频道为 latest 且不存在时，
+    /// 当前版本为最新版本；回滚后不指定版本的拉取和安装使用 latest 频道，直到推送更高的版本。
+    /// restore 时如果目标版本被强制推送覆盖过，先从覆盖之前的备份恢复包文件。频道受保护时回滚等待批准
+    pub async fn rollback(
+        &self,
+        name: &str,
+        channel: &str,
+        to: Option<&str>,
+        restore: bool,
+    ) -> Result<models::Rollback, BeepkgError> {
+        models::validate_channel(channel)?;
+        let registry = self.get_registry_metadata().await?;
+        self.ensure_access(&registry, name)?;
+        let current = match registry.channels.get(name).and_then(|c| c.get(channel)) {
+            Some(version) => version.clone(),
+            None if channel == models::LATEST_CHANNEL => {
+                self.resolve_version(&registry, name, "*").await?
+            }
+            None => {
+                return Err(BeepkgError::NotFound(format!(
+                    "channel {} of {}",
+                    channel, name
+                )));
+            }
+        };
+        let current_version = semver::Version::parse(&current)
+            .map_err(|_| format!("Invalid version format: {}", current))?;
+
+        let versions = self.approved_versions(&registry, name).await?;
+        let target = match to {
+            Some(to) => semver::Version::parse(to)
+                .ok()
+                .filter(|version| versions.contains(version))
+                .ok_or_else(|| BeepkgError::NotFound(format!("{}@{}", name, to)))?,
+            // 当前为正式版本时跳过预发布版本
+            None => versions
+                .into_iter()
+                .filter(|version| *version < current_version)
+                .filter(|version| version.pre.is_empty() || !current_version.pre.is_empty())
+                .max()
+                .ok_or_else(|| {
+                    BeepkgError::NotFound(format!("a version of {} before {}", name, current))
+                })?,
+        };
+        let target = target.to_string();
+        if target == current {
+            return Err(BeepkgError::Conflict(format!(
+                "channel {} of {} already points to {}",
+                channel, name, current
+            )));
+        }
+
+        let restored_backup = if restore {
+            self.restore_overwritten(name, &target).await?
+        } else {
+            None
+        };
+
+        let mut metadata = self.get_registry_metadata().await?;
+        let pending = metadata.is_protected(channel);
+        if pending {
+            self.request_approval(&mut metadata, name, &target, channel, false)?;
+        } else {
+            metadata
+                .channels
+                .entry(name.to_string())
+                .or_default()
+                .insert(channel.to_string(), target.clone());
+        }
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&metadata).await?;
+
+        let mut details = format!("channel {} from {} to {}", channel, current, target);
+        if let Some(backup) = &restored_backup {
+            details.push_str(&format!(", restored from backup {}", backup));
+        }
+        if pending {
+            details.push_str(" (pending approval)");
+        }
+        self.record_audit(
+            models::AuditAction::Rollback,
+            &format!("{}@{}", name, target),
+            Some(details),
+        )
+        .await?;
+        Ok(models::Rollback {
+            name: name.to_string(),
+            channel: channel.to_string(),
+            from: current,
+            to: target,
+            restored_backup,
+            pending,
+        })
+    }
+
+    // 版本在最后一次强制推送时被覆盖过则从覆盖之前最新的备份恢复包文件，并按恢复的内容重写校验文件和签名。
+    // 返回所用备份的时间，没有被覆盖过时返回 None
+    async fn restore_overwritten(
+        &self,
+        name: &str,
+        version: &str,
+    ) -> Result<Option<String>, BeepkgError> {
+        let package = format!("{}@{}", name, version);
+        let parse = |timestamp: &str| chrono::DateTime::parse_from_rfc3339(timestamp).ok();
+        let Some(overwritten) = self
+            .list_audit_records(None, Some(name))
+            .await?
+            .iter()
+            .filter(|record| {
+                record.action == models::AuditAction::ForcePush && record.package == package
+            })
+            .filter_map(|record| parse(&record.timestamp))
+            .max()
+        else {
+            return Ok(None);
+        };
+
+        let registry = self.get_registry_metadata().await?;
+        let backup = registry
+            .backups
+            .iter()
+            .filter(|backup| backup_source(&backup.original_path) == Some((name, version)))
+            .filter_map(|backup| Some((parse(&backup.timestamp)?, backup)))
+            .filter(|(taken, _)| *taken < overwritten)
+            .max_by_key(|(taken, _)| *taken)
+            .map(|(_, backup)| backup.timestamp.clone())
+            .ok_or_else(|| {
+                BeepkgError::NotFound(format!(
+                    "a backup of {} taken before it was force-pushed at {}",
+                    package,
+                    overwritten.to_rfc3339()
+                ))
+            })?;
+        self.restore_package_from_backup(name, version, Some(&backup))
+            .await?;
+
+        let archive = format!("{}-{}.zip", name, version);
+        let algorithm = self.registry_checksum_algorithm().await?;
+        let file = tempfile::NamedTempFile::new()?;
+        let (checksum, _) = self
+            .download_file_streaming(&archive, file.path(), algorithm)
+            .await?;
+        self.upload_checksum(&archive, &checksum).await?;
+        self.sign_package(&archive, &checksum).await?;
+        self.upload_sums(&archive, &checksum).await?;
+        self.emit(Event::Info(format!(
+            "Restored {} from backup {}",
+            package, backup
+        )));
+        Ok(Some(backup))
+    }
+
+    /// 设置频道是否受保护，已有的等待批准的变更不受影响
+    pub async fn set_channel_protected(
+        &self,
+        channel: &str,
+        protected: bool,
+    ) -> Result<(), BeepkgError> {
+        models::validate_channel(channel)?;
+        let mut metadata = self.get_registry_metadata().await?;
+        metadata.protected_channels.retain(|c| c != channel);
+        if protected {
+            metadata.protected_channels.push(channel.to_string());
+        }
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&metadata).await
+    }
+
+    /// 包的发布频道及其指向的版本
+    pub async fn list_channels(
+        &self,
+        package: &str,
+    ) -> Result<BTreeMap<String, String>, BeepkgError> {
+        let mut metadata = self.get_registry_metadata().await?;
+        self.ensure_access(&metadata, package)?;
+        Ok(metadata.channels.remove(package).unwrap_or_default())
+    }
+
+    /// 设置或删除已发布版本的标签，例如 `annotate demo@1.2.0 qa=passed`，返回修改后的全部标签。
+    /// 标签保存在版本的元数据摘要中，包文件本身不变，注册表不可变时同样可以修改
+    pub async fn annotate(
+        &self,
+        package: &str,
+        set: &BTreeMap<String, String>,
+        remove: &[String],
+    ) -> Result<BTreeMap<String, String>, BeepkgError> {
+        let (name, version) = models::split_package_spec(package)
+            .ok_or("Invalid package format, expected name@version")?;
+        for key in set.keys().chain(remove) {
+            models::validate_label_key(key)?;
+        }
+
+        let metadata = self.get_registry_metadata().await?;
+        self.ensure_access(&metadata, name)?;
+        if let Some(access) = metadata.access.get(name) {
+            self.check_owner(access, name)?;
+        }
+        let zip_name = format!("{}-{}.zip", name, version);
+        let checksum = self
+            .fetch_checksum(&zip_name)
+            .await
+            .map_err(|_| BeepkgError::NotFound(format!("{}@{}", name, version)))?;
+
+        // 较早推送的包没有元数据摘要，此时从包文件生成
+        let mut info = self.archive_info(&zip_name).await?;
+        for key in remove {
+            info.labels.remove(key);
+        }
+        info.labels.extend(set.clone());
+        self.put_object_bytes(
+            &package_info_key(&zip_name),
+            serde_json::to_vec_pretty(&info)?,
+            "application/json",
+        )
+        .await?;
+        self.upload_sums(&zip_name, &checksum).await?;
+
+        let changes: Vec<String> = set
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .chain(remove.iter().map(|key| format!("-{}", key)))
+            .collect();
+        self.record_audit(
+            models::AuditAction::Annotate,
+            &format!("{}@{}", name, version),
+            Some(changes.join(" ")),
+        )
+        .await?;
+        Ok(info.labels)
+    }
+
+    /// 注册表中配置的作用域默认设置
+    pub async fn list_scopes(
+        &self,
+    ) -> Result<BTreeMap<String, models::ScopeSettings>, BeepkgError> {
+        Ok(self.get_registry_metadata().await?.scopes)
+    }
+
+    /// 修改作用域的默认设置，未指定的项保持不变
+    pub async fn configure_scope(
+        &self,
+        scope: &str,
+        visibility: Option<models::Visibility>,
+        require_signature: Option<bool>,
+    ) -> Result<models::ScopeSettings, BeepkgError> {
+        let scope = scope.trim_start_matches('@');
+        models::validate_package_name(&format!("@{}/x", scope))
+            .map_err(|_| format!("Invalid scope: {}", scope))?;
+
+        let mut metadata = self.get_registry_metadata().await?;
+        let settings = metadata.scopes.entry(scope.to_string()).or_default();
+        if let Some(visibility) = visibility {
+            settings.visibility = Some(visibility);
+        }
+        if let Some(required) = require_signature {
+            settings.require_signature = required;
+        }
+        let settings = settings.clone();
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
         self.save_registry_metadata(&metadata).await?;
+        Ok(settings)
+    }
+
+    fn check_owner(
+        &self,
+        access: &models::PackageAccess,
+        package: &str,
+    ) -> Result<(), BeepkgError> {
+        match &access.owner {
+            Some(owner) if *owner != self.actor => Err(BeepkgError::AccessDenied(format!(
+                "only {} can change access to {}",
+                owner, package
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    // 推送后记录 pack.toml 中的可见性；首次记录时推送者成为所有者，
+    // pack.toml 未设置可见性时保留注册表中已有的设置，新包使用作用域的默认可见性
+    fn record_access(
+        &self,
+        registry: &mut models::RegistryMetadata,
+        metadata: &models::PackageMetadata,
+    ) {
+        let scope_default = models::package_scope(&metadata.name)
+            .and_then(|scope| registry.scopes.get(scope))
+            .and_then(|settings| settings.visibility.clone());
+        let visibility = match registry.access.get(&metadata.name) {
+            Some(_) => metadata.visibility.clone(),
+            None => metadata.visibility.clone().or(scope_default),
+        };
+        if visibility.is_none() && !registry.access.contains_key(&metadata.name) {
+            return;
+        }
+        let access = registry.access.entry(metadata.name.clone()).or_default();
+        if let Some(visibility) = visibility {
+            access.visibility = visibility;
+        }
+        access.owner.get_or_insert_with(|| self.actor.clone());
+    }
+
+    // 列出注册表中配置的 webhook
+    pub async fn list_webhooks(&self) -> Result<Vec<models::WebhookConfig>, BeepkgError> {
+        Ok(self.get_registry_metadata().await?.webhooks)
+    }
+
+    // 添加 webhook，已存在相同 URL 时替换其配置
+    pub async fn add_webhook(&self, webhook: models::WebhookConfig) -> Result<(), BeepkgError> {
+        let mut metadata = self.get_registry_metadata().await?;
+        metadata.webhooks.retain(|w| w.url != webhook.url);
+        metadata.webhooks.push(webhook);
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&metadata).await
+    }
+
+    // 删除指定 URL 的 webhook
+    pub async fn remove_webhook(&self, url: &str) -> Result<(), BeepkgError> {
+        let mut metadata = self.get_registry_metadata().await?;
+        let before = metadata.webhooks.len();
+        metadata.webhooks.retain(|w| w.url != url);
+        if metadata.webhooks.len() == before {
+            return Err(format!("Webhook {} is not configured", url).into());
+        }
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&metadata).await
+    }
+
+    // 按前缀列出对象，自动处理分页
+    async fn list_objects(
+        &self,
+        prefix: &str,
+        start_after: Option<&str>,
+    ) -> Result<Vec<S3Object>, BeepkgError> {
+        let mut objects = Vec::new();
+        let mut continuation_token: Option<String> = None;
+
+        loop {
+            let page = self
+                .list_objects_page(prefix, start_after, continuation_token.as_deref())
+                .await?;
+            objects.extend(page.contents);
+
+            match page.next_continuation_token {
+                Some(token) if page.is_truncated => continuation_token = Some(token),
+                _ => break,
+            }
+        }
+
+        Ok(objects)
+    }
+
+    async fn list_objects_page(
+        &self,
+        prefix: &str,
+        start_after: Option<&str>,
+        continuation_token: Option<&str>,
+    ) -> Result<ListObjectsResponse, BeepkgError> {
+        let credentials = self.credentials().await?;
+        let mut action = self.bucket.list_objects_v2(credentials.as_ref());
+        if !prefix.is_empty() {
+            action.with_prefix(prefix);
+        }
+        if let Some(after) = start_after {
+            action.with_start_after(after);
+        }
+        if let Some(token) = continuation_token {
+            action.with_continuation_token(token);
+        }
+        let url = action.sign(Duration::from_secs(3600));
+
+        let response = self.send(self.client.get(url)).await?;
+        if !response.status().is_success() {
+            return Err(BeepkgError::from_status(
+                response.status(),
+                "Failed to list objects",
+            ));
+        }
+
+        let content = response.text().await?;
+        Ok(from_str(&content)?)
+    }
+
+    // 上传带算法标签的校验文件，并清理其他算法的旧校验文件，避免拉取时读到过期的校验和
+    async fn upload_checksum(
+        &self,
+        archive_name: &str,
+        checksum: &Checksum,
+    ) -> Result<(), BeepkgError> {
+        let checksum_name = checksum.algorithm.sidecar_name(archive_name);
+        self.put_object_bytes(&checksum_name, checksum.to_string(), "text/plain")
+            .await?;
+
+        for algorithm in ChecksumAlgorithm::ALL {
+            if algorithm != checksum.algorithm {
+                let stale = algorithm.sidecar_name(archive_name);
+                if let Err(e) = self.delete_object(&stale).await {
+                    log::warn!("Failed to remove stale checksum file {}: {}", stale, e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // 配置了签名后端时对包的校验和签名并上传签名文件
+    async fn sign_package(
+        &self,
+        archive_name: &str,
+        checksum: &Checksum,
+    ) -> Result<(), BeepkgError> {
+        let signer = self
+            .sign_object(archive_name, checksum.to_string().as_bytes())
+            .await?;
+        if let Some(signer) = signer {
+            self.emit(Event::Info(format!("Package signed with {}", signer)));
+        }
+        Ok(())
+    }
+
+    // 配置了签名后端时对 message 签名，上传为对象 name 的签名文件并返回签名方式，
+    // 同时删除其他后端可能残留的旧签名，避免与新内容不匹配
+    async fn sign_object(&self, name: &str, message: &[u8]) -> Result<Option<String>, BeepkgError> {
+        let ed25519_name = format!("{}.{}", name, signing::SIGNATURE_EXTENSION);
+        let gpg_name = format!("{}.{}", name, gpg::SIGNATURE_EXTENSION);
+        let sigstore_name = format!("{}.{}", name, sigstore::BUNDLE_EXTENSION);
+
+        let (uploaded, signer) = match signing::configured_backend()? {
+            Some(signing::SigningBackend::Ed25519(key)) => {
+                let signature = signing::sign(&key, message);
+                self.put_object_bytes(&ed25519_name, signature, "text/plain")
+                    .await?;
+                let signer = format!("key {}", signing::public_key_of(&key));
+                (Some(&ed25519_name), Some(signer))
+            }
+            Some(signing::SigningBackend::Gpg { key_id }) => {
+                let signature = gpg::sign(key_id.as_deref(), message).await?;
+                self.put_object_bytes(&gpg_name, signature, "application/pgp-signature")
+                    .await?;
+                (Some(&gpg_name), Some("gpg".to_string()))
+            }
+            Some(signing::SigningBackend::Sigstore) => {
+                let bundle = sigstore::sign(message).await?;
+                self.put_object_bytes(&sigstore_name, bundle, "application/json")
+                    .await?;
+                (Some(&sigstore_name), Some("sigstore (keyless)".to_string()))
+            }
+            None => (None, None),
+        };
+
+        for stale in [&ed25519_name, &gpg_name, &sigstore_name] {
+            if uploaded != Some(stale) {
+                self.delete_object(stale).await?;
+            }
+        }
+
+        Ok(signer)
+    }
+
+    // 汇总该版本全部对象（包文件、校验文件、签名、SBOM、证明、产物等）的 sha256 写入
+    // <包文件>.SHA256SUMS，配置了签名后端时对清单内容签名，外部只需这一个文件即可校验下载的对象。
+    // checksum 为包文件的校验和，是 sha256 时不重新下载包文件
+    async fn upload_sums(
+        &self,
+        archive_name: &str,
+        checksum: &Checksum,
+    ) -> Result<(), BeepkgError> {
+        let sums_name = checksum::sums_name(archive_name);
+        // 清单中的文件名相对于包文件所在的目录（作用域目录）
+        let dir_len = archive_name.rfind('/').map_or(0, |i| i + 1);
+        let prefix = format!("{}.", archive_name);
+        let mut keys: Vec<String> = self
+            .list_objects(&prefix, None)
+            .await?
+            .into_iter()
+            .map(|object| object.key)
+            // 清单自身及其签名不在清单中
+            .filter(|key| key.starts_with(&prefix) && !key.starts_with(&sums_name))
+            .filter(|key| !gc::TEMP_SUFFIXES.iter().any(|suffix| key.ends_with(suffix)))
+            .collect();
+        keys.sort();
+
+        let mut entries = Vec::new();
+        if checksum.algorithm == ChecksumAlgorithm::Sha256 {
+            entries.push((archive_name.to_string(), checksum.clone()));
+        } else {
+            keys.insert(0, archive_name.to_string());
+        }
+        // 有 .sha256 校验文件的对象（包文件和产物）直接使用其中的校验和，其余对象下载后计算
+        for key in &keys {
+            let sidecar = ChecksumAlgorithm::Sha256.sidecar_name(key);
+            let recorded = if keys.contains(&sidecar) {
+                self.get_object_bytes(&sidecar).await?.and_then(|content| {
+                    Checksum::parse(
+                        &String::from_utf8_lossy(&content),
+                        ChecksumAlgorithm::Sha256,
+                    )
+                    .ok()
+                    .filter(|checksum| checksum.algorithm == ChecksumAlgorithm::Sha256)
+                })
+            } else {
+                None
+            };
+            let checksum = match recorded {
+                Some(checksum) => checksum,
+                None => {
+                    let file = tempfile::NamedTempFile::new()?;
+                    self.download_file_streaming(key, file.path(), ChecksumAlgorithm::Sha256)
+                        .await?
+                        .0
+                }
+            };
+            entries.push((key.clone(), checksum));
+        }
+        for (key, _) in &mut entries {
+            key.drain(..dir_len);
+        }
+
+        let content = checksum::format_sums(&entries);
+        self.put_object_bytes(&sums_name, content.clone().into_bytes(), "text/plain")
+            .await?;
+        self.sign_object(&sums_name, content.as_bytes()).await?;
+        Ok(())
+    }
+
+    // 启用时上传构建来源证明，否则删除可能残留的旧证明，避免与新内容不匹配
+    async fn upload_attestation(
+        &self,
+        archive_name: &str,
+        metadata: &models::PackageMetadata,
+        checksum: &Checksum,
+        package_path: &Path,
+        started_on: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), BeepkgError> {
+        let attestation_name = format!("{}.{}", archive_name, provenance::ATTESTATION_EXTENSION);
+        if !self.provenance {
+            return self.delete_object(&attestation_name).await;
+        }
+
+        let statement = provenance::Statement::for_package(
+            archive_name,
+            &format!("{}@{}", metadata.name, metadata.version),
+            checksum,
+            package_path,
+            &self.actor,
+            started_on,
+        );
+        self.put_object_bytes(
+            &attestation_name,
+            serde_json::to_vec_pretty(&statement)?,
+            "application/json",
+        )
+        .await?;
+        self.emit(Event::Info(format!(
+            "Provenance attestation uploaded (builder {})",
+            statement.predicate.run_details.builder.id
+        )));
+        Ok(())
+    }
+
+    /// 获取包的构建来源证明，并检查其主体摘要与当前校验和是否一致
+    pub async fn get_attestation(
+        &self,
+        name: &str,
+        version: &str,
+    ) -> Result<(provenance::Statement, bool), BeepkgError> {
+        let zip_name = format!("{}-{}.zip", name, version);
+        let attestation_name = format!("{}.{}", zip_name, provenance::ATTESTATION_EXTENSION);
+        let content = self
+            .get_object_bytes(&attestation_name)
+            .await?
+            .ok_or_else(|| {
+                BeepkgError::NotFound(format!("provenance attestation for {}@{}", name, version))
+            })?;
+        let statement: provenance::Statement = serde_json::from_slice(&content)?;
+
+        let checksum = self.fetch_checksum(&zip_name).await?;
+        let matches = statement.matches(&checksum);
+        Ok((statement, matches))
+    }
+
+    // 上传 SBOM 并删除其他格式的旧 SBOM；document 为 None 时删除全部
+    async fn store_sbom(
+        &self,
+        archive_name: &str,
+        document: Option<(SbomFormat, serde_json::Value)>,
+    ) -> Result<(), BeepkgError> {
+        let uploaded = document.as_ref().map(|(format, _)| *format);
+        if let Some((format, document)) = document {
+            self.put_object_bytes(
+                &format.sidecar_name(archive_name),
+                serde_json::to_vec_pretty(&document)?,
+                "application/json",
+            )
+            .await?;
+            self.emit(Event::Info(format!("SBOM uploaded ({})", format.name())));
+        }
+
+        for format in SbomFormat::ALL {
+            if uploaded != Some(format) {
+                self.delete_object(&format.sidecar_name(archive_name))
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 为已发布的包上传 SBOM
+    pub async fn upload_sbom(
+        &self,
+        name: &str,
+        version: &str,
+        format: SbomFormat,
+        document: serde_json::Value,
+    ) -> Result<(), BeepkgError> {
+        let zip_name = format!("{}-{}.zip", name, version);
+        // 确认包已经发布
+        let checksum = self.fetch_checksum(&zip_name).await?;
+        self.store_sbom(&zip_name, Some((format, document))).await?;
+        self.upload_sums(&zip_name, &checksum).await
+    }
+
+    /// 获取包的 SBOM，未指定格式时返回已上传的任意一种
+    pub async fn get_sbom(
+        &self,
+        name: &str,
+        version: &str,
+        format: Option<SbomFormat>,
+    ) -> Result<(SbomFormat, bytes::Bytes), BeepkgError> {
+        let zip_name = format!("{}-{}.zip", name, version);
+        let formats = match format {
+            Some(format) => vec![format],
+            None => SbomFormat::ALL.to_vec(),
+        };
+        for format in formats {
+            if let Some(content) = self
+                .get_object_bytes(&format.sidecar_name(&zip_name))
+                .await?
+            {
+                return Ok((format, content));
+            }
+        }
+        Err(BeepkgError::NotFound(format!(
+            "SBOM for {}@{}",
+            name, version
+        )))
+    }
+
+    /// 读取注册表中的安全公告索引
+    pub async fn fetch_advisories(&self, feed: &str) -> Result<AdvisoryIndex, BeepkgError> {
+        let content = self
+            .get_object_bytes(feed)
+            .await?
+            .ok_or_else(|| BeepkgError::NotFound(format!("advisory feed {}", feed)))?;
+        let index: AdvisoryIndex = serde_json::from_slice(&content)
+            .map_err(|e| format!("Invalid advisory feed {}: {}", feed, e))?;
+        Ok(index)
+    }
+
+    /// 校验并上传安全公告索引
+    pub async fn publish_advisories(
+        &self,
+        feed: &str,
+        index: &AdvisoryIndex,
+    ) -> Result<(), BeepkgError> {
+        index.validate()?;
+        self.put_object_bytes(feed, serde_json::to_vec_pretty(index)?, "application/json")
+            .await
+    }
+
+    /// 注册表中某个包的所有已发布版本
+    async fn package_versions(&self, name: &str) -> Result<Vec<semver::Version>, BeepkgError> {
+        // 离线时只能选择缓存中已有的版本
+        if self.offline {
+            return Ok(self
+                .cache
+                .as_ref()
+                .map(|cache| cache.versions(&self.cache_registry(), name))
+                .unwrap_or_default());
+        }
+        let prefix = format!("{}-", name);
+        let objects = self.list_objects(&prefix, None).await?;
+        Ok(objects
+            .iter()
+            .filter_map(|obj| models::parse_archive_key(&obj.key))
+            .filter(|(package, _)| *package == name)
+            .filter_map(|(_, version)| semver::Version::parse(version).ok())
+            .collect())
+    }
+
+    // 解析 name[@version|channel|range|digest]：检查访问权限，频道、版本范围和内容摘要解析为具体版本，
+    // 省略时取 latest 频道指向的版本，没有该频道时取最新版本
+    async fn resolve_package_spec(
+        &self,
+        registry: &models::RegistryMetadata,
+        spec: &str,
+    ) -> Result<(String, String), BeepkgError> {
+        let (name, requested) = models::split_package_spec(spec)
+            .unwrap_or_else(|| (spec, registry.default_version(spec)));
+        self.ensure_access(registry, name)?;
+        if let Some(digest) = Checksum::parse_digest(requested) {
+            let version = self.version_with_digest(name, &digest).await?;
+            self.emit(Event::Info(format!(
+                "Digest {} of {} is version {}",
+                digest, name, version
+            )));
+            return Ok((name.to_string(), version));
+        }
+        let channel_version = registry.resolve_version(name, requested);
+        if channel_version != requested {
+            self.emit(Event::Info(format!(
+                "Channel {} of {} points to {}",
+                requested, name, channel_version
+            )));
+        }
+        let version = self
+            .resolve_version(registry, name, channel_version)
+            .await?;
+        if version != channel_version {
+            self.emit(Event::Info(format!(
+                "Resolved {}@{} to {}",
+                name, channel_version, version
+            )));
+        }
+        if registry.is_pending(name, &version) {
+            return Err(BeepkgError::NotFound(format!(
+                "{}@{} (awaiting approval)",
+                name, version
+            )));
+        }
+        Ok((name.to_string(), version))
+    }
+
+    // 查找包文件校验和等于 digest 的版本，从新到旧比较；离线时使用缓存中记录的校验和
+    async fn version_with_digest(
+        &self,
+        name: &str,
+        digest: &Checksum,
+    ) -> Result<String, BeepkgError> {
+        let mut versions = self.package_versions(name).await?;
+        versions.sort_by(|a, b| b.cmp(a));
+        for version in versions {
+            let key = format!("{}-{}.zip", name, version);
+            let checksum = if self.offline {
+                self.cache
+                    .as_ref()
+                    .and_then(|cache| cache.lookup(&self.cache_registry(), &key))
+                    .map(|(checksum, _)| checksum)
+            } else {
+                match self.fetch_checksum(&key).await {
+                    Ok(checksum) => Some(checksum),
+                    Err(BeepkgError::MissingChecksum) => None,
+                    Err(e) => return Err(e),
+                }
+            };
+            if checksum.as_ref() == Some(digest) {
+                return Ok(version.to_string());
+            }
+        }
+        Err(BeepkgError::NotFound(format!(
+            "{}@{} (no version has this digest; it may have been force-pushed)",
+            name, digest
+        )))
+    }
+
+    // 列出包文件的附加产物
+    async fn version_artifacts(
+        &self,
+        zip_name: &str,
+    ) -> Result<Vec<models::Artifact>, BeepkgError> {
+        let objects = self
+            .list_objects(&artifacts::artifact_key(zip_name, ""), None)
+            .await?;
+        Ok(artifacts::list(
+            zip_name,
+            objects
+                .iter()
+                .map(|obj| (obj.key.as_str(), obj.size.unwrap_or(0))),
+        ))
+    }
+
+    // 命令行要求签名，或包所在作用域要求签名
+    fn signature_required(&self, registry: &models::RegistryMetadata, name: &str) -> bool {
+        self.require_signature
+            || models::package_scope(name)
+                .and_then(|scope| registry.scopes.get(scope))
+                .is_some_and(|settings| settings.require_signature)
+    }
+
+    // 精确版本原样返回，版本范围解析为注册表中满足条件的最高版本
+    async fn resolve_version(
+        &self,
+        registry: &models::RegistryMetadata,
+        name: &str,
+        requested: &str,
+    ) -> Result<String, BeepkgError> {
+        if semver::Version::parse(requested).is_ok()
+            || semver::VersionReq::parse(requested).is_err()
+        {
+            return Ok(requested.to_string());
+        }
+        let available = self.approved_versions(registry, name).await?;
+        advisory::resolve(requested, &available, self.include_prerelease)
+            .map(|version| version.to_string())
+            .ok_or_else(|| {
+                let hint = if self.include_prerelease {
+                    ""
+                } else {
+                    " (use --pre to include prereleases)"
+                };
+                format!("No version of {} matches {}{}", name, requested, hint).into()
+            })
+    }
+
+    // 已发布且不在等待批准的版本
+    async fn approved_versions(
+        &self,
+        registry: &models::RegistryMetadata,
+        name: &str,
+    ) -> Result<Vec<semver::Version>, BeepkgError> {
+        let mut versions = self.package_versions(name).await?;
+        versions.retain(|version| !registry.is_pending(name, &version.to_string()));
+        Ok(versions)
+    }
+
+    /// 将依赖的版本需求解析为注册表中的具体版本
+    pub async fn resolve_dependencies(
+        &self,
+        dependencies: &HashMap<String, String>,
+    ) -> Result<Vec<ResolvedDependency>, BeepkgError> {
+        let registry = self.get_registry_metadata().await?;
+        let mut resolved = Vec::new();
+        for (name, requirement) in dependencies {
+            let available = self.approved_versions(&registry, name).await?;
+            resolved.push(ResolvedDependency {
+                name: name.clone(),
+                requirement: requirement.clone(),
+                version: advisory::resolve(requirement, &available, self.include_prerelease),
+            });
+        }
+        resolved.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(resolved)
+    }
+
+    // 下载签名文件并验证：ed25519 签名使用受信任公钥，GPG 签名使用配置的密钥环或指纹允许列表，
+    // sigstore 签名校验证书身份和 Rekor 透明日志。验证通过时返回签名者标识
+    async fn verify_signature(
+        &self,
+        archive_name: &str,
+        checksum: &Checksum,
+        require_signature: bool,
+    ) -> Result<Option<String>, BeepkgError> {
+        if checksum.algorithm == ChecksumAlgorithm::Sha1 {
+            if require_signature {
+                return Err(
+                    "Cannot verify a signature over a sha1 checksum, run rehash first".into(),
+                );
+            }
+            return Ok(None);
+        }
+
+        let message = checksum.to_string();
+
+        let ed25519_name = format!("{}.{}", archive_name, signing::SIGNATURE_EXTENSION);
+        if let Some(content) = self.get_object_bytes(&ed25519_name).await? {
+            if self.trusted_keys.is_empty() {
+                if require_signature {
+                    return Err(
+                        "No trusted public keys configured, set BEEPKG_TRUSTED_KEYS to verify signatures"
+                            .into(),
+                    );
+                }
+                self.emit(Event::Warning(
+                    "Package is signed but no trusted keys are configured, skipping verification"
+                        .to_string(),
+                ));
+                return Ok(None);
+            }
+
+            let key = signing::verify(
+                &String::from_utf8_lossy(&content),
+                message.as_bytes(),
+                &self.trusted_keys,
+            )?;
+            let signer = signing::encode_public_key(&key);
+            self.emit(Event::SignatureVerified {
+                signer: format!("key {}", signer),
+            });
+            return Ok(Some(signer));
+        }
+
+        let gpg_name = format!("{}.{}", archive_name, gpg::SIGNATURE_EXTENSION);
+        if let Some(signature) = self.get_object_bytes(&gpg_name).await? {
+            let Some(policy) = gpg::TrustPolicy::from_env() else {
+                if require_signature {
+                    return Err("Set BEEPKG_GPG_KEYRING or BEEPKG_GPG_FINGERPRINTS to verify GPG signatures".into());
+                }
+                self.emit(Event::Warning(
+                    "Package has a GPG signature but no trusted keyring or fingerprints are configured, skipping verification"
+                        .to_string(),
+                ));
+                return Ok(None);
+            };
+            let fingerprint = gpg::verify(&signature, message.as_bytes(), &policy).await?;
+            self.emit(Event::SignatureVerified {
+                signer: format!("GPG key {}", fingerprint),
+            });
+            return Ok(Some(fingerprint));
+        }
+
+        let sigstore_name = format!("{}.{}", archive_name, sigstore::BUNDLE_EXTENSION);
+        if let Some(bundle) = self.get_object_bytes(&sigstore_name).await? {
+            let (Ok(identity), Ok(issuer)) = (
+                std::env::var("BEEPKG_SIGSTORE_IDENTITY"),
+                std::env::var("BEEPKG_SIGSTORE_ISSUER"),
+            ) else {
+                if require_signature {
+                    return Err("Set BEEPKG_SIGSTORE_IDENTITY and BEEPKG_SIGSTORE_ISSUER to verify sigstore signatures".into());
+                }
+                self.emit(Event::Warning(
+                    "Package has a sigstore signature but no identity policy is configured, skipping verification"
+                        .to_string(),
+                ));
+                return Ok(None);
+            };
+
+            sigstore::verify(&bundle, message.as_bytes(), &identity, &issuer).await?;
+            self.emit(Event::SignatureVerified {
+                signer: format!("sigstore identity {}", identity),
+            });
+            return Ok(Some(identity));
+        }
+
+        if require_signature {
+            return Err(format!("Package {} is not signed", archive_name).into());
+        }
+        Ok(None)
+    }
+
+    // 当前注册表配置的校验和算法，默认 sha256
+    async fn registry_checksum_algorithm(&self) -> Result<ChecksumAlgorithm, BeepkgError> {
+        match self.get_registry_metadata().await?.checksum_algorithm {
+            Some(name) => Ok(name.parse::<ChecksumAlgorithm>()?),
+            None => Ok(ChecksumAlgorithm::Sha256),
+        }
+    }
+
+    // 设置注册表使用的校验和算法
+    pub async fn set_checksum_algorithm(
+        &self,
+        algorithm: ChecksumAlgorithm,
+    ) -> Result<(), BeepkgError> {
+        let mut metadata = self.get_registry_metadata().await?;
+        metadata.checksum_algorithm = Some(algorithm.name().to_string());
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&metadata).await
+    }
+
+    /// 开启或关闭下载统计。开启后每次拉取（需要写权限）以及代理和 gRPC 服务的每次下载都会记录到
+    /// stats/downloads/
+    pub async fn set_download_stats(&self, enabled: bool) -> Result<(), BeepkgError> {
+        let mut metadata = self.get_registry_metadata().await?;
+        metadata.download_stats = enabled;
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&metadata).await
+    }
+
+    /// 开启或关闭不可变发布。开启后已发布的版本不能被覆盖，强制推送、镜像和导入遇到已有版本都会失败
+    pub async fn set_immutable(&self, immutable: bool) -> Result<(), BeepkgError> {
+        let mut metadata = self.get_registry_metadata().await?;
+        metadata.immutable = immutable;
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&metadata).await
+    }
+
+    /// 注册表元数据的格式版本，注册表还没有元数据时返回 None
+    pub async fn registry_schema_version(&self) -> Result<Option<u32>, BeepkgError> {
+        let Some(content) = self.get_object_bytes(REGISTRY_METADATA_KEY).await? else {
+            return Ok(None);
+        };
+        let value: serde_json::Value = serde_json::from_slice(&content)?;
+        Ok(Some(schema::version_of(&value)))
+    }
+
+    /// 把注册表元数据和各包的元数据摘要改写为当前格式版本，
+    /// dry_run 时只列出需要迁移的文档，返回迁移的文档
+    pub async fn migrate_metadata(
+        &self,
+        dry_run: bool,
+    ) -> Result<Vec<schema::MigratedDocument>, BeepkgError> {
+        let mut migrated = Vec::new();
+        if let Some(content) = self.get_object_bytes(REGISTRY_METADATA_KEY).await? {
+            let mut value: serde_json::Value = serde_json::from_slice(&content)?;
+            let from = schema::upgrade::<models::RegistryMetadata>(&mut value)?;
+            if from < models::RegistryMetadata::VERSION {
+                if !dry_run {
+                    let mut metadata: models::RegistryMetadata = serde_json::from_value(value)?;
+                    metadata.last_updated = chrono::Utc::now().to_rfc3339();
+                    self.save_registry_metadata(&metadata).await?;
+                }
+                migrated.push(schema::MigratedDocument {
+                    key: REGISTRY_METADATA_KEY.to_string(),
+                    from,
+                    to: models::RegistryMetadata::VERSION,
+                });
+            }
+        }
+
+        for object in self.list_objects("", None).await? {
+            let is_info = object
+                .key
+                .strip_suffix(PACKAGE_INFO_SUFFIX)
+                .is_some_and(|archive| archive.ends_with(".zip"));
+            if !is_info || artifacts::is_artifact_key(&object.key) {
+                continue;
+            }
+            let Some(content) = self.get_object_bytes(&object.key).await? else {
+                continue;
+            };
+            let mut value: serde_json::Value = serde_json::from_slice(&content)?;
+            let from = schema::upgrade::<models::PackageInfo>(&mut value)?;
+            if from == models::PackageInfo::VERSION {
+                continue;
+            }
+            if !dry_run {
+                self.put_object_bytes(
+                    &object.key,
+                    serde_json::to_vec_pretty(&value)?,
+                    "application/json",
+                )
+                .await?;
+            }
+            migrated.push(schema::MigratedDocument {
+                key: object.key,
+                from,
+                to: models::PackageInfo::VERSION,
+            });
+        }
+        Ok(migrated)
+    }
+
+    /// 把包文件和附加产物转换为指定的存储布局。先更新注册表设置，转换期间推送的包直接使用新布局；
+    /// 改写对象前按校验文件验证内容。dry_run 时只列出需要转换的对象，返回转换的对象键
+    pub async fn migrate_layout(
+        &self,
+        layout: models::StorageLayout,
+        dry_run: bool,
+    ) -> Result<Vec<String>, BeepkgError> {
+        if !dry_run {
+            let mut metadata = self.get_registry_metadata().await?;
+            metadata.layout = layout;
+            metadata.last_updated = chrono::Utc::now().to_rfc3339();
+            self.save_registry_metadata(&metadata).await?;
+        }
+
+        let objects = self.list_objects("", None).await?;
+        let sizes: Vec<(&str, u64)> = objects
+            .iter()
+            .map(|object| (object.key.as_str(), object.size.unwrap_or(0)))
+            .collect();
+        let mut converted = Vec::new();
+        for (archive, size) in &sizes {
+            if archive_package_name(archive).is_none() {
+                continue;
+            }
+            let mut keys = vec![(archive.to_string(), *size)];
+            keys.extend(
+                artifacts::list(archive, sizes.iter().copied())
+                    .into_iter()
+                    .map(|artifact| (artifact.key, artifact.size)),
+            );
+            for (key, size) in keys {
+                let changed = self
+                    .convert_layout(&key, size, layout, dry_run)
+                    .await
+                    .map_err(|e| format!("Failed to convert {}: {}", key, e))?;
+                if changed {
+                    converted.push(key);
+                }
+            }
+        }
+        Ok(converted)
+    }
+
+    // 把单个对象转换为指定布局，已经是该布局时返回 false
+    async fn convert_layout(
+        &self,
+        key: &str,
+        size: u64,
+        layout: models::StorageLayout,
+        dry_run: bool,
+    ) -> Result<bool, BeepkgError> {
+        // 只有足够小的对象可能是指针
+        let pointer = if size <= blobs::MAX_POINTER_SIZE {
+            self.get_raw_object_bytes(key)
+                .await?
+                .and_then(|content| BlobPointer::parse(&content))
+        } else {
+            None
+        };
+        if pointer.is_some() == (layout == models::StorageLayout::Cas) {
+            return Ok(false);
+        }
+        if dry_run {
+            return Ok(true);
+        }
+
+        // 转换为 cas 时按校验文件验证内容，转换为 flat 时按指针中的校验和验证
+        let expected = match &pointer {
+            Some(pointer) => pointer.digest()?,
+            None => self.fetch_checksum(key).await?,
+        };
+        let file = tempfile::NamedTempFile::new()?;
+        let (actual, _) = self
+            .download_file_streaming(key, file.path(), expected.algorithm)
+            .await?;
+        if actual != expected {
+            return Err(BeepkgError::ChecksumMismatch(format!(
+                "expected {}, got {}",
+                expected, actual
+            )));
+        }
+        // 注册表设置已更新，按新布局重新上传
+        self.upload_file_streaming(key, file.path(), expected.algorithm)
+            .await?;
+        Ok(true)
+    }
+
+    // 获取注册表级别的设置
+    pub async fn registry_settings(&self) -> Result<models::RegistryMetadata, BeepkgError> {
+        self.get_registry_metadata().await
+    }
+
+    // 上传打包好的文件：启用加密时在内存中加密后上传，否则以流方式上传，同时计算校验和。
+    // 配置了 age 接收者时按接收者公钥加密，否则使用 BEEPKG_USER_SECRET
+    async fn upload_archive(
+        &self,
+        package: &str,
+        zip_name: &str,
+        zip_path: &Path,
+        algorithm: ChecksumAlgorithm,
+        encryption: Option<&models::EncryptionConfig>,
+    ) -> Result<Checksum, BeepkgError> {
+        self.check_quota(package, zip_name, std::fs::metadata(zip_path)?.len())
+            .await?;
+        let Some(encryption) = encryption else {
+            return self
+                .upload_file_streaming(zip_name, zip_path, algorithm)
+                .await;
+        };
+
+        let cipher = self.prepare_cipher(encryption).await?;
+        if let Cipher::Secret { secret, kdf } = &cipher {
+            // 口令加密逐块写入临时文件再流式上传，内存占用与包大小无关
+            let encrypted_path = zip_path.with_extension("zip.enc");
+            SecurityManager::encrypt_stream(
+                secret,
+                kdf,
+                std::io::BufReader::new(std::fs::File::open(zip_path)?),
+                std::io::BufWriter::new(std::fs::File::create(&encrypted_path)?),
+            )
+            .map_err(|e| format!("Encryption failed: {}", e))?;
+            let checksum = self
+                .upload_file_streaming(zip_name, &encrypted_path, algorithm)
+                .await;
+            std::fs::remove_file(&encrypted_path)?;
+            self.emit(Event::Info("Package encrypted before upload".to_string()));
+            return checksum;
+        }
+
+        let content = read_for_cipher(zip_path, SecurityError::EncryptionFailed)?;
+        let encrypted = cipher
+            .encrypt(&content)
+            .map_err(|e| format!("Encryption failed: {}", e))?;
+        let checksum = Checksum::compute(algorithm, &encrypted);
+        self.put_archive_bytes(zip_name, &checksum, encrypted)
+            .await?;
+        self.emit(Event::Info("Package encrypted before upload".to_string()));
+        Ok(checksum)
+    }
+
+    // 上传包文件或产物之前检查单个文件的大小限制和包所在命名空间的配额，
+    // 替换已有对象时不计算旧对象
+    async fn check_quota(&self, package: &str, key: &str, size: u64) -> Result<(), BeepkgError> {
+        let registry = self.get_registry_metadata().await?;
+        if let Some(max) = registry.quota.max_artifact_size.filter(|max| size > *max) {
+            return Err(BeepkgError::QuotaExceeded(format!(
+                "{} is {} bytes, the registry allows at most {} bytes per file",
+                key, size, max
+            )));
+        }
+        let namespace = quota::namespace_of(package);
+        let Some(limit) = quota::namespace_limit(&registry, &namespace) else {
+            return Ok(());
+        };
+        let used: u64 = self
+            .version_usage(Some(&namespace), Some(key))
+            .await?
+            .iter()
+            .map(|(_, size)| size)
+            .sum();
+        if used + size > limit {
+            return Err(BeepkgError::QuotaExceeded(format!(
+                "{} ({} bytes) would bring {} to {} bytes, over its quota of {} bytes",
+                key,
+                size,
+                namespace,
+                used + size,
+                limit
+            )));
+        }
+        Ok(())
+    }
+
+    // 各版本（包名，包文件和产物的字节数），只统计 namespace 中的包，不计算 skip 对象。
+    // 包文件是指向 blob 的指针时按 blob 的大小计算
+    async fn version_usage(
+        &self,
+        namespace: Option<&str>,
+        skip: Option<&str>,
+    ) -> Result<Vec<(String, u64)>, BeepkgError> {
+        let objects = self.list_objects("", None).await?;
+        let mut usage = Vec::new();
+        for package in packages_from_objects(&objects) {
+            if namespace.is_some_and(|namespace| quota::namespace_of(&package.name) != namespace) {
+                continue;
+            }
+            let stored = std::iter::once((&package.storage.path, package.storage.size))
+                .chain(package.artifacts.iter().map(|a| (&a.key, a.size)));
+            let mut size = 0;
+            for (key, stored_size) in stored {
+                if Some(key.as_str()) != skip {
+                    size += self.logical_size(key, stored_size).await?;
+                }
+            }
+            usage.push((package.name, size));
+        }
+        Ok(usage)
+    }
+
+    // 对象内容的字节数，指针按它指向的 blob 计算
+    async fn logical_size(&self, key: &str, size: u64) -> Result<u64, BeepkgError> {
+        // 只有足够小的对象可能是指针
+        if size > blobs::MAX_POINTER_SIZE {
+            return Ok(size);
+        }
+        let pointer = self
+            .get_raw_object_bytes(key)
+            .await?
+            .and_then(|content| BlobPointer::parse(&content));
+        Ok(pointer.map_or(size, |pointer| pointer.size))
+    }
+
+    /// 各命名空间的存储用量和配额，按用量从多到少排列
+    pub async fn quota_status(&self) -> Result<Vec<quota::NamespaceUsage>, BeepkgError> {
+        let registry = self.get_registry_metadata().await?;
+        let usage = self.version_usage(None, None).await?;
+        Ok(quota::summarize(
+            usage.iter().map(|(name, size)| (name.as_str(), *size)),
+            &registry,
+        ))
+    }
+
+    /// 设置注册表的单文件大小上限和命名空间的默认配额，推送时在上传之前检查
+    pub async fn set_quota(&self, quota: models::QuotaPolicy) -> Result<(), BeepkgError> {
+        let mut metadata = self.get_registry_metadata().await?;
+        metadata.quota = quota;
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&metadata).await
+    }
+
+    /// 设置作用域的配额，覆盖注册表的默认配额；None 时使用默认配额
+    pub async fn set_scope_quota(
+        &self,
+        scope: &str,
+        max_bytes: Option<u64>,
+    ) -> Result<(), BeepkgError> {
+        let scope = scope.trim_start_matches('@');
+        models::validate_package_name(&format!("@{}/x", scope))
+            .map_err(|_| format!("Invalid scope: {}", scope))?;
+
+        let mut metadata = self.get_registry_metadata().await?;
+        metadata
+            .scopes
+            .entry(scope.to_string())
+            .or_default()
+            .max_bytes = max_bytes;
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&metadata).await
+    }
+
+    // 按包的加密配置准备加密方式：接收者公钥 > KMS 信封加密 > 口令
+    async fn prepare_cipher(
+        &self,
+        encryption: &models::EncryptionConfig,
+    ) -> Result<Cipher, BeepkgError> {
+        if !encryption.recipients.is_empty() {
+            SecurityManager::validate_recipients(&encryption.recipients)?;
+            return Ok(Cipher::Recipients(encryption.recipients.clone()));
+        }
+        if let Some(kms_config) = &encryption.kms {
+            // 信封加密：本地生成数据密钥，由 KMS 封装后随密文保存
+            let data_key = SecurityManager::generate_data_key();
+            let envelope = Envelope {
+                kms: kms_config.clone(),
+                wrapped_key: kms::wrap_key(&self.client, kms_config, &data_key).await?,
+            };
+            return Ok(Cipher::Envelope { data_key, envelope });
+        }
+        let source = match encryption.secret_source {
+            Some(source) => source,
+            None => SecretSource::from_env()?,
+        };
+        let kdf = encryption.kdf.unwrap_or_default();
+        kdf.validate()?;
+        Ok(Cipher::Secret {
+            secret: source.read()?,
+            kdf,
+        })
+    }
+
+    // 配置了 files 时返回选择性加密的匹配规则和加密方式，否则返回 None（整个包加密或不加密）
+    async fn selective_encryption(
+        &self,
+        encryption: Option<&models::EncryptionConfig>,
+    ) -> Result<Option<(FileSelector, Cipher)>, BeepkgError> {
+        match encryption {
+            Some(encryption) if !encryption.files.is_empty() => Ok(Some((
+                FileSelector::new(&encryption.files)?,
+                self.prepare_cipher(encryption).await?,
+            ))),
+            _ => Ok(None),
+        }
+    }
+
+    // 按格式头识别加密格式并解密，数据未加密时返回 None
+    async fn decrypt_bytes(
+        &self,
+        content: &[u8],
+        keys: &mut DecryptionKeys,
+    ) -> Result<Option<Vec<u8>>, BeepkgError> {
+        let decrypted = if let Some(envelope) = SecurityManager::read_envelope(content)? {
+            if !keys.data_keys.contains_key(&envelope.wrapped_key) {
+                self.emit(Event::Info("Unwrapping data key with KMS".to_string()));
+                let data_key = kms::unwrap_key(&self.client, &envelope.kms, &envelope.wrapped_key)
+                    .await
+                    .map_err(|e| format!("Failed to unwrap data key: {}", e))?;
+                keys.data_keys
+                    .insert(envelope.wrapped_key.clone(), data_key);
+            }
+            SecurityManager::decrypt_envelope(&keys.data_keys[&envelope.wrapped_key], content)
+        } else if SecurityManager::is_encrypted(content) {
+            SecurityManager::decrypt_with_secret(keys.secret()?, content)
+        } else if SecurityManager::is_age_encrypted(content) {
+            if keys.identities.is_none() {
+                let identity_path = std::env::var("BEEPKG_AGE_IDENTITY").map_err(|_| {
+                    "Package is encrypted to age recipients, set BEEPKG_AGE_IDENTITY to your identity file"
+                })?;
+                keys.identities = Some(std::fs::read_to_string(&identity_path).map_err(|e| {
+                    format!("Failed to read age identity {}: {}", identity_path, e)
+                })?);
+            }
+            SecurityManager::decrypt_with_identities(
+                keys.identities.as_deref().unwrap_or_default(),
+                content,
+            )
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(
+            decrypted.map_err(|e| format!("Decryption failed: {}", e))?,
+        ))
+    }
+
+    // 按 pack.toml 中的 [targets] 打包各平台的产物，连同 [artifacts] 中的命名产物一起上传，
+    // 加密和签名方式与主包相同
+    async fn upload_artifacts(
+        &self,
+        metadata: &models::PackageMetadata,
+        package_path: &Path,
+        layout: &artifacts::ArtifactLayout,
+        algorithm: ChecksumAlgorithm,
+        encryption: Option<&models::EncryptionConfig>,
+        selective: &Option<(FileSelector, Cipher)>,
+    ) -> Result<(), BeepkgError> {
+        let zip_name = &self.push_key(metadata).await?;
+        let temp_dir = tempfile::tempdir()?;
+        for target in layout.targets() {
+            let zip_path = temp_dir.path().join(format!("{}.zip", target));
+            let mut zip = zip::ZipWriter::new(std::fs::File::create(&zip_path)?);
+            for entry in walkdir::WalkDir::new(package_path) {
+                let entry = entry?;
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+                let path = entry.path();
+                let relative_path = path.strip_prefix(package_path)?.to_string_lossy();
+                if layout.target_of(&relative_path) != Some(target) {
+                    continue;
+                }
+                zip.start_file(relative_path.clone(), Default::default())?;
+                match selective {
+                    Some((selector, cipher)) if selector.matches(&relative_path) => {
+                        encrypt_file(cipher, path, &mut zip)?;
+                    }
+                    _ => {
+                        std::io::copy(&mut std::fs::File::open(path)?, &mut zip)?;
+                    }
+                }
+            }
+            zip.finish()?;
+
+            let key = artifacts::target_key(zip_name, target);
+            let checksum = self
+                .upload_archive(&metadata.name, &key, &zip_path, algorithm, encryption)
+                .await?;
+            self.upload_checksum(&key, &checksum).await?;
+            self.sign_package(&key, &checksum).await?;
+            self.emit(Event::Info(format!("Uploaded {} artifact", target)));
+        }
+
+        for (name, path) in layout.named() {
+            let local_path = package_path.join(path);
+            if !local_path.is_file() {
+                return Err(
+                    format!("Artifact {} not found: {}", name, local_path.display()).into(),
+                );
+            }
+            let file_name = artifacts::artifact_file_name(name, path);
+            // 加密时会在文件旁边写入临时文件，先复制到临时目录，避免改动包目录
+            let upload_path = if encryption.is_some() {
+                let copy = temp_dir.path().join(&file_name);
+                std::fs::copy(&local_path, &copy)?;
+                copy
+            } else {
+                local_path
+            };
+            let key = artifacts::artifact_key(zip_name, &file_name);
+            let checksum = self
+                .upload_archive(&metadata.name, &key, &upload_path, algorithm, encryption)
+                .await?;
+            self.upload_checksum(&key, &checksum).await?;
+            self.sign_package(&key, &checksum).await?;
+            self.emit(Event::Info(format!("Uploaded {} artifact", name)));
+        }
+        Ok(())
+    }
+
+    // 下载附加产物到指定路径：按校验文件验证内容并检查签名，整包加密的产物解密后写回
+    async fn download_artifact(
+        &self,
+        key: &str,
+        path: &Path,
+        require_signature: bool,
+        keys: &mut DecryptionKeys,
+    ) -> Result<(), BeepkgError> {
+        self.fetch_verified(key, path, require_signature).await?;
+        self.decrypt_archive(path, keys).await
+    }
+
+    // 获取对象到指定路径：先下载校验文件并检查签名，再按校验和验证内容，返回校验和与签名者。
+    // 启用缓存时优先使用缓存中的同一内容，下载的内容存入缓存；离线时只使用缓存中验证过的内容
+    async fn fetch_verified(
+        &self,
+        key: &str,
+        path: &Path,
+        require_signature: bool,
+    ) -> Result<(Checksum, Option<String>), BeepkgError> {
+        let registry = self.cache_registry();
+        if self.offline {
+            let cached = self
+                .cache
+                .as_ref()
+                .and_then(|cache| cache.lookup(&registry, key));
+            let Some((checksum, signer)) = cached else {
+                return Err(BeepkgError::Offline(format!(
+                    "{} is not in the local cache",
+                    key
+                )));
+            };
+            if require_signature && signer.is_none() {
+                return Err(format!("{} was cached without a verified signature", key).into());
+            }
+            let restored = match &self.cache {
+                Some(cache) => cache.restore(&checksum, path)?,
+                None => false,
+            };
+            if !restored {
+                return Err(BeepkgError::Offline(format!(
+                    "cached content of {} is missing or corrupt",
+                    key
+                )));
+            }
+            self.emit(Event::Info(format!("Using cached {} ({})", key, checksum)));
+            return Ok((checksum, signer));
+        }
+
+        // Download checksum file first, so the package can be hashed while streaming
+        let expected = self.fetch_checksum(key).await?;
+        self.emit(Event::Info(format!("Expected checksum: {}", expected)));
+
+        // 校验和文件由签名保护，验证通过后再用它校验包内容
+        let signer = self
+            .verify_signature(key, &expected, require_signature)
+            .await?;
+
+        let cached = self
+            .cache
+            .as_ref()
+            .is_some_and(|cache| cache.restore(&expected, path).unwrap_or(false));
+        if cached {
+            self.emit(Event::Info(format!("Using cached {}", key)));
+        } else if self.fetch_delta(key, path, &expected).await {
+            self.emit(Event::Info(format!("Restored {} from delta", key)));
+        } else {
+            let (actual, size) = self
+                .download_file_streaming(key, path, expected.algorithm)
+                .await?;
+            if actual != expected {
+                let err_msg = format!(
+                    "{} checksum mismatch:\nExpected: {}\nActual: {}\nBytes length: {}",
+                    key, expected, actual, size
+                );
+                return Err(BeepkgError::ChecksumMismatch(err_msg));
+            }
+        }
+        self.emit(Event::ChecksumVerified {
+            key: key.to_string(),
+            checksum: expected.to_string(),
+        });
+
+        // 缓存失败不影响拉取
+        if let Some(cache) = &self.cache {
+            let entry = CacheRef {
+                checksum: expected.to_string(),
+                signer: signer.clone(),
+                cached_at: chrono::Utc::now().to_rfc3339(),
+            };
+            let result = cache
+                .store(&expected, path)
+                .and_then(|_| cache.record(&registry, key, &entry));
+            if let Err(e) = result {
+                self.emit(Event::Warning(format!("failed to cache {}: {}", key, e)));
+            }
+        }
+        Ok((expected, signer))
+    }
+
+    // 推送时生成从上一个版本到新包文件的差量。整包加密的包每次加密结果都不同，不生成差量；
+    // 差量只是加速升级，失败时只提示，不影响推送
+    async fn upload_delta(
+        &self,
+        zip_name: &str,
+        zip_path: &Path,
+        metadata: &models::PackageMetadata,
+        encrypted: bool,
+    ) {
+        if !self.deltas {
+            return;
+        }
+        if encrypted {
+            self.emit(Event::Info(
+                "Skipping delta: encrypted packages cannot be diffed".to_string(),
+            ));
+            return;
+        }
+        match self.try_upload_delta(zip_name, zip_path, metadata).await {
+            Ok(Some(base)) => self.emit(Event::Info(format!(
+                "Uploaded delta from {}@{}",
+                metadata.name, base
+            ))),
+            Ok(None) => {}
+            Err(e) => self.emit(Event::Warning(format!("failed to create delta: {}", e))),
+        }
+    }
+
+    // 下载上一个版本并生成差量，差量明显小于完整包时上传，返回差量的基础版本
+    async fn try_upload_delta(
+        &self,
+        zip_name: &str,
+        zip_path: &Path,
+        metadata: &models::PackageMetadata,
+    ) -> Result<Option<semver::Version>, BeepkgError> {
+        let version = semver::Version::parse(&metadata.version)?;
+        let base = self
+            .package_versions(&metadata.name)
+            .await?
+            .into_iter()
+            .filter(|existing| *existing < version)
+            .max();
+        let Some(base) = base else {
+            return Ok(None);
+        };
+
+        let temp_dir = tempfile::tempdir()?;
+        let base_path = temp_dir.path().join("base.zip");
+        let base_key = format!("{}-{}.zip", metadata.name, base);
+        self.fetch_verified(&base_key, &base_path, false).await?;
+
+        let delta_path = temp_dir.path().join("delta.zst");
+        delta::create(&base_path, zip_path, &delta_path)?;
+        let delta_size = std::fs::metadata(&delta_path)?.len();
+        let full_size = std::fs::metadata(zip_path)?.len();
+        if !delta::worthwhile(delta_size, full_size) {
+            self.emit(Event::Info(format!(
+                "Skipping delta from {}: {} bytes is not much smaller than the {} byte package",
+                base, delta_size, full_size
+            )));
+            return Ok(None);
+        }
+
+        let algorithm = self.registry_checksum_algorithm().await?;
+        self.put_file_streaming(
+            &delta::delta_key(zip_name, &base.to_string()),
+            &delta_path,
+            algorithm,
+        )
+        .await?;
+        Ok(Some(base))
+    }
+
+    // 本地缓存中有同一包的较低版本、且注册表中有从该版本生成的差量时，下载差量并在本地还原包文件。
+    // 失败时返回 false，由调用方回退到完整下载
+    async fn fetch_delta(&self, key: &str, path: &Path, expected: &Checksum) -> bool {
+        match self.try_fetch_delta(key, path, expected).await {
+            Ok(applied) => applied,
+            Err(e) => {
+                self.emit(Event::Info(format!(
+                    "Delta update failed, downloading the full package: {}",
+                    e
+                )));
+                false
+            }
+        }
+    }
+
+    async fn try_fetch_delta(
+        &self,
+        key: &str,
+        path: &Path,
+        expected: &Checksum,
+    ) -> Result<bool, BeepkgError> {
+        let Some(cache) = &self.cache else {
+            return Ok(false);
+        };
+        let Some((name, version)) = models::parse_archive_key(key) else {
+            return Ok(false);
+        };
+        let Ok(version) = semver::Version::parse(version) else {
+            return Ok(false);
+        };
+        let registry = self.cache_registry();
+        let base = cache
+            .versions(&registry, name)
+            .into_iter()
+            .filter(|cached| *cached < version)
+            .max();
+        let Some(base) = base else {
+            return Ok(false);
+        };
+        let delta_key = delta::delta_key(key, &base.to_string());
+        if !self.object_exists(&delta_key).await? {
+            return Ok(false);
+        }
+
+        let base_key = format!("{}-{}.zip", name, base);
+        let (base_checksum, _) = cache
+            .lookup(&registry, &base_key)
+            .ok_or_else(|| format!("{} is no longer cached", base_key))?;
+        let temp_dir = tempfile::tempdir()?;
+        let base_path = temp_dir.path().join("base.zip");
+        if !cache.restore(&base_checksum, &base_path)? {
+            return Ok(false);
+        }
+        let delta_path = temp_dir.path().join("delta.zst");
+        let (_, size) = self
+            .download_file_streaming(&delta_key, &delta_path, expected.algorithm)
+            .await?;
+        delta::apply(&base_path, &delta_path, path)?;
+
+        // 还原的内容必须与校验文件一致
+        let actual = Checksum::compute_reader(expected.algorithm, std::fs::File::open(path)?)?;
+        if actual != *expected {
+            return Err(BeepkgError::ChecksumMismatch(format!(
+                "{} restored from delta: expected {}, got {}",
+                key, expected, actual
+            )));
+        }
+        self.emit(Event::Info(format!(
+            "Downloaded {} byte delta from {}@{}",
+            size, name, base
+        )));
+        Ok(true)
+    }
+
+    // 缓存中区分注册表的标识：地址和 bucket
+    fn cache_registry(&self) -> String {
+        format!("{}/{}", self.bucket.base_url(), self.bucket.name())
+    }
+
+    // 加密的包以格式头开头，解密后写回原文件
+    async fn decrypt_archive(
+        &self,
+        zip_path: &Path,
+        keys: &mut DecryptionKeys,
+    ) -> Result<(), BeepkgError> {
+        let mut header = Vec::new();
+        std::fs::File::open(zip_path)?
+            .take(16)
+            .read_to_end(&mut header)?;
+        if SecurityManager::is_password_encrypted(&header) {
+            // 口令加密的包逐块解密，不把整个包读入内存
+            let decrypted_path = zip_path.with_extension("zip.dec");
+            let result = SecurityManager::decrypt_stream(
+                keys.secret()?,
+                std::io::BufReader::new(std::fs::File::open(zip_path)?),
+                std::io::BufWriter::new(std::fs::File::create(&decrypted_path)?),
+            );
+            if let Err(e) = result {
+                std::fs::remove_file(&decrypted_path)?;
+                return Err(format!("Decryption failed: {}", e).into());
+            }
+            std::fs::rename(&decrypted_path, zip_path)?;
+            self.emit(Event::Info("Package decrypted".to_string()));
+        } else {
+            let content = read_for_cipher(zip_path, SecurityError::DecryptionFailed)?;
+            let decrypted = self.decrypt_bytes(&content, keys).await?;
+            drop(content);
+            if let Some(decrypted) = decrypted {
+                std::fs::write(zip_path, &decrypted)?;
+                self.emit(Event::Info("Package decrypted".to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    // 按注册表的存储布局上传包内容：flat 布局直接上传到对象键；cas 布局把内容存为 blob
+    // （存储中已有相同内容时不再上传），对象键处写入指向 blob 的指针
+    async fn upload_file_streaming(
+        &self,
+        key: &str,
+        path: &Path,
+        algorithm: ChecksumAlgorithm,
+    ) -> Result<Checksum, BeepkgError> {
+        if self.get_registry_metadata().await?.layout.is_flat() {
+            return self.put_file_streaming(key, path, algorithm).await;
+        }
+
+        // cas 布局：文件只读取一次，以流方式上传到临时对象并同时计算校验和，
+        // 再在存储端复制到 blobs/<算法>/<摘要>；内容已存在时只删除临时对象
+        let size = std::fs::metadata(path)?.len();
+        let staging = blobs::staging_key();
+        let checksum = self.put_file_streaming(&staging, path, algorithm).await?;
+        let pointer = BlobPointer::new(&checksum, size);
+        let stored = if self.object_exists(&pointer.blob).await? {
+            self.emit(Event::Info(format!(
+                "Content already stored as {}",
+                pointer.blob
+            )));
+            Ok(())
+        } else {
+            self.copy_object(&staging, &pointer.blob).await
+        };
+        self.delete_object(&staging).await?;
+        stored?;
+        self.put_object_bytes(key, pointer.to_bytes(), "application/json")
+            .await?;
+        Ok(checksum)
+    }
+
+    // 按注册表的存储布局上传内存中的包内容（例如加密后的包）
+    async fn put_archive_bytes(
+        &self,
+        key: &str,
+        checksum: &Checksum,
+        content: Vec<u8>,
+    ) -> Result<(), BeepkgError> {
+        let size = content.len() as u64;
+        let md5 = format!("{:x}", Md5::digest(&content));
+        if self.get_registry_metadata().await?.layout.is_flat() {
+            self.put_object_bytes(key, content, "application/octet-stream")
+                .await?;
+            return self.verify_upload(key, size, &md5, checksum).await;
+        }
+
+        let pointer = BlobPointer::new(checksum, size);
+        if !self.object_exists(&pointer.blob).await? {
+            self.put_object_bytes(&pointer.blob, content, "application/octet-stream")
+                .await?;
+            self.verify_upload(&pointer.blob, size, &md5, checksum)
+                .await?;
+        }
+        self.put_object_bytes(key, pointer.to_bytes(), "application/json")
+            .await
+    }
+
+    // 以流方式上传文件，同时计算校验和，避免把整个文件读入内存
+    async fn put_file_streaming(
+        &self,
+        key: &str,
+        path: &Path,
+        algorithm: ChecksumAlgorithm,
+    ) -> Result<Checksum, BeepkgError> {
+        let file = tokio::fs::File::open(path).await?;
+        let size = file.metadata().await?.len();
+
+        // 同时计算 MD5，上传后与存储返回的 ETag 比较
+        let hasher = Arc::new(Mutex::new((ChecksumHasher::new(algorithm), Md5::new())));
+        let stream_hasher = Arc::clone(&hasher);
+        let observer = Arc::clone(&self.observer);
+        let stream_key = key.to_string();
+        let mut uploaded = 0u64;
+        self.emit(Event::UploadStarted {
+            key: key.to_string(),
+            size,
+        });
+        let stream = ReaderStream::new(file).inspect_ok(move |chunk| {
+            let mut hashers = stream_hasher.lock().unwrap();
+            hashers.0.update(chunk);
+            hashers.1.update(chunk);
+            uploaded += chunk.len() as u64;
+            observer.on_event(&Event::BytesUploaded {
+                key: stream_key.clone(),
+                uploaded,
+                total: size,
+            });
+        });
+
+        let request = self
+            .put_request(key, "application/zip")
+            .await?
+            .header("Content-Length", size)
+            .body(reqwest::Body::wrap_stream(stream));
+        let response = self.send(request).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            log::debug!("Upload failed with status: {}, body: {}", status, body);
+            return Err(BeepkgError::from_status(status, "Failed to upload object"));
+        }
+        metrics::global().record_uploaded(size);
+
+        let (checksum_hasher, md5) = hasher.lock().unwrap().clone();
+        let checksum = checksum_hasher.finalize();
+        self.verify_upload(key, size, &format!("{:x}", md5.finalize()), &checksum)
+            .await?;
+        self.emit(Event::UploadFinished {
+            key: key.to_string(),
+            checksum: checksum.to_string(),
+        });
+        Ok(checksum)
+    }
+
+    // 按设置检查刚上传的对象，不一致时删除对象，避免被截断或损坏的内容留在注册表中
+    async fn verify_upload(
+        &self,
+        key: &str,
+        size: u64,
+        md5: &str,
+        checksum: &Checksum,
+    ) -> Result<(), BeepkgError> {
+        let problem = match self.upload_verification {
+            UploadVerification::None => return Ok(()),
+            UploadVerification::Head => self.compare_stored_object(key, size, md5).await?,
+            UploadVerification::Full => {
+                let mut stream = self.get_object_response(key).await?.bytes_stream();
+                let mut hasher = ChecksumHasher::new(checksum.algorithm);
+                let mut stored = 0u64;
+                while let Some(chunk) = stream.next().await {
+                    let chunk = chunk?;
+                    hasher.update(&chunk);
+                    stored += chunk.len() as u64;
+                }
+                let actual = hasher.finalize();
+                if stored != size {
+                    Some(format!("sent {} bytes, stored {}", size, stored))
+                } else if actual != *checksum {
+                    Some(format!("sent {}, stored {}", checksum, actual))
+                } else {
+                    None
+                }
+            }
+        };
+        let Some(problem) = problem else {
+            return Ok(());
+        };
+        self.delete_object(key).await?;
+        Err(BeepkgError::ChecksumMismatch(format!(
+            "{} was not stored intact ({}); the object was removed, push again",
+            key, problem
+        )))
+    }
+
+    // HEAD 请求读取对象的大小和 ETag，与发送的内容比较，返回不一致的原因。
+    // 分段上传或使用 KMS、客户密钥加密的对象的 ETag 不是 MD5，只比较大小
+    async fn compare_stored_object(
+        &self,
+        key: &str,
+        size: u64,
+        md5: &str,
+    ) -> Result<Option<String>, BeepkgError> {
+        let Some(headers) = self.head_object(key).await? else {
+            return Ok(Some("the object is missing".to_string()));
+        };
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+        let stored = header("content-length").and_then(|value| value.parse::<u64>().ok());
+        if let Some(stored) = stored.filter(|stored| *stored != size) {
+            return Ok(Some(format!("sent {} bytes, stored {}", size, stored)));
+        }
+        let encrypted = header("x-amz-server-side-encryption")
+            .is_some_and(|sse| sse.starts_with("aws:kms"))
+            || header("x-amz-server-side-encryption-customer-algorithm").is_some();
+        let etag = header("etag")
+            .map(|etag| etag.trim_matches('"').to_ascii_lowercase())
+            .filter(|etag| etag.len() == 32 && etag.chars().all(|c| c.is_ascii_hexdigit()));
+        match etag {
+            Some(etag) if !encrypted && etag != md5 => {
+                Ok(Some(format!("sent MD5 {}, stored ETag {}", md5, etag)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    // 以流方式下载对象到文件，同时计算校验和，返回校验和与字节数。
+    // 对象是指向 blob 的指针时下载 blob，并按指针中的校验和验证内容
+    async fn download_file_streaming(
+        &self,
+        key: &str,
+        path: &Path,
+        algorithm: ChecksumAlgorithm,
+    ) -> Result<(Checksum, u64), BeepkgError> {
+        let mut response = self.get_object_response(key).await?;
+        let mut digest = None;
+        if response
+            .content_length()
+            .is_some_and(|size| size <= blobs::MAX_POINTER_SIZE)
+        {
+            let content = response.bytes().await?;
+            let Some(pointer) = BlobPointer::parse(&content) else {
+                tokio::fs::write(path, &content).await?;
+                return Ok((Checksum::compute(algorithm, &content), content.len() as u64));
+            };
+            response = self.get_object_response(&pointer.blob).await?;
+            digest = Some(pointer.digest()?);
+        }
+
+        let mut file = tokio::fs::File::create(path).await?;
+        let mut hasher = ChecksumHasher::new(algorithm);
+        let mut size = 0u64;
+
+        self.emit(Event::DownloadStarted {
+            key: key.to_string(),
+        });
+        let total = response.content_length();
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            hasher.update(&chunk);
+            file.write_all(&chunk).await?;
+            size += chunk.len() as u64;
+            self.emit(Event::BytesDownloaded {
+                key: key.to_string(),
+                downloaded: size,
+                total,
+            });
+        }
+        file.flush().await?;
+        self.emit(Event::DownloadFinished {
+            key: key.to_string(),
+            bytes: size,
+        });
+
+        let actual = hasher.finalize();
+        let mismatch = digest.filter(|digest| digest.algorithm == algorithm && *digest != actual);
+        if let Some(digest) = mismatch {
+            return Err(BeepkgError::ChecksumMismatch(format!(
+                "blob of {}: expected {}, got {}",
+                key, digest, actual
+            )));
+        }
+        Ok((actual, size))
+    }
+
+    // 发送 GET 请求下载对象，失败的状态码作为错误返回
+    async fn get_object_response(&self, key: &str) -> Result<reqwest::Response, BeepkgError> {
+        let credentials = self.credentials().await?;
+        let action = self.bucket.get_object(credentials.as_ref(), key);
+        let url = action.sign(Duration::from_secs(3600));
+
+        let response = self.send(self.client.get(url)).await?;
+        if !response.status().is_success() {
+            return Err(BeepkgError::from_status(
+                response.status(),
+                "Failed to download package",
+            ));
+        }
+        Ok(response)
+    }
+
+    // 下载包的校验文件，优先使用 .sha256，兼容旧版 .sha1
+    async fn fetch_checksum(&self, archive_name: &str) -> Result<Checksum, BeepkgError> {
+        for algorithm in ChecksumAlgorithm::ALL {
+            let sidecar = algorithm.sidecar_name(archive_name);
+            if let Some(content) = self.get_object_bytes(&sidecar).await? {
+                return Ok(Checksum::parse(
+                    &String::from_utf8_lossy(&content),
+                    algorithm,
+                )?);
+            }
+        }
+
+        Err(BeepkgError::MissingChecksum)
+    }
+
+    // 为校验文件算法与注册表配置不一致（例如旧版 .sha1）的包重新生成校验文件。
+    // 签名针对校验和，已签名的包需要用配置的签名后端重新签名，未配置签名后端时拒绝处理
+    pub async fn rehash_packages(&self, dry_run: bool) -> Result<Vec<String>, BeepkgError> {
+        let target = self.registry_checksum_algorithm().await?;
+        let signing = signing::configured_backend()?.is_some();
+        let mut migrated = Vec::new();
+
+        for package in self.list_packages().await? {
+            let archive_name = &package.storage.path;
+            let existing = self.fetch_checksum(archive_name).await;
+            if matches!(&existing, Ok(checksum) if checksum.algorithm == target) {
+                continue;
+            }
+
+            let signatures = [
+                signing::SIGNATURE_EXTENSION,
+                gpg::SIGNATURE_EXTENSION,
+                sigstore::BUNDLE_EXTENSION,
+            ]
+            .map(|extension| format!("{}.{}", archive_name, extension));
+            let signed = self
+                .list_objects(&format!("{}.", archive_name), None)
+                .await?
+                .iter()
+                .any(|object| signatures.contains(&object.key));
+            if signed && !signing {
+                return Err(format!(
+                    "{} is signed over its current checksum; configure a signing key to re-sign it \
+                     while rehashing",
+                    archive_name
+                )
+                .into());
+            }
+
+            if dry_run {
+                migrated.push(archive_name.clone());
+                continue;
+            }
+
+            // 以流方式下载到临时文件，不把整个包文件读入内存
+            let dir = tempfile::tempdir()?;
+            let path = dir.path().join("archive.zip");
+            let (checksum, _) = self
+                .download_file_streaming(archive_name, &path, target)
+                .await?;
+
+            // 先用旧校验和确认存储中的内容完好，再生成新的校验文件
+            if let Ok(legacy) = existing {
+                let actual =
+                    Checksum::compute_reader(legacy.algorithm, std::fs::File::open(&path)?)?;
+                if actual != legacy {
+                    return Err(BeepkgError::ChecksumMismatch(format!(
+                        "{} does not match its {} checksum, refusing to rehash",
+                        archive_name,
+                        legacy.algorithm.name()
+                    )));
+                }
+            }
+
+            self.upload_checksum(archive_name, &checksum).await?;
+            if signed {
+                self.sign_package(archive_name, &checksum).await?;
+            }
+            self.upload_sums(archive_name, &checksum).await?;
+            migrated.push(archive_name.clone());
+        }
+
+        Ok(migrated)
+    }
+
+    /// 找出失败的推送等留下的垃圾对象：包文件已不存在的附属文件、没有校验文件的包文件、
+    /// 注册表元数据中没有记录的备份、未被引用的 blob、临时对象和未完成的分段上传。
+    /// 只处理早于 min_age 的对象，避免误删正在进行的推送；delete 为 false 时只报告
+    pub async fn collect_garbage(
+        &self,
+        min_age: Duration,
+        delete: bool,
+    ) -> Result<GcReport, BeepkgError> {
+        let cutoff = chrono::Utc::now() - chrono::Duration::from_std(min_age)?;
+        let registry = self.get_registry_metadata().await?;
+        let listed = self.list_objects("", None).await?;
+        let objects: Vec<gc::StoredObject> = listed
+            .iter()
+            .filter(|object| {
+                !object.key.starts_with(AUDIT_PREFIX) && !object.key.starts_with(STATS_PREFIX)
+            })
+            .map(|object| gc::StoredObject {
+                key: object.key.clone(),
+                size: object.size.unwrap_or(0),
+                expired: modified_before(object.last_modified.as_deref(), cutoff),
+            })
+            .collect();
+
+        // 包文件、产物和备份处的指针引用的 blob；flat 布局的存储桶中没有 blob，不需要读取
+        let mut referenced_blobs = HashSet::new();
+        if listed.iter().any(|object| blobs::is_blob_key(&object.key)) {
+            for object in &listed {
+                let may_point = archive_package_name(&object.key).is_some()
+                    || models::parse_backup_key(&object.key).is_some()
+                    || artifacts::is_artifact_key(&object.key);
+                if !may_point || object.size.unwrap_or(0) > blobs::MAX_POINTER_SIZE {
+                    continue;
+                }
+                let pointer = self
+                    .get_raw_object_bytes(&object.key)
+                    .await?
+                    .and_then(|content| BlobPointer::parse(&content));
+                if let Some(pointer) = pointer {
+                    referenced_blobs.insert(pointer.blob);
+                }
+            }
+        }
+
+        let backups: HashSet<&str> = registry
+            .backups
+            .iter()
+            .map(|backup| backup.backup_path.as_str())
+            .collect();
+        let mut garbage = gc::find(&objects, &backups, &referenced_blobs);
+        for upload in self.list_multipart_uploads().await? {
+            if modified_before(upload.initiated.as_deref(), cutoff) {
+                garbage.push(Garbage {
+                    key: upload.key,
+                    kind: GarbageKind::StaleUpload,
+                    size: 0,
+                    upload_id: Some(upload.upload_id),
+                });
+            }
+        }
+
+        let mut report = GcReport {
+            garbage,
+            ..Default::default()
+        };
+        if !delete {
+            return Ok(report);
+        }
+        for item in &report.garbage {
+            match &item.upload_id {
+                Some(upload_id) => self.abort_multipart_upload(&item.key, upload_id).await?,
+                None => self.delete_object(&item.key).await?,
+            }
+            report.deleted += 1;
+            report.freed += item.size;
+        }
+        Ok(report)
+    }
+
+    /// 交叉检查注册表元数据（锁、发布频道、备份）、校验文件、blob 指针与存储桶中的实际对象。
+    /// repair 时从注册表元数据中移除指向不存在对象的记录，其余问题只报告
+    pub async fn fsck(&self, repair: bool) -> Result<FsckReport, BeepkgError> {
+        let mut registry = self.get_registry_metadata().await?;
+        let listed = self.list_objects("", None).await?;
+        let keys: HashSet<&str> = listed.iter().map(|object| object.key.as_str()).collect();
+        let mut issues = fsck::check(&registry, &keys, chrono::Utc::now());
+
+        // 锁定时记录了校验和的版本，其校验文件不应再变化
+        for lock in &registry.locked_packages {
+            let archive = format!("{}-{}.zip", lock.name, lock.version);
+            if lock.checksum.is_empty() || !keys.contains(archive.as_str()) {
+                continue;
+            }
+            let locked = Checksum::parse(&lock.checksum, ChecksumAlgorithm::Sha256)?;
+            let Ok(current) = self.fetch_checksum(&archive).await else {
+                continue;
+            };
+            if current.algorithm == locked.algorithm && current != locked {
+                issues.push(fsck::Issue::LockDrift {
+                    name: lock.name.clone(),
+                    version: lock.version.clone(),
+                    locked: locked.to_string(),
+                    current: current.to_string(),
+                });
+            }
+        }
+
+        // 包文件、产物和备份处的指针必须指向存在的 blob；flat 布局的注册表中没有指针
+        if !registry.layout.is_flat() || keys.iter().any(|key| blobs::is_blob_key(key)) {
+            for object in &listed {
+                let may_point = archive_package_name(&object.key).is_some()
+                    || models::parse_backup_key(&object.key).is_some()
+                    || artifacts::is_artifact_key(&object.key);
+                if !may_point || object.size.unwrap_or(0) > blobs::MAX_POINTER_SIZE {
+                    continue;
+                }
+                let pointer = self
+                    .get_raw_object_bytes(&object.key)
+                    .await?
+                    .and_then(|content| BlobPointer::parse(&content));
+                let missing = pointer.filter(|pointer| !keys.contains(pointer.blob.as_str()));
+                if let Some(pointer) = missing {
+                    issues.push(fsck::Issue::MissingBlob {
+                        key: object.key.clone(),
+                        blob: pointer.blob,
+                    });
+                }
+            }
+        }
+
+        let mut report = FsckReport {
+            issues,
+            repaired: 0,
+        };
+        if repair {
+            report.repaired = fsck::repair(&mut registry, &report.issues);
+            if report.repaired > 0 {
+                registry.last_updated = chrono::Utc::now().to_rfc3339();
+                self.save_registry_metadata(&registry).await?;
+            }
+        }
+        Ok(report)
+    }
+
+    /// 按注册表中的保留规则删除旧版本及其附属文件。delete 为 false 时只返回每个版本的决定
+    pub async fn apply_retention(&self, delete: bool) -> Result<Vec<RetentionReport>, BeepkgError> {
+        let registry = self.get_registry_metadata().await?;
+        if registry.retention.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut versions: BTreeMap<String, Vec<semver::Version>> = BTreeMap::new();
+        for object in self.list_objects("", None).await? {
+            let Some(name) = archive_package_name(&object.key) else {
+                continue;
+            };
+            let version = &object.key[name.len() + 1..object.key.len() - ".zip".len()];
+            if let Ok(version) = semver::Version::parse(version) {
+                versions.entry(name.to_string()).or_default().push(version);
+            }
+        }
+
+        let now = chrono::Utc::now();
+        let mut reports = Vec::new();
+        for (name, versions) in versions {
+            let Some((pattern, rule)) = retention::rule_for(&registry.retention, &name)? else {
+                continue;
+            };
+            let decisions = retention::plan(
+                &name,
+                &versions,
+                rule,
+                registry.channels.get(&name),
+                &registry.locked_packages,
+                now,
+            );
+            if delete {
+                for decision in decisions.iter().filter(|decision| decision.is_deleted()) {
+                    self.remove_archive(&format!("{}-{}.zip", name, decision.version))
+                        .await?;
+                    self.record_audit(
+                        models::AuditAction::Delete,
+                        &format!("{}@{}", name, decision.version),
+                        Some(format!("retention rule {}", pattern)),
+                    )
+                    .await?;
+                }
+            }
+            reports.push(RetentionReport {
+                package: name,
+                pattern: pattern.to_string(),
+                versions: decisions,
+            });
+        }
+        Ok(reports)
+    }
+
+    /// 注册表中的保留规则
+    pub async fn retention_rules(
+        &self,
+    ) -> Result<BTreeMap<String, models::RetentionRule>, BeepkgError> {
+        Ok(self.get_registry_metadata().await?.retention)
+    }
+
+    /// 为包名或 glob 设置保留规则，已有的规则被替换
+    pub async fn set_retention_rule(
+        &self,
+        pattern: &str,
+        rule: models::RetentionRule,
+    ) -> Result<(), BeepkgError> {
+        retention::validate_pattern(pattern)?;
+        let mut metadata = self.get_registry_metadata().await?;
+        metadata.retention.insert(pattern.to_string(), rule);
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&metadata).await
+    }
+
+    pub async fn remove_retention_rule(&self, pattern: &str) -> Result<(), BeepkgError> {
+        let mut metadata = self.get_registry_metadata().await?;
+        if metadata.retention.remove(pattern).is_none() {
+            return Err(format!("No retention rule for {}", pattern).into());
+        }
+        metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        self.save_registry_metadata(&metadata).await
+    }
+
+    // 列出未完成的分段上传。只读取第一页（至多 1000 个），其余的在下次 gc 时处理。
+    // rusty-s3 没有 ListMultipartUploads，直接对 bucket 地址上的 GET ?uploads 签名
+    async fn list_multipart_uploads(&self) -> Result<Vec<MultipartUpload>, BeepkgError> {
+        let credentials = self.credentials().await?;
+        let mut url = self.bucket.base_url().clone();
+        let url = match &credentials {
+            Some(credentials) => rusty_s3::signing::sign(
+                &time::OffsetDateTime::now_utc(),
+                rusty_s3::Method::Get,
+                url,
+                credentials.key(),
+                credentials.secret(),
+                credentials.token(),
+                self.bucket.region(),
+                3600,
+                std::iter::once(("uploads", "")),
+                std::iter::empty(),
+            ),
+            None => {
+                url.set_query(Some("uploads"));
+                url
+            }
+        };
+
+        let response = self.send(self.client.get(url)).await?;
+        if !response.status().is_success() {
+            return Err(BeepkgError::from_status(
+                response.status(),
+                "Failed to list multipart uploads",
+            ));
+        }
+        let content = response.text().await?;
+        let result: ListMultipartUploadsResponse = from_str(&content)?;
+        Ok(result.uploads)
+    }
+
+    // 请求从归档存储类型中临时取回对象，返回 false 表示已有取回在进行中。
+    // rusty-s3 没有 RestoreObject，直接对对象地址上的 POST ?restore 签名
+    async fn request_restore(&self, key: &str) -> Result<bool, BeepkgError> {
+        let credentials = self.credentials().await?;
+        let mut url = self
+            .bucket
+            .object_url(key)
+            .map_err(|e| format!("Invalid object key {}: {}", key, e))?;
+        let url = match &credentials {
+            Some(credentials) => rusty_s3::signing::sign(
+                &time::OffsetDateTime::now_utc(),
+                rusty_s3::Method::Post,
+                url,
+                credentials.key(),
+                credentials.secret(),
+                credentials.token(),
+                self.bucket.region(),
+                3600,
+                std::iter::once(("restore", "")),
+                std::iter::empty(),
+            ),
+            None => {
+                url.set_query(Some("restore"));
+                url
+            }
+        };
+        let body = format!(
+            "<RestoreRequest><Days>{}</Days><GlacierJobParameters><Tier>Standard</Tier>\
+             </GlacierJobParameters></RestoreRequest>",
+            BACKUP_RESTORE_DAYS
+        );
+
+        let response = self
+            .send(
+                self.client
+                    .post(url)
+                    .header(reqwest::header::CONTENT_TYPE, "application/xml")
+                    .body(body),
+            )
+            .await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(true);
+        }
+        if status == reqwest::StatusCode::CONFLICT
+            && response.text().await?.contains("RestoreAlreadyInProgress")
+        {
+            return Ok(false);
+        }
+        Err(BeepkgError::from_status(
+            status,
+            format!("Failed to request a restore of {}", key),
+        ))
+    }
+
+    // 放弃未完成的分段上传，释放已上传的分段
+    async fn abort_multipart_upload(&self, key: &str, upload_id: &str) -> Result<(), BeepkgError> {
+        let credentials = self.credentials().await?;
+        let action = self
+            .bucket
+            .abort_multipart_upload(credentials.as_ref(), key, upload_id);
+        let url = action.sign(Duration::from_secs(3600));
+
+        let response = self.send(self.client.delete(url)).await?;
+        if !response.status().is_success() && response.status() != reqwest::StatusCode::NOT_FOUND {
+            return Err(BeepkgError::from_status(
+                response.status(),
+                format!("Failed to abort upload of {}", key),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 把目标注册表中缺失或已更新的包复制过去，包括校验、签名、证明等附属文件，并同步这些包的
+    /// 访问控制、发布频道和锁。filter 为包名 glob，jobs 为并行复制的包数量；dry_run 时只列出需要复制的包。
+    /// 两个注册表各列出一次，按列表中的大小和 ETag 判断对象是否变化；只有包文件的 ETag 和校验文件的
+    /// ETag 都不一致时（例如分段上传或 KMS 加密的对象）才读取两边的校验文件确认
+    pub async fn mirror_to(
+        &self,
+        target: &PackageManager,
+        filter: Option<&str>,
+        jobs: usize,
+        dry_run: bool,
+    ) -> Result<MirrorReport, BeepkgError> {
+        let filter = filter
+            .map(|pattern| globset::Glob::new(pattern).map(|glob| glob.compile_matcher()))
+            .transpose()
+            .map_err(|e| format!("Invalid filter: {}", e))?;
+
+        let source_objects = self.list_objects("", None).await?;
+        let target_objects: HashMap<String, S3Object> = target
+            .list_objects("", None)
+            .await?
+            .into_iter()
+            .map(|object| (object.key.clone(), object))
+            .collect();
+        let target_metadata = target.get_registry_metadata().await?;
+
+        let mut report = MirrorReport::default();
+        let mut pending = Vec::new();
+        let mut mirrored = BTreeSet::new();
+        for object in &source_objects {
+            let Some(name) = archive_package_name(&object.key) else {
+                continue;
+            };
+            if filter.as_ref().is_some_and(|filter| !filter.is_match(name)) {
+                continue;
+            }
+            mirrored.insert(name);
+
+            // 附属文件的对象键都以包文件名加 . 开头，例如 demo-1.0.0.zip.sha256
+            let prefix = format!("{}.", object.key);
+            let sidecars: Vec<&S3Object> = source_objects
+                .iter()
+                .filter(|other| other.key.starts_with(&prefix))
+                .collect();
+
+            // 包文件或其校验文件的 ETag 一致时内容相同；内容寻址布局中包文件只是指针，需比较校验文件
+            let same_etag = |source: &S3Object| {
+                source.etag.is_some()
+                    && target_objects
+                        .get(&source.key)
+                        .is_some_and(|existing| existing.etag == source.etag)
+            };
+            let outdated = if !target_objects.contains_key(&object.key) {
+                true
+            } else if same_etag(object)
+                || sidecars.iter().any(|sidecar| {
+                    ChecksumAlgorithm::ALL
+                        .iter()
+                        .any(|algorithm| algorithm.sidecar_name(&object.key) == sidecar.key)
+                        && same_etag(sidecar)
+                })
+            {
+                false
+            } else {
+                match self.fetch_checksum(&object.key).await {
+                    Ok(expected) => target.fetch_checksum(&object.key).await.ok() != Some(expected),
+                    Err(e) => {
+                        report.failed.push((object.key.clone(), e.to_string()));
+                        continue;
+                    }
+                }
+            };
+            // 不可变的目标注册表中已有的版本内容不同时不覆盖
+            if outdated && target_metadata.immutable && target_objects.contains_key(&object.key) {
+                report.failed.push((
+                    object.key.clone(),
+                    "differs from the target, which is immutable".to_string(),
+                ));
+                continue;
+            }
+            let mut keys = Vec::new();
+            if outdated {
+                keys.push(object.key.clone());
+            }
+            for sidecar in sidecars {
+                let changed = match target_objects.get(&sidecar.key) {
+                    None => true,
+                    Some(existing) => {
+                        existing.size != sidecar.size
+                            || (existing.etag.is_some()
+                                && sidecar.etag.is_some()
+                                && existing.etag != sidecar.etag)
+                    }
+                };
+                if outdated || changed {
+                    keys.push(sidecar.key.clone());
+                }
+            }
+
+            if keys.is_empty() {
+                report.up_to_date += 1;
+            } else {
+                pending.push((object.key.clone(), keys));
+            }
+        }
+
+        if dry_run {
+            report.copied = pending.into_iter().map(|(archive, _)| archive).collect();
+            return Ok(report);
+        }
+
+        let results: Vec<_> = futures_util::stream::iter(pending)
+            .map(|(archive, keys)| async move {
+                let result = self.copy_objects(target, &archive, &keys).await;
+                (archive, result)
+            })
+            .buffer_unordered(jobs.max(1))
+            .collect()
+            .await;
+        for (archive, result) in results {
+            match result {
+                Ok(()) => report.copied.push(archive),
+                Err(e) => report.failed.push((archive, e.to_string())),
+            }
+        }
+        report.copied.sort();
+
+        let source_metadata = self.get_registry_metadata().await?;
+        let mut target_metadata = target_metadata;
+        if sync_package_metadata(&source_metadata, &mut target_metadata, &mirrored) {
+            target_metadata.last_updated = chrono::Utc::now().to_rfc3339();
+            target.save_registry_metadata(&target_metadata).await?;
+        }
+
+        Ok(report)
+    }
+
+    // 把包文件及附属文件复制到目标注册表；包文件以流方式传输并按校验和验证
+    async fn copy_objects(
+        &self,
+        target: &PackageManager,
+        archive: &str,
+        keys: &[String],
+    ) -> Result<(), BeepkgError> {
+        for key in keys {
+            if key == archive {
+                let expected = self.fetch_checksum(archive).await?;
+                let file = tempfile::NamedTempFile::new()?;
+                let (actual, _) = self
+                    .download_file_streaming(archive, file.path(), expected.algorithm)
+                    .await?;
+                if actual != expected {
+                    return Err(BeepkgError::ChecksumMismatch(format!(
+                        "{}: expected {}, got {}",
+                        archive, expected, actual
+                    )));
+                }
+                target
+                    .upload_file_streaming(archive, file.path(), expected.algorithm)
+                    .await?;
+            } else {
+                let content = self
+                    .get_object_bytes(key)
+                    .await?
+                    .ok_or_else(|| format!("{} disappeared during mirroring", key))?;
+                target
+                    .put_object_bytes(key, content, content_type(key))
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 把本存储桶中的包按当前布局重写到目标存储桶：包文件按 pack.toml 中的名称和版本命名
+    /// （例如旧版扁平命名的作用域包改为 @scope/name-version.zip），按目标注册表的算法重新生成校验文件，
+    /// 复制前用原有校验和验证包内容。目标存储桶中没有注册表元数据时一并复制
+    pub async fn migrate_to(
+        &self,
+        target: &PackageManager,
+        dry_run: bool,
+    ) -> Result<Vec<MigrationEntry>, BeepkgError> {
+        let target_has_metadata = target
+            .get_object_bytes(REGISTRY_METADATA_KEY)
+            .await?
+            .is_some();
+        if !dry_run && !target_has_metadata {
+            let content = self.get_object_bytes(REGISTRY_METADATA_KEY).await?;
+            if let Some(content) = content {
+                target
+                    .put_object_bytes(REGISTRY_METADATA_KEY, content, "application/json")
+                    .await?;
+            }
+        }
+        let algorithm = target.registry_checksum_algorithm().await?;
+
+        let objects = self.list_objects("", None).await?;
+        let mut entries = Vec::new();
+        for object in &objects {
+            if archive_package_name(&object.key).is_none() {
+                continue;
+            }
+            let sidecars: Vec<&str> = objects
+                .iter()
+                .map(|other| other.key.as_str())
+                .filter(|key| key.starts_with(&format!("{}.", object.key)))
+                .collect();
+
+            let entry = match self
+                .migrate_package(target, &object.key, &sidecars, algorithm, dry_run)
+                .await
+            {
+                Ok(entry) => entry,
+                Err(e) => MigrationEntry {
+                    source: object.key.clone(),
+                    target: None,
+                    status: MigrationStatus::Failed,
+                    notes: vec![e.to_string()],
+                },
+            };
+            entries.push(entry);
+        }
+
+        Ok(entries)
+    }
+
+    // 迁移单个包及其附属文件
+    async fn migrate_package(
+        &self,
+        target: &PackageManager,
+        source_key: &str,
+        sidecars: &[&str],
+        algorithm: ChecksumAlgorithm,
+        dry_run: bool,
+    ) -> Result<MigrationEntry, BeepkgError> {
+        let mut notes = Vec::new();
+        let legacy = self.fetch_checksum(source_key).await.ok();
+        if legacy.is_none() {
+            notes.push("no checksum in the source bucket, content not verified".to_string());
+        }
+
+        let file = tempfile::NamedTempFile::new()?;
+        let (actual, _) = self
+            .download_file_streaming(
+                source_key,
+                file.path(),
+                legacy.as_ref().map_or(algorithm, |c| c.algorithm),
+            )
+            .await?;
+        if let Some(legacy) = legacy.as_ref().filter(|legacy| **legacy != actual) {
+            return Err(BeepkgError::ChecksumMismatch(format!(
+                "expected {}, got {}",
+                legacy, actual
+            )));
+        }
+
+        // 整包加密的包无法读取 pack.toml，保留原有对象键
+        let target_key = match self.get_package_metadata(file.path()) {
+            Ok(metadata) => format!("{}-{}.zip", metadata.name, metadata.version),
+            Err(_) => {
+                notes.push("pack.toml not readable, keeping the object key".to_string());
+                source_key.to_string()
+            }
+        };
+        if target_key != source_key {
+            notes.push(format!("renamed from {}", source_key));
+        }
+
+        if dry_run {
+            return Ok(MigrationEntry {
+                source: source_key.to_string(),
+                target: Some(target_key),
+                status: MigrationStatus::Planned,
+                notes,
+            });
+        }
+
+        let checksum = target
+            .upload_file_streaming(&target_key, file.path(), algorithm)
+            .await?;
+        target.upload_checksum(&target_key, &checksum).await?;
+
+        // 签名针对校验和字符串，只有校验和不变时才能沿用
+        let signature_extensions = [
+            signing::SIGNATURE_EXTENSION,
+            gpg::SIGNATURE_EXTENSION,
+            sigstore::BUNDLE_EXTENSION,
+        ];
+        let signatures_valid = legacy.as_ref() == Some(&checksum);
+        for key in sidecars {
+            let suffix = &key[source_key.len() + 1..];
+            if ChecksumAlgorithm::ALL.iter().any(|a| a.name() == suffix) {
+                continue;
+            }
+            // 差量以源包内容为基础，内容变化后不再适用
+            if delta::is_delta_key(key) && !signatures_valid {
+                notes.push(format!("dropped {}, the base content changed", suffix));
+                continue;
+            }
+            if signature_extensions.contains(&suffix) && !signatures_valid {
+                notes.push(format!(
+                    "dropped .{} signature, the package must be re-signed",
+                    suffix
+                ));
+                continue;
+            }
+            let content = self
+                .get_object_bytes(key)
+                .await?
+                .ok_or_else(|| format!("{} disappeared during migration", key))?;
+            target
+                .put_object_bytes(
+                    &format!("{}.{}", target_key, suffix),
+                    content,
+                    content_type(key),
+                )
+                .await?;
+        }
+
+        Ok(MigrationEntry {
+            source: source_key.to_string(),
+            target: Some(target_key),
+            status: MigrationStatus::Migrated,
+            notes,
+        })
+    }
+
+    // 备份所在的存储：记录了独立备份 bucket 的备份必须通过配置的同名备份存储访问
+    fn backup_store_for(
+        &self,
+        backup: &models::PackageBackup,
+    ) -> Result<&PackageManager, BeepkgError> {
+        let Some(bucket) = &backup.bucket else {
+            return Ok(self);
+        };
+        match &self.backup_store {
+            Some(store) if store.bucket.name() == bucket.as_str() => Ok(store.as_ref()),
+            _ => Err(format!(
+                "Backup {} is stored in bucket {}, set BEEPKG_BACKUP_BUCKET to access it",
+                backup.backup_path, bucket
+            )
+            .into()),
+        }
+    }
+
+    // 当前用于签名的 S3 凭证，临时凭证即将过期时先向凭证来源重新获取
+    async fn credentials(&self) -> Result<Option<Credentials>, BeepkgError> {
+        let Some(provider) = &self.credential_provider else {
+            return Ok(self.credentials.as_ref().map(aws::AwsCredentials::to_s3));
+        };
+        // 离线时不向凭证来源（STS、实例元数据等）发送请求
+        if self.offline {
+            return Ok(self.credentials.as_ref().map(aws::AwsCredentials::to_s3));
+        }
+
+        let cached = self.temporary_credentials.lock().unwrap().clone();
+        let credentials = match cached {
+            Some(Some(credentials)) if !credentials.expires_soon() => Some(credentials),
+            // 凭证来源在当前环境不可用，按匿名访问
+            Some(None) => None,
+            _ => {
+                let fresh = provider
+                    .fetch(&self.client, self.credentials.as_ref())
+                    .await?;
+                *self.temporary_credentials.lock().unwrap() = Some(fresh.clone());
+                fresh
+            }
+        };
+        Ok(credentials.as_ref().map(aws::AwsCredentials::to_s3))
+    }
 
+    // 把事件交给观察者
+    pub(crate) fn emit(&self, event: Event) {
+        self.observer.on_event(&event);
+    }
+
+    // 发送请求，并记录请求延迟、成功/失败次数和传输字节数。离线模式下拒绝发送
+    async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, BeepkgError> {
+        let mut request = request.build()?;
+        if self.offline {
+            return Err(BeepkgError::Offline(format!(
+                "{} {}",
+                request.method(),
+                request.url().path()
+            )));
+        }
+        let authorization = self.bearer_token.as_ref().and_then(|token| {
+            reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token)).ok()
+        });
+        if let Some(value) = authorization {
+            request
+                .headers_mut()
+                .insert(reqwest::header::AUTHORIZATION, value);
+        }
+        let method = request.method().to_string();
+        let uploaded = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map_or(0, |bytes| bytes.len() as u64);
+
+        let start = Instant::now();
+        let result = self.client.execute(request).await;
+
+        let metrics = metrics::global();
+        metrics.record_request(
+            &method,
+            result.as_ref().is_ok_and(|r| r.status().is_success()),
+            start.elapsed(),
+        );
+        if let Ok(response) = &result {
+            metrics.record_uploaded(uploaded);
+            metrics.record_downloaded(response.content_length().unwrap_or(0));
+        }
+
+        // 连接失败、超时等传输错误归为网络错误
+        result.map_err(|e| BeepkgError::Network(format!("{} request failed: {}", method, e)))
+    }
+}
+
+// 缓存代理（crate::proxy）使用的上游访问
+#[cfg(feature = "proxy")]
+impl PackageManager {
+    /// 获取包文件或附加产物到 path：按校验文件验证内容并存入缓存，缓存中已有相同内容时不再下载。
+    /// client 为客户端地址，开启下载统计时记录包文件的下载
+    pub(crate) async fn proxy_fetch(
+        &self,
+        key: &str,
+        path: &Path,
+        client: Option<std::net::IpAddr>,
+    ) -> Result<Checksum, BeepkgError> {
+        let (checksum, _) = self.fetch_verified(key, path, false).await?;
+        self.record_served_download(key, "proxy", client.map(|ip| ip.to_string()))
+            .await;
+        Ok(checksum)
+    }
+
+    /// 代理的客户端是否可以读取对象。客户端没有注册表中的身份，只能读取所有人可见的包的
+    /// 包文件及其附属文件（校验、签名、产物、差量等）；注册表元数据、审计日志、备份、blob、
+    /// 草稿等对象不经代理提供
+    pub(crate) async fn proxy_readable(&self, key: &str) -> Result<bool, BeepkgError> {
+        let Some(package) = proxy_package(key) else {
+            return Ok(false);
+        };
+        let registry = self.get_registry_metadata().await?;
+        Ok(visible_to(&registry, package, "", &[]))
+    }
+
+    /// 列出代理的客户端可以读取的对象，返回 ListObjectsV2 格式的 XML。query 中的 prefix 和
+    /// start-after 生效，结果一次返回，不分页
+    pub(crate) async fn proxy_list(
+        &self,
+        query: &[(String, String)],
+    ) -> Result<String, BeepkgError> {
+        let param = |name: &str| {
+            query
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let objects = self
+            .list_objects(param("prefix").unwrap_or_default(), param("start-after"))
+            .await?;
+        let registry = self.get_registry_metadata().await?;
+        let contents: String = objects
+            .iter()
+            .filter(|object| {
+                proxy_package(&object.key)
+                    .is_some_and(|package| visible_to(&registry, package, "", &[]))
+            })
+            .map(|object| {
+                let mut entry = format!(
+                    "<Contents><Key>{}</Key><Size>{}</Size>",
+                    quick_xml::escape::escape(object.key.as_str()),
+                    object.size.unwrap_or(0)
+                );
+                if let Some(modified) = &object.last_modified {
+                    entry.push_str(&format!(
+                        "<LastModified>{}</LastModified>",
+                        quick_xml::escape::escape(modified.as_str())
+                    ));
+                }
+                entry.push_str("</Contents>");
+                entry
+            })
+            .collect();
+        Ok(format!(
+            "<ListBucketResult>{}<IsTruncated>false</IsTruncated></ListBucketResult>",
+            contents
+        ))
+    }
+
+    /// 用本实例的凭证把对象的读请求转发到上游，query 为客户端的查询参数（不含签名）。
+    /// 调用前须用 proxy_readable 检查
+    pub(crate) async fn proxy_forward(
+        &self,
+        head: bool,
+        key: &str,
+        query: &[(String, String)],
+    ) -> Result<reqwest::Response, BeepkgError> {
+        fn sign<'a>(mut action: impl S3Action<'a>, query: &'a [(String, String)]) -> url::Url {
+            for (name, value) in query {
+                action.query_mut().insert(name.as_str(), value.as_str());
+            }
+            action.sign(Duration::from_secs(3600))
+        }
+
+        let credentials = self.credentials().await?;
+        let url = if head {
+            sign(self.bucket.head_object(credentials.as_ref(), key), query)
+        } else {
+            sign(self.bucket.get_object(credentials.as_ref(), key), query)
+        };
+        let request = if head {
+            self.client.head(url)
+        } else {
+            self.client.get(url)
+        };
+        self.send(request).await
+    }
+}
+
+#[cfg(feature = "grpc")]
+impl PackageManager {
+    /// 解析 name、name@version 或 name@channel，获取包文件到 path，按校验文件和签名要求验证。
+    /// client 为客户端地址，开启下载统计时记录下载。返回包名、版本和校验和
+    pub(crate) async fn grpc_fetch(
+        &self,
+        spec: &str,
+        path: &Path,
+        client: Option<std::net::IpAddr>,
+    ) -> Result<(String, String, Checksum), BeepkgError> {
+        let registry = self.get_registry_metadata().await?;
+        let (name, version) = self.resolve_package_spec(&registry, spec).await?;
+        let (checksum, _) = self
+            .fetch_verified(
+                &format!("{}-{}.zip", name, version),
+                path,
+                self.signature_required(&registry, &name),
+            )
+            .await?;
+        if registry.download_stats {
+            self.record_download(&name, &version, "grpc", client.map(|ip| ip.to_string()))
+                .await;
+        }
+        Ok((name, version, checksum))
+    }
+
+    /// 包文件的校验和，没有校验文件时为 None
+    pub(crate) async fn grpc_checksum(&self, key: &str) -> Result<Option<Checksum>, BeepkgError> {
+        match self.fetch_checksum(key).await {
+            Ok(checksum) => Ok(Some(checksum)),
+            Err(BeepkgError::MissingChecksum) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+// 离线包集和快照需要 tar 与 zstd
+#[cfg(feature = "archives")]
+impl PackageManager {
+    /// 把所选包及其附属文件、访问控制导出为离线包集（tar + zstd），用于搬运到隔离网络。
+    /// packages 为 name@version 列表，filter 为包名 glob；两者都未指定时导出全部可见的包
+    pub async fn export_bundle(
+        &self,
+        output: &Path,
+        packages: &[String],
+        filter: Option<&str>,
+    ) -> Result<BundleManifest, BeepkgError> {
+        let manifest = self.bundle_manifest(packages, filter).await?;
+        self.write_bundle(output, &manifest).await?;
+        Ok(manifest)
+    }
+
+    // 选择要导出的包文件及其附属文件
+    async fn bundle_manifest(
+        &self,
+        packages: &[String],
+        filter: Option<&str>,
+    ) -> Result<BundleManifest, BeepkgError> {
+        let filter = filter
+            .map(|pattern| globset::Glob::new(pattern).map(|glob| glob.compile_matcher()))
+            .transpose()
+            .map_err(|e| format!("Invalid filter: {}", e))?;
+        let mut requested = packages
+            .iter()
+            .map(|package| {
+                package
+                    .rsplit_once('@')
+                    .filter(|(name, version)| !name.is_empty() && !version.is_empty())
+                    .map(|(name, version)| format!("{}-{}.zip", name, version))
+                    .ok_or_else(|| {
+                        format!("Invalid package format {}, expected name@version", package)
+                    })
+            })
+            .collect::<Result<HashSet<String>, String>>()?;
+        let select_all = requested.is_empty() && filter.is_none();
+
+        let registry = self.get_registry_metadata().await?;
+        let objects = self.list_objects("", None).await?;
+        let mut manifest = BundleManifest {
+            format_version: bundle::FORMAT_VERSION,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            source: self.bucket.base_url().to_string(),
+            packages: Vec::new(),
+            access: BTreeMap::new(),
+            project: None,
+        };
+
+        for object in &objects {
+            let Some(name) = archive_package_name(&object.key) else {
+                continue;
+            };
+            let explicit = requested.remove(&object.key);
+            let matched = filter.as_ref().is_some_and(|filter| filter.is_match(name));
+            if !(select_all || explicit || matched) {
+                continue;
+            }
+            // 明确指定的包没有权限时报错，按 glob 选择的包直接跳过
+            if explicit {
+                self.ensure_access(&registry, name)?;
+            } else if !self.can_access(&registry, name) {
+                continue;
+            }
+
+            let checksum = self
+                .fetch_checksum(&object.key)
+                .await
+                .map_err(|e| format!("{}: {}", object.key, e))?;
+            let prefix = format!("{}.", object.key);
+            let sidecars = objects
+                .iter()
+                .filter(|other| other.key.starts_with(&prefix))
+                .map(|other| other.key.clone())
+                .collect();
+            if let Some(access) = registry.access.get(name) {
+                manifest.access.insert(name.to_string(), access.clone());
+            }
+            manifest.packages.push(BundlePackage {
+                archive: object.key.clone(),
+                checksum: checksum.to_string(),
+                sidecars,
+            });
+        }
+
+        if let Some(missing) = requested.into_iter().next() {
+            return Err(BeepkgError::NotFound(format!(
+                "package archive {}",
+                missing
+            )));
+        }
+        if manifest.packages.is_empty() {
+            return Err("No packages matched the selection".into());
+        }
+        Ok(manifest)
+    }
+
+    // 写入包集：按清单中的校验和验证下载的每个包文件
+    async fn write_bundle(
+        &self,
+        output: &Path,
+        manifest: &BundleManifest,
+    ) -> Result<(), BeepkgError> {
+        let mut writer = BundleWriter::create(output, manifest)?;
+        for package in &manifest.packages {
+            let expected = Checksum::parse(&package.checksum, ChecksumAlgorithm::Sha256)?;
+            let file = tempfile::NamedTempFile::new()?;
+            let (actual, _) = self
+                .download_file_streaming(&package.archive, file.path(), expected.algorithm)
+                .await?;
+            if actual != expected {
+                return Err(BeepkgError::ChecksumMismatch(format!(
+                    "{}: expected {}, got {}",
+                    package.archive, expected, actual
+                )));
+            }
+            writer.append_file(&package.archive, file.path())?;
+
+            for key in &package.sidecars {
+                let content = self
+                    .get_object_bytes(key)
+                    .await?
+                    .ok_or_else(|| format!("{} disappeared during export", key))?;
+                writer.append_bytes(key, &content)?;
+            }
+        }
+        writer.finish()?;
         Ok(())
     }
 
-    // 解锁特定版本的包
-    pub async fn unlock_package(
+    /// 把离线包集导入本注册表：按清单中的校验和验证每个包后再上传，并合并访问控制。
+    /// 包集先解压到临时目录，需要与包集大小相当的磁盘空间
+    pub async fn import_bundle(&self, path: &Path) -> Result<MirrorReport, BeepkgError> {
+        let dir = tempfile::tempdir()?;
+        let manifest = bundle::unpack(path, dir.path())?;
+        let objects = dir.path().join(bundle::OBJECTS_DIR);
+
+        let mut registry = self.get_registry_metadata().await?;
+        let mut report = MirrorReport::default();
+        for package in &manifest.packages {
+            match self
+                .import_bundle_package(&registry, package, &objects)
+                .await
+            {
+                Ok(true) => report.copied.push(package.archive.clone()),
+                Ok(false) => report.up_to_date += 1,
+                Err(e) => report.failed.push((package.archive.clone(), e.to_string())),
+            }
+        }
+
+        // 合并已导入包的可见性和授权
+        let mut changed = false;
+        for archive in &report.copied {
+            let access =
+                archive_package_name(archive).and_then(|name| manifest.access.get_key_value(name));
+            if let Some((name, access)) = access {
+                registry.access.insert(name.clone(), access.clone());
+                changed = true;
+            }
+        }
+        if changed {
+            registry.last_updated = chrono::Utc::now().to_rfc3339();
+            self.save_registry_metadata(&registry).await?;
+        }
+
+        Ok(report)
+    }
+
+    // 导入包集中的一个包，注册表中已有相同校验和的包时返回 false
+    async fn import_bundle_package(
+        &self,
+        registry: &models::RegistryMetadata,
+        package: &BundlePackage,
+        objects: &Path,
+    ) -> Result<bool, BeepkgError> {
+        let name = archive_package_name(&package.archive)
+            .ok_or_else(|| format!("{} is not a package archive", package.archive))?;
+        models::validate_package_name(name)?;
+        self.ensure_access(registry, name)?;
+
+        let expected = Checksum::parse(&package.checksum, ChecksumAlgorithm::Sha256)?;
+        let archive_path = objects.join(&package.archive);
+        let file = std::fs::File::open(&archive_path)
+            .map_err(|_| format!("{} is missing from the bundle", package.archive))?;
+        let actual = Checksum::compute_reader(expected.algorithm, file)?;
+        if actual != expected {
+            return Err(BeepkgError::ChecksumMismatch(format!(
+                "{}: expected {}, got {}",
+                package.archive, expected, actual
+            )));
+        }
+        match self.fetch_checksum(&package.archive).await {
+            Ok(existing) if existing == expected => return Ok(false),
+            Ok(_) if registry.immutable => {
+                return Err(BeepkgError::Conflict(format!(
+                    "{} already exists with different content and the registry is immutable",
+                    package.archive
+                )));
+            }
+            _ => {}
+        }
+
+        self.upload_file_streaming(&package.archive, &archive_path, expected.algorithm)
+            .await?;
+        for key in &package.sidecars {
+            let content = std::fs::read(objects.join(key))
+                .map_err(|_| format!("{} is missing from the bundle", key))?;
+            self.put_object_bytes(key, content, content_type(key))
+                .await?;
+        }
+        Ok(true)
+    }
+
+    /// 为隔离网络创建项目包集：解析根目录中 project.toml（profile 为环境配置）的全部依赖，
+    /// 把包文件和解析结果写入包集，由 bundle install 在不能访问注册表的环境中安装。
+    /// 锁文件与 project.toml 一致时打包其中的版本，否则像 sync 一样重新解析（不写锁文件）
+    pub async fn create_project_bundle(
+        &self,
+        root: &Path,
+        profile: Option<&str>,
+        output: &Path,
+    ) -> Result<BundleManifest, BeepkgError> {
+        let project = Project::load(root)?;
+        let requirements = project.requirements(profile)?;
+        let lock = match ProjectLock::load(root, profile)? {
+            Some(lock) if lock.requirements == requirements => lock,
+            _ => {
+                // 解析需要读取依赖的元数据，包拉取到临时目录后丢弃
+                let dir = tempfile::tempdir()?;
+                let packages = self
+                    .resolve_project(&requirements, dir.path(), &HashMap::new())
+                    .await?;
+                ProjectLock {
+                    generated_at: chrono::Utc::now().to_rfc3339(),
+                    requirements,
+                    packages,
+                }
+            }
+        };
+
+        let specs: Vec<String> = lock
+            .packages
+            .iter()
+            .map(|p| format!("{}@{}", p.name, p.version))
+            .collect();
+        let mut manifest = self.bundle_manifest(&specs, None).await?;
+        for package in &lock.packages {
+            let archive = format!("{}-{}.zip", package.name, package.version);
+            let bundled = manifest.packages.iter().find(|p| p.archive == archive);
+            if bundled.map(|p| &p.checksum) != Some(&package.checksum) {
+                return Err(BeepkgError::ChecksumMismatch(format!(
+                    "{}@{} in the registry no longer matches {}; run beepkg sync",
+                    package.name,
+                    package.version,
+                    project::lock_file(profile)
+                )));
+            }
+        }
+        manifest.project = Some(BundleProject {
+            profile: profile.map(str::to_string),
+            lock,
+        });
+        self.write_bundle(output, &manifest).await?;
+        Ok(manifest)
+    }
+
+    /// 把 bundle create 生成的项目包集安装到根目录中的项目，不访问注册表：包集中的包文件
+    /// 按清单校验后存入临时缓存，再离线按包集中的解析结果同步安装目录
+    pub async fn install_bundle(self, path: &Path, root: &Path) -> Result<SyncReport, BeepkgError> {
+        let project = Project::load(root)?;
+        let dir = tempfile::tempdir()?;
+        let manifest = bundle::unpack(path, dir.path())?;
+        let Some(bundled) = &manifest.project else {
+            return Err(format!(
+                "{} is not a project bundle; load it into a registry with beepkg import",
+                path.display()
+            )
+            .into());
+        };
+        if bundled.lock.requirements != project.requirements(bundled.profile.as_deref())? {
+            return Err(format!(
+                "{} was created for different requirements than {}; recreate it with beepkg bundle create",
+                path.display(),
+                project::PROJECT_FILE
+            )
+            .into());
+        }
+
+        let objects = dir.path().join(bundle::OBJECTS_DIR);
+        let cache_root = dir.path().join("cache");
+        let manager = self
+            .offline(true)
+            .cache(Some(Cache::new(cache_root.clone())));
+        let registry = manager.cache_registry();
+        let cache = Cache::new(cache_root);
+        cache.store_object(
+            &registry,
+            REGISTRY_METADATA_KEY,
+            &serde_json::to_vec(&models::RegistryMetadata::default())?,
+        )?;
+        for package in &manifest.packages {
+            let mut verified = vec![(
+                package.archive.clone(),
+                Checksum::parse(&package.checksum, ChecksumAlgorithm::Sha256)?,
+            )];
+            // 平台产物按包集中各自的校验文件验证
+            for key in package
+                .sidecars
+                .iter()
+                .filter(|key| artifacts::is_artifact_key(key))
+            {
+                let checksum = ChecksumAlgorithm::ALL.into_iter().find_map(|algorithm| {
+                    let content =
+                        std::fs::read_to_string(objects.join(algorithm.sidecar_name(key))).ok()?;
+                    Some(Checksum::parse(&content, algorithm))
+                });
+                if let Some(checksum) = checksum.transpose()? {
+                    verified.push((key.clone(), checksum));
+                }
+            }
+            for (key, expected) in verified {
+                let local = objects.join(&key);
+                let file = std::fs::File::open(&local)
+                    .map_err(|_| format!("{} is missing from the bundle", key))?;
+                let actual = Checksum::compute_reader(expected.algorithm, file)?;
+                if actual != expected {
+                    return Err(BeepkgError::ChecksumMismatch(format!(
+                        "{}: expected {}, got {}",
+                        key, expected, actual
+                    )));
+                }
+                cache.store(&expected, &local)?;
+                cache.record(
+                    &registry,
+                    &key,
+                    &CacheRef {
+                        checksum: expected.to_string(),
+                        signer: None,
+                        cached_at: chrono::Utc::now().to_rfc3339(),
+                    },
+                )?;
+            }
+        }
+
+        manager
+            .install_locked(
+                &root.join(&project.dir),
+                &bundled.lock.packages,
+                &path.display().to_string(),
+            )
+            .await
+    }
+
+    /// 把整个注册表（注册表元数据、审计日志、附属文件等全部对象）保存为快照（tar + zstd），
+    /// include_blobs 时同时保存包文件、备份、产物、差量和 blob 的内容，用于灾难恢复演练。
+    /// 对象按原样保存，内容寻址布局中的指针不会被解析。快照逐个读取对象，建议在没有推送时创建
+    pub async fn create_snapshot(
         &self,
-        package_name: &str,
-        version: &str,
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
-        // 获取注册表元数据
-        let mut metadata = self.get_registry_metadata().await?;
-
-        // 查找锁定的包索引
-        let index = metadata
-            .locked_packages
-            .iter()
-            .position(|lp| lp.name == package_name && lp.version == version);
-
-        if let Some(idx) = index {
-            // 移除锁定信息
-            metadata.locked_packages.remove(idx);
-            metadata.last_updated = chrono::Utc::now().to_rfc3339();
+        output: &Path,
+        include_blobs: bool,
+    ) -> Result<SnapshotManifest, BeepkgError> {
+        let objects = self.list_objects("", None).await?;
+        let manifest = SnapshotManifest {
+            format_version: snapshot::FORMAT_VERSION,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            source: self.bucket.base_url().to_string(),
+            include_blobs,
+            objects: objects
+                .iter()
+                // 未完成的上传留下的临时对象不属于注册表状态
+                .filter(|object| {
+                    !gc::TEMP_SUFFIXES
+                        .iter()
+                        .any(|suffix| object.key.ends_with(suffix))
+                })
+                .map(|object| SnapshotObject {
+                    key: object.key.clone(),
+                    size: object.size.unwrap_or(0),
+                    included: include_blobs || !snapshot::is_content_key(&object.key),
+                })
+                .collect(),
+        };
 
-            // 保存更新后的元数据
-            self.save_registry_metadata(&metadata).await?;
-            Ok(())
-        } else {
-            Err(format!("Package {}@{} is not locked", package_name, version).into())
+        let mut writer =
+            BundleWriter::create_with_manifest(output, snapshot::MANIFEST_PATH, &manifest)?;
+        for object in manifest.objects.iter().filter(|object| object.included) {
+            let file = tempfile::NamedTempFile::new()?;
+            self.download_raw_streaming(&object.key, file.path())
+                .await
+                .map_err(|e| format!("{}: {}", object.key, e))?;
+            writer.append_file(&object.key, file.path())?;
         }
+        writer.finish()?;
+
+        Ok(manifest)
     }
 
-    // 备份特定版本的包
-    pub async fn backup_package(
+    /// 从快照重建注册表：依次上传内容对象、其他对象，最后上传注册表元数据，
+    /// 中途失败时注册表不会引用尚未恢复的包。目标 bucket 不为空时需要 force。
+    /// 快照中不包含内容的对象必须已存在于目标 bucket 中，否则在结果中列为缺失
+    pub async fn restore_snapshot(
         &self,
-        package_name: &str,
-        version: &str,
-        reason: &str,
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
-        // 检查包是否存在
-        let packages = self.list_packages().await?;
-        let package = packages
+        path: &Path,
+        force: bool,
+    ) -> Result<SnapshotRestoreReport, BeepkgError> {
+        if !force && !self.list_objects("", None).await?.is_empty() {
+            return Err("The bucket is not empty, pass --force to restore into it".into());
+        }
+
+        let dir = tempfile::tempdir()?;
+        let manifest = snapshot::unpack(path, dir.path())?;
+        let objects = dir.path().join(bundle::OBJECTS_DIR);
+
+        // 不可变注册表中已发布的版本不能被快照覆盖，在写入任何对象之前检查
+        let archives = manifest
+            .objects
             .iter()
-            .find(|p| p.name == package_name && p.version == version);
+            .filter(|object| object.included && !is_staged_key(&object.key))
+            .filter_map(|object| models::parse_archive_key(&object.key));
+        for (name, version) in archives {
+            self.ensure_not_overwriting(name, version).await?;
+        }
 
-        let package = match package {
-            Some(pkg) => pkg,
-            None => {
-                return Err(format!("Package {}@{} does not exist", package_name, version).into());
+        let mut report = SnapshotRestoreReport::default();
+        let mut ordered: Vec<&SnapshotObject> = manifest.objects.iter().collect();
+        ordered.sort_by_key(|object| {
+            (
+                object.key == REGISTRY_METADATA_KEY,
+                !snapshot::is_content_key(&object.key),
+            )
+        });
+        for object in ordered {
+            if !object.included {
+                if !self.object_exists(&object.key).await? {
+                    report.missing.push(object.key.clone());
+                }
+                continue;
             }
-        };
+            let local = objects.join(&object.key);
+            if !local.is_file() {
+                return Err(format!("{} is missing from the snapshot", object.key).into());
+            }
+            if snapshot::is_content_key(&object.key) {
+                self.put_file_streaming(&object.key, &local, ChecksumAlgorithm::Sha256)
+                    .await?;
+            } else {
+                let content = std::fs::read(&local)?;
+                self.put_object_bytes(&object.key, content, content_type(&object.key))
+                    .await?;
+            }
+            report.restored += 1;
+        }
 
-        // 获取注册表元数据
-        let mut metadata = self.get_registry_metadata().await?;
+        Ok(report)
+    }
 
-        // 如果备份未启用，则启用它
-        if !metadata.backup_enabled {
-            metadata.backup_enabled = true;
+    // 以流方式把对象按原样下载到文件，不解析指针，返回字节数
+    async fn download_raw_streaming(&self, key: &str, path: &Path) -> Result<u64, BeepkgError> {
+        let response = self.get_object_response(key).await?;
+        let mut file = tokio::fs::File::create(path).await?;
+        let mut size = 0u64;
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            file.write_all(&chunk).await?;
+            size += chunk.len() as u64;
         }
+        file.flush().await?;
+        Ok(size)
+    }
+}
 
-        // 创建备份名称
-        let now = chrono::Utc::now();
-        let timestamp = now.to_rfc3339();
-        let backup_name = format!(
-            "{}-{}-backup-{}.zip",
-            package_name,
-            version,
-            now.timestamp()
-        );
+impl PackageManager {
+    // 下载对象内容，对象不存在时返回 None；对象是指向 blob 的指针时返回验证过的 blob 内容
+    async fn get_object_bytes(&self, key: &str) -> Result<Option<bytes::Bytes>, BeepkgError> {
+        let Some(content) = self.get_raw_object_bytes(key).await? else {
+            return Ok(None);
+        };
+        let Some(pointer) = BlobPointer::parse(&content) else {
+            return Ok(Some(content));
+        };
+        let blob = self
+            .get_raw_object_bytes(&pointer.blob)
+            .await?
+            .ok_or_else(|| format!("{} points to missing {}", key, pointer.blob))?;
+        let digest = pointer.digest()?;
+        if !digest.verify(&blob) {
+            return Err(BeepkgError::ChecksumMismatch(format!(
+                "{} does not match {}",
+                pointer.blob, digest
+            )));
+        }
+        Ok(Some(blob))
+    }
 
-        // 复制包到备份位置
-        let source_key = &package.storage.path;
-        let action = self
-            .bucket
-            .get_object(self.credentials.as_ref(), source_key);
+    // 按范围下载对象的一部分，返回数据的起始偏移、数据和对象的总长度；
+    // 服务器忽略 Range 时返回整个对象
+    async fn get_object_range(
+        &self,
+        key: &str,
+        range: &str,
+    ) -> Result<(u64, bytes::Bytes, u64), BeepkgError> {
+        let credentials = self.credentials().await?;
+        let action = self.bucket.get_object(credentials.as_ref(), key);
         let url = action.sign(Duration::from_secs(3600));
 
-        // 下载原始对象
-        let response = self.client.get(url).send().await?;
+        let request = self.client.get(url).header(reqwest::header::RANGE, range);
+        let response = self.send(request).await?;
         if !response.status().is_success() {
-            return Err(format!(
-                "Failed to download object for backup: {}",
-                response.status()
-            )
-            .into());
+            return Err(BeepkgError::from_status(
+                response.status(),
+                format!("Failed to download {}", key),
+            ));
         }
+        // Content-Range: bytes <start>-<end>/<total>
+        let content_range = response
+            .headers()
+            .get(reqwest::header::CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("bytes "))
+            .and_then(|value| {
+                let (range, total) = value.split_once('/')?;
+                let (start, _) = range.split_once('-')?;
+                Some((start.parse().ok()?, total.parse().ok()?))
+            })
+            .filter(|_| response.status() == reqwest::StatusCode::PARTIAL_CONTENT);
 
-        let bytes = response.bytes().await?;
+        let data = response.bytes().await?;
+        Ok(match content_range {
+            Some((start, total)) => (start, data, total),
+            None => {
+                let len = data.len() as u64;
+                (0, data, len)
+            }
+        })
+    }
 
-        // 上传到备份位置
-        let action = self
-            .bucket
-            .put_object(self.credentials.as_ref(), &backup_name);
+    // 下载对象的原始内容，不解析指针
+    async fn get_raw_object_bytes(&self, key: &str) -> Result<Option<bytes::Bytes>, BeepkgError> {
+        let credentials = self.credentials().await?;
+        let action = self.bucket.get_object(credentials.as_ref(), key);
         let url = action.sign(Duration::from_secs(3600));
 
-        // 上传备份对象
-        let response = self
-            .client
-            .put(url)
-            .header("Content-Type", "application/zip")
-            .body(bytes)
-            .send()
-            .await?;
-
+        let response = self.send(self.client.get(url)).await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
         if !response.status().is_success() {
-            return Err(format!("Failed to upload backup: {}", response.status()).into());
+            return Err(BeepkgError::from_status(
+                response.status(),
+                format!("Failed to download {}", key),
+            ));
         }
 
-        // 更新元数据
-        metadata.backups.push(models::PackageBackup {
-            original_path: source_key.to_string(),
-            backup_path: backup_name,
-            timestamp,
-            reason: reason.to_string(),
-        });
-
-        metadata.last_updated = chrono::Utc::now().to_rfc3339();
-
-        // 保存更新后的元数据
-        self.save_registry_metadata(&metadata).await?;
+        Ok(Some(response.bytes().await?))
+    }
 
+    // 删除包文件及其所有附属文件（校验、签名、产物等以 <archive>. 开头的对象）
+    async fn remove_archive(&self, zip_name: &str) -> Result<(), BeepkgError> {
+        let sidecar_prefix = format!("{}.", zip_name);
+        for object in self.list_objects(zip_name, None).await? {
+            if object.key == zip_name || object.key.starts_with(&sidecar_prefix) {
+                self.delete_object(&object.key).await?;
+            }
+        }
         Ok(())
     }
 
-    // 从备份恢复特定版本的包
-    pub async fn restore_package_from_backup(
-        &self,
-        package_name: &str,
-        version: &str,
-        timestamp: Option<&str>,
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
-        // 获取注册表元数据
-        let metadata = self.get_registry_metadata().await?;
-
-        // 查找备份
-        let mut filtered_backups: Vec<&models::PackageBackup> = metadata
-            .backups
-            .iter()
-            .filter(|b| {
-                let parts: Vec<&str> = b
-                    .original_path
-                    .split('.')
-                    .next()
-                    .unwrap_or("")
-                    .split('-')
-                    .collect();
+    // 对象是否存在（HEAD 请求）
+    async fn object_exists(&self, key: &str) -> Result<bool, BeepkgError> {
+        Ok(self.head_object(key).await?.is_some())
+    }
 
-                if parts.len() >= 2 {
-                    let name = parts[0..parts.len() - 1].join("-");
-                    let ver = parts.last().unwrap_or(&"");
-                    name == package_name && *ver == version
-                } else {
-                    false
-                }
-            })
-            .collect();
+    // 发送 HEAD 请求，返回对象的响应头，对象不存在时返回 None
+    async fn head_object(
+        &self,
+        key: &str,
+    ) -> Result<Option<reqwest::header::HeaderMap>, BeepkgError> {
+        let credentials = self.credentials().await?;
+        let action = self.bucket.head_object(credentials.as_ref(), key);
+        let url = action.sign(Duration::from_secs(3600));
 
-        if filtered_backups.is_empty() {
-            return Err(
-                format!("No backups found for package {}@{}", package_name, version).into(),
-            );
+        let response = self.send(self.client.head(url)).await?;
+        match response.status() {
+            reqwest::StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => Ok(Some(response.headers().clone())),
+            status => Err(BeepkgError::from_status(
+                status,
+                format!("Failed to check {}", key),
+            )),
         }
+    }
 
-        // 如果指定了时间戳，找到特定备份
-        let backup = if let Some(ts) = timestamp {
-            filtered_backups
-                .iter()
-                .find(|b| b.timestamp.starts_with(ts))
-                .ok_or_else(|| format!("No backup found with timestamp {}", ts))?
-        } else {
-            // 否则使用最新的备份
-            filtered_backups.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-            filtered_backups
-                .first()
-                .ok_or_else(|| "Failed to get latest backup".to_string())?
-        };
-
-        // 从备份恢复
-        let backup_key = &backup.backup_path;
-        let action = self
-            .bucket
-            .get_object(self.credentials.as_ref(), backup_key);
+    // 删除对象
+    async fn delete_object(&self, key: &str) -> Result<(), BeepkgError> {
+        let credentials = self.credentials().await?;
+        let action = self.bucket.delete_object(credentials.as_ref(), key);
         let url = action.sign(Duration::from_secs(3600));
 
-        // 下载备份对象
-        let response = self.client.get(url).send().await?;
-        if !response.status().is_success() {
-            return Err(format!("Failed to download backup: {}", response.status()).into());
+        let response = self.send(self.client.delete(url)).await?;
+        if !response.status().is_success() && response.status() != reqwest::StatusCode::NOT_FOUND {
+            return Err(BeepkgError::from_status(
+                response.status(),
+                format!("Failed to delete {}", key),
+            ));
         }
 
-        let bytes = response.bytes().await?;
+        Ok(())
+    }
 
-        // 确定原始路径
-        let original_key = &backup.original_path;
+    // 构造 PUT 请求；配置了服务端加密时附加 SSE 请求头，并将其纳入预签名
+    async fn put_request(
+        &self,
+        key: &str,
+        content_type: &str,
+    ) -> Result<reqwest::RequestBuilder, BeepkgError> {
+        self.put_request_with_headers(key, content_type, None).await
+    }
 
-        // 上传回原始位置
-        let action = self
-            .bucket
-            .put_object(self.credentials.as_ref(), original_key);
+    // 上传请求，额外的请求头（例如存储类型）与 SSE 请求头一起签名
+    async fn put_request_with_headers(
+        &self,
+        key: &str,
+        content_type: &str,
+        extra: impl IntoIterator<Item = (&'static str, String)>,
+    ) -> Result<reqwest::RequestBuilder, BeepkgError> {
+        let mut headers = self
+            .server_side_encryption
+            .as_ref()
+            .map(models::ServerSideEncryption::headers)
+            .unwrap_or_default();
+        headers.extend(extra);
+
+        let credentials = self.credentials().await?;
+        let mut action = self.bucket.put_object(credentials.as_ref(), key);
+        for (name, value) in &headers {
+            action.headers_mut().insert(*name, value.clone());
+        }
         let url = action.sign(Duration::from_secs(3600));
 
-        // 上传恢复的对象
-        let response = self
-            .client
-            .put(url)
-            .header("Content-Type", "application/zip")
-            .body(bytes)
-            .send()
+        let mut request = self.client.put(url).header("Content-Type", content_type);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        Ok(request)
+    }
+
+    // 在存储端把对象复制到另一个键，内容不经过客户端。源对象键不做 URL 编码，只用于 blobs::staging_key 这样
+    // 只含安全字符的键。S3 的复制请求在传输开始后出错时仍返回 200，错误写在响应内容中
+    async fn copy_object(&self, source: &str, target: &str) -> Result<(), BeepkgError> {
+        let copy_source = format!("/{}/{}", self.bucket.name(), source);
+        let request = self
+            .put_request_with_headers(
+                target,
+                "application/octet-stream",
+                [("x-amz-copy-source", copy_source)],
+            )
             .await?;
+        let response = self.send(request).await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(BeepkgError::from_status(
+                status,
+                format!("Failed to copy {} to {}", source, target),
+            ));
+        }
+        let body = response.text().await?;
+        if body.contains("<Error>") {
+            return Err(format!("Failed to copy {} to {}: {}", source, target, body).into());
+        }
+        Ok(())
+    }
+
+    // 上传对象内容
+    async fn put_object_bytes(
+        &self,
+        key: &str,
+        body: impl Into<reqwest::Body>,
+        content_type: &str,
+    ) -> Result<(), BeepkgError> {
+        let request = self.put_request(key, content_type).await?.body(body);
+        let response = self.send(request).await?;
 
         if !response.status().is_success() {
-            return Err(format!("Failed to restore package: {}", response.status()).into());
+            return Err(BeepkgError::from_status(
+                response.status(),
+                format!("Failed to upload {}", key),
+            ));
         }
 
         Ok(())
     }
 
     // 获取注册表元数据
-    async fn get_registry_metadata(
-        &self,
-    ) -> Result<models::RegistryMetadata, Box<dyn Error + Send + Sync>> {
+    async fn get_registry_metadata(&self) -> Result<models::RegistryMetadata, BeepkgError> {
         // 元数据文件名
-        let metadata_key = "registry-metadata.json";
+        let metadata_key = REGISTRY_METADATA_KEY;
+
+        // 离线时使用缓存的副本
+        let cached = match (&self.cache, self.offline) {
+            (Some(cache), true) => cache.load_object(&self.cache_registry(), metadata_key),
+            _ => None,
+        };
+        if let Some(content) = cached {
+            return schema::from_json(&content);
+        }
 
         // 尝试获取元数据
-        let action = self
-            .bucket
-            .get_object(self.credentials.as_ref(), metadata_key);
+        let credentials = self.credentials().await?;
+        let action = self.bucket.get_object(credentials.as_ref(), metadata_key);
         let url = action.sign(Duration::from_secs(3600));
 
         // 下载元数据
-        let response = self.client.get(url).send().await;
+        let response = self.send(self.client.get(url)).await;
 
         match response {
             Ok(resp) if resp.status().is_success() => {
                 // 解析元数据
                 let content = resp.text().await?;
-                let metadata: models::RegistryMetadata = serde_json::from_str(&content)?;
+                let metadata: models::RegistryMetadata = schema::from_json(content.as_bytes())?;
+                if let Some(cache) = &self.cache {
+                    // 缓存失败不影响读取
+                    let _ = cache.store_object(
+                        &self.cache_registry(),
+                        metadata_key,
+                        content.as_bytes(),
+                    );
+                }
                 Ok(metadata)
             }
             _ => {
                 // 如果不存在，创建新的元数据
-                let now = chrono::Utc::now().to_rfc3339();
-                Ok(models::RegistryMetadata {
-                    registry_name: "MinIO Package Registry".to_string(),
-                    backup_enabled: false,
-                    locked_packages: Vec::new(),
-                    backups: Vec::new(),
-                    last_updated: now,
-                })
+                Ok(models::RegistryMetadata::default())
             }
         }
     }
@@ -959,7 +8016,7 @@ impl PackageManager {
     fn get_package_metadata(
         &self,
         zip_path: &Path,
-    ) -> Result<models::PackageMetadata, Box<dyn Error + Send + Sync>> {
+    ) -> Result<models::PackageMetadata, BeepkgError> {
         // 创建临时目录解压zip文件
         let temp_dir = tempfile::tempdir()?;
         let file = std::fs::File::open(zip_path)?;
@@ -972,10 +8029,10 @@ impl PackageManager {
 
         let metadata: models::PackageMetadata = if toml_path.exists() {
             let toml_content = std::fs::read_to_string(&toml_path)?;
-            toml::from_str(&toml_content)?
+            schema::from_toml(&toml_content)?
         } else if json_path.exists() {
             let json_content = std::fs::read_to_string(&json_path)?;
-            serde_json::from_str(&json_content)?
+            schema::from_json(json_content.as_bytes())?
         } else {
             return Err("Neither pack.toml nor pack.json found in package".into());
         };
@@ -986,32 +8043,274 @@ impl PackageManager {
     async fn save_registry_metadata(
         &self,
         metadata: &models::RegistryMetadata,
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    ) -> Result<(), BeepkgError> {
         // 元数据文件名
-        let metadata_key = "registry-metadata.json";
+        let metadata_key = REGISTRY_METADATA_KEY;
 
         // 序列化元数据
         let content = serde_json::to_string_pretty(metadata)?;
 
         // 上传元数据
-        let action = self
-            .bucket
-            .put_object(self.credentials.as_ref(), metadata_key);
-        let url = action.sign(Duration::from_secs(3600));
-
-        // 上传对象
-        let response = self
-            .client
-            .put(url)
-            .header("Content-Type", "application/json")
-            .body(content)
-            .send()
-            .await?;
+        let request = self
+            .put_request(metadata_key, "application/json")
+            .await?
+            .body(content);
+        let response = self.send(request).await?;
 
         if !response.status().is_success() {
-            return Err(format!("Failed to save registry metadata: {}", response.status()).into());
+            return Err(BeepkgError::from_status(
+                response.status(),
+                "Failed to save registry metadata",
+            ));
         }
 
         Ok(())
     }
 }
+
+/// 按顺序从多个注册表拉取包：包不存在或注册表无法访问时尝试下一个，
+/// 校验失败等其他错误直接返回。成功时返回所用注册表的名称
+pub async fn pull_from_registries<'a>(
+    registries: &'a [(String, PackageManager)],
+    package_name: &str,
+    output_dir: &Path,
+) -> Result<&'a str, BeepkgError> {
+    let mut last_error = None;
+    for (name, manager) in registries {
+        manager.emit(Event::Info(format!("Trying registry {}", name)));
+        match manager.pull_package(package_name, output_dir).await {
+            Ok(()) => return Ok(name),
+            Err(e) if is_unavailable(&e) => {
+                manager.emit(Event::Info(format!(
+                    "{} not available from {}: {}",
+                    package_name, name, e
+                )));
+                last_error = Some(e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| "No registries configured".into()))
+}
+
+// 包不存在或注册表无法访问，可以回退到下一个注册表
+fn is_unavailable(error: &BeepkgError) -> bool {
+    matches!(
+        error,
+        BeepkgError::MissingChecksum
+            | BeepkgError::Offline(_)
+            | BeepkgError::NotFound(_)
+            | BeepkgError::Network(_)
+            | BeepkgError::Http(_)
+    )
+}
+
+// 安装记录中目录仍然存在的包：名称 -> 校验和
+fn reusable_packages(dir: &Path, installed: &[models::VendoredPackage]) -> HashMap<String, String> {
+    installed
+        .iter()
+        .filter(|p| project::is_inside(&p.path) && dir.join(&p.path).is_dir())
+        .map(|p| (p.name.clone(), p.checksum.clone()))
+        .collect()
+}
+
+// 安装完成后对比安装记录：删除不再需要的包，写入新的安装记录
+fn finish_sync(
+    dir: &Path,
+    installed: &[models::VendoredPackage],
+    reuse: &HashMap<String, String>,
+    packages: &[models::VendoredPackage],
+) -> Result<SyncReport, BeepkgError> {
+    let mut report = SyncReport::default();
+    for package in packages {
+        let spec = format!("{}@{}", package.name, package.version);
+        if reuse.get(&package.name) == Some(&package.checksum) {
+            report.unchanged.push(spec);
+        } else {
+            report.installed.push(spec);
+        }
+    }
+    for old in installed {
+        if packages.iter().any(|p| p.name == old.name) {
+            continue;
+        }
+        let old_dir = dir.join(&old.path);
+        if project::is_inside(&old.path) && old_dir.is_dir() {
+            std::fs::remove_dir_all(&old_dir)?;
+        }
+        report.removed.push(format!("{}@{}", old.name, old.version));
+    }
+    project::save_installed(dir, packages)?;
+    Ok(report)
+}
+
+// 包文件对象键对应的包名，审计日志和备份等其他 zip 对象返回 None
+fn archive_package_name(key: &str) -> Option<&str> {
+    if key.starts_with(AUDIT_PREFIX) || is_staged_key(key) {
+        return None;
+    }
+    models::parse_archive_key(key).map(|(name, _version)| name)
+}
+
+// 包文件 name-version.zip 或其附属文件 name-version.zip.<后缀> 所属的包名。
+// 备份、草稿、待审批等其他对象返回 None
+#[cfg(feature = "proxy")]
+fn proxy_package(key: &str) -> Option<&str> {
+    let archive = key.find(".zip.").map_or(key, |index| &key[..index + 4]);
+    let name = archive_package_name(archive)?;
+    models::validate_package_name(name).ok()?;
+    Some(name)
+}
+
+// 读取需要在内存中加密或解密的文件，超过 MAX_IN_MEMORY_CIPHER_SIZE 时返回错误，
+// 避免把大文件整个读入内存
+fn read_for_cipher(
+    path: &Path,
+    error: fn(String) -> SecurityError,
+) -> Result<Vec<u8>, BeepkgError> {
+    let size = std::fs::metadata(path)?.len();
+    if size > MAX_IN_MEMORY_CIPHER_SIZE {
+        return Err(error(format!(
+            "{} is {} bytes, age recipient and KMS envelope encryption support at most {} bytes; \
+             use passphrase encryption for larger packages",
+            path.display(),
+            size,
+            MAX_IN_MEMORY_CIPHER_SIZE
+        ))
+        .into());
+    }
+    Ok(std::fs::read(path)?)
+}
+
+// 加密单个文件并写入 writer，返回明文大小；口令加密逐块处理，其他方式在内存中加密
+fn encrypt_file(cipher: &Cipher, path: &Path, mut writer: impl Write) -> Result<u64, BeepkgError> {
+    if let Cipher::Secret { secret, kdf } = cipher {
+        let file = std::fs::File::open(path)?;
+        let size = file.metadata()?.len();
+        SecurityManager::encrypt_stream(secret, kdf, std::io::BufReader::new(file), writer)?;
+        return Ok(size);
+    }
+    let content = read_for_cipher(path, SecurityError::EncryptionFailed)?;
+    writer.write_all(&cipher.encrypt(&content)?)?;
+    Ok(content.len() as u64)
+}
+
+// 备份记录中原始包文件对应的包名和版本
+fn backup_source(original_path: &str) -> Option<(&str, &str)> {
+    models::split_archive_name(original_path.strip_suffix(".zip")?)
+}
+
+// 把所选包的访问控制、发布频道和锁从源注册表的元数据同步到目标注册表的元数据，
+// 源注册表中没有的记录从目标中移除。返回目标元数据是否有变化
+fn sync_package_metadata(
+    source: &models::RegistryMetadata,
+    target: &mut models::RegistryMetadata,
+    packages: &BTreeSet<&str>,
+) -> bool {
+    let mut changed = false;
+    for &name in packages {
+        match source.access.get(name) {
+            Some(access) if target.access.get(name) != Some(access) => {
+                target.access.insert(name.to_string(), access.clone());
+                changed = true;
+            }
+            None => changed |= target.access.remove(name).is_some(),
+            _ => {}
+        }
+        match source.channels.get(name) {
+            Some(channels) if target.channels.get(name) != Some(channels) => {
+                target.channels.insert(name.to_string(), channels.clone());
+                changed = true;
+            }
+            None => changed |= target.channels.remove(name).is_some(),
+            _ => {}
+        }
+        let source_locks: Vec<&models::LockedPackage> = source
+            .locked_packages
+            .iter()
+            .filter(|lock| lock.name == name)
+            .collect();
+        let target_locks: Vec<&models::LockedPackage> = target
+            .locked_packages
+            .iter()
+            .filter(|lock| lock.name == name)
+            .collect();
+        if source_locks != target_locks {
+            let locks: Vec<models::LockedPackage> = source_locks.into_iter().cloned().collect();
+            target.locked_packages.retain(|lock| lock.name != name);
+            target.locked_packages.extend(locks);
+            changed = true;
+        }
+    }
+    changed
+}
+
+// 指定用户（及其所属团队）是否可以查看和拉取包，没有访问控制记录的包所有人可见
+fn visible_to(
+    registry: &models::RegistryMetadata,
+    package: &str,
+    user: &str,
+    teams: &[String],
+) -> bool {
+    registry
+        .access
+        .get(package)
+        .is_none_or(|access| access.allows(user, teams))
+}
+
+// 对象的修改时间是否早于 cutoff，时间未知的对象视为较新，不会被清理
+fn modified_before(last_modified: Option<&str>, cutoff: chrono::DateTime<chrono::Utc>) -> bool {
+    last_modified
+        .and_then(|time| chrono::DateTime::parse_from_rfc3339(time).ok())
+        .is_some_and(|time| time < cutoff)
+}
+
+// 请求注册表使用的 HTTP 客户端，未配置代理时 reqwest 使用 HTTP_PROXY 等环境变量
+fn http_client(
+    tls: &config::TlsConfig,
+    proxy: Option<&config::ProxyConfig>,
+    timeout: Duration,
+) -> Result<ReqwestClient, BeepkgError> {
+    let mut builder = tls.apply(ReqwestClient::builder().timeout(timeout))?;
+    if let Some(proxy) = proxy {
+        builder = proxy.apply(builder)?;
+    }
+    Ok(builder.build()?)
+}
+
+// 按扩展名推断附属文件的 Content-Type
+fn content_type(key: &str) -> &'static str {
+    match key.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("json") => "application/json",
+        Some("zip") => "application/zip",
+        Some("asc") => "application/pgp-signature",
+        _ => "text/plain",
+    }
+}
+
+// 本地临时文件名，作用域包的对象键中包含 /
+fn local_file_name(key: &str) -> String {
+    key.replace('/', "__")
+}
+
+// 解析逗号分隔的团队列表
+fn parse_teams(teams: &str) -> Vec<String> {
+    teams
+        .split(',')
+        .map(str::trim)
+        .filter(|team| !team.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// 授权对象为用户名或 team:<name>
+fn validate_principal(principal: &str) -> Result<(), BeepkgError> {
+    if principal.trim().is_empty() || principal == "team:" {
+        return Err(format!(
+            "Invalid principal: {:?} (expected a user or team:<name>)",
+            principal
+        )
+        .into());
+    }
+    Ok(())
+}
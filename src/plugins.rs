@@ -0,0 +1,155 @@
+use crate::Result;
+use crate::auth;
+use crate::config::Config;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// 插件可执行文件的前缀：`beepkg foo` 运行 PATH 中的 beepkg-foo
+pub const PLUGIN_PREFIX: &str = "beepkg-";
+
+/// 通过标准输入以 JSON 传给插件的连接配置。凭证不在其中，
+/// 插件从继承的 S3_ACCESS_KEY 等环境变量、配置文件或 `beepkg login` 保存的令牌获取
+#[derive(Debug, Serialize)]
+pub struct PluginContext {
+    /// 调用插件的 beepkg 版本
+    pub version: String,
+    /// beepkg 可执行文件路径，插件可以用它回调内置命令
+    pub executable: Option<PathBuf>,
+    pub config_dir: Option<PathBuf>,
+    /// S3_ENDPOINT / S3_BUCKET 指定的默认注册表
+    pub endpoint: Option<String>,
+    pub bucket: String,
+    /// 配置文件中的注册表
+    pub registries: Vec<PluginRegistry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PluginRegistry {
+    pub name: String,
+    pub endpoint: String,
+    pub bucket: String,
+}
+
+impl PluginContext {
+    pub fn from_env() -> Result<Self> {
+        let config = Config::from_env()?;
+        Ok(Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            executable: std::env::current_exe().ok(),
+            config_dir: auth::config_dir().ok(),
+            endpoint: std::env::var("S3_ENDPOINT").ok(),
+            bucket: std::env::var("S3_BUCKET").unwrap_or_else(|_| "packages".to_string()),
+            registries: config
+                .registries
+                .into_iter()
+                .map(|registry| PluginRegistry {
+                    name: registry.name,
+                    endpoint: registry.endpoint,
+                    bucket: registry.bucket,
+                })
+                .collect(),
+        })
+    }
+}
+
+/// 在 PATH 中查找插件，Windows 上同时尝试 PATHEXT 中的扩展名
+pub fn find(name: &str) -> Option<PathBuf> {
+    if name.is_empty() || name.contains(['/', '\\']) {
+        return None;
+    }
+    let file_name = format!("{}{}", PLUGIN_PREFIX, name);
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths).find_map(|dir| {
+        candidates(&file_name)
+            .into_iter()
+            .map(|candidate| dir.join(candidate))
+            .find(|path| is_executable(path))
+    })
+}
+
+/// PATH 中所有插件的名称（去掉 beepkg- 前缀和扩展名）
+pub fn list() -> Vec<String> {
+    let Some(paths) = std::env::var_os("PATH") else {
+        return Vec::new();
+    };
+    let mut names = BTreeSet::new();
+    for dir in std::env::split_paths(&paths) {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.filter_map(|entry| Some(entry.ok()?.path())) {
+            let Some(name) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.strip_prefix(PLUGIN_PREFIX))
+            else {
+                continue;
+            };
+            if !name.is_empty() && is_executable(&path) {
+                names.insert(name.to_string());
+            }
+        }
+    }
+    names.into_iter().collect()
+}
+
+/// 运行插件：其余参数原样传入，连接配置写入标准输入后关闭，并设置 BEEPKG_PLUGIN、
+/// BEEPKG_BIN、BEEPKG_CONFIG_DIR 环境变量。返回插件的退出码
+pub async fn run(
+    plugin: &Path,
+    name: &str,
+    args: &[String],
+    context: &PluginContext,
+) -> Result<i32> {
+    let mut command = Command::new(plugin);
+    command
+        .args(args)
+        .env("BEEPKG_PLUGIN", name)
+        .stdin(Stdio::piped());
+    if let Some(executable) = &context.executable {
+        command.env("BEEPKG_BIN", executable);
+    }
+    if let Some(config_dir) = &context.config_dir {
+        command.env("BEEPKG_CONFIG_DIR", config_dir);
+    }
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to run plugin {}: {}", plugin.display(), e))?;
+    let mut stdin = child.stdin.take().ok_or("Failed to open plugin stdin")?;
+    // 插件可以不读取标准输入，管道已关闭时忽略写入错误
+    let _ = stdin.write_all(&serde_json::to_vec(context)?).await;
+    drop(stdin);
+
+    let status = child.wait().await?;
+    Ok(status.code().unwrap_or(1))
+}
+
+fn candidates(file_name: &str) -> Vec<String> {
+    if !cfg!(windows) {
+        return vec![file_name.to_string()];
+    }
+    let extensions = std::env::var("PATHEXT").unwrap_or_else(|_| ".EXE;.CMD;.BAT".to_string());
+    extensions
+        .split(';')
+        .filter(|extension| !extension.is_empty())
+        .map(|extension| format!("{}{}", file_name, extension.to_ascii_lowercase()))
+        .collect()
+}
+
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        path.metadata()
+            .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
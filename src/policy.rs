@@ -0,0 +1,120 @@
+use crate::Result;
+use crate::checksum::{Checksum, ChecksumAlgorithm};
+use serde::Deserialize;
+use std::path::Path;
+
+/// 项目目录下默认的信任策略文件名
+pub const POLICY_FILE: &str = "beepkg-trust.toml";
+
+/// 项目级信任策略，拉取时在解压之前检查
+///
+/// ```toml
+/// require_signature = true
+/// required_signers = ["ed25519:...", "0123ABCD..."]
+/// allowed_authors = ["team-infra"]
+/// min_checksum_algorithm = "sha256"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrustPolicy {
+    /// 拒绝没有有效签名的包
+    #[serde(default)]
+    pub require_signature: bool,
+    /// 允许的签名者：ed25519 公钥、GPG 指纹或 sigstore 身份，为空时不限制
+    #[serde(default)]
+    pub required_signers: Vec<String>,
+    /// 允许的包作者（pack.toml 中的 author），为空时不限制
+    #[serde(default)]
+    pub allowed_authors: Vec<String>,
+    /// 允许的最弱校验和算法
+    #[serde(default)]
+    pub min_checksum_algorithm: Option<String>,
+}
+
+impl TrustPolicy {
+    /// 读取并校验策略文件
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read trust policy {}: {}", path.display(), e))?;
+        let policy: TrustPolicy = toml::from_str(&content)
+            .map_err(|e| format!("Invalid trust policy {}: {}", path.display(), e))?;
+        policy.min_checksum()?;
+        Ok(policy)
+    }
+
+    /// 查找当前项目的策略文件：优先 BEEPKG_TRUST_POLICY，其次当前目录下的 beepkg-trust.toml
+    pub fn discover() -> Result<Option<Self>> {
+        if let Ok(path) = std::env::var("BEEPKG_TRUST_POLICY") {
+            return Ok(Some(Self::load(Path::new(&path))?));
+        }
+        let path = std::env::current_dir()?.join(POLICY_FILE);
+        if path.exists() {
+            return Ok(Some(Self::load(&path)?));
+        }
+        Ok(None)
+    }
+
+    fn min_checksum(&self) -> Result<Option<ChecksumAlgorithm>> {
+        match &self.min_checksum_algorithm {
+            Some(name) => Ok(Some(name.parse::<ChecksumAlgorithm>()?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 是否需要签名信息才能完成检查
+    pub fn requires_signature(&self) -> bool {
+        self.require_signature || !self.required_signers.is_empty()
+    }
+
+    /// 检查包是否满足策略，返回所有违反的规则
+    pub fn evaluate(&self, checksum: &Checksum, signer: Option<&str>, author: &str) -> Vec<String> {
+        let mut violations = Vec::new();
+
+        match self.min_checksum() {
+            Ok(Some(minimum)) if checksum.algorithm.strength() < minimum.strength() => {
+                violations.push(format!(
+                    "checksum algorithm {} is weaker than the required {}",
+                    checksum.algorithm.name(),
+                    minimum.name()
+                ));
+            }
+            _ => {}
+        }
+
+        match signer {
+            None if self.requires_signature() => {
+                violations.push("package is not signed by a verified signer".to_string());
+            }
+            Some(signer)
+                if !self.required_signers.is_empty()
+                    && !self
+                        .required_signers
+                        .iter()
+                        .any(|allowed| signer_matches(allowed, signer)) =>
+            {
+                violations.push(format!("signer {} is not in required_signers", signer));
+            }
+            _ => {}
+        }
+
+        if !self.allowed_authors.is_empty() && !self.allowed_authors.iter().any(|a| a == author) {
+            violations.push(format!("author {} is not in allowed_authors", author));
+        }
+
+        violations
+    }
+}
+
+// GPG 指纹不区分大小写，并允许使用长密钥 ID（指纹后缀）
+fn signer_matches(allowed: &str, signer: &str) -> bool {
+    let allowed = allowed.trim();
+    if allowed == signer {
+        return true;
+    }
+    let is_hex = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_hexdigit());
+    is_hex(allowed)
+        && is_hex(signer)
+        && signer
+            .to_ascii_uppercase()
+            .ends_with(&allowed.to_ascii_uppercase())
+}
@@ -0,0 +1,215 @@
+use crate::Result;
+use crate::models::{self, VendoredPackage};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+
+/// 项目清单文件名，位于应用仓库根目录
+pub const PROJECT_FILE: &str = "project.toml";
+
+/// sync 写入的锁文件，与 project.toml 一起提交，`sync --frozen` 按它安装。
+/// 使用环境配置时为 `project-lock.<配置>.json`，见 [`lock_file`]
+pub const PROJECT_LOCK: &str = "project-lock.json";
+
+/// 安装目录中记录当前已安装的包，切换环境配置后据此复用或删除包
+pub const INSTALLED_FILE: &str = ".beepkg-installed.json";
+
+/// 应用仓库需要的包，`beepkg sync` 把安装目录同步到与它一致
+///
+/// ```toml
+/// dir = "assets"
+///
+/// [packages]
+/// ui-icons = "^2.1"
+/// fonts = "1.0.0"
+///
+/// # 环境配置，覆盖上面的版本或增加包，通过 `sync --profile prod` 选择
+/// [profile.prod]
+/// fonts = "1.1.0"
+/// analytics = "^3.0"
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Project {
+    /// 安装目录，相对于项目根目录，每个包解压到其中的 `<name>` 子目录
+    #[serde(default = "default_dir")]
+    pub dir: String,
+    /// 包名 -> 版本需求（版本或范围）
+    #[serde(default)]
+    pub packages: BTreeMap<String, String>,
+    /// 环境配置名 -> 覆盖或增加的包
+    #[serde(default)]
+    pub profile: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+fn default_dir() -> String {
+    "packages".to_string()
+}
+
+impl Project {
+    /// 读取根目录中的 project.toml
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join(PROJECT_FILE);
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        let project: Self = toml::from_str(&content)
+            .map_err(|e| format!("Invalid project file {}: {}", path.display(), e))?;
+        if project.packages.is_empty() && project.profile.is_empty() {
+            return Err(format!("{} lists no packages", path.display()).into());
+        }
+        // sync 会删除安装目录中不再需要的包，目录必须在项目内
+        if !is_inside(&project.dir) {
+            return Err(format!(
+                "Install directory {} must be a relative path inside the project",
+                project.dir
+            )
+            .into());
+        }
+        for profile in project.profile.keys() {
+            validate_profile_name(profile)?;
+        }
+        let overrides = project
+            .profile
+            .values()
+            .flat_map(|packages| packages.keys());
+        for name in project.packages.keys().chain(overrides) {
+            models::validate_package_name(name)?;
+        }
+        Ok(project)
+    }
+
+    /// 指定环境配置下的版本需求：[packages] 加上该配置覆盖或增加的包
+    pub fn requirements(&self, profile: Option<&str>) -> Result<BTreeMap<String, String>> {
+        let mut requirements = self.packages.clone();
+        if let Some(profile) = profile {
+            let overrides = self.profile.get(profile).ok_or_else(|| {
+                let defined: Vec<_> = self.profile.keys().map(String::as_str).collect();
+                format!(
+                    "Profile {} is not defined in {} (defined: {})",
+                    profile,
+                    PROJECT_FILE,
+                    if defined.is_empty() {
+                        "none".to_string()
+                    } else {
+                        defined.join(", ")
+                    }
+                )
+            })?;
+            requirements.extend(overrides.clone());
+        }
+        if requirements.is_empty() {
+            return Err(format!(
+                "{} lists no packages without a profile; use --profile",
+                PROJECT_FILE
+            )
+            .into());
+        }
+        Ok(requirements)
+    }
+}
+
+// 配置名出现在锁文件名中，只允许字母、数字、- 和 _
+fn validate_profile_name(profile: &str) -> Result<()> {
+    let valid = !profile.is_empty()
+        && profile
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!(
+            "Invalid profile name {:?}: use letters, digits, - and _",
+            profile
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// 环境配置使用的锁文件名，不指定配置时为 project-lock.json
+pub fn lock_file(profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) => format!("project-lock.{}.json", profile),
+        None => PROJECT_LOCK.to_string(),
+    }
+}
+
+/// sync 解析出的完整依赖闭包
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectLock {
+    pub generated_at: String,
+    /// 生成锁文件时 project.toml 中的需求，`--frozen` 时必须与当前清单一致
+    pub requirements: BTreeMap<String, String>,
+    /// 安装的包（包括间接依赖），path 相对于安装目录
+    pub packages: Vec<VendoredPackage>,
+}
+
+impl ProjectLock {
+    /// 读取根目录中环境配置的锁文件，不存在时返回 None
+    pub fn load(root: &Path, profile: Option<&str>) -> Result<Option<Self>> {
+        let path = root.join(lock_file(profile));
+        if !path.exists() {
+            return Ok(None);
+        }
+        let lock = serde_json::from_str(&std::fs::read_to_string(&path)?)
+            .map_err(|e| format!("Invalid lock file {}: {}", path.display(), e))?;
+        Ok(Some(lock))
+    }
+
+    pub fn save(&self, root: &Path, profile: Option<&str>) -> Result<()> {
+        std::fs::write(
+            root.join(lock_file(profile)),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        Ok(())
+    }
+
+    /// 需求和安装的包是否相同，不比较生成时间
+    pub fn same_as(&self, other: &ProjectLock) -> bool {
+        let key = |lock: &ProjectLock| -> Vec<(String, String, String)> {
+            lock.packages
+                .iter()
+                .map(|p| (p.name.clone(), p.version.clone(), p.checksum.clone()))
+                .collect()
+        };
+        self.requirements == other.requirements && key(self) == key(other)
+    }
+}
+
+/// 读取安装目录中记录的已安装包，没有记录时为空
+pub fn load_installed(dir: &Path) -> Result<Vec<VendoredPackage>> {
+    let path = dir.join(INSTALLED_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let installed = serde_json::from_str(&std::fs::read_to_string(&path)?)
+        .map_err(|e| format!("Invalid install record {}: {}", path.display(), e))?;
+    Ok(installed)
+}
+
+pub fn save_installed(dir: &Path, packages: &[VendoredPackage]) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(
+        dir.join(INSTALLED_FILE),
+        serde_json::to_string_pretty(packages)?,
+    )?;
+    Ok(())
+}
+
+/// 一次 sync 的结果，元素为 name@version
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    /// 新安装或内容变化的包
+    pub installed: Vec<String>,
+    /// 不再需要而删除的包
+    pub removed: Vec<String>,
+    /// 已是所需内容、没有重新下载的包
+    pub unchanged: Vec<String>,
+}
+
+/// 路径是否为只包含普通部分的相对路径
+pub fn is_inside(path: &str) -> bool {
+    let path = Path::new(path);
+    path.components().next().is_some()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
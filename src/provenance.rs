@@ -0,0 +1,203 @@
+use crate::checksum::Checksum;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+/// 包文件对应的证明文件后缀，例如 demo-1.0.0.zip.intoto.json
+pub const ATTESTATION_EXTENSION: &str = "intoto.json";
+
+pub const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+pub const PREDICATE_TYPE: &str = "https://slsa.dev/provenance/v1";
+pub const BUILD_TYPE: &str = "https://github.com/zhongjingjogy/packman/push/v1";
+
+/// in-toto 声明，谓词为 SLSA v1 构建来源
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Statement {
+    #[serde(rename = "_type")]
+    pub statement_type: String,
+    pub subject: Vec<ResourceDescriptor>,
+    #[serde(rename = "predicateType")]
+    pub predicate_type: String,
+    pub predicate: Provenance,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResourceDescriptor {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    #[serde(default)]
+    pub digest: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Provenance {
+    pub build_definition: BuildDefinition,
+    pub run_details: RunDetails,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildDefinition {
+    pub build_type: String,
+    pub external_parameters: ExternalParameters,
+    #[serde(default)]
+    pub resolved_dependencies: Vec<ResourceDescriptor>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExternalParameters {
+    /// name@version
+    pub package: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunDetails {
+    pub builder: Builder,
+    pub metadata: BuildMetadata,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Builder {
+    pub id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invocation_id: Option<String>,
+    pub started_on: String,
+    pub finished_on: String,
+}
+
+impl Statement {
+    /// 为推送的包生成构建来源声明，源码仓库和提交从 CI 环境变量或包目录的 git 信息中获取
+    pub fn for_package(
+        archive_name: &str,
+        package: &str,
+        checksum: &Checksum,
+        package_dir: &Path,
+        actor: &str,
+        started_on: DateTime<Utc>,
+    ) -> Self {
+        let repository = source_repository(package_dir);
+        let commit = source_commit(package_dir);
+
+        let mut resolved_dependencies = Vec::new();
+        if let (Some(repository), Some(commit)) = (&repository, &commit) {
+            resolved_dependencies.push(ResourceDescriptor {
+                name: None,
+                uri: Some(format!("git+{}", repository)),
+                digest: BTreeMap::from([("gitCommit".to_string(), commit.clone())]),
+            });
+        }
+
+        Statement {
+            statement_type: STATEMENT_TYPE.to_string(),
+            subject: vec![ResourceDescriptor {
+                name: Some(archive_name.to_string()),
+                uri: None,
+                digest: BTreeMap::from([(
+                    checksum.algorithm.name().to_string(),
+                    checksum.hex.clone(),
+                )]),
+            }],
+            predicate_type: PREDICATE_TYPE.to_string(),
+            predicate: Provenance {
+                build_definition: BuildDefinition {
+                    build_type: BUILD_TYPE.to_string(),
+                    external_parameters: ExternalParameters {
+                        package: package.to_string(),
+                        repository,
+                        commit,
+                    },
+                    resolved_dependencies,
+                },
+                run_details: RunDetails {
+                    builder: Builder {
+                        id: builder_id(actor),
+                    },
+                    metadata: BuildMetadata {
+                        invocation_id: invocation_id(),
+                        started_on: started_on.to_rfc3339(),
+                        finished_on: Utc::now().to_rfc3339(),
+                    },
+                },
+            },
+        }
+    }
+
+    /// 声明的主体摘要是否与给定校验和一致
+    pub fn matches(&self, checksum: &Checksum) -> bool {
+        self.subject
+            .iter()
+            .any(|subject| subject.digest.get(checksum.algorithm.name()) == Some(&checksum.hex))
+    }
+}
+
+// 构建者身份：BEEPKG_BUILDER_ID > CI 运行地址 > 本地用户
+fn builder_id(actor: &str) -> String {
+    if let Ok(id) = std::env::var("BEEPKG_BUILDER_ID") {
+        return id;
+    }
+    if let (Ok(server), Ok(repository), Ok(run_id)) = (
+        std::env::var("GITHUB_SERVER_URL"),
+        std::env::var("GITHUB_REPOSITORY"),
+        std::env::var("GITHUB_RUN_ID"),
+    ) {
+        return format!("{}/{}/actions/runs/{}", server, repository, run_id);
+    }
+    if let Ok(job_url) = std::env::var("CI_JOB_URL") {
+        return job_url;
+    }
+    format!("local:{}", actor)
+}
+
+fn invocation_id() -> Option<String> {
+    std::env::var("GITHUB_RUN_ID")
+        .or_else(|_| std::env::var("CI_PIPELINE_ID"))
+        .ok()
+}
+
+fn source_repository(package_dir: &Path) -> Option<String> {
+    if let (Ok(server), Ok(repository)) = (
+        std::env::var("GITHUB_SERVER_URL"),
+        std::env::var("GITHUB_REPOSITORY"),
+    ) {
+        return Some(format!("{}/{}", server, repository));
+    }
+    std::env::var("CI_PROJECT_URL")
+        .ok()
+        .or_else(|| git(package_dir, &["remote", "get-url", "origin"]))
+}
+
+fn source_commit(package_dir: &Path) -> Option<String> {
+    std::env::var("GITHUB_SHA")
+        .or_else(|_| std::env::var("CI_COMMIT_SHA"))
+        .ok()
+        .or_else(|| git(package_dir, &["rev-parse", "HEAD"]))
+}
+
+// 在包目录下执行 git 命令，失败（不是 git 仓库或没有 git）时返回 None
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let value = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
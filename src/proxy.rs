@@ -0,0 +1,279 @@
+use crate::artifacts;
+use crate::blobs;
+use crate::checksum::ChecksumAlgorithm;
+use crate::dashboard;
+use crate::delta;
+use crate::error::BeepkgError;
+use crate::operations::PackageManager;
+use crate::signing;
+use futures_util::TryStreamExt;
+use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, Empty, Full, StreamBody};
+use hyper::body::{Bytes, Frame, Incoming};
+use hyper::header::{self, HeaderValue};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use std::convert::Infallible;
+use std::net::IpAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio_util::io::ReaderStream;
+
+type Body = BoxBody<Bytes, std::io::Error>;
+
+// 转发上游响应时保留的头
+const FORWARDED_HEADERS: [header::HeaderName; 4] = [
+    header::CONTENT_TYPE,
+    header::CONTENT_LENGTH,
+    header::ETAG,
+    header::LAST_MODIFIED,
+];
+
+/// 代理服务的设置
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// 在 /-/ 下提供网页控制台，使用代理的凭证浏览包、锁、备份和审计日志
+    pub dashboard: bool,
+    /// 请求需要带上的令牌（authorization: Bearer <令牌>），为 None 时不检查
+    pub token: Option<String>,
+}
+
+/// 只读的缓存代理。包文件和附加产物从上游拉取、按校验文件验证后存入 manager 的本地缓存，
+/// 之后的请求只向上游确认校验文件；校验文件、签名等附属文件直接转发到上游。
+/// 客户端以 path 寻址方式把代理地址作为端点，bucket 名称和凭证不影响结果。
+/// 客户端没有注册表中的身份，只能读取所有人可见的包，列表中也只有这些包的对象
+pub async fn serve(
+    manager: Arc<PackageManager>,
+    listener: TcpListener,
+    options: Options,
+) -> Result<(), BeepkgError> {
+    let options = Arc::new(options);
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let (manager, options) = (manager.clone(), options.clone());
+        tokio::spawn(async move {
+            let service = service_fn(move |request| {
+                handle(manager.clone(), options.clone(), peer.ip(), request)
+            });
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                log::debug!("proxy connection closed: {}", e);
+            }
+        });
+    }
+}
+
+async fn handle(
+    manager: Arc<PackageManager>,
+    options: Arc<Options>,
+    client: IpAddr,
+    request: Request<Incoming>,
+) -> Result<Response<Body>, Infallible> {
+    if !authorized(options.token.as_deref(), &request) {
+        let mut response = text(StatusCode::UNAUTHORIZED, "Missing or invalid token");
+        response
+            .headers_mut()
+            .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        return Ok(response);
+    }
+    let head = match *request.method() {
+        Method::GET => false,
+        Method::HEAD => true,
+        _ => {
+            return Ok(text(
+                StatusCode::METHOD_NOT_ALLOWED,
+                "The proxy is read-only",
+            ));
+        }
+    };
+    if options.dashboard && dashboard::is_dashboard_path(request.uri().path()) {
+        let query = client_query(request.uri().query());
+        let response = dashboard::handle(&manager, request.uri().path(), &query)
+            .await
+            .map(|page| {
+                let mut response = Response::new(if head {
+                    Empty::new().map_err(|never| match never {}).boxed()
+                } else {
+                    Full::new(Bytes::from(page.body))
+                        .map_err(|never| match never {})
+                        .boxed()
+                });
+                response.headers_mut().insert(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static(page.content_type),
+                );
+                response
+            });
+        return Ok(response.unwrap_or_else(|e| error_response(&e)));
+    }
+    let Some(key) = object_key(request.uri().path()) else {
+        return Ok(text(StatusCode::BAD_REQUEST, "Expected /<bucket>/<key>"));
+    };
+    let query = client_query(request.uri().query());
+    if key.is_empty() {
+        let response = manager.proxy_list(&query).await.map(|xml| {
+            let mut response = Response::new(if head {
+                Empty::new().map_err(|never| match never {}).boxed()
+            } else {
+                Full::new(Bytes::from(xml))
+                    .map_err(|never| match never {})
+                    .boxed()
+            });
+            response.headers_mut().insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/xml"),
+            );
+            response
+        });
+        return Ok(response.unwrap_or_else(|e| error_response(&e)));
+    }
+    match manager.proxy_readable(&key).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return Ok(text(
+                StatusCode::FORBIDDEN,
+                "Not available through the proxy",
+            ));
+        }
+        Err(e) => return Ok(error_response(&e)),
+    }
+
+    let response = if !head && is_content_key(&key) {
+        match fetch(&manager, &key, client).await {
+            // 没有校验文件的对象无法验证，不缓存
+            Err(BeepkgError::MissingChecksum) => forward(&manager, head, &key, &query).await,
+            result => result,
+        }
+    } else {
+        forward(&manager, head, &key, &query).await
+    };
+    Ok(response.unwrap_or_else(|e| {
+        log::warn!("proxy request for {} failed: {}", key, e);
+        error_response(&e)
+    }))
+}
+
+// 经缓存获取对象，临时文件在响应发送完后删除
+async fn fetch(
+    manager: &PackageManager,
+    key: &str,
+    client: IpAddr,
+) -> Result<Response<Body>, BeepkgError> {
+    let temp = tempfile::NamedTempFile::new()?.into_temp_path();
+    manager.proxy_fetch(key, &temp, Some(client)).await?;
+
+    let file = tokio::fs::File::open(&temp).await?;
+    let size = file.metadata().await?.len();
+    let stream = ReaderStream::new(file).map_ok(move |chunk| {
+        let _ = &temp;
+        Frame::data(chunk)
+    });
+    let mut response = Response::new(StreamBody::new(stream).boxed());
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_LENGTH, HeaderValue::from(size));
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/octet-stream"),
+    );
+    Ok(response)
+}
+
+async fn forward(
+    manager: &PackageManager,
+    head: bool,
+    key: &str,
+    query: &[(String, String)],
+) -> Result<Response<Body>, BeepkgError> {
+    let upstream = manager.proxy_forward(head, key, query).await?;
+    let mut response = Response::builder().status(upstream.status().as_u16());
+    for name in FORWARDED_HEADERS {
+        if let Some(value) = upstream.headers().get(name.as_str()) {
+            response = response.header(name, value.as_bytes());
+        }
+    }
+    let body = if head {
+        Empty::new().map_err(|never| match never {}).boxed()
+    } else {
+        let stream = upstream
+            .bytes_stream()
+            .map_ok(Frame::data)
+            .map_err(std::io::Error::other);
+        StreamBody::new(stream).boxed()
+    };
+    response
+        .body(body)
+        .map_err(|e| BeepkgError::Other(e.to_string()))
+}
+
+// 未设置令牌时不检查
+fn authorized(token: Option<&str>, request: &Request<Incoming>) -> bool {
+    let Some(token) = token else {
+        return true;
+    };
+    request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        == Some(token)
+}
+
+// 请求路径 /<bucket>/<key> 中的对象键，列表请求为空字符串
+fn object_key(path: &str) -> Option<String> {
+    let path = path.strip_prefix('/')?;
+    let key = path.split_once('/').map_or("", |(_, key)| key);
+    percent_encoding::percent_decode_str(key)
+        .decode_utf8()
+        .ok()
+        .map(|key| key.into_owned())
+}
+
+// 客户端的查询参数，去掉客户端自己的签名
+fn client_query(query: Option<&str>) -> Vec<(String, String)> {
+    url::form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+        .filter(|(name, _)| !name.to_ascii_lowercase().starts_with("x-amz-"))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect()
+}
+
+// 包文件和附加产物有校验文件，可以验证后缓存；校验文件、签名、差量和 blob 直接转发
+fn is_content_key(key: &str) -> bool {
+    if delta::is_delta_key(key) || blobs::is_blob_key(key) {
+        return false;
+    }
+    let sidecar = ChecksumAlgorithm::ALL
+        .iter()
+        .map(ChecksumAlgorithm::name)
+        .chain([signing::SIGNATURE_EXTENSION])
+        .any(|extension| {
+            key.rsplit_once('.')
+                .is_some_and(|(_, suffix)| suffix == extension)
+        });
+    !sidecar && (key.ends_with(".zip") || artifacts::is_artifact_key(key))
+}
+
+fn error_response(error: &BeepkgError) -> Response<Body> {
+    let status = match error {
+        BeepkgError::NotFound(_) => StatusCode::NOT_FOUND,
+        BeepkgError::Auth(_)
+        | BeepkgError::AccessDenied(_)
+        | BeepkgError::Network(_)
+        | BeepkgError::Http(_)
+        | BeepkgError::ChecksumMismatch(_) => StatusCode::BAD_GATEWAY,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    text(status, &error.to_string())
+}
+
+fn text(status: StatusCode, message: &str) -> Response<Body> {
+    let body = Full::new(Bytes::from(message.to_string()))
+        .map_err(|never| match never {})
+        .boxed();
+    let mut response = Response::new(body);
+    *response.status_mut() = status;
+    response
+}
@@ -0,0 +1,71 @@
+use crate::models::{self, RegistryMetadata};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// 包所属的命名空间：作用域包为 @<scope>，未分作用域的包各自是一个命名空间
+pub fn namespace_of(name: &str) -> String {
+    match models::package_scope(name) {
+        Some(scope) => format!("@{}", scope),
+        None => name.to_string(),
+    }
+}
+
+/// 命名空间的总字节数上限，作用域的设置优先于注册表的默认值
+pub fn namespace_limit(registry: &RegistryMetadata, namespace: &str) -> Option<u64> {
+    namespace
+        .strip_prefix('@')
+        .and_then(|scope| registry.scopes.get(scope))
+        .and_then(|settings| settings.max_bytes)
+        .or(registry.quota.max_namespace_bytes)
+}
+
+/// 一个命名空间的存储用量
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NamespaceUsage {
+    pub namespace: String,
+    /// 包文件和产物占用的字节数
+    pub used: u64,
+    pub limit: Option<u64>,
+    /// 版本数
+    pub versions: usize,
+}
+
+impl NamespaceUsage {
+    pub fn exceeded(&self) -> bool {
+        self.limit.is_some_and(|limit| self.used > limit)
+    }
+}
+
+/// 按命名空间汇总各版本（包名，字节数）的用量，按用量从多到少排列。
+/// 设置了上限但还没有包的作用域也会列出
+pub fn summarize<'a>(
+    versions: impl IntoIterator<Item = (&'a str, u64)>,
+    registry: &RegistryMetadata,
+) -> Vec<NamespaceUsage> {
+    let mut namespaces: BTreeMap<String, NamespaceUsage> = BTreeMap::new();
+    for (scope, settings) in &registry.scopes {
+        if settings.max_bytes.is_some() {
+            namespaces.entry(format!("@{}", scope)).or_default();
+        }
+    }
+    for (name, size) in versions {
+        let usage = namespaces.entry(namespace_of(name)).or_default();
+        usage.used += size;
+        usage.versions += 1;
+    }
+
+    let mut summaries: Vec<NamespaceUsage> = namespaces
+        .into_iter()
+        .map(|(namespace, mut usage)| {
+            usage.limit = namespace_limit(registry, &namespace);
+            usage.namespace = namespace;
+            usage
+        })
+        .collect();
+    summaries.sort_by(|a, b| {
+        b.used
+            .cmp(&a.used)
+            .then_with(|| a.namespace.cmp(&b.namespace))
+    });
+    summaries
+}
@@ -0,0 +1,170 @@
+use std::io::{Read, Seek, SeekFrom};
+
+/// 没有声明 readme 时按顺序查找的包根目录文件名（不区分大小写）
+pub const README_NAMES: [&str; 4] = ["readme.md", "readme", "readme.txt", "readme.rst"];
+
+/// 归档末尾的 EOCD 记录最多 22 字节加 65535 字节的注释
+pub const TAIL_SIZE: u64 = 22 + 65535;
+
+const EOCD_SIGNATURE: [u8; 4] = [0x50, 0x4b, 0x05, 0x06];
+const CENTRAL_HEADER_SIGNATURE: [u8; 4] = [0x50, 0x4b, 0x01, 0x02];
+const LOCAL_HEADER_SIZE: u64 = 30;
+
+/// 选择归档中的 README：优先 pack.toml 中声明的路径，其次包根目录下的常见文件名
+pub fn find<'a>(
+    names: impl IntoIterator<Item = &'a str>,
+    declared: Option<&str>,
+) -> Option<String> {
+    let names: Vec<&str> = names.into_iter().collect();
+    if let Some(declared) = declared {
+        let declared = declared.trim_start_matches("./");
+        if names.contains(&declared) {
+            return Some(declared.to_string());
+        }
+    }
+    README_NAMES.iter().find_map(|candidate| {
+        names
+            .iter()
+            .find(|name| name.eq_ignore_ascii_case(candidate))
+            .map(|name| name.to_string())
+    })
+}
+
+/// 中央目录中的一项
+#[derive(Debug, Clone)]
+pub struct CentralEntry {
+    pub name: String,
+    /// 本地文件头在归档中的偏移
+    pub header_offset: u64,
+    pub compressed_size: u64,
+    /// 中央目录中的扩展字段长度，本地文件头的扩展字段通常与之相同
+    pub extra_len: u64,
+}
+
+impl CentralEntry {
+    /// 读取这一项预计需要的字节范围：本地文件头和压缩数据
+    pub fn range(&self, archive_len: u64) -> (u64, u64) {
+        let end = self.header_offset
+            + LOCAL_HEADER_SIZE
+            + self.name.len() as u64
+            + self.extra_len
+            + self.compressed_size;
+        (self.header_offset, end.min(archive_len))
+    }
+
+    /// 根据本地文件头计算这一项实际的结束位置
+    pub fn local_end(&self, header: &[u8]) -> Option<u64> {
+        let name_len = u16::from_le_bytes(header.get(26..28)?.try_into().ok()?) as u64;
+        let extra_len = u16::from_le_bytes(header.get(28..30)?.try_into().ok()?) as u64;
+        Some(self.header_offset + LOCAL_HEADER_SIZE + name_len + extra_len + self.compressed_size)
+    }
+}
+
+/// 从归档末尾的数据中找到中央目录的偏移和长度；不支持 ZIP64
+pub fn central_directory(tail: &[u8]) -> Option<(u64, u64)> {
+    let position = tail.windows(4).rposition(|w| w == EOCD_SIGNATURE)?;
+    let record = tail.get(position..position + 22)?;
+    let size = u32::from_le_bytes(record[12..16].try_into().ok()?);
+    let offset = u32::from_le_bytes(record[16..20].try_into().ok()?);
+    if offset == u32::MAX || size == u32::MAX {
+        return None;
+    }
+    Some((offset as u64, size as u64))
+}
+
+/// 解析中央目录，遇到无法识别的数据时停止
+pub fn parse_central_directory(data: &[u8]) -> Vec<CentralEntry> {
+    let u16_at = |offset: usize| u16::from_le_bytes([data[offset], data[offset + 1]]) as usize;
+    let u32_at =
+        |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as u64;
+
+    let mut entries = Vec::new();
+    let mut position = 0;
+    while position + 46 <= data.len() && data[position..position + 4] == CENTRAL_HEADER_SIGNATURE {
+        let name_len = u16_at(position + 28);
+        let extra_len = u16_at(position + 30);
+        let comment_len = u16_at(position + 32);
+        let Some(name) = data.get(position + 46..position + 46 + name_len) else {
+            break;
+        };
+        entries.push(CentralEntry {
+            name: String::from_utf8_lossy(name).to_string(),
+            header_offset: u32_at(position + 42),
+            compressed_size: u32_at(position + 20),
+            extra_len: extra_len as u64,
+        });
+        position += 46 + name_len + extra_len + comment_len;
+    }
+    entries
+}
+
+/// 只包含已下载片段的归档，供 zip 读取中央目录和单个文件；读取未下载的部分会出错
+#[derive(Debug, Default)]
+pub struct SparseArchive {
+    len: u64,
+    position: u64,
+    chunks: Vec<(u64, bytes::Bytes)>,
+}
+
+impl SparseArchive {
+    pub fn new(len: u64) -> Self {
+        SparseArchive {
+            len,
+            ..Default::default()
+        }
+    }
+
+    pub fn insert(&mut self, offset: u64, data: bytes::Bytes) {
+        self.chunks.push((offset, data));
+    }
+
+    /// [start, end) 是否已经完整下载
+    pub fn contains(&self, start: u64, end: u64) -> bool {
+        self.chunks
+            .iter()
+            .any(|(offset, data)| *offset <= start && end <= offset + data.len() as u64)
+    }
+
+    /// 已下载的 [start, end)
+    pub fn slice(&self, start: u64, end: u64) -> Option<&[u8]> {
+        self.chunks.iter().find_map(|(offset, data)| {
+            (*offset <= start && end <= offset + data.len() as u64)
+                .then(|| &data[(start - offset) as usize..(end - offset) as usize])
+        })
+    }
+}
+
+impl Read for SparseArchive {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.position >= self.len || buf.is_empty() {
+            return Ok(0);
+        }
+        let Some((offset, data)) = self.chunks.iter().find(|(offset, data)| {
+            *offset <= self.position && self.position < offset + data.len() as u64
+        }) else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!("byte {} of the archive was not downloaded", self.position),
+            ));
+        };
+        let start = (self.position - offset) as usize;
+        let count = buf.len().min(data.len() - start);
+        buf[..count].copy_from_slice(&data[start..start + count]);
+        self.position += count as u64;
+        Ok(count)
+    }
+}
+
+impl Seek for SparseArchive {
+    fn seek(&mut self, position: SeekFrom) -> std::io::Result<u64> {
+        let position = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        self.position = position.ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "seek before the start")
+        })?;
+        Ok(self.position)
+    }
+}
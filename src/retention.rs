@@ -0,0 +1,144 @@
+use crate::Result;
+use crate::models::{LockedPackage, PackageBackup, RetentionRule};
+use globset::Glob;
+use std::collections::BTreeMap;
+
+/// 版本被保留的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeepReason {
+    /// 最新的 N 个版本之一
+    Recent,
+    /// 发布频道指向的版本
+    Channel(String),
+    /// 锁定的版本
+    Locked,
+}
+
+impl std::fmt::Display for KeepReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeepReason::Recent => f.write_str("recent"),
+            KeepReason::Channel(channel) => write!(f, "channel {}", channel),
+            KeepReason::Locked => f.write_str("locked"),
+        }
+    }
+}
+
+/// 对单个版本的决定
+#[derive(Debug, Clone)]
+pub struct VersionDecision {
+    pub version: semver::Version,
+    /// 保留的原因，为空时删除
+    pub keep: Vec<KeepReason>,
+}
+
+impl VersionDecision {
+    pub fn is_deleted(&self) -> bool {
+        self.keep.is_empty()
+    }
+}
+
+/// 检查规则的包名模式：包名或 glob，例如 demo、@acme/*、*
+pub fn validate_pattern(pattern: &str) -> Result<()> {
+    Glob::new(pattern).map_err(|e| format!("Invalid package pattern {}: {}", pattern, e))?;
+    Ok(())
+}
+
+/// 包适用的规则：完全匹配的包名优先，其次是匹配的模式中最长（最具体）的一个
+pub fn rule_for<'a>(
+    rules: &'a BTreeMap<String, RetentionRule>,
+    name: &str,
+) -> Result<Option<(&'a str, &'a RetentionRule)>> {
+    if let Some((pattern, rule)) = rules.get_key_value(name) {
+        return Ok(Some((pattern.as_str(), rule)));
+    }
+    let mut best: Option<(&str, &RetentionRule)> = None;
+    for (pattern, rule) in rules {
+        let matcher = Glob::new(pattern)
+            .map_err(|e| format!("Invalid package pattern {}: {}", pattern, e))?
+            .compile_matcher();
+        if matcher.is_match(name) && best.is_none_or(|(other, _)| pattern.len() > other.len()) {
+            best = Some((pattern.as_str(), rule));
+        }
+    }
+    Ok(best)
+}
+
+/// 按规则决定包的每个版本是否保留，结果按版本从新到旧排列。
+/// channels 为该包的发布频道，未过期的锁才会保留版本
+pub fn plan(
+    name: &str,
+    versions: &[semver::Version],
+    rule: &RetentionRule,
+    channels: Option<&BTreeMap<String, String>>,
+    locks: &[LockedPackage],
+    now: chrono::DateTime<chrono::Utc>,
+) -> Vec<VersionDecision> {
+    let mut versions = versions.to_vec();
+    versions.sort_by(|a, b| b.cmp(a));
+    versions.dedup();
+
+    versions
+        .into_iter()
+        .enumerate()
+        .map(|(index, version)| {
+            let version_string = version.to_string();
+            let mut keep = Vec::new();
+            if index < rule.keep_last {
+                keep.push(KeepReason::Recent);
+            }
+            if rule.keep_channels {
+                keep.extend(
+                    channels
+                        .into_iter()
+                        .flatten()
+                        .filter(|(_, target)| **target == version_string)
+                        .map(|(channel, _)| KeepReason::Channel(channel.clone())),
+                );
+            }
+            let locked = locks.iter().any(|lock| {
+                lock.name == name && lock.version == version_string && !lock.is_expired(now)
+            });
+            if rule.keep_locked && locked {
+                keep.push(KeepReason::Locked);
+            }
+            VersionDecision { version, keep }
+        })
+        .collect()
+}
+
+/// 备份的清理规则：同一个包版本保留最新的 keep_last 个备份，以及 keep_days 天内的所有备份，
+/// 只设置其中一项时只按该项保留。返回需要删除的备份在 backups 中的下标；
+/// 时间戳无法解析的备份总是保留
+pub fn prune_backups(
+    backups: &[PackageBackup],
+    keep_last: Option<usize>,
+    keep_days: Option<u64>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Vec<usize> {
+    let cutoff = keep_days.map(|days| now - chrono::Duration::days(days as i64));
+    let mut by_package: BTreeMap<&str, Vec<(usize, chrono::DateTime<chrono::Utc>)>> =
+        BTreeMap::new();
+    for (index, backup) in backups.iter().enumerate() {
+        if let Ok(time) = chrono::DateTime::parse_from_rfc3339(&backup.timestamp) {
+            by_package
+                .entry(backup.original_path.as_str())
+                .or_default()
+                .push((index, time.with_timezone(&chrono::Utc)));
+        }
+    }
+
+    let mut pruned = Vec::new();
+    for mut entries in by_package.into_values() {
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.1));
+        for (position, (index, time)) in entries.into_iter().enumerate() {
+            let recent = keep_last.is_some_and(|keep| position < keep);
+            let young = cutoff.is_some_and(|cutoff| time >= cutoff);
+            if !recent && !young {
+                pruned.push(index);
+            }
+        }
+    }
+    pruned.sort_unstable();
+    pruned
+}
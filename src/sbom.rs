@@ -0,0 +1,282 @@
+use crate::Result;
+use crate::checksum::ChecksumAlgorithm;
+use crate::models::PackageMetadata;
+use serde_json::{Value, json};
+use std::path::Path;
+
+/// SBOM 文档格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SbomFormat {
+    CycloneDx,
+    Spdx,
+}
+
+impl SbomFormat {
+    pub const ALL: [SbomFormat; 2] = [SbomFormat::CycloneDx, SbomFormat::Spdx];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SbomFormat::CycloneDx => "cyclonedx",
+            SbomFormat::Spdx => "spdx",
+        }
+    }
+
+    /// 包文件对应的 SBOM 文件名，例如 demo-1.0.0.zip.cdx.json
+    pub fn sidecar_name(&self, archive_name: &str) -> String {
+        match self {
+            SbomFormat::CycloneDx => format!("{}.cdx.json", archive_name),
+            SbomFormat::Spdx => format!("{}.spdx.json", archive_name),
+        }
+    }
+}
+
+impl std::str::FromStr for SbomFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "cyclonedx" | "cdx" => Ok(SbomFormat::CycloneDx),
+            "spdx" => Ok(SbomFormat::Spdx),
+            other => Err(format!("Unknown SBOM format: {}", other)),
+        }
+    }
+}
+
+/// 包内文件及其 sha256 摘要
+pub struct FileEntry {
+    pub path: String,
+    pub sha256: String,
+}
+
+/// 列出包目录下的所有文件（与推送时打包的文件一致）
+pub fn file_inventory(package_dir: &Path) -> Result<Vec<FileEntry>> {
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(package_dir).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            let relative_path = entry.path().strip_prefix(package_dir)?;
+            files.push(FileEntry {
+                path: relative_path.to_string_lossy().replace('\\', "/"),
+                sha256: ChecksumAlgorithm::Sha256.digest(&std::fs::read(entry.path())?),
+            });
+        }
+    }
+    Ok(files)
+}
+
+/// 列出包文件中的所有文件，按路径排序
+pub fn archive_inventory<R: std::io::Read + std::io::Seek>(
+    zip: &mut zip::ZipArchive<R>,
+) -> Result<Vec<FileEntry>> {
+    let mut files = Vec::new();
+    for index in 0..zip.len() {
+        let mut file = zip.by_index(index)?;
+        if file.is_dir() {
+            continue;
+        }
+        let mut content = Vec::new();
+        std::io::Read::read_to_end(&mut file, &mut content)?;
+        files.push(FileEntry {
+            path: file.name().to_string(),
+            sha256: ChecksumAlgorithm::Sha256.digest(&content),
+        });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// 根据包元数据中的依赖（以及可选的文件清单）生成 SBOM 文档
+pub fn generate(format: SbomFormat, metadata: &PackageMetadata, files: &[FileEntry]) -> Value {
+    let mut dependencies: Vec<(&String, &String)> = metadata.dependencies.iter().collect();
+    dependencies.sort();
+
+    match format {
+        SbomFormat::CycloneDx => cyclonedx(metadata, &dependencies, files),
+        SbomFormat::Spdx => spdx(metadata, &dependencies, files),
+    }
+}
+
+fn purl(name: &str, version: &str) -> String {
+    format!("pkg:generic/{}@{}", name, version)
+}
+
+fn random_uuid() -> String {
+    let mut bytes = rand::random::<[u8; 16]>();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+fn cyclonedx(
+    metadata: &PackageMetadata,
+    dependencies: &[(&String, &String)],
+    files: &[FileEntry],
+) -> Value {
+    let root_ref = purl(&metadata.name, &metadata.version);
+
+    let mut components: Vec<Value> = dependencies
+        .iter()
+        .map(|(name, version)| {
+            json!({
+                "type": "library",
+                "bom-ref": purl(name, version),
+                "name": name,
+                "version": version,
+                "purl": purl(name, version),
+                "scope": "required",
+            })
+        })
+        .collect();
+    components.extend(files.iter().map(|file| {
+        json!({
+            "type": "file",
+            "bom-ref": format!("file:{}", file.path),
+            "name": file.path,
+            "hashes": [{ "alg": "SHA-256", "content": file.sha256 }],
+        })
+    }));
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "serialNumber": format!("urn:uuid:{}", random_uuid()),
+        "version": 1,
+        "metadata": {
+            "timestamp": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            "tools": {
+                "components": [{
+                    "type": "application",
+                    "name": "beepkg",
+                    "version": env!("CARGO_PKG_VERSION"),
+                }],
+            },
+            "component": {
+                "type": "library",
+                "bom-ref": root_ref,
+                "name": metadata.name,
+                "version": metadata.version,
+                "description": metadata.description,
+                "authors": [{ "name": metadata.author }],
+                "purl": root_ref,
+            },
+        },
+        "components": components,
+        "dependencies": [{
+            "ref": root_ref,
+            "dependsOn": dependencies
+                .iter()
+                .map(|(name, version)| purl(name, version))
+                .collect::<Vec<_>>(),
+        }],
+    })
+}
+
+// SPDX 标识只允许字母、数字、点和连字符
+fn spdx_id(kind: &str, value: &str) -> String {
+    let value: String = value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("SPDXRef-{}-{}", kind, value)
+}
+
+fn spdx(
+    metadata: &PackageMetadata,
+    dependencies: &[(&String, &String)],
+    files: &[FileEntry],
+) -> Value {
+    let root_id = spdx_id("Package", &metadata.name);
+
+    let mut packages = vec![json!({
+        "SPDXID": root_id,
+        "name": metadata.name,
+        "versionInfo": metadata.version,
+        "supplier": format!("Person: {}", metadata.author),
+        "description": metadata.description,
+        "downloadLocation": "NOASSERTION",
+        "filesAnalyzed": !files.is_empty(),
+        "externalRefs": [{
+            "referenceCategory": "PACKAGE-MANAGER",
+            "referenceType": "purl",
+            "referenceLocator": purl(&metadata.name, &metadata.version),
+        }],
+    })];
+    let mut relationships = vec![json!({
+        "spdxElementId": "SPDXRef-DOCUMENT",
+        "relationshipType": "DESCRIBES",
+        "relatedSpdxElement": root_id,
+    })];
+
+    for (name, version) in dependencies {
+        let id = spdx_id("Dependency", name);
+        packages.push(json!({
+            "SPDXID": id,
+            "name": name,
+            "versionInfo": version,
+            "downloadLocation": "NOASSERTION",
+            "filesAnalyzed": false,
+            "externalRefs": [{
+                "referenceCategory": "PACKAGE-MANAGER",
+                "referenceType": "purl",
+                "referenceLocator": purl(name, version),
+            }],
+        }));
+        relationships.push(json!({
+            "spdxElementId": root_id,
+            "relationshipType": "DEPENDS_ON",
+            "relatedSpdxElement": id,
+        }));
+    }
+
+    let files: Vec<Value> = files
+        .iter()
+        .enumerate()
+        .map(|(index, file)| {
+            let id = format!("SPDXRef-File-{}", index + 1);
+            relationships.push(json!({
+                "spdxElementId": root_id,
+                "relationshipType": "CONTAINS",
+                "relatedSpdxElement": id,
+            }));
+            json!({
+                "SPDXID": id,
+                "fileName": format!("./{}", file.path),
+                "checksums": [{ "algorithm": "SHA256", "checksumValue": file.sha256 }],
+            })
+        })
+        .collect();
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": format!("{}@{}", metadata.name, metadata.version),
+        "documentNamespace": format!(
+            "https://spdx.org/spdxdocs/{}-{}-{}",
+            metadata.name,
+            metadata.version,
+            random_uuid()
+        ),
+        "creationInfo": {
+            "created": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            "creators": [format!("Tool: beepkg-{}", env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": packages,
+        "files": files,
+        "relationships": relationships,
+    })
+}
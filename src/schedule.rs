@@ -0,0 +1,129 @@
+use crate::Result;
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
+
+/// 向后查找下一次执行时间的范围，超过该范围的表达式（例如 2 月 30 日）视为永不执行
+const SEARCH_DAYS: i64 = 366 * 4;
+
+/// 五段式 cron 表达式（分 时 日 月 周），按 UTC 计算。
+/// 每段支持 *、数字、范围（1-5）、步长（*/15、0-30/10）和逗号分隔的列表，周日为 0 或 7
+#[derive(Debug, Clone)]
+pub struct CronSchedule {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days: Vec<bool>,
+    months: Vec<bool>,
+    weekdays: Vec<bool>,
+    // 日和周都被限定时，满足其中之一即可（与 cron 相同）
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "Invalid cron expression {:?}: expected 5 fields (minute hour day month weekday)",
+                expression
+            )
+            .into());
+        };
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        // 7 和 0 都表示周日
+        if weekdays[7] {
+            weekdays[0] = true;
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            weekdays,
+            days_restricted: day != "*",
+            weekdays_restricted: weekday != "*",
+        })
+    }
+
+    /// 该分钟是否匹配表达式
+    pub fn matches(&self, time: DateTime<Utc>) -> bool {
+        self.minutes[time.minute() as usize]
+            && self.hours[time.hour() as usize]
+            && self.months[time.month() as usize]
+            && self.day_matches(time)
+    }
+
+    /// after 之后（不含）的下一次执行时间
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let end = start + Duration::days(SEARCH_DAYS);
+        let mut time = start;
+        while time < end {
+            // 整天或整小时不匹配时直接跳过，避免逐分钟检查
+            if !self.months[time.month() as usize] || !self.day_matches(time) {
+                time = (time + Duration::days(1)).with_hour(0)?.with_minute(0)?;
+                continue;
+            }
+            if !self.hours[time.hour() as usize] {
+                time = (time + Duration::hours(1)).with_minute(0)?;
+                continue;
+            }
+            if self.minutes[time.minute() as usize] {
+                return Some(time);
+            }
+            time += Duration::minutes(1);
+        }
+        None
+    }
+
+    /// 自 since（上次执行或创建计划的时间）以来是否到过执行时间
+    pub fn is_due(&self, since: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        self.next_after(since).is_some_and(|next| next <= now)
+    }
+
+    fn day_matches(&self, time: DateTime<Utc>) -> bool {
+        let day = self.days[time.day() as usize];
+        let weekday = self.weekdays[time.weekday().num_days_from_sunday() as usize];
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+}
+
+// 解析一段表达式，返回按数值索引的匹配表
+fn parse_field(field: &str, min: u32, max: u32) -> Result<Vec<bool>> {
+    let invalid = || {
+        format!(
+            "Invalid cron field {:?} (allowed values {}-{})",
+            field, min, max
+        )
+    };
+    let mut allowed = vec![false; max as usize + 1];
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (
+                    start.parse::<u32>().map_err(|_| invalid())?,
+                    end.parse::<u32>().map_err(|_| invalid())?,
+                ),
+                // 带步长的单个值（5/15）表示从该值到最大值
+                None => {
+                    let start = range.parse::<u32>().map_err(|_| invalid())?;
+                    (start, if part.contains('/') { max } else { start })
+                }
+            },
+        };
+        if step == 0 || start < min || end > max || start > end {
+            return Err(invalid().into());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            allowed[value as usize] = true;
+        }
+    }
+    Ok(allowed)
+}
@@ -0,0 +1,80 @@
+use crate::error::BeepkgError;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+/// 把文档从一个格式版本升级到下一个版本
+pub type Migration = fn(&mut Map<String, Value>);
+
+/// 带格式版本（schema_version 字段）的元数据文档。
+/// 没有 schema_version 的文档视为版本 0，读取时依次执行迁移升级到当前版本
+pub trait Versioned: DeserializeOwned {
+    /// 文档名称，用于错误信息
+    const DOCUMENT: &'static str;
+    /// 当前格式版本
+    const VERSION: u32;
+    /// 第 i 项把版本 i 的文档升级到版本 i + 1，长度等于 VERSION
+    const MIGRATIONS: &'static [Migration];
+}
+
+/// 版本 0 是加入格式版本之前写入的文档，字段与版本 1 相同
+pub fn unversioned(_: &mut Map<String, Value>) {}
+
+/// 迁移过的文档
+#[derive(Debug, Clone, Serialize)]
+pub struct MigratedDocument {
+    /// 对象名或本地文件路径
+    pub key: String,
+    pub from: u32,
+    pub to: u32,
+}
+
+/// 文档的格式版本，没有 schema_version 时为 0
+pub fn version_of(value: &Value) -> u32 {
+    value
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .map_or(0, |version| version as u32)
+}
+
+/// 把文档升级到 T 的当前版本，返回原来的版本。比当前版本更新的文档无法读取
+pub fn upgrade<T: Versioned>(value: &mut Value) -> Result<u32, BeepkgError> {
+    let original = version_of(value);
+    if original > T::VERSION {
+        return Err(BeepkgError::MetadataParse(format!(
+            "{} schema version {} is newer than the supported version {}, upgrade beepkg",
+            T::DOCUMENT,
+            original,
+            T::VERSION
+        )));
+    }
+    let Value::Object(fields) = value else {
+        return Err(BeepkgError::MetadataParse(format!(
+            "{} must be an object",
+            T::DOCUMENT
+        )));
+    };
+    for migration in &T::MIGRATIONS[original as usize..] {
+        migration(fields);
+    }
+    fields.insert("schema_version".to_string(), T::VERSION.into());
+    Ok(original)
+}
+
+/// 读取 JSON 文档并升级到当前版本
+pub fn from_json<T: Versioned>(content: &[u8]) -> Result<T, BeepkgError> {
+    let value = serde_json::from_slice(content)?;
+    from_value(value)
+}
+
+/// 读取 TOML 文档并升级到当前版本
+pub fn from_toml<T: Versioned>(content: &str) -> Result<T, BeepkgError> {
+    let value = toml::from_str(content)?;
+    from_value(value)
+}
+
+fn from_value<T: Versioned>(mut value: Value) -> Result<T, BeepkgError> {
+    upgrade::<T>(&mut value)?;
+    serde_json::from_value(value)
+        .map_err(|e| BeepkgError::MetadataParse(format!("Invalid {}: {}", T::DOCUMENT, e)))
+}
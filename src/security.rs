@@ -1,15 +1,43 @@
+use crate::models::KmsConfig;
+#[cfg(feature = "encryption")]
 use aes_gcm::{
     Aes256Gcm, Nonce,
-    aead::{Aead, KeyInit, OsRng},
+    aead::{
+        Aead, KeyInit, Payload,
+        generic_array::GenericArray,
+        stream::{DecryptorBE32, EncryptorBE32},
+    },
 };
-use argon2::{
-    Argon2,
-    password_hash::{PasswordHasher, SaltString},
-};
-use base64::{Engine as _, engine::general_purpose};
+#[cfg(feature = "encryption")]
+use age::secrecy::ExposeSecret;
+#[cfg(feature = "encryption")]
+use argon2::Argon2;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
 use std::env;
+use std::io::{Read, Write};
 use thiserror::Error;
 
+// 加密包的格式头
+const MAGIC: &[u8] = b"BEEPKG";
+const FORMAT_VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+#[cfg(feature = "encryption")]
+const HEADER_LEN: usize = MAGIC.len() + 1 + SALT_LEN + NONCE_LEN;
+const ENVELOPE_FORMAT_VERSION: u8 = 2;
+// 流式格式：STREAM 结构的 nonce 前缀长度（12 字节 nonce 中另有 4 字节计数器和 1 字节末块标记）
+const STREAM_FORMAT_VERSION: u8 = 3;
+// 流式格式，密文头中记录密钥派生参数
+const KDF_STREAM_FORMAT_VERSION: u8 = 4;
+const STREAM_NONCE_LEN: usize = 7;
+const KDF_PARAMS_LEN: usize = 1 + 3 * 4;
+const STREAM_HEADER_LEN: usize = MAGIC.len() + 1 + KDF_PARAMS_LEN + SALT_LEN + STREAM_NONCE_LEN;
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+const TAG_LEN: usize = 16;
+// age 文件格式头
+const AGE_MAGIC: &[u8] = b"age-encryption.org/v1\n";
+
 #[derive(Error, Debug)]
 pub enum SecurityError {
     #[error("Environment variable BEEPKG_USER_SECRET not set")]
@@ -20,8 +48,298 @@ pub enum SecurityError {
     DecryptionFailed(String),
     #[error("Password hashing failed: {0}")]
     HashingFailed(String),
+    #[error("Secret unavailable: {0}")]
+    SecretUnavailable(String),
+}
+
+// 系统密钥环中保存口令使用的服务名
+const KEYRING_SERVICE: &str = "beepkg";
+
+/// 加密口令的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SecretSource {
+    /// BEEPKG_USER_SECRET 环境变量
+    #[default]
+    Env,
+    /// 系统密钥环（macOS Keychain、Windows 凭据管理器、Secret Service）
+    Keyring,
+    /// 在终端交互输入，不回显
+    Prompt,
+}
+
+impl SecretSource {
+    /// 从 BEEPKG_SECRET_SOURCE 读取口令来源，未设置时使用环境变量
+    pub fn from_env() -> Result<Self, SecurityError> {
+        match env::var("BEEPKG_SECRET_SOURCE") {
+            Ok(value) => value.parse().map_err(SecurityError::SecretUnavailable),
+            Err(_) => Ok(SecretSource::Env),
+        }
+    }
+
+    /// 读取口令
+    pub fn read(&self) -> Result<String, SecurityError> {
+        match self {
+            SecretSource::Env => {
+                env::var("BEEPKG_USER_SECRET").map_err(|_| SecurityError::MissingSecret)
+            }
+            SecretSource::Keyring => keyring_entry()?.get_password().map_err(|e| {
+                SecurityError::SecretUnavailable(format!(
+                    "keyring: {} (store one with `beepkg secret store`)",
+                    e
+                ))
+            }),
+            SecretSource::Prompt => rpassword::prompt_password("Package passphrase: ")
+                .map_err(|e| SecurityError::SecretUnavailable(format!("prompt: {}", e))),
+        }
+    }
+}
+
+impl std::str::FromStr for SecretSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "env" => Ok(SecretSource::Env),
+            "keyring" => Ok(SecretSource::Keyring),
+            "prompt" => Ok(SecretSource::Prompt),
+            other => Err(format!(
+                "Unknown secret source: {} (expected env, keyring or prompt)",
+                other
+            )),
+        }
+    }
+}
+
+// 密钥环条目的账户名来自 BEEPKG_KEYRING_USER，默认 default
+fn keyring_entry() -> Result<keyring::Entry, SecurityError> {
+    let user = env::var("BEEPKG_KEYRING_USER").unwrap_or_else(|_| "default".to_string());
+    keyring::Entry::new(KEYRING_SERVICE, &user)
+        .map_err(|e| SecurityError::SecretUnavailable(format!("keyring: {}", e)))
+}
+
+// 未启用 keyring 特性时代替 keyring crate，所有操作都返回错误
+#[cfg(not(feature = "keyring"))]
+mod keyring {
+    const DISABLED: &str = "support is not enabled in this build";
+
+    pub struct Entry;
+
+    impl Entry {
+        pub fn new(_service: &str, _user: &str) -> Result<Self, &'static str> {
+            Err(DISABLED)
+        }
+
+        pub fn get_password(&self) -> Result<String, &'static str> {
+            Err(DISABLED)
+        }
+
+        pub fn set_password(&self, _password: &str) -> Result<(), &'static str> {
+            Err(DISABLED)
+        }
+
+        pub fn delete_credential(&self) -> Result<(), &'static str> {
+            Err(DISABLED)
+        }
+    }
+}
+
+/// 将口令保存到系统密钥环
+pub fn store_keyring_secret(secret: &str) -> Result<(), SecurityError> {
+    keyring_entry()?
+        .set_password(secret)
+        .map_err(|e| SecurityError::SecretUnavailable(format!("keyring: {}", e)))
+}
+
+/// 从系统密钥环删除口令
+pub fn delete_keyring_secret() -> Result<(), SecurityError> {
+    keyring_entry()?
+        .delete_credential()
+        .map_err(|e| SecurityError::SecretUnavailable(format!("keyring: {}", e)))
+}
+
+/// 信封加密的元数据，随密文一起保存
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Envelope {
+    pub kms: KmsConfig,
+    /// KMS 封装后的数据密钥
+    pub wrapped_key: String,
+}
+
+/// Argon2id 密钥派生参数
+///
+/// 加密时写入密文头，解密时按密文头中的参数派生密钥，因此调整参数后旧的包仍能解密
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KdfParams {
+    /// Argon2 版本（16 即 0x10，19 即 0x13）
+    pub version: u32,
+    /// 内存开销，单位 KiB
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams {
+            version: 0x13,
+            // 与 argon2::Params 的默认值相同
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
 }
 
+impl KdfParams {
+    // 拒绝超过 4 GiB 的内存开销、64 次迭代和 64 个并行通道，防止被篡改的密文头耗尽内存或 CPU
+    #[cfg(feature = "encryption")]
+    const MAX_MEMORY_KIB: u32 = 4 * 1024 * 1024;
+    #[cfg(feature = "encryption")]
+    const MAX_ITERATIONS: u32 = 64;
+    #[cfg(feature = "encryption")]
+    const MAX_PARALLELISM: u32 = 64;
+
+    /// 检查参数是否能被 Argon2 接受
+    #[cfg(feature = "encryption")]
+    pub fn validate(&self) -> Result<(), SecurityError> {
+        self.argon2().map(|_| ())
+    }
+
+    #[cfg(not(feature = "encryption"))]
+    pub fn validate(&self) -> Result<(), SecurityError> {
+        Err(SecurityError::HashingFailed(DISABLED.to_string()))
+    }
+
+    #[cfg(feature = "encryption")]
+    fn argon2(&self) -> Result<Argon2<'static>, SecurityError> {
+        let version = argon2::Version::try_from(self.version).map_err(|_| {
+            SecurityError::HashingFailed(format!("unsupported Argon2 version {}", self.version))
+        })?;
+        if self.memory_kib > Self::MAX_MEMORY_KIB {
+            return Err(SecurityError::HashingFailed(format!(
+                "Argon2 memory cost {} KiB exceeds {} KiB",
+                self.memory_kib,
+                Self::MAX_MEMORY_KIB
+            )));
+        }
+        if self.iterations > Self::MAX_ITERATIONS {
+            return Err(SecurityError::HashingFailed(format!(
+                "Argon2 iterations {} exceed {}",
+                self.iterations,
+                Self::MAX_ITERATIONS
+            )));
+        }
+        if self.parallelism > Self::MAX_PARALLELISM {
+            return Err(SecurityError::HashingFailed(format!(
+                "Argon2 parallelism {} exceeds {}",
+                self.parallelism,
+                Self::MAX_PARALLELISM
+            )));
+        }
+        let params =
+            argon2::Params::new(self.memory_kib, self.iterations, self.parallelism, Some(32))
+                .map_err(|e| SecurityError::HashingFailed(e.to_string()))?;
+        Ok(Argon2::new(argon2::Algorithm::Argon2id, version, params))
+    }
+
+    // 密文头中的编码：版本 | 内存 | 迭代次数 | 并行度，整数均为大端序
+    #[cfg(feature = "encryption")]
+    fn encode(&self) -> [u8; KDF_PARAMS_LEN] {
+        let mut bytes = [0u8; KDF_PARAMS_LEN];
+        bytes[0] = self.version as u8;
+        bytes[1..5].copy_from_slice(&self.memory_kib.to_be_bytes());
+        bytes[5..9].copy_from_slice(&self.iterations.to_be_bytes());
+        bytes[9..13].copy_from_slice(&self.parallelism.to_be_bytes());
+        bytes
+    }
+
+    #[cfg(feature = "encryption")]
+    fn decode(bytes: &[u8]) -> Self {
+        let word = |start: usize| {
+            u32::from_be_bytes([
+                bytes[start],
+                bytes[start + 1],
+                bytes[start + 2],
+                bytes[start + 3],
+            ])
+        };
+        KdfParams {
+            version: bytes[0] as u32,
+            memory_kib: word(1),
+            iterations: word(5),
+            parallelism: word(9),
+        }
+    }
+}
+
+/// 推送时准备好的加密方式，选择性加密时包内所有匹配文件共用（只读取一次口令、只调用一次 KMS）
+pub enum Cipher {
+    Recipients(Vec<String>),
+    Envelope {
+        data_key: [u8; 32],
+        envelope: Envelope,
+    },
+    Secret {
+        secret: String,
+        kdf: KdfParams,
+    },
+}
+
+impl Cipher {
+    pub fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>, SecurityError> {
+        match self {
+            Cipher::Recipients(recipients) => {
+                SecurityManager::encrypt_to_recipients(recipients, data)
+            }
+            Cipher::Envelope { data_key, envelope } => {
+                SecurityManager::encrypt_envelope(data_key, envelope, data)
+            }
+            Cipher::Secret { secret, kdf } => {
+                let mut output = Vec::with_capacity(data.len() + 256);
+                SecurityManager::encrypt_stream(secret, kdf, data, &mut output)?;
+                Ok(output)
+            }
+        }
+    }
+}
+
+/// 选择性加密的文件匹配规则，路径相对于包根目录并使用 / 分隔
+///
+/// pack.toml 和 pack.json 始终保持明文，拉取时需要读取它们
+pub struct FileSelector(GlobSet);
+
+impl FileSelector {
+    pub fn new(patterns: &[String]) -> Result<Self, SecurityError> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            let glob = GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| {
+                    SecurityError::EncryptionFailed(format!(
+                        "invalid file pattern {}: {}",
+                        pattern, e
+                    ))
+                })?;
+            builder.add(glob);
+        }
+        builder
+            .build()
+            .map(FileSelector)
+            .map_err(|e| SecurityError::EncryptionFailed(e.to_string()))
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        let path = path.replace('\\', "/");
+        path != "pack.toml" && path != "pack.json" && self.0.is_match(&path)
+    }
+}
+
+// 信封 JSON、附加认证数据、nonce、密文
+type EnvelopeParts<'a> = (&'a [u8], &'a [u8], &'a [u8], &'a [u8]);
+
 pub struct SecurityManager;
 
 impl Default for SecurityManager {
@@ -35,76 +353,479 @@ impl SecurityManager {
         Self
     }
 
-    /// 从环境变量获取密码
-    fn get_secret() -> Result<String, SecurityError> {
-        env::var("BEEPKG_USER_SECRET").map_err(|_| SecurityError::MissingSecret)
+    /// 使用指定来源的口令加密数据
+    pub fn encrypt_data(source: SecretSource, data: &[u8]) -> Result<Vec<u8>, SecurityError> {
+        Self::encrypt_with_secret(&source.read()?, data)
+    }
+
+    /// 使用指定来源的口令解密数据
+    pub fn decrypt_data(source: SecretSource, data: &[u8]) -> Result<Vec<u8>, SecurityError> {
+        Self::decrypt_with_secret(&source.read()?, data)
+    }
+
+    /// 数据是否为加密后的包（以格式头开头）
+    pub fn is_encrypted(data: &[u8]) -> bool {
+        data.starts_with(MAGIC)
+    }
+
+    /// 数据是否为口令加密的包（整体加密或流式加密格式）
+    pub fn is_password_encrypted(data: &[u8]) -> bool {
+        data.len() > MAGIC.len()
+            && data.starts_with(MAGIC)
+            && matches!(
+                data[MAGIC.len()],
+                FORMAT_VERSION | STREAM_FORMAT_VERSION | KDF_STREAM_FORMAT_VERSION
+            )
+    }
+
+    /// 使用口令和默认的密钥派生参数加密内存中的数据，输出格式与 encrypt_stream 相同
+    pub fn encrypt_with_secret(secret: &str, data: &[u8]) -> Result<Vec<u8>, SecurityError> {
+        let chunks = data.len() / STREAM_CHUNK_SIZE + 1;
+        let mut output = Vec::with_capacity(STREAM_HEADER_LEN + data.len() + chunks * TAG_LEN);
+        Self::encrypt_stream(secret, &KdfParams::default(), data, &mut output)?;
+        Ok(output)
+    }
+
+    /// 使用口令解密内存中的数据
+    pub fn decrypt_with_secret(secret: &str, data: &[u8]) -> Result<Vec<u8>, SecurityError> {
+        let mut output = Vec::with_capacity(data.len());
+        Self::decrypt_stream(secret, data, &mut output)?;
+        Ok(output)
+    }
+
+    /// 生成随机的 256 位数据密钥，用于信封加密
+    pub fn generate_data_key() -> [u8; 32] {
+        rand::random::<[u8; 32]>()
+    }
+
+    /// 读取信封加密数据中的信封，数据不是信封格式时返回 None
+    pub fn read_envelope(data: &[u8]) -> Result<Option<Envelope>, SecurityError> {
+        match Self::split_envelope(data)? {
+            Some((envelope, _, _, _)) => serde_json::from_slice(envelope)
+                .map(Some)
+                .map_err(|e| SecurityError::DecryptionFailed(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    // 拆分信封格式：返回（信封 JSON，附加认证数据，nonce，密文）
+    fn split_envelope(data: &[u8]) -> Result<Option<EnvelopeParts<'_>>, SecurityError> {
+        let prefix_len = MAGIC.len() + 1;
+        if !Self::is_encrypted(data)
+            || data.len() < prefix_len
+            || data[MAGIC.len()] != ENVELOPE_FORMAT_VERSION
+        {
+            return Ok(None);
+        }
+
+        let truncated = || SecurityError::DecryptionFailed("truncated envelope".to_string());
+        let length_bytes: [u8; 4] = data
+            .get(prefix_len..prefix_len + 4)
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(truncated)?;
+        let envelope_start = prefix_len + 4;
+        let envelope_end = envelope_start + u32::from_be_bytes(length_bytes) as usize;
+        let nonce_end = envelope_end + NONCE_LEN;
+        if data.len() < nonce_end {
+            return Err(truncated());
+        }
+
+        Ok(Some((
+            &data[envelope_start..envelope_end],
+            &data[..envelope_end],
+            &data[envelope_end..nonce_end],
+            &data[nonce_end..],
+        )))
+    }
+
+    /// 数据是否为 age 格式（按接收者公钥加密）
+    pub fn is_age_encrypted(data: &[u8]) -> bool {
+        data.starts_with(AGE_MAGIC)
+    }
+}
+
+// 依赖 aes-gcm、argon2 和 age 的加密实现
+#[cfg(feature = "encryption")]
+impl SecurityManager {
+    /// 流式加密，输出格式：格式头 | 版本 4 | 密钥派生参数 | 盐值 | nonce 前缀 | 分块密文
+    ///
+    /// 明文按 64 KiB 分块，使用 STREAM 结构（nonce 前缀 + 块计数器 + 末块标记）逐块加密，
+    /// 截断、重排或替换分块都会导致解密失败，内存占用与包大小无关
+    pub fn encrypt_stream(
+        secret: &str,
+        kdf: &KdfParams,
+        mut reader: impl Read,
+        mut writer: impl Write,
+    ) -> Result<(), SecurityError> {
+        let io_error = |e: std::io::Error| SecurityError::EncryptionFailed(e.to_string());
+        let salt = rand::random::<[u8; SALT_LEN]>();
+        let nonce = rand::random::<[u8; STREAM_NONCE_LEN]>();
+        let cipher = Self::cipher(secret, &salt, kdf)
+            .map_err(|e| SecurityError::EncryptionFailed(e.to_string()))?;
+
+        let mut header = Vec::with_capacity(STREAM_HEADER_LEN);
+        header.extend_from_slice(MAGIC);
+        header.push(KDF_STREAM_FORMAT_VERSION);
+        header.extend_from_slice(&kdf.encode());
+        header.extend_from_slice(&salt);
+        header.extend_from_slice(&nonce);
+        writer.write_all(&header).map_err(io_error)?;
+
+        let mut encryptor = EncryptorBE32::from_aead(cipher, GenericArray::from_slice(&nonce));
+
+        // 预读下一块，以确定当前块是否为最后一块；格式头作为每块的附加认证数据
+        let mut current = vec![0u8; STREAM_CHUNK_SIZE];
+        let mut next = vec![0u8; STREAM_CHUNK_SIZE];
+        let mut filled = read_full(&mut reader, &mut current).map_err(io_error)?;
+        loop {
+            let next_filled = read_full(&mut reader, &mut next).map_err(io_error)?;
+            let payload = Payload {
+                msg: &current[..filled],
+                aad: &header,
+            };
+            if next_filled == 0 {
+                let ciphertext = encryptor
+                    .encrypt_last(payload)
+                    .map_err(|e| SecurityError::EncryptionFailed(e.to_string()))?;
+                writer.write_all(&ciphertext).map_err(io_error)?;
+                break;
+            }
+            let ciphertext = encryptor
+                .encrypt_next(payload)
+                .map_err(|e| SecurityError::EncryptionFailed(e.to_string()))?;
+            writer.write_all(&ciphertext).map_err(io_error)?;
+            std::mem::swap(&mut current, &mut next);
+            filled = next_filled;
+        }
+        writer.flush().map_err(io_error)
+    }
+
+    /// 流式解密 encrypt_stream 生成的数据，也支持旧的整体加密格式
+    ///
+    /// 解密失败时 writer 中可能已写入部分明文，调用方应丢弃输出
+    pub fn decrypt_stream(
+        secret: &str,
+        mut reader: impl Read,
+        mut writer: impl Write,
+    ) -> Result<(), SecurityError> {
+        let io_error = |e: std::io::Error| SecurityError::DecryptionFailed(e.to_string());
+        let corrupted =
+            || SecurityError::DecryptionFailed("wrong secret or corrupted archive".to_string());
+
+        let mut header = vec![0u8; MAGIC.len() + 1];
+        let filled = read_full(&mut reader, &mut header).map_err(io_error)?;
+        if filled < header.len() || !Self::is_encrypted(&header) {
+            return Err(SecurityError::DecryptionFailed(
+                "data is not a beepkg encrypted archive".to_string(),
+            ));
+        }
+
+        // 版本 3 的流式格式没有记录参数，使用当时的默认参数
+        let version = header[MAGIC.len()];
+        let kdf_len = match version {
+            KDF_STREAM_FORMAT_VERSION => KDF_PARAMS_LEN,
+            STREAM_FORMAT_VERSION => 0,
+            FORMAT_VERSION => {
+                // 旧的整体加密格式只能读入内存后解密
+                let mut data = header;
+                reader.read_to_end(&mut data).map_err(io_error)?;
+                let plaintext = Self::decrypt_whole(secret, &data)?;
+                writer.write_all(&plaintext).map_err(io_error)?;
+                return writer.flush().map_err(io_error);
+            }
+            ENVELOPE_FORMAT_VERSION => {
+                return Err(SecurityError::DecryptionFailed(
+                    "archive uses KMS envelope encryption".to_string(),
+                ));
+            }
+            version => {
+                return Err(SecurityError::DecryptionFailed(format!(
+                    "unsupported encryption format version {}",
+                    version
+                )));
+            }
+        };
+
+        let prefix_len = header.len();
+        header.resize(prefix_len + kdf_len + SALT_LEN + STREAM_NONCE_LEN, 0);
+        let filled = read_full(&mut reader, &mut header[prefix_len..]).map_err(io_error)?;
+        if filled < header.len() - prefix_len {
+            return Err(corrupted());
+        }
+        let kdf = if kdf_len == 0 {
+            KdfParams::default()
+        } else {
+            KdfParams::decode(&header[prefix_len..prefix_len + kdf_len])
+        };
+        let salt_start = prefix_len + kdf_len;
+        let salt = &header[salt_start..salt_start + SALT_LEN];
+        let nonce = &header[salt_start + SALT_LEN..];
+
+        let cipher = Self::cipher(secret, salt, &kdf)
+            .map_err(|e| SecurityError::DecryptionFailed(e.to_string()))?;
+        let mut decryptor = DecryptorBE32::from_aead(cipher, GenericArray::from_slice(nonce));
+
+        let mut current = vec![0u8; STREAM_CHUNK_SIZE + TAG_LEN];
+        let mut next = vec![0u8; STREAM_CHUNK_SIZE + TAG_LEN];
+        let mut filled = read_full(&mut reader, &mut current).map_err(io_error)?;
+        loop {
+            let next_filled = read_full(&mut reader, &mut next).map_err(io_error)?;
+            let payload = Payload {
+                msg: &current[..filled],
+                aad: &header,
+            };
+            if next_filled == 0 {
+                let plaintext = decryptor.decrypt_last(payload).map_err(|_| corrupted())?;
+                writer.write_all(&plaintext).map_err(io_error)?;
+                break;
+            }
+            let plaintext = decryptor.decrypt_next(payload).map_err(|_| corrupted())?;
+            writer.write_all(&plaintext).map_err(io_error)?;
+            std::mem::swap(&mut current, &mut next);
+            filled = next_filled;
+        }
+        writer.flush().map_err(io_error)
     }
 
-    /// 加密数据
-    pub fn encrypt_data(data: &[u8]) -> Result<(String, String), SecurityError> {
-        let password = Self::get_secret()?;
+    // 解密旧的整体加密格式：格式头 | 版本 1 | 盐值 | nonce | 密文
+    fn decrypt_whole(secret: &str, data: &[u8]) -> Result<Vec<u8>, SecurityError> {
+        if data.len() < HEADER_LEN {
+            return Err(SecurityError::DecryptionFailed(
+                "data is not a beepkg encrypted archive".to_string(),
+            ));
+        }
 
-        // 生成随机盐值
-        let salt = SaltString::generate(&mut OsRng);
+        let salt_start = MAGIC.len() + 1;
+        let nonce_start = salt_start + SALT_LEN;
+        let salt = &data[salt_start..nonce_start];
+        let nonce = Nonce::from_slice(&data[nonce_start..HEADER_LEN]);
+
+        let cipher = Self::cipher(secret, salt, &KdfParams::default())
+            .map_err(|e| SecurityError::DecryptionFailed(e.to_string()))?;
 
-        // 使用Argon2派生密钥
-        let argon2 = Argon2::default();
-        let key = argon2
-            .hash_password(password.as_bytes(), &salt)
-            .map_err(|e| SecurityError::HashingFailed(e.to_string()))?
-            .hash
-            .ok_or_else(|| SecurityError::HashingFailed("No hash generated".to_string()))?;
+        cipher
+            .decrypt(
+                nonce,
+                Payload {
+                    msg: &data[HEADER_LEN..],
+                    aad: &data[..salt_start],
+                },
+            )
+            .map_err(|_| {
+                SecurityError::DecryptionFailed("wrong secret or corrupted archive".to_string())
+            })
+    }
 
-        let key = key.as_bytes();
-        let cipher = Aes256Gcm::new_from_slice(key)
+    /// 使用数据密钥加密，输出格式：格式头 | 版本 2 | 信封长度 | 信封 JSON | nonce | 密文
+    ///
+    /// 信封记录 KMS 配置和被封装的数据密钥，作为附加认证数据防止被替换
+    pub fn encrypt_envelope(
+        data_key: &[u8],
+        envelope: &Envelope,
+        data: &[u8],
+    ) -> Result<Vec<u8>, SecurityError> {
+        let envelope = serde_json::to_vec(envelope)
             .map_err(|e| SecurityError::EncryptionFailed(e.to_string()))?;
+        let nonce_bytes = rand::random::<[u8; NONCE_LEN]>();
 
-        // 生成随机nonce
-        let nonce_bytes = rand::random::<[u8; 12]>();
-        let nonce = Nonce::from_slice(&nonce_bytes);
+        let mut output = Vec::with_capacity(data.len() + envelope.len() + 64);
+        output.extend_from_slice(MAGIC);
+        output.push(ENVELOPE_FORMAT_VERSION);
+        output.extend_from_slice(&(envelope.len() as u32).to_be_bytes());
+        output.extend_from_slice(&envelope);
+        let aad_len = output.len();
+        output.extend_from_slice(&nonce_bytes);
 
-        // 加密数据
+        let cipher = Aes256Gcm::new_from_slice(data_key)
+            .map_err(|e| SecurityError::EncryptionFailed(e.to_string()))?;
         let ciphertext = cipher
-            .encrypt(nonce, data)
+            .encrypt(
+                Nonce::from_slice(&nonce_bytes),
+                Payload {
+                    msg: data,
+                    aad: &output[..aad_len],
+                },
+            )
             .map_err(|e| SecurityError::EncryptionFailed(e.to_string()))?;
 
-        // 返回base64编码的加密数据和盐值
+        output.extend_from_slice(&ciphertext);
+        Ok(output)
+    }
+
+    /// 使用解封后的数据密钥解密
+    pub fn decrypt_envelope(data_key: &[u8], data: &[u8]) -> Result<Vec<u8>, SecurityError> {
+        let (_, aad, nonce, ciphertext) = Self::split_envelope(data)?.ok_or_else(|| {
+            SecurityError::DecryptionFailed("data is not envelope encrypted".to_string())
+        })?;
+
+        let cipher = Aes256Gcm::new_from_slice(data_key)
+            .map_err(|e| SecurityError::DecryptionFailed(e.to_string()))?;
+        cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad,
+                },
+            )
+            .map_err(|_| {
+                SecurityError::DecryptionFailed("wrong data key or corrupted archive".to_string())
+            })
+    }
+
+    /// 生成 age 密钥对，返回（私钥，公钥）
+    pub fn generate_age_identity() -> Result<(String, String), SecurityError> {
+        let identity = age::x25519::Identity::generate();
         Ok((
-            general_purpose::STANDARD.encode(ciphertext),
-            salt.to_string(),
+            identity.to_string().expose_secret().to_string(),
+            identity.to_public().to_string(),
         ))
     }
 
-    /// 解密数据
-    pub fn decrypt_data(encrypted: &str, salt: &str) -> Result<Vec<u8>, SecurityError> {
-        let password = Self::get_secret()?;
+    /// 检查 age 接收者公钥（age1...）是否有效
+    pub fn validate_recipients(recipients: &[String]) -> Result<(), SecurityError> {
+        Self::parse_recipients(recipients).map(|_| ())
+    }
+
+    // 解析 age 接收者公钥
+    fn parse_recipients(
+        recipients: &[String],
+    ) -> Result<Vec<age::x25519::Recipient>, SecurityError> {
+        recipients
+            .iter()
+            .map(|r| {
+                r.parse::<age::x25519::Recipient>().map_err(|e| {
+                    SecurityError::EncryptionFailed(format!("invalid recipient {}: {}", r, e))
+                })
+            })
+            .collect()
+    }
+
+    /// 使用接收者公钥（age1...）加密数据，任一接收者的私钥都可以解密
+    pub fn encrypt_to_recipients(
+        recipients: &[String],
+        data: &[u8],
+    ) -> Result<Vec<u8>, SecurityError> {
+        let recipients = Self::parse_recipients(recipients)?;
 
-        // 使用盐值派生密钥
-        let argon2 = Argon2::default();
-        let salt =
-            SaltString::from_b64(salt).map_err(|e| SecurityError::DecryptionFailed(e.to_string()))?;
+        let encryptor =
+            age::Encryptor::with_recipients(recipients.iter().map(|r| r as &dyn age::Recipient))
+                .map_err(|e| SecurityError::EncryptionFailed(e.to_string()))?;
+
+        let mut output = Vec::with_capacity(data.len() + 256);
+        let mut writer = encryptor
+            .wrap_output(&mut output)
+            .map_err(|e| SecurityError::EncryptionFailed(e.to_string()))?;
+        writer
+            .write_all(data)
+            .and_then(|_| writer.finish())
+            .map_err(|e| SecurityError::EncryptionFailed(e.to_string()))?;
+        Ok(output)
+    }
 
-        let key = argon2
-            .hash_password(password.as_bytes(), &salt)
+    /// 使用 age 身份文件内容（可包含多个私钥）解密数据
+    pub fn decrypt_with_identities(
+        identities: &str,
+        data: &[u8],
+    ) -> Result<Vec<u8>, SecurityError> {
+        let identities = age::IdentityFile::from_buffer(identities.as_bytes())
             .map_err(|e| SecurityError::DecryptionFailed(e.to_string()))?
-            .hash
-            .ok_or_else(|| SecurityError::DecryptionFailed("No hash generated".to_string()))?;
+            .into_identities()
+            .map_err(|e| SecurityError::DecryptionFailed(e.to_string()))?;
 
-        let key = key.as_bytes();
-        let cipher = Aes256Gcm::new_from_slice(key)
+        let decryptor = age::Decryptor::new(data)
+            .map_err(|e| SecurityError::DecryptionFailed(e.to_string()))?;
+        let mut reader = decryptor
+            .decrypt(identities.iter().map(|i| i.as_ref() as &dyn age::Identity))
             .map_err(|e| SecurityError::DecryptionFailed(e.to_string()))?;
 
-        // 解码base64数据
-        let ciphertext = general_purpose::STANDARD
-            .decode(encrypted)
+        let mut output = Vec::with_capacity(data.len());
+        reader
+            .read_to_end(&mut output)
             .map_err(|e| SecurityError::DecryptionFailed(e.to_string()))?;
+        Ok(output)
+    }
 
-        // 使用固定nonce (实际应用中应该存储nonce)
-        let nonce = Nonce::from_slice(&[0; 12]);
+    // 使用 Argon2id 从密码和盐值派生 AES-256 密钥
+    fn cipher(secret: &str, salt: &[u8], kdf: &KdfParams) -> Result<Aes256Gcm, SecurityError> {
+        let mut key = [0u8; 32];
+        kdf.argon2()?
+            .hash_password_into(secret.as_bytes(), salt, &mut key)
+            .map_err(|e| SecurityError::HashingFailed(e.to_string()))?;
 
-        // 解密数据
-        cipher
-            .decrypt(nonce, ciphertext.as_ref())
-            .map_err(|e| SecurityError::DecryptionFailed(e.to_string()))
+        Aes256Gcm::new_from_slice(&key).map_err(|e| SecurityError::HashingFailed(e.to_string()))
+    }
+}
+
+// 未启用 encryption 特性时没有加密实现，加密和解密都返回错误
+#[cfg(not(feature = "encryption"))]
+const DISABLED: &str = "encryption support is not enabled in this build";
+
+#[cfg(not(feature = "encryption"))]
+impl SecurityManager {
+    pub fn encrypt_stream(
+        _secret: &str,
+        _kdf: &KdfParams,
+        _reader: impl Read,
+        _writer: impl Write,
+    ) -> Result<(), SecurityError> {
+        Err(SecurityError::EncryptionFailed(DISABLED.to_string()))
+    }
+
+    pub fn decrypt_stream(
+        _secret: &str,
+        _reader: impl Read,
+        _writer: impl Write,
+    ) -> Result<(), SecurityError> {
+        Err(SecurityError::DecryptionFailed(DISABLED.to_string()))
+    }
+
+    pub fn encrypt_envelope(
+        _data_key: &[u8],
+        _envelope: &Envelope,
+        _data: &[u8],
+    ) -> Result<Vec<u8>, SecurityError> {
+        Err(SecurityError::EncryptionFailed(DISABLED.to_string()))
+    }
+
+    pub fn decrypt_envelope(_data_key: &[u8], _data: &[u8]) -> Result<Vec<u8>, SecurityError> {
+        Err(SecurityError::DecryptionFailed(DISABLED.to_string()))
+    }
+
+    pub fn generate_age_identity() -> Result<(String, String), SecurityError> {
+        Err(SecurityError::EncryptionFailed(DISABLED.to_string()))
+    }
+
+    pub fn validate_recipients(_recipients: &[String]) -> Result<(), SecurityError> {
+        Err(SecurityError::EncryptionFailed(DISABLED.to_string()))
+    }
+
+    pub fn encrypt_to_recipients(
+        _recipients: &[String],
+        _data: &[u8],
+    ) -> Result<Vec<u8>, SecurityError> {
+        Err(SecurityError::EncryptionFailed(DISABLED.to_string()))
+    }
+
+    pub fn decrypt_with_identities(
+        _identities: &str,
+        _data: &[u8],
+    ) -> Result<Vec<u8>, SecurityError> {
+        Err(SecurityError::DecryptionFailed(DISABLED.to_string()))
+    }
+}
+
+// 尽量读满缓冲区，返回读取的字节数，小于缓冲区长度表示已读到末尾
+#[cfg(feature = "encryption")]
+fn read_full(reader: &mut impl Read, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
     }
+    Ok(filled)
 }
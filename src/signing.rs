@@ -0,0 +1,203 @@
+use crate::Result;
+#[cfg(feature = "encryption")]
+use base64::{Engine as _, engine::general_purpose};
+#[cfg(feature = "encryption")]
+use ed25519_dalek::{Signature, Signer as _};
+#[cfg(feature = "encryption")]
+pub use ed25519_dalek::{SigningKey, VerifyingKey};
+#[cfg(feature = "encryption")]
+use rand::rngs::OsRng;
+use std::path::Path;
+
+/// 签名文件扩展名，例如 demo-1.0.0.zip.sig
+pub const SIGNATURE_EXTENSION: &str = "sig";
+
+#[cfg(feature = "encryption")]
+const SCHEME: &str = "ed25519";
+#[cfg(feature = "encryption")]
+const SECRET_SCHEME: &str = "ed25519-secret";
+
+/// 生成新的密钥对，返回 (私钥文件内容, 公钥文件内容)
+#[cfg(feature = "encryption")]
+pub fn generate_keypair() -> Result<(String, String)> {
+    let signing_key = SigningKey::generate(&mut OsRng);
+    let secret = format!(
+        "{}:{}",
+        SECRET_SCHEME,
+        general_purpose::STANDARD.encode(signing_key.to_bytes())
+    );
+    Ok((secret, encode_public_key(&signing_key.verifying_key())))
+}
+
+/// 公钥的文本形式：ed25519:<base64>
+#[cfg(feature = "encryption")]
+pub fn encode_public_key(key: &VerifyingKey) -> String {
+    format!(
+        "{}:{}",
+        SCHEME,
+        general_purpose::STANDARD.encode(key.as_bytes())
+    )
+}
+
+#[cfg(feature = "encryption")]
+pub fn parse_public_key(value: &str) -> Result<VerifyingKey> {
+    let encoded = value
+        .trim()
+        .strip_prefix("ed25519:")
+        .ok_or_else(|| format!("Not an ed25519 public key: {}", value.trim()))?;
+    let bytes: [u8; 32] = general_purpose::STANDARD
+        .decode(encoded)?
+        .try_into()
+        .map_err(|_| "Invalid ed25519 public key length")?;
+    Ok(VerifyingKey::from_bytes(&bytes)?)
+}
+
+/// 从文件读取签名私钥
+#[cfg(feature = "encryption")]
+pub fn load_signing_key(path: &Path) -> Result<SigningKey> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read signing key {}: {}", path.display(), e))?;
+    let encoded = content
+        .trim()
+        .strip_prefix("ed25519-secret:")
+        .ok_or_else(|| format!("{} is not an ed25519 secret key", path.display()))?;
+    let bytes: [u8; 32] = general_purpose::STANDARD
+        .decode(encoded)?
+        .try_into()
+        .map_err(|_| "Invalid ed25519 secret key length")?;
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+/// 读取受信任公钥，参数为逗号分隔的公钥文件列表，每个文件可包含多行公钥（# 开头为注释）
+pub fn load_trusted_keys(paths: &str) -> Result<Vec<VerifyingKey>> {
+    let mut keys = Vec::new();
+    for path in paths.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read trusted key file {}: {}", path, e))?;
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            keys.push(parse_public_key(line)?);
+        }
+    }
+    Ok(keys)
+}
+
+/// 对消息签名，返回签名文件内容：ed25519:<公钥>:<签名>
+#[cfg(feature = "encryption")]
+pub fn sign(key: &SigningKey, message: &[u8]) -> String {
+    let signature = key.sign(message);
+    format!(
+        "{}:{}",
+        encode_public_key(&key.verifying_key()),
+        general_purpose::STANDARD.encode(signature.to_bytes())
+    )
+}
+
+/// 使用受信任公钥验证签名文件，成功时返回签名所用的公钥
+#[cfg(feature = "encryption")]
+pub fn verify(sidecar: &str, message: &[u8], trusted: &[VerifyingKey]) -> Result<VerifyingKey> {
+    let (public_key, signature) = sidecar
+        .trim()
+        .rsplit_once(':')
+        .ok_or("Malformed signature file")?;
+    let key = parse_public_key(public_key)?;
+
+    if !trusted.contains(&key) {
+        return Err(format!(
+            "Package is signed by an untrusted key: {}",
+            encode_public_key(&key)
+        )
+        .into());
+    }
+
+    let signature = Signature::from_slice(&general_purpose::STANDARD.decode(signature)?)?;
+    key.verify_strict(message, &signature)
+        .map_err(|_| "Package signature verification failed")?;
+
+    Ok(key)
+}
+
+/// 签名私钥的公钥文本，用于说明签名者
+#[cfg(feature = "encryption")]
+pub fn public_key_of(key: &SigningKey) -> String {
+    encode_public_key(&key.verifying_key())
+}
+
+/// 推送时使用的签名后端
+pub enum SigningBackend {
+    /// 内置 ed25519 签名，私钥文件由 BEEPKG_SIGNING_KEY 指定
+    Ed25519(SigningKey),
+    /// 调用 gpg 签名，密钥由 BEEPKG_GPG_KEY 指定（可选）
+    Gpg { key_id: Option<String> },
+    /// 调用 cosign 进行 sigstore 无密钥签名，身份来自 CI 的 OIDC 令牌
+    Sigstore,
+}
+
+/// 根据环境变量选择签名后端（BEEPKG_SIGNING_BACKEND=ed25519|gpg|sigstore），未配置签名时返回 None
+pub fn configured_backend() -> Result<Option<SigningBackend>> {
+    let backend = std::env::var("BEEPKG_SIGNING_BACKEND").ok();
+    match backend.as_deref() {
+        Some("gpg") => Ok(Some(SigningBackend::Gpg {
+            key_id: std::env::var("BEEPKG_GPG_KEY").ok(),
+        })),
+        Some("sigstore") => Ok(Some(SigningBackend::Sigstore)),
+        Some("ed25519") | None => match std::env::var("BEEPKG_SIGNING_KEY") {
+            Ok(path) => Ok(Some(SigningBackend::Ed25519(load_signing_key(Path::new(
+                &path,
+            ))?))),
+            Err(_) if backend.is_some() => {
+                Err("BEEPKG_SIGNING_KEY must be set for the ed25519 signing backend".into())
+            }
+            Err(_) => Ok(None),
+        },
+        Some(other) => Err(format!("Unknown signing backend: {}", other).into()),
+    }
+}
+
+// 未启用 encryption 特性时没有 ed25519 实现：无法生成或读取密钥，因此不存在密钥值
+#[cfg(not(feature = "encryption"))]
+const DISABLED: &str = "ed25519 signing support is not enabled in this build";
+
+#[cfg(not(feature = "encryption"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyingKey {}
+
+#[cfg(not(feature = "encryption"))]
+pub enum SigningKey {}
+
+#[cfg(not(feature = "encryption"))]
+pub fn generate_keypair() -> Result<(String, String)> {
+    Err(DISABLED.into())
+}
+
+#[cfg(not(feature = "encryption"))]
+pub fn encode_public_key(key: &VerifyingKey) -> String {
+    match *key {}
+}
+
+#[cfg(not(feature = "encryption"))]
+pub fn parse_public_key(_value: &str) -> Result<VerifyingKey> {
+    Err(DISABLED.into())
+}
+
+#[cfg(not(feature = "encryption"))]
+pub fn load_signing_key(_path: &Path) -> Result<SigningKey> {
+    Err(DISABLED.into())
+}
+
+#[cfg(not(feature = "encryption"))]
+pub fn public_key_of(key: &SigningKey) -> String {
+    match *key {}
+}
+
+#[cfg(not(feature = "encryption"))]
+pub fn sign(key: &SigningKey, _message: &[u8]) -> String {
+    match *key {}
+}
+
+#[cfg(not(feature = "encryption"))]
+pub fn verify(_sidecar: &str, _message: &[u8], _trusted: &[VerifyingKey]) -> Result<VerifyingKey> {
+    Err(DISABLED.into())
+}
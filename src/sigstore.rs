@@ -0,0 +1,72 @@
+use crate::Result;
+use std::process::Stdio;
+use tokio::process::Command;
+
+/// sigstore 签名包（证书 + 签名 + Rekor 透明日志条目）的扩展名，例如 demo-1.0.0.zip.sigstore.json
+pub const BUNDLE_EXTENSION: &str = "sigstore.json";
+
+// cosign 可执行文件，可通过 BEEPKG_COSIGN_PROGRAM 指定
+fn cosign_command() -> Command {
+    let mut command = Command::new(
+        std::env::var("BEEPKG_COSIGN_PROGRAM").unwrap_or_else(|_| "cosign".to_string()),
+    );
+    command.stdin(Stdio::null());
+    command
+}
+
+/// 使用 cosign 进行无密钥签名（通过 OIDC 获取 Fulcio 证书，并记录到 Rekor），返回 bundle 内容
+pub async fn sign(message: &[u8]) -> Result<String> {
+    let dir = tempfile::tempdir()?;
+    let blob_path = dir.path().join("blob");
+    let bundle_path = dir.path().join("bundle.json");
+    std::fs::write(&blob_path, message)?;
+
+    let output = cosign_command()
+        .args(["sign-blob", "--yes", "--bundle"])
+        .arg(&bundle_path)
+        .arg(&blob_path)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run cosign: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "cosign signing failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    Ok(std::fs::read_to_string(&bundle_path)?)
+}
+
+/// 使用 cosign 验证 bundle：检查证书身份、OIDC 签发者以及 Rekor 透明日志中的记录。
+/// identity 须与证书中的身份完全一致，不作为正则表达式匹配
+pub async fn verify(bundle: &[u8], message: &[u8], identity: &str, issuer: &str) -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let blob_path = dir.path().join("blob");
+    let bundle_path = dir.path().join("bundle.json");
+    std::fs::write(&blob_path, message)?;
+    std::fs::write(&bundle_path, bundle)?;
+
+    let output = cosign_command()
+        .arg("verify-blob")
+        .arg("--bundle")
+        .arg(&bundle_path)
+        .args(["--certificate-identity", identity])
+        .args(["--certificate-oidc-issuer", issuer])
+        .arg(&blob_path)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run cosign: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "Sigstore verification failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,196 @@
+use crate::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// 静态索引：注册表中当前凭证可见的包，按名称排序
+#[derive(Debug, Clone, Serialize)]
+pub struct SiteIndex {
+    /// 生成索引的注册表（端点/bucket）
+    pub registry: String,
+    pub generated_at: String,
+    pub packages: Vec<SitePackage>,
+}
+
+/// 索引中的一个包，版本从新到旧排列
+#[derive(Debug, Clone, Serialize)]
+pub struct SitePackage {
+    pub name: String,
+    pub latest: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub channels: BTreeMap<String, String>,
+    pub versions: Vec<SiteVersion>,
+}
+
+/// 包的一个版本及其下载地址
+#[derive(Debug, Clone, Serialize)]
+pub struct SiteVersion {
+    pub version: String,
+    /// 包文件名
+    pub file: String,
+    /// 下载地址，公开 bucket 的对象地址或预签名地址
+    pub url: String,
+    pub size: u64,
+    pub created_at: String,
+    /// 校验和，格式为 <算法>:<十六进制>，没有校验文件时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub locked: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lock_reason: Option<String>,
+    /// 包内 pack.toml 中的元数据，只在生成时读取了包内容才有
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<SiteMetadata>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SiteMetadata {
+    pub author: String,
+    pub description: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependencies: BTreeMap<String, String>,
+}
+
+/// 把索引写入 output：根目录的 index.html、index.json 列出全部包，
+/// packages/<name>/ 下的 index.html、index.json 列出包的各版本。
+/// 页面之间使用相对链接，可以放在任意 Web 服务器的任意路径下。返回写入的文件数
+pub fn write(index: &SiteIndex, output: &Path) -> Result<usize> {
+    std::fs::create_dir_all(output)?;
+    std::fs::write(output.join("index.html"), render_index(index))?;
+    std::fs::write(
+        output.join("index.json"),
+        serde_json::to_string_pretty(index)?,
+    )?;
+
+    for package in &index.packages {
+        let dir = output.join("packages").join(&package.name);
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("index.html"), render_package(index, package))?;
+        std::fs::write(
+            dir.join("index.json"),
+            serde_json::to_string_pretty(package)?,
+        )?;
+    }
+    Ok(2 + index.packages.len() * 2)
+}
+
+/// 根目录的包列表页面
+pub fn render_index(index: &SiteIndex) -> String {
+    let mut body = format!(
+        "<h1>{}</h1>\n<p>{} packages &middot; <a href=\"index.json\">index.json</a></p>\n",
+        escape(&index.registry),
+        index.packages.len()
+    );
+    body.push_str("<table>\n<tr><th>Package</th><th>Latest</th><th>Description</th></tr>\n");
+    for package in &index.packages {
+        let description = package
+            .versions
+            .first()
+            .and_then(|version| version.metadata.as_ref())
+            .map(|metadata| metadata.description.as_str())
+            .unwrap_or_default();
+        let _ = writeln!(
+            body,
+            "<tr><td><a href=\"packages/{0}/\">{0}</a></td><td>{1}</td><td>{2}</td></tr>",
+            escape(&package.name),
+            escape(&package.latest),
+            escape(description)
+        );
+    }
+    body.push_str("</table>\n");
+    page(&index.registry, &body, &index.generated_at)
+}
+
+/// 包页面，版本链接带 #<算法>=<十六进制> 片段，与 PyPI simple 索引相同，客户端可以据此验证下载的文件
+pub fn render_package(index: &SiteIndex, package: &SitePackage) -> String {
+    // packages/<name>/ 或 packages/@scope/name/ 到根目录的相对路径
+    let root = "../".repeat(1 + package.name.split('/').count());
+    let mut body = format!(
+        "<p><a href=\"{}\">&larr; {}</a></p>\n<h1>{}</h1>\n<p>Latest: {} &middot; <a href=\"index.json\">index.json</a></p>\n",
+        root,
+        escape(&index.registry),
+        escape(&package.name),
+        escape(&package.latest)
+    );
+
+    if let Some(metadata) = package.versions.first().and_then(|v| v.metadata.as_ref()) {
+        if !metadata.description.is_empty() {
+            let _ = writeln!(body, "<p>{}</p>", escape(&metadata.description));
+        }
+        if !metadata.author.is_empty() {
+            let _ = writeln!(body, "<p>Author: {}</p>", escape(&metadata.author));
+        }
+    }
+
+    if !package.channels.is_empty() {
+        body.push_str("<h2>Channels</h2>\n<ul>\n");
+        for (channel, version) in &package.channels {
+            let _ = writeln!(body, "<li>{}: {}</li>", escape(channel), escape(version));
+        }
+        body.push_str("</ul>\n");
+    }
+
+    body.push_str("<h2>Versions</h2>\n<table>\n<tr><th>Version</th><th>Size</th><th>Published</th><th>Dependencies</th><th>Status</th></tr>\n");
+    for version in &package.versions {
+        let href = match version.checksum.as_deref().and_then(|c| c.split_once(':')) {
+            Some((algorithm, hex)) => format!("{}#{}={}", version.url, algorithm, hex),
+            None => version.url.clone(),
+        };
+        let dependencies = version
+            .metadata
+            .as_ref()
+            .map(|metadata| {
+                metadata
+                    .dependencies
+                    .iter()
+                    .map(|(name, requirement)| format!("{} {}", name, requirement))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default();
+        let status = match (&version.locked, &version.lock_reason) {
+            (true, Some(reason)) => format!("locked: {}", reason),
+            (true, None) => "locked".to_string(),
+            (false, _) => String::new(),
+        };
+        let _ = writeln!(
+            body,
+            "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(&href),
+            escape(&version.file),
+            version.size,
+            escape(&version.created_at),
+            escape(&dependencies),
+            escape(&status)
+        );
+    }
+    body.push_str("</table>\n");
+    page(&package.name, &body, &index.generated_at)
+}
+
+fn page(title: &str, body: &str, generated_at: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n{}<footer>Generated by beepkg at {}</footer>\n</body>\n</html>\n",
+        escape(title),
+        body,
+        escape(generated_at)
+    )
+}
+
+/// 转义 HTML 文本和属性值中的特殊字符
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
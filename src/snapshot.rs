@@ -0,0 +1,99 @@
+use crate::Result;
+use crate::artifacts;
+use crate::blobs;
+use crate::bundle;
+use crate::delta;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// 当前的快照格式版本
+pub const FORMAT_VERSION: u32 = 1;
+
+/// 清单在快照中的路径，总是第一个条目。对象与包集相同，保存在 objects/<key>
+pub const MANIFEST_PATH: &str = "snapshot.json";
+
+/// 注册表快照（tar + zstd）的清单：创建时 bucket 中的全部对象
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub format_version: u32,
+    pub created_at: String,
+    /// 创建快照时的注册表地址
+    pub source: String,
+    /// 是否包含包文件、产物、差量、备份和 blob 的内容
+    pub include_blobs: bool,
+    pub objects: Vec<SnapshotObject>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotObject {
+    pub key: String,
+    pub size: u64,
+    /// 快照中是否包含对象内容，不包含的对象恢复时必须已存在于目标 bucket 中
+    pub included: bool,
+}
+
+impl SnapshotManifest {
+    /// 清单中是否列出了该对象且包含其内容
+    pub fn contains(&self, key: &str) -> bool {
+        self.objects
+            .iter()
+            .any(|object| object.included && object.key == key)
+    }
+}
+
+/// 对象是否是内容对象：包文件、备份、产物、差量和内容寻址的 blob。
+/// 其余对象（注册表元数据、审计日志、校验和签名等附属文件）总是包含在快照中
+pub fn is_content_key(key: &str) -> bool {
+    key.ends_with(".zip")
+        || blobs::is_blob_key(key)
+        || delta::is_delta_key(key)
+        || artifacts::is_artifact_key(key)
+}
+
+/// 把快照解压到目录：第一个条目必须是清单，其余条目必须是清单中包含内容的对象，
+/// 对象保存在 `<dir>/objects/<key>`
+pub fn unpack(path: &Path, dir: &Path) -> Result<SnapshotManifest> {
+    let decoder = zstd::Decoder::new(File::open(path)?)
+        .map_err(|e| format!("Failed to open snapshot {}: {}", path.display(), e))?;
+    let mut archive = tar::Archive::new(decoder);
+    let mut entries = archive.entries()?;
+
+    let mut first = entries
+        .next()
+        .ok_or_else(|| format!("Snapshot {} is empty", path.display()))??;
+    if first.path()?.to_str() != Some(MANIFEST_PATH) {
+        return Err(format!(
+            "Snapshot {} does not start with {}",
+            path.display(),
+            MANIFEST_PATH
+        )
+        .into());
+    }
+    let manifest: SnapshotManifest = serde_json::from_reader(BufReader::new(&mut first))
+        .map_err(|e| format!("Invalid snapshot manifest: {}", e))?;
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(format!(
+            "Snapshot format version {} is newer than the supported version {}",
+            manifest.format_version, FORMAT_VERSION
+        )
+        .into());
+    }
+
+    for entry in entries {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.to_string_lossy().into_owned();
+        let key = entry_path
+            .strip_prefix(bundle::OBJECTS_DIR)
+            .and_then(|key| key.strip_prefix('/'))
+            .filter(|key| manifest.contains(key))
+            .ok_or_else(|| format!("Unexpected entry in snapshot: {}", entry_path))?;
+        // unpack_in 拒绝包含 .. 的路径
+        if !entry.unpack_in(dir)? {
+            return Err(format!("Refusing to unpack {} outside the target directory", key).into());
+        }
+    }
+
+    Ok(manifest)
+}
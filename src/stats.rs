@@ -0,0 +1,83 @@
+use crate::models::DownloadRecord;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// 下载统计的时间粒度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Period {
+    #[default]
+    Day,
+    /// ISO 周，例如 2024-W03
+    Week,
+    Month,
+}
+
+impl Period {
+    /// 时间戳（RFC 3339）所在的时间段，无法解析的时间戳归入 unknown
+    pub fn bucket(&self, timestamp: &str) -> String {
+        let Ok(time) = chrono::DateTime::parse_from_rfc3339(timestamp) else {
+            return "unknown".to_string();
+        };
+        let time = time.with_timezone(&chrono::Utc);
+        match self {
+            Period::Day => time.format("%Y-%m-%d").to_string(),
+            Period::Week => time.format("%G-W%V").to_string(),
+            Period::Month => time.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+impl std::str::FromStr for Period {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "day" => Ok(Period::Day),
+            "week" => Ok(Period::Week),
+            "month" => Ok(Period::Month),
+            other => Err(format!(
+                "Unknown period: {} (expected day, week or month)",
+                other
+            )),
+        }
+    }
+}
+
+/// 一个包的下载次数
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PackageDownloads {
+    pub name: String,
+    pub total: usize,
+    /// 版本 -> 下载次数
+    pub versions: BTreeMap<String, usize>,
+    /// 时间段 -> 下载次数，按时间顺序排列
+    pub periods: BTreeMap<String, usize>,
+    /// 不同客户端的数量
+    pub clients: usize,
+}
+
+/// 按包汇总下载记录，按下载次数从多到少排列
+pub fn summarize(records: &[DownloadRecord], period: Period) -> Vec<PackageDownloads> {
+    let mut packages: BTreeMap<&str, (PackageDownloads, BTreeSet<&str>)> = BTreeMap::new();
+    for record in records {
+        let (summary, clients) = packages.entry(&record.package).or_default();
+        summary.total += 1;
+        *summary.versions.entry(record.version.clone()).or_default() += 1;
+        *summary
+            .periods
+            .entry(period.bucket(&record.timestamp))
+            .or_default() += 1;
+        clients.insert(&record.client);
+    }
+
+    let mut summaries: Vec<PackageDownloads> = packages
+        .into_iter()
+        .map(|(name, (mut summary, clients))| {
+            summary.name = name.to_string();
+            summary.clients = clients.len();
+            summary
+        })
+        .collect();
+    summaries.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+    summaries
+}
@@ -0,0 +1,59 @@
+use crate::Result;
+use crate::metrics;
+use crate::models::{AuditRecord, WebhookConfig};
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use sha2::Sha256;
+use std::time::Duration;
+
+// 单个 webhook 的最大投递次数
+const MAX_ATTEMPTS: u32 = 3;
+
+/// 使用 HMAC-SHA256 对载荷签名，结果放在 X-Beepkg-Signature 头中
+pub fn sign_payload(secret: &str, payload: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(payload);
+    format!("sha256={:x}", mac.finalize().into_bytes())
+}
+
+/// 向单个 webhook 投递事件，失败时按指数退避重试。配置了 secret_env 时用该环境变量中的密钥签名
+pub async fn deliver(client: &Client, webhook: &WebhookConfig, record: &AuditRecord) -> Result<()> {
+    let payload = serde_json::to_vec(record)?;
+    let secret = match &webhook.secret_env {
+        Some(var) => Some(
+            std::env::var(var)
+                .map_err(|_| format!("Webhook secret variable {} is not set", var))?,
+        ),
+        None => None,
+    };
+    let mut last_error = String::new();
+
+    for attempt in 0..MAX_ATTEMPTS {
+        if attempt > 0 {
+            metrics::global().record_retry();
+            tokio::time::sleep(Duration::from_secs(1 << (attempt - 1))).await;
+        }
+
+        let mut request = client
+            .post(&webhook.url)
+            .header("Content-Type", "application/json")
+            .header("X-Beepkg-Event", record.action.to_string())
+            .body(payload.clone());
+        if let Some(secret) = &secret {
+            request = request.header("X-Beepkg-Signature", sign_payload(secret, &payload));
+        }
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => last_error = format!("HTTP {}", response.status()),
+            Err(e) => last_error = e.to_string(),
+        }
+    }
+
+    Err(format!(
+        "Webhook {} failed after {} attempts: {}",
+        webhook.url, MAX_ATTEMPTS, last_error
+    )
+    .into())
+}
@@ -0,0 +1,195 @@
+use crate::Result;
+use crate::models::PackageMetadata;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// 工作区配置文件名，位于仓库根目录
+pub const WORKSPACE_FILE: &str = "workspace.toml";
+
+/// 多包仓库的工作区
+///
+/// ```toml
+/// members = ["packages/core", "packages/cli"]
+/// excludes = ["**/*.log", "target/**"]
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Workspace {
+    /// 成员包目录，相对于工作区根目录
+    pub members: Vec<String>,
+    /// 所有成员打包时都排除的文件（相对于各成员目录的 glob）
+    #[serde(default)]
+    pub excludes: Vec<String>,
+}
+
+/// 工作区中的一个包
+#[derive(Debug)]
+pub struct WorkspaceMember {
+    pub path: PathBuf,
+    pub metadata: PackageMetadata,
+}
+
+impl WorkspaceMember {
+    pub fn spec(&self) -> String {
+        format!("{}@{}", self.metadata.name, self.metadata.version)
+    }
+}
+
+impl Workspace {
+    /// 读取根目录中的 workspace.toml
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join(WORKSPACE_FILE);
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        let workspace: Self = toml::from_str(&content)
+            .map_err(|e| format!("Invalid workspace file {}: {}", path.display(), e))?;
+        if workspace.members.is_empty() {
+            return Err(format!("{} lists no members", path.display()).into());
+        }
+        Ok(workspace)
+    }
+
+    /// 读取并校验所有成员的元数据，按依赖顺序返回：被依赖的成员排在前面。
+    /// 成员之间的依赖必须能由工作区中的版本满足，不允许循环依赖和重名
+    pub fn members(&self, root: &Path) -> Result<Vec<WorkspaceMember>> {
+        let mut members = Vec::new();
+        for member in &self.members {
+            let path = root.join(member);
+            let metadata = PackageMetadata::load(&path)
+                .map_err(|e| format!("Workspace member {}: {}", member, e))?;
+            metadata
+                .validate()
+                .map_err(|e| format!("Workspace member {}: {}", member, e))?;
+            if let Some(other) = members
+                .iter()
+                .find(|other: &&WorkspaceMember| other.metadata.name == metadata.name)
+            {
+                return Err(format!(
+                    "Package {} is defined by both {} and {}",
+                    metadata.name,
+                    other.path.display(),
+                    path.display()
+                )
+                .into());
+            }
+            members.push(WorkspaceMember { path, metadata });
+        }
+
+        check_versions(&members)?;
+        let order = publish_order(&members)?;
+        let mut members: Vec<Option<WorkspaceMember>> = members.into_iter().map(Some).collect();
+        Ok(order
+            .into_iter()
+            .filter_map(|index| members[index].take())
+            .collect())
+    }
+}
+
+// 成员之间的依赖需求必须匹配工作区中的版本，否则发布后依赖方无法解析
+fn check_versions(members: &[WorkspaceMember]) -> Result<()> {
+    let versions: HashMap<&str, &str> = members
+        .iter()
+        .map(|member| {
+            (
+                member.metadata.name.as_str(),
+                member.metadata.version.as_str(),
+            )
+        })
+        .collect();
+    for member in members {
+        for (name, requirement) in internal_dependencies(member, &versions) {
+            let version = semver::Version::parse(versions[name])?;
+            let matches = semver::VersionReq::parse(requirement)
+                .map(|req| req.matches(&version))
+                .unwrap_or_else(|_| requirement == versions[name]);
+            if !matches {
+                return Err(format!(
+                    "{} requires {} {}, but the workspace has {}",
+                    member.spec(),
+                    name,
+                    requirement,
+                    versions[name]
+                )
+                .into());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 成员的发布顺序（下标），依赖的成员排在依赖方之前，存在循环依赖时报错
+pub fn publish_order(members: &[WorkspaceMember]) -> Result<Vec<usize>> {
+    let versions: HashMap<&str, &str> = members
+        .iter()
+        .map(|member| {
+            (
+                member.metadata.name.as_str(),
+                member.metadata.version.as_str(),
+            )
+        })
+        .collect();
+    let index: HashMap<&str, usize> = members
+        .iter()
+        .enumerate()
+        .map(|(i, member)| (member.metadata.name.as_str(), i))
+        .collect();
+
+    // 0 未访问，1 访问中，2 已完成
+    let mut state = vec![0u8; members.len()];
+    let mut order = Vec::with_capacity(members.len());
+    for start in 0..members.len() {
+        let mut stack = vec![(start, false)];
+        while let Some((i, done)) = stack.pop() {
+            if done {
+                state[i] = 2;
+                order.push(i);
+                continue;
+            }
+            match state[i] {
+                2 => continue,
+                1 => {
+                    return Err(format!(
+                        "Workspace members have a dependency cycle involving {}",
+                        members[i].metadata.name
+                    )
+                    .into());
+                }
+                _ => {}
+            }
+            state[i] = 1;
+            stack.push((i, true));
+            let mut dependencies: Vec<usize> = internal_dependencies(&members[i], &versions)
+                .map(|(name, _)| index[name])
+                .collect();
+            dependencies.sort_unstable_by(|a, b| b.cmp(a));
+            for dependency in dependencies {
+                if state[dependency] == 1 {
+                    return Err(format!(
+                        "Workspace members have a dependency cycle involving {}",
+                        members[dependency].metadata.name
+                    )
+                    .into());
+                }
+                if state[dependency] == 0 {
+                    stack.push((dependency, false));
+                }
+            }
+        }
+    }
+    Ok(order)
+}
+
+// 成员对其他成员的依赖（包括可选依赖）
+fn internal_dependencies<'a>(
+    member: &'a WorkspaceMember,
+    versions: &'a HashMap<&str, &str>,
+) -> impl Iterator<Item = (&'a str, &'a str)> {
+    member
+        .metadata
+        .dependencies
+        .iter()
+        .chain(&member.metadata.optional_dependencies)
+        .filter(|(name, _)| versions.contains_key(name.as_str()))
+        .map(|(name, requirement)| (name.as_str(), requirement.as_str()))
+}
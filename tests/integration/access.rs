@@ -0,0 +1,69 @@
+use beepkg::models::{self, PackageAccess, PackageMetadata, Visibility};
+
+#[test]
+fn test_visibility_parsing() {
+    assert_eq!("public".parse::<Visibility>().unwrap(), Visibility::Public);
+    assert_eq!(
+        "team:payments".parse::<Visibility>().unwrap(),
+        Visibility::Team("payments".to_string())
+    );
+    assert!("team:".parse::<Visibility>().is_err());
+    assert!("internal".parse::<Visibility>().is_err());
+
+    let metadata: PackageMetadata = toml::from_str(
+        r#"
+        name = "billing"
+        version = "1.0.0"
+        author = "alice"
+        description = "billing library"
+        includes = []
+        excludes = []
+        visibility = "private"
+
+        [dependencies]
+        "#,
+    )
+    .unwrap();
+    assert_eq!(metadata.visibility, Some(Visibility::Private));
+}
+
+#[test]
+fn test_package_access_rules() {
+    let teams = vec!["payments".to_string()];
+
+    // 未记录访问控制的包为公开
+    assert!(PackageAccess::default().allows("bob", &[]));
+
+    let private = PackageAccess {
+        visibility: Visibility::Private,
+        owner: Some("alice".to_string()),
+        grants: vec!["carol".to_string(), "team:security".to_string()],
+    };
+    assert!(private.allows("alice", &[]));
+    assert!(private.allows("carol", &[]));
+    assert!(private.allows("dave", &["security".to_string()]));
+    assert!(!private.allows("bob", &teams));
+
+    let team = PackageAccess {
+        visibility: Visibility::Team("payments".to_string()),
+        owner: Some("alice".to_string()),
+        grants: Vec::new(),
+    };
+    assert!(team.allows("bob", &teams));
+    assert!(!team.allows("bob", &[]));
+}
+
+#[test]
+fn test_scoped_package_names() {
+    assert_eq!(
+        models::package_scope("@payments/billing-lib"),
+        Some("payments")
+    );
+    assert_eq!(models::package_scope("billing-lib"), None);
+
+    assert!(models::validate_package_name("@payments/billing-lib").is_ok());
+    assert!(models::validate_package_name("billing-lib").is_ok());
+    assert!(models::validate_package_name("payments/billing-lib").is_err());
+    assert!(models::validate_package_name("@payments").is_err());
+    assert!(models::validate_package_name("@payments/billing/lib").is_err());
+}
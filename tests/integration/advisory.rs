@@ -0,0 +1,70 @@
+use beepkg::advisory::{self, AdvisoryIndex};
+use semver::Version;
+
+#[test]
+fn test_resolved_version_matches_advisory() {
+    let index: AdvisoryIndex = serde_json::from_str(
+        r#"{
+            "advisories": [{
+                "id": "BEE-2024-0001",
+                "package": "zlib",
+                "affected": [">=1.2.0, <1.2.13"],
+                "fixed": ["1.2.13"],
+                "severity": "high",
+                "summary": "heap overflow in inflate"
+            }]
+        }"#,
+    )
+    .unwrap();
+    index.validate().unwrap();
+
+    let available = [
+        Version::new(1, 2, 11),
+        Version::new(1, 2, 12),
+        Version::new(1, 3, 0),
+    ];
+    let resolved = advisory::resolve("~1.2", &available, false).unwrap();
+    assert_eq!(resolved, Version::new(1, 2, 12));
+    assert_eq!(index.affecting("zlib", &resolved).len(), 1);
+
+    let fixed = advisory::resolve("1.3.0", &available, false).unwrap();
+    assert!(index.affecting("zlib", &fixed).is_empty());
+    assert!(index.affecting("openssl", &resolved).is_empty());
+}
+
+#[test]
+fn test_prereleases_excluded_unless_requested() {
+    let available = [Version::new(1, 9, 2), Version::parse("2.0.0-rc.1").unwrap()];
+    assert_eq!(
+        advisory::resolve("*", &available, false).unwrap(),
+        Version::new(1, 9, 2)
+    );
+    assert_eq!(
+        advisory::resolve(">=1.0", &available, false).unwrap(),
+        Version::new(1, 9, 2)
+    );
+    assert_eq!(
+        advisory::resolve("*", &available, true).unwrap(),
+        Version::parse("2.0.0-rc.1").unwrap()
+    );
+    // ^1.9 不包含 2.x 的预发布版本
+    assert_eq!(
+        advisory::resolve("^1.9", &available, true).unwrap(),
+        Version::new(1, 9, 2)
+    );
+    // 需求中明确指定预发布版本时照常匹配
+    assert_eq!(
+        advisory::resolve(">=2.0.0-rc.0", &available, false).unwrap(),
+        Version::parse("2.0.0-rc.1").unwrap()
+    );
+}
+
+#[test]
+fn test_advisory_affects_prereleases() {
+    let index: AdvisoryIndex = serde_json::from_str(
+        r#"{"advisories": [{"id": "BEE-1", "package": "zlib", "affected": [">=1.2.0, <1.2.13"]}]}"#,
+    )
+    .unwrap();
+    let prerelease = Version::parse("1.2.12-beta.1").unwrap();
+    assert_eq!(index.affecting("zlib", &prerelease).len(), 1);
+}
@@ -0,0 +1,140 @@
+use super::test_helpers::{MockBucket, write_package};
+use beepkg::error::BeepkgError;
+
+#[tokio::test]
+async fn test_protected_channel_push() {
+    let bucket = MockBucket::start().await;
+    let (alice, bob) = bucket.approvers();
+    let dir = tempfile::tempdir().unwrap();
+    write_package(dir.path(), "demo", "1.0.0");
+    alice.push_package(dir.path()).await.unwrap();
+
+    alice.set_channel_protected("stable", true).await.unwrap();
+    let settings = alice.registry_settings().await.unwrap();
+    assert_eq!(settings.protected_channels, ["stable"]);
+
+    write_package(dir.path(), "demo", "1.1.0");
+    let alice = alice.channel(Some("stable".to_string()));
+    alice.push_package(dir.path()).await.unwrap();
+
+    // 批准前新版本不可见，频道也不移动；包文件和附属文件暂存在 pending/ 下
+    assert!(bucket.object("demo-1.1.0.zip").is_none());
+    assert!(bucket.object("pending/demo-1.1.0.zip").is_some());
+    assert!(bucket.object("pending/demo-1.1.0.zip.sha256").is_some());
+    let pending = bob.pending_approvals().await.unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].requested_by, "alice");
+    let info = bob.package_info("demo").await.unwrap();
+    assert_eq!(info.version, "1.0.0");
+    let err = bob.package_info("demo@1.1.0").await.unwrap_err();
+    assert!(err.to_string().contains("awaiting approval"), "{}", err);
+    assert!(bob.list_channels("demo").await.unwrap().is_empty());
+
+    // 请求者不能批准自己的变更
+    let err = alice.approve("demo@1.1.0").await.unwrap_err();
+    assert!(matches!(err, BeepkgError::PolicyViolation(_)), "{}", err);
+
+    let approved = bob.approve("demo@1.1.0").await.unwrap();
+    assert_eq!(approved.len(), 1);
+    assert_eq!(approved[0].approved_by, "bob");
+    assert_eq!(approved[0].channel, "stable");
+    assert!(bob.pending_approvals().await.unwrap().is_empty());
+    assert!(bucket.object("demo-1.1.0.zip").is_some());
+    assert!(bucket.object("demo-1.1.0.zip.sha256").is_some());
+    assert!(bucket.object("pending/demo-1.1.0.zip").is_none());
+    assert!(bucket.object("pending/demo-1.1.0.zip.sha256").is_none());
+    let info = alice.package_info("demo@stable").await.unwrap();
+    assert_eq!(info.version, "1.1.0");
+    let settings = alice.registry_settings().await.unwrap();
+    assert_eq!(settings.approvals.len(), 1);
+    assert_eq!(settings.approvals[0].requested_by, "alice");
+
+    let err = bob.approve("demo@1.1.0").await.unwrap_err();
+    assert!(matches!(err, BeepkgError::NotFound(_)), "{}", err);
+}
+
+#[tokio::test]
+async fn test_protected_channel_tag() {
+    let bucket = MockBucket::start().await;
+    let (alice, bob) = bucket.approvers();
+    let dir = tempfile::tempdir().unwrap();
+    for version in ["1.0.0", "1.1.0"] {
+        write_package(dir.path(), "demo", version);
+        alice.push_package(dir.path()).await.unwrap();
+    }
+    alice.set_channel_protected("stable", true).await.unwrap();
+
+    // 未受保护的频道直接生效；受保护的频道等待批准，但已发布的版本仍可拉取
+    assert!(alice.tag_package("demo@1.0.0", "beta").await.unwrap());
+    assert!(!alice.tag_package("demo@1.1.0", "stable").await.unwrap());
+    let info = bob.package_info("demo@1.1.0").await.unwrap();
+    assert_eq!(info.version, "1.1.0");
+    let channels = bob.list_channels("demo").await.unwrap();
+    assert_eq!(channels.len(), 1);
+
+    bob.approve("demo@1.1.0").await.unwrap();
+    let info = bob.package_info("demo@stable").await.unwrap();
+    assert_eq!(info.version, "1.1.0");
+
+    alice.set_channel_protected("stable", false).await.unwrap();
+    assert!(alice.tag_package("demo@1.0.0", "stable").await.unwrap());
+    assert!(
+        alice
+            .set_channel_protected("bad channel", true)
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_approval_requires_trusted_keys() {
+    let bucket = MockBucket::start().await;
+    let (alice, bob) = bucket.approvers();
+    let dir = tempfile::tempdir().unwrap();
+    for version in ["1.0.0", "1.1.0"] {
+        write_package(dir.path(), "demo", version);
+        alice.push_package(dir.path()).await.unwrap();
+    }
+    alice.set_channel_protected("stable", true).await.unwrap();
+
+    // 没有签名私钥时不能请求受保护频道的变更
+    let err = bucket
+        .manager()
+        .actor("alice")
+        .tag_package("demo@1.1.0", "stable")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, BeepkgError::PolicyViolation(_)), "{}", err);
+    assert!(bob.pending_approvals().await.unwrap().is_empty());
+
+    assert!(!alice.tag_package("demo@1.1.0", "stable").await.unwrap());
+    let pending = bob.pending_approvals().await.unwrap();
+    assert!(pending[0].signature.is_some());
+
+    // 只改用户名不能绕过双人批准：没有受信任私钥，或者用请求者自己的私钥都会被拒绝
+    let err = bucket
+        .manager()
+        .actor("bob")
+        .approve("demo@1.1.0")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, BeepkgError::PolicyViolation(_)), "{}", err);
+    let err = alice
+        .actor("mallory")
+        .approve("demo@1.1.0")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("another key"), "{}", err);
+
+    // 元数据中伪造的请求签名不能通过验证
+    let mut metadata: serde_json::Value =
+        serde_json::from_slice(&bucket.object("registry-metadata.json").unwrap()).unwrap();
+    metadata["pending_approvals"][0]["signature"] = serde_json::Value::Null;
+    bucket.objects.lock().unwrap().insert(
+        "registry-metadata.json".to_string(),
+        serde_json::to_vec(&metadata).unwrap(),
+    );
+    let err = bob.approve("demo@1.1.0").await.unwrap_err();
+    assert!(err.to_string().contains("no valid signature"), "{}", err);
+    assert!(bob.list_channels("demo").await.unwrap().is_empty());
+}
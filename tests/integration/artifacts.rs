@@ -0,0 +1,149 @@
+use beepkg::artifacts::{self, ArtifactLayout};
+use beepkg::models::PackageMetadata;
+
+fn metadata() -> PackageMetadata {
+    toml::from_str(
+        r#"
+        name = "native-tool"
+        version = "1.0.0"
+        author = "dev@company.com"
+        description = "Native binaries"
+        includes = []
+        excludes = []
+
+        [dependencies]
+
+        [targets.linux-x86_64]
+        includes = ["bin/linux-x86_64/**"]
+
+        [targets.windows-x86_64]
+        includes = ["bin/windows-x86_64/**"]
+
+        [targets.noarch]
+        includes = ["bin/*.sh"]
+
+        [artifacts]
+        docs = "dist/docs.zip"
+        debug-symbols = "dist/tool.pdb.tar.gz"
+        "#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_files_split_by_target() {
+    let metadata = metadata();
+    let targets = ArtifactLayout::new(&metadata).unwrap();
+    assert_eq!(
+        targets.target_of("bin/linux-x86_64/tool"),
+        Some("linux-x86_64")
+    );
+    assert_eq!(
+        targets.target_of("bin/windows-x86_64/tool.exe"),
+        Some("windows-x86_64")
+    );
+    assert_eq!(targets.target_of("bin/run.sh"), Some("noarch"));
+    assert_eq!(targets.target_of("README.md"), None);
+    assert_eq!(targets.target_of("pack.toml"), None);
+}
+
+#[test]
+fn test_named_artifacts_excluded_from_archive() {
+    let layout = ArtifactLayout::new(&metadata()).unwrap();
+    assert_eq!(
+        layout.named().collect::<Vec<_>>(),
+        vec![
+            ("debug-symbols", "dist/tool.pdb.tar.gz"),
+            ("docs", "dist/docs.zip")
+        ]
+    );
+    assert!(layout.excludes("dist/docs.zip"));
+    assert!(layout.excludes("bin/run.sh"));
+    assert!(!layout.excludes("dist/other.zip"));
+
+    assert_eq!(
+        artifacts::artifact_file_name("debug-symbols", "dist/tool.pdb.tar.gz"),
+        "debug-symbols.pdb.tar.gz"
+    );
+    assert_eq!(
+        artifacts::artifact_file_name("docs", "docs.zip"),
+        "docs.zip"
+    );
+    assert_eq!(artifacts::artifact_file_name("notes", "NOTES"), "notes");
+}
+
+#[test]
+fn test_artifact_name_conflicts_with_target() {
+    let mut metadata = metadata();
+    metadata
+        .artifacts
+        .insert("noarch".to_string(), "dist/noarch.zip".to_string());
+    assert!(ArtifactLayout::new(&metadata).is_err());
+}
+
+#[test]
+fn test_list_artifacts() {
+    let objects = [
+        ("tool-1.0.0.zip", 10),
+        ("tool-1.0.0.zip.sha256", 64),
+        ("tool-1.0.0.zip.artifacts/docs.zip", 100),
+        ("tool-1.0.0.zip.artifacts/docs.zip.sha256", 64),
+        ("tool-1.0.0.zip.artifacts/docs.zip.sig", 64),
+        ("tool-1.0.0.zip.artifacts/linux-x86_64.zip", 200),
+        ("tool-1.0.1.zip.artifacts/docs.zip", 100),
+    ];
+    let listed = artifacts::list("tool-1.0.0.zip", objects);
+    let names: Vec<&str> = listed.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(names, vec!["docs", "linux-x86_64"]);
+    assert_eq!(listed[0].key, "tool-1.0.0.zip.artifacts/docs.zip");
+    assert_eq!(listed[0].size, 100);
+}
+
+#[test]
+fn test_select_target() {
+    let metadata = metadata();
+    assert_eq!(
+        artifacts::select_target(&metadata.targets, "linux-x86_64"),
+        Some("linux-x86_64")
+    );
+    // 没有对应平台时回退到 noarch
+    assert_eq!(
+        artifacts::select_target(&metadata.targets, "macos-aarch64"),
+        Some("noarch")
+    );
+
+    let mut without_noarch = metadata.targets.clone();
+    without_noarch.remove(artifacts::NOARCH);
+    assert_eq!(
+        artifacts::select_target(&without_noarch, "macos-aarch64"),
+        None
+    );
+}
+
+#[test]
+fn test_artifact_keys() {
+    let key = artifacts::target_key("@acme/tool-1.0.0.zip", "linux-x86_64");
+    assert_eq!(key, "@acme/tool-1.0.0.zip.artifacts/linux-x86_64.zip");
+    assert!(key.starts_with("@acme/tool-1.0.0.zip."));
+    assert!(artifacts::is_artifact_key(&key));
+    assert!(!artifacts::is_artifact_key("@acme/tool-1.0.0.zip"));
+
+    assert!(artifacts::validate_artifact_name("linux-x86_64").is_ok());
+    assert!(artifacts::validate_artifact_name("debug_symbols").is_ok());
+    assert!(artifacts::validate_artifact_name("../escape").is_err());
+    assert!(artifacts::validate_artifact_name("docs.zip").is_err());
+}
+
+#[test]
+fn test_shared_excludes() {
+    let layout = ArtifactLayout::new(&metadata())
+        .unwrap()
+        .exclude(&["**/*.log".to_string(), "pack.toml".to_string()])
+        .unwrap();
+    assert!(layout.excludes("logs/build.log"));
+    assert!(layout.excludes("bin/linux-x86_64/debug.log"));
+    assert_eq!(layout.target_of("bin/linux-x86_64/debug.log"), None);
+    // 元数据文件始终放入主包
+    assert!(!layout.excludes("pack.toml"));
+    assert!(!layout.excludes("README.md"));
+}
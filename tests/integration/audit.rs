@@ -0,0 +1,105 @@
+use super::test_helpers::{MockBucket, write_package};
+use beepkg::models::{AuditAction, RetentionRule};
+
+#[tokio::test]
+async fn test_audit_records_order_and_filters() {
+    let bucket = MockBucket::start().await;
+    let manager = bucket.manager().actor("alice");
+    let dir = tempfile::tempdir().unwrap();
+    for (name, version) in [
+        ("demo", "1.0.0"),
+        ("demo", "1.1.0"),
+        ("@team/ml-core", "2.0.0"),
+    ] {
+        write_package(dir.path(), name, version);
+        manager.push_package(dir.path()).await.unwrap();
+    }
+    manager
+        .lock_package("demo", "1.1.0", "release", "alice", None)
+        .await
+        .unwrap();
+    manager
+        .set_retention_rule(
+            "demo",
+            RetentionRule {
+                keep_last: 1,
+                keep_channels: false,
+                keep_locked: true,
+            },
+        )
+        .await
+        .unwrap();
+    manager.apply_retention(true).await.unwrap();
+
+    // 按发生的先后顺序返回
+    let records = manager.list_audit_records(None, None).await.unwrap();
+    let actions: Vec<_> = records
+        .iter()
+        .map(|record| (record.action, record.package.as_str()))
+        .collect();
+    assert_eq!(
+        actions,
+        [
+            (AuditAction::Push, "demo@1.0.0"),
+            (AuditAction::Push, "demo@1.1.0"),
+            (AuditAction::Push, "@team/ml-core@2.0.0"),
+            (AuditAction::Lock, "demo@1.1.0"),
+            (AuditAction::Delete, "demo@1.0.0"),
+        ]
+    );
+    assert!(records.iter().all(|record| record.actor == "alice"));
+    assert!(
+        records
+            .windows(2)
+            .all(|pair| pair[0].timestamp <= pair[1].timestamp)
+    );
+    assert_eq!(
+        records[3].details.as_deref(),
+        Some("locked by alice: release")
+    );
+
+    // 按包名过滤，作用域包名中的 @ 不被当作版本分隔符
+    let demo = manager
+        .list_audit_records(None, Some("demo"))
+        .await
+        .unwrap();
+    assert_eq!(demo.len(), 4);
+    assert!(
+        demo.iter()
+            .all(|record| record.package.starts_with("demo@"))
+    );
+    let scoped = manager
+        .list_audit_records(None, Some("@team/ml-core"))
+        .await
+        .unwrap();
+    assert_eq!(scoped.len(), 1);
+    assert_eq!(scoped[0].package, "@team/ml-core@2.0.0");
+    assert!(
+        manager
+            .list_audit_records(None, Some("ml-core"))
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    // 按起始时间过滤，精确到秒
+    let since = chrono::DateTime::parse_from_rfc3339(&records[3].timestamp)
+        .unwrap()
+        .with_timezone(&chrono::Utc);
+    let recent = manager.list_audit_records(Some(since), None).await.unwrap();
+    assert!(recent.len() >= 2);
+    assert!(
+        recent
+            .iter()
+            .any(|record| record.action == AuditAction::Lock)
+    );
+    assert_eq!(recent.last().unwrap().action, AuditAction::Delete);
+    let future = chrono::Utc::now() + chrono::Duration::days(1);
+    assert!(
+        manager
+            .list_audit_records(Some(future), None)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
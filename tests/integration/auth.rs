@@ -0,0 +1,62 @@
+use beepkg::auth::{self, TokenStore};
+
+#[test]
+fn test_token_store_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("beepkg").join(auth::CREDENTIALS_FILE);
+
+    // 文件不存在时为空
+    let mut store = TokenStore::load(&path).unwrap();
+    assert!(store.tokens.is_empty());
+
+    store.insert("registry.example.com/", " t0ken\n");
+    store.save(&path).unwrap();
+
+    let store = TokenStore::load(&path).unwrap();
+    assert_eq!(store.get("https://registry.example.com"), Some("t0ken"));
+    assert_eq!(store.get("http://registry.example.com"), None);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    let mut store = store;
+    assert!(store.remove("registry.example.com"));
+    assert!(!store.remove("registry.example.com"));
+}
+
+#[test]
+fn test_registry_key_normalization() {
+    assert_eq!(auth::registry_key("minio:9000"), "https://minio:9000");
+    assert_eq!(
+        auth::registry_key("http://localhost:9000/"),
+        "http://localhost:9000"
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_credential_helper() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let helper = dir.path().join("helper");
+    std::fs::write(
+        &helper,
+        "#!/bin/sh\nread endpoint\n[ \"$1\" = get ] || exit 1\n\
+         printf '{\"ServerURL\":\"%s\",\"Username\":\"AKIAHELPER\",\"Secret\":\"s3cret\"}' \"$endpoint\"\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&helper, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let credentials = auth::credential_helper(helper.to_str().unwrap(), "minio:9000")
+        .await
+        .unwrap();
+    assert_eq!(credentials.access_key_id, "AKIAHELPER");
+    assert_eq!(credentials.secret_access_key, "s3cret");
+    assert!(credentials.session_token.is_none());
+    assert!(credentials.expiration.is_none());
+}
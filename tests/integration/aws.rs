@@ -0,0 +1,79 @@
+use beepkg::aws::{self, AssumeRole, AwsCredentials};
+use chrono::{Duration, Utc};
+
+#[test]
+fn test_temporary_credentials_refresh_before_expiry() {
+    let mut credentials = AwsCredentials {
+        access_key_id: "ASIAEXAMPLE".to_string(),
+        secret_access_key: "secret".to_string(),
+        session_token: Some("token".to_string()),
+        expiration: None,
+    };
+    // 静态凭证永不过期
+    assert!(!credentials.expires_soon());
+
+    credentials.expiration = Some(Utc::now() + Duration::hours(1));
+    assert!(!credentials.expires_soon());
+
+    // 距离过期不足刷新余量时需要重新获取
+    credentials.expiration = Some(Utc::now() + Duration::seconds(60));
+    assert!(credentials.expires_soon());
+}
+
+#[test]
+fn test_assume_role_config_defaults() {
+    let role: AssumeRole =
+        toml::from_str(r#"role_arn = "arn:aws:iam::123456789012:role/publisher""#).unwrap();
+    assert_eq!(role.session_name, "beepkg");
+    assert_eq!(role.duration_seconds, 3600);
+    assert!(role.external_id.is_none());
+
+    assert!(toml::from_str::<AssumeRole>("role = \"x\"").is_err());
+}
+
+#[tokio::test]
+async fn test_instance_metadata_credentials() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // 模拟 IMDSv2：令牌 -> 角色名 -> 凭证
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 4096];
+            let n = socket.read(&mut buf).await.unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]).to_string();
+            let body = if request.starts_with("PUT /latest/api/token") {
+                "imds-token".to_string()
+            } else if !request.contains("x-aws-ec2-metadata-token: imds-token") {
+                String::new()
+            } else if request.starts_with("GET /latest/meta-data/iam/security-credentials/ ") {
+                "build-agent\n".to_string()
+            } else {
+                r#"{"Code":"Success","AccessKeyId":"ASIAIMDS","SecretAccessKey":"secret","Token":"session","Expiration":"2099-01-01T00:00:00Z"}"#.to_string()
+            };
+            let status = if body.is_empty() {
+                "401 Unauthorized"
+            } else {
+                "200 OK"
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+
+    let client = reqwest::Client::new();
+    let credentials = aws::instance_metadata_credentials(&client, &endpoint)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(credentials.access_key_id, "ASIAIMDS");
+    assert_eq!(credentials.session_token.as_deref(), Some("session"));
+    assert!(!credentials.expires_soon());
+}
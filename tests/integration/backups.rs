@@ -0,0 +1,216 @@
+use super::test_helpers::{MockBucket, MockResponse};
+use beepkg::error::BeepkgError;
+use beepkg::models::{PackageBackup, RegistryMetadata};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+const BACKUP_KEY: &str = "demo-1.0.0-backup-1700000000.zip";
+
+fn registry() -> (String, Vec<u8>) {
+    let mut metadata = RegistryMetadata::default();
+    metadata.backups.push(PackageBackup {
+        original_path: "demo-1.0.0.zip".to_string(),
+        backup_path: BACKUP_KEY.to_string(),
+        timestamp: "2023-11-14T22:13:20+00:00".to_string(),
+        reason: "release".to_string(),
+        bucket: None,
+    });
+    (
+        "registry-metadata.json".to_string(),
+        serde_json::to_vec(&metadata).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn test_restore_from_archival_storage_requests_object_restore() {
+    // 备份对象位于 GLACIER 中：读取返回 InvalidObjectState，第一次取回请求被接受，之后返回正在取回
+    let restores = Arc::new(Mutex::new(Vec::new()));
+    let recorded = restores.clone();
+    let bucket = MockBucket::with_handler(
+        BTreeMap::from([registry(), (BACKUP_KEY.to_string(), b"archived".to_vec())]),
+        move |request, _| {
+            if request.key != BACKUP_KEY {
+                return None;
+            }
+            match request.method.as_str() {
+                "GET" => Some(MockResponse::new(
+                    "403 Forbidden",
+                    "<Error><Code>InvalidObjectState</Code>\
+                     <StorageClass>GLACIER</StorageClass></Error>",
+                )),
+                "POST" if request.param("restore").is_some() => {
+                    let mut restores = recorded.lock().unwrap();
+                    restores.push(String::from_utf8_lossy(&request.body).to_string());
+                    Some(if restores.len() == 1 {
+                        MockResponse::new("202 Accepted", Vec::new())
+                    } else {
+                        MockResponse::new(
+                            "409 Conflict",
+                            "<Error><Code>RestoreAlreadyInProgress</Code></Error>",
+                        )
+                    })
+                }
+                _ => None,
+            }
+        },
+    )
+    .await;
+    let manager = bucket.manager();
+
+    let error = manager
+        .restore_package_from_backup("demo", "1.0.0", None)
+        .await
+        .unwrap_err();
+    assert!(!matches!(error, BeepkgError::AccessDenied(_)), "{}", error);
+    let message = error.to_string();
+    assert!(message.contains("archival storage"), "{}", message);
+    assert!(message.contains("requested a restore"), "{}", message);
+    {
+        let restores = restores.lock().unwrap();
+        assert_eq!(restores.len(), 1);
+        assert!(restores[0].contains("<Days>7</Days>"), "{}", restores[0]);
+    }
+
+    let error = manager
+        .restore_package_from_backup("demo", "1.0.0", None)
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("already in progress"),
+        "{}",
+        error
+    );
+    // 取回完成前不写回原始位置
+    assert_eq!(bucket.object("demo-1.0.0.zip"), None);
+}
+
+#[tokio::test]
+async fn test_list_backups_order_sizes_and_filters() {
+    let backup =
+        |original: &str, path: &str, timestamp: &str, bucket: Option<&str>| PackageBackup {
+            original_path: original.to_string(),
+            backup_path: path.to_string(),
+            timestamp: timestamp.to_string(),
+            reason: format!("before {}", timestamp),
+            bucket: bucket.map(str::to_string),
+        };
+    // 元数据中的记录顺序与期望的输出顺序不同
+    let metadata = RegistryMetadata {
+        backups: vec![
+            backup(
+                "demo-1.10.0.zip",
+                "demo-1.10.0-backup-1700000300.zip",
+                "2023-11-14T22:18:20+00:00",
+                None,
+            ),
+            backup(
+                "demo-1.9.0.zip",
+                "demo-1.9.0-backup-1700000200.zip",
+                "2023-11-14T22:16:40+00:00",
+                None,
+            ),
+            backup(
+                "demo-1.9.0.zip",
+                "demo-1.9.0-backup-1700000100.zip",
+                "2023-11-14T22:15:00+00:00",
+                None,
+            ),
+            backup(
+                "@team/ml-core-2.0.0.zip",
+                "@team/ml-core-2.0.0-backup-1700000000.zip",
+                "2023-11-14T22:13:20+00:00",
+                Some("archive"),
+            ),
+        ],
+        ..Default::default()
+    };
+    let bucket = MockBucket::with_objects(BTreeMap::from([
+        (
+            "registry-metadata.json".to_string(),
+            serde_json::to_vec(&metadata).unwrap(),
+        ),
+        (
+            "demo-1.10.0-backup-1700000300.zip".to_string(),
+            vec![0; 300],
+        ),
+        ("demo-1.9.0-backup-1700000100.zip".to_string(), vec![0; 100]),
+        // 已被删除的 demo-1.9.0-backup-1700000200.zip 没有大小
+    ]))
+    .await;
+    let archive = MockBucket::with_objects(BTreeMap::from([(
+        "@team/ml-core-2.0.0-backup-1700000000.zip".to_string(),
+        vec![0; 42],
+    )]))
+    .await;
+    let archive_store = beepkg::operations::PackageManager::builder()
+        .endpoint(archive.endpoint())
+        .bucket("archive")
+        .build()
+        .unwrap()
+        .cache(None);
+    let manager = bucket.manager().backup_store(Some(archive_store));
+
+    let backups = manager.list_backups(None, None).await.unwrap();
+    let listed: Vec<_> = backups
+        .iter()
+        .map(|entry| {
+            (
+                format!("{}@{}", entry.package, entry.version),
+                entry.timestamp.as_str(),
+                entry.size,
+            )
+        })
+        .collect();
+    assert_eq!(
+        listed,
+        [
+            (
+                "@team/ml-core@2.0.0".to_string(),
+                "2023-11-14T22:13:20+00:00",
+                Some(42)
+            ),
+            (
+                "demo@1.9.0".to_string(),
+                "2023-11-14T22:15:00+00:00",
+                Some(100)
+            ),
+            ("demo@1.9.0".to_string(), "2023-11-14T22:16:40+00:00", None),
+            (
+                "demo@1.10.0".to_string(),
+                "2023-11-14T22:18:20+00:00",
+                Some(300)
+            ),
+        ]
+    );
+    assert_eq!(backups[0].bucket.as_deref(), Some("archive"));
+    assert_eq!(backups[1].reason, "before 2023-11-14T22:15:00+00:00");
+    assert_eq!(backups[1].backup_path, "demo-1.9.0-backup-1700000100.zip");
+    // 每个存储桶只列出一次
+    let lists = |bucket: &MockBucket| {
+        bucket
+            .requests()
+            .iter()
+            .filter(|request| {
+                request.starts_with("GET /packages/?") || request.starts_with("GET /archive/?")
+            })
+            .count()
+    };
+    assert_eq!((lists(&bucket), lists(&archive)), (1, 1));
+
+    let demo = manager.list_backups(Some("demo"), None).await.unwrap();
+    assert_eq!(demo.len(), 3);
+    let pinned = manager
+        .list_backups(Some("demo"), Some("1.9.0"))
+        .await
+        .unwrap();
+    assert_eq!(pinned.len(), 2);
+    // 只有主存储桶中的备份时不列出备份存储桶
+    assert_eq!(lists(&archive), 1);
+    assert!(
+        manager
+            .list_backups(Some("missing"), None)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
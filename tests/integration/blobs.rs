@@ -0,0 +1,97 @@
+use super::test_helpers::{MockBucket, write_package};
+use beepkg::blobs::{self, BlobPointer};
+use beepkg::checksum::{Checksum, ChecksumAlgorithm};
+use beepkg::models::{RegistryMetadata, StorageLayout};
+use std::collections::BTreeMap;
+
+#[test]
+fn test_blob_pointer_round_trip() {
+    let checksum = Checksum::compute(ChecksumAlgorithm::Sha256, b"package content");
+    let pointer = BlobPointer::new(&checksum, 15);
+    assert_eq!(pointer.blob, format!("blobs/sha256/{}", checksum.hex));
+    assert!(blobs::is_blob_key(&pointer.blob));
+
+    let parsed = BlobPointer::parse(&pointer.to_bytes()).unwrap();
+    assert_eq!(parsed, pointer);
+    assert_eq!(parsed.digest().unwrap(), checksum);
+}
+
+#[test]
+fn test_blob_pointer_rejects_other_content() {
+    // zip 文件、其他 JSON 附属文件都不是指针
+    assert!(BlobPointer::parse(b"PK\x03\x04").is_none());
+    assert!(BlobPointer::parse(br#"{"registry_name": "demo"}"#).is_none());
+
+    // 对象键与校验和不一致的指针被拒绝
+    let checksum = Checksum::compute(ChecksumAlgorithm::Sha256, b"one");
+    let mut pointer = BlobPointer::new(&checksum, 3);
+    pointer.blob = "blobs/sha256/0000".to_string();
+    assert!(BlobPointer::parse(&pointer.to_bytes()).is_none());
+    pointer.blob = "registry-metadata.json".to_string();
+    assert!(BlobPointer::parse(&pointer.to_bytes()).is_none());
+}
+
+#[test]
+fn test_storage_layout_in_registry_metadata() {
+    let content = r#"{
+        "registry_name": "demo",
+        "backup_enabled": false,
+        "locked_packages": [],
+        "backups": [],
+        "last_updated": "2024-01-01T00:00:00Z"
+    }"#;
+    let mut metadata: RegistryMetadata = serde_json::from_str(content).unwrap();
+    assert_eq!(metadata.layout, StorageLayout::Flat);
+    assert!(!serde_json::to_string(&metadata).unwrap().contains("layout"));
+
+    metadata.layout = "cas".parse().unwrap();
+    let saved = serde_json::to_string(&metadata).unwrap();
+    assert!(saved.contains(r#""layout":"cas""#), "{}", saved);
+    assert!("tree".parse::<StorageLayout>().is_err());
+}
+
+#[tokio::test]
+async fn test_cas_push_streams_content_once() {
+    let bucket = MockBucket::with_objects(BTreeMap::from([(
+        "registry-metadata.json".to_string(),
+        br#"{"registry_name": "demo", "backup_enabled": false, "locked_packages": [],
+             "backups": [], "last_updated": "", "layout": "cas"}"#
+            .to_vec(),
+    )]))
+    .await;
+    let manager = bucket.manager();
+    let dir = tempfile::tempdir().unwrap();
+    write_package(dir.path(), "demo", "1.0.0");
+    manager.push_package(dir.path()).await.unwrap();
+
+    // 内容只上传一次，写到临时对象后在存储端复制为 blob，临时对象随后删除
+    let pointer = BlobPointer::parse(&bucket.object("demo-1.0.0.zip").unwrap()).unwrap();
+    let blob = bucket.object(&pointer.blob).unwrap();
+    assert_eq!(
+        pointer.digest().unwrap(),
+        Checksum::compute(ChecksumAlgorithm::Sha256, &blob)
+    );
+    let requests = bucket.requests();
+    let staged: Vec<_> = requests
+        .iter()
+        .filter(|request| request.starts_with("PUT /packages/blobs/uploads/"))
+        .collect();
+    assert_eq!(staged.len(), 1, "{:?}", requests);
+    assert_eq!(bucket.count("PUT", &pointer.blob), 1);
+    let blob_keys = |bucket: &MockBucket| -> Vec<String> {
+        bucket
+            .objects
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|key| blobs::is_blob_key(key))
+            .cloned()
+            .collect()
+    };
+    assert_eq!(blob_keys(&bucket), std::slice::from_ref(&pointer.blob));
+
+    // 内容已存在时不再复制
+    manager.force_push_package(dir.path()).await.unwrap();
+    assert_eq!(bucket.count("PUT", &pointer.blob), 1);
+    assert_eq!(blob_keys(&bucket), [pointer.blob]);
+}
@@ -0,0 +1,18 @@
+use beepkg::operations::PackageManager;
+use std::time::Duration;
+
+#[test]
+fn test_blocking_reports_errors() {
+    let manager = PackageManager::builder()
+        .endpoint("http://127.0.0.1:1")
+        .bucket("packages")
+        .timeout(Duration::from_secs(1))
+        .build()
+        .unwrap()
+        .blocking()
+        .unwrap();
+
+    assert!(manager.list_packages().is_err());
+    let (connected, _) = manager.block_on(|m| m.test_connection()).unwrap();
+    assert!(!connected);
+}
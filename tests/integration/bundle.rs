@@ -0,0 +1,65 @@
+use beepkg::bundle::{self, BundleManifest, BundlePackage, BundleWriter};
+use beepkg::checksum::{Checksum, ChecksumAlgorithm};
+
+fn manifest(archive: &[u8]) -> BundleManifest {
+    BundleManifest {
+        format_version: bundle::FORMAT_VERSION,
+        created_at: "2024-01-01T00:00:00Z".to_string(),
+        source: "https://minio.example.com/packages/".to_string(),
+        packages: vec![BundlePackage {
+            archive: "@acme/demo-1.0.0.zip".to_string(),
+            checksum: Checksum::compute(ChecksumAlgorithm::Sha256, archive).to_string(),
+            sidecars: vec!["@acme/demo-1.0.0.zip.sha256".to_string()],
+        }],
+        access: Default::default(),
+        project: None,
+    }
+}
+
+#[test]
+fn test_bundle_roundtrip() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive = b"zip content";
+    let archive_path = dir.path().join("demo.zip");
+    std::fs::write(&archive_path, archive).unwrap();
+    let manifest = manifest(archive);
+
+    let path = dir.path().join("registry.tar.zst");
+    let mut writer = BundleWriter::create(&path, &manifest).unwrap();
+    writer
+        .append_file("@acme/demo-1.0.0.zip", &archive_path)
+        .unwrap();
+    writer
+        .append_bytes(
+            "@acme/demo-1.0.0.zip.sha256",
+            manifest.packages[0].checksum.as_bytes(),
+        )
+        .unwrap();
+    writer.finish().unwrap();
+
+    let out = tempfile::tempdir().unwrap();
+    let unpacked = bundle::unpack(&path, out.path()).unwrap();
+    assert_eq!(unpacked.packages.len(), 1);
+    assert_eq!(unpacked.source, manifest.source);
+
+    let objects = out.path().join(bundle::OBJECTS_DIR);
+    let file = std::fs::File::open(objects.join("@acme/demo-1.0.0.zip")).unwrap();
+    let checksum = Checksum::compute_reader(ChecksumAlgorithm::Sha256, file).unwrap();
+    assert_eq!(checksum.to_string(), unpacked.packages[0].checksum);
+    assert!(objects.join("@acme/demo-1.0.0.zip.sha256").exists());
+}
+
+#[test]
+fn test_bundle_rejects_unlisted_objects() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("registry.tar.zst");
+    let mut writer = BundleWriter::create(&path, &manifest(b"zip content")).unwrap();
+    writer
+        .append_bytes("other-2.0.0.zip", b"unexpected")
+        .unwrap();
+    writer.finish().unwrap();
+
+    let out = tempfile::tempdir().unwrap();
+    let error = bundle::unpack(&path, out.path()).unwrap_err();
+    assert!(error.to_string().contains("Unexpected entry"));
+}
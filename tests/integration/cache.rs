@@ -0,0 +1,142 @@
+use beepkg::cache::{Cache, CacheRef};
+use beepkg::checksum::{Checksum, ChecksumAlgorithm};
+use beepkg::operations::PackageManager;
+
+const REGISTRY: &str = "http://localhost:9000/packages";
+
+fn cache_package(cache: &Cache, dir: &std::path::Path, key: &str, content: &[u8]) -> Checksum {
+    let path = dir.join("download");
+    std::fs::write(&path, content).unwrap();
+    let checksum = Checksum::compute(ChecksumAlgorithm::Sha256, content);
+    cache.store(&checksum, &path).unwrap();
+    cache
+        .record(
+            REGISTRY,
+            key,
+            &CacheRef {
+                checksum: checksum.to_string(),
+                signer: None,
+                cached_at: "2024-01-01T00:00:00Z".to_string(),
+            },
+        )
+        .unwrap();
+    checksum
+}
+
+#[test]
+fn test_cache_store_and_restore() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache::new(dir.path().join("cache"));
+    let checksum = cache_package(&cache, dir.path(), "demo-1.0.0.zip", b"demo content");
+
+    let (found, signer) = cache.lookup(REGISTRY, "demo-1.0.0.zip").unwrap();
+    assert_eq!(found, checksum);
+    assert!(signer.is_none());
+    assert!(
+        cache
+            .lookup("http://other/packages", "demo-1.0.0.zip")
+            .is_none()
+    );
+
+    let restored = dir.path().join("restored.zip");
+    assert!(cache.restore(&checksum, &restored).unwrap());
+    assert_eq!(std::fs::read(&restored).unwrap(), b"demo content");
+
+    // 缓存内容被篡改时不再使用
+    std::fs::write(cache.archive_path(&checksum), b"tampered").unwrap();
+    assert!(!cache.restore(&checksum, &restored).unwrap());
+    assert!(cache.lookup(REGISTRY, "demo-1.0.0.zip").is_none());
+}
+
+#[test]
+fn test_cache_versions() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache::new(dir.path().join("cache"));
+    cache_package(&cache, dir.path(), "demo-1.0.0.zip", b"one");
+    cache_package(&cache, dir.path(), "demo-1.1.0.zip", b"two");
+    cache_package(&cache, dir.path(), "demo-extra-2.0.0.zip", b"three");
+    cache_package(&cache, dir.path(), "@acme/demo-3.0.0.zip", b"four");
+
+    let mut versions = cache.versions(REGISTRY, "demo");
+    versions.sort();
+    assert_eq!(
+        versions,
+        vec![semver::Version::new(1, 0, 0), semver::Version::new(1, 1, 0)]
+    );
+    assert_eq!(
+        cache.versions(REGISTRY, "@acme/demo"),
+        vec![semver::Version::new(3, 0, 0)]
+    );
+}
+
+#[test]
+fn test_cache_clean_removes_least_recently_used() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache::new(dir.path().join("cache"));
+    let old = cache_package(&cache, dir.path(), "demo-1.0.0.zip", &[0; 100]);
+    let new = cache_package(&cache, dir.path(), "demo-1.1.0.zip", &[1; 100]);
+    std::fs::File::options()
+        .write(true)
+        .open(cache.archive_path(&old))
+        .unwrap()
+        .set_modified(std::time::SystemTime::UNIX_EPOCH)
+        .unwrap();
+    assert_eq!(cache.size().unwrap(), 200);
+
+    let report = cache.clean(150).unwrap();
+    assert_eq!(report.removed, 1);
+    assert_eq!(report.freed, 100);
+    assert_eq!(report.remaining, 100);
+    assert!(cache.lookup(REGISTRY, "demo-1.0.0.zip").is_none());
+    assert_eq!(cache.lookup(REGISTRY, "demo-1.1.0.zip").unwrap().0, new);
+
+    let report = cache.clean(0).unwrap();
+    assert_eq!(report.removed, 1);
+    assert_eq!(cache.size().unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_offline_pull_without_cache_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = PackageManager::builder()
+        .endpoint("http://127.0.0.1:9")
+        .bucket("packages")
+        .build()
+        .unwrap()
+        .cache(Some(Cache::new(dir.path().join("cache"))))
+        .offline(true);
+    let err = manager
+        .pull_package("demo@1.0.0", dir.path())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("offline"), "{}", err);
+}
+
+#[test]
+fn test_cache_package_names() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache::new(dir.path().join("cache"));
+    assert!(cache.package_names(REGISTRY, "").is_empty());
+
+    cache
+        .store_package_names(REGISTRY, &["demo".to_string(), "web".to_string()])
+        .unwrap();
+    // 拉取过的包即使不在索引中也能补全
+    cache_package(&cache, dir.path(), "demo-extra-2.0.0-rc.1.zip", b"one");
+    cache_package(&cache, dir.path(), "@acme/demo-3.0.0.zip", b"two");
+    cache_package(&cache, dir.path(), "web-1.0.0.zip", b"three");
+
+    assert_eq!(
+        cache.package_names(REGISTRY, ""),
+        ["@acme/demo", "demo", "demo-extra", "web"]
+    );
+    assert_eq!(
+        cache.package_names(REGISTRY, "demo"),
+        ["demo", "demo-extra"]
+    );
+    assert!(
+        cache
+            .package_names("http://other/packages", "demo")
+            .is_empty()
+    );
+}
@@ -0,0 +1,72 @@
+use super::test_helpers::{MockBucket, pack_toml};
+use beepkg::error::BeepkgError;
+use beepkg::models::{MetadataFormat, PackageMetadata};
+
+#[test]
+fn test_release_section() {
+    let content = "name = \"demo\"\nversion = \"1.0.0\"\nauthor = \"\"\ndescription = \"\"\n\
+                   includes = []\nexcludes = []\n\n[dependencies]\n\n\
+                   [release]\nnotes = \"\"\"\n- First release\n\"\"\"\n";
+    let metadata = PackageMetadata::parse(content, MetadataFormat::Toml).unwrap();
+    assert_eq!(metadata.release.notes.as_deref(), Some("- First release\n"));
+
+    // 没有发布说明时不写出 [release]
+    let mut metadata = metadata;
+    metadata.release.notes = None;
+    assert!(!toml::to_string(&metadata).unwrap().contains("release"));
+}
+
+#[tokio::test]
+async fn test_changelog_lists_release_notes() {
+    let bucket = MockBucket::start().await;
+    let manager = || bucket.manager();
+
+    let dir = tempfile::tempdir().unwrap();
+    let write = |version: &str, tail: &str| {
+        std::fs::write(
+            dir.path().join("pack.toml"),
+            pack_toml("demo", version, tail),
+        )
+        .unwrap()
+    };
+    write("1.0.0", "\n[release]\nnotes = \"- First release\"\n");
+    manager().push_package(dir.path()).await.unwrap();
+    // --notes-file 优先于 pack.toml 中的说明
+    write("1.1.0", "\n[release]\nnotes = \"ignored\"\n");
+    manager()
+        .release_notes(Some("- Faster pulls\n".to_string()))
+        .push_package(dir.path())
+        .await
+        .unwrap();
+    write("2.0.0", "");
+    manager().push_package(dir.path()).await.unwrap();
+
+    let releases = manager().changelog("demo", None, None).await.unwrap();
+    let versions: Vec<&str> = releases.iter().map(|r| r.version.as_str()).collect();
+    assert_eq!(versions, ["2.0.0", "1.1.0", "1.0.0"]);
+    assert_eq!(releases[0].release_notes, None);
+    assert_eq!(
+        releases[1].release_notes.as_deref(),
+        Some("- Faster pulls\n")
+    );
+    assert_eq!(
+        releases[2].release_notes.as_deref(),
+        Some("- First release")
+    );
+
+    let from = semver::Version::new(1, 0, 1);
+    let to = semver::Version::new(1, 9, 0);
+    let releases = manager()
+        .changelog("demo", Some(&from), Some(&to))
+        .await
+        .unwrap();
+    assert_eq!(releases.len(), 1);
+    assert_eq!(releases[0].version, "1.1.0");
+
+    let from = semver::Version::new(3, 0, 0);
+    let err = manager()
+        .changelog("demo", Some(&from), None)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, BeepkgError::NotFound(_)), "{}", err);
+}
@@ -0,0 +1,123 @@
+use super::test_helpers::MockBucket;
+use beepkg::checksum::{Checksum, ChecksumAlgorithm, ChecksumHasher};
+use std::collections::BTreeMap;
+
+const CONTENT: &[u8] = b"demo package content";
+
+// 只有旧版 sha1 校验文件的包
+async fn legacy_bucket(extra: &[(&str, &[u8])]) -> MockBucket {
+    let sha1 = Checksum::compute(ChecksumAlgorithm::Sha1, CONTENT);
+    let mut objects = BTreeMap::from([
+        ("demo-1.0.0.zip".to_string(), CONTENT.to_vec()),
+        (
+            "demo-1.0.0.zip.sha1".to_string(),
+            sha1.to_string().into_bytes(),
+        ),
+    ]);
+    for (key, value) in extra {
+        objects.insert(key.to_string(), value.to_vec());
+    }
+    MockBucket::with_objects(objects).await
+}
+
+#[test]
+fn test_sha256_sidecar_round_trip() {
+    let checksum = Checksum::compute(ChecksumAlgorithm::Sha256, b"abc");
+    assert_eq!(
+        checksum.to_string(),
+        "sha256:ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+
+    let parsed = Checksum::parse(&checksum.to_string(), ChecksumAlgorithm::Sha1).unwrap();
+    assert_eq!(parsed, checksum);
+    assert!(parsed.verify(b"abc"));
+}
+
+#[test]
+fn test_legacy_sha1_sidecar() {
+    let parsed = Checksum::parse(
+        "a9993e364706816aba3e25717850c26c9cd0d89d\n",
+        ChecksumAlgorithm::Sha1,
+    )
+    .unwrap();
+    assert_eq!(parsed.algorithm, ChecksumAlgorithm::Sha1);
+    assert!(parsed.verify(b"abc"));
+    assert!(!parsed.verify(b"abd"));
+}
+
+#[test]
+fn test_streaming_hasher_matches_one_shot() {
+    let data = vec![7u8; 200_000];
+    for algorithm in ChecksumAlgorithm::ALL {
+        let mut hasher = ChecksumHasher::new(algorithm);
+        for chunk in data.chunks(8192) {
+            hasher.update(chunk);
+        }
+        assert_eq!(hasher.finalize(), Checksum::compute(algorithm, &data));
+    }
+}
+
+#[test]
+fn test_parse_digest_requires_full_prefixed_digest() {
+    let checksum = Checksum::compute(ChecksumAlgorithm::Sha256, b"abc");
+    let upper = format!("sha256:{}", checksum.hex.to_uppercase());
+    assert_eq!(Checksum::parse_digest(&upper), Some(checksum.clone()));
+    assert_eq!(Checksum::parse_digest(&checksum.hex), None);
+    assert_eq!(
+        Checksum::parse_digest(&format!("sha256:{}", &checksum.hex[..12])),
+        None
+    );
+    assert_eq!(
+        Checksum::parse_digest("sha1:a9993e364706816aba3e25717850c26c9cd0d89d")
+            .map(|c| c.algorithm),
+        Some(ChecksumAlgorithm::Sha1)
+    );
+    // 频道名和版本范围不会被当作摘要
+    assert_eq!(Checksum::parse_digest("stable"), None);
+    assert_eq!(Checksum::parse_digest("^1.0"), None);
+}
+
+#[tokio::test]
+async fn test_rehash_replaces_legacy_checksums() {
+    let bucket = legacy_bucket(&[]).await;
+    let manager = bucket.manager();
+
+    assert_eq!(
+        manager.rehash_packages(true).await.unwrap(),
+        ["demo-1.0.0.zip"]
+    );
+    assert!(bucket.object("demo-1.0.0.zip.sha256").is_none());
+
+    assert_eq!(
+        manager.rehash_packages(false).await.unwrap(),
+        ["demo-1.0.0.zip"]
+    );
+    let sha256 = Checksum::compute(ChecksumAlgorithm::Sha256, CONTENT);
+    assert_eq!(
+        bucket.object("demo-1.0.0.zip.sha256").unwrap(),
+        sha256.to_string().into_bytes()
+    );
+    assert!(bucket.object("demo-1.0.0.zip.sha1").is_none());
+    assert!(bucket.object("demo-1.0.0.zip.SHA256SUMS").is_some());
+    assert!(manager.rehash_packages(false).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_rehash_refuses_signed_packages_without_a_signing_key() {
+    let bucket = legacy_bucket(&[("demo-1.0.0.zip.sig", b"signature")]).await;
+    let manager = bucket.manager();
+
+    // 签名针对旧的校验和，不能重新签名时不改写校验文件
+    for dry_run in [true, false] {
+        let error = manager.rehash_packages(dry_run).await.unwrap_err();
+        assert!(
+            error.to_string().contains("configure a signing key"),
+            "{}",
+            error
+        );
+    }
+    assert!(bucket.object("demo-1.0.0.zip.sha1").is_some());
+    assert!(bucket.object("demo-1.0.0.zip.sha256").is_none());
+    assert_eq!(bucket.object("demo-1.0.0.zip.sig").unwrap(), b"signature");
+    assert_eq!(bucket.count("GET", "demo-1.0.0.zip"), 0);
+}
@@ -0,0 +1,176 @@
+use beepkg::config::{self, Config};
+
+#[test]
+fn test_registries_in_config_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(config::CONFIG_FILE);
+
+    // 文件不存在时为默认配置
+    assert!(Config::load(&path).unwrap().registries.is_empty());
+
+    std::fs::write(
+        &path,
+        r#"
+        [[registries]]
+        name = "private"
+        endpoint = "https://minio.internal.example.com"
+
+        [[registries]]
+        name = "mirror"
+        endpoint = "https://packages.example.com"
+        bucket = "shared"
+        "#,
+    )
+    .unwrap();
+    let config = Config::load(&path).unwrap();
+    config.validate().unwrap();
+    let names: Vec<_> = config.registries.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, ["private", "mirror"]);
+    assert_eq!(config.registry("private").unwrap().bucket, "packages");
+    assert_eq!(config.registry("mirror").unwrap().bucket, "shared");
+    assert!(config.registry("missing").is_err());
+}
+
+#[test]
+fn test_duplicate_registry_names_rejected() {
+    let config: Config = toml::from_str(
+        r#"
+        [[registries]]
+        name = "private"
+        endpoint = "a.example.com"
+
+        [[registries]]
+        name = "private"
+        endpoint = "b.example.com"
+        "#,
+    )
+    .unwrap();
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_url_style_and_region() {
+    let config: Config = toml::from_str(
+        r#"
+        url_style = "virtual-host"
+        region = "eu-central-1"
+
+        [[registries]]
+        name = "minio"
+        endpoint = "https://minio.internal.example.com"
+        url_style = "path"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.url_style, Some(config::UrlStyle::VirtualHost));
+    assert_eq!(config.region.as_deref(), Some("eu-central-1"));
+    let registry = config.registry("minio").unwrap();
+    assert_eq!(registry.url_style, Some(config::UrlStyle::Path));
+    assert_eq!(registry.region, None);
+
+    assert_eq!(
+        "virtual".parse::<config::UrlStyle>().unwrap(),
+        config::UrlStyle::VirtualHost
+    );
+    assert!("dns".parse::<config::UrlStyle>().is_err());
+    assert!(toml::from_str::<Config>(r#"url_style = "dns""#).is_err());
+}
+
+#[test]
+fn test_tls_settings() {
+    let config: Config = toml::from_str(
+        r#"
+        [[registries]]
+        name = "internal"
+        endpoint = "https://minio.corp.example.com"
+
+        [registries.tls]
+        ca_bundle = "/etc/beepkg/corp-ca.pem"
+        system_roots = false
+        "#,
+    )
+    .unwrap();
+    assert!(config.tls.system_roots);
+    assert!(config.tls.ca_bundle.is_none());
+    let tls = config.registry("internal").unwrap().tls.as_ref().unwrap();
+    assert!(!tls.system_roots);
+    assert_eq!(
+        tls.ca_bundle.as_deref(),
+        Some(std::path::Path::new("/etc/beepkg/corp-ca.pem"))
+    );
+
+    // 默认设置可以直接使用
+    assert!(
+        config::TlsConfig::default()
+            .apply(reqwest::Client::builder())
+            .is_ok()
+    );
+
+    // 不信任系统根证书时必须提供 CA
+    let no_roots = config::TlsConfig {
+        system_roots: false,
+        ..Default::default()
+    };
+    assert!(no_roots.apply(reqwest::Client::builder()).is_err());
+
+    // 客户端证书和私钥必须同时设置
+    let dir = tempfile::tempdir().unwrap();
+    let cert = dir.path().join("client.pem");
+    std::fs::write(&cert, "not a certificate").unwrap();
+    let half = config::TlsConfig {
+        client_cert: Some(cert),
+        ..Default::default()
+    };
+    assert!(half.apply(reqwest::Client::builder()).is_err());
+
+    let empty = dir.path().join("empty.pem");
+    std::fs::write(&empty, "").unwrap();
+    let bundle = config::TlsConfig {
+        ca_bundle: Some(empty),
+        ..Default::default()
+    };
+    let error = bundle.apply(reqwest::Client::builder()).unwrap_err();
+    assert!(error.to_string().contains("no certificates"), "{}", error);
+}
+
+#[test]
+fn test_proxy_settings() {
+    let config: Config = toml::from_str(
+        r#"
+        [proxy]
+        url = "http://proxy.corp.example.com:3128"
+
+        [[registries]]
+        name = "internal"
+        endpoint = "https://minio.corp.example.com"
+
+        [registries.proxy]
+        url = "socks5h://proxy.corp.example.com:1080"
+        no_proxy = "localhost,.corp.example.com"
+        "#,
+    )
+    .unwrap();
+    let proxy = config.proxy.as_ref().unwrap();
+    assert_eq!(proxy.url, "http://proxy.corp.example.com:3128");
+    assert!(proxy.no_proxy.is_none());
+    let registry_proxy = config.registry("internal").unwrap().proxy.as_ref().unwrap();
+    assert_eq!(registry_proxy.url, "socks5h://proxy.corp.example.com:1080");
+    assert_eq!(
+        registry_proxy.no_proxy.as_deref(),
+        Some("localhost,.corp.example.com")
+    );
+
+    // HTTP 和 SOCKS5 代理都可以使用
+    assert!(proxy.apply(reqwest::Client::builder()).is_ok());
+    assert!(registry_proxy.apply(reqwest::Client::builder()).is_ok());
+
+    let invalid = config::ProxyConfig {
+        url: "not a proxy url".to_string(),
+        no_proxy: None,
+    };
+    let error = invalid.apply(reqwest::Client::builder()).unwrap_err();
+    assert!(error.to_string().contains("Invalid proxy"), "{}", error);
+
+    // 代理设置中不允许未知字段
+    assert!(toml::from_str::<Config>("[proxy]\nurl = \"http://p:1\"\nport = 1").is_err());
+}
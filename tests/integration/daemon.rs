@@ -0,0 +1,117 @@
+use super::test_helpers::MockBucket;
+use beepkg::cache::Cache;
+use beepkg::daemon::{self, Client};
+use beepkg::error::BeepkgError;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+
+// 在 socket 上启动守护进程，注册表是模拟的 bucket
+async fn start_daemon(socket: &Path, objects: BTreeMap<String, Vec<u8>>, cache: &Path) {
+    let bucket = MockBucket::with_objects(objects).await;
+    let manager = bucket.manager();
+    let listener = daemon::bind(socket).await.unwrap();
+    let options = daemon::Options {
+        cache: Some(Cache::new(cache.to_path_buf())),
+    };
+    tokio::spawn(daemon::serve(Arc::new(manager), listener, options));
+}
+
+#[tokio::test]
+async fn test_daemon_requests() {
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("beepkg.sock");
+    let objects = BTreeMap::from([("demo-1.0.0.zip".to_string(), vec![0u8; 64])]);
+    start_daemon(&socket, objects, &dir.path().join("cache")).await;
+
+    // 只有当前用户可以连接
+    use std::os::unix::fs::PermissionsExt;
+    let mode = std::fs::metadata(&socket).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    // 创建套接字使用的临时目录已删除
+    let entries: Vec<_> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert!(
+        !entries
+            .iter()
+            .any(|name| name.to_string_lossy().starts_with(".beepkg-"))
+    );
+
+    // 同一个连接上依次发送多个请求
+    let mut client = Client::connect(&socket).await.unwrap();
+    let pong = client.call("ping", json!(null)).await.unwrap();
+    assert_eq!(pong["version"], env!("CARGO_PKG_VERSION"));
+    let exists = client
+        .call("exists", json!({"name": "demo", "version": "1.0.0"}))
+        .await
+        .unwrap();
+    assert_eq!(exists, json!(true));
+    let stat = client
+        .call("stat", json!({"name": "demo", "version": "1.0.0"}))
+        .await
+        .unwrap();
+    assert_eq!(stat["size"], 64);
+    let info = client.call("cache.info", json!(null)).await.unwrap();
+    assert_eq!(info["size"], 0);
+
+    // 错误带上与命令行相同的退出码
+    let err = client
+        .call(
+            "fetch",
+            json!({"package": format!("demo@sha256:{}", "0".repeat(64)), "output": dir.path().join("out.zip")}),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.exit_code(), 5, "{}", err);
+    let err = client.call("exists", json!({})).await.unwrap_err();
+    assert!(
+        matches!(err, BeepkgError::Remote { code: 2, .. }),
+        "{}",
+        err
+    );
+    let err = client.call("unknown", json!(null)).await.unwrap_err();
+    assert!(err.to_string().contains("Unknown method"), "{}", err);
+
+    // 无法解析的请求也有响应，连接保持可用
+    let mut stream = UnixStream::connect(&socket).await.unwrap();
+    stream
+        .write_all(b"not json\n{\"id\": 7, \"method\": \"ping\"}\n")
+        .await
+        .unwrap();
+    let mut lines = BufReader::new(stream).lines();
+    let first: serde_json::Value =
+        serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+    assert_eq!(first["error"]["code"], 2);
+    let second: serde_json::Value =
+        serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+    assert_eq!(second["id"], 7);
+    assert!(second["result"].is_object());
+}
+
+#[tokio::test]
+async fn test_daemon_socket_reuse() {
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("beepkg.sock");
+
+    // 上次异常退出留下的套接字文件被替换
+    drop(std::os::unix::net::UnixListener::bind(&socket).unwrap());
+    assert!(socket.exists());
+    start_daemon(&socket, BTreeMap::new(), dir.path()).await;
+
+    // 已有守护进程在运行时不能再启动
+    let err = daemon::bind(&socket).await.unwrap_err();
+    assert!(matches!(err, BeepkgError::Conflict(_)), "{}", err);
+    let mut client = Client::connect(&socket).await.unwrap();
+    client.call("ping", json!(null)).await.unwrap();
+
+    let err = Client::connect(&dir.path().join("missing.sock"))
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(err, BeepkgError::Network(_)), "{}", err);
+}
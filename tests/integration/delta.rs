@@ -0,0 +1,70 @@
+use beepkg::delta;
+
+// 可重复的伪随机内容，压缩无法缩小，只有引用旧版本才能让差量变小
+fn pseudo_random(len: usize, seed: u64) -> Vec<u8> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+#[test]
+fn test_delta_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let base = pseudo_random(512 * 1024, 1);
+    let mut target = base.clone();
+    target[1000..1100].copy_from_slice(&pseudo_random(100, 2));
+    target.extend(pseudo_random(4096, 3));
+
+    let base_path = dir.path().join("base.zip");
+    let target_path = dir.path().join("target.zip");
+    let delta_path = dir.path().join("delta.zst");
+    std::fs::write(&base_path, &base).unwrap();
+    std::fs::write(&target_path, &target).unwrap();
+
+    delta::create(&base_path, &target_path, &delta_path).unwrap();
+    let delta_size = std::fs::metadata(&delta_path).unwrap().len();
+    assert!(delta_size < 64 * 1024, "delta is {} bytes", delta_size);
+    assert!(delta::worthwhile(delta_size, target.len() as u64));
+
+    let restored = dir.path().join("restored.zip");
+    delta::apply(&base_path, &delta_path, &restored).unwrap();
+    assert_eq!(std::fs::read(&restored).unwrap(), target);
+}
+
+#[test]
+fn test_delta_requires_matching_base() {
+    let dir = tempfile::tempdir().unwrap();
+    let base_path = dir.path().join("base.zip");
+    let target_path = dir.path().join("target.zip");
+    let delta_path = dir.path().join("delta.zst");
+    std::fs::write(&base_path, pseudo_random(64 * 1024, 1)).unwrap();
+    std::fs::write(&target_path, pseudo_random(64 * 1024, 1)).unwrap();
+    delta::create(&base_path, &target_path, &delta_path).unwrap();
+
+    // 用其他内容作为基础还原，要么失败，要么得到不同的内容（由校验和拒绝）
+    let other = dir.path().join("other.zip");
+    std::fs::write(&other, pseudo_random(64 * 1024, 9)).unwrap();
+    let restored = dir.path().join("restored.zip");
+    let result = delta::apply(&other, &delta_path, &restored);
+    assert!(
+        result.is_err()
+            || std::fs::read(&restored).unwrap() != std::fs::read(&target_path).unwrap()
+    );
+}
+
+#[test]
+fn test_delta_key() {
+    assert_eq!(
+        delta::delta_key("@acme/demo-1.1.0.zip", "1.0.0"),
+        "@acme/demo-1.1.0.zip.deltas/1.0.0.zst"
+    );
+    assert!(delta::is_delta_key("demo-1.1.0.zip.deltas/1.0.0.zst"));
+    assert!(!delta::is_delta_key("demo-1.1.0.zip.sha256"));
+    assert!(!delta::worthwhile(600, 1000));
+}
@@ -0,0 +1,222 @@
+use super::test_helpers::{MockBucket, MockResponse};
+use beepkg::diagnostics::{self, Diagnosis, Stage, Status};
+use beepkg::events::SilentObserver;
+use beepkg::operations::PackageManager;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+#[derive(Clone, Default)]
+struct Behavior {
+    // 所有请求都以 403 和该错误码拒绝
+    auth_error: Option<&'static str>,
+    // PUT 请求返回 403 AccessDenied
+    read_only: bool,
+    // 响应的 Date 头
+    date: Option<String>,
+}
+
+// 诊断用的 bucket：list 返回空列表，PUT 和 DELETE 总是成功，behavior 决定拒绝哪些请求
+async fn upstream(behavior: Behavior) -> MockBucket {
+    MockBucket::with_handler(BTreeMap::new(), move |request, _| {
+        let error = |code: &str| {
+            let body = format!(
+                "<Error><Code>{}</Code><Message>denied</Message></Error>",
+                code
+            );
+            MockResponse::new("403 Forbidden", body)
+        };
+        let response = match (request.method.as_str(), behavior.auth_error) {
+            (_, Some(code)) => error(code),
+            ("PUT", _) if behavior.read_only => error("AccessDenied"),
+            ("PUT", _) => MockResponse::new("200 OK", Vec::new()),
+            ("DELETE", _) => MockResponse::new("204 No Content", Vec::new()),
+            _ => MockResponse::new(
+                "200 OK",
+                "<ListBucketResult><IsTruncated>false</IsTruncated></ListBucketResult>",
+            ),
+        };
+        Some(match &behavior.date {
+            Some(date) => response.header("Date", date.clone()),
+            None => response,
+        })
+    })
+    .await
+}
+
+// 收到的请求的方法和路径，不含查询参数
+fn paths(bucket: &MockBucket) -> Vec<String> {
+    bucket
+        .requests()
+        .iter()
+        .map(|request| request.split('?').next().unwrap_or_default().to_string())
+        .collect()
+}
+
+fn manager(endpoint: String) -> PackageManager {
+    PackageManager::builder()
+        .endpoint(endpoint)
+        .bucket("packages")
+        .timeout(Duration::from_secs(2))
+        .build()
+        .unwrap()
+        .observer(Arc::new(SilentObserver))
+        .cache(None)
+}
+
+fn statuses(diagnosis: &Diagnosis) -> Vec<(Stage, Status)> {
+    diagnosis
+        .stages
+        .iter()
+        .map(|result| (result.stage, result.status))
+        .collect()
+}
+
+#[test]
+fn test_clock_and_errors() {
+    let now = chrono::DateTime::parse_from_rfc3339("2024-06-15T12:00:00Z")
+        .unwrap()
+        .with_timezone(&chrono::Utc);
+    assert_eq!(
+        diagnostics::clock_skew("Sat, 15 Jun 2024 11:59:30 GMT", now),
+        Some(30)
+    );
+    assert_eq!(
+        diagnostics::clock_skew("Sat, 15 Jun 2024 12:20:00 GMT", now),
+        Some(-1200)
+    );
+    assert_eq!(diagnostics::clock_skew("yesterday", now), None);
+    assert_eq!(diagnostics::check_clock(30).0, Status::Ok);
+    assert_eq!(diagnostics::check_clock(-120).0, Status::Warning);
+    let (status, detail) = diagnostics::check_clock(-1200);
+    assert_eq!(status, Status::Failed);
+    assert!(detail.contains("1200s behind"), "{}", detail);
+
+    let body = "<?xml version=\"1.0\"?><Error><Code>SignatureDoesNotMatch</Code></Error>";
+    let code = diagnostics::error_code(body);
+    assert_eq!(code, Some("SignatureDoesNotMatch"));
+    assert!(diagnostics::explain_auth_failure(403, code).contains("secret key"));
+    assert_eq!(diagnostics::error_code("not xml"), None);
+    assert!(diagnostics::explain_auth_failure(404, None).contains("bucket does not exist"));
+}
+
+#[tokio::test]
+async fn test_diagnose_healthy() {
+    let date = chrono::Utc::now()
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string();
+    let bucket = upstream(Behavior {
+        date: Some(date),
+        ..Default::default()
+    })
+    .await;
+    let manager = manager(bucket.endpoint());
+
+    let diagnosis = manager.diagnose(true).await.unwrap();
+    assert_eq!(
+        statuses(&diagnosis),
+        [
+            (Stage::Dns, Status::Ok),
+            (Stage::Tcp, Status::Ok),
+            (Stage::Tls, Status::Skipped),
+            (Stage::Auth, Status::Ok),
+            (Stage::Write, Status::Ok),
+            (Stage::Clock, Status::Ok),
+            (Stage::Latency, Status::Ok),
+        ]
+    );
+    assert!(diagnosis.is_ok());
+    assert!(diagnosis.get(Stage::Latency).unwrap().elapsed_ms.is_some());
+
+    // 探测对象写入后被删除，且以 .tmp 结尾，删除失败时 gc 可以清理
+    let requests = paths(&bucket);
+    let put = requests.iter().find(|r| r.starts_with("PUT ")).unwrap();
+    assert!(put.ends_with(".tmp"), "{}", put);
+    assert!(requests.contains(&put.replacen("PUT", "DELETE", 1)));
+
+    let (connected, _) = manager.test_connection().await.unwrap();
+    assert!(connected);
+}
+
+#[tokio::test]
+async fn test_diagnose_read_only() {
+    let bucket = upstream(Behavior {
+        read_only: true,
+        ..Default::default()
+    })
+    .await;
+    let manager = manager(bucket.endpoint());
+
+    // 只读凭证只是警告；没有 Date 头时跳过时钟检查
+    let diagnosis = manager.diagnose(true).await.unwrap();
+    assert_eq!(diagnosis.get(Stage::Write).unwrap().status, Status::Warning);
+    assert_eq!(diagnosis.get(Stage::Clock).unwrap().status, Status::Skipped);
+    assert!(diagnosis.is_ok());
+
+    bucket.requests.lock().unwrap().clear();
+    let diagnosis = manager.diagnose(false).await.unwrap();
+    assert_eq!(diagnosis.get(Stage::Write).unwrap().status, Status::Skipped);
+    assert!(!bucket.requests().iter().any(|r| r.starts_with("PUT ")));
+}
+
+#[tokio::test]
+async fn test_diagnose_auth_failure() {
+    let bucket = upstream(Behavior {
+        auth_error: Some("InvalidAccessKeyId"),
+        date: Some("Sat, 15 Jun 2024 12:00:00 GMT".to_string()),
+        ..Default::default()
+    })
+    .await;
+    let manager = manager(bucket.endpoint());
+
+    let diagnosis = manager.diagnose(true).await.unwrap();
+    let auth = diagnosis.get(Stage::Auth).unwrap();
+    assert_eq!(auth.status, Status::Failed);
+    assert!(
+        auth.detail.contains("InvalidAccessKeyId"),
+        "{}",
+        auth.detail
+    );
+    assert_eq!(diagnosis.get(Stage::Write).unwrap().status, Status::Skipped);
+    // 错误响应的 Date 仍可用于检查时钟，这里服务器时间远早于本机
+    assert_eq!(diagnosis.get(Stage::Clock).unwrap().status, Status::Failed);
+    assert_eq!(diagnosis.first_failure().unwrap().stage, Stage::Auth);
+    assert!(!bucket.requests().iter().any(|r| r.starts_with("PUT ")));
+
+    let (connected, message) = manager.test_connection().await.unwrap();
+    assert!(!connected);
+    assert!(message.starts_with("Auth:"), "{}", message);
+}
+
+#[tokio::test]
+async fn test_diagnose_unreachable() {
+    // 绑定后立即关闭，得到一个没有监听的端口
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let manager = manager(format!("http://{}", addr));
+
+    let diagnosis = manager.diagnose(true).await.unwrap();
+    assert_eq!(
+        statuses(&diagnosis),
+        [
+            (Stage::Dns, Status::Ok),
+            (Stage::Tcp, Status::Failed),
+            (Stage::Tls, Status::Skipped),
+            (Stage::Auth, Status::Skipped),
+            (Stage::Write, Status::Skipped),
+            (Stage::Clock, Status::Skipped),
+            (Stage::Latency, Status::Skipped),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_diagnose_offline() {
+    let manager = manager("http://127.0.0.1:1".to_string()).offline(true);
+    let error = manager.diagnose(true).await.unwrap_err();
+    assert_eq!(error.exit_code(), 4);
+}
@@ -0,0 +1,179 @@
+use super::test_helpers::MockBucket;
+use beepkg::diagnostics::Status;
+use beepkg::doctor::{self, Check};
+use beepkg::models::RegistryMetadata;
+use beepkg::schema::Versioned;
+use std::collections::BTreeMap;
+use tempfile::TempDir;
+
+fn statuses(checks: &[Check]) -> Vec<Status> {
+    checks.iter().map(|check| check.status).collect()
+}
+
+#[test]
+fn test_check_config() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+
+    // 没有配置文件时使用默认配置
+    assert_eq!(statuses(&doctor::check_config(&path)), [Status::Ok]);
+
+    std::fs::write(
+        &path,
+        "[[registries]]\nname = \"a\"\nendpoint = \"https://a\"\n",
+    )
+    .unwrap();
+    assert_eq!(statuses(&doctor::check_config(&path)), [Status::Ok]);
+
+    std::fs::write(&path, "registries = 3\n").unwrap();
+    let checks = doctor::check_config(&path);
+    assert_eq!(statuses(&checks), [Status::Failed]);
+    assert!(checks[0].fix.as_ref().unwrap().contains("config.toml"));
+
+    std::fs::write(
+        &path,
+        "[[registries]]\nname = \"a\"\nendpoint = \"https://a\"\n\
+         [[registries]]\nname = \"a\"\nendpoint = \"https://b\"\n",
+    )
+    .unwrap();
+    let checks = doctor::check_config(&path);
+    assert_eq!(statuses(&checks), [Status::Failed]);
+    assert!(
+        checks[0].detail.contains("more than once"),
+        "{}",
+        checks[0].detail
+    );
+
+    // 缺失的 TLS 文件和凭证助手逐项报告
+    let ca = dir.path().join("ca.pem");
+    std::fs::write(
+        &path,
+        format!(
+            "credential_helper = \"beepkg-no-such-helper\"\n\
+             [[registries]]\nname = \"a\"\nendpoint = \"https://a\"\n\
+             [registries.tls]\nca_bundle = {:?}\nclient_cert = {:?}\n",
+            ca.display().to_string(),
+            dir.path().join("client.pem").display().to_string(),
+        ),
+    )
+    .unwrap();
+    let checks = doctor::check_config(&path);
+    assert_eq!(
+        statuses(&checks),
+        [
+            Status::Ok,
+            Status::Failed,
+            Status::Failed,
+            Status::Failed,
+            Status::Failed
+        ]
+    );
+    assert!(checks[1].detail.contains("ca.pem"), "{}", checks[1].detail);
+    assert!(
+        checks[3].detail.contains("client_key"),
+        "{}",
+        checks[3].detail
+    );
+    assert!(checks[4].detail.contains("beepkg-no-such-helper"));
+
+    std::fs::write(&ca, "").unwrap();
+    std::fs::write(dir.path().join("client.pem"), "").unwrap();
+    let checks = doctor::check_config(&path);
+    assert_eq!(checks.len(), 3);
+}
+
+#[test]
+fn test_check_encryption() {
+    let dir = TempDir::new().unwrap();
+    assert_eq!(doctor::check_encryption(dir.path()).status, Status::Skipped);
+
+    let metadata = "name = \"demo\"\nversion = \"1.0.0\"\nauthor = \"\"\ndescription = \"\"\n\
+                    includes = []\nexcludes = []\n[dependencies]\n";
+    std::fs::write(dir.path().join("pack.toml"), metadata).unwrap();
+    assert_eq!(doctor::check_encryption(dir.path()).status, Status::Skipped);
+
+    // 按接收者公钥加密时不需要口令
+    std::fs::write(
+        dir.path().join("pack.toml"),
+        format!(
+            "{}[encryption]\nenabled = true\nrecipients = [\"age1qqqq\"]\n",
+            metadata
+        ),
+    )
+    .unwrap();
+    let check = doctor::check_encryption(dir.path());
+    assert_eq!(check.status, Status::Ok);
+    assert!(check.detail.contains("recipients"), "{}", check.detail);
+
+    std::fs::write(dir.path().join("pack.toml"), "name = ").unwrap();
+    assert_eq!(doctor::check_encryption(dir.path()).status, Status::Failed);
+}
+
+#[test]
+fn test_check_cache() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("cache");
+    assert_eq!(doctor::check_cache(&root).status, Status::Ok);
+    assert!(root.is_dir());
+    // 探测文件不会留在缓存中
+    assert_eq!(std::fs::read_dir(&root).unwrap().count(), 0);
+
+    let file = dir.path().join("file");
+    std::fs::write(&file, "").unwrap();
+    let check = doctor::check_cache(&file);
+    assert_eq!(check.status, Status::Warning);
+    assert!(check.fix.unwrap().contains("BEEPKG_CACHE_DIR"));
+}
+
+#[test]
+fn test_check_registry_schema() {
+    assert_eq!(doctor::check_registry_schema(None).status, Status::Ok);
+    assert_eq!(
+        doctor::check_registry_schema(Some(RegistryMetadata::VERSION)).status,
+        Status::Ok
+    );
+    let check = doctor::check_registry_schema(Some(0));
+    assert_eq!(check.status, Status::Warning);
+    assert!(check.fix.unwrap().contains("migrate-metadata"));
+    let check = doctor::check_registry_schema(Some(99));
+    assert_eq!(check.status, Status::Failed);
+    assert_eq!(check.fix.as_deref(), Some("upgrade beepkg"));
+
+    let mut report = doctor::Report::default();
+    report.push(doctor::check_registry_schema(Some(0)));
+    assert!(report.is_ok());
+    report.push(doctor::check_registry_schema(Some(99)));
+    assert_eq!(report.failures(), 1);
+}
+
+#[tokio::test]
+async fn test_registry_schema_version() {
+    let bucket = MockBucket::with_objects(BTreeMap::new()).await;
+    assert_eq!(
+        bucket.manager().registry_schema_version().await.unwrap(),
+        None
+    );
+
+    let bucket = MockBucket::with_objects(BTreeMap::from([(
+        "registry-metadata.json".to_string(),
+        br#"{"schema_version": 7, "registry_name": "future"}"#.to_vec(),
+    )]))
+    .await;
+    // 比当前版本新的元数据也能读出版本号
+    assert_eq!(
+        bucket.manager().registry_schema_version().await.unwrap(),
+        Some(7)
+    );
+    assert_eq!(bucket.requests().len(), 1);
+    assert_eq!(bucket.count("GET", "registry-metadata.json"), 1);
+
+    let bucket = MockBucket::with_objects(BTreeMap::from([(
+        "registry-metadata.json".to_string(),
+        br#"{"registry_name": "old"}"#.to_vec(),
+    )]))
+    .await;
+    assert_eq!(
+        bucket.manager().registry_schema_version().await.unwrap(),
+        Some(0)
+    );
+}
@@ -0,0 +1,84 @@
+use super::test_helpers::{MockBucket, write_package};
+use beepkg::error::BeepkgError;
+
+#[tokio::test]
+async fn test_draft_release() {
+    let bucket = MockBucket::start().await;
+    let alice = bucket.manager().actor("alice");
+    let dir = tempfile::tempdir().unwrap();
+    write_package(dir.path(), "demo", "1.0.0");
+    alice.push_package(dir.path()).await.unwrap();
+
+    write_package(dir.path(), "demo", "1.1.0");
+    bucket
+        .manager()
+        .actor("alice")
+        .draft(true)
+        .channel(Some("stable".to_string()))
+        .push_package(dir.path())
+        .await
+        .unwrap();
+
+    // 草稿的对象都在 drafts/ 下，不在列表中，也不参与解析
+    let keys: Vec<String> = bucket.objects.lock().unwrap().keys().cloned().collect();
+    assert!(keys.contains(&"drafts/demo-1.1.0.zip".to_string()));
+    assert!(
+        !keys.iter().any(|key| key.starts_with("demo-1.1.0")),
+        "{:?}",
+        keys
+    );
+    let packages = alice.list_packages().await.unwrap();
+    assert_eq!(packages.len(), 1);
+    assert_eq!(alice.package_info("demo").await.unwrap().version, "1.0.0");
+    assert!(alice.package_info("demo@1.1.0").await.is_err());
+    assert!(alice.list_channels("demo").await.unwrap().is_empty());
+
+    let drafts = alice.drafts().await.unwrap();
+    assert_eq!(drafts.len(), 1);
+    assert_eq!(drafts[0].channel.as_deref(), Some("stable"));
+
+    alice.release("demo@1.1.0").await.unwrap();
+    let keys: Vec<String> = bucket.objects.lock().unwrap().keys().cloned().collect();
+    assert!(
+        !keys.iter().any(|key| key.starts_with("drafts/")),
+        "{:?}",
+        keys
+    );
+    assert!(keys.contains(&"demo-1.1.0.zip.sha256".to_string()));
+    assert!(alice.drafts().await.unwrap().is_empty());
+    assert_eq!(
+        alice.package_info("demo@stable").await.unwrap().version,
+        "1.1.0"
+    );
+    let output = tempfile::tempdir().unwrap();
+    alice
+        .pull_package("demo@1.1.0", output.path())
+        .await
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(output.path().join("data.txt")).unwrap(),
+        "1.1.0"
+    );
+
+    let err = alice.release("demo@1.1.0").await.unwrap_err();
+    assert!(matches!(err, BeepkgError::NotFound(_)), "{}", err);
+}
+
+#[tokio::test]
+async fn test_draft_already_published() {
+    let bucket = MockBucket::start().await;
+    let alice = bucket.manager().actor("alice");
+    let dir = tempfile::tempdir().unwrap();
+    write_package(dir.path(), "demo", "1.0.0");
+    bucket
+        .manager()
+        .actor("alice")
+        .draft(true)
+        .push_package(dir.path())
+        .await
+        .unwrap();
+    // 草稿发布前同一版本被正常推送时，release 不覆盖已发布的版本
+    alice.push_package(dir.path()).await.unwrap();
+    let err = alice.release("demo@1.0.0").await.unwrap_err();
+    assert!(matches!(err, BeepkgError::Conflict(_)), "{}", err);
+}
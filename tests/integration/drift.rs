@@ -0,0 +1,56 @@
+use super::test_helpers::{MockBucket, write_package};
+use beepkg::drift;
+use beepkg::error::BeepkgError;
+use std::collections::BTreeMap;
+
+#[test]
+fn test_compare() {
+    let manifest = |entries: &[(&str, &str)]| -> BTreeMap<String, String> {
+        entries
+            .iter()
+            .map(|(path, digest)| (path.to_string(), digest.to_string()))
+            .collect()
+    };
+    let published = manifest(&[("a", "1"), ("b", "2"), ("c", "3")]);
+    let report = drift::compare(
+        "demo",
+        "1.0.0",
+        &published,
+        &manifest(&[("a", "1"), ("b", "x"), ("d", "4")]),
+    );
+    assert_eq!(report.modified, ["b"]);
+    assert_eq!(report.added, ["d"]);
+    assert_eq!(report.removed, ["c"]);
+    assert!(!report.is_clean());
+    assert!(drift::compare("demo", "1.0.0", &published, &published).is_clean());
+}
+
+#[tokio::test]
+async fn test_status_against_published_version() {
+    let bucket = MockBucket::start().await;
+    let manager = bucket.manager();
+    let dir = tempfile::tempdir().unwrap();
+    write_package(dir.path(), "demo", "1.0.0");
+    std::fs::create_dir_all(dir.path().join("src")).unwrap();
+    std::fs::write(dir.path().join("src/main.py"), "print('hello')").unwrap();
+    std::fs::write(dir.path().join("config.ini"), "debug = false").unwrap();
+
+    let err = manager.status(dir.path()).await.unwrap_err();
+    assert!(matches!(err, BeepkgError::NotFound(_)), "{}", err);
+
+    manager.push_package(dir.path()).await.unwrap();
+    let report = manager.status(dir.path()).await.unwrap();
+    assert!(report.is_clean(), "{:?}", report);
+    assert_eq!(
+        (report.name.as_str(), report.version.as_str()),
+        ("demo", "1.0.0")
+    );
+
+    std::fs::write(dir.path().join("config.ini"), "debug = true").unwrap();
+    std::fs::write(dir.path().join("src/extra.py"), "pass").unwrap();
+    std::fs::remove_file(dir.path().join("src/main.py")).unwrap();
+    let report = manager.status(dir.path()).await.unwrap();
+    assert_eq!(report.modified, ["config.ini"]);
+    assert_eq!(report.added, ["src/extra.py"]);
+    assert_eq!(report.removed, ["src/main.py"]);
+}
@@ -0,0 +1,93 @@
+use super::test_helpers::MockBucket;
+use beepkg::error::BeepkgError;
+use beepkg::operations::PackageManager;
+use beepkg::security::SecurityError;
+use reqwest::StatusCode;
+
+#[test]
+fn test_status_codes_map_to_error_kinds() {
+    let error = BeepkgError::from_status(StatusCode::UNAUTHORIZED, "Failed to upload demo.zip");
+    assert!(matches!(error, BeepkgError::Auth(_)));
+    assert_eq!(
+        error.to_string(),
+        "Authentication failed: Failed to upload demo.zip: 401 Unauthorized"
+    );
+    assert!(matches!(
+        BeepkgError::from_status(StatusCode::FORBIDDEN, "upload"),
+        BeepkgError::AccessDenied(_)
+    ));
+    assert!(matches!(
+        BeepkgError::from_status(StatusCode::NOT_FOUND, "download"),
+        BeepkgError::NotFound(_)
+    ));
+    assert!(matches!(
+        BeepkgError::from_status(StatusCode::PRECONDITION_FAILED, "upload"),
+        BeepkgError::Conflict(_)
+    ));
+    assert!(matches!(
+        BeepkgError::from_status(StatusCode::SERVICE_UNAVAILABLE, "upload"),
+        BeepkgError::Network(_)
+    ));
+}
+
+#[test]
+fn test_exit_codes() {
+    assert_eq!(BeepkgError::Auth(String::new()).exit_code(), 3);
+    assert_eq!(BeepkgError::Locked(String::new()).exit_code(), 7);
+    assert_eq!(BeepkgError::MissingChecksum.exit_code(), 8);
+    assert_eq!(BeepkgError::Other(String::new()).exit_code(), 1);
+    assert_ne!(
+        BeepkgError::NotFound(String::new()).exit_code(),
+        BeepkgError::Conflict(String::new()).exit_code()
+    );
+}
+
+#[test]
+fn test_boxed_errors_keep_their_kind() {
+    // 其他模块返回的错误转换后保留原有分类
+    let boxed: Box<dyn std::error::Error + Send + Sync> =
+        Box::new(BeepkgError::Locked("demo@1.0.0".to_string()));
+    assert!(matches!(BeepkgError::from(boxed), BeepkgError::Locked(_)));
+
+    let boxed: Box<dyn std::error::Error + Send + Sync> = Box::new(SecurityError::MissingSecret);
+    assert!(matches!(
+        BeepkgError::from(boxed),
+        BeepkgError::Encryption(SecurityError::MissingSecret)
+    ));
+
+    let boxed: Box<dyn std::error::Error + Send + Sync> = "unexpected".into();
+    let error = BeepkgError::from(boxed);
+    assert!(matches!(&error, BeepkgError::Other(message) if message == "unexpected"));
+
+    let error = BeepkgError::from(serde_json::from_str::<u32>("{").unwrap_err());
+    assert!(matches!(error, BeepkgError::MetadataParse(_)));
+}
+
+#[tokio::test]
+async fn test_request_failures_map_to_error_kinds() {
+    // 端口上没有服务，请求在传输层失败
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+    let manager = PackageManager::builder()
+        .endpoint(endpoint)
+        .bucket("packages")
+        .build()
+        .unwrap()
+        .cache(None);
+    let err = manager.list_packages().await.unwrap_err();
+    assert!(matches!(err, BeepkgError::Network(_)), "{}", err);
+
+    let bucket = MockBucket::start().await;
+    let manager = bucket.manager();
+    let err = manager
+        .restore_package_from_backup("demo", "1.0.0", None)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, BeepkgError::NotFound(_)), "{}", err);
+    let err = manager
+        .tag_package("demo@1.0.0", "stable")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, BeepkgError::NotFound(_)), "{}", err);
+}
@@ -0,0 +1,62 @@
+use beepkg::events::{Event, Observer};
+use beepkg::operations::PackageManager;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_event_display() {
+    let event = Event::FileArchived {
+        path: "src/main.rs".into(),
+        bytes: 12,
+        encrypted: false,
+    };
+    assert_eq!(
+        event.to_string(),
+        r#"Copied 12 bytes for file: "src/main.rs""#
+    );
+    assert_eq!(
+        Event::Warning("failed to cache demo-1.0.0.zip".to_string()).to_string(),
+        "Warning: failed to cache demo-1.0.0.zip"
+    );
+    assert_eq!(
+        Event::BytesDownloaded {
+            key: "demo-1.0.0.zip".to_string(),
+            downloaded: 10,
+            total: Some(20),
+        }
+        .to_string(),
+        "Downloaded 10/20 bytes of demo-1.0.0.zip"
+    );
+}
+
+#[test]
+fn test_closure_observer() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let recorder = Arc::clone(&seen);
+    let observer = move |event: &Event| recorder.lock().unwrap().push(event.clone());
+    observer.on_event(&Event::Info("hello".to_string()));
+    assert_eq!(*seen.lock().unwrap(), [Event::Info("hello".to_string())]);
+
+    // 观察者可以替换默认的标准输出
+    let manager = PackageManager::builder()
+        .endpoint("http://localhost:9000")
+        .bucket("packages")
+        .build()
+        .unwrap();
+    let _manager = manager.observer(Arc::new(observer));
+}
+
+#[tokio::test]
+async fn test_channel_observer() {
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    sender.on_event(&Event::DownloadStarted {
+        key: "demo-1.0.0.zip".to_string(),
+    });
+    drop(sender);
+    assert_eq!(
+        receiver.recv().await,
+        Some(Event::DownloadStarted {
+            key: "demo-1.0.0.zip".to_string(),
+        })
+    );
+    assert_eq!(receiver.recv().await, None);
+}
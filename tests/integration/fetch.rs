@@ -0,0 +1,111 @@
+use super::test_helpers::MockBucket;
+use beepkg::checksum::{Checksum, ChecksumAlgorithm};
+use beepkg::error::BeepkgError;
+use futures_util::StreamExt;
+use std::collections::BTreeMap;
+
+// 包文件和校验文件；tampered 时存储的包内容与校验和不符
+fn objects(content: &[u8], tampered: bool) -> BTreeMap<String, Vec<u8>> {
+    let checksum = Checksum::compute(ChecksumAlgorithm::Sha256, content);
+    let mut stored = content.to_vec();
+    if tampered {
+        stored[0] ^= 0xff;
+    }
+    BTreeMap::from([
+        ("demo-1.0.0.zip".to_string(), stored),
+        (
+            "demo-1.0.0.zip.sha256".to_string(),
+            checksum.to_string().into_bytes(),
+        ),
+    ])
+}
+
+// 收集流中的数据，返回数据和最后一个错误
+async fn collect(
+    stream: impl futures_util::Stream<Item = Result<bytes::Bytes, BeepkgError>>,
+) -> (Vec<u8>, Option<BeepkgError>) {
+    let mut stream = std::pin::pin!(stream);
+    let mut content = Vec::new();
+    let mut error = None;
+    while let Some(chunk) = stream.next().await {
+        match chunk {
+            Ok(chunk) => content.extend_from_slice(&chunk),
+            Err(e) => error = Some(e),
+        }
+    }
+    (content, error)
+}
+
+#[tokio::test]
+async fn test_fetch_keeps_archive_intact() {
+    // 内容不必是合法的 ZIP，fetch 不解压
+    let content: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    let bucket = MockBucket::with_objects(objects(&content, false)).await;
+    let manager = bucket.manager();
+
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("out").join("pkg.zip");
+    let checksum = manager.fetch_package("demo@1.0.0", &output).await.unwrap();
+    assert_eq!(
+        checksum,
+        Checksum::compute(ChecksumAlgorithm::Sha256, &content)
+    );
+    assert_eq!(std::fs::read(&output).unwrap(), content);
+    // 输出目录中只有包文件
+    let entries = std::fs::read_dir(output.parent().unwrap()).unwrap().count();
+    assert_eq!(entries, 1);
+
+    let (streamed, error) = collect(manager.fetch_package_stream("demo").await.unwrap()).await;
+    assert!(error.is_none(), "{:?}", error);
+    assert_eq!(streamed, content);
+}
+
+#[tokio::test]
+async fn test_fetch_rejects_tampered_archive() {
+    let content = b"archive content".repeat(100);
+    let bucket = MockBucket::with_objects(objects(&content, true)).await;
+    let manager = bucket.manager();
+
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("pkg.zip");
+    let err = manager
+        .fetch_package("demo@1.0.0", &output)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, BeepkgError::ChecksumMismatch(_)), "{}", err);
+    // 验证失败时不留下文件
+    assert!(!output.exists());
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+    // 流的最后一项是校验错误
+    let (_, error) = collect(manager.fetch_package_stream("demo@1.0.0").await.unwrap()).await;
+    assert!(
+        matches!(error, Some(BeepkgError::ChecksumMismatch(_))),
+        "{:?}",
+        error
+    );
+}
+
+#[tokio::test]
+async fn test_fetch_pinned_digest() {
+    let content = b"pinned".to_vec();
+    let bucket = MockBucket::with_objects(objects(&content, false)).await;
+    let manager = bucket.manager();
+    let other = Checksum::compute(ChecksumAlgorithm::Sha256, b"other");
+
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("pkg.zip");
+    let spec = format!(
+        "demo@{}",
+        Checksum::compute(ChecksumAlgorithm::Sha256, &content)
+    );
+    manager.fetch_package(&spec, &output).await.unwrap();
+    assert_eq!(std::fs::read(&output).unwrap(), content);
+
+    let err = manager
+        .fetch_package_stream(&format!("demo@{}", other))
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(err, BeepkgError::NotFound(_)), "{}", err);
+}
@@ -0,0 +1,91 @@
+use beepkg::foreign::{self, ForeignFormat};
+use std::path::Path;
+
+#[test]
+fn test_detect_format() {
+    assert_eq!(
+        ForeignFormat::detect(Path::new("serde-1.0.0.crate")).unwrap(),
+        ForeignFormat::Crate
+    );
+    assert_eq!(
+        ForeignFormat::detect(Path::new("acme-ui-2.1.0.tgz")).unwrap(),
+        ForeignFormat::Npm
+    );
+    assert_eq!(
+        ForeignFormat::detect(Path::new("requests-2.31.0-py3-none-any.whl")).unwrap(),
+        ForeignFormat::Wheel
+    );
+    assert!(ForeignFormat::detect(Path::new("demo.zip")).is_err());
+}
+
+#[test]
+fn test_crate_metadata() {
+    let metadata = foreign::crate_metadata(
+        r#"
+        [package]
+        name = "demo"
+        version = "0.3.1"
+        authors = ["Alice <alice@example.com>", "Bob"]
+        description = "Demo crate"
+        license = "MIT OR Apache-2.0"
+
+        [dependencies]
+        serde = { version = "1.0", features = ["derive"] }
+        log = "0.4"
+        local = { path = "../local" }
+        "#,
+    )
+    .unwrap();
+    assert_eq!(metadata.name, "demo");
+    assert_eq!(metadata.version, "0.3.1");
+    assert_eq!(metadata.author, "Alice <alice@example.com>, Bob");
+    assert_eq!(metadata.license.as_deref(), Some("MIT OR Apache-2.0"));
+    assert_eq!(metadata.dependencies["serde"], "1.0");
+    assert_eq!(metadata.dependencies["log"], "0.4");
+    assert!(!metadata.dependencies.contains_key("local"));
+}
+
+#[test]
+fn test_npm_metadata() {
+    let metadata = foreign::npm_metadata(
+        r#"{
+            "name": "@acme/ui",
+            "version": "2.1.0",
+            "author": {"name": "Acme", "email": "dev@acme.example"},
+            "license": "ISC",
+            "dependencies": {"react": "^18.2.0"}
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(metadata.name, "@acme/ui");
+    assert_eq!(metadata.author, "Acme");
+    assert_eq!(metadata.license.as_deref(), Some("ISC"));
+    assert_eq!(metadata.dependencies["react"], "^18.2.0");
+}
+
+#[test]
+fn test_wheel_metadata() {
+    let metadata = foreign::wheel_metadata(
+        "Metadata-Version: 2.1\n\
+         Name: internal-tools\n\
+         Version: 1.4.0\n\
+         Summary: Internal tooling\n\
+         Author-email: Team <team@example.com>\n\
+         Requires-Dist: requests (>=2.0,<3)\n\
+         Requires-Dist: click==8.1.7\n\
+         Requires-Dist: pyyaml\n\
+         Requires-Dist: pytest ; extra == 'test'\n\
+         \n\
+         Long description: not a header\n",
+    )
+    .unwrap();
+    assert_eq!(metadata.name, "internal-tools");
+    assert_eq!(metadata.version, "1.4.0");
+    assert_eq!(metadata.description, "Internal tooling");
+    assert_eq!(metadata.author, "Team <team@example.com>");
+    assert_eq!(metadata.dependencies["requests"], ">=2.0,<3");
+    assert_eq!(metadata.dependencies["click"], "=8.1.7");
+    assert_eq!(metadata.dependencies["pyyaml"], "*");
+    assert!(!metadata.dependencies.contains_key("pytest"));
+    assert!(!metadata.dependencies.contains_key("Long description"));
+}
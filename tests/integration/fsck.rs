@@ -0,0 +1,87 @@
+use beepkg::fsck::{self, Issue};
+use beepkg::models::RegistryMetadata;
+use std::collections::HashSet;
+
+fn registry() -> RegistryMetadata {
+    serde_json::from_str(
+        r#"{
+            "registry_name": "demo",
+            "backup_enabled": true,
+            "locked_packages": [
+                {"name": "demo", "version": "1.0.0", "lock_reason": "", "locked_at": "", "locked_by": ""},
+                {"name": "demo", "version": "0.9.0", "lock_reason": "", "locked_at": "", "locked_by": ""},
+                {"name": "demo", "version": "2.0.0", "lock_reason": "", "locked_at": "", "locked_by": "",
+                 "expires_at": "2020-01-01T00:00:00Z"}
+            ],
+            "backups": [
+                {"original_path": "demo-1.0.0.zip", "backup_path": "demo-1.0.0-backup-1.zip", "timestamp": "", "reason": ""},
+                {"original_path": "demo-1.0.0.zip", "backup_path": "demo-1.0.0-backup-2.zip", "timestamp": "", "reason": ""},
+                {"original_path": "demo-1.0.0.zip", "backup_path": "demo-1.0.0-backup-3.zip", "timestamp": "", "reason": "",
+                 "bucket": "offsite"}
+            ],
+            "channels": {"demo": {"stable": "1.0.0", "beta": "3.0.0-rc.1"}},
+            "last_updated": ""
+        }"#,
+    )
+    .unwrap()
+}
+
+const KEYS: [&str; 6] = [
+    "registry-metadata.json",
+    "demo-1.0.0.zip",
+    "demo-1.0.0.zip.sha256",
+    "demo-2.0.0.zip",
+    "demo-2.0.0.zip.artifacts/docs.zip",
+    "demo-1.0.0-backup-1.zip",
+];
+
+#[test]
+fn test_fsck_finds_inconsistencies() {
+    let keys: HashSet<&str> = KEYS.into_iter().collect();
+    let issues = fsck::check(&registry(), &keys, chrono::Utc::now());
+    assert_eq!(
+        issues,
+        vec![
+            Issue::MissingChecksum {
+                archive: "demo-2.0.0.zip".to_string()
+            },
+            Issue::DanglingLock {
+                name: "demo".to_string(),
+                version: "0.9.0".to_string()
+            },
+            Issue::ExpiredLock {
+                name: "demo".to_string(),
+                version: "2.0.0".to_string()
+            },
+            Issue::DanglingChannel {
+                name: "demo".to_string(),
+                channel: "beta".to_string(),
+                version: "3.0.0-rc.1".to_string()
+            },
+            // 独立备份 bucket 中的备份不检查
+            Issue::MissingBackup {
+                backup_path: "demo-1.0.0-backup-2.zip".to_string()
+            },
+        ]
+    );
+    assert!(!issues[0].repairable());
+    assert!(issues[1..].iter().all(Issue::repairable));
+}
+
+#[test]
+fn test_fsck_repair_removes_dangling_records() {
+    let keys: HashSet<&str> = KEYS.into_iter().collect();
+    let mut registry = registry();
+    let issues = fsck::check(&registry, &keys, chrono::Utc::now());
+    assert_eq!(fsck::repair(&mut registry, &issues), 4);
+
+    assert_eq!(registry.locked_packages.len(), 1);
+    assert_eq!(registry.locked_packages[0].version, "1.0.0");
+    assert_eq!(registry.backups.len(), 2);
+    assert_eq!(registry.channels["demo"].len(), 1);
+
+    // 修复后只剩下无法修复的问题
+    let remaining = fsck::check(&registry, &keys, chrono::Utc::now());
+    assert!(remaining.iter().all(|issue| !issue.repairable()));
+    assert_eq!(fsck::repair(&mut registry, &remaining), 0);
+}
@@ -0,0 +1,196 @@
+use super::test_helpers::{MockBucket, MockResponse};
+use beepkg::gc::{self, GarbageKind, StoredObject};
+use beepkg::operations::PackageManager;
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+
+fn object(key: &str, expired: bool) -> StoredObject {
+    StoredObject {
+        key: key.to_string(),
+        size: 10,
+        expired,
+    }
+}
+
+fn kinds(objects: &[StoredObject], backups: &[&str], blobs: &[&str]) -> Vec<(String, GarbageKind)> {
+    let backups: HashSet<&str> = backups.iter().copied().collect();
+    let blobs: HashSet<String> = blobs.iter().map(|blob| blob.to_string()).collect();
+    gc::find(objects, &backups, &blobs)
+        .into_iter()
+        .map(|garbage| (garbage.key, garbage.kind))
+        .collect()
+}
+
+#[test]
+fn test_gc_finds_orphans() {
+    let objects = vec![
+        object("registry-metadata.json", true),
+        object("demo-1.0.0.zip", true),
+        object("demo-1.0.0.zip.sha256", true),
+        object("demo-1.0.0.zip.artifacts/linux-x86_64.zip", true),
+        object("demo-1.0.0.zip.artifacts/linux-x86_64.zip.sha256", true),
+        object("gone-2.0.0.zip.sha256", true),
+        object("gone-2.0.0.zip.artifacts/docs.tar.gz", true),
+        object("broken-1.0.0.zip", true),
+        object("demo-1.0.0-backup-1700000000.zip", true),
+        object("demo-1.0.0-backup-1600000000.zip", true),
+        object("blobs/sha256/aaaa", true),
+        object("blobs/sha256/bbbb", true),
+        object("demo-1.0.0.zip.tmp", true),
+        object("advisories/index.json", true),
+    ];
+    let found = kinds(
+        &objects,
+        &["demo-1.0.0-backup-1700000000.zip"],
+        &["blobs/sha256/aaaa"],
+    );
+    assert_eq!(
+        found,
+        vec![
+            (
+                "gone-2.0.0.zip.sha256".to_string(),
+                GarbageKind::OrphanedSidecar
+            ),
+            (
+                "gone-2.0.0.zip.artifacts/docs.tar.gz".to_string(),
+                GarbageKind::OrphanedSidecar
+            ),
+            (
+                "broken-1.0.0.zip".to_string(),
+                GarbageKind::UnverifiedArchive
+            ),
+            (
+                "demo-1.0.0-backup-1600000000.zip".to_string(),
+                GarbageKind::UnreferencedBackup
+            ),
+            (
+                "blobs/sha256/bbbb".to_string(),
+                GarbageKind::UnreferencedBlob
+            ),
+            ("demo-1.0.0.zip.tmp".to_string(), GarbageKind::TempFile),
+        ]
+    );
+}
+
+#[test]
+fn test_gc_skips_recent_objects() {
+    // 正在推送的包：包文件已上传，校验文件尚未上传
+    let objects = vec![
+        object("demo-1.1.0.zip", false),
+        object("old-1.0.0.zip.sha1", true),
+    ];
+    let found = kinds(&objects, &[], &[]);
+    assert_eq!(
+        found,
+        vec![(
+            "old-1.0.0.zip.sha1".to_string(),
+            GarbageKind::OrphanedSidecar
+        )]
+    );
+}
+
+#[test]
+fn test_gc_keeps_packages_named_like_backups() {
+    // 包名中含有 -backup- 的包不是备份，即使注册表元数据中没有对应的备份记录
+    let objects = vec![
+        object("db-backup-tool-1.0.0.zip", true),
+        object("db-backup-tool-1.0.0.zip.sha256", true),
+        object("db-backup-2.0.0.zip", true),
+        object("db-backup-2.0.0.zip.sha256", true),
+        object("db-backup-tool-1.0.0-backup-1700000000.zip", true),
+        object("db-backup-tool-1.0.0-backup-1600000000.zip", true),
+        object("orphan-backup-1.0.0.zip", true),
+    ];
+    let found = kinds(
+        &objects,
+        &["db-backup-tool-1.0.0-backup-1700000000.zip"],
+        &[],
+    );
+    assert_eq!(
+        found,
+        vec![
+            (
+                "db-backup-tool-1.0.0-backup-1600000000.zip".to_string(),
+                GarbageKind::UnreferencedBackup
+            ),
+            (
+                "orphan-backup-1.0.0.zip".to_string(),
+                GarbageKind::UnverifiedArchive
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_gc_aborts_stale_uploads() {
+    // 一个很早以前开始、一个刚开始的分段上传
+    let recent = chrono::Utc::now().to_rfc3339();
+    let bucket = MockBucket::with_handler(BTreeMap::new(), move |request, _| {
+        (request.method == "GET" && request.key.is_empty() && request.param("uploads").is_some())
+            .then(|| {
+                let xml = format!(
+                    "<ListMultipartUploadsResult><Bucket>packages</Bucket>\
+                     <Upload><Key>big-1.0.0.zip</Key><UploadId>stale</UploadId>\
+                     <Initiated>2020-01-01T00:00:00.000Z</Initiated></Upload>\
+                     <Upload><Key>big-1.1.0.zip</Key><UploadId>active</UploadId>\
+                     <Initiated>{}</Initiated></Upload></ListMultipartUploadsResult>",
+                    recent
+                );
+                MockResponse::new("200 OK", xml)
+            })
+    })
+    .await;
+    let manager = PackageManager::builder()
+        .endpoint(bucket.endpoint())
+        .bucket("packages")
+        .credentials("access-key", "secret-key")
+        .build()
+        .unwrap()
+        .cache(None);
+
+    let report = manager
+        .collect_garbage(Duration::from_secs(3600), true)
+        .await
+        .unwrap();
+    let found: Vec<_> = report
+        .garbage
+        .iter()
+        .map(|garbage| {
+            (
+                garbage.key.as_str(),
+                garbage.kind,
+                garbage.upload_id.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![("big-1.0.0.zip", GarbageKind::StaleUpload, Some("stale"))]
+    );
+    assert_eq!(report.deleted, 1);
+
+    let requests = bucket.requests();
+    // 列出分段上传的请求带签名
+    assert!(
+        requests
+            .iter()
+            .any(|request| request.starts_with("GET /packages/?")
+                && request.contains("&uploads=&")
+                && request.contains("X-Amz-Signature=")),
+        "{:?}",
+        requests
+    );
+    assert!(
+        requests.iter().any(
+            |request| request.starts_with("DELETE /packages/big-1.0.0.zip?")
+                && request.contains("uploadId=stale")
+        ),
+        "{:?}",
+        requests
+    );
+    assert!(
+        !requests
+            .iter()
+            .any(|request| request.contains("uploadId=active"))
+    );
+}
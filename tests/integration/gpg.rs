@@ -0,0 +1,109 @@
+use beepkg::gpg::{self, TrustPolicy};
+use std::os::unix::fs::PermissionsExt;
+
+const SUBKEY: &str = "1111222233334444555566667777888899990000";
+const PRIMARY: &str = "AAAABBBBCCCCDDDDEEEEFFFF0000111122223333";
+
+// 假的 gpg：报告版本 version，记录其余调用的参数，对任何签名都报告由 SUBKEY（主密钥 PRIMARY）签署
+fn fake_gpg(dir: &std::path::Path, version: &str) -> std::path::PathBuf {
+    let program = dir.join("gpg");
+    std::fs::write(
+        &program,
+        format!(
+            "#!/bin/sh\n\
+             if [ \"$1\" = --version ]; then echo 'gpg (GnuPG) {version}'; exit 0; fi\n\
+             echo \"$@\" >> {log}\ncat > /dev/null\n\
+             echo '[GNUPG:] VALIDSIG {sub} 2024-01-01 1704067200 0 4 0 1 10 00 {primary}'\n",
+            log = dir.join("args.log").display(),
+            sub = SUBKEY,
+            primary = PRIMARY
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+    program
+}
+
+#[tokio::test]
+async fn test_gpg_verify_requires_a_trust_policy() {
+    let dir = tempfile::tempdir().unwrap();
+    let program = fake_gpg(dir.path(), "2.4.4");
+    // 只有本测试使用 gpg
+    unsafe { std::env::set_var("BEEPKG_GPG_PROGRAM", &program) };
+    let log = || std::fs::read_to_string(dir.path().join("args.log")).unwrap_or_default();
+
+    // 没有密钥环和指纹时不调用 gpg，避免信任默认密钥环
+    let error = gpg::verify(b"sig", b"sha256:abcd", &TrustPolicy::default())
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("BEEPKG_GPG_KEYRING"),
+        "{}",
+        error
+    );
+    assert_eq!(log(), "");
+
+    // 只指定密钥环时由 gpg 按该密钥环验证
+    let keyring = dir.path().join("trusted.kbx");
+    let policy = TrustPolicy {
+        keyring: Some(keyring.clone()),
+        fingerprints: Vec::new(),
+    };
+    let fingerprint = gpg::verify(b"sig", b"sha256:abcd", &policy).await.unwrap();
+    assert_eq!(fingerprint, SUBKEY);
+    assert!(
+        log().contains(&format!(
+            "--no-default-keyring --keyring {}",
+            keyring.display()
+        )),
+        "{}",
+        log()
+    );
+
+    // 指纹匹配签名子密钥或主密钥，忽略大小写和空格
+    for allowed in [
+        SUBKEY.to_string(),
+        "aaaa bbbb cccc dddd eeee ffff 0000 1111 2222 3333".to_string(),
+    ] {
+        let policy = TrustPolicy {
+            keyring: None,
+            fingerprints: vec![allowed],
+        };
+        gpg::verify(b"sig", b"sha256:abcd", &policy).await.unwrap();
+    }
+    assert!(!log().lines().last().unwrap().contains("--keyring"));
+
+    let policy = TrustPolicy {
+        keyring: Some(keyring),
+        fingerprints: vec!["0123456789ABCDEF0123456789ABCDEF01234567".to_string()],
+    };
+    let error = gpg::verify(b"sig", b"sha256:abcd", &policy)
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("not from a trusted fingerprint"),
+        "{}",
+        error
+    );
+
+    // 找不到 gpg 或版本过低时在签名和验证前报错
+    let policy = TrustPolicy {
+        keyring: Some(dir.path().join("trusted.kbx")),
+        fingerprints: Vec::new(),
+    };
+    unsafe { std::env::set_var("BEEPKG_GPG_PROGRAM", dir.path().join("missing-gpg")) };
+    let error = gpg::verify(b"sig", b"sha256:abcd", &policy)
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("BEEPKG_GPG_PROGRAM"),
+        "{}",
+        error
+    );
+    let calls = log().lines().count();
+    fake_gpg(dir.path(), "1.4.23");
+    unsafe { std::env::set_var("BEEPKG_GPG_PROGRAM", &program) };
+    let error = gpg::sign(None, b"sha256:abcd").await.unwrap_err();
+    assert!(error.to_string().contains("too old"), "{}", error);
+    assert_eq!(log().lines().count(), calls);
+}
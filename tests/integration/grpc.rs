@@ -0,0 +1,118 @@
+use super::test_helpers::{MockBucket, manager_for};
+use beepkg::checksum::{Checksum, ChecksumAlgorithm};
+use beepkg::grpc::{self, proto};
+use beepkg::operations::PackageManager;
+use proto::registry_client::RegistryClient;
+use proto::{pull_response, push_request};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tonic::Code;
+
+const CONTENT: &[u8] = b"demo package content";
+
+// 以 manager 为后端启动 gRPC 服务，返回服务地址
+async fn start_server(manager: PackageManager, token: Option<&str>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let options = grpc::Options {
+        token: token.map(str::to_string),
+    };
+    tokio::spawn(grpc::serve(Arc::new(manager), listener, options));
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_grpc_list_info_and_pull() {
+    // 上游只有 demo-1.0.0.zip 及其校验文件
+    let upstream = MockBucket::with_package("demo", "1.0.0", CONTENT).await;
+    let server = start_server(upstream.manager(), None).await;
+    let mut client = RegistryClient::connect(server).await.unwrap();
+
+    let packages = client
+        .list(proto::ListRequest {
+            query: "DEM".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .packages;
+    assert_eq!(packages.len(), 1);
+    assert_eq!(packages[0].name, "demo");
+    assert_eq!(packages[0].version, "1.0.0");
+
+    let checksum = Checksum::compute(ChecksumAlgorithm::Sha256, CONTENT).to_string();
+    let info = client
+        .info(proto::InfoRequest {
+            name: "demo".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(info.versions.len(), 1);
+    assert_eq!(info.versions[0].checksum, checksum);
+    assert!(!info.versions[0].locked);
+
+    let missing = client
+        .info(proto::InfoRequest {
+            name: "other".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(missing.code(), Code::NotFound);
+
+    let mut stream = client
+        .pull(proto::PullRequest {
+            package: "demo@1.0.0".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let Some(pull_response::Payload::Header(header)) =
+        stream.message().await.unwrap().unwrap().payload
+    else {
+        panic!("expected a header first");
+    };
+    assert_eq!(header.version, "1.0.0");
+    assert_eq!(header.checksum, checksum);
+    assert_eq!(header.size, CONTENT.len() as u64);
+    let mut content = Vec::new();
+    while let Some(message) = stream.message().await.unwrap() {
+        match message.payload {
+            Some(pull_response::Payload::Chunk(chunk)) => content.extend_from_slice(&chunk),
+            _ => panic!("expected content"),
+        }
+    }
+    assert_eq!(content, CONTENT);
+}
+
+#[tokio::test]
+async fn test_grpc_requires_token() {
+    // 上游不可达
+    let server = start_server(manager_for("http://127.0.0.1:1"), Some("secret")).await;
+    let mut client = RegistryClient::connect(server).await.unwrap();
+
+    let denied = client
+        .list(proto::ListRequest::default())
+        .await
+        .unwrap_err();
+    assert_eq!(denied.code(), Code::Unauthenticated);
+
+    let mut request = tonic::Request::new(proto::ListRequest::default());
+    request
+        .metadata_mut()
+        .insert("authorization", "Bearer secret".parse().unwrap());
+    let unavailable = client.list(request).await.unwrap_err();
+    assert_eq!(unavailable.code(), Code::Unavailable);
+}
+
+#[tokio::test]
+async fn test_grpc_push_requires_header() {
+    let server = start_server(manager_for("http://127.0.0.1:1"), None).await;
+    let mut client = RegistryClient::connect(server).await.unwrap();
+
+    let messages = futures_util::stream::iter(vec![proto::PushRequest {
+        payload: Some(push_request::Payload::Chunk(b"zip".to_vec().into())),
+    }]);
+    let error = client.push(messages).await.unwrap_err();
+    assert_eq!(error.code(), Code::InvalidArgument);
+}
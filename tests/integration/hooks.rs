@@ -0,0 +1,91 @@
+use beepkg::events::{Event, SilentObserver};
+use beepkg::hooks::{self, Hook};
+use beepkg::models::PackageMetadata;
+use std::sync::Mutex;
+
+fn metadata() -> PackageMetadata {
+    toml::from_str(
+        r#"
+        name = "codegen"
+        version = "0.3.0"
+        author = "dev@company.com"
+        description = "Generated bindings"
+        includes = ["**/*"]
+        excludes = []
+
+        [dependencies]
+
+        [hooks]
+        pre_push = "scripts/build.sh"
+        post_pull = "scripts/setup.sh"
+        "#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_hook_scripts_must_stay_in_package() {
+    assert!(hooks::validate_script("scripts/build.sh").is_ok());
+    assert!(hooks::validate_script("./build.sh").is_ok());
+    assert!(hooks::validate_script("../build.sh").is_err());
+    assert!(hooks::validate_script("/usr/bin/env").is_err());
+    assert!(hooks::validate_script("").is_err());
+
+    let mut metadata = metadata();
+    metadata.validate().unwrap();
+    metadata.hooks.post_pull = Some("../../escape.sh".to_string());
+    assert!(metadata.validate().is_err());
+}
+
+#[tokio::test]
+async fn test_hooks_skipped_unless_allowed() {
+    // 脚本不存在也不会报错，因为未允许时不会运行
+    let dir = tempfile::tempdir().unwrap();
+    let events = Mutex::new(Vec::new());
+    let observer = |event: &Event| events.lock().unwrap().push(event.to_string());
+    hooks::run(Hook::PrePush, &metadata(), dir.path(), false, &observer)
+        .await
+        .unwrap();
+    let events = events.into_inner().unwrap();
+    assert_eq!(events.len(), 1);
+    assert!(events[0].contains("--allow-hooks"), "{}", events[0]);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_hook_runs_in_package_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("scripts")).unwrap();
+    std::fs::write(
+        dir.path().join("scripts/build.sh"),
+        "echo \"$BEEPKG_HOOK $BEEPKG_PACKAGE_NAME@$BEEPKG_PACKAGE_VERSION\" > built.txt\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("scripts/setup.sh"), "exit 3\n").unwrap();
+
+    hooks::run(
+        Hook::PrePush,
+        &metadata(),
+        dir.path(),
+        true,
+        &SilentObserver,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("built.txt")).unwrap(),
+        "pre_push codegen@0.3.0\n"
+    );
+
+    assert!(
+        hooks::run(
+            Hook::PostPull,
+            &metadata(),
+            dir.path(),
+            true,
+            &SilentObserver
+        )
+        .await
+        .is_err()
+    );
+}
@@ -0,0 +1,147 @@
+use super::test_helpers::MockBucket;
+use beepkg::error::BeepkgError;
+use beepkg::models::RegistryMetadata;
+use std::collections::BTreeMap;
+
+// 开启了不可变发布的 bucket，已有 demo-1.0.0.zip
+async fn upstream() -> MockBucket {
+    MockBucket::with_objects(BTreeMap::from([
+        (
+            "registry-metadata.json".to_string(),
+            br#"{"registry_name": "demo", "backup_enabled": false,
+                 "locked_packages": [], "backups": [], "last_updated": "",
+                 "immutable": true}"#
+                .to_vec(),
+        ),
+        ("demo-1.0.0.zip".to_string(), b"zip".to_vec()),
+    ]))
+    .await
+}
+
+// bucket 收到的写入请求
+fn writes(bucket: &MockBucket) -> Vec<String> {
+    bucket
+        .requests()
+        .into_iter()
+        .filter(|request| !request.starts_with("GET ") && !request.starts_with("HEAD "))
+        .collect()
+}
+
+fn package(version: &str) -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("pack.toml"),
+        format!(
+            "name = \"demo\"\nversion = \"{}\"\nauthor = \"\"\ndescription = \"\"\n\
+             includes = []\nexcludes = []\n\n[dependencies]\n",
+            version
+        ),
+    )
+    .unwrap();
+    dir
+}
+
+#[test]
+fn test_immutable_defaults_to_off() {
+    let registry: RegistryMetadata = serde_json::from_str(
+        r#"{"registry_name": "demo", "backup_enabled": false, "locked_packages": [],
+            "backups": [], "last_updated": ""}"#,
+    )
+    .unwrap();
+    assert!(!registry.immutable);
+    let json = serde_json::to_string(&registry).unwrap();
+    assert!(!json.contains("immutable"));
+}
+
+#[tokio::test]
+async fn test_immutable_registry_rejects_force_push() {
+    let bucket = upstream().await;
+    let manager = bucket.manager();
+
+    let existing = package("1.0.0");
+    let err = manager
+        .force_push_package(existing.path())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, BeepkgError::Conflict(_)), "{}", err);
+    assert!(err.to_string().contains("immutable"));
+    assert!(writes(&bucket).is_empty());
+
+    // 新版本（即使低于已有版本）仍可以强制推送
+    let older = package("0.9.0");
+    manager.force_push_package(older.path()).await.unwrap();
+    assert!(bucket.object("demo-0.9.0.zip").is_some());
+}
+
+#[tokio::test]
+async fn test_immutable_registry_rejects_restore_over_existing_version() {
+    let bucket = MockBucket::with_objects(BTreeMap::from([
+        (
+            "registry-metadata.json".to_string(),
+            br#"{"registry_name": "demo", "backup_enabled": true, "locked_packages": [],
+                 "backups": [{"original_path": "demo-1.0.0.zip",
+                              "backup_path": "demo-1.0.0-backup-1714564800.zip",
+                              "timestamp": "2024-05-01T12:00:00+00:00", "reason": ""}],
+                 "last_updated": "", "immutable": true}"#
+                .to_vec(),
+        ),
+        ("demo-1.0.0.zip".to_string(), b"zip".to_vec()),
+        (
+            "demo-1.0.0-backup-1714564800.zip".to_string(),
+            b"old zip".to_vec(),
+        ),
+    ]))
+    .await;
+    let manager = bucket.manager();
+
+    let err = manager
+        .restore_package_from_backup("demo", "1.0.0", None)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, BeepkgError::Conflict(_)), "{}", err);
+    assert!(writes(&bucket).is_empty());
+    assert_eq!(bucket.object("demo-1.0.0.zip").unwrap(), b"zip");
+
+    // 已删除的版本仍可以从备份恢复
+    bucket.objects.lock().unwrap().remove("demo-1.0.0.zip");
+    manager
+        .restore_package_from_backup("demo", "1.0.0", None)
+        .await
+        .unwrap();
+    assert_eq!(bucket.object("demo-1.0.0.zip").unwrap(), b"old zip");
+}
+
+#[cfg(feature = "archives")]
+#[tokio::test]
+async fn test_immutable_registry_rejects_snapshot_over_existing_version() {
+    use beepkg::bundle::BundleWriter;
+    use beepkg::snapshot::{self, SnapshotManifest, SnapshotObject};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("snapshot.tar.zst");
+    let manifest = SnapshotManifest {
+        format_version: snapshot::FORMAT_VERSION,
+        created_at: "2024-01-01T00:00:00Z".to_string(),
+        source: "https://minio.example.com/packages/".to_string(),
+        include_blobs: true,
+        objects: vec![SnapshotObject {
+            key: "demo-1.0.0.zip".to_string(),
+            size: 7,
+            included: true,
+        }],
+    };
+    let mut writer =
+        BundleWriter::create_with_manifest(&path, snapshot::MANIFEST_PATH, &manifest).unwrap();
+    writer.append_bytes("demo-1.0.0.zip", b"new zip").unwrap();
+    writer.finish().unwrap();
+
+    let bucket = upstream().await;
+    let err = bucket
+        .manager()
+        .restore_snapshot(&path, true)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, BeepkgError::Conflict(_)), "{}", err);
+    assert!(writes(&bucket).is_empty());
+    assert_eq!(bucket.object("demo-1.0.0.zip").unwrap(), b"zip");
+}
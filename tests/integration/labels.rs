@@ -0,0 +1,104 @@
+use super::test_helpers::{MockBucket, write_package};
+use beepkg::error::BeepkgError;
+use beepkg::listing::{Filter, ListQuery};
+use beepkg::models;
+use std::collections::BTreeMap;
+
+fn labels(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_parse_labels() {
+    assert_eq!(
+        models::parse_label("git_sha=1a2b3c").unwrap(),
+        ("git_sha".to_string(), "1a2b3c".to_string())
+    );
+    // 值中可以包含 =
+    assert_eq!(models::parse_label("ci/build=a=b").unwrap().1, "a=b");
+    assert!(models::parse_label("qa").is_err());
+    assert!(models::parse_label("=passed").is_err());
+    assert!(models::parse_label("q a=passed").is_err());
+
+    let filter: Filter = "label:qa=passed".parse().unwrap();
+    assert!(filter.field.needs_details());
+    assert!("label:=passed".parse::<Filter>().is_err());
+}
+
+#[tokio::test]
+async fn test_annotate() {
+    let bucket = MockBucket::start().await;
+    let manager = bucket.manager();
+    let dir = tempfile::tempdir().unwrap();
+    for version in ["1.0.0", "1.1.0"] {
+        write_package(dir.path(), "demo", version);
+        manager.push_package(dir.path()).await.unwrap();
+    }
+    let archive = bucket.object("demo-1.0.0.zip").unwrap();
+
+    let updated = manager
+        .annotate(
+            "demo@1.0.0",
+            &labels(&[("git_sha", "1a2b3c"), ("qa", "pending")]),
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(updated, labels(&[("git_sha", "1a2b3c"), ("qa", "pending")]));
+
+    // 注册表不可变时也能修改标签，包文件不变
+    manager.set_immutable(true).await.unwrap();
+    let updated = manager
+        .annotate(
+            "demo@1.0.0",
+            &labels(&[("qa", "passed")]),
+            &["git_sha".to_string()],
+        )
+        .await
+        .unwrap();
+    assert_eq!(updated, labels(&[("qa", "passed")]));
+    assert_eq!(bucket.object("demo-1.0.0.zip").unwrap(), archive);
+    let info = manager.package_info("demo@1.0.0").await.unwrap();
+    assert_eq!(info.labels, labels(&[("qa", "passed")]));
+
+    let query = ListQuery {
+        filters: vec!["label:qa=passed".parse().unwrap()],
+        ..Default::default()
+    };
+    let packages = manager.query_packages(&query).await.unwrap();
+    assert_eq!(packages.len(), 1);
+    assert_eq!(packages[0].version, "1.0.0");
+    assert_eq!(packages[0].labels, labels(&[("qa", "passed")]));
+
+    // 没有该标签的版本满足 !=
+    let query = ListQuery {
+        filters: vec!["label:qa!=passed".parse().unwrap()],
+        ..Default::default()
+    };
+    let packages = manager.query_packages(&query).await.unwrap();
+    assert_eq!(packages.len(), 1);
+    assert_eq!(packages[0].version, "1.1.0");
+}
+
+#[tokio::test]
+async fn test_annotate_errors() {
+    let bucket = MockBucket::start().await;
+    let manager = bucket.manager();
+    let dir = tempfile::tempdir().unwrap();
+    write_package(dir.path(), "demo", "1.0.0");
+    manager.push_package(dir.path()).await.unwrap();
+
+    let set = labels(&[("qa", "passed")]);
+    let err = manager.annotate("demo@2.0.0", &set, &[]).await.unwrap_err();
+    assert!(matches!(err, BeepkgError::NotFound(_)), "{}", err);
+    // 需要明确的版本
+    assert!(manager.annotate("demo", &set, &[]).await.is_err());
+    let err = manager
+        .annotate("demo@1.0.0", &labels(&[("bad key", "x")]), &[])
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("bad key"), "{}", err);
+}
@@ -0,0 +1,137 @@
+use super::test_helpers::MockBucket;
+use beepkg::error::BeepkgError;
+use beepkg::license::LicensePolicy;
+
+fn policy(content: &str) -> LicensePolicy {
+    toml::from_str(content).unwrap()
+}
+
+#[test]
+fn test_license_policy_deny() {
+    let policy = policy(r#"deny = ["GPL-3.0", "AGPL-3.0"]"#);
+    assert!(policy.evaluate(Some("MIT")).is_none());
+    assert!(policy.evaluate(None).is_none());
+    // 版本后缀和大小写不影响匹配
+    assert!(policy.evaluate(Some("GPL-3.0-only")).is_some());
+    assert!(policy.evaluate(Some("gpl-3.0-or-later")).is_some());
+    assert!(policy.evaluate(Some("GPL-3.0+")).is_some());
+    assert!(policy.evaluate(Some("GPL-2.0-only")).is_none());
+
+    // OR 只要有一个可选许可证满足，AND 要求全部满足
+    assert!(policy.evaluate(Some("MIT OR GPL-3.0-only")).is_none());
+    assert!(policy.evaluate(Some("MIT AND GPL-3.0-only")).is_some());
+    assert!(
+        policy
+            .evaluate(Some("(MIT AND AGPL-3.0) OR Apache-2.0"))
+            .is_none()
+    );
+    assert!(
+        policy
+            .evaluate(Some("Apache-2.0 AND (MIT OR GPL-3.0)"))
+            .is_none()
+    );
+    assert!(
+        policy
+            .evaluate(Some("GPL-3.0-or-later WITH GCC-exception-3.1"))
+            .is_some()
+    );
+    let violation = policy.evaluate(Some("AGPL-3.0-only")).unwrap();
+    assert!(violation.contains("AGPL-3.0-only"));
+}
+
+#[test]
+fn test_license_policy_allow() {
+    let policy = policy(
+        r#"
+allow = ["MIT", "Apache-2.0"]
+deny_unknown = true
+"#,
+    );
+    assert!(policy.evaluate(Some("Apache-2.0")).is_none());
+    assert!(policy.evaluate(Some("BSD-3-Clause")).is_some());
+    assert!(policy.evaluate(Some("BSD-3-Clause OR MIT")).is_none());
+    assert_eq!(
+        policy.evaluate(None).as_deref(),
+        Some("no license declared")
+    );
+    assert!(policy.evaluate(Some("  ")).is_some());
+    // 无法解析的表达式按单个许可证名检查
+    assert!(policy.evaluate(Some("MIT AND")).is_some());
+
+    assert!(toml::from_str::<LicensePolicy>("forbid = [\"MIT\"]").is_err());
+}
+
+fn package(license: Option<&str>) -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let license = license
+        .map(|license| format!("license = \"{}\"\n", license))
+        .unwrap_or_default();
+    std::fs::write(
+        dir.path().join("pack.toml"),
+        format!(
+            "name = \"demo\"\nversion = \"1.0.0\"\nauthor = \"alice\"\n\
+             description = \"Demo\"\n{}includes = []\nexcludes = []\n\n[dependencies]\n",
+            license
+        ),
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("main.txt"), "demo").unwrap();
+    dir
+}
+
+#[tokio::test]
+async fn test_license_recorded_and_enforced_on_pull() {
+    let bucket = MockBucket::start().await;
+    let manager = bucket.manager();
+
+    let dir = package(Some("GPL-3.0-only"));
+    manager.push_package(dir.path()).await.unwrap();
+    let sidecar = bucket.object("demo-1.0.0.zip.meta.json").unwrap();
+    let sidecar: serde_json::Value = serde_json::from_slice(&sidecar).unwrap();
+    assert_eq!(sidecar["license"], "GPL-3.0-only");
+
+    let info = manager.package_info("demo").await.unwrap();
+    assert_eq!(info.version, "1.0.0");
+    assert_eq!(info.author, "alice");
+    assert_eq!(info.license.as_deref(), Some("GPL-3.0-only"));
+
+    // 没有元数据摘要时从归档读取
+    bucket
+        .objects
+        .lock()
+        .unwrap()
+        .remove("demo-1.0.0.zip.meta.json");
+    let info = manager.package_info("demo@1.0.0").await.unwrap();
+    assert_eq!(info.license.as_deref(), Some("GPL-3.0-only"));
+
+    let output = tempfile::tempdir().unwrap();
+    let denied = manager
+        .license_policy(Some(policy(r#"deny = ["GPL-3.0"]"#)))
+        .pull_package("demo@1.0.0", output.path())
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(denied, BeepkgError::PolicyViolation(_)),
+        "{}",
+        denied
+    );
+    assert!(denied.to_string().contains("GPL-3.0-only"));
+    assert!(!output.path().join("main.txt").exists());
+}
+
+#[tokio::test]
+async fn test_license_policy_allows_matching_package() {
+    let bucket = MockBucket::start().await;
+    let manager = bucket
+        .manager()
+        .license_policy(Some(policy(r#"allow = ["MIT"]"#)));
+
+    let dir = package(Some("MIT"));
+    manager.push_package(dir.path()).await.unwrap();
+    let output = tempfile::tempdir().unwrap();
+    manager
+        .pull_package("demo@1.0.0", output.path())
+        .await
+        .unwrap();
+    assert!(output.path().join("main.txt").exists());
+}
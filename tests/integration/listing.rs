@@ -0,0 +1,281 @@
+use super::test_helpers::MockBucket;
+use beepkg::cache::{Cache, CacheRef};
+use beepkg::checksum::{Checksum, ChecksumAlgorithm};
+use beepkg::listing::{self, Column, Filter, ListFormat, ListQuery, SortKey};
+use beepkg::models::Package;
+use std::collections::BTreeMap;
+
+fn package(name: &str, version: &str, size: u64, date: &str, author: &str) -> Package {
+    serde_json::from_value(serde_json::json!({
+        "name": name,
+        "version": version,
+        "author": author,
+        "description": "",
+        "keywords": ["ml"],
+        "dependencies": {},
+        "storage": {"path": format!("{}-{}.zip", name, version), "size": size, "created_at": date},
+    }))
+    .unwrap()
+}
+
+fn specs(packages: &[Package]) -> Vec<String> {
+    packages
+        .iter()
+        .map(|p| format!("{}@{}", p.name, p.version))
+        .collect()
+}
+
+#[test]
+fn test_filters() {
+    let ml = package("ml-core", "1.0.0", 10, "", "Alice <alice@example.com>");
+    let web = package("web", "2.0.0", 10, "", "Bob");
+
+    let filter: Filter = "name~ML-*".parse().unwrap();
+    assert!(filter.matches(&ml));
+    assert!(!filter.matches(&web));
+    let filter: Filter = "version!=2.0.0".parse().unwrap();
+    assert!(filter.matches(&ml) && !filter.matches(&web));
+    let filter: Filter = "keyword=ml".parse().unwrap();
+    assert!(filter.matches(&ml) && filter.matches(&web));
+
+    assert!("name".parse::<Filter>().is_err());
+    assert!("owner=bob".parse::<Filter>().is_err());
+    assert!("name!~x".parse::<Filter>().is_err());
+    assert!("size".parse::<SortKey>().is_ok());
+    assert!("nme".parse::<Column>().is_err());
+    assert!("locked".parse::<Column>().unwrap().needs_markers());
+    assert_eq!("JSON".parse::<ListFormat>().unwrap(), ListFormat::Json);
+    assert!("yaml".parse::<ListFormat>().is_err());
+
+    let query = ListQuery {
+        author: Some("alice".to_string()),
+        ..Default::default()
+    };
+    assert!(query.needs_details());
+    assert!(query.matches(&ml) && !query.matches(&web));
+}
+
+fn sample() -> Vec<Package> {
+    vec![
+        package("b", "1.10.0", 30, "2024-03-01T00:00:00.000Z", ""),
+        package("a", "2.0.0", 10, "2024-02-01T00:00:00.000Z", ""),
+        package("b", "1.9.0", 20, "2024-01-01T00:00:00.000Z", ""),
+    ]
+}
+
+#[test]
+fn test_sort_and_page() {
+    let query = ListQuery::default();
+    assert_eq!(
+        specs(&query.arrange(sample())),
+        ["a@2.0.0", "b@1.9.0", "b@1.10.0"]
+    );
+    let query = ListQuery {
+        sort: SortKey::Date,
+        reverse: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        specs(&query.arrange(sample())),
+        ["b@1.10.0", "a@2.0.0", "b@1.9.0"]
+    );
+    let query = ListQuery {
+        sort: SortKey::Size,
+        offset: 1,
+        limit: Some(1),
+        ..Default::default()
+    };
+    assert_eq!(specs(&query.arrange(sample())), ["b@1.9.0"]);
+
+    // 守护进程以 JSON 传入同样的参数
+    let query: ListQuery = serde_json::from_value(serde_json::json!({
+        "filters": ["name~b"], "sort": "size", "limit": 5
+    }))
+    .unwrap();
+    assert_eq!(
+        (query.filters.len(), query.sort, query.limit),
+        (1, SortKey::Size, Some(5))
+    );
+    assert!(serde_json::from_value::<ListQuery>(serde_json::json!({"sort": "weight"})).is_err());
+}
+
+fn info(name: &str, version: &str, author: &str) -> Vec<u8> {
+    serde_json::to_vec(&serde_json::json!({
+        "name": name, "version": version, "author": author, "description": "",
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_query_packages() {
+    let bucket = MockBucket::with_objects(BTreeMap::from([
+        ("ml-core-1.0.0.zip".to_string(), vec![0u8; 300]),
+        (
+            "ml-core-1.0.0.zip.meta.json".to_string(),
+            info("ml-core", "1.0.0", "Alice"),
+        ),
+        ("ml-utils-0.1.0.zip".to_string(), vec![0u8; 100]),
+        (
+            "ml-utils-0.1.0.zip.meta.json".to_string(),
+            info("ml-utils", "0.1.0", "Bob"),
+        ),
+        ("web-2.0.0.zip".to_string(), vec![0u8; 200]),
+        (
+            "web-2.0.0.zip.meta.json".to_string(),
+            info("web", "2.0.0", "Alice"),
+        ),
+    ]))
+    .await;
+    let manager = bucket.manager();
+
+    let query = ListQuery {
+        filters: vec!["name~ml-*".parse().unwrap()],
+        sort: SortKey::Size,
+        ..Default::default()
+    };
+    let packages = manager.query_packages(&query).await.unwrap();
+    assert_eq!(specs(&packages), ["ml-utils@0.1.0", "ml-core@1.0.0"]);
+    // 只按名称过滤时不读取元数据
+    assert!(!bucket.requests().iter().any(|r| r.contains(".meta.json")));
+
+    // 按作者过滤时只读取名称匹配的包的元数据
+    let query = ListQuery {
+        filters: vec!["name~ml-*".parse().unwrap()],
+        author: Some("alice".to_string()),
+        ..Default::default()
+    };
+    let packages = manager.query_packages(&query).await.unwrap();
+    assert_eq!(specs(&packages), ["ml-core@1.0.0"]);
+    assert_eq!(packages[0].author, "Alice");
+    let requests = bucket.requests();
+    assert!(
+        !requests
+            .iter()
+            .any(|r| r.contains("web-2.0.0.zip.meta.json"))
+    );
+}
+
+#[tokio::test]
+async fn test_list_records_package_names() {
+    let bucket = MockBucket::with_objects(BTreeMap::from([
+        ("ml-core-1.0.0.zip".to_string(), vec![0u8; 300]),
+        ("ml-core-1.1.0.zip".to_string(), vec![0u8; 300]),
+        ("web-2.0.0.zip".to_string(), vec![0u8; 200]),
+    ]))
+    .await;
+    let dir = tempfile::tempdir().unwrap();
+    let manager = bucket
+        .manager()
+        .cache(Some(Cache::new(dir.path().to_path_buf())));
+    assert!(manager.complete_packages("").is_empty());
+
+    manager.list_packages().await.unwrap();
+    assert_eq!(manager.complete_packages(""), ["ml-core", "web"]);
+    assert_eq!(manager.complete_packages("ml"), ["ml-core"]);
+
+    // 版本只从缓存过的包文件中补全，较新的在前
+    let registry = std::fs::read_dir(dir.path().join("refs"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .file_name()
+        .into_string()
+        .unwrap();
+    let cache = Cache::new(dir.path().to_path_buf());
+    for (key, content) in [("ml-core-1.0.0.zip", b"one"), ("ml-core-1.1.0.zip", b"two")] {
+        let path = dir.path().join("download");
+        std::fs::write(&path, content).unwrap();
+        let checksum = Checksum::compute(ChecksumAlgorithm::Sha256, content);
+        cache.store(&checksum, &path).unwrap();
+        let entry = CacheRef {
+            checksum: checksum.to_string(),
+            signer: None,
+            cached_at: "2024-01-01T00:00:00Z".to_string(),
+        };
+        cache.record(&registry, key, &entry).unwrap();
+    }
+    assert_eq!(
+        manager.complete_packages("ml-core@"),
+        ["ml-core@1.1.0", "ml-core@1.0.0"]
+    );
+    assert_eq!(manager.complete_packages("ml-core@1.0"), ["ml-core@1.0.0"]);
+    assert!(manager.complete_packages("web@").is_empty());
+}
+
+#[test]
+fn test_relative_time() {
+    let now = chrono::DateTime::parse_from_rfc3339("2024-06-15T12:00:00Z")
+        .unwrap()
+        .with_timezone(&chrono::Utc);
+    let cases = [
+        ("2024-06-15T11:59:30Z", "just now"),
+        ("2024-06-15T12:00:05Z", "just now"),
+        ("2024-06-15T11:59:00.000Z", "1 minute ago"),
+        ("2024-06-15T09:00:00Z", "3 hours ago"),
+        ("2024-06-14T12:00:00Z", "1 day ago"),
+        ("2024-04-01T00:00:00Z", "2 months ago"),
+        ("2021-01-01T00:00:00Z", "3 years ago"),
+        ("yesterday", "yesterday"),
+    ];
+    for (timestamp, expected) in cases {
+        assert_eq!(
+            listing::relative_time(timestamp, now),
+            expected,
+            "{}",
+            timestamp
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_query_markers() {
+    let registry = serde_json::json!({
+        "registry_name": "test",
+        "backup_enabled": false,
+        "locked_packages": [
+            {"name": "demo", "version": "1.0.0", "lock_reason": "release", "locked_at": "", "locked_by": "ci"},
+            {"name": "demo", "version": "1.1.0", "lock_reason": "old", "locked_at": "", "locked_by": "ci",
+             "expires_at": "2000-01-01T00:00:00Z"},
+        ],
+        "backups": [],
+        "last_updated": "",
+        "channels": {"demo": {"stable": "1.0.0", "lts": "1.0.0", "beta": "1.1.0"}},
+    });
+    let bucket = MockBucket::with_objects(BTreeMap::from([
+        ("demo-1.0.0.zip".to_string(), vec![0u8; 10]),
+        ("demo-1.1.0.zip".to_string(), vec![0u8; 10]),
+        ("demo-1.2.0.zip".to_string(), vec![0u8; 10]),
+        (
+            "registry-metadata.json".to_string(),
+            serde_json::to_vec(&registry).unwrap(),
+        ),
+    ]))
+    .await;
+    let manager = bucket.manager();
+
+    let query = ListQuery {
+        markers: true,
+        ..Default::default()
+    };
+    let packages = manager.query_packages(&query).await.unwrap();
+    let markers: Vec<(bool, String)> = packages
+        .iter()
+        .map(|p| (p.is_locked, Column::Channels.value(p)))
+        .collect();
+    // 过期的锁不算锁定
+    assert_eq!(
+        markers,
+        [
+            (true, "lts,stable".to_string()),
+            (false, "beta".to_string()),
+            (false, String::new()),
+        ]
+    );
+    assert_eq!(packages[0].lock_reason.as_deref(), Some("release"));
+    assert_eq!(Column::Locked.value(&packages[0]), "locked");
+
+    // 不需要标记时不填充
+    let packages = manager.query_packages(&ListQuery::default()).await.unwrap();
+    assert!(!packages[0].is_locked && packages[0].channels.is_empty());
+}
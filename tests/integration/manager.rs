@@ -0,0 +1,107 @@
+use super::test_helpers::{MockBucket, write_package};
+use beepkg::error::BeepkgError;
+use beepkg::operations::PackageManager;
+use std::time::Duration;
+
+#[test]
+fn test_builder_validation() {
+    let manager = PackageManager::builder()
+        .endpoint("minio.example.com:9000")
+        .bucket("packages")
+        .credentials("access", "secret")
+        .region(Some("eu-west-1".to_string()))
+        .timeout(Duration::from_secs(5))
+        .build();
+    assert!(manager.is_ok());
+
+    let missing_endpoint = PackageManager::builder().bucket("packages").build();
+    assert!(matches!(missing_endpoint, Err(BeepkgError::Config(_))));
+
+    let missing_bucket = PackageManager::builder()
+        .endpoint("http://localhost:9000")
+        .bucket(" ")
+        .build();
+    assert!(matches!(missing_bucket, Err(BeepkgError::Config(_))));
+
+    // 只提供一半的凭证通常是配置错误
+    let half_credentials = PackageManager::builder()
+        .endpoint("http://localhost:9000")
+        .bucket("packages")
+        .credentials("access", "")
+        .build();
+    assert!(matches!(half_credentials, Err(BeepkgError::Config(_))));
+
+    let zero_timeout = PackageManager::builder()
+        .endpoint("http://localhost:9000")
+        .bucket("packages")
+        .timeout(Duration::ZERO)
+        .build();
+    assert!(matches!(zero_timeout, Err(BeepkgError::Config(_))));
+}
+
+#[tokio::test]
+async fn test_stream_packages_reports_errors() {
+    use futures_util::StreamExt;
+
+    let manager = PackageManager::builder()
+        .endpoint("http://127.0.0.1:1")
+        .bucket("packages")
+        .timeout(Duration::from_secs(1))
+        .build()
+        .unwrap();
+
+    // 流在第一次轮询时才发起请求，请求失败后结束
+    let mut packages = Box::pin(manager.stream_packages());
+    assert!(packages.next().await.unwrap().is_err());
+    assert!(packages.next().await.is_none());
+}
+
+// 绑定和嵌入方会在多线程运行时上 spawn 这些操作
+#[test]
+fn test_operations_are_send() {
+    fn assert_send<T: Send>(_: &T) {}
+
+    let manager = PackageManager::builder()
+        .endpoint("http://localhost:9000")
+        .bucket("packages")
+        .build()
+        .unwrap();
+    let path = std::path::Path::new("pkg");
+    assert_send(&manager.list_packages());
+    assert_send(&manager.push_package(path));
+    assert_send(&manager.force_push_package(path));
+    assert_send(&manager.pull_package("demo@1.0.0", path));
+    assert_send(&manager.lock_package("demo", "1.0.0", "release", "ci", None));
+    assert_send(&manager.unlock_package("demo", "1.0.0"));
+}
+
+#[tokio::test]
+async fn test_build_reads_environment_only_through_from_env() {
+    // 只有本测试设置 BEEPKG_USER
+    unsafe { std::env::set_var("BEEPKG_USER", "env-user") };
+    let bucket = MockBucket::start().await;
+    let dir = tempfile::tempdir().unwrap();
+    write_package(dir.path(), "demo", "1.0.0");
+    bucket.manager().push_package(dir.path()).await.unwrap();
+
+    let manager = PackageManager::builder()
+        .endpoint(bucket.endpoint())
+        .bucket("packages")
+        .from_env()
+        .unwrap()
+        .build()
+        .unwrap()
+        .cache(None);
+    write_package(dir.path(), "demo", "1.1.0");
+    manager.push_package(dir.path()).await.unwrap();
+
+    let records = manager.list_audit_records(None, None).await.unwrap();
+    let actors: Vec<_> = records
+        .iter()
+        .map(|record| (record.package.as_str(), record.actor.as_str()))
+        .collect();
+    assert_eq!(
+        actors,
+        [("demo@1.0.0", "unknown"), ("demo@1.1.0", "env-user")]
+    );
+}
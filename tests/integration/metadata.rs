@@ -0,0 +1,238 @@
+use beepkg::models::{self, MetadataFormat, PackageMetadata, RegistryMetadata};
+
+const PACK_JSON: &str = r#"{
+    "name": "demo-pkg",
+    "version": "1.2.0",
+    "author": "dev@company.com",
+    "description": "Demo package",
+    "includes": ["src/*"],
+    "excludes": ["*.log"],
+    "dependencies": {"utils": "^1.0"}
+}"#;
+
+#[test]
+fn test_convert_json_to_toml_and_back() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("pack.json"), PACK_JSON).unwrap();
+
+    let path = PackageMetadata::convert(dir.path(), MetadataFormat::Toml, false).unwrap();
+    assert_eq!(path, dir.path().join("pack.toml"));
+    assert!(!dir.path().join("pack.json").exists());
+    let metadata = PackageMetadata::load(dir.path()).unwrap();
+    assert_eq!(metadata.name, "demo-pkg");
+    assert_eq!(metadata.dependencies["utils"], "^1.0");
+
+    PackageMetadata::convert(dir.path(), MetadataFormat::Json, true).unwrap();
+    assert!(dir.path().join("pack.toml").exists());
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join("pack.json")).unwrap())
+            .unwrap();
+    assert_eq!(json["version"], "1.2.0");
+
+    // 目标文件已存在时不覆盖
+    assert!(PackageMetadata::convert(dir.path(), MetadataFormat::Json, false).is_err());
+}
+
+#[test]
+fn test_convert_rejects_invalid_metadata() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("pack.json"),
+        PACK_JSON.replace("1.2.0", "latest"),
+    )
+    .unwrap();
+
+    let error = PackageMetadata::convert(dir.path(), MetadataFormat::Toml, false).unwrap_err();
+    assert!(error.to_string().contains("Invalid version"));
+    assert!(dir.path().join("pack.json").exists());
+    assert!(!dir.path().join("pack.toml").exists());
+}
+
+#[test]
+fn test_metadata_format_parse() {
+    assert_eq!(
+        "TOML".parse::<MetadataFormat>().unwrap(),
+        MetadataFormat::Toml
+    );
+    assert_eq!(
+        "json".parse::<MetadataFormat>().unwrap(),
+        MetadataFormat::Json
+    );
+    assert!("yaml".parse::<MetadataFormat>().is_err());
+}
+
+#[test]
+fn test_split_package_spec() {
+    assert_eq!(
+        models::split_package_spec("demo@1.0.0"),
+        Some(("demo", "1.0.0"))
+    );
+    assert_eq!(
+        models::split_package_spec("@acme/ui@stable"),
+        Some(("@acme/ui", "stable"))
+    );
+    assert_eq!(models::split_package_spec("@acme/ui"), None);
+    assert_eq!(models::split_package_spec("demo@"), None);
+}
+
+#[test]
+fn test_channel_resolution() {
+    assert!(models::validate_channel("stable").is_ok());
+    assert!(models::validate_channel("release-1.x").is_ok());
+    assert!(models::validate_channel("1.2.0").is_err());
+    assert!(models::validate_channel("be/ta").is_err());
+
+    let registry: RegistryMetadata = serde_json::from_str(
+        r#"{
+            "registry_name": "test",
+            "backup_enabled": false,
+            "locked_packages": [],
+            "backups": [],
+            "last_updated": "2024-01-01T00:00:00Z",
+            "channels": {"demo": {"stable": "1.2.0", "beta": "1.3.0-beta.1"}}
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(registry.resolve_version("demo", "stable"), "1.2.0");
+    assert_eq!(registry.resolve_version("demo", "beta"), "1.3.0-beta.1");
+    assert_eq!(registry.resolve_version("demo", "1.0.0"), "1.0.0");
+    assert_eq!(registry.resolve_version("other", "stable"), "stable");
+}
+
+fn featured_metadata() -> PackageMetadata {
+    toml::from_str(
+        r#"
+        name = "render"
+        version = "2.0.0"
+        author = "dev@company.com"
+        description = "Renderer"
+        includes = []
+        excludes = []
+
+        [dependencies]
+        core = "^1.0"
+
+        [optional_dependencies]
+        cuda-runtime = "^12.0"
+        vulkan = "^1.3"
+        cli-args = "^4.0"
+
+        [features]
+        default = ["cli"]
+        cli = ["cli-args"]
+        gpu = ["cuda-runtime", "vulkan"]
+        full = ["gpu", "cli"]
+        "#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_features_enable_optional_dependencies() {
+    let metadata = featured_metadata();
+    metadata.validate().unwrap();
+
+    let mut defaults: Vec<String> = metadata
+        .active_dependencies(&[], true)
+        .unwrap()
+        .into_keys()
+        .collect();
+    defaults.sort();
+    assert_eq!(defaults, vec!["cli-args", "core"]);
+
+    let minimal = metadata.active_dependencies(&[], false).unwrap();
+    assert_eq!(minimal.len(), 1);
+    assert_eq!(minimal["core"], "^1.0");
+
+    // 特性可以间接启用其他特性，也可以直接用可选依赖名启用
+    let mut full: Vec<String> = metadata
+        .active_dependencies(&["full".to_string()], false)
+        .unwrap()
+        .into_keys()
+        .collect();
+    full.sort();
+    assert_eq!(full, vec!["cli-args", "core", "cuda-runtime", "vulkan"]);
+    let vulkan = metadata
+        .active_dependencies(&["vulkan".to_string()], false)
+        .unwrap();
+    assert_eq!(vulkan["vulkan"], "^1.3");
+
+    assert!(
+        metadata
+            .active_dependencies(&["opengl".to_string()], true)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_validate_rejects_unknown_feature_entries() {
+    let mut metadata = featured_metadata();
+    metadata
+        .features
+        .insert("broken".to_string(), vec!["missing".to_string()]);
+    assert!(metadata.validate().is_err());
+
+    let mut metadata = featured_metadata();
+    metadata
+        .optional_dependencies
+        .insert("core".to_string(), "^1.0".to_string());
+    assert!(metadata.validate().is_err());
+}
+
+#[test]
+fn test_backup_bucket_is_optional() {
+    let json = r#"{
+        "original_path": "demo-1.0.0.zip",
+        "backup_path": "demo-1.0.0-backup-1700000000.zip",
+        "timestamp": "2023-11-14T22:13:20+00:00",
+        "reason": "release"
+    }"#;
+    let backup: models::PackageBackup = serde_json::from_str(json).unwrap();
+    assert_eq!(backup.bucket, None);
+    assert!(!serde_json::to_string(&backup).unwrap().contains("bucket"));
+
+    let backup = models::PackageBackup {
+        bucket: Some("packages-backup".to_string()),
+        ..backup
+    };
+    let json = serde_json::to_string(&backup).unwrap();
+    let parsed: models::PackageBackup = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.bucket.as_deref(), Some("packages-backup"));
+}
+
+#[test]
+fn test_extended_metadata_fields() {
+    let metadata: PackageMetadata = toml::from_str(
+        r#"
+name = "demo"
+version = "1.0.0"
+author = "alice"
+description = "Image decoding helpers"
+homepage = "https://demo.example.com"
+repository = "https://git.example.com/team/demo"
+keywords = ["PNG", "codec"]
+readme = "README.md"
+maintainers = ["Alice <alice@example.com>", "Bob"]
+includes = []
+excludes = []
+
+[dependencies]
+"#,
+    )
+    .unwrap();
+    let info = models::PackageInfo::from(&metadata);
+    assert_eq!(info.homepage.as_deref(), Some("https://demo.example.com"));
+    assert_eq!(info.readme.as_deref(), Some("README.md"));
+    assert_eq!(info.maintainers.len(), 2);
+
+    // 名称、描述和关键字都参与搜索，不区分大小写
+    assert!(info.matches("DEMO"));
+    assert!(info.matches("decoding"));
+    assert!(info.matches("png"));
+    assert!(!info.matches("audio"));
+
+    // 未填写的字段不写入元数据摘要
+    let json = serde_json::to_value(models::PackageInfo::from(&featured_metadata())).unwrap();
+    assert!(json.get("homepage").is_none());
+    assert!(json.get("keywords").is_none());
+}
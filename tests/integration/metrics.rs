@@ -0,0 +1,80 @@
+use super::test_helpers::MockBucket;
+use beepkg::metrics::{self, Metrics};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+// 渲染结果中某个指标的值，没有该指标时为 0
+fn value(rendered: &str, metric: &str) -> f64 {
+    rendered
+        .lines()
+        .find_map(|line| line.strip_prefix(metric)?.strip_prefix(' ')?.parse().ok())
+        .unwrap_or(0.0)
+}
+
+const HEAD_SUCCESS: &str = r#"beepkg_http_requests_total{method="HEAD",outcome="success"}"#;
+const HEAD_ERROR: &str = r#"beepkg_http_requests_total{method="HEAD",outcome="error"}"#;
+const LATENCY_COUNT: &str = "beepkg_http_request_duration_seconds_count";
+
+#[test]
+fn test_record_requests() {
+    let metrics = Metrics::default();
+    metrics.record_request("GET", true, Duration::from_millis(3));
+    metrics.record_request("GET", false, Duration::from_millis(200));
+    metrics.record_request("PUT", true, Duration::from_secs(60));
+    metrics.record_uploaded(10);
+    metrics.record_downloaded(25);
+    metrics.record_retry();
+
+    let rendered = metrics.render();
+    let requests = |method: &str, outcome: &str| {
+        let metric = format!(
+            "beepkg_http_requests_total{{method=\"{}\",outcome=\"{}\"}}",
+            method, outcome
+        );
+        value(&rendered, &metric)
+    };
+    assert_eq!(requests("GET", "success"), 1.0);
+    assert_eq!(requests("GET", "error"), 1.0);
+    assert_eq!(requests("PUT", "success"), 1.0);
+    assert_eq!(requests("PUT", "error"), 0.0);
+    assert_eq!(value(&rendered, "beepkg_bytes_uploaded_total"), 10.0);
+    assert_eq!(value(&rendered, "beepkg_bytes_downloaded_total"), 25.0);
+    assert_eq!(value(&rendered, "beepkg_retries_total"), 1.0);
+
+    // 直方图的桶是累计的，超过最大上界的请求只计入 +Inf
+    let bucket = |le: &str| {
+        let metric = format!(
+            "beepkg_http_request_duration_seconds_bucket{{le=\"{}\"}}",
+            le
+        );
+        value(&rendered, &metric)
+    };
+    assert_eq!(bucket("0.005"), 1.0);
+    assert_eq!(bucket("0.1"), 1.0);
+    assert_eq!(bucket("0.25"), 2.0);
+    assert_eq!(bucket("30"), 2.0);
+    assert_eq!(bucket("+Inf"), 3.0);
+    assert_eq!(value(&rendered, LATENCY_COUNT), 3.0);
+    let sum = value(&rendered, "beepkg_http_request_duration_seconds_sum");
+    assert!((sum - 60.203).abs() < 1e-6, "{}", sum);
+}
+
+#[tokio::test]
+async fn test_sends_recorded() {
+    let bucket = MockBucket::with_objects(BTreeMap::from([(
+        "demo-1.0.0.zip".to_string(),
+        vec![0u8; 8],
+    )]))
+    .await;
+    let manager = bucket.manager();
+
+    // 全局指标由所有测试共享，只检查增量
+    let before = metrics::global().render();
+    assert!(manager.exists("demo", "1.0.0").await.unwrap());
+    assert!(!manager.exists("demo", "2.0.0").await.unwrap());
+    let after = metrics::global().render();
+
+    assert!(value(&after, HEAD_SUCCESS) >= value(&before, HEAD_SUCCESS) + 1.0);
+    assert!(value(&after, HEAD_ERROR) >= value(&before, HEAD_ERROR) + 1.0);
+    assert!(value(&after, LATENCY_COUNT) >= value(&before, LATENCY_COUNT) + 2.0);
+}
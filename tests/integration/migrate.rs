@@ -0,0 +1,150 @@
+use super::test_helpers::MockBucket;
+use beepkg::checksum::{Checksum, ChecksumAlgorithm};
+use beepkg::operations::MigrationStatus;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+fn archive(name: &str, version: &str) -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    zip.start_file("pack.toml", Default::default()).unwrap();
+    write!(
+        zip,
+        "name = \"{}\"\nversion = \"{}\"\nauthor = \"\"\ndescription = \"\"\n\
+         includes = []\nexcludes = []\n\n[dependencies]\n",
+        name, version
+    )
+    .unwrap();
+    zip.finish().unwrap().into_inner()
+}
+
+const REGISTRY: &[u8] = br#"{"registry_name": "legacy", "backup_enabled": false,
+    "locked_packages": [], "backups": [], "last_updated": ""}"#;
+
+// 旧版布局的源存储桶：作用域包按扁平名称存放并只有 .sha1 校验文件，
+// demo 已是当前布局，bad 的内容与校验文件不一致
+async fn legacy_source() -> MockBucket {
+    let scoped = archive("@team/ml-core", "1.2.0");
+    let demo = archive("demo", "1.0.0");
+    let demo_checksum = Checksum::compute(ChecksumAlgorithm::Sha256, &demo);
+    MockBucket::with_objects(BTreeMap::from([
+        ("registry-metadata.json".to_string(), REGISTRY.to_vec()),
+        (
+            "ml-core-1.2.0.zip.sha1".to_string(),
+            ChecksumAlgorithm::Sha1.digest(&scoped).into_bytes(),
+        ),
+        (
+            "ml-core-1.2.0.zip.sig".to_string(),
+            b"old signature".to_vec(),
+        ),
+        (
+            "ml-core-1.2.0.zip.meta.json".to_string(),
+            br#"{"readme": "ML core"}"#.to_vec(),
+        ),
+        ("ml-core-1.2.0.zip".to_string(), scoped),
+        (
+            "demo-1.0.0.zip.sha256".to_string(),
+            demo_checksum.to_string().into_bytes(),
+        ),
+        ("demo-1.0.0.zip.sig".to_string(), b"demo signature".to_vec()),
+        ("demo-1.0.0.zip".to_string(), demo),
+        (
+            "bad-1.0.0.zip.sha256".to_string(),
+            demo_checksum.to_string().into_bytes(),
+        ),
+        ("bad-1.0.0.zip".to_string(), archive("bad", "1.0.0")),
+    ]))
+    .await
+}
+
+#[tokio::test]
+async fn test_migrate_rewrites_legacy_layout() {
+    let source = legacy_source().await;
+    let target = MockBucket::start().await;
+
+    let entries = source
+        .manager()
+        .migrate_to(&target.manager(), false)
+        .await
+        .unwrap();
+    let report: BTreeMap<&str, _> = entries
+        .iter()
+        .map(|entry| (entry.source.as_str(), entry))
+        .collect();
+    assert_eq!(report.len(), 3);
+
+    // 作用域包按 pack.toml 改名，按目标注册表的算法重新生成校验文件
+    let scoped = &report["ml-core-1.2.0.zip"];
+    assert_eq!(scoped.status, MigrationStatus::Migrated);
+    assert_eq!(scoped.target.as_deref(), Some("@team/ml-core-1.2.0.zip"));
+    let content = target.object("@team/ml-core-1.2.0.zip").unwrap();
+    assert_eq!(content, source.object("ml-core-1.2.0.zip").unwrap());
+    assert_eq!(
+        target.object("@team/ml-core-1.2.0.zip.sha256").unwrap(),
+        Checksum::compute(ChecksumAlgorithm::Sha256, &content)
+            .to_string()
+            .into_bytes()
+    );
+    assert_eq!(
+        target.object("@team/ml-core-1.2.0.zip.meta.json"),
+        source.object("ml-core-1.2.0.zip.meta.json")
+    );
+    // 校验和变化后旧签名失效，不复制
+    assert_eq!(target.object("@team/ml-core-1.2.0.zip.sig"), None);
+    assert_eq!(target.object("@team/ml-core-1.2.0.zip.sha1"), None);
+    assert!(
+        scoped
+            .notes
+            .iter()
+            .any(|note| note.contains("must be re-signed"))
+    );
+
+    // 校验和不变的包保留对象键和签名
+    let demo = &report["demo-1.0.0.zip"];
+    assert_eq!(demo.status, MigrationStatus::Migrated);
+    assert_eq!(demo.target.as_deref(), Some("demo-1.0.0.zip"));
+    assert_eq!(
+        target.object("demo-1.0.0.zip.sig").unwrap(),
+        b"demo signature"
+    );
+    assert_eq!(
+        target.object("demo-1.0.0.zip.sha256"),
+        source.object("demo-1.0.0.zip.sha256")
+    );
+
+    // 内容与校验文件不一致的包不迁移
+    let bad = &report["bad-1.0.0.zip"];
+    assert_eq!(bad.status, MigrationStatus::Failed);
+    assert_eq!(bad.target, None);
+    assert_eq!(target.object("bad-1.0.0.zip"), None);
+
+    // 目标存储桶中没有注册表元数据时一并复制
+    assert_eq!(
+        target.object("registry-metadata.json").as_deref(),
+        Some(REGISTRY)
+    );
+}
+
+#[tokio::test]
+async fn test_migrate_dry_run_writes_nothing() {
+    let source = legacy_source().await;
+    let target = MockBucket::start().await;
+
+    let entries = source
+        .manager()
+        .migrate_to(&target.manager(), true)
+        .await
+        .unwrap();
+    let planned: Vec<_> = entries
+        .iter()
+        .filter(|entry| entry.status == MigrationStatus::Planned)
+        .map(|entry| entry.target.as_deref().unwrap())
+        .collect();
+    assert_eq!(planned, ["demo-1.0.0.zip", "@team/ml-core-1.2.0.zip"]);
+    assert!(target.objects.lock().unwrap().is_empty());
+    assert!(
+        !target
+            .requests()
+            .iter()
+            .any(|request| request.starts_with("PUT "))
+    );
+}
@@ -0,0 +1,223 @@
+use super::test_helpers::MockBucket;
+use beepkg::checksum::{Checksum, ChecksumAlgorithm};
+use beepkg::mirror::{self, WatchOptions};
+use beepkg::models::{LockedPackage, PackageAccess, RegistryMetadata, Visibility};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+const CONTENT: &[u8] = b"PK demo archive";
+
+fn package(content: &[u8]) -> Vec<(String, Vec<u8>)> {
+    let checksum = Checksum::compute(ChecksumAlgorithm::Sha256, content);
+    vec![
+        ("demo-1.0.0.zip".to_string(), content.to_vec()),
+        (
+            "demo-1.0.0.zip.sha256".to_string(),
+            checksum.to_string().into_bytes(),
+        ),
+    ]
+}
+
+fn registry(metadata: &RegistryMetadata) -> (String, Vec<u8>) {
+    (
+        "registry-metadata.json".to_string(),
+        serde_json::to_vec(metadata).unwrap(),
+    )
+}
+
+fn source_metadata() -> RegistryMetadata {
+    let mut metadata = RegistryMetadata::default();
+    metadata.access.insert(
+        "demo".to_string(),
+        PackageAccess {
+            visibility: Visibility::Private,
+            owner: Some("alice".to_string()),
+            grants: vec!["team:platform".to_string()],
+        },
+    );
+    metadata.channels.insert(
+        "demo".to_string(),
+        BTreeMap::from([("stable".to_string(), "1.0.0".to_string())]),
+    );
+    metadata.locked_packages.push(LockedPackage {
+        name: "demo".to_string(),
+        version: "1.0.0".to_string(),
+        lock_reason: "release".to_string(),
+        locked_at: "2024-01-01T00:00:00+00:00".to_string(),
+        locked_by: "alice".to_string(),
+        checksum: String::new(),
+        expires_at: None,
+    });
+    metadata
+}
+
+// 读取校验文件的请求数
+fn checksum_reads(bucket: &MockBucket) -> usize {
+    bucket.count("GET", "demo-1.0.0.zip.sha256")
+}
+
+#[tokio::test]
+async fn test_mirror_compares_listings_without_reading_checksums() {
+    let mut objects: BTreeMap<_, _> = package(CONTENT).into_iter().collect();
+    let metadata = source_metadata();
+    let source = MockBucket::with_objects(
+        objects
+            .clone()
+            .into_iter()
+            .chain([registry(&metadata)])
+            .collect(),
+    )
+    .await;
+    objects.extend([registry(&metadata)]);
+    let target = MockBucket::with_objects(objects).await;
+
+    let report = source
+        .manager()
+        .mirror_to(&target.manager(), None, 4, false)
+        .await
+        .unwrap();
+    assert!(report.copied.is_empty());
+    assert!(report.failed.is_empty());
+    assert_eq!(report.up_to_date, 1);
+    // 每边列出一次、读取一次注册表元数据，不读取校验文件和包文件
+    for bucket in [&source, &target] {
+        assert_eq!(checksum_reads(bucket), 0);
+        assert_eq!(bucket.count("GET", "demo-1.0.0.zip"), 0);
+        assert_eq!(bucket.requests().len(), 2, "{:?}", bucket.requests());
+    }
+    // 元数据一致时不写回目标注册表
+    assert_eq!(target.count("PUT", "registry-metadata.json"), 0);
+}
+
+#[tokio::test]
+async fn test_mirror_copies_packages_and_their_metadata() {
+    let source = MockBucket::with_objects(
+        package(CONTENT)
+            .into_iter()
+            .chain([registry(&source_metadata())])
+            .collect(),
+    )
+    .await;
+    let mut stale = RegistryMetadata::default();
+    stale.channels.insert(
+        "demo".to_string(),
+        BTreeMap::from([("beta".to_string(), "0.9.0".to_string())]),
+    );
+    let target = MockBucket::with_objects(
+        package(b"PK old demo archive")
+            .into_iter()
+            .chain([registry(&stale)])
+            .collect(),
+    )
+    .await;
+
+    let report = source
+        .manager()
+        .mirror_to(&target.manager(), None, 4, false)
+        .await
+        .unwrap();
+    assert_eq!(report.copied, vec!["demo-1.0.0.zip".to_string()]);
+    assert!(report.failed.is_empty());
+    assert_eq!(target.object("demo-1.0.0.zip").unwrap(), CONTENT);
+    assert_eq!(
+        target.object("demo-1.0.0.zip.sha256"),
+        source.object("demo-1.0.0.zip.sha256")
+    );
+
+    let mirrored: RegistryMetadata =
+        serde_json::from_slice(&target.object("registry-metadata.json").unwrap()).unwrap();
+    let expected = source_metadata();
+    assert_eq!(mirrored.access, expected.access);
+    assert_eq!(mirrored.channels, expected.channels);
+    assert_eq!(mirrored.locked_packages, expected.locked_packages);
+
+    // 再次镜像时两边的列表一致，不再读取校验文件
+    let (source_reads, target_reads) = (checksum_reads(&source), checksum_reads(&target));
+    let report = source
+        .manager()
+        .mirror_to(&target.manager(), None, 4, false)
+        .await
+        .unwrap();
+    assert!(report.copied.is_empty());
+    assert_eq!(report.up_to_date, 1);
+    assert_eq!(checksum_reads(&source), source_reads);
+    assert_eq!(checksum_reads(&target), target_reads);
+}
+
+#[tokio::test]
+async fn test_mirror_dry_run_leaves_target_metadata_alone() {
+    let source = MockBucket::with_objects(
+        package(CONTENT)
+            .into_iter()
+            .chain([registry(&source_metadata())])
+            .collect(),
+    )
+    .await;
+    let target =
+        MockBucket::with_objects(BTreeMap::from([registry(&RegistryMetadata::default())])).await;
+
+    let report = source
+        .manager()
+        .mirror_to(&target.manager(), None, 4, true)
+        .await
+        .unwrap();
+    assert_eq!(report.copied, vec!["demo-1.0.0.zip".to_string()]);
+    assert!(
+        !target
+            .requests()
+            .iter()
+            .any(|request| request.starts_with("PUT "))
+    );
+}
+
+#[tokio::test]
+async fn test_mirror_watch_runs_until_stopped() {
+    let source = MockBucket::with_objects(
+        package(CONTENT)
+            .into_iter()
+            .chain([registry(&source_metadata())])
+            .collect(),
+    )
+    .await;
+    let target =
+        MockBucket::with_objects(BTreeMap::from([registry(&RegistryMetadata::default())])).await;
+    let targets = vec![("downstream".to_string(), target.manager())];
+    let dir = tempfile::tempdir().unwrap();
+    let options = WatchOptions {
+        filter: None,
+        jobs: 2,
+        interval: Duration::from_secs(3600),
+        status_file: Some(dir.path().join("status.json")),
+    };
+
+    // 已完成的 stop 只运行一轮，不等待轮询间隔
+    mirror::watch(&source.manager(), &targets, &options, async {})
+        .await
+        .unwrap();
+    assert_eq!(target.object("demo-1.0.0.zip").unwrap(), CONTENT);
+    let status: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(dir.path().join("status.json")).unwrap()).unwrap();
+    assert_eq!(status[0]["registry"], "downstream");
+    assert_eq!(status[0]["copied"], 1);
+    assert!(status[0]["last_success"].is_string());
+    assert!(status[0]["last_error"].is_null());
+
+    // 下一轮只比较列表，不读取校验文件和包文件
+    let reads = (
+        checksum_reads(&source),
+        source.count("GET", "demo-1.0.0.zip"),
+    );
+    mirror::watch(&source.manager(), &targets, &options, async {})
+        .await
+        .unwrap();
+    assert_eq!(
+        (
+            checksum_reads(&source),
+            source.count("GET", "demo-1.0.0.zip")
+        ),
+        reads
+    );
+    let status: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(dir.path().join("status.json")).unwrap()).unwrap();
+    assert_eq!(status[0]["copied"], 0);
+}
@@ -1,3 +1,85 @@
 #[macro_use]
 pub mod test_helpers;
+pub mod access;
+pub mod advisory;
+#[cfg(feature = "encryption")]
+pub mod approval;
+pub mod artifacts;
+pub mod audit;
+pub mod auth;
+pub mod aws;
+pub mod backups;
+pub mod blobs;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "archives")]
+pub mod bundle;
+pub mod cache;
+pub mod changelog;
+pub mod checksum;
+pub mod config;
+#[cfg(unix)]
+pub mod daemon;
+#[cfg(feature = "archives")]
+pub mod delta;
+pub mod diagnostics;
+pub mod doctor;
+pub mod drafts;
+pub mod drift;
+pub mod error;
+pub mod events;
+pub mod fetch;
+#[cfg(feature = "archives")]
+pub mod foreign;
+pub mod fsck;
+pub mod gc;
+#[cfg(unix)]
+pub mod gpg;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod hooks;
+pub mod immutable;
+pub mod labels;
+pub mod license;
+pub mod listing;
+pub mod manager;
+pub mod metadata;
+pub mod metrics;
+pub mod migrate;
+pub mod mirror;
+pub mod notifiers;
+pub mod oci;
 pub mod package_ops;
+pub mod plugins;
+pub mod policy;
+pub mod prerelease;
+pub mod project;
+pub mod provenance;
+#[cfg(feature = "proxy")]
+pub mod proxy;
+pub mod push_archive;
+pub mod quota;
+pub mod readme;
+pub mod retention;
+pub mod rollback;
+pub mod sbom;
+pub mod schedule;
+pub mod schema;
+#[cfg(feature = "encryption")]
+pub mod security;
+pub mod share;
+#[cfg(feature = "encryption")]
+pub mod signing;
+#[cfg(unix)]
+pub mod sigstore;
+pub mod site;
+#[cfg(feature = "archives")]
+pub mod snapshot;
+pub mod sse;
+pub mod stat;
+pub mod stats;
+pub mod sums;
+pub mod upload_verification;
+pub mod vendor;
+pub mod webhooks;
+pub mod workspace;
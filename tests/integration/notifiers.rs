@@ -0,0 +1,20 @@
+use beepkg::models::{AuditAction, AuditRecord};
+use beepkg::notifiers::{DEFAULT_TEMPLATE, render_template};
+
+#[test]
+fn test_render_default_template() {
+    let record = AuditRecord {
+        actor: "alice".to_string(),
+        action: AuditAction::Push,
+        package: "demo-pkg@1.2.3".to_string(),
+        timestamp: "2024-01-01T00:00:00+00:00".to_string(),
+        client_version: "0.1.0".to_string(),
+        source_ip: None,
+        details: None,
+    };
+
+    assert_eq!(
+        render_template(DEFAULT_TEMPLATE, &record),
+        "pkg demo-pkg v1.2.3 published by alice"
+    );
+}
@@ -0,0 +1,280 @@
+use beepkg::checksum::{Checksum, ChecksumAlgorithm};
+use beepkg::error::BeepkgError;
+use beepkg::oci::{self, OciRegistry};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+const TOKEN: &str = "registry-token";
+
+#[derive(Default)]
+struct Store {
+    blobs: HashMap<String, Vec<u8>>,
+    // 仓库 -> 标签 -> 清单
+    manifests: HashMap<String, HashMap<String, Vec<u8>>>,
+}
+
+// 模拟 OCI 仓库（distribution 规范的最小子集），要求 Bearer 令牌：
+// 未带令牌的请求返回挑战，/token 用 Basic 认证 alice:secret 换取令牌。每个连接只处理一个请求
+async fn registry() -> (SocketAddr, Arc<Mutex<Store>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let store = Arc::new(Mutex::new(Store::default()));
+    let shared = store.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let store = shared.clone();
+            tokio::spawn(async move {
+                let mut request = Vec::new();
+                let mut buf = [0u8; 8192];
+                let header_end = loop {
+                    if let Some(pos) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break pos + 4;
+                    }
+                    let n = stream.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        return;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                };
+                let head = String::from_utf8_lossy(&request[..header_end]).to_string();
+                let header = |name: &str| {
+                    head.lines().find_map(|line| {
+                        let (key, value) = line.split_once(':')?;
+                        key.eq_ignore_ascii_case(name)
+                            .then(|| value.trim().to_string())
+                    })
+                };
+                let length: usize = header("content-length")
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(0);
+                while request.len() < header_end + length {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let body = request[header_end..header_end + length].to_vec();
+
+                let mut parts = head.split(' ');
+                let method = parts.next().unwrap_or_default();
+                let target = parts.next().unwrap_or_default();
+                let (path, query) = target.split_once('?').unwrap_or((target, ""));
+                let authorization = header("authorization").unwrap_or_default();
+
+                let (status, extra, body) = if path == "/token" {
+                    // base64("alice:secret")
+                    if authorization == "Basic YWxpY2U6c2VjcmV0" {
+                        let token = format!(r#"{{"token":"{}"}}"#, TOKEN);
+                        ("200 OK", String::new(), token.into_bytes())
+                    } else {
+                        ("401 Unauthorized", String::new(), Vec::new())
+                    }
+                } else if authorization != format!("Bearer {}", TOKEN) {
+                    let challenge = format!(
+                        "WWW-Authenticate: Bearer realm=\"http://{}/token\",service=\"fake\"\r\n",
+                        addr
+                    );
+                    ("401 Unauthorized", challenge, Vec::new())
+                } else {
+                    handle(&mut store.lock().unwrap(), method, path, query, body)
+                };
+                let head = format!(
+                    "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    extra,
+                    body.len()
+                );
+                stream.write_all(head.as_bytes()).await.unwrap();
+                if method != "HEAD" {
+                    stream.write_all(&body).await.unwrap();
+                }
+            });
+        }
+    });
+    (addr, store)
+}
+
+fn handle(
+    store: &mut Store,
+    method: &str,
+    path: &str,
+    query: &str,
+    body: Vec<u8>,
+) -> (&'static str, String, Vec<u8>) {
+    let not_found = ("404 Not Found", String::new(), Vec::new());
+    let Some(path) = path.strip_prefix("/v2/") else {
+        return not_found;
+    };
+    if let Some(repository) = path.strip_suffix("/tags/list") {
+        let Some(tags) = store.manifests.get(repository) else {
+            return not_found;
+        };
+        let tags: Vec<_> = tags.keys().collect();
+        let list = serde_json::json!({ "name": repository, "tags": tags });
+        return ("200 OK", String::new(), list.to_string().into_bytes());
+    }
+    if let Some((repository, _)) = path.split_once("/blobs/uploads/") {
+        return match method {
+            "POST" => {
+                let location = format!("Location: /v2/{}/blobs/uploads/1\r\n", repository);
+                ("202 Accepted", location, Vec::new())
+            }
+            "PUT" => {
+                let digest = query
+                    .split('&')
+                    .find_map(|p| p.strip_prefix("digest="))
+                    .unwrap_or_default()
+                    .replace("%3A", ":");
+                store.blobs.insert(digest, body);
+                ("201 Created", String::new(), Vec::new())
+            }
+            _ => not_found,
+        };
+    }
+    if let Some((_, digest)) = path.split_once("/blobs/") {
+        return match store.blobs.get(digest) {
+            Some(blob) => ("200 OK", String::new(), blob.clone()),
+            None => not_found,
+        };
+    }
+    if let Some((repository, tag)) = path.split_once("/manifests/") {
+        let tags = store.manifests.entry(repository.to_string()).or_default();
+        return match method {
+            "PUT" => {
+                tags.insert(tag.to_string(), body);
+                ("201 Created", String::new(), Vec::new())
+            }
+            _ => match tags.get(tag) {
+                Some(manifest) => ("200 OK", String::new(), manifest.clone()),
+                None => not_found,
+            },
+        };
+    }
+    not_found
+}
+
+fn package(dir: &Path, version: &str) {
+    std::fs::write(
+        dir.join("pack.toml"),
+        format!(
+            r#"
+            name = "@team/demo"
+            version = "{}"
+            author = "dev@company.com"
+            description = "Demo package"
+            includes = []
+            excludes = []
+
+            [dependencies]
+            "#,
+            version
+        ),
+    )
+    .unwrap();
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(dir.join("src/main.txt"), format!("demo {}", version)).unwrap();
+}
+
+#[test]
+fn test_oci_mapping() {
+    let registry = OciRegistry::new("https://harbor.example.com/team/").unwrap();
+    assert_eq!(registry.repository("Demo"), "team/demo");
+    assert_eq!(registry.repository("@acme/tool"), "team/acme/tool");
+    let registry = OciRegistry::new("http://localhost:5000").unwrap();
+    assert_eq!(registry.repository("demo"), "demo");
+
+    assert_eq!(oci::version_tag("1.0.0+build.5"), "1.0.0_build.5");
+    assert_eq!(oci::tag_version("1.0.0_build.5"), "1.0.0+build.5");
+    assert!(OciRegistry::new("ftp://example.com").is_err());
+}
+
+#[tokio::test]
+async fn test_oci_push_pull_and_list() {
+    let (addr, store) = registry().await;
+    let registry = OciRegistry::new(&format!("http://{}/team", addr))
+        .unwrap()
+        .credentials("alice", "secret");
+
+    let dir = tempfile::tempdir().unwrap();
+    package(dir.path(), "1.0.0");
+    let pushed = registry.push(dir.path(), false).await.unwrap();
+    assert_eq!(pushed.name, "@team/demo");
+    assert!(pushed.digest.starts_with("sha256:"));
+    package(dir.path(), "1.1.0+build.2");
+    registry.push(dir.path(), false).await.unwrap();
+
+    // 已存在的版本需要 force
+    let conflict = registry.push(dir.path(), false).await.unwrap_err();
+    assert!(matches!(conflict, BeepkgError::Conflict(_)));
+    registry.push(dir.path(), true).await.unwrap();
+
+    {
+        let store = store.lock().unwrap();
+        let tags = &store.manifests["team/team/demo"];
+        assert!(tags.contains_key("1.1.0_build.2"));
+        let manifest: serde_json::Value = serde_json::from_slice(&tags["1.0.0"]).unwrap();
+        assert_eq!(manifest["artifactType"], oci::ARTIFACT_TYPE);
+        assert_eq!(manifest["layers"][0]["mediaType"], oci::LAYER_MEDIA_TYPE);
+        assert_eq!(manifest["layers"][0]["digest"], pushed.digest.as_str());
+    }
+
+    let versions = registry.list_versions("@team/demo").await.unwrap();
+    assert_eq!(versions, ["1.1.0+build.2", "1.0.0"]);
+    assert!(registry.list_versions("other").await.unwrap().is_empty());
+
+    let output = tempfile::tempdir().unwrap();
+    let pulled = registry
+        .install("@team/demo", "1.0.0", output.path())
+        .await
+        .unwrap();
+    assert_eq!(pulled.digest, pushed.digest);
+    assert_eq!(
+        std::fs::read_to_string(output.path().join("src/main.txt")).unwrap(),
+        "demo 1.0.0"
+    );
+    assert!(output.path().join("pack.toml").exists());
+
+    let missing = registry
+        .install("@team/demo", "9.9.9", output.path())
+        .await
+        .unwrap_err();
+    assert!(matches!(missing, BeepkgError::NotFound(_)));
+}
+
+#[tokio::test]
+async fn test_oci_pull_verifies_digest() {
+    let (addr, store) = registry().await;
+    let registry = OciRegistry::new(&format!("http://{}", addr))
+        .unwrap()
+        .credentials("alice", "secret");
+    let dir = tempfile::tempdir().unwrap();
+    package(dir.path(), "1.0.0");
+    let pushed = registry.push(dir.path(), false).await.unwrap();
+
+    store
+        .lock()
+        .unwrap()
+        .blobs
+        .insert(pushed.digest.clone(), b"tampered".to_vec());
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let error = registry
+        .pull("@team/demo", "1.0.0", file.path())
+        .await
+        .unwrap_err();
+    assert!(matches!(error, BeepkgError::ChecksumMismatch(_)));
+    let tampered = Checksum::compute(ChecksumAlgorithm::Sha256, b"tampered").to_string();
+    assert!(error.to_string().contains(&tampered));
+}
+
+#[tokio::test]
+async fn test_oci_rejects_bad_credentials() {
+    let (addr, _) = registry().await;
+    let registry = OciRegistry::new(&format!("http://{}", addr))
+        .unwrap()
+        .credentials("alice", "wrong");
+    let error = registry.list_versions("demo").await.unwrap_err();
+    assert!(matches!(error, BeepkgError::Auth(_)));
+}
@@ -0,0 +1,57 @@
+use beepkg::plugins::{self, PluginContext, PluginRegistry};
+
+fn context() -> PluginContext {
+    PluginContext {
+        version: "0.0.0".to_string(),
+        executable: None,
+        config_dir: None,
+        endpoint: Some("http://localhost:9000".to_string()),
+        bucket: "packages".to_string(),
+        registries: vec![PluginRegistry {
+            name: "mirror".to_string(),
+            endpoint: "https://packages.example.com".to_string(),
+            bucket: "shared".to_string(),
+        }],
+    }
+}
+
+#[test]
+fn test_find_rejects_paths() {
+    assert!(plugins::find("").is_none());
+    assert!(plugins::find("../bin/sh").is_none());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_run_plugin() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let plugin = dir.path().join("beepkg-report");
+    let output = dir.path().join("output.txt");
+    std::fs::write(
+        &plugin,
+        format!(
+            "#!/bin/sh\ncat > {out}.json\necho \"$BEEPKG_PLUGIN $*\" > {out}\nexit 7\n",
+            out = output.display()
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&plugin, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let args = vec!["--since".to_string(), "7d".to_string()];
+    let code = plugins::run(&plugin, "report", &args, &context())
+        .await
+        .unwrap();
+    assert_eq!(code, 7);
+    assert_eq!(
+        std::fs::read_to_string(&output).unwrap(),
+        "report --since 7d\n"
+    );
+
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(output.with_extension("txt.json")).unwrap())
+            .unwrap();
+    assert_eq!(json["endpoint"], "http://localhost:9000");
+    assert_eq!(json["registries"][0]["name"], "mirror");
+}
@@ -0,0 +1,34 @@
+use beepkg::checksum::{Checksum, ChecksumAlgorithm};
+use beepkg::policy::TrustPolicy;
+
+#[test]
+fn test_policy_violations() {
+    let policy: TrustPolicy = toml::from_str(
+        r#"
+require_signature = true
+required_signers = ["89ABCDEF"]
+allowed_authors = ["team-infra"]
+min_checksum_algorithm = "sha256"
+"#,
+    )
+    .unwrap();
+
+    let sha256 = Checksum::compute(ChecksumAlgorithm::Sha256, b"abc");
+    let sha1 = Checksum::compute(ChecksumAlgorithm::Sha1, b"abc");
+
+    // GPG 长密钥 ID 匹配指纹后缀，大小写不敏感
+    assert!(
+        policy
+            .evaluate(&sha256, Some("0123456789abcdef"), "team-infra")
+            .is_empty()
+    );
+
+    assert_eq!(policy.evaluate(&sha256, None, "team-infra").len(), 1);
+    assert_eq!(
+        policy
+            .evaluate(&sha256, Some("FFFF0000"), "team-infra")
+            .len(),
+        1
+    );
+    assert_eq!(policy.evaluate(&sha1, None, "someone").len(), 3);
+}
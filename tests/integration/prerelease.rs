@@ -0,0 +1,93 @@
+use super::test_helpers::{MockBucket, write_package};
+use beepkg::models::{parse_archive_key, parse_backup_key, split_archive_name};
+
+#[test]
+fn test_parse_archive_key() {
+    assert_eq!(split_archive_name("demo-1.0.0"), Some(("demo", "1.0.0")));
+    assert_eq!(
+        split_archive_name("demo-2.0.0-rc.1"),
+        Some(("demo", "2.0.0-rc.1"))
+    );
+    assert_eq!(
+        split_archive_name("ml-core-1.2.0-beta-2"),
+        Some(("ml-core", "1.2.0-beta-2"))
+    );
+    assert_eq!(split_archive_name("demo"), None);
+    assert_eq!(split_archive_name("demo-latest"), None);
+
+    assert_eq!(
+        parse_archive_key("@team/ml-core-2.0.0-rc.1.zip"),
+        Some(("@team/ml-core", "2.0.0-rc.1"))
+    );
+    assert_eq!(
+        parse_archive_key("demo-2.0.0-rc.1-backup-1714564800.zip"),
+        None
+    );
+    assert_eq!(
+        parse_archive_key("demo-2.0.0-rc.1.zip.artifacts/linux-x86_64.zip"),
+        None
+    );
+    assert_eq!(parse_archive_key("demo-2.0.0-rc.1.zip.sha256"), None);
+
+    // 包名中含有 -backup- 的包不是备份
+    assert_eq!(
+        parse_archive_key("db-backup-tool-1.0.0.zip"),
+        Some(("db-backup-tool", "1.0.0"))
+    );
+    assert_eq!(
+        parse_archive_key("db-backup-1.0.0.zip"),
+        Some(("db-backup", "1.0.0"))
+    );
+    assert_eq!(parse_backup_key("db-backup-tool-1.0.0.zip"), None);
+    assert_eq!(
+        parse_backup_key("db-backup-tool-1.0.0-backup-1714564800.zip"),
+        Some(("db-backup-tool", "1.0.0", 1714564800))
+    );
+    assert_eq!(
+        parse_backup_key("demo-2.0.0-rc.1-backup-1714564800.zip"),
+        Some(("demo", "2.0.0-rc.1", 1714564800))
+    );
+    assert_eq!(parse_backup_key("demo-1.0.0-backup-latest.zip"), None);
+}
+
+#[tokio::test]
+async fn test_prerelease_listing_and_backup() {
+    let bucket = MockBucket::start().await;
+    let manager = bucket.manager().actor("alice").prerelease(true);
+    let dir = tempfile::tempdir().unwrap();
+    for version in ["1.0.0", "2.0.0-rc.1"] {
+        write_package(dir.path(), "demo", version);
+        manager.push_package(dir.path()).await.unwrap();
+    }
+    manager
+        .backup_package("demo", "2.0.0-rc.1", "before rebuild")
+        .await
+        .unwrap();
+
+    // 备份对象不出现在包列表中，也不会被当作更高的预发布版本
+    let listed: Vec<String> = manager
+        .list_packages()
+        .await
+        .unwrap()
+        .iter()
+        .map(|p| format!("{}@{}", p.name, p.version))
+        .collect();
+    assert_eq!(listed, ["demo@1.0.0", "demo@2.0.0-rc.1"]);
+    let output = tempfile::tempdir().unwrap();
+    manager.pull_package("demo@*", output.path()).await.unwrap();
+    assert_eq!(
+        std::fs::read_to_string(output.path().join("data.txt")).unwrap(),
+        "2.0.0-rc.1"
+    );
+
+    // 预发布版本的备份按完整版本号匹配，覆盖后可以恢复
+    let original = bucket.object("demo-2.0.0-rc.1.zip").unwrap();
+    std::fs::write(dir.path().join("data.txt"), "broken").unwrap();
+    manager.force_push_package(dir.path()).await.unwrap();
+    assert_ne!(bucket.object("demo-2.0.0-rc.1.zip").unwrap(), original);
+    manager
+        .restore_package_from_backup("demo", "2.0.0-rc.1", None)
+        .await
+        .unwrap();
+    assert_eq!(bucket.object("demo-2.0.0-rc.1.zip").unwrap(), original);
+}
@@ -0,0 +1,315 @@
+use super::test_helpers::{MockBucket, pack_toml, write_package};
+use beepkg::error::BeepkgError;
+use beepkg::operations::PackageManager;
+use beepkg::project::{Project, ProjectLock};
+use std::path::Path;
+
+async fn publish(manager: &PackageManager, name: &str, version: &str, dependencies: &str) {
+    let dir = tempfile::tempdir().unwrap();
+    write_package(dir.path(), name, version);
+    std::fs::write(
+        dir.path().join("pack.toml"),
+        pack_toml(name, version, dependencies),
+    )
+    .unwrap();
+    manager.push_package(dir.path()).await.unwrap();
+}
+
+fn write_project(root: &Path, packages: &str) {
+    std::fs::write(
+        root.join("project.toml"),
+        format!("[packages]\n{}", packages),
+    )
+    .unwrap();
+}
+
+fn read_data(root: &Path, name: &str) -> String {
+    std::fs::read_to_string(root.join("packages").join(name).join("data.txt")).unwrap()
+}
+
+#[tokio::test]
+async fn test_sync_project() {
+    let bucket = MockBucket::start().await;
+    let manager = bucket.manager();
+    publish(&manager, "icons", "1.0.0", "").await;
+    publish(&manager, "icons", "1.1.0", "").await;
+    publish(&manager, "fonts", "2.0.0", "icons = \"^1.0\"\n").await;
+    publish(&manager, "themes", "0.1.0", "").await;
+
+    let root = tempfile::tempdir().unwrap();
+    write_project(root.path(), "fonts = \"^2.0\"\nthemes = \"0.1.0\"\n");
+    let report = manager
+        .sync_project(root.path(), None, false)
+        .await
+        .unwrap();
+    assert_eq!(
+        report.installed,
+        ["fonts@2.0.0", "icons@1.1.0", "themes@0.1.0"]
+    );
+    assert!(report.removed.is_empty() && report.unchanged.is_empty());
+    // 间接依赖也安装到安装目录
+    assert_eq!(read_data(root.path(), "icons"), "1.1.0");
+    let lock = ProjectLock::load(root.path(), None).unwrap().unwrap();
+    assert_eq!(lock.packages.len(), 3);
+    assert_eq!(lock.requirements.len(), 2);
+
+    // 没有变化时不重新下载，也不重写锁文件
+    let written = std::fs::read_to_string(root.path().join("project-lock.json")).unwrap();
+    let report = manager
+        .sync_project(root.path(), None, false)
+        .await
+        .unwrap();
+    assert!(report.installed.is_empty());
+    assert_eq!(report.unchanged.len(), 3);
+    assert_eq!(
+        std::fs::read_to_string(root.path().join("project-lock.json")).unwrap(),
+        written
+    );
+
+    // fonts 需要 icons ^1.0，与项目的 ^2.0 冲突
+    publish(&manager, "icons", "2.0.0", "").await;
+    write_project(root.path(), "fonts = \"^2.0\"\nicons = \"^2.0\"\n");
+    let err = manager
+        .sync_project(root.path(), None, false)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("icons"), "{}", err);
+
+    // 需求变化时升级，不再需要的包被删除
+    write_project(root.path(), "icons = \"^2.0\"\n");
+    let report = manager
+        .sync_project(root.path(), None, false)
+        .await
+        .unwrap();
+    assert_eq!(report.installed, ["icons@2.0.0"]);
+    assert_eq!(report.removed, ["fonts@2.0.0", "themes@0.1.0"]);
+    assert_eq!(read_data(root.path(), "icons"), "2.0.0");
+    assert!(!root.path().join("packages/themes").exists());
+    assert!(!root.path().join("packages/fonts").exists());
+    let lock = ProjectLock::load(root.path(), None).unwrap().unwrap();
+    assert_eq!(lock.packages.len(), 1);
+    assert_eq!(lock.packages[0].version, "2.0.0");
+}
+
+#[tokio::test]
+async fn test_sync_frozen() {
+    let bucket = MockBucket::start().await;
+    let manager = bucket.manager();
+    publish(&manager, "icons", "1.0.0", "").await;
+
+    let root = tempfile::tempdir().unwrap();
+    write_project(root.path(), "icons = \"^1.0\"\n");
+    let err = manager
+        .sync_project(root.path(), None, true)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, BeepkgError::NotFound(_)), "{}", err);
+
+    manager
+        .sync_project(root.path(), None, false)
+        .await
+        .unwrap();
+    std::fs::remove_dir_all(root.path().join("packages")).unwrap();
+    // 新版本发布后 --frozen 仍安装锁定的版本
+    publish(&manager, "icons", "1.1.0", "").await;
+    let report = manager.sync_project(root.path(), None, true).await.unwrap();
+    assert_eq!(report.installed, ["icons@1.0.0"]);
+    assert_eq!(read_data(root.path(), "icons"), "1.0.0");
+
+    // 清单修改后锁文件过期
+    write_project(root.path(), "icons = \"^1.1\"\n");
+    let err = manager
+        .sync_project(root.path(), None, true)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("out of date"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_sync_profiles() {
+    let bucket = MockBucket::start().await;
+    let manager = bucket.manager();
+    publish(&manager, "icons", "1.0.0", "").await;
+    publish(&manager, "icons", "1.1.0", "").await;
+    publish(&manager, "fonts", "1.0.0", "").await;
+    publish(&manager, "analytics", "3.0.0", "").await;
+
+    let root = tempfile::tempdir().unwrap();
+    write_project(
+        root.path(),
+        "icons = \"1.0.0\"\nfonts = \"1.0.0\"\n\
+         [profile.prod]\nicons = \"1.1.0\"\nanalytics = \"^3.0\"\n",
+    );
+    let report = manager
+        .sync_project(root.path(), None, false)
+        .await
+        .unwrap();
+    assert_eq!(report.installed, ["fonts@1.0.0", "icons@1.0.0"]);
+
+    // 配置覆盖版本并增加包，使用自己的锁文件
+    let report = manager
+        .sync_project(root.path(), Some("prod"), false)
+        .await
+        .unwrap();
+    assert_eq!(report.installed, ["analytics@3.0.0", "icons@1.1.0"]);
+    assert_eq!(report.unchanged, ["fonts@1.0.0"]);
+    assert_eq!(read_data(root.path(), "icons"), "1.1.0");
+    let lock = ProjectLock::load(root.path(), Some("prod"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(lock.requirements["icons"], "1.1.0");
+    assert_eq!(
+        ProjectLock::load(root.path(), None)
+            .unwrap()
+            .unwrap()
+            .packages[1]
+            .version,
+        "1.0.0"
+    );
+
+    // 切换回默认配置时按安装记录替换和删除包
+    let report = manager
+        .sync_project(root.path(), None, false)
+        .await
+        .unwrap();
+    assert_eq!(report.installed, ["icons@1.0.0"]);
+    assert_eq!(report.removed, ["analytics@3.0.0"]);
+    assert_eq!(read_data(root.path(), "icons"), "1.0.0");
+    assert!(!root.path().join("packages/analytics").exists());
+
+    let report = manager
+        .sync_project(root.path(), Some("prod"), true)
+        .await
+        .unwrap();
+    assert_eq!(report.installed, ["analytics@3.0.0", "icons@1.1.0"]);
+    assert_eq!(report.unchanged, ["fonts@1.0.0"]);
+
+    let err = manager
+        .sync_project(root.path(), Some("staging"), false)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("defined: prod"), "{}", err);
+}
+
+#[cfg(feature = "archives")]
+#[tokio::test]
+async fn test_project_bundle() {
+    use beepkg::events::SilentObserver;
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+
+    let bucket = MockBucket::start().await;
+    let source = bucket.manager();
+    publish(&source, "icons", "1.0.0", "").await;
+    publish(&source, "fonts", "2.0.0", "icons = \"^1.0\"\n").await;
+    publish(&source, "analytics", "3.0.0", "").await;
+
+    let root = tempfile::tempdir().unwrap();
+    let project = "fonts = \"^2.0\"\n[profile.prod]\nanalytics = \"3.0.0\"\n";
+    write_project(root.path(), project);
+    // 锁文件与清单一致时按锁定的版本打包
+    source.sync_project(root.path(), None, false).await.unwrap();
+    publish(&source, "icons", "1.1.0", "").await;
+    let output = root.path().join("bundle.tar.zst");
+    let manifest = source
+        .create_project_bundle(root.path(), None, &output)
+        .await
+        .unwrap();
+    let archives: Vec<_> = manifest
+        .packages
+        .iter()
+        .map(|p| p.archive.as_str())
+        .collect();
+    assert_eq!(archives, ["fonts-2.0.0.zip", "icons-1.0.0.zip"]);
+
+    // 目标网络没有注册表：绑定后立即关闭的端口
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let offline = || {
+        PackageManager::builder()
+            .endpoint(format!("http://{}", addr))
+            .bucket("packages")
+            .build()
+            .unwrap()
+            .observer(Arc::new(SilentObserver))
+            .cache(None)
+    };
+    let target = tempfile::tempdir().unwrap();
+    write_project(target.path(), project);
+    let report = offline()
+        .install_bundle(&output, target.path())
+        .await
+        .unwrap();
+    assert_eq!(report.installed, ["fonts@2.0.0", "icons@1.0.0"]);
+    assert_eq!(read_data(target.path(), "icons"), "1.0.0");
+    let report = offline()
+        .install_bundle(&output, target.path())
+        .await
+        .unwrap();
+    assert_eq!(report.unchanged.len(), 2);
+
+    // 没有锁文件的环境配置重新解析，不写锁文件
+    let prod = root.path().join("prod.tar.zst");
+    source
+        .create_project_bundle(root.path(), Some("prod"), &prod)
+        .await
+        .unwrap();
+    assert!(!root.path().join("project-lock.prod.json").exists());
+    let report = offline()
+        .install_bundle(&prod, target.path())
+        .await
+        .unwrap();
+    assert_eq!(report.installed, ["analytics@3.0.0", "icons@1.1.0"]);
+    assert_eq!(report.unchanged, ["fonts@2.0.0"]);
+
+    write_project(target.path(), "fonts = \"^2.1\"\n");
+    let err = offline()
+        .install_bundle(&output, target.path())
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("different requirements"),
+        "{}",
+        err
+    );
+
+    let exported = root.path().join("registry.tar.zst");
+    source
+        .export_bundle(&exported, &["icons@1.0.0".to_string()], None)
+        .await
+        .unwrap();
+    let err = offline()
+        .install_bundle(&exported, target.path())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("not a project bundle"), "{}", err);
+}
+
+#[test]
+fn test_load_project() {
+    let root = tempfile::tempdir().unwrap();
+    assert!(Project::load(root.path()).is_err());
+
+    std::fs::write(
+        root.path().join("project.toml"),
+        "dir = \"assets/vendor\"\n[packages]\nicons = \"1.0.0\"\n",
+    )
+    .unwrap();
+    let project = Project::load(root.path()).unwrap();
+    assert_eq!(project.dir, "assets/vendor");
+
+    for content in [
+        "dir = \"../shared\"\n[packages]\nicons = \"1.0.0\"\n",
+        "dir = \"/opt\"\n[packages]\nicons = \"1.0.0\"\n",
+        "[packages]\n",
+        "[packages]\n\"../icons\" = \"1.0.0\"\n",
+        "[packages]\nicons = \"1.0.0\"\n[profile.\"../prod\"]\nfonts = \"1.0.0\"\n",
+        "[packages]\nicons = \"1.0.0\"\n[profile.prod]\n\"../fonts\" = \"1.0.0\"\n",
+    ] {
+        std::fs::write(root.path().join("project.toml"), content).unwrap();
+        assert!(Project::load(root.path()).is_err(), "{}", content);
+    }
+}
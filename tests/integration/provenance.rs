@@ -0,0 +1,22 @@
+use beepkg::checksum::{Checksum, ChecksumAlgorithm};
+use beepkg::provenance::{PREDICATE_TYPE, Statement};
+
+#[test]
+fn test_statement_subject_matches_checksum() {
+    let dir = tempfile::tempdir().unwrap();
+    let checksum = Checksum::compute(ChecksumAlgorithm::Sha256, b"archive");
+    let statement = Statement::for_package(
+        "demo-1.0.0.zip",
+        "demo@1.0.0",
+        &checksum,
+        dir.path(),
+        "alice",
+        chrono::Utc::now(),
+    );
+
+    let json = serde_json::to_string(&statement).unwrap();
+    let parsed: Statement = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.predicate_type, PREDICATE_TYPE);
+    assert!(parsed.matches(&checksum));
+    assert!(!parsed.matches(&Checksum::compute(ChecksumAlgorithm::Sha256, b"other")));
+}
@@ -0,0 +1,230 @@
+use super::test_helpers::{MockBucket, manager_for};
+use beepkg::cache::Cache;
+use beepkg::checksum::{Checksum, ChecksumAlgorithm};
+use beepkg::models::{PackageAccess, RegistryMetadata, Visibility};
+use beepkg::operations::PackageManager;
+use beepkg::proxy;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+const CONTENT: &[u8] = b"demo package content";
+
+// 以 manager 为上游启动代理，包文件缓存在 dir 中
+async fn start_proxy(manager: PackageManager, dir: &Path, options: proxy::Options) -> SocketAddr {
+    let manager = manager.cache(Some(Cache::new(dir.into())));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(proxy::serve(Arc::new(manager), listener, options));
+    addr
+}
+
+#[tokio::test]
+async fn test_proxy_caches_packages() {
+    let dir = tempfile::tempdir().unwrap();
+    let upstream = MockBucket::with_package("demo", "1.0.0", CONTENT).await;
+    let proxy = start_proxy(upstream.manager(), dir.path(), proxy::Options::default()).await;
+
+    let client = reqwest::Client::new();
+    // 客户端的 bucket 名称和签名参数不影响结果
+    let url = format!(
+        "http://{}/any-bucket/demo-1.0.0.zip?X-Amz-Signature=abc",
+        proxy
+    );
+    for _ in 0..2 {
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.bytes().await.unwrap().as_ref(), CONTENT);
+    }
+    assert_eq!(upstream.count("GET", "demo-1.0.0.zip"), 1);
+
+    // 其他请求转发到上游
+    let checksum = client
+        .get(format!("http://{}/any-bucket/demo-1.0.0.zip.sha256", proxy))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(checksum.status(), 200);
+    let missing = client
+        .get(format!("http://{}/any-bucket/other-1.0.0.zip", proxy))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), 404);
+}
+
+#[tokio::test]
+async fn test_proxy_is_read_only() {
+    let dir = tempfile::tempdir().unwrap();
+    let proxy = start_proxy(
+        manager_for("http://127.0.0.1:1"),
+        dir.path(),
+        proxy::Options::default(),
+    )
+    .await;
+
+    let client = reqwest::Client::new();
+    let url = format!("http://{}/packages/demo-1.0.0.zip", proxy);
+    let put = client.put(&url).body("content").send().await.unwrap();
+    assert_eq!(put.status(), 405);
+    // 上游不可达
+    let get = client.get(&url).send().await.unwrap();
+    assert_eq!(get.status(), 502);
+}
+
+#[tokio::test]
+async fn test_proxy_dashboard() {
+    let dir = tempfile::tempdir().unwrap();
+    let upstream = MockBucket::with_package("demo", "1.0.0", CONTENT).await;
+    let options = proxy::Options {
+        dashboard: true,
+        ..Default::default()
+    };
+    let proxy = start_proxy(upstream.manager(), dir.path(), options).await;
+
+    let client = reqwest::Client::new();
+    let page = client
+        .get(format!("http://{}/-/", proxy))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(page.status(), 200);
+    assert!(
+        page.text()
+            .await
+            .unwrap()
+            .contains("<title>beepkg registry</title>")
+    );
+
+    let packages: serde_json::Value = client
+        .get(format!("http://{}/-/api/packages?q=DEM", proxy))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(packages[0]["name"], "demo");
+    assert_eq!(packages[0]["version"], "1.0.0");
+    let filtered: serde_json::Value = client
+        .get(format!("http://{}/-/api/packages?q=other", proxy))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(filtered, serde_json::json!([]));
+
+    for api in ["locks", "backups", "audit"] {
+        let response = client
+            .get(format!("http://{}/-/api/{}", proxy, api))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200, "{}", api);
+        assert_eq!(
+            response.json::<serde_json::Value>().await.unwrap(),
+            serde_json::json!([])
+        );
+    }
+    let unknown = client
+        .get(format!("http://{}/-/api/unknown", proxy))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(unknown.status(), 404);
+
+    // 未启用控制台时 /-/ 按对象请求处理
+    let plain = start_proxy(upstream.manager(), dir.path(), proxy::Options::default()).await;
+    let response = client
+        .get(format!("http://{}/-/api/packages", plain))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+}
+
+#[tokio::test]
+async fn test_proxy_refuses_registry_objects_and_private_packages() {
+    let dir = tempfile::tempdir().unwrap();
+    let upstream = MockBucket::with_package("demo", "1.0.0", CONTENT).await;
+    let checksum = Checksum::compute(ChecksumAlgorithm::Sha256, b"secret");
+    let mut registry = RegistryMetadata::default();
+    registry.access.insert(
+        "secret".to_string(),
+        PackageAccess {
+            visibility: Visibility::Private,
+            owner: Some("alice".to_string()),
+            grants: Vec::new(),
+        },
+    );
+    let registry = serde_json::to_string(&registry).unwrap();
+    {
+        let mut objects = upstream.objects.lock().unwrap();
+        objects.insert("registry-metadata.json".to_string(), registry.into_bytes());
+        objects.insert("secret-1.0.0.zip".to_string(), b"secret".to_vec());
+        objects.insert(
+            "secret-1.0.0.zip.sha256".to_string(),
+            checksum.to_string().into_bytes(),
+        );
+        objects.insert("audit/2024-05-01.json".to_string(), b"[]".to_vec());
+        objects.insert(
+            "demo-1.0.0-backup-1714564800.zip".to_string(),
+            CONTENT.to_vec(),
+        );
+    }
+    let options = proxy::Options {
+        token: Some("s3cret-token".to_string()),
+        ..Default::default()
+    };
+    let proxy = start_proxy(upstream.manager(), dir.path(), options).await;
+
+    let client = reqwest::Client::new();
+    let get = |key: &str, token: Option<&str>| {
+        let mut request = client.get(format!("http://{}/packages/{}", proxy, key));
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        request.send()
+    };
+    assert_eq!(get("demo-1.0.0.zip", None).await.unwrap().status(), 401);
+    assert_eq!(
+        get("demo-1.0.0.zip", Some("wrong")).await.unwrap().status(),
+        401
+    );
+    let token = Some("s3cret-token");
+    let demo = get("demo-1.0.0.zip", token).await.unwrap();
+    assert_eq!(demo.status(), 200);
+    assert_eq!(demo.bytes().await.unwrap().as_ref(), CONTENT);
+    assert_eq!(
+        get("demo-1.0.0.zip.sha256", token).await.unwrap().status(),
+        200
+    );
+
+    for key in [
+        "registry-metadata.json",
+        "secret-1.0.0.zip",
+        "secret-1.0.0.zip.sha256",
+        "audit/2024-05-01.json",
+        "demo-1.0.0-backup-1714564800.zip",
+    ] {
+        let response = get(key, token).await.unwrap();
+        assert_eq!(response.status(), 403, "{}", key);
+    }
+    assert_eq!(upstream.count("GET", "secret-1.0.0.zip"), 0);
+
+    // 列表中只有可见的包的对象
+    let listing = get("", token).await.unwrap().text().await.unwrap();
+    assert!(listing.contains("<Key>demo-1.0.0.zip</Key>"), "{}", listing);
+    assert!(listing.contains("<Key>demo-1.0.0.zip.sha256</Key>"));
+    for hidden in [
+        "secret-1.0.0.zip",
+        "registry-metadata.json",
+        "audit/",
+        "backup",
+    ] {
+        assert!(!listing.contains(hidden), "{}", listing);
+    }
+}
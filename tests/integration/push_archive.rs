@@ -0,0 +1,156 @@
+use super::test_helpers::MockBucket;
+use beepkg::checksum::{Checksum, ChecksumAlgorithm};
+use beepkg::error::BeepkgError;
+use std::io::Write;
+use std::path::Path;
+
+// 写入包含 files 的包文件
+fn write_archive(path: &Path, files: &[(&str, &str)]) {
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+    for (name, content) in files {
+        zip.start_file(*name, Default::default()).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+fn pack_toml(version: &str, extra: &str) -> String {
+    format!(
+        "name = \"demo\"\nversion = \"{}\"\nauthor = \"\"\ndescription = \"\"\n\
+         includes = []\nexcludes = []\n{}\n[dependencies]\n",
+        version, extra
+    )
+}
+
+#[tokio::test]
+async fn test_push_archive_uploads_exact_bytes() {
+    let bucket = MockBucket::start().await;
+    let manager = bucket.manager();
+    let dir = tempfile::tempdir().unwrap();
+    let archive = dir.path().join("demo-build.zip");
+    let toml = pack_toml("1.2.0", "readme = \"docs/README.md\"");
+    write_archive(
+        &archive,
+        &[
+            ("pack.toml", toml.as_str()),
+            ("bin/tool", "binary"),
+            ("docs/README.md", "# demo"),
+        ],
+    );
+    let bytes = std::fs::read(&archive).unwrap();
+
+    let metadata = manager.push_archive(&archive, false).await.unwrap();
+    assert_eq!(
+        (metadata.name.as_str(), metadata.version.as_str()),
+        ("demo", "1.2.0")
+    );
+    {
+        let objects = bucket.objects.lock().unwrap();
+        assert_eq!(objects["demo-1.2.0.zip"], bytes);
+        let checksum = Checksum::compute(ChecksumAlgorithm::Sha256, &bytes);
+        let stored = String::from_utf8(objects["demo-1.2.0.zip.sha256"].clone()).unwrap();
+        assert_eq!(
+            Checksum::parse(&stored, ChecksumAlgorithm::Sha256).unwrap(),
+            checksum
+        );
+        assert_eq!(objects["demo-1.2.0.zip.readme"], b"# demo");
+        assert!(objects.contains_key("demo-1.2.0.zip.meta.json"));
+    }
+    // 包文件保持原样，目录中没有留下临时文件
+    assert_eq!(std::fs::read(&archive).unwrap(), bytes);
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+    let err = manager.push_archive(&archive, false).await.unwrap_err();
+    assert!(matches!(err, BeepkgError::Conflict(_)), "{}", err);
+    manager.push_archive(&archive, true).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_push_archive_rejects_invalid_archives() {
+    let bucket = MockBucket::start().await;
+    let manager = bucket.manager();
+    let dir = tempfile::tempdir().unwrap();
+
+    let archive = dir.path().join("no-metadata.zip");
+    write_archive(&archive, &[("nested/pack.toml", &pack_toml("1.0.0", ""))]);
+    let err = manager.push_archive(&archive, false).await.unwrap_err();
+    assert!(err.to_string().contains("pack.toml"), "{}", err);
+
+    let archive = dir.path().join("bad-version.zip");
+    write_archive(&archive, &[("pack.toml", &pack_toml("latest", ""))]);
+    let err = manager.push_archive(&archive, false).await.unwrap_err();
+    assert!(err.to_string().contains("Invalid version"), "{}", err);
+
+    // 平台产物需要从包目录推送
+    let archive = dir.path().join("targets.zip");
+    let toml = pack_toml(
+        "1.0.0",
+        "[targets.linux-x86_64]\nincludes = [\"bin/linux/**\"]\n",
+    );
+    write_archive(&archive, &[("pack.toml", &toml)]);
+    let err = manager.push_archive(&archive, false).await.unwrap_err();
+    assert!(err.to_string().contains("package directory"), "{}", err);
+
+    let archive = dir.path().join("missing-readme.zip");
+    write_archive(
+        &archive,
+        &[("pack.toml", &pack_toml("1.0.0", "readme = \"README.md\""))],
+    );
+    let err = manager.push_archive(&archive, false).await.unwrap_err();
+    assert!(err.to_string().contains("README"), "{}", err);
+
+    assert!(
+        !bucket
+            .objects
+            .lock()
+            .unwrap()
+            .keys()
+            .any(|key| key.ends_with(".zip")),
+        "{:?}",
+        bucket.objects.lock().unwrap().keys().collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn test_force_push_refreshes_locked_checksum() {
+    let bucket = MockBucket::start().await;
+    let manager = bucket.manager();
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("pack.toml"), pack_toml("1.0.0", "")).unwrap();
+    std::fs::write(dir.path().join("data.txt"), "first").unwrap();
+    manager.push_package(dir.path()).await.unwrap();
+    manager
+        .lock_package("demo", "1.0.0", "release", "ci", None)
+        .await
+        .unwrap();
+    let locked_checksum = || {
+        let registry: serde_json::Value =
+            serde_json::from_slice(&bucket.object("registry-metadata.json").unwrap()).unwrap();
+        registry["locked_packages"][0]["checksum"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    };
+    let stored_checksum = || {
+        String::from_utf8(bucket.object("demo-1.0.0.zip.sha256").unwrap())
+            .unwrap()
+            .trim()
+            .to_string()
+    };
+
+    // 从包目录强制推送和推送包文件都更新锁定记录中的校验和
+    std::fs::write(dir.path().join("data.txt"), "second").unwrap();
+    manager.force_push_package(dir.path()).await.unwrap();
+    assert_eq!(locked_checksum(), stored_checksum());
+
+    let archive = dir.path().join("demo-build.zip");
+    let toml = pack_toml("1.0.0", "");
+    write_archive(
+        &archive,
+        &[("pack.toml", toml.as_str()), ("data.txt", "third")],
+    );
+    let before = locked_checksum();
+    manager.push_archive(&archive, true).await.unwrap();
+    assert_ne!(locked_checksum(), before);
+    assert_eq!(locked_checksum(), stored_checksum());
+}
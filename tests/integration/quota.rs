@@ -0,0 +1,145 @@
+use super::test_helpers::{MockBucket, write_package};
+use beepkg::error::BeepkgError;
+use beepkg::models::{QuotaPolicy, RegistryMetadata};
+use beepkg::quota;
+use std::path::Path;
+
+// 包含 size 字节不可压缩数据的包
+fn write_sized_package(dir: &Path, name: &str, version: &str, size: usize) {
+    write_package(dir, name, version);
+    let mut state = 7u32;
+    let data: Vec<u8> = (0..size)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 16) as u8
+        })
+        .collect();
+    std::fs::write(dir.join("data.bin"), data).unwrap();
+}
+
+fn registry(quota: serde_json::Value, scopes: serde_json::Value) -> RegistryMetadata {
+    serde_json::from_value(serde_json::json!({
+        "registry_name": "test",
+        "backup_enabled": false,
+        "locked_packages": [],
+        "backups": [],
+        "last_updated": "",
+        "quota": quota,
+        "scopes": scopes,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_namespace_limits() {
+    let registry = registry(
+        serde_json::json!({"max_namespace_bytes": 1000}),
+        serde_json::json!({"acme": {"max_bytes": 5000}, "open": {}}),
+    );
+    assert_eq!(quota::namespace_of("@acme/lib"), "@acme");
+    assert_eq!(quota::namespace_of("demo"), "demo");
+    assert_eq!(quota::namespace_limit(&registry, "@acme"), Some(5000));
+    assert_eq!(quota::namespace_limit(&registry, "@open"), Some(1000));
+    assert_eq!(quota::namespace_limit(&registry, "demo"), Some(1000));
+
+    let report = quota::summarize(
+        [
+            ("@acme/lib", 300),
+            ("@acme/cli", 200),
+            ("demo", 1200),
+            ("demo", 100),
+        ],
+        &registry,
+    );
+    let rows: Vec<(&str, u64, usize, bool)> = report
+        .iter()
+        .map(|u| (u.namespace.as_str(), u.used, u.versions, u.exceeded()))
+        .collect();
+    assert_eq!(rows, [("demo", 1300, 2, true), ("@acme", 500, 2, false)]);
+
+    // 设置了配额但还没有包的作用域也会列出
+    let registry = self::registry(
+        serde_json::json!({}),
+        serde_json::json!({"acme": {"max_bytes": 5000}}),
+    );
+    let report = quota::summarize([], &registry);
+    assert_eq!(report.len(), 1);
+    assert_eq!((report[0].used, report[0].limit), (0, Some(5000)));
+}
+
+#[tokio::test]
+async fn test_max_artifact_size() {
+    let bucket = MockBucket::start().await;
+    let manager = bucket.manager();
+    manager
+        .set_quota(QuotaPolicy {
+            max_artifact_size: Some(10_000),
+            max_namespace_bytes: None,
+        })
+        .await
+        .unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    write_sized_package(dir.path(), "demo", "1.0.0", 20_000);
+    let err = manager.push_package(dir.path()).await.unwrap_err();
+    assert!(matches!(err, BeepkgError::QuotaExceeded(_)), "{}", err);
+    assert_eq!(err.exit_code(), 11);
+    // 超出限制时不上传任何内容
+    assert!(
+        !bucket
+            .objects
+            .lock()
+            .unwrap()
+            .keys()
+            .any(|key| key.starts_with("demo-"))
+    );
+
+    write_sized_package(dir.path(), "demo", "1.0.0", 1_000);
+    manager.push_package(dir.path()).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_namespace_quota() {
+    let bucket = MockBucket::start().await;
+    let manager = bucket.manager();
+    manager
+        .set_quota(QuotaPolicy {
+            max_artifact_size: None,
+            max_namespace_bytes: Some(50_000),
+        })
+        .await
+        .unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    write_sized_package(dir.path(), "@acme/lib", "1.0.0", 30_000);
+    manager.push_package(dir.path()).await.unwrap();
+    write_sized_package(dir.path(), "@acme/cli", "1.0.0", 30_000);
+    let err = manager.push_package(dir.path()).await.unwrap_err();
+    assert!(matches!(err, BeepkgError::QuotaExceeded(_)), "{}", err);
+    assert!(err.to_string().contains("@acme"), "{}", err);
+
+    // 强制推送替换已有版本时不重复计算旧的包文件
+    write_sized_package(dir.path(), "@acme/lib", "1.0.0", 40_000);
+    manager.force_push_package(dir.path()).await.unwrap();
+
+    // 其他命名空间不受影响，作用域的配额覆盖默认配额
+    write_sized_package(dir.path(), "demo", "1.0.0", 30_000);
+    manager.push_package(dir.path()).await.unwrap();
+    manager
+        .set_scope_quota("@acme", Some(100_000))
+        .await
+        .unwrap();
+    write_sized_package(dir.path(), "@acme/cli", "1.0.0", 30_000);
+    manager.push_package(dir.path()).await.unwrap();
+
+    let report = manager.quota_status().await.unwrap();
+    let rows: Vec<(&str, usize, Option<u64>)> = report
+        .iter()
+        .map(|u| (u.namespace.as_str(), u.versions, u.limit))
+        .collect();
+    assert_eq!(
+        rows,
+        [("@acme", 2, Some(100_000)), ("demo", 1, Some(50_000))]
+    );
+    assert!(report[0].used > 70_000 && report[0].used < 100_000);
+}
@@ -0,0 +1,130 @@
+use super::test_helpers::MockBucket;
+use beepkg::error::BeepkgError;
+use beepkg::readme::{self, SparseArchive};
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+// README 在前、200 KiB 不可压缩的数据在后，归档末尾的 64 KiB 不包含 README
+fn archive(readme_name: &str) -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    zip.start_file("pack.toml", Default::default()).unwrap();
+    zip.write_all(
+        b"name = \"demo\"\nversion = \"1.0.0\"\nauthor = \"\"\ndescription = \"\"\n\
+          includes = []\nexcludes = []\n\n[dependencies]\n",
+    )
+    .unwrap();
+    zip.start_file(readme_name, Default::default()).unwrap();
+    zip.write_all("# Demo\n\nUsage: demo --help\n".repeat(20).as_bytes())
+        .unwrap();
+    let stored =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    zip.start_file("data.bin", stored).unwrap();
+    let mut state = 1u32;
+    let noise: Vec<u8> = (0..200 * 1024)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 16) as u8
+        })
+        .collect();
+    zip.write_all(&noise).unwrap();
+    zip.finish().unwrap().into_inner()
+}
+
+#[test]
+fn test_find_readme() {
+    let names = ["pack.toml", "docs/README.md", "Readme.txt", "README"];
+    assert_eq!(readme::find(names, None).as_deref(), Some("README"));
+    assert_eq!(
+        readme::find(names, Some("./docs/README.md")).as_deref(),
+        Some("docs/README.md")
+    );
+    // 声明的文件不存在时按常见文件名查找
+    assert_eq!(
+        readme::find(names, Some("GUIDE.md")).as_deref(),
+        Some("README")
+    );
+    assert_eq!(readme::find(["pack.toml", "docs/README.md"], None), None);
+}
+
+#[test]
+fn test_central_directory() {
+    let data = archive("README.md");
+    let tail = &data[data.len() - readme::TAIL_SIZE as usize..];
+    let (offset, size) = readme::central_directory(tail).unwrap();
+    assert_eq!(offset + size + 22, data.len() as u64);
+
+    let entries = readme::parse_central_directory(&data[offset as usize..(offset + size) as usize]);
+    let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, ["pack.toml", "README.md", "data.bin"]);
+    assert!(entries[1].header_offset > 0);
+    assert!(readme::central_directory(b"not a zip").is_none());
+}
+
+#[test]
+fn test_sparse_archive_reads_only_downloaded_ranges() {
+    let mut archive = SparseArchive::new(100);
+    archive.insert(90, bytes::Bytes::from(vec![7u8; 10]));
+    assert!(archive.contains(92, 100));
+    assert!(!archive.contains(80, 95));
+
+    let mut buf = [0u8; 4];
+    archive.seek(SeekFrom::End(-4)).unwrap();
+    archive.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, [7; 4]);
+    assert_eq!(archive.read(&mut buf).unwrap(), 0);
+    archive.seek(SeekFrom::Start(10)).unwrap();
+    assert!(archive.read(&mut buf).is_err());
+}
+
+#[tokio::test]
+async fn test_readme_read_from_archive_with_ranges() {
+    let data = archive("Readme.md");
+    let size = data.len();
+    let bucket =
+        MockBucket::with_objects(BTreeMap::from([("demo-1.0.0.zip".to_string(), data)])).await;
+
+    let readme = bucket.manager().readme("demo@1.0.0").await.unwrap();
+    assert!(readme.starts_with("# Demo\n\nUsage: demo --help"));
+    // 只下载了归档末尾和 README 所在的片段
+    let downloaded = bucket.served("demo-1.0.0.zip");
+    assert!(downloaded < size / 2, "{} of {} bytes", downloaded, size);
+}
+
+#[tokio::test]
+async fn test_readme_missing() {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    zip.start_file("pack.toml", Default::default()).unwrap();
+    let data = zip.finish().unwrap().into_inner();
+    let bucket =
+        MockBucket::with_objects(BTreeMap::from([("demo-1.0.0.zip".to_string(), data)])).await;
+
+    let err = bucket.manager().readme("demo@1.0.0").await.unwrap_err();
+    assert!(matches!(err, BeepkgError::NotFound(_)), "{}", err);
+}
+
+#[tokio::test]
+async fn test_declared_readme_uploaded_on_push() {
+    let bucket = MockBucket::start().await;
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("pack.toml"),
+        "name = \"demo\"\nversion = \"1.0.0\"\nauthor = \"\"\ndescription = \"\"\n\
+         readme = \"docs/GUIDE.md\"\nincludes = []\nexcludes = []\n\n[dependencies]\n",
+    )
+    .unwrap();
+    let manager = bucket.manager();
+    // 声明的 README 不存在时推送失败
+    let err = manager.push_package(dir.path()).await.unwrap_err();
+    assert!(err.to_string().contains("GUIDE.md"), "{}", err);
+
+    std::fs::create_dir(dir.path().join("docs")).unwrap();
+    std::fs::write(dir.path().join("docs/GUIDE.md"), "# Guide\n").unwrap();
+    manager.push_package(dir.path()).await.unwrap();
+    assert_eq!(
+        bucket.object("demo-1.0.0.zip.readme").unwrap(),
+        b"# Guide\n"
+    );
+
+    assert_eq!(manager.readme("demo@1.0.0").await.unwrap(), "# Guide\n");
+    assert_eq!(bucket.served("demo-1.0.0.zip"), 0);
+}
@@ -0,0 +1,145 @@
+use beepkg::models::{LockedPackage, PackageBackup, RetentionRule};
+use beepkg::retention::{self, KeepReason};
+use std::collections::BTreeMap;
+
+fn rule(keep_last: usize) -> RetentionRule {
+    RetentionRule {
+        keep_last,
+        keep_channels: true,
+        keep_locked: true,
+    }
+}
+
+fn versions(list: &[&str]) -> Vec<semver::Version> {
+    list.iter()
+        .map(|version| semver::Version::parse(version).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_retention_rule_for_prefers_specific_patterns() {
+    let mut rules = BTreeMap::new();
+    rules.insert("*".to_string(), rule(10));
+    rules.insert("@acme/*".to_string(), rule(5));
+    rules.insert("@acme/big".to_string(), rule(1));
+
+    let pattern = |name| retention::rule_for(&rules, name).unwrap().map(|(p, _)| p);
+    assert_eq!(pattern("@acme/big"), Some("@acme/big"));
+    assert_eq!(pattern("@acme/small"), Some("@acme/*"));
+    assert_eq!(pattern("demo"), Some("*"));
+
+    rules.remove("*");
+    assert_eq!(retention::rule_for(&rules, "demo").unwrap(), None);
+    assert!(retention::validate_pattern("[").is_err());
+}
+
+#[test]
+fn test_retention_plan() {
+    let mut channels = BTreeMap::new();
+    channels.insert("stable".to_string(), "1.0.0".to_string());
+    let now = chrono::Utc::now();
+    let locks = vec![
+        LockedPackage {
+            name: "demo".to_string(),
+            version: "1.1.0".to_string(),
+            lock_reason: "audit".to_string(),
+            locked_at: now.to_rfc3339(),
+            locked_by: "ops".to_string(),
+            checksum: String::new(),
+            expires_at: None,
+        },
+        // 过期的锁不再保留版本
+        LockedPackage {
+            name: "demo".to_string(),
+            version: "1.2.0".to_string(),
+            lock_reason: "release".to_string(),
+            locked_at: now.to_rfc3339(),
+            locked_by: "ops".to_string(),
+            checksum: String::new(),
+            expires_at: Some("2000-01-01T00:00:00Z".to_string()),
+        },
+    ];
+
+    let decisions = retention::plan(
+        "demo",
+        &versions(&["1.0.0", "1.1.0", "1.2.0", "2.0.0", "2.1.0"]),
+        &rule(2),
+        Some(&channels),
+        &locks,
+        now,
+    );
+    let summary: Vec<(String, Vec<KeepReason>)> = decisions
+        .into_iter()
+        .map(|decision| (decision.version.to_string(), decision.keep))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("2.1.0".to_string(), vec![KeepReason::Recent]),
+            ("2.0.0".to_string(), vec![KeepReason::Recent]),
+            ("1.2.0".to_string(), vec![]),
+            ("1.1.0".to_string(), vec![KeepReason::Locked]),
+            (
+                "1.0.0".to_string(),
+                vec![KeepReason::Channel("stable".to_string())]
+            ),
+        ]
+    );
+
+    let strict = RetentionRule {
+        keep_last: 1,
+        keep_channels: false,
+        keep_locked: false,
+    };
+    let deleted = retention::plan(
+        "demo",
+        &versions(&["1.0.0", "1.1.0"]),
+        &strict,
+        Some(&channels),
+        &locks,
+        now,
+    )
+    .iter()
+    .filter(|decision| decision.is_deleted())
+    .count();
+    assert_eq!(deleted, 1);
+}
+
+#[test]
+fn test_prune_backups() {
+    let now = chrono::Utc::now();
+    let backup = |original: &str, days_ago: i64| PackageBackup {
+        original_path: original.to_string(),
+        backup_path: format!("{}-backup-{}", original, days_ago),
+        timestamp: (now - chrono::Duration::days(days_ago)).to_rfc3339(),
+        reason: String::new(),
+        bucket: None,
+    };
+    let backups = vec![
+        backup("demo-1.0.0.zip", 200),
+        backup("demo-1.0.0.zip", 100),
+        backup("demo-1.0.0.zip", 10),
+        backup("demo-1.0.0.zip", 1),
+        backup("demo-2.0.0.zip", 300),
+        PackageBackup {
+            timestamp: "yesterday".to_string(),
+            ..backup("demo-2.0.0.zip", 0)
+        },
+    ];
+
+    // 每个包版本保留最新的 2 个
+    assert_eq!(
+        retention::prune_backups(&backups, Some(2), None, now),
+        vec![0, 1]
+    );
+    // 保留 90 天内的备份
+    assert_eq!(
+        retention::prune_backups(&backups, None, Some(90), now),
+        vec![0, 1, 4]
+    );
+    // 两项都设置时满足其一即保留
+    assert_eq!(
+        retention::prune_backups(&backups, Some(1), Some(150), now),
+        vec![0]
+    );
+}
@@ -0,0 +1,171 @@
+use super::test_helpers::{MockBucket, write_package};
+use beepkg::error::BeepkgError;
+use beepkg::models::AuditAction;
+
+#[tokio::test]
+async fn test_rollback_latest() {
+    let bucket = MockBucket::start().await;
+    let manager = bucket.manager().actor("alice");
+    let dir = tempfile::tempdir().unwrap();
+    for version in ["1.0.0", "1.1.0", "1.2.0"] {
+        write_package(dir.path(), "demo", version);
+        manager.push_package(dir.path()).await.unwrap();
+    }
+
+    let rollback = manager
+        .rollback("demo", "latest", None, false)
+        .await
+        .unwrap();
+    assert_eq!(
+        (rollback.from.as_str(), rollback.to.as_str()),
+        ("1.2.0", "1.1.0")
+    );
+    assert!(!rollback.pending);
+    // 不指定版本时使用 latest 频道，回滚掉的版本仍可按版本号拉取
+    assert_eq!(manager.package_info("demo").await.unwrap().version, "1.1.0");
+    assert_eq!(
+        manager.package_info("demo@1.2.0").await.unwrap().version,
+        "1.2.0"
+    );
+
+    // 没有被强制推送覆盖过的版本不需要恢复
+    let rollback = manager
+        .rollback("demo", "latest", None, true)
+        .await
+        .unwrap();
+    assert_eq!(rollback.to, "1.0.0");
+    assert_eq!(rollback.restored_backup, None);
+    let err = manager
+        .rollback("demo", "latest", None, false)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, BeepkgError::NotFound(_)), "{}", err);
+
+    // 推送更高的版本后 latest 随之前进
+    write_package(dir.path(), "demo", "1.3.0");
+    manager.push_package(dir.path()).await.unwrap();
+    assert_eq!(manager.package_info("demo").await.unwrap().version, "1.3.0");
+
+    let records = manager
+        .list_audit_records(None, Some("demo"))
+        .await
+        .unwrap();
+    let rollbacks: Vec<_> = records
+        .iter()
+        .filter(|record| record.action == AuditAction::Rollback)
+        .collect();
+    assert_eq!(rollbacks.len(), 2);
+    assert_eq!(
+        rollbacks[0].details.as_deref(),
+        Some("channel latest from 1.2.0 to 1.1.0")
+    );
+}
+
+#[tokio::test]
+async fn test_rollback_channel() {
+    let bucket = MockBucket::start().await;
+    let alice = bucket.manager().actor("alice");
+    let dir = tempfile::tempdir().unwrap();
+    for version in ["1.0.0", "1.1.0", "1.2.0"] {
+        write_package(dir.path(), "demo", version);
+        alice.push_package(dir.path()).await.unwrap();
+    }
+    alice.tag_package("demo@1.2.0", "stable").await.unwrap();
+
+    let rollback = alice
+        .rollback("demo", "stable", Some("1.0.0"), false)
+        .await
+        .unwrap();
+    assert_eq!(rollback.to, "1.0.0");
+    assert_eq!(
+        alice.package_info("demo@stable").await.unwrap().version,
+        "1.0.0"
+    );
+    // 其他频道不受影响
+    assert_eq!(alice.package_info("demo").await.unwrap().version, "1.2.0");
+
+    let err = alice
+        .rollback("demo", "beta", None, false)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, BeepkgError::NotFound(_)), "{}", err);
+    let err = alice
+        .rollback("demo", "stable", Some("0.9.0"), false)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, BeepkgError::NotFound(_)), "{}", err);
+
+    // 受保护的频道需要另一个用户批准回滚，请求和批准都需要受信任的签名私钥
+    #[cfg(feature = "encryption")]
+    {
+        let (alice, bob) = bucket.approvers();
+        alice.set_channel_protected("stable", true).await.unwrap();
+        let rollback = alice
+            .rollback("demo", "stable", Some("1.1.0"), false)
+            .await
+            .unwrap();
+        assert!(rollback.pending);
+        assert_eq!(
+            alice.package_info("demo@stable").await.unwrap().version,
+            "1.0.0"
+        );
+        bob.approve("demo@1.1.0").await.unwrap();
+        assert_eq!(
+            alice.package_info("demo@stable").await.unwrap().version,
+            "1.1.0"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_rollback_restore() {
+    let bucket = MockBucket::start().await;
+    let manager = bucket.manager().actor("alice");
+    let dir = tempfile::tempdir().unwrap();
+    write_package(dir.path(), "demo", "1.0.0");
+    manager.push_package(dir.path()).await.unwrap();
+    manager
+        .backup_package("demo", "1.0.0", "before release")
+        .await
+        .unwrap();
+    write_package(dir.path(), "demo", "1.1.0");
+    manager.push_package(dir.path()).await.unwrap();
+
+    // 1.0.0 被强制推送覆盖后从之前的备份恢复，校验文件随之更新，拉取仍能通过校验
+    write_package(dir.path(), "demo", "1.0.0");
+    std::fs::write(dir.path().join("data.txt"), "broken").unwrap();
+    manager.force_push_package(dir.path()).await.unwrap();
+    let rollback = manager
+        .rollback("demo", "latest", None, true)
+        .await
+        .unwrap();
+    assert_eq!(rollback.to, "1.0.0");
+    assert!(rollback.restored_backup.is_some());
+    let output = tempfile::tempdir().unwrap();
+    manager.pull_package("demo", output.path()).await.unwrap();
+    assert_eq!(
+        std::fs::read_to_string(output.path().join("data.txt")).unwrap(),
+        "1.0.0"
+    );
+}
+
+#[tokio::test]
+async fn test_rollback_restore_without_backup() {
+    let bucket = MockBucket::start().await;
+    let manager = bucket.manager().actor("alice");
+    let dir = tempfile::tempdir().unwrap();
+    for version in ["1.0.0", "1.1.0"] {
+        write_package(dir.path(), "demo", version);
+        manager.push_package(dir.path()).await.unwrap();
+    }
+    write_package(dir.path(), "demo", "1.0.0");
+    manager.force_push_package(dir.path()).await.unwrap();
+    let err = manager
+        .rollback("demo", "latest", None, true)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, BeepkgError::NotFound(_)), "{}", err);
+    assert!(err.to_string().contains("backup"), "{}", err);
+    // 恢复失败时频道不变
+    assert_eq!(manager.package_info("demo").await.unwrap().version, "1.1.0");
+}
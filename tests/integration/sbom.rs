@@ -0,0 +1,66 @@
+use beepkg::models::PackageMetadata;
+use beepkg::sbom::{self, SbomFormat};
+use std::collections::HashMap;
+
+fn metadata() -> PackageMetadata {
+    PackageMetadata {
+        schema_version: 1,
+        name: "demo".to_string(),
+        version: "1.0.0".to_string(),
+        author: "alice".to_string(),
+        description: "demo package".to_string(),
+        license: Some("MIT".to_string()),
+        homepage: None,
+        repository: None,
+        keywords: Vec::new(),
+        readme: None,
+        maintainers: Vec::new(),
+        includes: Vec::new(),
+        excludes: Vec::new(),
+        dependencies: HashMap::from([
+            ("zlib".to_string(), "1.3".to_string()),
+            ("openssl".to_string(), "3.0".to_string()),
+        ]),
+        encryption: None,
+        visibility: None,
+        targets: Default::default(),
+        artifacts: Default::default(),
+        optional_dependencies: Default::default(),
+        features: Default::default(),
+        hooks: Default::default(),
+        release: Default::default(),
+    }
+}
+
+#[test]
+fn test_cyclonedx_lists_dependencies() {
+    let document = sbom::generate(SbomFormat::CycloneDx, &metadata(), &[]);
+    assert_eq!(document["bomFormat"], "CycloneDX");
+    assert_eq!(
+        document["metadata"]["component"]["purl"],
+        "pkg:generic/demo@1.0.0"
+    );
+    assert_eq!(document["components"][0]["name"], "openssl");
+    assert_eq!(
+        document["dependencies"][0]["dependsOn"][1],
+        "pkg:generic/zlib@1.3"
+    );
+}
+
+#[test]
+fn test_spdx_includes_file_inventory() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("lib.txt"), b"abc").unwrap();
+    let files = sbom::file_inventory(dir.path()).unwrap();
+
+    let document = sbom::generate(SbomFormat::Spdx, &metadata(), &files);
+    assert_eq!(document["spdxVersion"], "SPDX-2.3");
+    assert_eq!(document["packages"].as_array().unwrap().len(), 3);
+    assert_eq!(document["files"][0]["fileName"], "./lib.txt");
+    assert_eq!(
+        document["files"][0]["checksums"][0]["checksumValue"],
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    // DESCRIBES + 2 x DEPENDS_ON + CONTAINS
+    assert_eq!(document["relationships"].as_array().unwrap().len(), 4);
+}
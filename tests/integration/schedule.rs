@@ -0,0 +1,74 @@
+use beepkg::schedule::CronSchedule;
+use chrono::{DateTime, Utc};
+
+fn time(value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value)
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+#[test]
+fn test_cron_next_after() {
+    let daily = CronSchedule::parse("0 3 * * *").unwrap();
+    assert_eq!(
+        daily.next_after(time("2024-05-01T02:59:30Z")),
+        Some(time("2024-05-01T03:00:00Z"))
+    );
+    assert_eq!(
+        daily.next_after(time("2024-05-01T03:00:00Z")),
+        Some(time("2024-05-02T03:00:00Z"))
+    );
+
+    let quarter = CronSchedule::parse("*/15 9-17 * * 1-5").unwrap();
+    // 2024-05-04 是周六
+    assert_eq!(
+        quarter.next_after(time("2024-05-03T17:50:00Z")),
+        Some(time("2024-05-06T09:00:00Z"))
+    );
+    assert!(quarter.matches(time("2024-05-06T09:45:00Z")));
+    assert!(!quarter.matches(time("2024-05-06T09:50:00Z")));
+
+    let sunday = CronSchedule::parse("30 1 * * 7").unwrap();
+    assert_eq!(
+        sunday.next_after(time("2024-05-01T00:00:00Z")),
+        Some(time("2024-05-05T01:30:00Z"))
+    );
+
+    // 日和周都限定时满足其一即可
+    let either = CronSchedule::parse("0 0 1 * 1").unwrap();
+    assert_eq!(
+        either.next_after(time("2024-05-01T00:00:00Z")),
+        Some(time("2024-05-06T00:00:00Z"))
+    );
+
+    assert_eq!(
+        CronSchedule::parse("0 0 30 2 *")
+            .unwrap()
+            .next_after(time("2024-01-01T00:00:00Z")),
+        None
+    );
+}
+
+#[test]
+fn test_cron_is_due() {
+    let daily = CronSchedule::parse("0 3 * * *").unwrap();
+    let last_run = time("2024-05-01T03:00:05Z");
+    assert!(!daily.is_due(last_run, time("2024-05-02T02:59:00Z")));
+    assert!(daily.is_due(last_run, time("2024-05-02T03:00:00Z")));
+    // 错过多次执行时只算一次到期
+    assert!(daily.is_due(last_run, time("2024-05-09T12:00:00Z")));
+}
+
+#[test]
+fn test_cron_rejects_invalid_expressions() {
+    for expression in [
+        "0 3 * *",
+        "60 * * * *",
+        "* 24 * * *",
+        "*/0 * * * *",
+        "5-1 * * * *",
+        "a * * * *",
+    ] {
+        assert!(CronSchedule::parse(expression).is_err(), "{}", expression);
+    }
+}
@@ -0,0 +1,137 @@
+use super::test_helpers::MockBucket;
+use beepkg::error::BeepkgError;
+use beepkg::models::{MetadataFormat, PackageInfo, PackageMetadata, RegistryMetadata};
+use beepkg::schema::{self, Versioned};
+use std::collections::BTreeMap;
+
+const PACK_TOML: &str = r#"
+name = "demo"
+version = "1.0.0"
+author = "alice"
+description = "Demo"
+includes = []
+excludes = []
+
+[dependencies]
+"#;
+
+const REGISTRY: &str = r#"{"registry_name": "demo", "backup_enabled": false,
+    "locked_packages": [], "backups": [], "last_updated": ""}"#;
+
+#[test]
+fn test_every_version_has_a_migration() {
+    assert_eq!(
+        PackageMetadata::MIGRATIONS.len(),
+        PackageMetadata::VERSION as usize
+    );
+    assert_eq!(PackageInfo::MIGRATIONS.len(), PackageInfo::VERSION as usize);
+    assert_eq!(
+        RegistryMetadata::MIGRATIONS.len(),
+        RegistryMetadata::VERSION as usize
+    );
+}
+
+#[test]
+fn test_unversioned_documents_are_upgraded() {
+    let metadata = PackageMetadata::parse(PACK_TOML, MetadataFormat::Toml).unwrap();
+    assert_eq!(metadata.schema_version, PackageMetadata::VERSION);
+    assert_eq!(metadata.name, "demo");
+
+    let registry: RegistryMetadata = schema::from_json(REGISTRY.as_bytes()).unwrap();
+    assert_eq!(registry.schema_version, RegistryMetadata::VERSION);
+
+    let mut value: serde_json::Value = serde_json::from_str(REGISTRY).unwrap();
+    assert_eq!(schema::upgrade::<RegistryMetadata>(&mut value).unwrap(), 0);
+    assert_eq!(value["schema_version"], RegistryMetadata::VERSION);
+    assert_eq!(
+        schema::upgrade::<RegistryMetadata>(&mut value).unwrap(),
+        RegistryMetadata::VERSION
+    );
+}
+
+#[test]
+fn test_webhook_secrets_are_dropped() {
+    let content = br#"{"schema_version": 1, "registry_name": "demo", "backup_enabled": false,
+        "locked_packages": [], "backups": [], "last_updated": "",
+        "webhooks": [{"url": "http://hooks.example.com", "secret": "plain"}]}"#;
+    let mut value: serde_json::Value = serde_json::from_slice(content).unwrap();
+    assert_eq!(schema::upgrade::<RegistryMetadata>(&mut value).unwrap(), 1);
+    assert_eq!(
+        value["webhooks"],
+        serde_json::json!([{"url": "http://hooks.example.com"}])
+    );
+
+    let registry: RegistryMetadata = schema::from_json(content).unwrap();
+    assert_eq!(registry.webhooks[0].secret_env, None);
+    let saved = serde_json::to_string(&registry).unwrap();
+    assert!(!saved.contains("plain"), "{}", saved);
+}
+
+#[test]
+fn test_newer_schema_is_rejected() {
+    let content = format!("schema_version = 99\n{}", PACK_TOML);
+    let err = PackageMetadata::parse(&content, MetadataFormat::Toml).unwrap_err();
+    assert!(
+        err.to_string().contains("newer than the supported"),
+        "{}",
+        err
+    );
+
+    let err =
+        schema::from_json::<PackageInfo>(br#"{"schema_version": 2, "name": "demo"}"#).unwrap_err();
+    assert!(matches!(err, BeepkgError::MetadataParse(_)));
+}
+
+#[test]
+fn test_migrate_local_manifest() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("pack.toml"), PACK_TOML).unwrap();
+
+    let planned = PackageMetadata::migrate(dir.path(), true).unwrap().unwrap();
+    assert_eq!((planned.from, planned.to), (0, 1));
+    let content = std::fs::read_to_string(dir.path().join("pack.toml")).unwrap();
+    assert!(!content.contains("schema_version"));
+
+    PackageMetadata::migrate(dir.path(), false)
+        .unwrap()
+        .unwrap();
+    let content = std::fs::read_to_string(dir.path().join("pack.toml")).unwrap();
+    assert!(content.contains("schema_version = 1"));
+    assert_eq!(PackageMetadata::load(dir.path()).unwrap().name, "demo");
+    assert!(
+        PackageMetadata::migrate(dir.path(), false)
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn test_migrate_registry_metadata() {
+    let info = br#"{"name": "demo", "version": "1.0.0", "author": "alice", "description": ""}"#;
+    let bucket = MockBucket::with_objects(BTreeMap::from([
+        (
+            "registry-metadata.json".to_string(),
+            REGISTRY.as_bytes().to_vec(),
+        ),
+        ("demo-1.0.0.zip".to_string(), b"zip".to_vec()),
+        ("demo-1.0.0.zip.meta.json".to_string(), info.to_vec()),
+    ]))
+    .await;
+    let manager = bucket.manager();
+
+    let planned = manager.migrate_metadata(true).await.unwrap();
+    let keys: Vec<&str> = planned.iter().map(|d| d.key.as_str()).collect();
+    assert_eq!(keys, ["registry-metadata.json", "demo-1.0.0.zip.meta.json"]);
+    assert_eq!(bucket.object("demo-1.0.0.zip.meta.json").unwrap(), info);
+
+    assert_eq!(manager.migrate_metadata(false).await.unwrap().len(), 2);
+    for (key, version) in [
+        ("registry-metadata.json", RegistryMetadata::VERSION),
+        ("demo-1.0.0.zip.meta.json", PackageInfo::VERSION),
+    ] {
+        let value: serde_json::Value =
+            serde_json::from_slice(&bucket.object(key).unwrap()).unwrap();
+        assert_eq!(value["schema_version"], version, "{}", key);
+    }
+    assert!(manager.migrate_metadata(false).await.unwrap().is_empty());
+}
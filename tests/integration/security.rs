@@ -0,0 +1,233 @@
+use beepkg::models::{EncryptionConfig, KmsConfig};
+use beepkg::security::{Cipher, Envelope, FileSelector, KdfParams, SecretSource, SecurityManager};
+
+#[test]
+fn test_encrypt_decrypt_round_trip() {
+    let data = b"PK\x03\x04 archive bytes".repeat(1000);
+    let encrypted = SecurityManager::encrypt_with_secret("s3cret", &data).unwrap();
+
+    assert!(SecurityManager::is_encrypted(&encrypted));
+    assert!(!SecurityManager::is_encrypted(&data));
+    assert_ne!(&encrypted[encrypted.len() - data.len()..], &data[..]);
+
+    let decrypted = SecurityManager::decrypt_with_secret("s3cret", &encrypted).unwrap();
+    assert_eq!(decrypted, data);
+}
+
+#[test]
+fn test_each_encryption_uses_fresh_salt_and_nonce() {
+    let first = SecurityManager::encrypt_with_secret("s3cret", b"same input").unwrap();
+    let second = SecurityManager::encrypt_with_secret("s3cret", b"same input").unwrap();
+    assert_ne!(first, second);
+}
+
+#[test]
+fn test_decrypt_rejects_wrong_secret_and_tampering() {
+    let mut encrypted = SecurityManager::encrypt_with_secret("s3cret", b"payload").unwrap();
+    assert!(SecurityManager::decrypt_with_secret("other", &encrypted).is_err());
+
+    let last = encrypted.len() - 1;
+    encrypted[last] ^= 0x01;
+    assert!(SecurityManager::decrypt_with_secret("s3cret", &encrypted).is_err());
+
+    assert!(SecurityManager::decrypt_with_secret("s3cret", b"plain zip").is_err());
+}
+
+#[test]
+fn test_age_recipients_round_trip() {
+    let (alice_secret, alice_public) = SecurityManager::generate_age_identity().unwrap();
+    let (bob_secret, bob_public) = SecurityManager::generate_age_identity().unwrap();
+    let (eve_secret, _) = SecurityManager::generate_age_identity().unwrap();
+
+    let encrypted =
+        SecurityManager::encrypt_to_recipients(&[alice_public, bob_public], b"archive").unwrap();
+    assert!(SecurityManager::is_age_encrypted(&encrypted));
+    assert!(!SecurityManager::is_encrypted(&encrypted));
+
+    for secret in [&alice_secret, &bob_secret] {
+        let identities = format!("# comment\n{}\n", secret);
+        assert_eq!(
+            SecurityManager::decrypt_with_identities(&identities, &encrypted).unwrap(),
+            b"archive"
+        );
+    }
+    assert!(SecurityManager::decrypt_with_identities(&eve_secret, &encrypted).is_err());
+}
+
+#[test]
+fn test_envelope_round_trip_keeps_wrapped_key() {
+    let data_key = SecurityManager::generate_data_key();
+    let envelope = Envelope {
+        kms: KmsConfig::Vault {
+            key_id: "beepkg".to_string(),
+            mount: "transit".to_string(),
+            address: None,
+        },
+        wrapped_key: "vault:v1:wrapped".to_string(),
+    };
+    let encrypted = SecurityManager::encrypt_envelope(&data_key, &envelope, b"archive").unwrap();
+
+    let stored = SecurityManager::read_envelope(&encrypted).unwrap().unwrap();
+    assert_eq!(stored.wrapped_key, "vault:v1:wrapped");
+    assert!(matches!(stored.kms, KmsConfig::Vault { ref key_id, .. } if key_id == "beepkg"));
+
+    assert_eq!(
+        SecurityManager::decrypt_envelope(&data_key, &encrypted).unwrap(),
+        b"archive"
+    );
+    let other_key = SecurityManager::generate_data_key();
+    assert!(SecurityManager::decrypt_envelope(&other_key, &encrypted).is_err());
+
+    // 密码加密的数据不是信封格式
+    let encrypted = SecurityManager::encrypt_with_secret("s3cret", b"archive").unwrap();
+    assert!(
+        SecurityManager::read_envelope(&encrypted)
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn test_secret_source_from_pack_toml() {
+    let config: EncryptionConfig = toml::from_str(
+        r#"
+enabled = true
+secret_source = "keyring"
+"#,
+    )
+    .unwrap();
+    assert_eq!(config.secret_source, Some(SecretSource::Keyring));
+    assert_eq!("prompt".parse::<SecretSource>(), Ok(SecretSource::Prompt));
+    assert!("vault".parse::<SecretSource>().is_err());
+}
+
+#[test]
+fn test_selective_file_encryption() {
+    let config: EncryptionConfig = toml::from_str(
+        r#"
+enabled = true
+files = ["secrets/**", "*.key", "**"]
+"#,
+    )
+    .unwrap();
+    let selector = FileSelector::new(&config.files[..2]).unwrap();
+
+    assert!(selector.matches("secrets/db/password.txt"));
+    assert!(selector.matches("secrets\\token"));
+    assert!(selector.matches("signing.key"));
+    assert!(!selector.matches("keys/signing.key"));
+    assert!(!selector.matches("README.md"));
+    // 元数据文件始终保持明文
+    assert!(
+        !FileSelector::new(&config.files)
+            .unwrap()
+            .matches("pack.toml")
+    );
+    assert!(FileSelector::new(&["secrets/[".to_string()]).is_err());
+
+    let cipher = Cipher::Secret {
+        secret: "s3cret".to_string(),
+        kdf: KdfParams::default(),
+    };
+    let encrypted = cipher.encrypt(b"token").unwrap();
+    assert_eq!(
+        SecurityManager::decrypt_with_secret("s3cret", &encrypted).unwrap(),
+        b"token"
+    );
+}
+
+#[test]
+fn test_stream_encryption_spans_chunks() {
+    // 三个完整的 64 KiB 分块加一个不满的末块
+    let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    let mut encrypted = Vec::new();
+    SecurityManager::encrypt_stream("s3cret", &KdfParams::default(), &data[..], &mut encrypted)
+        .unwrap();
+    assert!(SecurityManager::is_password_encrypted(&encrypted));
+
+    let mut decrypted = Vec::new();
+    SecurityManager::decrypt_stream("s3cret", &encrypted[..], &mut decrypted).unwrap();
+    assert_eq!(decrypted, data);
+
+    // 去掉最后一块后，剩余的块不是末块，解密失败
+    let truncated = &encrypted[..encrypted.len() - (200_000 - 3 * 65_536) - 16];
+    assert!(SecurityManager::decrypt_with_secret("s3cret", truncated).is_err());
+
+    // 空数据和正好一个分块的数据
+    for data in [Vec::new(), vec![7u8; 65_536]] {
+        let encrypted = SecurityManager::encrypt_with_secret("s3cret", &data).unwrap();
+        assert_eq!(
+            SecurityManager::decrypt_with_secret("s3cret", &encrypted).unwrap(),
+            data
+        );
+    }
+}
+
+#[test]
+fn test_kdf_params_travel_with_ciphertext() {
+    let config: EncryptionConfig = toml::from_str(
+        r#"
+enabled = true
+
+[kdf]
+memory_kib = 8192
+iterations = 3
+"#,
+    )
+    .unwrap();
+    let kdf = config.kdf.unwrap();
+    assert_eq!(kdf.version, 0x13);
+    assert_eq!(kdf.parallelism, 1);
+    kdf.validate().unwrap();
+
+    let cipher = Cipher::Secret {
+        secret: "s3cret".to_string(),
+        kdf,
+    };
+    let encrypted = cipher.encrypt(b"archive").unwrap();
+    // 解密时从密文头读取参数，不依赖当前配置
+    assert_eq!(
+        SecurityManager::decrypt_with_secret("s3cret", &encrypted).unwrap(),
+        b"archive"
+    );
+    assert_ne!(
+        encrypted[..20],
+        SecurityManager::encrypt_with_secret("s3cret", b"archive").unwrap()[..20]
+    );
+
+    let weak = KdfParams {
+        memory_kib: 1,
+        ..KdfParams::default()
+    };
+    assert!(weak.validate().is_err());
+    let unknown_version = KdfParams {
+        version: 7,
+        ..KdfParams::default()
+    };
+    assert!(unknown_version.validate().is_err());
+}
+
+#[test]
+fn test_decrypt_rejects_oversized_kdf_header() {
+    let encrypted = SecurityManager::encrypt_with_secret("s3cret", b"archive").unwrap();
+    // 密文头：BEEPKG | 格式版本 | Argon2 版本 | 内存 | 迭代次数 | 并行度 | ...，整数为大端序
+    for (offset, field) in [(8, "memory"), (12, "iterations"), (16, "parallelism")] {
+        let mut tampered = encrypted.clone();
+        tampered[offset..offset + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+        let error = SecurityManager::decrypt_with_secret("s3cret", &tampered).unwrap_err();
+        assert!(error.to_string().contains(field), "{}", error);
+    }
+
+    for kdf in [
+        KdfParams {
+            iterations: 65,
+            ..KdfParams::default()
+        },
+        KdfParams {
+            parallelism: 65,
+            ..KdfParams::default()
+        },
+    ] {
+        assert!(kdf.validate().is_err());
+    }
+}
@@ -0,0 +1,128 @@
+use super::test_helpers::MockBucket;
+use beepkg::checksum::{Checksum, ChecksumAlgorithm};
+use beepkg::error::BeepkgError;
+use beepkg::events::SilentObserver;
+use beepkg::models::{PackageAccess, RegistryMetadata, Visibility};
+use beepkg::operations::PackageManager;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+const HOUR: Duration = Duration::from_secs(3600);
+
+// demo 所有人可见；internal 是 alice 的私有包；secret 是其他用户的私有包
+async fn registry() -> MockBucket {
+    let mut metadata = RegistryMetadata::default();
+    for (name, owner) in [("internal", "alice"), ("secret", "mallory")] {
+        metadata.access.insert(
+            name.to_string(),
+            PackageAccess {
+                visibility: Visibility::Private,
+                owner: Some(owner.to_string()),
+                grants: Vec::new(),
+            },
+        );
+    }
+    let mut objects = BTreeMap::from([(
+        "registry-metadata.json".to_string(),
+        serde_json::to_vec(&metadata).unwrap(),
+    )]);
+    for name in ["demo", "internal", "secret"] {
+        let archive = format!("{}-1.0.0.zip", name);
+        let checksum = Checksum::compute(ChecksumAlgorithm::Sha256, archive.as_bytes());
+        objects.insert(
+            format!("{}.sha256", archive),
+            checksum.to_string().into_bytes(),
+        );
+        objects.insert(archive.clone(), archive.into_bytes());
+    }
+    MockBucket::with_objects(objects).await
+}
+
+fn manager(bucket: &MockBucket) -> PackageManager {
+    PackageManager::builder()
+        .endpoint(bucket.endpoint())
+        .bucket("packages")
+        .credentials("test-access-key", "test-secret-key")
+        .build()
+        .unwrap()
+        .observer(Arc::new(SilentObserver))
+        .cache(None)
+        .actor("alice")
+}
+
+#[tokio::test]
+async fn test_share_presigns_archive_and_checksum() {
+    let bucket = registry().await;
+    let (archive, checksum) = manager(&bucket)
+        .share_package("demo@1.0.0", HOUR)
+        .await
+        .unwrap();
+    assert_eq!(archive.path(), "/packages/demo-1.0.0.zip");
+    assert_eq!(checksum.path(), "/packages/demo-1.0.0.zip.sha256");
+    for url in [&archive, &checksum] {
+        let query: BTreeMap<_, _> = url.query_pairs().collect();
+        assert_eq!(query["X-Amz-Expires"], "3600");
+        assert!(query.contains_key("X-Amz-Signature"));
+    }
+
+    // 预签名地址无需凭证即可下载
+    let content = reqwest::get(archive).await.unwrap().bytes().await.unwrap();
+    assert_eq!(&content[..], b"demo-1.0.0.zip");
+}
+
+#[tokio::test]
+async fn test_share_expiry_bounds() {
+    let bucket = registry().await;
+    let manager = manager(&bucket);
+    for expires in [Duration::ZERO, Duration::from_secs(7 * 24 * 3600 + 1)] {
+        let error = manager
+            .share_package("demo@1.0.0", expires)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("within 7 days"), "{}", error);
+    }
+    let (archive, _) = manager
+        .share_package("demo@1.0.0", Duration::from_secs(7 * 24 * 3600))
+        .await
+        .unwrap();
+    assert!(archive.as_str().contains("X-Amz-Expires=604800"));
+    // 超出范围时不访问注册表
+    assert_eq!(bucket.count("GET", "registry-metadata.json"), 1);
+}
+
+#[tokio::test]
+async fn test_share_rejects_private_and_unknown_packages() {
+    let bucket = registry().await;
+    let manager = manager(&bucket);
+
+    // 自己的私有包也不能分享，预签名地址会绕过访问控制
+    let error = manager
+        .share_package("internal@1.0.0", HOUR)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, BeepkgError::AccessDenied(_)), "{}", error);
+    assert!(error.to_string().contains("not public"), "{}", error);
+
+    let error = manager
+        .share_package("secret@1.0.0", HOUR)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, BeepkgError::AccessDenied(_)), "{}", error);
+
+    let error = manager.share_package("demo@9.9.9", HOUR).await.unwrap_err();
+    assert!(matches!(error, BeepkgError::NotFound(_)), "{}", error);
+    let error = manager
+        .share_package("missing@1.0.0", HOUR)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, BeepkgError::NotFound(_)), "{}", error);
+
+    // 被拒绝的包不读取校验文件
+    for name in ["internal", "secret"] {
+        assert_eq!(
+            bucket.count("GET", &format!("{}-1.0.0.zip.sha256", name)),
+            0
+        );
+    }
+}
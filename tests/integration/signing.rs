@@ -0,0 +1,22 @@
+use super::test_helpers::*;
+use beepkg::signing;
+use std::fs;
+
+#[test]
+fn test_sign_and_verify() {
+    let env = test_setup!();
+    let (secret, public) = signing::generate_keypair().unwrap();
+    let key_path = env.workspace.join("test.key");
+    fs::write(&key_path, secret).unwrap();
+
+    let key = signing::load_signing_key(&key_path).unwrap();
+    let trusted = vec![signing::parse_public_key(&public).unwrap()];
+    let sidecar = signing::sign(&key, b"sha256:abcdef");
+
+    assert!(signing::verify(&sidecar, b"sha256:abcdef", &trusted).is_ok());
+    assert!(signing::verify(&sidecar, b"sha256:abcdeg", &trusted).is_err());
+
+    let (_, other_public) = signing::generate_keypair().unwrap();
+    let untrusted = vec![signing::parse_public_key(&other_public).unwrap()];
+    assert!(signing::verify(&sidecar, b"sha256:abcdef", &untrusted).is_err());
+}
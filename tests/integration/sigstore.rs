@@ -0,0 +1,61 @@
+use beepkg::sigstore;
+use std::os::unix::fs::PermissionsExt;
+
+// 假的 cosign：记录参数，sign-blob 写出固定的 bundle，verify-blob 只接受 me@corp.com 这一身份
+fn fake_cosign(dir: &std::path::Path) -> std::path::PathBuf {
+    let program = dir.join("cosign");
+    std::fs::write(
+        &program,
+        format!(
+            "#!/bin/sh\necho \"$@\" >> {log}\n\
+             if [ \"$1\" = sign-blob ]; then echo '{{\"bundle\":1}}' > \"$4\"; exit 0; fi\n\
+             if [ \"$4\" = --certificate-identity ] && [ \"$5\" = me@corp.com ]; then exit 0; fi\n\
+             echo 'none of the expected identities matched' >&2\nexit 1\n",
+            log = dir.join("args.log").display()
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+    program
+}
+
+#[tokio::test]
+async fn test_sign_and_verify_with_cosign() {
+    let dir = tempfile::tempdir().unwrap();
+    let program = fake_cosign(dir.path());
+    // 只有本测试使用 cosign
+    unsafe { std::env::set_var("BEEPKG_COSIGN_PROGRAM", &program) };
+
+    let bundle = sigstore::sign(b"sha256:abcd").await.unwrap();
+    assert_eq!(bundle.trim(), r#"{"bundle":1}"#);
+
+    let issuer = "https://accounts.example.com";
+    sigstore::verify(bundle.as_bytes(), b"sha256:abcd", "me@corp.com", issuer)
+        .await
+        .unwrap();
+    // 身份按原样精确匹配，不作为正则表达式传给 cosign
+    let error = sigstore::verify(bundle.as_bytes(), b"sha256:abcd", "me@corp.c.m", issuer)
+        .await
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("none of the expected identities"),
+        "{}",
+        error
+    );
+
+    let log = std::fs::read_to_string(dir.path().join("args.log")).unwrap();
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("sign-blob --yes --bundle "));
+    assert!(
+        lines[1].contains(&format!(
+            "--certificate-identity me@corp.com --certificate-oidc-issuer {}",
+            issuer
+        )),
+        "{}",
+        lines[1]
+    );
+    assert!(!log.contains("regexp"), "{}", log);
+}
@@ -0,0 +1,109 @@
+use beepkg::site::{self, SiteIndex, SiteMetadata, SitePackage, SiteVersion};
+use std::collections::BTreeMap;
+
+fn version(version: &str, name: &str) -> SiteVersion {
+    let file = format!("{}-{}.zip", name.rsplit('/').next().unwrap(), version);
+    SiteVersion {
+        version: version.to_string(),
+        url: format!("http://localhost:9000/registry/{}-{}.zip", name, version),
+        file,
+        size: 1024,
+        created_at: "2026-01-01T00:00:00Z".to_string(),
+        checksum: Some("sha256:abc123".to_string()),
+        locked: false,
+        lock_reason: None,
+        metadata: None,
+    }
+}
+
+fn sample_index() -> SiteIndex {
+    let mut latest = version("1.1.0", "demo");
+    latest.metadata = Some(SiteMetadata {
+        author: "Jane".to_string(),
+        description: "Tools <& helpers>".to_string(),
+        dependencies: BTreeMap::from([("base".to_string(), "^1.0".to_string())]),
+    });
+    let mut old = version("1.0.0", "demo");
+    old.checksum = None;
+    old.locked = true;
+    old.lock_reason = Some("release".to_string());
+
+    SiteIndex {
+        registry: "http://localhost:9000/registry".to_string(),
+        generated_at: "2026-01-02T00:00:00Z".to_string(),
+        packages: vec![
+            SitePackage {
+                name: "@team/app".to_string(),
+                latest: "2.0.0".to_string(),
+                channels: BTreeMap::new(),
+                versions: vec![version("2.0.0", "@team/app")],
+            },
+            SitePackage {
+                name: "demo".to_string(),
+                latest: "1.1.0".to_string(),
+                channels: BTreeMap::from([("stable".to_string(), "1.0.0".to_string())]),
+                versions: vec![latest, old],
+            },
+        ],
+    }
+}
+
+#[test]
+fn test_site_write_layout() {
+    let dir = tempfile::tempdir().unwrap();
+    let index = sample_index();
+    let files = site::write(&index, dir.path()).unwrap();
+    assert_eq!(files, 6);
+
+    for path in [
+        "index.html",
+        "index.json",
+        "packages/demo/index.html",
+        "packages/demo/index.json",
+        "packages/@team/app/index.html",
+        "packages/@team/app/index.json",
+    ] {
+        assert!(dir.path().join(path).is_file(), "{} missing", path);
+    }
+
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join("index.json")).unwrap())
+            .unwrap();
+    assert_eq!(json["packages"][1]["name"], "demo");
+    assert_eq!(
+        json["packages"][1]["versions"][0]["checksum"],
+        "sha256:abc123"
+    );
+    assert_eq!(json["packages"][1]["versions"][1]["locked"], true);
+    assert!(json["packages"][0]["versions"][0].get("locked").is_none());
+}
+
+#[test]
+fn test_site_package_page() {
+    let index = sample_index();
+    let html = site::render_package(&index, &index.packages[1]);
+    // 带校验和的链接与 PyPI simple 索引相同
+    assert!(html.contains("href=\"http://localhost:9000/registry/demo-1.1.0.zip#sha256=abc123\""));
+    assert!(html.contains("href=\"http://localhost:9000/registry/demo-1.0.0.zip\""));
+    assert!(html.contains("Tools &lt;&amp; helpers&gt;"));
+    assert!(html.contains("base ^1.0"));
+    assert!(html.contains("locked: release"));
+    assert!(html.contains("stable: 1.0.0"));
+    assert!(html.contains("<a href=\"../../\">"));
+
+    // 作用域包多一层目录
+    let scoped = site::render_package(&index, &index.packages[0]);
+    assert!(scoped.contains("<a href=\"../../../\">"));
+
+    let root = site::render_index(&index);
+    assert!(root.contains("<a href=\"packages/demo/\">demo</a>"));
+    assert!(root.contains("<a href=\"packages/@team/app/\">@team/app</a>"));
+}
+
+#[test]
+fn test_site_escape() {
+    assert_eq!(
+        site::escape("<a href=\"x\">'&'</a>"),
+        "&lt;a href=&quot;x&quot;&gt;&#39;&amp;&#39;&lt;/a&gt;"
+    );
+}
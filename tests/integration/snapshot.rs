@@ -0,0 +1,99 @@
+use beepkg::bundle::{self, BundleWriter};
+use beepkg::snapshot::{self, SnapshotManifest, SnapshotObject};
+
+fn manifest(include_blobs: bool) -> SnapshotManifest {
+    let keys = [
+        "registry-metadata.json",
+        "demo-1.0.0.zip",
+        "demo-1.0.0.zip.sha256",
+    ];
+    SnapshotManifest {
+        format_version: snapshot::FORMAT_VERSION,
+        created_at: "2024-01-01T00:00:00Z".to_string(),
+        source: "https://minio.example.com/packages/".to_string(),
+        include_blobs,
+        objects: keys
+            .iter()
+            .map(|key| SnapshotObject {
+                key: key.to_string(),
+                size: 4,
+                included: include_blobs || !snapshot::is_content_key(key),
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn test_content_keys() {
+    assert!(snapshot::is_content_key("demo-1.0.0.zip"));
+    assert!(snapshot::is_content_key("demo-1.0.0-backup-1700000000.zip"));
+    assert!(snapshot::is_content_key(
+        "demo-1.0.0.zip.artifacts/linux-x86_64.zip"
+    ));
+    assert!(snapshot::is_content_key("demo-1.1.0.zip.deltas/1.0.0.zst"));
+    assert!(snapshot::is_content_key("blobs/sha256/abcdef"));
+    assert!(!snapshot::is_content_key("registry-metadata.json"));
+    assert!(!snapshot::is_content_key("demo-1.0.0.zip.sha256"));
+    assert!(!snapshot::is_content_key(
+        "audit/2024-01-01T00:00:00-push.json"
+    ));
+}
+
+#[test]
+fn test_snapshot_roundtrip_without_blobs() {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest = manifest(false);
+    let path = dir.path().join("snapshot.tar.zst");
+    let mut writer =
+        BundleWriter::create_with_manifest(&path, snapshot::MANIFEST_PATH, &manifest).unwrap();
+    writer
+        .append_bytes("registry-metadata.json", b"{}")
+        .unwrap();
+    writer
+        .append_bytes("demo-1.0.0.zip.sha256", b"sha256:00")
+        .unwrap();
+    writer.finish().unwrap();
+
+    let out = tempfile::tempdir().unwrap();
+    let unpacked = snapshot::unpack(&path, out.path()).unwrap();
+    assert!(!unpacked.include_blobs);
+    assert_eq!(unpacked.objects.len(), 3);
+    assert!(!unpacked.contains("demo-1.0.0.zip"));
+
+    let objects = out.path().join(bundle::OBJECTS_DIR);
+    assert!(objects.join("registry-metadata.json").exists());
+    assert!(!objects.join("demo-1.0.0.zip").exists());
+}
+
+#[test]
+fn test_snapshot_rejects_excluded_content() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("snapshot.tar.zst");
+    let mut writer =
+        BundleWriter::create_with_manifest(&path, snapshot::MANIFEST_PATH, &manifest(false))
+            .unwrap();
+    writer
+        .append_bytes("demo-1.0.0.zip", b"zip content")
+        .unwrap();
+    writer.finish().unwrap();
+
+    let out = tempfile::tempdir().unwrap();
+    let error = snapshot::unpack(&path, out.path()).unwrap_err();
+    assert!(error.to_string().contains("Unexpected entry"));
+
+    // 包集不能作为快照恢复
+    let bundle_path = dir.path().join("registry.tar.zst");
+    let bundle_manifest = bundle::BundleManifest {
+        format_version: bundle::FORMAT_VERSION,
+        created_at: String::new(),
+        source: String::new(),
+        packages: Vec::new(),
+        access: Default::default(),
+        project: None,
+    };
+    BundleWriter::create(&bundle_path, &bundle_manifest)
+        .unwrap()
+        .finish()
+        .unwrap();
+    assert!(snapshot::unpack(&bundle_path, out.path()).is_err());
+}
@@ -0,0 +1,85 @@
+use super::test_helpers::{MockBucket, write_package};
+use beepkg::events::SilentObserver;
+use beepkg::models::ServerSideEncryption;
+use beepkg::operations::PackageManager;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+// 推送并备份一个包，返回每个 PUT 请求的对象键和服务端加密请求头。
+// 加密请求头必须纳入预签名，否则存储端会拒绝请求
+async fn uploads(sse: ServerSideEncryption) -> Vec<(String, Option<String>, Option<String>)> {
+    let puts = Arc::new(Mutex::new(Vec::new()));
+    let recorded = puts.clone();
+    let bucket = MockBucket::with_handler(BTreeMap::new(), move |request, _| {
+        if request.method == "PUT" {
+            let signed = request.param("X-Amz-SignedHeaders").unwrap_or_default();
+            assert!(
+                signed.contains("x-amz-server-side-encryption"),
+                "{}: {}",
+                request.key,
+                signed
+            );
+            recorded.lock().unwrap().push((
+                request.key.clone(),
+                request.header("x-amz-server-side-encryption"),
+                request.header("x-amz-server-side-encryption-aws-kms-key-id"),
+            ));
+        }
+        None
+    })
+    .await;
+    let manager = PackageManager::builder()
+        .endpoint(bucket.endpoint())
+        .bucket("packages")
+        .credentials("test-access-key", "test-secret-key")
+        .build()
+        .unwrap()
+        .observer(Arc::new(SilentObserver))
+        .cache(None)
+        .server_side_encryption(Some(sse));
+    let dir = tempfile::tempdir().unwrap();
+    write_package(dir.path(), "demo", "1.0.0");
+    manager.push_package(dir.path()).await.unwrap();
+    manager
+        .backup_package("demo", "1.0.0", "release")
+        .await
+        .unwrap();
+
+    let puts = puts.lock().unwrap().clone();
+    // 包文件、校验文件和备份都经过同一个上传路径
+    for key in [
+        "demo-1.0.0.zip",
+        "demo-1.0.0.zip.sha256",
+        "registry-metadata.json",
+    ] {
+        assert!(puts.iter().any(|(put, _, _)| put == key), "{:?}", puts);
+    }
+    assert!(
+        puts.iter()
+            .any(|(key, _, _)| key.starts_with("demo-1.0.0-backup-")),
+        "{:?}",
+        puts
+    );
+    puts
+}
+
+#[tokio::test]
+async fn test_sse_kms_headers_are_sent_on_every_upload() {
+    let puts = uploads(ServerSideEncryption::Kms {
+        key_id: Some("alias/beepkg".to_string()),
+    })
+    .await;
+    for (key, sse, key_id) in puts {
+        assert_eq!(sse.as_deref(), Some("aws:kms"), "{}", key);
+        assert_eq!(key_id.as_deref(), Some("alias/beepkg"), "{}", key);
+    }
+}
+
+#[tokio::test]
+async fn test_sse_s3_headers_are_sent_on_every_upload() {
+    let puts = uploads(ServerSideEncryption::S3).await;
+    for (key, sse, key_id) in puts {
+        assert_eq!(sse.as_deref(), Some("AES256"), "{}", key);
+        assert_eq!(key_id, None, "{}", key);
+    }
+}
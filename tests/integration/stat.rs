@@ -0,0 +1,138 @@
+use super::test_helpers::{LAST_MODIFIED, MockBucket};
+use beepkg::error::BeepkgError;
+use beepkg::operations::PackageConflictStatus;
+use md5::{Digest, Md5};
+use std::collections::BTreeMap;
+
+fn registry_with(extra: &str) -> Vec<u8> {
+    format!(
+        r#"{{"registry_name": "demo", "backup_enabled": false, "locked_packages": [],
+            "backups": [], "last_updated": ""{}}}"#,
+        extra
+    )
+    .into_bytes()
+}
+
+#[tokio::test]
+async fn test_exists_and_stat() {
+    let bucket = MockBucket::with_objects(BTreeMap::from([
+        ("demo-1.0.0.zip".to_string(), vec![0u8; 1234]),
+        ("secret-1.0.0.zip".to_string(), vec![0u8; 10]),
+        ("tiny-1.0.0.zip".to_string(), vec![0u8; 10]),
+        (
+            "registry-metadata.json".to_string(),
+            registry_with(
+                r#", "access": {"secret": {"visibility": "private", "owner": "someone-else"}}"#,
+            ),
+        ),
+    ]))
+    .await;
+    let manager = bucket.manager();
+
+    assert!(manager.exists("demo", "1.0.0").await.unwrap());
+    // 读取注册表元数据检查访问权限，再发送一个 HEAD 请求
+    assert_eq!(bucket.requests().len(), 2);
+    assert_eq!(bucket.count("GET", "registry-metadata.json"), 1);
+    assert_eq!(bucket.count("HEAD", "demo-1.0.0.zip"), 1);
+    assert!(!manager.exists("demo", "1.0.1").await.unwrap());
+    // 无权查看的包视为不存在，不发送 HEAD 请求
+    assert!(!manager.exists("secret", "1.0.0").await.unwrap());
+    assert_eq!(bucket.count("HEAD", "secret-1.0.0.zip"), 0);
+    assert_eq!(bucket.requests().len(), 5);
+
+    let stat = manager.stat("demo", "1.0.0").await.unwrap().unwrap();
+    // 包文件大于指针大小上限，不需要下载
+    assert_eq!(bucket.requests().len(), 7);
+    assert_eq!(bucket.count("GET", "demo-1.0.0.zip"), 0);
+    assert_eq!(stat.key, "demo-1.0.0.zip");
+    assert_eq!(stat.size, 1234);
+    let md5 = format!("{:x}", Md5::digest(vec![0u8; 1234]));
+    assert_eq!(stat.etag.as_deref(), Some(md5.as_str()));
+    assert_eq!(stat.last_modified.as_deref(), Some(LAST_MODIFIED));
+
+    // 可能是指针的小包文件还要下载一次
+    let stat = manager.stat("tiny", "1.0.0").await.unwrap().unwrap();
+    assert_eq!(stat.size, 10);
+    assert_eq!(bucket.requests().len(), 10);
+    assert_eq!(bucket.count("GET", "tiny-1.0.0.zip"), 1);
+
+    // 不列出 bucket
+    let requests = bucket.requests();
+    assert!(!requests.iter().any(|r| r.starts_with("GET /packages/?")));
+}
+
+#[tokio::test]
+async fn test_conflict_check_without_full_listing() {
+    let bucket = MockBucket::with_objects(BTreeMap::from([
+        ("demo-1.0.0.zip".to_string(), b"zip".to_vec()),
+        ("demo-2.0.0-rc.1.zip".to_string(), b"zip".to_vec()),
+        ("demo-utils-3.0.0.zip".to_string(), b"zip".to_vec()),
+        ("other-9.0.0.zip".to_string(), b"zip".to_vec()),
+    ]))
+    .await;
+    let manager = bucket.manager();
+
+    let status = manager
+        .check_package_conflict("demo", "1.0.0")
+        .await
+        .unwrap();
+    assert!(matches!(status, PackageConflictStatus::VersionExists));
+    // 已有的预发布版本和名称以 demo- 开头的其他包不算更高版本
+    let status = manager
+        .check_package_conflict("demo", "1.5.0")
+        .await
+        .unwrap();
+    assert!(matches!(status, PackageConflictStatus::NoConflict));
+    match manager
+        .check_package_conflict("demo", "0.9.0")
+        .await
+        .unwrap()
+    {
+        PackageConflictStatus::HigherVersionExists(version) => assert_eq!(version, "1.0.0"),
+        other => panic!("{:?}", other),
+    }
+    match manager
+        .check_package_conflict("demo", "2.0.0-beta.1")
+        .await
+        .unwrap()
+    {
+        PackageConflictStatus::HigherVersionExists(version) => assert_eq!(version, "2.0.0-rc.1"),
+        other => panic!("{:?}", other),
+    }
+
+    // 只列出同名包的对象
+    let requests = bucket.requests();
+    let listings: Vec<&String> = requests
+        .iter()
+        .filter(|r| r.starts_with("GET /packages/?"))
+        .collect();
+    assert!(!listings.is_empty());
+    assert!(
+        listings.iter().all(|r| r.contains("prefix=demo-")),
+        "{:?}",
+        listings
+    );
+}
+
+#[tokio::test]
+async fn test_conflict_on_locked_version() {
+    let bucket = MockBucket::with_objects(BTreeMap::from([
+        ("demo-1.0.0.zip".to_string(), b"zip".to_vec()),
+        (
+            "registry-metadata.json".to_string(),
+            br#"{"registry_name": "demo", "backup_enabled": false, "backups": [],
+                 "last_updated": "", "locked_packages": [{"name": "demo",
+                 "version": "1.0.0", "lock_reason": "audited", "locked_at": "",
+                 "locked_by": "ops"}]}"#
+                .to_vec(),
+        ),
+    ]))
+    .await;
+    let err = bucket
+        .manager()
+        .check_package_conflict("demo", "1.0.0")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, BeepkgError::Locked(_)), "{}", err);
+    assert!(err.to_string().contains("audited"));
+}
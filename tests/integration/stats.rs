@@ -0,0 +1,112 @@
+use super::test_helpers::MockBucket;
+use beepkg::checksum::{Checksum, ChecksumAlgorithm};
+use beepkg::models::DownloadRecord;
+use beepkg::stats::{self, Period};
+use std::collections::BTreeMap;
+use std::io::Write;
+
+fn record(package: &str, version: &str, timestamp: &str, client: &str) -> DownloadRecord {
+    DownloadRecord {
+        package: package.to_string(),
+        version: version.to_string(),
+        timestamp: timestamp.to_string(),
+        client: client.to_string(),
+        source_ip: None,
+    }
+}
+
+#[test]
+fn test_period_buckets() {
+    let timestamp = "2024-01-17T10:00:00+00:00";
+    assert_eq!(Period::Day.bucket(timestamp), "2024-01-17");
+    assert_eq!(Period::Week.bucket(timestamp), "2024-W03");
+    assert_eq!(Period::Month.bucket(timestamp), "2024-01");
+    assert_eq!(Period::Day.bucket("yesterday"), "unknown");
+    assert_eq!("MONTH".parse::<Period>().unwrap(), Period::Month);
+    assert!("year".parse::<Period>().is_err());
+}
+
+#[test]
+fn test_summarize_downloads() {
+    let records = vec![
+        record("demo", "1.0.0", "2024-01-01T10:00:00Z", "alice"),
+        record("demo", "1.1.0", "2024-01-02T10:00:00Z", "alice"),
+        record("demo", "1.1.0", "2024-01-02T11:00:00Z", "proxy"),
+        record("utils", "2.0.0", "2024-01-01T09:00:00Z", "bob"),
+    ];
+    let report = stats::summarize(&records, Period::Day);
+    assert_eq!(report.len(), 2);
+    assert_eq!(report[0].name, "demo");
+    assert_eq!(report[0].total, 3);
+    assert_eq!(report[0].clients, 2);
+    assert_eq!(report[0].versions["1.1.0"], 2);
+    assert_eq!(
+        report[0].periods.iter().collect::<Vec<_>>(),
+        [
+            (&"2024-01-01".to_string(), &1),
+            (&"2024-01-02".to_string(), &2)
+        ]
+    );
+    assert_eq!(report[1].name, "utils");
+}
+
+fn demo_archive() -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    zip.start_file("pack.toml", Default::default()).unwrap();
+    zip.write_all(
+        b"name = \"demo\"\nversion = \"1.0.0\"\nauthor = \"\"\ndescription = \"\"\n\
+          includes = []\nexcludes = []\n\n[dependencies]\n",
+    )
+    .unwrap();
+    zip.finish().unwrap().into_inner()
+}
+
+// 开启了下载统计的 bucket，有 demo-1.0.0.zip
+async fn upstream() -> MockBucket {
+    let archive = demo_archive();
+    let checksum = Checksum::compute(ChecksumAlgorithm::Sha256, &archive).to_string();
+    MockBucket::with_objects(BTreeMap::from([
+        (
+            "registry-metadata.json".to_string(),
+            br#"{"registry_name": "demo", "backup_enabled": false, "locked_packages": [],
+                 "backups": [], "last_updated": "", "download_stats": true}"#
+                .to_vec(),
+        ),
+        ("demo-1.0.0.zip".to_string(), archive),
+        ("demo-1.0.0.zip.sha256".to_string(), checksum.into_bytes()),
+    ]))
+    .await
+}
+
+#[tokio::test]
+async fn test_pull_records_download() {
+    let bucket = upstream().await;
+    let manager = bucket.manager();
+
+    let output = tempfile::tempdir().unwrap();
+    manager
+        .pull_package("demo@1.0.0", output.path())
+        .await
+        .unwrap();
+    assert_eq!(
+        bucket
+            .objects
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|key| key.starts_with("stats/downloads/"))
+            .count(),
+        1
+    );
+
+    let records = manager.list_downloads(None, Some("demo")).await.unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].version, "1.0.0");
+    assert!(
+        manager
+            .list_downloads(None, Some("other"))
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
@@ -0,0 +1,127 @@
+use super::test_helpers::{MockBucket, write_package};
+use beepkg::artifacts;
+use beepkg::checksum::{Checksum, ChecksumAlgorithm};
+use beepkg::sbom::SbomFormat;
+use std::collections::BTreeMap;
+
+// 解析 SHA256SUMS，返回 (文件名, 十六进制摘要)
+fn parse_sums(content: &[u8]) -> Vec<(String, String)> {
+    String::from_utf8(content.to_vec())
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let (hex, name) = line.split_once("  ").unwrap();
+            (name.to_string(), hex.to_string())
+        })
+        .collect()
+}
+
+// 检查清单覆盖 dir 下以 archive 开头的全部对象（清单自身除外），且摘要与内容一致
+fn check_sums(objects: &BTreeMap<String, Vec<u8>>, dir: &str, archive: &str) -> Vec<String> {
+    let sums_key = format!("{}{}.SHA256SUMS", dir, archive);
+    let sums = parse_sums(&objects[&sums_key]);
+    let names: Vec<String> = sums.iter().map(|(name, _)| name.clone()).collect();
+    let expected: Vec<String> = objects
+        .keys()
+        .filter(|key| key.starts_with(&format!("{}{}", dir, archive)) && **key != sums_key)
+        .map(|key| key[dir.len()..].to_string())
+        .collect();
+    assert_eq!(names, expected);
+    for (name, hex) in &sums {
+        let content = &objects[&format!("{}{}", dir, name)];
+        assert_eq!(
+            *hex,
+            Checksum::compute(ChecksumAlgorithm::Sha256, content).hex,
+            "{}",
+            name
+        );
+    }
+    names
+}
+
+#[tokio::test]
+async fn test_push_writes_sums() {
+    let bucket = MockBucket::start().await;
+    let manager = bucket.manager();
+    let dir = tempfile::tempdir().unwrap();
+    write_package(dir.path(), "demo", "1.0.0");
+    manager.push_package(dir.path()).await.unwrap();
+
+    let names = check_sums(&bucket.objects.lock().unwrap(), "", "demo-1.0.0.zip");
+    assert_eq!(names[0], "demo-1.0.0.zip");
+    assert!(names.contains(&"demo-1.0.0.zip.sha256".to_string()));
+
+    // 发布后附加的 SBOM 和标签也更新清单
+    manager
+        .upload_sbom(
+            "demo",
+            "1.0.0",
+            SbomFormat::CycloneDx,
+            serde_json::json!({"bomFormat": "CycloneDX"}),
+        )
+        .await
+        .unwrap();
+    let before = bucket.object("demo-1.0.0.zip.SHA256SUMS").unwrap();
+    manager
+        .annotate(
+            "demo@1.0.0",
+            &BTreeMap::from([("tier".to_string(), "gold".to_string())]),
+            &[],
+        )
+        .await
+        .unwrap();
+    let objects = bucket.objects.lock().unwrap();
+    let names = check_sums(&objects, "", "demo-1.0.0.zip");
+    assert!(names.iter().any(|name| name.contains("cdx")), "{:?}", names);
+    assert_ne!(objects["demo-1.0.0.zip.SHA256SUMS"], before);
+}
+
+#[tokio::test]
+async fn test_scoped_sums() {
+    let bucket = MockBucket::start().await;
+    let manager = bucket.manager();
+    let dir = tempfile::tempdir().unwrap();
+    write_package(dir.path(), "@acme/tool", "2.0.0");
+    manager.push_package(dir.path()).await.unwrap();
+
+    // 文件名相对于作用域目录，下载到同一目录后可以直接 sha256sum -c
+    let names = check_sums(&bucket.objects.lock().unwrap(), "@acme/", "tool-2.0.0.zip");
+    assert_eq!(names[0], "tool-2.0.0.zip");
+}
+
+#[tokio::test]
+async fn test_sums_use_sidecars() {
+    let bucket = MockBucket::start().await;
+    let manager = bucket.manager();
+    let dir = tempfile::tempdir().unwrap();
+    write_package(dir.path(), "demo", "1.0.0");
+    let manifest = dir.path().join("pack.toml");
+    let content = std::fs::read_to_string(&manifest).unwrap();
+    std::fs::write(
+        &manifest,
+        format!("{}\n[artifacts]\ndocs = \"dist/docs.txt\"\n", content),
+    )
+    .unwrap();
+    std::fs::create_dir(dir.path().join("dist")).unwrap();
+    std::fs::write(dir.path().join("dist/docs.txt"), "docs").unwrap();
+    manager.push_package(dir.path()).await.unwrap();
+
+    // 产物有 .sha256 校验文件，生成清单时不再下载产物本身
+    let artifact = artifacts::artifact_key("demo-1.0.0.zip", "docs.txt");
+    assert!(bucket.object(&format!("{}.sha256", artifact)).is_some());
+    manager
+        .annotate(
+            "demo@1.0.0",
+            &BTreeMap::from([("tier".to_string(), "gold".to_string())]),
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(bucket.served(&artifact), 0);
+    let names = check_sums(&bucket.objects.lock().unwrap(), "", "demo-1.0.0.zip");
+    assert!(
+        names.iter().any(|name| name.ends_with("docs.txt")),
+        "{:?}",
+        names
+    );
+}
@@ -1,7 +1,16 @@
-use tempfile::TempDir;
-use std::path::PathBuf;
+use beepkg::checksum::{Checksum, ChecksumAlgorithm};
+use beepkg::events::SilentObserver;
+use beepkg::operations::PackageManager;
+use md5::{Digest, Md5};
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
 
 pub struct TestEnv {
     pub temp_dir: TempDir,
@@ -19,11 +28,13 @@ impl TestEnv {
 
         let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
         let workspace = temp_dir.path().to_path_buf();
-        
+
         // Create required subdirectories
-        fs::create_dir_all(workspace.join("local-storage")).expect("Failed to create local-storage");
-        fs::create_dir_all(workspace.join("remote-storage")).expect("Failed to create remote-storage");
-        
+        fs::create_dir_all(workspace.join("local-storage"))
+            .expect("Failed to create local-storage");
+        fs::create_dir_all(workspace.join("remote-storage"))
+            .expect("Failed to create remote-storage");
+
         Self {
             temp_dir,
             workspace,
@@ -35,13 +46,406 @@ impl TestEnv {
     }
 }
 
+impl Default for TestEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[macro_export(local_inner_macros)]
 macro_rules! test_setup {
-    () => {
-        {
-            let test_env = TestEnv::new();
-            std::env::set_current_dir(&test_env.workspace).unwrap();
-            test_env
+    () => {{
+        let test_env = TestEnv::new();
+        std::env::set_current_dir(&test_env.workspace).unwrap();
+        test_env
+    }};
+}
+
+/// pack.toml 的内容，tail 追加在 [dependencies] 之后：依赖项或之后的表（例如 [release]）
+pub fn pack_toml(name: &str, version: &str, tail: &str) -> String {
+    format!(
+        "name = \"{}\"\nversion = \"{}\"\nauthor = \"\"\ndescription = \"\"\n\
+         includes = []\nexcludes = []\n\n[dependencies]\n{}",
+        name, version, tail
+    )
+}
+
+/// 在 dir 中写入包 name@version：pack.toml 和内容为版本号的 data.txt，
+/// 同一目录可以依次写入多个版本再推送
+pub fn write_package(dir: &Path, name: &str, version: &str) {
+    fs::write(dir.join("pack.toml"), pack_toml(name, version, "")).unwrap();
+    fs::write(dir.join("data.txt"), version).unwrap();
+}
+
+/// 测试 bucket 收到的请求，key 为解码后的对象键，列表请求的 key 为空
+pub struct MockRequest {
+    pub method: String,
+    pub key: String,
+    pub query: String,
+    pub head: String,
+    pub body: Vec<u8>,
+}
+
+impl MockRequest {
+    /// 请求头的值，名称不区分大小写
+    pub fn header(&self, name: &str) -> Option<String> {
+        self.head.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case(name)
+                .then(|| value.trim().to_string())
+        })
+    }
+
+    /// 查询参数的值，已解码
+    pub fn param(&self, name: &str) -> Option<String> {
+        self.query.split('&').find_map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (key == name).then(|| decode(value))
+        })
+    }
+}
+
+pub struct MockResponse {
+    pub status: &'static str,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl MockResponse {
+    pub fn new(status: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.headers.push((name.to_string(), value.into()));
+        self
+    }
+}
+
+/// 访问 endpoint 上名为 packages 的 bucket 的管理器，不输出事件，不使用本地缓存。
+/// endpoint 不可达时（例如 http://127.0.0.1:1）用于测试上游不可用的情况
+pub fn manager_for(endpoint: &str) -> PackageManager {
+    PackageManager::builder()
+        .endpoint(endpoint)
+        .bucket("packages")
+        .build()
+        .unwrap()
+        .observer(Arc::new(SilentObserver))
+        .cache(None)
+}
+
+/// 测试 bucket 中所有对象的修改时间
+pub const LAST_MODIFIED: &str = "Wed, 01 May 2024 12:00:00 GMT";
+
+type Handler =
+    dyn Fn(&MockRequest, &mut BTreeMap<String, Vec<u8>>) -> Option<MockResponse> + Send + Sync;
+
+/// 内存中的 S3 bucket，bucket 名为 packages，使用路径风格的地址。
+/// 写入的对象保存在 objects 中，带 x-amz-copy-source 的 PUT 复制已有对象，
+/// 列表请求返回带前缀且排在 start-after 之后的对象，
+/// requests 记录每个请求的方法和地址，served 记录每个对象返回的字节数。
+/// 列出和读取对象时返回内容 MD5 的 ETag，读取时还返回固定的修改时间，
+/// 支持 Range 请求，每个连接只处理一个请求
+pub struct MockBucket {
+    pub addr: SocketAddr,
+    pub objects: Arc<Mutex<BTreeMap<String, Vec<u8>>>>,
+    pub requests: Arc<Mutex<Vec<String>>>,
+    pub served: Arc<Mutex<BTreeMap<String, usize>>>,
+}
+
+impl MockBucket {
+    pub async fn start() -> Self {
+        Self::with_objects(BTreeMap::new()).await
+    }
+
+    pub async fn with_objects(objects: BTreeMap<String, Vec<u8>>) -> Self {
+        Self::with_handler(objects, |_, _| None).await
+    }
+
+    /// 只有一个包文件 <name>-<version>.zip 及其 sha256 校验文件的 bucket
+    pub async fn with_package(name: &str, version: &str, content: &[u8]) -> Self {
+        let archive = format!("{}-{}.zip", name, version);
+        let checksum = Checksum::compute(ChecksumAlgorithm::Sha256, content);
+        Self::with_objects(BTreeMap::from([
+            (
+                format!("{}.sha256", archive),
+                checksum.to_string().into_bytes(),
+            ),
+            (archive, content.to_vec()),
+        ]))
+        .await
+    }
+
+    /// handler 先处理每个请求，返回 None 时按普通 bucket 处理
+    pub async fn with_handler(
+        objects: BTreeMap<String, Vec<u8>>,
+        handler: impl Fn(&MockRequest, &mut BTreeMap<String, Vec<u8>>) -> Option<MockResponse>
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let objects = Arc::new(Mutex::new(objects));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let served = Arc::new(Mutex::new(BTreeMap::new()));
+        let handler: Arc<Handler> = Arc::new(handler);
+        let (shared, recorded, counted) = (objects.clone(), requests.clone(), served.clone());
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let (objects, requests, served, handler) = (
+                    shared.clone(),
+                    recorded.clone(),
+                    counted.clone(),
+                    handler.clone(),
+                );
+                tokio::spawn(async move {
+                    let Some((target, request)) = read_request(&mut stream).await else {
+                        return;
+                    };
+                    requests
+                        .lock()
+                        .unwrap()
+                        .push(format!("{} {}", request.method, target));
+                    let response = {
+                        let mut objects = objects.lock().unwrap();
+                        handler(&request, &mut objects)
+                            .unwrap_or_else(|| respond(&request, &mut objects))
+                    };
+                    if request.method == "GET"
+                        && !request.key.is_empty()
+                        && response.status.starts_with('2')
+                    {
+                        *served.lock().unwrap().entry(request.key).or_default() +=
+                            response.body.len();
+                    }
+                    let mut head = format!("HTTP/1.1 {}\r\n", response.status);
+                    for (name, value) in &response.headers {
+                        head.push_str(&format!("{}: {}\r\n", name, value));
+                    }
+                    head.push_str(&format!(
+                        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+                        response.body.len()
+                    ));
+                    stream.write_all(head.as_bytes()).await.unwrap();
+                    if request.method != "HEAD" {
+                        stream.write_all(&response.body).await.unwrap();
+                    }
+                });
+            }
+        });
+        Self {
+            addr,
+            objects,
+            requests,
+            served,
+        }
+    }
+
+    pub fn endpoint(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn manager(&self) -> PackageManager {
+        manager_for(&self.endpoint())
+    }
+
+    /// 受保护频道的两个用户 alice 和 bob，各自持有批准私钥，两把公钥都受信任
+    #[cfg(feature = "encryption")]
+    pub fn approvers(&self) -> (PackageManager, PackageManager) {
+        let dir = tempfile::tempdir().unwrap();
+        let mut keys = Vec::new();
+        let mut trusted = Vec::new();
+        for user in ["alice", "bob"] {
+            let (secret, public) = beepkg::signing::generate_keypair().unwrap();
+            let path = dir.path().join(user);
+            fs::write(&path, secret).unwrap();
+            keys.push(beepkg::signing::load_signing_key(&path).unwrap());
+            trusted.push(beepkg::signing::parse_public_key(&public).unwrap());
+        }
+        let mut managers = ["alice", "bob"].into_iter().zip(keys).map(|(user, key)| {
+            self.manager()
+                .actor(user)
+                .trusted_keys(trusted.clone())
+                .approval_key(Some(key))
+        });
+        (managers.next().unwrap(), managers.next().unwrap())
+    }
+
+    pub fn object(&self, key: &str) -> Option<Vec<u8>> {
+        self.objects.lock().unwrap().get(key).cloned()
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// 某个对象返回的字节数
+    pub fn served(&self, key: &str) -> usize {
+        self.served.lock().unwrap().get(key).copied().unwrap_or(0)
+    }
+
+    /// 对某个对象发出的指定方法的请求次数，忽略查询参数
+    pub fn count(&self, method: &str, key: &str) -> usize {
+        let path = format!("{} /packages/{}", method, key);
+        self.requests()
+            .iter()
+            .filter(|request| request.split('?').next() == Some(path.as_str()))
+            .count()
+    }
+}
+
+// 读取一个请求，返回请求地址和解析后的请求
+async fn read_request(stream: &mut TcpStream) -> Option<(String, MockRequest)> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 8192];
+    let header_end = loop {
+        if let Some(pos) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        let n = stream.read(&mut buf).await.ok()?;
+        if n == 0 {
+            return None;
         }
+        request.extend_from_slice(&buf[..n]);
     };
+    let head = String::from_utf8_lossy(&request[..header_end]).to_string();
+    let length: usize = head
+        .lines()
+        .find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case("content-length")
+                .then(|| value.trim().parse().ok())?
+        })
+        .unwrap_or(0);
+    while request.len() < header_end + length {
+        let n = stream.read(&mut buf).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+
+    let mut parts = head.split(' ');
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default().to_string();
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+    let key = decode(path.strip_prefix("/packages/").unwrap_or_default());
+    let query = query.to_string();
+    let body = request[header_end..].to_vec();
+    Some((
+        target,
+        MockRequest {
+            method,
+            key,
+            query,
+            head,
+            body,
+        },
+    ))
+}
+
+// 普通 bucket 的行为：读写对象和按前缀、start-after 列出对象
+fn respond(request: &MockRequest, objects: &mut BTreeMap<String, Vec<u8>>) -> MockResponse {
+    match request.method.as_str() {
+        "PUT" => match request.header("x-amz-copy-source") {
+            Some(source) => {
+                let source = decode(source.strip_prefix("/packages/").unwrap_or_default());
+                match objects.get(&source).cloned() {
+                    Some(value) => {
+                        objects.insert(request.key.clone(), value);
+                        MockResponse::new("200 OK", "<CopyObjectResult></CopyObjectResult>")
+                    }
+                    None => {
+                        MockResponse::new("404 Not Found", "<Error><Code>NoSuchKey</Code></Error>")
+                    }
+                }
+            }
+            None => {
+                objects.insert(request.key.clone(), request.body.clone());
+                MockResponse::new("200 OK", Vec::new())
+            }
+        },
+        "DELETE" => {
+            objects.remove(&request.key);
+            MockResponse::new("204 No Content", Vec::new())
+        }
+        "GET" if request.key.is_empty() => {
+            let prefix = request.param("prefix").unwrap_or_default();
+            let start_after = request.param("start-after").unwrap_or_default();
+            let contents: String = objects
+                .iter()
+                .filter(|(key, _)| key.starts_with(&prefix) && **key > start_after)
+                .map(|(key, value)| {
+                    format!(
+                        "<Contents><Key>{}</Key><Size>{}</Size><ETag>\"{:x}\"</ETag></Contents>",
+                        key,
+                        value.len(),
+                        Md5::digest(value)
+                    )
+                })
+                .collect();
+            let xml = format!(
+                "<ListBucketResult>{}<IsTruncated>false</IsTruncated></ListBucketResult>",
+                contents
+            );
+            MockResponse::new("200 OK", xml)
+        }
+        "GET" | "HEAD" => match objects.get(&request.key) {
+            Some(value) => match range(request, value.len()) {
+                Some((start, end)) => MockResponse::new("206 Partial Content", &value[start..=end])
+                    .header(
+                        "Content-Range",
+                        format!("bytes {}-{}/{}", start, end, value.len()),
+                    ),
+                None => MockResponse::new("200 OK", value.clone())
+                    .header("ETag", format!("\"{:x}\"", Md5::digest(value)))
+                    .header("Last-Modified", LAST_MODIFIED),
+            },
+            None => MockResponse::new("404 Not Found", Vec::new()),
+        },
+        _ => MockResponse::new("404 Not Found", Vec::new()),
+    }
+}
+
+// 解析 Range 请求头，返回包含两端的字节范围
+fn range(request: &MockRequest, total: usize) -> Option<(usize, usize)> {
+    let range = request.header("range")?;
+    let (start, end) = range.strip_prefix("bytes=")?.split_once('-')?;
+    Some(match start {
+        "" => (total.saturating_sub(end.parse().ok()?), total - 1),
+        _ => (
+            start.parse().ok()?,
+            end.parse::<usize>().ok()?.min(total - 1),
+        ),
+    })
+}
+
+// 解码 URL 中的 %XX
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
 }
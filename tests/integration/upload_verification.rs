@@ -0,0 +1,107 @@
+use super::test_helpers::{MockBucket, MockResponse, write_package};
+use beepkg::error::BeepkgError;
+use beepkg::operations::UploadVerification;
+use std::collections::BTreeMap;
+
+// 空的 bucket，写入的对象经 tamper 处理后保存
+async fn upstream(tamper: fn(&str, Vec<u8>) -> Vec<u8>) -> MockBucket {
+    MockBucket::with_handler(BTreeMap::new(), move |request, objects| {
+        (request.method == "PUT").then(|| {
+            let body = tamper(&request.key, request.body.clone());
+            objects.insert(request.key.clone(), body);
+            MockResponse::new("200 OK", Vec::new())
+        })
+    })
+    .await
+}
+
+fn intact(_: &str, body: Vec<u8>) -> Vec<u8> {
+    body
+}
+
+// 网关截断了包文件
+fn truncate(key: &str, mut body: Vec<u8>) -> Vec<u8> {
+    if key.ends_with(".zip") {
+        body.truncate(body.len() / 2);
+    }
+    body
+}
+
+// 包文件大小不变，但其中一个字节被改写
+fn corrupt(key: &str, mut body: Vec<u8>) -> Vec<u8> {
+    if key.ends_with(".zip") {
+        body[10] ^= 0xff;
+    }
+    body
+}
+
+#[tokio::test]
+async fn test_verified_push() {
+    let dir = tempfile::tempdir().unwrap();
+    write_package(dir.path(), "demo", "1.0.0");
+    for verification in [UploadVerification::Head, UploadVerification::Full] {
+        let bucket = upstream(intact).await;
+        bucket
+            .manager()
+            .upload_verification(verification)
+            .push_package(dir.path())
+            .await
+            .unwrap();
+        assert!(bucket.object("demo-1.0.0.zip").is_some());
+    }
+}
+
+#[tokio::test]
+async fn test_truncated_upload_detected() {
+    let dir = tempfile::tempdir().unwrap();
+    write_package(dir.path(), "demo", "1.0.0");
+    let bucket = upstream(truncate).await;
+    let err = bucket
+        .manager()
+        .upload_verification(UploadVerification::Head)
+        .push_package(dir.path())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, BeepkgError::ChecksumMismatch(_)), "{}", err);
+    assert!(err.to_string().contains("bytes"), "{}", err);
+    // 损坏的对象被删除，校验文件没有上传
+    let objects = bucket.objects.lock().unwrap();
+    assert!(!objects.keys().any(|key| key.starts_with("demo-1.0.0.zip")));
+}
+
+#[tokio::test]
+async fn test_corrupted_upload_detected() {
+    let dir = tempfile::tempdir().unwrap();
+    write_package(dir.path(), "demo", "1.0.0");
+    for verification in [UploadVerification::Head, UploadVerification::Full] {
+        let bucket = upstream(corrupt).await;
+        let err = bucket
+            .manager()
+            .upload_verification(verification)
+            .push_package(dir.path())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, BeepkgError::ChecksumMismatch(_)), "{}", err);
+        assert!(bucket.object("demo-1.0.0.zip").is_none());
+    }
+
+    // 不检查时损坏的内容会留在注册表中
+    let bucket = upstream(corrupt).await;
+    bucket
+        .manager()
+        .upload_verification(UploadVerification::None)
+        .push_package(dir.path())
+        .await
+        .unwrap();
+    assert!(bucket.object("demo-1.0.0.zip").is_some());
+}
+
+#[test]
+fn test_parse_upload_verification() {
+    assert_eq!(
+        "FULL".parse::<UploadVerification>().unwrap(),
+        UploadVerification::Full
+    );
+    assert_eq!(UploadVerification::default(), UploadVerification::Head);
+    assert!("md5".parse::<UploadVerification>().is_err());
+}
@@ -0,0 +1,139 @@
+use super::test_helpers::MockBucket;
+use beepkg::checksum::{Checksum, ChecksumAlgorithm};
+use beepkg::error::BeepkgError;
+use beepkg::models::{VENDOR_MANIFEST, VendorManifest, VendoredPackage};
+use beepkg::operations::PackageManager;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+fn utils_archive() -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    zip.start_file("pack.toml", Default::default()).unwrap();
+    zip.write_all(
+        b"name = \"utils\"\nversion = \"1.2.0\"\nauthor = \"\"\ndescription = \"\"\n\
+          includes = []\nexcludes = []\n\n[dependencies]\n",
+    )
+    .unwrap();
+    zip.start_file("lib.txt", Default::default()).unwrap();
+    zip.write_all(b"utils 1.2.0").unwrap();
+    zip.finish().unwrap().into_inner()
+}
+
+// 上游 bucket 只有 utils-1.2.0.zip 及其 sha256 校验文件
+async fn upstream(archive: Vec<u8>) -> MockBucket {
+    let checksum = Checksum::compute(ChecksumAlgorithm::Sha256, &archive);
+    MockBucket::with_objects(BTreeMap::from([
+        ("utils-1.2.0.zip".to_string(), archive),
+        (
+            "utils-1.2.0.zip.sha256".to_string(),
+            checksum.to_string().into_bytes(),
+        ),
+    ]))
+    .await
+}
+
+#[tokio::test]
+async fn test_vendor_refuses_foreign_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("pack.toml"),
+        "name = \"app\"\nversion = \"1.0.0\"\nauthor = \"\"\ndescription = \"\"\n\
+         includes = []\nexcludes = []\n\n[dependencies]\nutils = \"^1.0\"\n",
+    )
+    .unwrap();
+    let vendor = dir.path().join("vendor");
+    std::fs::create_dir(&vendor).unwrap();
+    std::fs::write(vendor.join("notes.txt"), "keep me").unwrap();
+
+    // 在访问注册表之前就拒绝覆盖不是由 vendor 生成的目录
+    let manager = PackageManager::builder()
+        .endpoint("http://127.0.0.1:9")
+        .bucket("packages")
+        .build()
+        .unwrap();
+    let err = manager
+        .vendor_dependencies(dir.path(), &vendor, &[], true)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains(VENDOR_MANIFEST), "{}", err);
+    assert!(vendor.join("notes.txt").exists());
+}
+
+#[test]
+fn test_vendor_manifest_format() {
+    let manifest: VendorManifest = serde_json::from_str(
+        r#"{
+            "generated_at": "2024-01-01T00:00:00Z",
+            "package": "app@1.0.0",
+            "packages": [
+                {"name": "@acme/utils", "version": "1.2.0", "checksum": "sha256:00", "path": "@acme/utils"}
+            ]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(manifest.packages[0].path, "@acme/utils");
+}
+
+#[tokio::test]
+async fn test_pull_by_digest() {
+    let archive = utils_archive();
+    let digest = Checksum::compute(ChecksumAlgorithm::Sha256, &archive);
+    let upstream = upstream(archive).await;
+    let manager = upstream.manager();
+
+    let output = tempfile::tempdir().unwrap();
+    manager
+        .pull_package(&format!("utils@{}", digest), output.path())
+        .await
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(output.path().join("lib.txt")).unwrap(),
+        "utils 1.2.0"
+    );
+
+    // 没有版本的内容是这个摘要，例如被强制推送替换
+    let replaced = Checksum::compute(ChecksumAlgorithm::Sha256, b"old content");
+    let err = manager
+        .pull_package(&format!("utils@{}", replaced), output.path())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, BeepkgError::NotFound(_)), "{}", err);
+}
+
+#[tokio::test]
+async fn test_vendor_locked_pulls_recorded_digests() {
+    let archive = utils_archive();
+    let digest = Checksum::compute(ChecksumAlgorithm::Sha256, &archive);
+    let upstream = upstream(archive).await;
+    let manager = upstream.manager();
+
+    let vendor = tempfile::tempdir().unwrap();
+    let mut package = VendoredPackage {
+        name: "utils".to_string(),
+        version: "1.2.0".to_string(),
+        checksum: digest.to_string(),
+        path: "utils".to_string(),
+    };
+    assert_eq!(package.pinned_spec(), format!("utils@{}", digest));
+    let write_manifest = |package: &VendoredPackage| {
+        let manifest = VendorManifest {
+            generated_at: "2024-01-01T00:00:00Z".to_string(),
+            package: "app@1.0.0".to_string(),
+            packages: vec![package.clone()],
+        };
+        std::fs::write(
+            vendor.path().join(VENDOR_MANIFEST),
+            serde_json::to_string(&manifest).unwrap(),
+        )
+        .unwrap();
+    };
+    write_manifest(&package);
+
+    let manifest = manager.vendor_locked(vendor.path()).await.unwrap();
+    assert_eq!(manifest.packages.len(), 1);
+    assert!(vendor.path().join("utils/lib.txt").exists());
+
+    package.checksum = Checksum::compute(ChecksumAlgorithm::Sha256, b"old content").to_string();
+    write_manifest(&package);
+    assert!(manager.vendor_locked(vendor.path()).await.is_err());
+}
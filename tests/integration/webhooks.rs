@@ -0,0 +1,143 @@
+use beepkg::models::{AuditAction, AuditRecord, WebhookConfig};
+use beepkg::webhooks::{deliver, sign_payload};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+fn record() -> AuditRecord {
+    AuditRecord {
+        actor: "alice".to_string(),
+        action: AuditAction::Push,
+        package: "demo@1.0.0".to_string(),
+        timestamp: "2024-01-01T00:00:00+00:00".to_string(),
+        client_version: "0.1.0".to_string(),
+        source_ip: None,
+        details: None,
+    }
+}
+
+// 依次以 statuses 中的状态码响应的 webhook 接收端，之后的请求返回最后一个状态码。
+// 返回地址和收到的请求（请求头和请求体）
+async fn receiver(statuses: &'static [&'static str]) -> (SocketAddr, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let recorded = received.clone();
+    tokio::spawn(async move {
+        for index in 0.. {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            // 载荷很小，读到请求体结束即可
+            while !String::from_utf8_lossy(&request).ends_with('}') {
+                let n = stream.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            recorded
+                .lock()
+                .unwrap()
+                .push(String::from_utf8_lossy(&request).to_string());
+            let status = statuses[index.min(statuses.len() - 1)];
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                status
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+    (addr, received)
+}
+
+#[test]
+fn test_webhook_signature() {
+    let signature = sign_payload("key", b"The quick brown fox jumps over the lazy dog");
+    assert_eq!(
+        signature,
+        "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+    );
+}
+
+#[tokio::test]
+async fn test_webhook_delivery_is_signed_and_retried() {
+    // 变量名只在本测试中使用
+    unsafe { std::env::set_var("BEEPKG_TEST_WEBHOOK_SECRET", "hook-secret") };
+    let (addr, received) = receiver(&[
+        "500 Internal Server Error",
+        "503 Service Unavailable",
+        "200 OK",
+    ])
+    .await;
+    let webhook = WebhookConfig {
+        url: format!("http://{}/hook", addr),
+        secret_env: Some("BEEPKG_TEST_WEBHOOK_SECRET".to_string()),
+        events: Vec::new(),
+    };
+
+    let start = Instant::now();
+    deliver(&reqwest::Client::new(), &webhook, &record())
+        .await
+        .unwrap();
+    // 两次重试前分别等待 1 秒和 2 秒
+    assert!(start.elapsed() >= Duration::from_secs(3));
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 3);
+    let payload = serde_json::to_vec(&record()).unwrap();
+    let signature = sign_payload("hook-secret", &payload);
+    for request in received.iter() {
+        assert!(request.starts_with("POST /hook "), "{}", request);
+        assert!(
+            request
+                .to_ascii_lowercase()
+                .contains("x-beepkg-event: push")
+        );
+        assert!(request.contains(&signature), "{}", request);
+        assert!(request.ends_with(&*String::from_utf8_lossy(&payload)));
+    }
+}
+
+#[tokio::test]
+async fn test_webhook_delivery_gives_up_after_three_attempts() {
+    let (addr, received) = receiver(&["500 Internal Server Error"]).await;
+    let webhook = WebhookConfig {
+        url: format!("http://{}/hook", addr),
+        secret_env: None,
+        events: Vec::new(),
+    };
+
+    let error = deliver(&reqwest::Client::new(), &webhook, &record())
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("after 3 attempts"), "{}", error);
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 3);
+    assert!(
+        !received
+            .iter()
+            .any(|request| request.to_ascii_lowercase().contains("x-beepkg-signature"))
+    );
+}
+
+#[tokio::test]
+async fn test_webhook_secret_variable_must_be_set() {
+    let webhook = WebhookConfig {
+        url: "http://127.0.0.1:1/hook".to_string(),
+        secret_env: Some("BEEPKG_TEST_WEBHOOK_SECRET_UNSET".to_string()),
+        events: Vec::new(),
+    };
+    let error = deliver(&reqwest::Client::new(), &webhook, &record())
+        .await
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("BEEPKG_TEST_WEBHOOK_SECRET_UNSET is not set"),
+        "{}",
+        error
+    );
+}
@@ -0,0 +1,89 @@
+use beepkg::workspace::{self, Workspace, WorkspaceMember};
+use std::path::Path;
+
+fn write_member(root: &Path, dir: &str, name: &str, version: &str, dependencies: &str) {
+    let path = root.join(dir);
+    std::fs::create_dir_all(&path).unwrap();
+    std::fs::write(
+        path.join("pack.toml"),
+        format!(
+            "name = \"{}\"\nversion = \"{}\"\nauthor = \"dev@company.com\"\n\
+             description = \"\"\nincludes = []\nexcludes = []\n\n[dependencies]\n{}\n",
+            name, version, dependencies
+        ),
+    )
+    .unwrap();
+}
+
+fn names(members: &[WorkspaceMember]) -> Vec<&str> {
+    members
+        .iter()
+        .map(|member| member.metadata.name.as_str())
+        .collect()
+}
+
+#[test]
+fn test_members_in_dependency_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::write(
+        root.join(workspace::WORKSPACE_FILE),
+        "members = [\"cli\", \"core\", \"utils\"]\nexcludes = [\"**/*.log\"]\n",
+    )
+    .unwrap();
+    write_member(
+        root,
+        "cli",
+        "app-cli",
+        "1.0.0",
+        "app-core = \"^1.0\"\nclap = \"^4\"",
+    );
+    write_member(root, "core", "app-core", "1.1.0", "app-utils = \"0.2.0\"");
+    write_member(root, "utils", "app-utils", "0.2.0", "");
+
+    let workspace = Workspace::load(root).unwrap();
+    assert_eq!(workspace.excludes, vec!["**/*.log"]);
+    let members = workspace.members(root).unwrap();
+    assert_eq!(names(&members), vec!["app-utils", "app-core", "app-cli"]);
+    assert_eq!(members[2].path, root.join("cli"));
+}
+
+#[test]
+fn test_members_version_mismatch() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::write(
+        root.join(workspace::WORKSPACE_FILE),
+        "members = [\"a\", \"b\"]\n",
+    )
+    .unwrap();
+    write_member(root, "a", "pkg-a", "1.0.0", "pkg-b = \"^2.0\"");
+    write_member(root, "b", "pkg-b", "1.5.0", "");
+
+    let err = Workspace::load(root)
+        .unwrap()
+        .members(root)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("pkg-b"), "{}", err);
+}
+
+#[test]
+fn test_members_dependency_cycle() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::write(
+        root.join(workspace::WORKSPACE_FILE),
+        "members = [\"a\", \"b\"]\n",
+    )
+    .unwrap();
+    write_member(root, "a", "pkg-a", "1.0.0", "pkg-b = \"^1.0\"");
+    write_member(root, "b", "pkg-b", "1.0.0", "pkg-a = \"^1.0\"");
+
+    let err = Workspace::load(root)
+        .unwrap()
+        .members(root)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("cycle"), "{}", err);
+}